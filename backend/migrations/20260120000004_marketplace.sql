@@ -156,7 +156,7 @@ CREATE TABLE price_history (
     
     -- Titan attributes for aggregation
     element ELEMENT_TYPE NOT NULL,
-    threat_class THREAT_CLASS_TYPE NOT NULL,
+    threat_class SMALLINT NOT NULL,
     species_id INTEGER,
     
     -- Price data
@@ -268,7 +268,6 @@ SELECT
     pt.element,
     pt.threat_class,
     pt.species_id,
-    pt.level,
     pt.nickname,
     l.listing_type,
     l.price,
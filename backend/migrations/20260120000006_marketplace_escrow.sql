@@ -0,0 +1,24 @@
+-- Marketplace On-chain Escrow Migration
+-- Version: 0.8.0
+
+-- ============================================
+-- 1. On-chain Titan ID
+-- ============================================
+-- Maps a player Titan to its Titan NFT Program `titan_id`
+ALTER TABLE player_titans ADD COLUMN IF NOT EXISTS onchain_id BIGINT UNIQUE;
+
+-- ============================================
+-- 2. Escrow Listing Columns
+-- ============================================
+-- Listing PDA (["listing", titan_id]) when the listing is held in the Game Logic escrow
+ALTER TABLE marketplace_listings ADD COLUMN IF NOT EXISTS escrow_address VARCHAR(64);
+-- Transaction that created the on-chain listing
+ALTER TABLE marketplace_listings ADD COLUMN IF NOT EXISTS listing_tx_signature VARCHAR(128);
+
+-- Only one active listing may point at a given escrow account
+CREATE UNIQUE INDEX IF NOT EXISTS idx_listings_escrow_active
+    ON marketplace_listings(escrow_address)
+    WHERE status = 'active' AND escrow_address IS NOT NULL;
+
+COMMENT ON COLUMN marketplace_listings.escrow_address IS 'Game Logic Listing PDA; NULL for database-only listings';
+COMMENT ON COLUMN player_titans.onchain_id IS 'Titan NFT Program titan_id';
@@ -0,0 +1,51 @@
+-- Guild Activity Requirements Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Inactivity Policy
+-- ============================================
+CREATE TYPE guild_inactivity_policy AS ENUM ('demote', 'kick');
+
+ALTER TYPE notification_type ADD VALUE IF NOT EXISTS 'guild_inactivity_warning';
+
+-- ============================================
+-- 2. Guild Settings
+-- ============================================
+-- NULL requirement = not enforced
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS activity_min_weekly_xp BIGINT;
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS activity_min_weekly_captures INT;
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS inactivity_policy guild_inactivity_policy NOT NULL DEFAULT 'demote';
+-- Auto-kick must be opted into explicitly
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS auto_kick_enabled BOOLEAN NOT NULL DEFAULT false;
+-- Start of the week currently being tracked
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS activity_week_started_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_guilds_activity_week
+    ON guilds(activity_week_started_at)
+    WHERE activity_week_started_at IS NOT NULL;
+
+-- ============================================
+-- 3. Member Weekly Tracking
+-- ============================================
+-- Player experience / captures at the start of the tracked week
+ALTER TABLE guild_members ADD COLUMN IF NOT EXISTS week_start_xp BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE guild_members ADD COLUMN IF NOT EXISTS week_start_captures INT NOT NULL DEFAULT 0;
+-- Consecutive weeks below the requirement
+ALTER TABLE guild_members ADD COLUMN IF NOT EXISTS missed_weeks INT NOT NULL DEFAULT 0;
+
+-- ============================================
+-- 4. Vacations
+-- ============================================
+CREATE TABLE guild_vacations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    player_id UUID NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    starts_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    ends_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CHECK (ends_at > starts_at)
+);
+
+CREATE INDEX idx_guild_vacations_player ON guild_vacations(player_id, ends_at DESC);
+
+COMMENT ON TABLE guild_vacations IS 'Self-service guild vacations, exempt from activity requirements';
@@ -0,0 +1,75 @@
+-- On-chain Fee Budget Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Action Types
+-- ============================================
+CREATE TYPE onchain_action_type AS ENUM (
+    'capture_mint',
+    'record_battle',
+    'add_experience',
+    'breach_transfer'
+);
+
+CREATE TYPE onchain_action_status AS ENUM ('pending', 'completed', 'failed');
+
+-- ============================================
+-- 2. Per-player Daily Spend
+-- ============================================
+-- Estimated lamports the backend paid on a player's behalf (UTC day)
+CREATE TABLE onchain_fee_spend (
+    player_id UUID NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    day DATE NOT NULL,
+    lamports BIGINT NOT NULL DEFAULT 0,
+    actions INT NOT NULL DEFAULT 0,
+    deferred INT NOT NULL DEFAULT 0,
+
+    PRIMARY KEY (player_id, day)
+);
+
+CREATE INDEX idx_onchain_fee_spend_day ON onchain_fee_spend(day, lamports DESC);
+
+-- ============================================
+-- 3. Global Hourly Spend
+-- ============================================
+CREATE TABLE onchain_fee_hourly (
+    hour TIMESTAMPTZ PRIMARY KEY,
+    lamports BIGINT NOT NULL DEFAULT 0,
+    actions INT NOT NULL DEFAULT 0,
+    -- Actions deferred because the global cap was reached
+    cap_hits INT NOT NULL DEFAULT 0
+);
+
+-- ============================================
+-- 4. Partner Cap Overrides
+-- ============================================
+CREATE TABLE onchain_fee_caps (
+    player_id UUID PRIMARY KEY REFERENCES players(id) ON DELETE CASCADE,
+    daily_cap_lamports BIGINT NOT NULL CHECK (daily_cap_lamports >= 0),
+    note TEXT,
+    updated_by VARCHAR(64),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- ============================================
+-- 5. Deferred Action Queue
+-- ============================================
+CREATE TABLE pending_onchain_actions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    player_id UUID NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    action onchain_action_type NOT NULL,
+    payload JSONB NOT NULL,
+    status onchain_action_status NOT NULL DEFAULT 'pending',
+    attempts INT NOT NULL DEFAULT 0,
+    last_error TEXT,
+    tx_signature VARCHAR(128),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    processed_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_pending_onchain_actions_pending
+    ON pending_onchain_actions(created_at)
+    WHERE status = 'pending';
+CREATE INDEX idx_pending_onchain_actions_player ON pending_onchain_actions(player_id, created_at DESC);
+
+COMMENT ON TABLE pending_onchain_actions IS 'On-chain actions deferred by the fee budget, retried by the scheduler';
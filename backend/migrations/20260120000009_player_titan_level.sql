@@ -0,0 +1,39 @@
+-- Player Titan Level Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Titan Level
+-- ============================================
+-- Marketplace queries and models already expect a per-Titan level
+ALTER TABLE player_titans ADD COLUMN IF NOT EXISTS level INT NOT NULL DEFAULT 1;
+
+CREATE INDEX IF NOT EXISTS idx_player_titans_level ON player_titans(level);
+
+-- ============================================
+-- 2. Active Listings View
+-- ============================================
+DROP VIEW IF EXISTS v_active_listings;
+
+CREATE VIEW v_active_listings AS
+SELECT
+    l.id,
+    l.seller_id,
+    p.username as seller_username,
+    l.titan_id,
+    pt.element,
+    pt.threat_class,
+    pt.species_id,
+    pt.level,
+    pt.nickname,
+    l.listing_type,
+    l.price,
+    l.min_price,
+    get_highest_bid(l.id) as current_bid,
+    l.expires_at,
+    l.views,
+    l.favorites,
+    l.created_at
+FROM marketplace_listings l
+JOIN players p ON l.seller_id = p.id
+JOIN player_titans pt ON l.titan_id = pt.id
+WHERE l.status = 'active';
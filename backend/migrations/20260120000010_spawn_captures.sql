@@ -0,0 +1,15 @@
+-- Spawn Captures Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Capture Log
+-- ============================================
+-- One row per player capture of a spawn; drives the crowding reward modifier
+CREATE TABLE spawn_captures (
+    id BIGSERIAL PRIMARY KEY,
+    spawn_id UUID NOT NULL REFERENCES titan_spawns(id) ON DELETE CASCADE,
+    player_id UUID NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    captured_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_spawn_captures_spawn ON spawn_captures(spawn_id, captured_at DESC);
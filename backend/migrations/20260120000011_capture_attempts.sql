@@ -0,0 +1,41 @@
+-- Capture Attempts Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Attempt States
+-- ============================================
+-- pending -> minted -> confirmed, or pending -> confirmed when nothing was
+-- minted (blockchain skipped, mint deferred or failed); failed when the spawn
+-- was gone by the time the capture was finalized
+CREATE TYPE capture_attempt_state AS ENUM ('pending', 'minted', 'confirmed', 'failed');
+
+-- ============================================
+-- 2. Capture Attempts
+-- ============================================
+-- Written before touching Solana so a retried confirm resumes from the
+-- recorded state instead of minting again
+CREATE TABLE capture_attempts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    player_id UUID NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    titan_spawn_id UUID NOT NULL REFERENCES titan_spawns(id) ON DELETE CASCADE,
+    state capture_attempt_state NOT NULL DEFAULT 'pending',
+
+    -- Set by the mint
+    mint_address VARCHAR(64),
+    token_account VARCHAR(64),
+    tx_signature VARCHAR(128),
+
+    -- Set when confirmed
+    remaining_captures INT,
+    breach_reward BIGINT,
+    breach_tx_signature VARCHAR(128),
+
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    UNIQUE (player_id, titan_spawn_id)
+);
+
+CREATE INDEX idx_capture_attempts_state ON capture_attempts(state, updated_at)
+    WHERE state IN ('pending', 'minted');
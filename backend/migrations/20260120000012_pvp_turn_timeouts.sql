@@ -0,0 +1,15 @@
+-- PvP Turn Timeouts Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Missed Turns
+-- ============================================
+-- Turns each player has let run out in a row; reset when they act. The
+-- timeout task skips the turn until a player reaches the configured limit,
+-- then ends the match in the opponent's favour
+ALTER TABLE pvp_matches
+    ADD COLUMN player1_consecutive_skips INT NOT NULL DEFAULT 0,
+    ADD COLUMN player2_consecutive_skips INT NOT NULL DEFAULT 0;
+
+CREATE INDEX idx_pvp_matches_turn_deadline ON pvp_matches(turn_deadline)
+    WHERE status = 'active';
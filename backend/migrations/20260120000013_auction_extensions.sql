@@ -0,0 +1,11 @@
+-- Auction Extensions Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Anti-sniping Extensions
+-- ============================================
+-- A bid in an auction's final minutes pushes expires_at out. The first
+-- extension records the original end time, which caps the total extension
+ALTER TABLE marketplace_listings
+    ADD COLUMN extension_count INT NOT NULL DEFAULT 0,
+    ADD COLUMN original_expires_at TIMESTAMPTZ;
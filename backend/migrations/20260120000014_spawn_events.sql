@@ -0,0 +1,35 @@
+-- Spawn Events Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Timed Events
+-- ============================================
+-- Limited-time special spawns (holiday events, tournament kick-offs). The
+-- scheduler runs an event's spawns once it starts (started_at) and expires
+-- its Titans once it ends (ended_at)
+CREATE TABLE spawn_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL,
+    start_at TIMESTAMPTZ NOT NULL,
+    end_at TIMESTAMPTZ NOT NULL,
+    -- Species pool, threat class weights and spawn count (SpawnEventConfig)
+    spawn_config JSONB NOT NULL DEFAULT '{}',
+    -- Geohash prefixes the event covers; empty means everywhere
+    geohash_filter TEXT[] NOT NULL DEFAULT '{}',
+    started_at TIMESTAMPTZ,
+    ended_at TIMESTAMPTZ,
+    created_by VARCHAR(44),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CHECK (end_at > start_at)
+);
+
+CREATE INDEX idx_spawn_events_open ON spawn_events(start_at) WHERE ended_at IS NULL;
+
+-- ============================================
+-- 2. Event Titans
+-- ============================================
+ALTER TABLE titan_spawns
+    ADD COLUMN event_id UUID REFERENCES spawn_events(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_titan_spawns_event ON titan_spawns(event_id) WHERE event_id IS NOT NULL;
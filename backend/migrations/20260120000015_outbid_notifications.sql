@@ -0,0 +1,5 @@
+-- Outbid Notifications Migration
+-- Version: 0.9.0
+
+-- Sent to an auction's top bidder when a higher bid displaces theirs
+ALTER TYPE notification_type ADD VALUE IF NOT EXISTS 'outbid';
@@ -0,0 +1,24 @@
+-- Location Violations Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Speed Violations
+-- ============================================
+-- Location updates rejected for implying impossible travel, kept for review.
+-- Enough of them in a window quarantines the player's location for captures
+CREATE TABLE player_location_violations (
+    id BIGSERIAL PRIMARY KEY,
+    player_id UUID NOT NULL REFERENCES players(id),
+    -- 'websocket' or 'http'
+    source VARCHAR(20) NOT NULL,
+    from_lat DOUBLE PRECISION NOT NULL,
+    from_lng DOUBLE PRECISION NOT NULL,
+    to_lat DOUBLE PRECISION NOT NULL,
+    to_lng DOUBLE PRECISION NOT NULL,
+    distance_m DOUBLE PRECISION NOT NULL,
+    elapsed_seconds DOUBLE PRECISION NOT NULL,
+    speed_mps DOUBLE PRECISION NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_location_violations_player ON player_location_violations(player_id, created_at DESC);
@@ -0,0 +1,40 @@
+-- Titan Escrow Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Escrow Column
+-- ============================================
+-- Active listing holding the Titan. An escrowed Titan can't be fused,
+-- transferred, entered into PvP or sold through an offer
+ALTER TABLE player_titans ADD COLUMN IF NOT EXISTS escrowed_listing_id UUID
+    REFERENCES marketplace_listings(id) ON DELETE SET NULL;
+
+COMMENT ON COLUMN player_titans.escrowed_listing_id IS 'Active marketplace listing holding the Titan; NULL when free';
+
+-- ============================================
+-- 2. Escrow Sync
+-- ============================================
+-- Every path that opens or closes a listing (create, cancel, sale, auction
+-- end, on-chain sync) goes through marketplace_listings.status, so escrow
+-- follows it here rather than in each of them
+CREATE OR REPLACE FUNCTION sync_titan_escrow()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.status = 'active' THEN
+        UPDATE player_titans SET escrowed_listing_id = NEW.id WHERE id = NEW.titan_id;
+    ELSIF TG_OP = 'UPDATE' AND OLD.status = 'active' THEN
+        UPDATE player_titans SET escrowed_listing_id = NULL
+        WHERE id = NEW.titan_id AND escrowed_listing_id = NEW.id;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_sync_titan_escrow
+AFTER INSERT OR UPDATE OF status ON marketplace_listings
+FOR EACH ROW EXECUTE FUNCTION sync_titan_escrow();
+
+-- Listings already open
+UPDATE player_titans pt SET escrowed_listing_id = l.id
+FROM marketplace_listings l
+WHERE l.titan_id = pt.id AND l.status = 'active';
@@ -0,0 +1,30 @@
+-- Guild Treasury Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Treasury Balance
+-- ============================================
+-- $BREACH held by the guild (smallest units). Funded by member deposits and
+-- a share of the marketplace fee on members' sales
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS breach_treasury BIGINT NOT NULL DEFAULT 0
+    CHECK (breach_treasury >= 0);
+
+COMMENT ON COLUMN guilds.breach_treasury IS 'Guild $BREACH treasury balance in smallest units';
+
+-- ============================================
+-- 2. Treasury Log
+-- ============================================
+CREATE TABLE IF NOT EXISTS guild_treasury_log (
+    id BIGSERIAL PRIMARY KEY,
+    guild_id UUID NOT NULL REFERENCES guilds(id) ON DELETE CASCADE,
+    player_id UUID REFERENCES players(id) ON DELETE SET NULL,
+    kind VARCHAR(20) NOT NULL,            -- deposit, marketplace_fee, withdrawal
+    amount BIGINT NOT NULL CHECK (amount > 0),
+    balance_after BIGINT NOT NULL,
+    reason TEXT,
+    marketplace_transaction_id UUID REFERENCES marketplace_transactions(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_guild_treasury_log_guild
+    ON guild_treasury_log(guild_id, created_at DESC);
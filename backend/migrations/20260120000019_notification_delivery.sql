@@ -0,0 +1,15 @@
+-- Notification Delivery Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Read Receipts
+-- ============================================
+-- When the player marked the notification read; NULL for notifications read
+-- before receipts were recorded
+ALTER TABLE notifications ADD COLUMN IF NOT EXISTS read_at TIMESTAMPTZ;
+
+-- ============================================
+-- 2. PvP Match Notifications
+-- ============================================
+-- Sent to both players when matchmaking pairs them
+ALTER TYPE notification_type ADD VALUE IF NOT EXISTS 'pvp_match_found';
@@ -0,0 +1,11 @@
+-- Titan Escapes Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Escape Counter
+-- ============================================
+-- Failed capture rolls against the spawn, across all players
+ALTER TABLE titan_spawns ADD COLUMN IF NOT EXISTS escaped_count INTEGER NOT NULL DEFAULT 0
+    CHECK (escaped_count >= 0);
+
+COMMENT ON COLUMN titan_spawns.escaped_count IS 'Capture attempts the Titan escaped';
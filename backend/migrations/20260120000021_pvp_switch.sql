@@ -0,0 +1,24 @@
+-- PvP Titan Switch Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Switch Action
+-- ============================================
+ALTER TYPE pvp_action_type ADD VALUE IF NOT EXISTS 'switch';
+
+-- Titan a switch turn brought in
+ALTER TABLE pvp_battle_turns ADD COLUMN IF NOT EXISTS switched_titan_id UUID
+    REFERENCES player_titans(id) ON DELETE SET NULL;
+
+-- ============================================
+-- 2. Benched Titan HP
+-- ============================================
+-- HP a Titan had when it was switched out; the active Titan's HP stays in
+-- pvp_matches.playerN_hp. A Titan with no row comes in at full HP
+CREATE TABLE IF NOT EXISTS pvp_match_titans (
+    match_id UUID NOT NULL REFERENCES pvp_matches(id) ON DELETE CASCADE,
+    titan_id UUID NOT NULL REFERENCES player_titans(id) ON DELETE CASCADE,
+    player_id UUID NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    hp INT NOT NULL CHECK (hp >= 0),
+    PRIMARY KEY (match_id, titan_id)
+);
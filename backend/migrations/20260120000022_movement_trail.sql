@@ -0,0 +1,12 @@
+-- Movement Trail Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Suspicious Movement Count
+-- ============================================
+-- Location trails that didn't fit a plausible path. Never decays; reset by
+-- hand after review
+ALTER TABLE players ADD COLUMN IF NOT EXISTS suspicious_movement_count INTEGER NOT NULL DEFAULT 0
+    CHECK (suspicious_movement_count >= 0);
+
+COMMENT ON COLUMN players.suspicious_movement_count IS 'Implausible location trails detected';
@@ -0,0 +1,11 @@
+-- Shadow Ban Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Shadow Ban Flag
+-- ============================================
+-- Confirmed cheaters keep playing, but their captures quietly don't happen
+-- and they earn no $BREACH. Never exposed to the player
+ALTER TABLE players ADD COLUMN IF NOT EXISTS shadow_banned BOOLEAN NOT NULL DEFAULT false;
+
+COMMENT ON COLUMN players.shadow_banned IS 'Captures and rewards silently skipped';
@@ -0,0 +1,43 @@
+-- Pending Transactions Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Transaction Kinds and Statuses
+-- ============================================
+CREATE TYPE tracked_transaction_kind AS ENUM (
+    'capture_mint',
+    'listing_purchase',
+    'listing_cancel'
+);
+
+-- pending -> confirmed | failed, or expired once the blockhash can no longer
+-- land (or the poll attempts run out)
+CREATE TYPE tracked_transaction_status AS ENUM ('pending', 'confirmed', 'failed', 'expired');
+
+-- ============================================
+-- 2. Pending Transactions
+-- ============================================
+-- Written before a player-signed transaction is sent, so one that lands after
+-- the RPC call timed out is still followed up by the scheduler
+CREATE TABLE pending_transactions (
+    signature VARCHAR(128) PRIMARY KEY,
+    kind tracked_transaction_kind NOT NULL,
+    player_id UUID NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    -- Capture attempt or marketplace listing
+    related_id UUID NOT NULL,
+    -- Mint address recorded on the capture attempt (capture mints only)
+    mint_address VARCHAR(64),
+    recent_blockhash VARCHAR(64) NOT NULL,
+    status tracked_transaction_status NOT NULL DEFAULT 'pending',
+    attempts INT NOT NULL DEFAULT 0,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_pending_transactions_pending
+    ON pending_transactions(created_at)
+    WHERE status = 'pending';
+CREATE INDEX idx_pending_transactions_player ON pending_transactions(player_id, created_at DESC);
+
+COMMENT ON TABLE pending_transactions IS 'Player-signed transactions polled by the scheduler until they finalize';
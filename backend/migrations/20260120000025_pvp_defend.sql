@@ -0,0 +1,10 @@
+-- PvP Defend Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Defending Flags
+-- ============================================
+-- Set by a defend action; halves the next attack against that player and is
+-- cleared by it, or by the player's next action
+ALTER TABLE pvp_matches ADD COLUMN IF NOT EXISTS player1_defending BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE pvp_matches ADD COLUMN IF NOT EXISTS player2_defending BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,23 @@
+-- Auction Proxy Bids Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Proxy Bids
+-- ============================================
+-- A bidder's maximum on an auction; their bid is raised one increment past
+-- each challenger up to it. Only the leading bidder's proxy is active, and it
+-- is deactivated once a bid goes past max_bid
+CREATE TABLE IF NOT EXISTS auction_proxy_bids (
+    listing_id UUID NOT NULL REFERENCES marketplace_listings(id) ON DELETE CASCADE,
+    bidder_id UUID NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    max_bid BIGINT NOT NULL CHECK (max_bid > 0),
+    active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (listing_id, bidder_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_auction_proxy_bids_active
+    ON auction_proxy_bids(listing_id)
+    WHERE active;
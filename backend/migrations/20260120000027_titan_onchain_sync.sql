@@ -0,0 +1,18 @@
+-- Titan On-chain Sync Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Titan Experience
+-- ============================================
+-- Mirrors TitanData.experience (u32), refreshed by the collection sync
+ALTER TABLE player_titans ADD COLUMN IF NOT EXISTS experience BIGINT NOT NULL DEFAULT 0;
+
+-- ============================================
+-- 2. Sync Flags
+-- ============================================
+ALTER TABLE player_titans ADD COLUMN IF NOT EXISTS onchain_owner_mismatch BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE player_titans ADD COLUMN IF NOT EXISTS onchain_missing BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE player_titans ADD COLUMN IF NOT EXISTS onchain_synced_at TIMESTAMPTZ;
+
+COMMENT ON COLUMN player_titans.onchain_owner_mismatch IS 'Titan PDA owner was not the player wallet at the last sync';
+COMMENT ON COLUMN player_titans.onchain_missing IS 'Titan PDA did not exist at the last sync';
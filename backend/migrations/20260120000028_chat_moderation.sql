@@ -0,0 +1,26 @@
+-- Chat Moderation Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Player Sanctions
+-- ============================================
+-- Mutes and bans issued by admins, usually while resolving a chat report.
+-- A mute stops the player sending chat messages until it expires; a ban
+-- (which also sets players.is_banned) never expires.
+CREATE TABLE player_sanctions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    player_id UUID NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    sanction_type VARCHAR(20) NOT NULL CHECK (sanction_type IN ('mute', 'ban')),
+    reason TEXT,
+    report_id UUID REFERENCES chat_reports(id) ON DELETE SET NULL,
+    issued_by UUID REFERENCES players(id) ON DELETE SET NULL,
+    expires_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- ============================================
+-- 2. Indexes
+-- ============================================
+CREATE INDEX idx_sanctions_player ON player_sanctions(player_id, sanction_type, expires_at);
+
+COMMENT ON TABLE player_sanctions IS 'Admin mutes and bans; NULL expires_at never expires';
@@ -0,0 +1,27 @@
+-- PvP Best-of-Three Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Match Format
+-- ============================================
+-- `single` ends on the first KO; `bo3` is won by the first player to win two
+-- rounds. Players are only matched with others queued for the same format.
+CREATE TYPE pvp_match_format AS ENUM ('single', 'bo3');
+
+ALTER TABLE matchmaking_queue ADD COLUMN IF NOT EXISTS match_format pvp_match_format NOT NULL DEFAULT 'single';
+ALTER TABLE pvp_matches ADD COLUMN IF NOT EXISTS match_format pvp_match_format NOT NULL DEFAULT 'single';
+
+-- ============================================
+-- 2. Round State
+-- ============================================
+-- A KO wins the round; when the series isn't decided both sides are healed
+-- and the next round starts
+ALTER TABLE pvp_matches ADD COLUMN IF NOT EXISTS round_number INT NOT NULL DEFAULT 1;
+ALTER TABLE pvp_matches ADD COLUMN IF NOT EXISTS player1_rounds_won INT NOT NULL DEFAULT 0;
+ALTER TABLE pvp_matches ADD COLUMN IF NOT EXISTS player2_rounds_won INT NOT NULL DEFAULT 0;
+ALTER TABLE pvp_battle_turns ADD COLUMN IF NOT EXISTS round_number INT NOT NULL DEFAULT 1;
+
+-- ============================================
+-- 3. Indexes
+-- ============================================
+CREATE INDEX IF NOT EXISTS idx_queue_searching_format ON matchmaking_queue(match_format, elo_rating) WHERE status = 'searching';
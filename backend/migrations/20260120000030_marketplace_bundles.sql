@@ -0,0 +1,56 @@
+-- Marketplace Bundles Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Bundle Listing Type
+-- ============================================
+-- A fixed-price listing of 2 to 5 Titans sold together. The listing's
+-- `titan_id` is the first Titan of the bundle, shown as its cover.
+ALTER TYPE listing_type ADD VALUE IF NOT EXISTS 'bundle';
+
+-- ============================================
+-- 2. Bundle Titans
+-- ============================================
+-- Every Titan of a bundle listing, the cover included, in the order listed
+CREATE TABLE IF NOT EXISTS listing_bundle_titans (
+    listing_id UUID NOT NULL REFERENCES marketplace_listings(id) ON DELETE CASCADE,
+    titan_id UUID NOT NULL REFERENCES player_titans(id) ON DELETE CASCADE,
+    position SMALLINT NOT NULL,
+    PRIMARY KEY (listing_id, titan_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_bundle_titans_titan ON listing_bundle_titans(titan_id);
+
+-- ============================================
+-- 3. Escrow Sync
+-- ============================================
+-- Bundle rows are written after their listing, so they escrow their Titan
+-- as they are inserted; closing the listing frees all of them
+CREATE OR REPLACE FUNCTION sync_titan_escrow()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.status = 'active' THEN
+        UPDATE player_titans SET escrowed_listing_id = NEW.id WHERE id = NEW.titan_id;
+    ELSIF TG_OP = 'UPDATE' AND OLD.status = 'active' THEN
+        UPDATE player_titans SET escrowed_listing_id = NULL
+        WHERE escrowed_listing_id = NEW.id
+          AND (id = NEW.titan_id
+               OR id IN (SELECT titan_id FROM listing_bundle_titans WHERE listing_id = NEW.id));
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION sync_bundle_titan_escrow()
+RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE player_titans pt SET escrowed_listing_id = NEW.listing_id
+    FROM marketplace_listings l
+    WHERE pt.id = NEW.titan_id AND l.id = NEW.listing_id AND l.status = 'active';
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_sync_bundle_titan_escrow
+AFTER INSERT ON listing_bundle_titans
+FOR EACH ROW EXECUTE FUNCTION sync_bundle_titan_escrow();
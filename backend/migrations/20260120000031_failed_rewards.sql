@@ -0,0 +1,38 @@
+-- Failed Rewards Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Reward Types
+-- ============================================
+-- What earned the reward
+CREATE TYPE reward_reason AS ENUM ('capture', 'battle');
+
+-- `pending` is retried by the scheduler, `paid` has its transfer signature,
+-- `failed` ran out of attempts and waits for manual review
+CREATE TYPE failed_reward_status AS ENUM ('pending', 'paid', 'failed');
+
+-- ============================================
+-- 2. Failed Reward Queue
+-- ============================================
+-- $BREACH rewards whose transfer failed, owed to the player until paid
+CREATE TABLE failed_rewards (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    player_id UUID NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    amount BIGINT NOT NULL CHECK (amount > 0),
+    reason reward_reason NOT NULL,
+    status failed_reward_status NOT NULL DEFAULT 'pending',
+    attempts INT NOT NULL DEFAULT 1,
+    last_error TEXT,
+    next_retry_at TIMESTAMPTZ NOT NULL,
+    -- Set once the transfer lands; an entry with a signature is never paid again
+    tx_signature VARCHAR(128),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    paid_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_failed_rewards_due
+    ON failed_rewards(next_retry_at)
+    WHERE status = 'pending';
+CREATE INDEX idx_failed_rewards_player ON failed_rewards(player_id, created_at DESC);
+
+COMMENT ON TABLE failed_rewards IS 'Failed $BREACH reward transfers, retried with backoff by the scheduler';
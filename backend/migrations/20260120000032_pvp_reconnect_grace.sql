@@ -0,0 +1,12 @@
+-- PvP Reconnect Grace Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Disconnect Timers
+-- ============================================
+-- When each side's last connection dropped mid-match; cleared when they
+-- come back. A disconnected player's timed-out turn waits out the reconnect
+-- grace window instead of being skipped, then forfeits the match.
+ALTER TABLE pvp_matches
+    ADD COLUMN IF NOT EXISTS player1_disconnected_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS player2_disconnected_at TIMESTAMPTZ;
@@ -0,0 +1,42 @@
+-- Creator Royalties Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Titan Creators
+-- ============================================
+-- The player who captured a Titan, kept through every resale. Titans from
+-- before this migration are credited to their current owner.
+ALTER TABLE player_titans ADD COLUMN IF NOT EXISTS creator_id UUID REFERENCES players(id);
+
+UPDATE player_titans SET creator_id = player_id WHERE creator_id IS NULL;
+
+-- A Titan inserted without a creator was captured by its first owner
+CREATE OR REPLACE FUNCTION default_titan_creator()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.creator_id := COALESCE(NEW.creator_id, NEW.player_id);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_default_titan_creator
+BEFORE INSERT ON player_titans
+FOR EACH ROW EXECUTE FUNCTION default_titan_creator();
+
+ALTER TABLE player_titans ALTER COLUMN creator_id SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_player_titans_creator ON player_titans(creator_id);
+
+-- ============================================
+-- 2. Royalty Accounting
+-- ============================================
+-- The creator's cut of a resale, taken out of `seller_receives`
+ALTER TABLE marketplace_transactions
+    ADD COLUMN IF NOT EXISTS royalty_recipient_id UUID REFERENCES players(id),
+    ADD COLUMN IF NOT EXISTS royalty_amount BIGINT NOT NULL DEFAULT 0;
+
+-- ============================================
+-- 3. Royalty Payouts
+-- ============================================
+-- A royalty whose transfer to the creator failed joins the reward retry queue
+ALTER TYPE reward_reason ADD VALUE IF NOT EXISTS 'royalty';
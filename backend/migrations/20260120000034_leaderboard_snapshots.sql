@@ -0,0 +1,36 @@
+-- Leaderboard Snapshots Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Snapshot Boards
+-- ============================================
+-- Boards frozen at each weekly rollover; `guild_weekly_xp` ranks guilds,
+-- the others players
+CREATE TYPE leaderboard_board AS ENUM ('captures', 'experience', 'pvp_elo', 'guild_weekly_xp');
+
+-- ============================================
+-- 2. Snapshots
+-- ============================================
+-- One snapshot per board and ISO week (`YYYY-WW`), so a rollover that runs
+-- twice, or on two instances, takes it once
+CREATE TABLE IF NOT EXISTS leaderboard_snapshots (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    board leaderboard_board NOT NULL,
+    period VARCHAR(8) NOT NULL,
+    taken_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (board, period)
+);
+
+-- The top of the board as it stood when the snapshot was taken
+CREATE TABLE IF NOT EXISTS leaderboard_snapshot_entries (
+    snapshot_id UUID NOT NULL REFERENCES leaderboard_snapshots(id) ON DELETE CASCADE,
+    rank INT NOT NULL,
+    -- Player, or guild on the guild board
+    subject_id UUID NOT NULL,
+    name VARCHAR(50),
+    score BIGINT NOT NULL,
+    PRIMARY KEY (snapshot_id, rank)
+);
+
+CREATE INDEX IF NOT EXISTS idx_snapshot_entries_subject
+    ON leaderboard_snapshot_entries(snapshot_id, subject_id);
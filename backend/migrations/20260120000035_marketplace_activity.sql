@@ -0,0 +1,31 @@
+-- Marketplace Activity Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Activity Types
+-- ============================================
+CREATE TYPE marketplace_activity_type AS ENUM (
+    'listing_created', 'bid_placed', 'sale_completed', 'listing_cancelled'
+);
+
+-- ============================================
+-- 2. Activity Log
+-- ============================================
+-- Append-only feed of marketplace events. `listing_id` is the virtual
+-- listing of an accepted offer, so it isn't a foreign key; the username is
+-- kept as it was when the event happened.
+CREATE TABLE IF NOT EXISTS marketplace_activity_log (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    activity_type marketplace_activity_type NOT NULL,
+    listing_id UUID NOT NULL,
+    titan_id UUID NOT NULL,
+    price BIGINT NOT NULL,
+    -- Seller for listings and cancellations, bidder for bids, buyer for sales
+    player_id UUID NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    player_username VARCHAR(32),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Newest first, with the ID breaking ties for the pagination cursor
+CREATE INDEX IF NOT EXISTS idx_marketplace_activity_feed
+    ON marketplace_activity_log(created_at DESC, id DESC);
@@ -0,0 +1,21 @@
+-- Game Config Audit Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Audit Log
+-- ============================================
+-- Every change to the live `game` settings: an admin's PATCH, a reload
+-- through the API, or a SIGHUP reload
+CREATE TABLE IF NOT EXISTS game_config_audit_log (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- `patch`, `reload` or `sighup`
+    source VARCHAR(16) NOT NULL,
+    -- Admin wallet; NULL for a SIGHUP reload
+    changed_by VARCHAR(64),
+    -- `{ field: { "from": ..., "to": ... } }` for each changed setting
+    changes JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_game_config_audit_created
+    ON game_config_audit_log(created_at DESC);
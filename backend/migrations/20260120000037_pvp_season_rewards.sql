@@ -0,0 +1,59 @@
+-- PvP Season Rewards Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Season Closing
+-- ============================================
+-- Set when the season's final rankings are taken; a closed season is never
+-- ranked again
+ALTER TABLE pvp_seasons ADD COLUMN IF NOT EXISTS closed_at TIMESTAMPTZ;
+
+-- ============================================
+-- 2. Reward Tiers
+-- ============================================
+-- $BREACH (9 decimals) paid to the final ranks in each range. Ranks past
+-- the last tier, or past 100, get nothing.
+CREATE TABLE IF NOT EXISTS pvp_season_rewards (
+    id SERIAL PRIMARY KEY,
+    min_rank INT NOT NULL CHECK (min_rank >= 1),
+    max_rank INT NOT NULL CHECK (max_rank >= min_rank AND max_rank <= 100),
+    breach_amount BIGINT NOT NULL CHECK (breach_amount > 0)
+);
+
+INSERT INTO pvp_season_rewards (min_rank, max_rank, breach_amount) VALUES
+    (1, 1, 5000000000000),      -- 5,000 BREACH
+    (2, 3, 2500000000000),      -- 2,500 BREACH
+    (4, 10, 1000000000000),     -- 1,000 BREACH
+    (11, 50, 250000000000),     -- 250 BREACH
+    (51, 100, 100000000000);    -- 100 BREACH
+
+-- ============================================
+-- 3. Final Rankings
+-- ============================================
+-- Top of each closed season by Elo, with the reward each rank earned
+CREATE TABLE IF NOT EXISTS pvp_season_rankings (
+    season_id INT NOT NULL REFERENCES pvp_seasons(id),
+    player_id UUID NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    rank INT NOT NULL,
+    elo_rating INT NOT NULL,
+    rank_tier VARCHAR(20) NOT NULL,
+    matches_played INT NOT NULL,
+    matches_won INT NOT NULL,
+    reward_amount BIGINT NOT NULL DEFAULT 0,
+    -- Set when the reward is claimed for sending, so it's never sent twice;
+    -- the signature is missing when the transfer failed and the reward went
+    -- to `failed_rewards` for retry
+    paid_at TIMESTAMPTZ,
+    tx_signature VARCHAR(128),
+    PRIMARY KEY (season_id, player_id),
+    UNIQUE (season_id, rank)
+);
+
+CREATE INDEX IF NOT EXISTS idx_pvp_season_rankings_unpaid
+    ON pvp_season_rankings(season_id, rank)
+    WHERE reward_amount > 0 AND paid_at IS NULL;
+
+-- ============================================
+-- 4. Failed Season Rewards
+-- ============================================
+ALTER TYPE reward_reason ADD VALUE IF NOT EXISTS 'pvp_season';
@@ -0,0 +1,62 @@
+-- Guild Wars Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. War Status
+-- ============================================
+-- `pending` until the challenged guild accepts (or the challenge expires),
+-- `active` during its window, then `completed`
+CREATE TYPE guild_war_status AS ENUM ('pending', 'active', 'completed', 'expired');
+
+-- ============================================
+-- 2. Wars
+-- ============================================
+-- A guild that disbands leaves its side NULL; its names are kept for history
+CREATE TABLE IF NOT EXISTS guild_wars (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    challenger_guild_id UUID REFERENCES guilds(id) ON DELETE SET NULL,
+    defender_guild_id UUID REFERENCES guilds(id) ON DELETE SET NULL,
+    challenger_name VARCHAR(50) NOT NULL,
+    defender_name VARCHAR(50) NOT NULL,
+    status guild_war_status NOT NULL DEFAULT 'pending',
+    duration_hours INT NOT NULL CHECK (duration_hours BETWEEN 1 AND 168),
+    challenger_score INT NOT NULL DEFAULT 0,
+    defender_score INT NOT NULL DEFAULT 0,
+    -- NULL after a completed war means a tie (or both guilds gone)
+    winner_guild_id UUID REFERENCES guilds(id) ON DELETE SET NULL,
+    challenged_by UUID REFERENCES players(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    starts_at TIMESTAMPTZ,
+    ends_at TIMESTAMPTZ,
+    ended_at TIMESTAMPTZ,
+    CHECK (challenger_guild_id <> defender_guild_id)
+);
+
+-- Capture hook: active wars of a guild
+CREATE INDEX IF NOT EXISTS idx_guild_wars_active_challenger
+    ON guild_wars(challenger_guild_id) WHERE status = 'active';
+CREATE INDEX IF NOT EXISTS idx_guild_wars_active_defender
+    ON guild_wars(defender_guild_id) WHERE status = 'active';
+CREATE INDEX IF NOT EXISTS idx_guild_wars_ends ON guild_wars(ends_at) WHERE status = 'active';
+CREATE INDEX IF NOT EXISTS idx_guild_wars_history_challenger
+    ON guild_wars(challenger_guild_id, ended_at DESC);
+CREATE INDEX IF NOT EXISTS idx_guild_wars_history_defender
+    ON guild_wars(defender_guild_id, ended_at DESC);
+
+-- ============================================
+-- 3. Member Scores
+-- ============================================
+-- Captures each member made for their guild during a war
+CREATE TABLE IF NOT EXISTS guild_war_scores (
+    war_id UUID NOT NULL REFERENCES guild_wars(id) ON DELETE CASCADE,
+    player_id UUID NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    guild_id UUID NOT NULL,
+    captures INT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (war_id, player_id, guild_id)
+);
+
+-- ============================================
+-- 4. Notifications
+-- ============================================
+ALTER TYPE notification_type ADD VALUE IF NOT EXISTS 'guild_war';
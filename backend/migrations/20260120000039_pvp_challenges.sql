@@ -0,0 +1,33 @@
+-- PvP Challenges Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Challenge Status
+-- ============================================
+-- `pending` until the challenged player answers; one left unanswered past
+-- `expires_at` is marked `expired` by the matchmaking task
+CREATE TYPE pvp_challenge_status AS ENUM ('pending', 'accepted', 'rejected', 'expired');
+
+-- ============================================
+-- 2. Challenges
+-- ============================================
+-- A player inviting another straight into a match, bypassing the queue
+CREATE TABLE IF NOT EXISTS pvp_challenges (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    challenger_id UUID NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    target_id UUID NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    -- The Titan the challenger means to fight with, shown to the target
+    titan_id UUID NOT NULL REFERENCES player_titans(id) ON DELETE CASCADE,
+    match_format pvp_match_format NOT NULL DEFAULT 'single',
+    status pvp_challenge_status NOT NULL DEFAULT 'pending',
+    match_id UUID REFERENCES pvp_matches(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    responded_at TIMESTAMPTZ,
+    CHECK (challenger_id <> target_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_pvp_challenges_pending
+    ON pvp_challenges(challenger_id, target_id) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_pvp_challenges_expiry
+    ON pvp_challenges(expires_at) WHERE status = 'pending';
@@ -0,0 +1,12 @@
+-- Friend Location Sharing Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Per-Friend Sharing
+-- ============================================
+-- Each side of a friendship decides whether the other sees its coarse
+-- location; `player1_shares_location` is player1 sharing with player2.
+-- Deleting the friendship ends the sharing with it.
+ALTER TABLE friendships
+    ADD COLUMN IF NOT EXISTS player1_shares_location BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN IF NOT EXISTS player2_shares_location BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,37 @@
+-- Marketplace Payment Settlement Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Payment States
+-- ============================================
+-- `awaiting_payment`: an ended auction or accepted offer held for its buyer
+-- (`buyer_id`, at `final_price`) until they pay. `settling`: a buyer's payment
+-- is being sent; the listing goes back to where it was if the payment fails.
+ALTER TYPE listing_status ADD VALUE IF NOT EXISTS 'awaiting_payment';
+ALTER TYPE listing_status ADD VALUE IF NOT EXISTS 'settling';
+
+-- ============================================
+-- 2. Offer Sales
+-- ============================================
+-- An accepted offer is sold through a listing of its own
+ALTER TABLE price_offers ADD COLUMN IF NOT EXISTS listing_id UUID
+    REFERENCES marketplace_listings(id) ON DELETE SET NULL;
+
+-- ============================================
+-- 3. Escrow Sync
+-- ============================================
+-- A Titan stays in escrow until its sale is paid for
+CREATE OR REPLACE FUNCTION sync_titan_escrow()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.status IN ('active', 'awaiting_payment', 'settling') THEN
+        UPDATE player_titans SET escrowed_listing_id = NEW.id WHERE id = NEW.titan_id;
+    ELSIF TG_OP = 'UPDATE' AND OLD.status IN ('active', 'awaiting_payment', 'settling') THEN
+        UPDATE player_titans SET escrowed_listing_id = NULL
+        WHERE escrowed_listing_id = NEW.id
+          AND (id = NEW.titan_id
+               OR id IN (SELECT titan_id FROM listing_bundle_titans WHERE listing_id = NEW.id));
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
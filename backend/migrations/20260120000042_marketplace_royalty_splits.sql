@@ -0,0 +1,19 @@
+-- Marketplace Royalty Splits Migration
+-- Version: 0.9.0
+
+-- ============================================
+-- 1. Royalties per Titan
+-- ============================================
+-- Every royalty a sale pays, one row per Titan: a bundle's price is split
+-- evenly across its Titans, each owing its own creator. The transaction's
+-- `royalty_amount` is their sum, and `royalty_recipient_id` the creator when
+-- only one is paid.
+CREATE TABLE IF NOT EXISTS marketplace_royalties (
+    transaction_id UUID NOT NULL REFERENCES marketplace_transactions(id) ON DELETE CASCADE,
+    titan_id UUID NOT NULL REFERENCES player_titans(id) ON DELETE CASCADE,
+    creator_id UUID NOT NULL REFERENCES players(id),
+    amount BIGINT NOT NULL,
+    PRIMARY KEY (transaction_id, titan_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_marketplace_royalties_creator ON marketplace_royalties(creator_id);
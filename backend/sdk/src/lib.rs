@@ -0,0 +1,5 @@
+//! BREACH client SDK
+//!
+//! Wire formats shared between the backend and game clients.
+
+pub mod map;
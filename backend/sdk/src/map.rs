@@ -0,0 +1,339 @@
+//! Compact binary map snapshot (`application/x-breach-map-v1`)
+//!
+//! Columnar little-endian layout, negotiated with
+//! `Accept: application/x-breach-map-v1` or `?format=compact` on
+//! `GET /api/v1/map/titans`. JSON remains the default.
+//!
+//! ```text
+//! Header (20 bytes)
+//!   0..4    magic          b"BRMP"
+//!   4       version        u8 (1)
+//!   5..8    reserved       [u8; 3] (zero)
+//!   8..12   count          u32
+//!   12..20  generated_at   i64 Unix seconds
+//!
+//! Columns (count entries each, in this order)
+//!   lat            i32  microdegrees
+//!   lng            i32  microdegrees
+//!   element        u8   0 Abyssal .. 5 Ossified
+//!   threat_class   u8   1..=5
+//!   species_id     u16
+//!   expires_at     i64  Unix seconds
+//!   id             [u8; 16] UUID bytes
+//! ```
+//!
+//! Each Titan costs 36 bytes. Distance and POI name are not included;
+//! clients compute distance locally.
+
+/// Content type for version 1
+pub const CONTENT_TYPE: &str = "application/x-breach-map-v1";
+
+/// Header magic
+pub const MAGIC: [u8; 4] = *b"BRMP";
+
+/// Current format version
+pub const VERSION: u8 = 1;
+
+/// Header size in bytes
+pub const HEADER_SIZE: usize = 20;
+
+/// Bytes per Titan across all columns
+pub const RECORD_SIZE: usize = 4 + 4 + 1 + 1 + 2 + 8 + 16;
+
+/// One Titan in a compact snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactTitan {
+    pub id: [u8; 16],
+    /// Latitude in microdegrees
+    pub lat_e6: i32,
+    /// Longitude in microdegrees
+    pub lng_e6: i32,
+    pub element: u8,
+    pub threat_class: u8,
+    pub species_id: u16,
+    /// Unix seconds
+    pub expires_at: i64,
+}
+
+impl CompactTitan {
+    /// Convert degrees to microdegrees
+    pub fn to_microdegrees(degrees: f64) -> i32 {
+        (degrees * 1_000_000.0).round() as i32
+    }
+
+    pub fn lat(&self) -> f64 {
+        self.lat_e6 as f64 / 1_000_000.0
+    }
+
+    pub fn lng(&self) -> f64 {
+        self.lng_e6 as f64 / 1_000_000.0
+    }
+}
+
+/// Decoded snapshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapSnapshot {
+    /// Unix seconds when the server built the snapshot
+    pub generated_at: i64,
+    pub titans: Vec<CompactTitan>,
+}
+
+/// Snapshot decoding errors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Shorter than the header
+    TooShort,
+    /// Magic bytes do not match
+    BadMagic,
+    /// Version newer than this decoder
+    UnsupportedVersion(u8),
+    /// Body length does not match the header count
+    LengthMismatch { expected: usize, actual: usize },
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::TooShort => write!(f, "snapshot shorter than header"),
+            DecodeError::BadMagic => write!(f, "bad snapshot magic"),
+            DecodeError::UnsupportedVersion(v) => write!(f, "unsupported snapshot version {}", v),
+            DecodeError::LengthMismatch { expected, actual } => {
+                write!(f, "snapshot length {} does not match expected {}", actual, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Encode a snapshot
+pub fn encode(generated_at: i64, titans: &[CompactTitan]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(HEADER_SIZE + titans.len() * RECORD_SIZE);
+
+    // Header
+    buf.extend_from_slice(&MAGIC);
+    buf.push(VERSION);
+    buf.extend_from_slice(&[0u8; 3]);
+    buf.extend_from_slice(&(titans.len() as u32).to_le_bytes());
+    buf.extend_from_slice(&generated_at.to_le_bytes());
+
+    // Columns
+    for t in titans {
+        buf.extend_from_slice(&t.lat_e6.to_le_bytes());
+    }
+    for t in titans {
+        buf.extend_from_slice(&t.lng_e6.to_le_bytes());
+    }
+    for t in titans {
+        buf.push(t.element);
+    }
+    for t in titans {
+        buf.push(t.threat_class);
+    }
+    for t in titans {
+        buf.extend_from_slice(&t.species_id.to_le_bytes());
+    }
+    for t in titans {
+        buf.extend_from_slice(&t.expires_at.to_le_bytes());
+    }
+    for t in titans {
+        buf.extend_from_slice(&t.id);
+    }
+
+    buf
+}
+
+/// Decode a snapshot
+pub fn decode(data: &[u8]) -> Result<MapSnapshot, DecodeError> {
+    if data.len() < HEADER_SIZE {
+        return Err(DecodeError::TooShort);
+    }
+    if data[0..4] != MAGIC {
+        return Err(DecodeError::BadMagic);
+    }
+    if data[4] != VERSION {
+        return Err(DecodeError::UnsupportedVersion(data[4]));
+    }
+
+    let count = u32::from_le_bytes(data[8..12].try_into().unwrap()) as usize;
+    let generated_at = i64::from_le_bytes(data[12..20].try_into().unwrap());
+
+    let expected = count
+        .checked_mul(RECORD_SIZE)
+        .and_then(|body| body.checked_add(HEADER_SIZE))
+        .unwrap_or(usize::MAX);
+    if data.len() != expected {
+        return Err(DecodeError::LengthMismatch { expected, actual: data.len() });
+    }
+
+    let mut columns = Columns { data, offset: HEADER_SIZE };
+    let lat = columns.take(count, 4);
+    let lng = columns.take(count, 4);
+    let element = columns.take(count, 1);
+    let threat_class = columns.take(count, 1);
+    let species = columns.take(count, 2);
+    let expires = columns.take(count, 8);
+    let ids = columns.take(count, 16);
+
+    let titans = (0..count)
+        .map(|i| CompactTitan {
+            id: ids[i * 16..(i + 1) * 16].try_into().unwrap(),
+            lat_e6: i32::from_le_bytes(lat[i * 4..(i + 1) * 4].try_into().unwrap()),
+            lng_e6: i32::from_le_bytes(lng[i * 4..(i + 1) * 4].try_into().unwrap()),
+            element: element[i],
+            threat_class: threat_class[i],
+            species_id: u16::from_le_bytes(species[i * 2..(i + 1) * 2].try_into().unwrap()),
+            expires_at: i64::from_le_bytes(expires[i * 8..(i + 1) * 8].try_into().unwrap()),
+        })
+        .collect();
+
+    Ok(MapSnapshot { generated_at, titans })
+}
+
+/// Sequential column reader (length already validated)
+struct Columns<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Columns<'a> {
+    fn take(&mut self, count: usize, width: usize) -> &'a [u8] {
+        let start = self.offset;
+        self.offset += count * width;
+        &self.data[start..self.offset]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titan(i: u32) -> CompactTitan {
+        let mut id = [0u8; 16];
+        id[..4].copy_from_slice(&i.to_le_bytes());
+        id[15] = 0xAB;
+
+        CompactTitan {
+            id,
+            lat_e6: CompactTitan::to_microdegrees(35.6762 + i as f64 * 0.0001),
+            lng_e6: CompactTitan::to_microdegrees(139.6503 - i as f64 * 0.0001),
+            element: (i % 6) as u8,
+            threat_class: (i % 5 + 1) as u8,
+            species_id: (i * 7) as u16,
+            expires_at: 1_700_000_000 + i as i64,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let titans: Vec<_> = (0..100).map(titan).collect();
+        let bytes = encode(1_700_000_123, &titans);
+
+        assert_eq!(bytes.len(), HEADER_SIZE + titans.len() * RECORD_SIZE);
+
+        let snapshot = decode(&bytes).unwrap();
+        assert_eq!(snapshot.generated_at, 1_700_000_123);
+        assert_eq!(snapshot.titans, titans);
+    }
+
+    #[test]
+    fn test_round_trip_empty() {
+        let bytes = encode(42, &[]);
+        assert_eq!(bytes.len(), HEADER_SIZE);
+
+        let snapshot = decode(&bytes).unwrap();
+        assert_eq!(snapshot.generated_at, 42);
+        assert!(snapshot.titans.is_empty());
+    }
+
+    #[test]
+    fn test_round_trip_every_field_value() {
+        // Every element and threat class
+        let mut titans = Vec::new();
+        for element in 0..=5u8 {
+            for threat_class in 1..=5u8 {
+                titans.push(CompactTitan {
+                    element,
+                    threat_class,
+                    ..titan(titans.len() as u32)
+                });
+            }
+        }
+
+        // Coordinate, species, expiry and id extremes
+        for (lat, lng) in [(90.0, 180.0), (-90.0, -180.0), (0.0, 0.0), (-0.000001, 0.000001)] {
+            titans.push(CompactTitan {
+                lat_e6: CompactTitan::to_microdegrees(lat),
+                lng_e6: CompactTitan::to_microdegrees(lng),
+                ..titan(0)
+            });
+        }
+        for species_id in [0, 1, u16::MAX] {
+            titans.push(CompactTitan { species_id, ..titan(1) });
+        }
+        for expires_at in [0, -1, i64::MIN, i64::MAX] {
+            titans.push(CompactTitan { expires_at, ..titan(2) });
+        }
+        titans.push(CompactTitan { id: [0u8; 16], ..titan(3) });
+        titans.push(CompactTitan { id: [0xFF; 16], ..titan(4) });
+
+        let snapshot = decode(&encode(i64::MAX, &titans)).unwrap();
+        assert_eq!(snapshot.generated_at, i64::MAX);
+        assert_eq!(snapshot.titans, titans);
+    }
+
+    #[test]
+    fn test_microdegree_precision() {
+        for degrees in [35.676_234_5, -122.419_415_5, 0.000_000_4, 179.999_999] {
+            let t = CompactTitan {
+                lat_e6: CompactTitan::to_microdegrees(degrees / 2.0),
+                lng_e6: CompactTitan::to_microdegrees(degrees),
+                ..titan(0)
+            };
+            assert!((t.lng() - degrees).abs() <= 0.000_000_5 + f64::EPSILON);
+            assert!((t.lat() - degrees / 2.0).abs() <= 0.000_000_5 + f64::EPSILON);
+        }
+    }
+
+    #[test]
+    fn test_header_layout() {
+        let bytes = encode(-5, &[titan(0), titan(1)]);
+
+        assert_eq!(&bytes[0..4], b"BRMP");
+        assert_eq!(bytes[4], VERSION);
+        assert_eq!(&bytes[5..8], &[0, 0, 0]);
+        assert_eq!(&bytes[8..12], &2u32.to_le_bytes());
+        assert_eq!(&bytes[12..20], &(-5i64).to_le_bytes());
+        // First lat column entry follows the header
+        assert_eq!(&bytes[20..24], &titan(0).lat_e6.to_le_bytes());
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let bytes = encode(0, &[titan(0)]);
+
+        assert_eq!(decode(&bytes[..HEADER_SIZE - 1]), Err(DecodeError::TooShort));
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        assert_eq!(decode(&bad_magic), Err(DecodeError::BadMagic));
+
+        let mut bad_version = bytes.clone();
+        bad_version[4] = 2;
+        assert_eq!(decode(&bad_version), Err(DecodeError::UnsupportedVersion(2)));
+
+        assert_eq!(
+            decode(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::LengthMismatch { expected: bytes.len(), actual: bytes.len() - 1 })
+        );
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(decode(&trailing), Err(DecodeError::LengthMismatch { .. })));
+
+        // Huge count must not overflow
+        let mut huge = bytes.clone();
+        huge[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(decode(&huge), Err(DecodeError::LengthMismatch { .. })));
+    }
+}
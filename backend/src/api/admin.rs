@@ -0,0 +1,346 @@
+//! Admin endpoints (wallets listed in `auth.admin_wallets`)
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::config::{GameConfig, SpawnSchedule};
+use crate::error::{ApiResult, AppError};
+use crate::middleware::auth::AdminPlayer;
+use crate::models::{
+    ChatReport, CreateSpawnEventRequest, FeeBudgetSummary, PlayerFeeSpend, ReportsQuery,
+    ResolveReportRequest, ResolvedReport, SetFeeCapRequest, SpawnEvent,
+};
+use crate::services::{ConfigChangeSource, GameConfigUpdate};
+use crate::websocket::{ConnectionStats, WsMessage, ANNOUNCEMENT_SEVERITIES};
+use crate::AppState;
+
+/// Longest announcement title and body, in characters
+const MAX_ANNOUNCEMENT_TITLE: usize = 100;
+const MAX_ANNOUNCEMENT_BODY: usize = 1000;
+
+/// On-chain fee spend counters
+async fn get_fee_budget(
+    State(state): State<Arc<AppState>>,
+    AdminPlayer(_admin): AdminPlayer,
+) -> ApiResult<Json<FeeBudgetSummary>> {
+    let summary = state.services.fee_budget.summary().await?;
+
+    Ok(Json(summary))
+}
+
+/// A player's spend today and effective cap
+async fn get_player_fee_budget(
+    State(state): State<Arc<AppState>>,
+    AdminPlayer(_admin): AdminPlayer,
+    Path(player_id): Path<Uuid>,
+) -> ApiResult<Json<PlayerFeeSpend>> {
+    let spend = state.services.fee_budget.get_player_spend(player_id).await?;
+
+    Ok(Json(spend))
+}
+
+/// Raise (or reset) a player's daily cap, e.g. for partners
+async fn set_player_fee_cap(
+    State(state): State<Arc<AppState>>,
+    AdminPlayer(admin): AdminPlayer,
+    Path(player_id): Path<Uuid>,
+    Json(req): Json<SetFeeCapRequest>,
+) -> ApiResult<Json<PlayerFeeSpend>> {
+    state
+        .services
+        .fee_budget
+        .set_player_cap(player_id, req.daily_cap_lamports, req.note, &admin.wallet_address)
+        .await?;
+
+    tracing::info!(
+        "Fee cap for player {} set to {:?} by {}",
+        player_id,
+        req.daily_cap_lamports,
+        admin.wallet_address
+    );
+
+    let spend = state.services.fee_budget.get_player_spend(player_id).await?;
+
+    Ok(Json(spend))
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShadowBanResponse {
+    pub player_id: Uuid,
+    pub shadow_banned: bool,
+}
+
+/// Shadow-ban a confirmed cheater: they keep seeing the normal game, but
+/// their captures quietly don't happen and they earn no $BREACH
+async fn shadow_ban_player(
+    State(state): State<Arc<AppState>>,
+    AdminPlayer(admin): AdminPlayer,
+    Path(player_id): Path<Uuid>,
+) -> ApiResult<Json<ShadowBanResponse>> {
+    state.services.player.set_shadow_ban(player_id, true).await?;
+
+    tracing::info!("Player {} shadow-banned by {}", player_id, admin.wallet_address);
+
+    Ok(Json(ShadowBanResponse { player_id, shadow_banned: true }))
+}
+
+/// Lift a shadow ban
+async fn lift_shadow_ban(
+    State(state): State<Arc<AppState>>,
+    AdminPlayer(admin): AdminPlayer,
+    Path(player_id): Path<Uuid>,
+) -> ApiResult<Json<ShadowBanResponse>> {
+    state.services.player.set_shadow_ban(player_id, false).await?;
+
+    tracing::info!("Shadow ban on player {} lifted by {}", player_id, admin.wallet_address);
+
+    Ok(Json(ShadowBanResponse { player_id, shadow_banned: false }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnnouncementRequest {
+    pub title: String,
+    pub body: String,
+    /// `info` (default), `warning` or `critical`
+    #[serde(default = "default_severity")]
+    pub severity: String,
+}
+
+fn default_severity() -> String {
+    "info".to_string()
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnnouncementResponse {
+    pub sent: bool,
+    /// Connections on this instance it was delivered to
+    pub local_connections: usize,
+}
+
+/// Push a notice to every connected player
+async fn post_announcement(
+    State(state): State<Arc<AppState>>,
+    AdminPlayer(admin): AdminPlayer,
+    Json(req): Json<AnnouncementRequest>,
+) -> ApiResult<Json<AnnouncementResponse>> {
+    let title = req.title.trim();
+    let body = req.body.trim();
+    if title.is_empty() || title.chars().count() > MAX_ANNOUNCEMENT_TITLE {
+        return Err(AppError::Validation(format!(
+            "Title must be 1-{} characters",
+            MAX_ANNOUNCEMENT_TITLE
+        )));
+    }
+    if body.is_empty() || body.chars().count() > MAX_ANNOUNCEMENT_BODY {
+        return Err(AppError::Validation(format!(
+            "Body must be 1-{} characters",
+            MAX_ANNOUNCEMENT_BODY
+        )));
+    }
+    if !ANNOUNCEMENT_SEVERITIES.contains(&req.severity.as_str()) {
+        return Err(AppError::Validation(format!(
+            "Severity must be one of {}",
+            ANNOUNCEMENT_SEVERITIES.join(", ")
+        )));
+    }
+
+    let message = WsMessage::SystemAnnouncement {
+        title: title.to_string(),
+        body: body.to_string(),
+        severity: req.severity,
+    };
+    state.broadcaster.broadcast_global(message).await;
+
+    tracing::info!("System announcement {:?} sent by {}", title, admin.wallet_address);
+
+    Ok(Json(AnnouncementResponse {
+        sent: true,
+        local_connections: state.broadcaster.get_total_connections().await,
+    }))
+}
+
+/// WebSocket connections and message counters on this instance
+async fn get_ws_stats(
+    State(state): State<Arc<AppState>>,
+    AdminPlayer(_admin): AdminPlayer,
+) -> ApiResult<Json<ConnectionStats>> {
+    Ok(Json(state.broadcaster.connection_stats().await))
+}
+
+/// The `game` settings in effect
+async fn get_config(
+    State(state): State<Arc<AppState>>,
+    AdminPlayer(_admin): AdminPlayer,
+) -> ApiResult<Json<GameConfig>> {
+    Ok(Json(state.services.game_config.current().await))
+}
+
+/// Change some `game` settings, e.g. `{ "capture_radius_meters": 80 }`.
+/// Lasts until the next restart or reload.
+async fn patch_config(
+    State(state): State<Arc<AppState>>,
+    AdminPlayer(admin): AdminPlayer,
+    Json(patch): Json<serde_json::Map<String, serde_json::Value>>,
+) -> ApiResult<Json<GameConfigUpdate>> {
+    let update = state
+        .services
+        .game_config
+        .update(&patch, &admin.wallet_address)
+        .await?;
+
+    Ok(Json(update))
+}
+
+/// Re-read the config files, as a SIGHUP does
+async fn reload_config(
+    State(state): State<Arc<AppState>>,
+    AdminPlayer(admin): AdminPlayer,
+) -> ApiResult<Json<GameConfigUpdate>> {
+    let update = state
+        .services
+        .game_config
+        .reload(ConfigChangeSource::Reload, Some(&admin.wallet_address))
+        .await?;
+
+    Ok(Json(update))
+}
+
+/// Replace the live spawn schedule. Lasts until the next restart or SIGHUP
+/// reload, which restore the config file's schedule
+async fn post_spawn_schedule(
+    State(state): State<Arc<AppState>>,
+    AdminPlayer(admin): AdminPlayer,
+    Json(schedule): Json<SpawnSchedule>,
+) -> ApiResult<Json<SpawnSchedule>> {
+    schedule
+        .validate()
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+
+    state.game.write().await.spawn_schedule = schedule.clone();
+
+    tracing::info!(
+        "Spawn schedule replaced with {} entries by {}",
+        schedule.entries.len(),
+        admin.wallet_address
+    );
+
+    Ok(Json(schedule))
+}
+
+/// Schedule a timed spawn event
+async fn create_spawn_event(
+    State(state): State<Arc<AppState>>,
+    AdminPlayer(admin): AdminPlayer,
+    Json(req): Json<CreateSpawnEventRequest>,
+) -> ApiResult<Json<SpawnEvent>> {
+    let event = state
+        .services
+        .event_spawn
+        .create_event(req, &admin.wallet_address)
+        .await?;
+
+    tracing::info!(
+        "Spawn event {:?} scheduled for {} by {}",
+        event.name,
+        event.start_at,
+        admin.wallet_address
+    );
+
+    Ok(Json(event))
+}
+
+/// Upcoming, running and recently ended spawn events
+async fn list_spawn_events(
+    State(state): State<Arc<AppState>>,
+    AdminPlayer(_admin): AdminPlayer,
+) -> ApiResult<Json<Vec<SpawnEvent>>> {
+    let events = state.services.event_spawn.list_events().await?;
+
+    Ok(Json(events))
+}
+
+/// Delete an event that hasn't started, or end a running one early
+async fn cancel_spawn_event(
+    State(state): State<Arc<AppState>>,
+    AdminPlayer(admin): AdminPlayer,
+    Path(event_id): Path<Uuid>,
+) -> ApiResult<Json<serde_json::Value>> {
+    state.services.event_spawn.cancel_event(event_id).await?;
+
+    tracing::info!("Spawn event {} cancelled by {}", event_id, admin.wallet_address);
+
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+/// Chat reports by status (`pending` by default), oldest first
+async fn list_chat_reports(
+    State(state): State<Arc<AppState>>,
+    AdminPlayer(_admin): AdminPlayer,
+    Query(query): Query<ReportsQuery>,
+) -> ApiResult<Json<Vec<ChatReport>>> {
+    let reports = state.services.chat.get_reports(query).await?;
+
+    Ok(Json(reports))
+}
+
+/// Dismiss a chat report, or act on it: delete the message, or mute or ban
+/// its sender
+async fn resolve_chat_report(
+    State(state): State<Arc<AppState>>,
+    AdminPlayer(admin): AdminPlayer,
+    Path(report_id): Path<Uuid>,
+    Json(req): Json<ResolveReportRequest>,
+) -> ApiResult<Json<ResolvedReport>> {
+    let resolved = state
+        .services
+        .chat
+        .resolve_report(admin.player_id, report_id, req)
+        .await?;
+
+    if let (Some(channel_id), Some(message_id)) = (resolved.deleted_from_channel, resolved.report.message_id) {
+        let message = WsMessage::ChatMessageDeleted {
+            channel_id: channel_id.to_string(),
+            message_id: message_id.to_string(),
+        };
+        state.broadcaster.broadcast_chat_message(channel_id, message).await;
+    }
+
+    Ok(Json(resolved))
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/admin/fee-budget", get(get_fee_budget))
+        .route(
+            "/admin/fee-budget/players/:player_id",
+            get(get_player_fee_budget).put(set_player_fee_cap),
+        )
+        .route(
+            "/admin/players/:player_id/shadow-ban",
+            post(shadow_ban_player).delete(lift_shadow_ban),
+        )
+        .route("/admin/announcement", post(post_announcement))
+        .route("/admin/config", get(get_config).patch(patch_config))
+        .route("/admin/config/reload", post(reload_config))
+        .route("/admin/config/spawn-schedule", post(post_spawn_schedule))
+        .route("/admin/events", get(list_spawn_events).post(create_spawn_event))
+        .route("/admin/events/:id", delete(cancel_spawn_event))
+        .route("/admin/chat/reports", get(list_chat_reports))
+        .route("/admin/chat/reports/:id/resolve", post(resolve_chat_report))
+        .with_state(state)
+}
+
+/// Operational endpoints, mounted outside the `/api/v1` player routes so they
+/// keep working however those are layered
+pub fn ops_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/api/v1/admin/ws/stats", get(get_ws_stats))
+        .with_state(state)
+}
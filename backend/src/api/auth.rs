@@ -33,6 +33,8 @@ async fn authenticate(
     State(state): State<Arc<AppState>>,
     Json(req): Json<AuthRequest>,
 ) -> ApiResult<Json<AuthResponse>> {
+    req.check_issued_at()?;
+
     // Verify signature
     state
         .services
@@ -13,8 +13,8 @@ use uuid::Uuid;
 use crate::error::ApiResult;
 use crate::middleware::auth::AuthPlayer;
 use crate::models::{
-    Battle, BattleAction, BattleActionRequest, BattleResultResponse, BattleSummary,
-    StartWildBattleRequest,
+    Battle, BattleAction, BattleActionRequest, BattleSummary, DeferredOnchain,
+    OnchainActionPayload, RewardReason, StartWildBattleRequest,
 };
 use crate::AppState;
 
@@ -82,6 +82,9 @@ pub struct ExtendedBattleResultResponse {
     pub breach_reward: Option<u64>,
     pub breach_tx_signature: Option<String>,
     pub xp_reward: Option<u64>,
+    /// On-chain actions queued because the fee budget was exhausted
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub onchain_deferred: Vec<DeferredOnchain>,
 }
 
 /// End battle
@@ -101,7 +104,7 @@ async fn end_battle(
     let mut tx_signature = None;
     let mut breach_reward = None;
     let mut breach_tx_signature = None;
-    let xp_reward;
+    let mut onchain_deferred = Vec::new();
 
     // Calculate rewards based on battle result
     let (battle_type, battle_result_code) = if result.is_winner {
@@ -111,7 +114,7 @@ async fn end_battle(
     };
 
     // Calculate XP and BREACH rewards based on xp_earned from database
-    xp_reward = Some(result.xp_earned as u64);
+    let xp_reward = Some(result.xp_earned as u64);
 
     // Record on blockchain (if enabled)
     if !query.skip_blockchain {
@@ -119,38 +122,57 @@ async fn end_battle(
             // Use battle_id as placeholder for titan mint (in production, would look up actual mint)
             let titan_mint = result.battle_id.to_string();
             
-            // Record battle on-chain
-            match solana.record_battle(
-                &player.wallet_address,
-                &titan_mint,
+            // Record battle on-chain (queued if the fee budget is exhausted)
+            let record_payload = OnchainActionPayload::RecordBattle {
+                titan_mint: titan_mint.clone(),
                 battle_type,
-                battle_result_code,
-            ).await {
-                Ok(sig) => {
-                    tx_signature = Some(sig.clone());
-                    tracing::info!("Battle recorded on-chain: sig={}", sig);
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to record battle on-chain: {}", e);
+                result: battle_result_code,
+            };
+            if let Some(deferred) = state.services.fee_budget.authorize(player.player_id, record_payload).await? {
+                onchain_deferred.push(deferred);
+            } else {
+                match solana.record_battle(
+                    &player.wallet_address,
+                    &titan_mint,
+                    battle_type,
+                    battle_result_code,
+                ).await {
+                    Ok(sig) => {
+                        tx_signature = Some(sig.clone());
+                        tracing::info!("Battle recorded on-chain: sig={}", sig);
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to record battle on-chain: {}", e);
+                    }
                 }
             }
 
             // Distribute BREACH reward for victory
-            if result.is_winner {
+            if result.is_winner && !state.services.player.is_shadow_banned(player.player_id).await? {
                 let reward = calculate_battle_breach_reward(result.xp_earned);
                 if reward > 0 {
-                    match solana.transfer_breach_tokens(&player.wallet_address, reward).await {
-                        Ok(transfer_result) => {
-                            breach_reward = Some(transfer_result.amount);
-                            breach_tx_signature = Some(transfer_result.signature);
-                            tracing::info!(
-                                "Battle reward distributed: player={}, amount={}",
-                                player.wallet_address,
-                                reward
-                            );
-                        }
-                        Err(e) => {
-                            tracing::warn!("Failed to distribute battle reward: {}", e);
+                    let reward_payload = OnchainActionPayload::BreachTransfer { amount: reward };
+                    if let Some(deferred) = state.services.fee_budget.authorize(player.player_id, reward_payload).await? {
+                        onchain_deferred.push(deferred);
+                    } else {
+                        match solana.transfer_breach_tokens(&player.wallet_address, reward).await {
+                            Ok(transfer_result) => {
+                                breach_reward = Some(transfer_result.amount);
+                                breach_tx_signature = Some(transfer_result.signature);
+                                tracing::info!(
+                                    "Battle reward distributed: player={}, amount={}",
+                                    player.wallet_address,
+                                    reward
+                                );
+                            }
+                            Err(e) => {
+                                tracing::warn!("Failed to distribute battle reward: {}", e);
+                                state
+                                    .services
+                                    .reward
+                                    .record_failure_or_warn(player.player_id, reward, RewardReason::Battle, &e)
+                                    .await;
+                            }
                         }
                     }
                 }
@@ -158,7 +180,13 @@ async fn end_battle(
 
             // Add XP to Titan on-chain
             if let Some(xp) = xp_reward {
-                if let Err(e) = solana.add_titan_experience(&titan_mint, xp).await {
+                let xp_payload = OnchainActionPayload::AddExperience {
+                    titan_mint: titan_mint.clone(),
+                    xp,
+                };
+                if let Some(deferred) = state.services.fee_budget.authorize(player.player_id, xp_payload).await? {
+                    onchain_deferred.push(deferred);
+                } else if let Err(e) = solana.add_titan_experience(&titan_mint, xp).await {
                     tracing::warn!("Failed to add Titan XP on-chain: {}", e);
                 }
             }
@@ -178,6 +206,7 @@ async fn end_battle(
         breach_reward,
         breach_tx_signature,
         xp_reward,
+        onchain_deferred,
     }))
 }
 
@@ -4,14 +4,31 @@
 //! 1. Backend-paid mode (testing): POST /capture/confirm
 //! 2. Frontend-signed mode (production): POST /capture/build-transaction + POST /capture/submit-transaction
 
+use std::collections::HashSet;
 use std::sync::Arc;
 
-use axum::{extract::State, routing::post, Json, Router};
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use crate::error::{ApiResult, AppError};
 use crate::middleware::auth::AuthPlayer;
-use crate::models::{CaptureAuthorization, CaptureRequest};
+use crate::middleware::rate_limit::limit_captures;
+use crate::models::{
+    AppliedModifier, CaptureAttempt, CaptureAuthorization, CaptureChallenge, CaptureModifiers,
+    CaptureRequest, CooperativeCapture, DeferredOnchain, OnchainActionPayload, PlayerLocation,
+    PlayerSession, RewardReason, TitanSpawn, TrackedTransaction,
+};
+use crate::services::CooperativeConfirm;
+use crate::services::solana::{
+    MintParams, MintResult, SolanaService, TransferResult, REWARD_TYPE_CAPTURE,
+};
 use crate::websocket::WsMessage;
 use crate::AppState;
 
@@ -34,6 +51,8 @@ async fn request_capture(
 #[derive(Debug, Deserialize)]
 pub struct ConfirmCaptureRequest {
     pub titan_id: uuid::Uuid,
+    /// Challenge returned by POST /capture/request
+    pub challenge: CaptureChallenge,
     #[serde(default)]
     pub skip_blockchain: bool, // For testing without blockchain
 }
@@ -41,6 +60,8 @@ pub struct ConfirmCaptureRequest {
 #[derive(Debug, Serialize)]
 pub struct ConfirmCaptureResponse {
     pub success: bool,
+    /// Capture attempt; confirming the same spawn again returns the same one
+    pub attempt_id: String,
     pub titan_id: String,
     pub remaining_captures: i32,
     // Blockchain details
@@ -49,107 +70,235 @@ pub struct ConfirmCaptureResponse {
     pub tx_signature: Option<String>,
     pub breach_reward: Option<u64>,
     pub breach_tx_signature: Option<String>,
+    /// Time-of-day, crowding and streak modifiers applied to the reward
+    pub reward_modifiers: Vec<AppliedModifier>,
+    /// Captures in a row including this one; null on a retried confirm or
+    /// with streaks disabled
+    pub capture_streak: Option<u32>,
+    /// On-chain actions queued because the fee budget was exhausted
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub onchain_deferred: Vec<DeferredOnchain>,
 }
 
+impl ConfirmCaptureResponse {
+    /// Response for an attempt that is already confirmed (a retried confirm)
+    fn from_attempt(attempt: &CaptureAttempt) -> Self {
+        Self {
+            success: true,
+            attempt_id: attempt.id.to_string(),
+            titan_id: attempt.titan_spawn_id.to_string(),
+            remaining_captures: attempt.remaining_captures.unwrap_or(0),
+            mint_address: attempt.mint_address.clone(),
+            token_account: attempt.token_account.clone(),
+            tx_signature: attempt.tx_signature.clone(),
+            breach_reward: attempt.breach_reward.map(|amount| amount as u64),
+            breach_tx_signature: attempt.breach_tx_signature.clone(),
+            reward_modifiers: Vec::new(),
+            capture_streak: None,
+            onchain_deferred: Vec::new(),
+        }
+    }
+}
+
+/// Header that makes a confirm retry replay the first response
+const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+/// `Idempotency-Key` header, if sent
+fn idempotency_key(headers: &HeaderMap) -> ApiResult<Option<&str>> {
+    let Some(value) = headers.get(IDEMPOTENCY_KEY) else {
+        return Ok(None);
+    };
+    match value.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= 255 => Ok(Some(key)),
+        _ => Err(AppError::BadRequest(
+            "Idempotency-Key must be 1-255 visible ASCII characters".into(),
+        )),
+    }
+}
+
+/// Confirm a capture and mint the NFT
+///
+/// With an `Idempotency-Key` header, a repeat of the key by the same player
+/// within 10 minutes returns the first response without running again.
 async fn confirm_capture(
     State(state): State<Arc<AppState>>,
     AuthPlayer(player): AuthPlayer,
+    headers: HeaderMap,
     Json(request): Json<ConfirmCaptureRequest>,
-) -> ApiResult<Json<ConfirmCaptureResponse>> {
+) -> ApiResult<Response> {
+    let Some(key) = idempotency_key(&headers)? else {
+        return Ok(Json(run_confirm(&state, &player, request).await?).into_response());
+    };
+
+    let response = state
+        .services
+        .capture
+        .confirm_once(player.player_id, key, || run_confirm(&state, &player, request))
+        .await?;
+    Ok(Json(response).into_response())
+}
+
+/// Confirm a capture and mint the NFT
+///
+/// The attempt is recorded before touching Solana and advanced
+/// pending -> minted -> confirmed, so a retry after a timeout resumes where
+/// the previous call stopped: it never mints twice and never counts the
+/// capture twice.
+async fn run_confirm(
+    state: &AppState,
+    player: &PlayerSession,
+    request: ConfirmCaptureRequest,
+) -> ApiResult<ConfirmCaptureResponse> {
+    let (titan, mut attempt, modifiers) = match prepare_confirm(state, player, &request).await? {
+        PreparedConfirm::Confirmed(attempt) => return Ok(ConfirmCaptureResponse::from_attempt(&attempt)),
+        PreparedConfirm::Pending { titan, attempt, modifiers } => (titan, attempt, modifiers),
+    };
+
+    let mut onchain_deferred = Vec::new();
+    let solana = if request.skip_blockchain {
+        None
+    } else {
+        state.services.solana.as_ref()
+    };
+
+    // Mint NFT on Solana (if blockchain enabled and not minted by an earlier try)
+    if let Some(solana) = solana.filter(|_| attempt.needs_mint()) {
+        attempt = mint_capture(
+            state,
+            solana,
+            player.player_id,
+            &player.wallet_address,
+            &titan,
+            attempt,
+            &mut onchain_deferred,
+        )
+        .await?;
+    }
+
+    finish_confirm(state, player, solana, &titan, attempt, modifiers, onchain_deferred).await
+}
+
+/// A confirm that passed its checks, or one an earlier call already finished
+enum PreparedConfirm {
+    Confirmed(CaptureAttempt),
+    Pending {
+        titan: TitanSpawn,
+        attempt: CaptureAttempt,
+        modifiers: CaptureModifiers,
+    },
+}
+
+/// Check the challenge and that the Titan is still capturable, and record
+/// (or resume) the player's attempt
+async fn prepare_confirm(
+    state: &AppState,
+    player: &PlayerSession,
+    request: &ConfirmCaptureRequest,
+) -> ApiResult<PreparedConfirm> {
     // Get the titan before confirming (to get data for minting)
     let titan = state.services.map.get_titan(request.titan_id).await?
         .ok_or(AppError::TitanNotFound)?;
 
+    state
+        .services
+        .capture
+        .verify_challenge(player.player_id, request.titan_id, &request.challenge)
+        .await?;
+
+    let attempt = state
+        .services
+        .capture
+        .begin_capture_attempt(player.player_id, request.titan_id)
+        .await?;
+
+    // Already done: return what the first call produced
+    if attempt.is_confirmed() {
+        return Ok(PreparedConfirm::Confirmed(attempt));
+    }
+
     // Verify titan is capturable
     if titan.captured_by.is_some() && titan.capture_count >= titan.max_captures {
         return Err(AppError::TitanAlreadyCaptured);
     }
 
-    // Initialize response fields
-    let mut mint_address = None;
-    let mut token_account = None;
-    let mut tx_signature = None;
-    let mut breach_reward = None;
-    let mut breach_tx_signature = None;
-
-    // Mint NFT on Solana (if blockchain enabled)
-    if !request.skip_blockchain {
-        if let Some(solana) = &state.services.solana {
-            // Convert genes from Vec<u8> to [u8; 32]
-            let mut genes_array = [0u8; 32];
-            let len = titan.genes.len().min(32);
-            genes_array[..len].copy_from_slice(&titan.genes[..len]);
-
-            // Mint the Titan NFT
-            match solana.mint_titan_nft(
-                &player.wallet_address,
-                titan.element,
-                titan.threat_class as u8,
-                titan.species_id as u32,
-                genes_array,
-            ).await {
-                Ok(result) => {
-                    mint_address = Some(result.mint_address.clone());
-                    token_account = Some(result.token_account.clone());
-                    tx_signature = Some(result.signature.clone());
+    // Shadow-banned: looks captured to the player, nothing actually happens
+    if state.services.player.is_shadow_banned(player.player_id).await? {
+        let attempt = state
+            .services
+            .capture
+            .shadow_capture(attempt.id, shown_remaining_captures(&titan))
+            .await?;
+        return Ok(PreparedConfirm::Confirmed(attempt));
+    }
 
-                    tracing::info!(
-                        "NFT minted: player={}, mint={}, sig={}",
-                        player.wallet_address,
-                        result.mint_address,
-                        result.signature
-                    );
+    // Reward modifiers (evaluated before this capture is logged)
+    let modifiers = state
+        .services
+        .capture
+        .capture_modifiers(&titan, player.player_id)
+        .await?;
 
-                    // Record capture on Game Logic contract
-                    if let Err(e) = solana.record_capture(
-                        &player.wallet_address,
-                        &result.mint_address,
-                        &titan.geohash,
-                    ).await {
-                        tracing::warn!("Failed to record capture on-chain: {}", e);
-                    }
-                }
-                Err(e) => {
-                    tracing::error!("Failed to mint NFT: {}", e);
-                    // Continue without minting - don't fail the capture
-                }
-            }
+    Ok(PreparedConfirm::Pending { titan, attempt, modifiers })
+}
 
-            // Calculate and distribute $BREACH reward based on threat class
-            let reward_amount = calculate_breach_reward(titan.threat_class);
-            if reward_amount > 0 {
-                match solana.transfer_breach_tokens(&player.wallet_address, reward_amount).await {
-                    Ok(result) => {
-                        breach_reward = Some(result.amount);
-                        breach_tx_signature = Some(result.signature);
-                        
-                        tracing::info!(
-                            "BREACH reward distributed: player={}, amount={}",
-                            player.wallet_address,
-                            reward_amount
-                        );
-                    }
-                    Err(e) => {
-                        tracing::warn!("Failed to distribute BREACH reward: {}", e);
-                    }
-                }
-            }
-        }
-    }
+/// Captures left on `titan` as a capture by this player would leave them
+fn shown_remaining_captures(titan: &TitanSpawn) -> i32 {
+    (titan.max_captures - titan.capture_count - 1).max(0)
+}
 
+/// Confirm a (minted, deferred or off-chain) attempt, pay its reward and
+/// announce the capture
+async fn finish_confirm(
+    state: &AppState,
+    player: &PlayerSession,
+    solana: Option<&SolanaService>,
+    titan: &TitanSpawn,
+    attempt: CaptureAttempt,
+    modifiers: CaptureModifiers,
+    mut onchain_deferred: Vec<DeferredOnchain>,
+) -> ApiResult<ConfirmCaptureResponse> {
     // Confirm the capture in database
-    state
+    let attempt = state
         .services
         .capture
-        .confirm_capture(request.titan_id, player.player_id)
+        .finalize_capture(attempt.id)
         .await?;
+    state.metrics.captures_confirmed.inc();
+    let remaining_captures = attempt.remaining_captures.unwrap_or(0);
+    let (modifiers, capture_streak) = with_capture_streak(state, player.player_id, modifiers).await;
 
-    // Calculate remaining captures
-    let remaining_captures = titan.max_captures - titan.capture_count - 1;
+    // Calculate and distribute $BREACH reward based on threat class (after the
+    // capture is confirmed, so a retry can't pay it twice)
+    let mut breach_reward = None;
+    let mut breach_tx_signature = None;
+    if let Some(solana) = solana {
+        let reward_amount = modifiers.apply_reward(calculate_breach_reward(titan.threat_class));
+        let paid = distribute_reward(
+            state,
+            solana,
+            player.player_id,
+            &player.wallet_address,
+            reward_amount,
+            &mut onchain_deferred,
+        )
+        .await?;
+        if let Some(result) = paid {
+            if let Err(e) = state
+                .services
+                .capture
+                .record_capture_reward(attempt.id, result.amount, &result.signature)
+                .await
+            {
+                tracing::warn!("Failed to record capture reward: {}", e);
+            }
+            breach_reward = Some(result.amount);
+            breach_tx_signature = Some(result.signature);
+        }
+    }
 
     // Broadcast capture event via WebSocket
     let message = WsMessage::TitanCaptured {
-        titan_id: request.titan_id.to_string(),
+        titan_id: titan.id.to_string(),
         captured_by: player.wallet_address.clone(),
         remaining_captures,
     };
@@ -159,23 +308,386 @@ async fn confirm_capture(
         .broadcast_to_neighbors(&titan.geohash, message)
         .await;
 
+    // Still capturable by others: send the new count so the map can grey it out
+    if remaining_captures > 0 {
+        let update = WsMessage::TitanUpdate {
+            titan_id: titan.id.to_string(),
+            capture_count: titan.max_captures - remaining_captures,
+            remaining_captures,
+        };
+        state
+            .broadcaster
+            .broadcast_to_neighbors(&titan.geohash, update)
+            .await;
+    }
+
     tracing::info!(
         "Player {} captured Titan {} ({} captures remaining)",
         player.wallet_address,
-        request.titan_id,
+        titan.id,
         remaining_captures
     );
 
-    Ok(Json(ConfirmCaptureResponse {
-        success: true,
-        titan_id: request.titan_id.to_string(),
-        remaining_captures,
-        mint_address,
-        token_account,
-        tx_signature,
+    Ok(ConfirmCaptureResponse {
         breach_reward,
         breach_tx_signature,
-    }))
+        reward_modifiers: modifiers.applied,
+        capture_streak,
+        onchain_deferred,
+        ..ConfirmCaptureResponse::from_attempt(&attempt)
+    })
+}
+
+/// Captures per `POST /capture/confirm-batch`
+const MAX_CONFIRM_BATCH: usize = 10;
+
+/// Outcome of one capture in a batch confirm
+#[derive(Debug, Serialize)]
+pub struct BatchConfirmItem {
+    pub titan_id: String,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<ConfirmCaptureResponse>,
+    /// Same `{code, message, details}` object a single confirm fails with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<serde_json::Value>,
+}
+
+impl BatchConfirmItem {
+    fn new(titan_id: Uuid, outcome: ApiResult<ConfirmCaptureResponse>) -> Self {
+        match outcome {
+            Ok(result) => Self {
+                titan_id: titan_id.to_string(),
+                success: true,
+                result: Some(result),
+                error: None,
+            },
+            Err(e) => Self {
+                titan_id: titan_id.to_string(),
+                success: false,
+                result: None,
+                error: Some(e.status_and_body().1["error"].take()),
+            },
+        }
+    }
+}
+
+/// A batch item that passed its checks
+struct BatchEntry {
+    index: usize,
+    titan: TitanSpawn,
+    attempt: CaptureAttempt,
+    modifiers: CaptureModifiers,
+    onchain_deferred: Vec<DeferredOnchain>,
+}
+
+/// Confirm several captures, minting their NFTs in as few transactions as fit
+///
+/// Every Titan is checked before anything is minted. Each capture succeeds
+/// or fails on its own: one whose mint fails stays pending (a later confirm
+/// resumes it) while the rest of the batch is confirmed.
+async fn confirm_capture_batch(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Json(requests): Json<Vec<ConfirmCaptureRequest>>,
+) -> ApiResult<Json<Vec<BatchConfirmItem>>> {
+    if requests.is_empty() || requests.len() > MAX_CONFIRM_BATCH {
+        return Err(AppError::BadRequest(format!(
+            "Batch confirm takes 1-{} captures, got {}",
+            MAX_CONFIRM_BATCH,
+            requests.len()
+        )));
+    }
+
+    let mut outcomes: Vec<Option<ApiResult<ConfirmCaptureResponse>>> =
+        requests.iter().map(|_| None).collect();
+    let mut entries = Vec::new();
+    let mut seen = HashSet::new();
+    for (index, request) in requests.iter().enumerate() {
+        if !seen.insert(request.titan_id) {
+            outcomes[index] = Some(Err(AppError::BadRequest("Titan is already in this batch".into())));
+            continue;
+        }
+        match prepare_confirm(&state, &player, request).await {
+            Ok(PreparedConfirm::Confirmed(attempt)) => {
+                outcomes[index] = Some(Ok(ConfirmCaptureResponse::from_attempt(&attempt)));
+            }
+            Ok(PreparedConfirm::Pending { titan, attempt, modifiers }) => entries.push(BatchEntry {
+                index,
+                titan,
+                attempt,
+                modifiers,
+                onchain_deferred: Vec::new(),
+            }),
+            Err(e) => outcomes[index] = Some(Err(e)),
+        }
+    }
+
+    let solana_for = |request: &ConfirmCaptureRequest| {
+        state.services.solana.as_ref().filter(|_| !request.skip_blockchain)
+    };
+
+    // Mint every capture that still needs it (and fits the fee budget) together
+    let mut mints = Vec::new();
+    let mut minting = Vec::new();
+    for (slot, entry) in entries.iter_mut().enumerate() {
+        if solana_for(&requests[entry.index]).is_none() || !entry.attempt.needs_mint() {
+            continue;
+        }
+        let params = mint_params(&player.wallet_address, &entry.titan);
+        match authorize_mint(&state, player.player_id, &entry.titan, &params).await {
+            Ok(Some(deferred)) => entry.onchain_deferred.push(deferred),
+            Ok(None) => {
+                mints.push(params);
+                minting.push(slot);
+            }
+            Err(e) => outcomes[entry.index] = Some(Err(e)),
+        }
+    }
+    if let Some(solana) = state.services.solana.as_ref().filter(|_| !mints.is_empty()) {
+        let minted = solana.mint_titans_batch(mints).await;
+        for (slot, result) in minting.into_iter().zip(minted) {
+            let entry = &mut entries[slot];
+            let recorded = match result {
+                Ok(result) => {
+                    record_mint(&state, solana, &player.wallet_address, &entry.titan, entry.attempt.id, &result)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+            match recorded {
+                Ok(attempt) => entry.attempt = attempt,
+                Err(e) => outcomes[entry.index] = Some(Err(e)),
+            }
+        }
+    }
+
+    // Confirm each capture that got this far on its own
+    for entry in entries {
+        if outcomes[entry.index].is_some() {
+            continue;
+        }
+        let solana = solana_for(&requests[entry.index]);
+        let outcome = finish_confirm(
+            &state,
+            &player,
+            solana,
+            &entry.titan,
+            entry.attempt,
+            entry.modifiers,
+            entry.onchain_deferred,
+        )
+        .await;
+        outcomes[entry.index] = Some(outcome);
+    }
+
+    Ok(Json(
+        requests
+            .iter()
+            .zip(outcomes)
+            .map(|(request, outcome)| {
+                let outcome = outcome.unwrap_or_else(|| Err(AppError::Internal(anyhow::anyhow!("Capture not confirmed"))));
+                BatchConfirmItem::new(request.titan_id, outcome)
+            })
+            .collect(),
+    ))
+}
+
+/// Mint a pending capture's NFT to `wallet` and mark the attempt minted.
+/// Queued when the player's fee budget is exhausted; a failed mint is logged
+/// and leaves the attempt pending rather than failing the capture.
+async fn mint_capture(
+    state: &AppState,
+    solana: &SolanaService,
+    player_id: uuid::Uuid,
+    wallet: &str,
+    titan: &TitanSpawn,
+    mut attempt: CaptureAttempt,
+    onchain_deferred: &mut Vec<DeferredOnchain>,
+) -> ApiResult<CaptureAttempt> {
+    let params = mint_params(wallet, titan);
+
+    // Mint the Titan NFT (queued if the fee budget is exhausted)
+    if let Some(deferred) = authorize_mint(state, player_id, titan, &params).await? {
+        onchain_deferred.push(deferred);
+        return Ok(attempt);
+    }
+
+    // Batched with other captures when the mint queue is enabled
+    let minted = match &state.services.mint_queue {
+        Some(queue) => queue.mint(params).await,
+        None => solana.mint_titan_nft(
+            &params.player_wallet,
+            params.element,
+            params.threat_class,
+            params.species_id,
+            params.genes,
+        ).await,
+    };
+    match minted {
+        Ok(result) => {
+            attempt = record_mint(state, solana, wallet, titan, attempt.id, &result).await?;
+        }
+        Err(e) => {
+            tracing::error!("Failed to mint NFT: {}", e);
+            // Continue without minting - don't fail the capture
+        }
+    }
+
+    Ok(attempt)
+}
+
+/// Mint parameters for `titan` going to `wallet`
+fn mint_params(wallet: &str, titan: &TitanSpawn) -> MintParams {
+    // Convert genes from Vec<u8> to [u8; 32]
+    let mut genes = [0u8; 32];
+    let len = titan.genes.len().min(32);
+    genes[..len].copy_from_slice(&titan.genes[..len]);
+
+    MintParams {
+        player_wallet: wallet.to_string(),
+        element: titan.element,
+        threat_class: titan.threat_class as u8,
+        species_id: titan.species_id as u32,
+        genes,
+    }
+}
+
+/// Charge a capture mint to the player's fee budget; `Some` when it was
+/// queued instead
+async fn authorize_mint(
+    state: &AppState,
+    player_id: uuid::Uuid,
+    titan: &TitanSpawn,
+    params: &MintParams,
+) -> ApiResult<Option<DeferredOnchain>> {
+    let mint_payload = OnchainActionPayload::CaptureMint {
+        element: params.element,
+        threat_class: params.threat_class,
+        species_id: params.species_id,
+        genes: params.genes.to_vec(),
+        geohash: titan.geohash.clone(),
+    };
+
+    state
+        .services
+        .fee_budget
+        .authorize(player_id, mint_payload)
+        .await
+}
+
+/// Mark an attempt minted and record the capture on the Game Logic contract
+async fn record_mint(
+    state: &AppState,
+    solana: &SolanaService,
+    wallet: &str,
+    titan: &TitanSpawn,
+    attempt_id: Uuid,
+    result: &MintResult,
+) -> ApiResult<CaptureAttempt> {
+    tracing::info!(
+        "NFT minted: player={}, mint={}, sig={}",
+        wallet,
+        result.mint_address,
+        result.signature
+    );
+
+    let attempt = state
+        .services
+        .capture
+        .mark_minted(
+            attempt_id,
+            &result.signature,
+            &result.mint_address,
+            Some(result.token_account.as_str()),
+        )
+        .await?;
+
+    // Record capture on Game Logic contract
+    if let Err(e) = solana.record_capture(
+        wallet,
+        &result.mint_address,
+        &titan.geohash,
+    ).await {
+        tracing::warn!("Failed to record capture on-chain: {}", e);
+    }
+
+    Ok(attempt)
+}
+
+/// Pay `amount` of $BREACH to `wallet` as a capture reward; `None` when
+/// nothing was paid (zero amount, queued by the fee budget, or a failed
+/// transfer, which is logged)
+async fn distribute_reward(
+    state: &AppState,
+    solana: &SolanaService,
+    player_id: uuid::Uuid,
+    wallet: &str,
+    amount: u64,
+    onchain_deferred: &mut Vec<DeferredOnchain>,
+) -> ApiResult<Option<TransferResult>> {
+    if amount == 0 {
+        return Ok(None);
+    }
+    // Shadow-banned players are paid nothing, as if the transfer had failed
+    if state.services.player.is_shadow_banned(player_id).await? {
+        return Ok(None);
+    }
+
+    let reward_deferred = state
+        .services
+        .fee_budget
+        .authorize(player_id, OnchainActionPayload::BreachTransfer { amount })
+        .await?;
+
+    if let Some(deferred) = reward_deferred {
+        onchain_deferred.push(deferred);
+        return Ok(None);
+    }
+
+    match pay_capture_reward(state, solana, wallet, amount).await {
+        Ok(result) => {
+            tracing::info!("BREACH reward distributed: player={}, amount={}", wallet, amount);
+            Ok(Some(result))
+        }
+        Err(e) => {
+            tracing::warn!("Failed to distribute BREACH reward: {}", e);
+            state.services.reward.record_failure_or_warn(player_id, amount, RewardReason::Capture, &e).await;
+            Ok(None)
+        }
+    }
+}
+
+/// Count a confirmed capture into the player's streak and add its reward
+/// bonus. A Redis failure is logged and leaves the reward without one.
+async fn with_capture_streak(
+    state: &AppState,
+    player_id: uuid::Uuid,
+    modifiers: CaptureModifiers,
+) -> (CaptureModifiers, Option<u32>) {
+    match state.services.capture.record_capture_streak(player_id).await {
+        Ok(Some(streak)) => (modifiers.with_streak(streak), Some(streak)),
+        Ok(None) => (modifiers, None),
+        Err(e) => {
+            tracing::warn!("Failed to update capture streak: {}", e);
+            (modifiers, None)
+        }
+    }
+}
+
+/// Pay a capture reward: accrue it in the player's on-chain reward ledger when
+/// `game.reward_accrual_enabled` is set, otherwise transfer it right away
+async fn pay_capture_reward(
+    state: &AppState,
+    solana: &SolanaService,
+    wallet: &str,
+    amount: u64,
+) -> ApiResult<TransferResult> {
+    if state.game.read().await.reward_accrual_enabled {
+        solana.accrue_reward_onchain(wallet, REWARD_TYPE_CAPTURE, amount).await
+    } else {
+        solana.transfer_breach_tokens(wallet, amount).await
+    }
 }
 
 /// Calculate BREACH reward based on threat class
@@ -186,7 +698,7 @@ fn calculate_breach_reward(threat_class: i16) -> u64 {
     const BASE_REWARD: u64 = 100_000_000; // 0.1 BREACH
     
     match threat_class {
-        1 => BASE_REWARD * 1,      // 0.1 BREACH
+        1 => BASE_REWARD,          // 0.1 BREACH
         2 => BASE_REWARD * 3,      // 0.3 BREACH
         3 => BASE_REWARD * 10,     // 1 BREACH
         4 => BASE_REWARD * 50,     // 5 BREACH
@@ -315,6 +827,8 @@ pub struct SubmitSignedTransactionRequest {
 #[derive(Debug, Serialize)]
 pub struct SubmitSignedTransactionResponse {
     pub success: bool,
+    /// Capture attempt; resubmitting for the same spawn returns the same one.
+    pub attempt_id: String,
     /// Transaction signature.
     pub tx_signature: String,
     /// Titan PDA (NFT address).
@@ -325,6 +839,14 @@ pub struct SubmitSignedTransactionResponse {
     pub breach_reward: Option<u64>,
     /// BREACH reward transaction signature.
     pub breach_tx_signature: Option<String>,
+    /// Time-of-day, crowding and streak modifiers applied to the reward.
+    pub reward_modifiers: Vec<AppliedModifier>,
+    /// Captures in a row including this one; null on a resubmit or with
+    /// streaks disabled.
+    pub capture_streak: Option<u32>,
+    /// On-chain actions queued because the fee budget was exhausted.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub onchain_deferred: Vec<DeferredOnchain>,
 }
 
 /// Submit a signed mint transaction.
@@ -339,6 +861,27 @@ async fn submit_signed_transaction(
     let titan = state.services.map.get_titan(request.titan_id).await?
         .ok_or(AppError::TitanNotFound)?;
 
+    // Record the attempt before broadcasting anything.
+    let attempt = state.services.capture
+        .begin_capture_attempt(player.player_id, request.titan_id)
+        .await?;
+
+    // Already confirmed: return the stored result.
+    if attempt.is_confirmed() {
+        return Ok(Json(SubmitSignedTransactionResponse {
+            success: true,
+            attempt_id: attempt.id.to_string(),
+            tx_signature: attempt.tx_signature.unwrap_or_default(),
+            mint_address: attempt.mint_address.unwrap_or(request.titan_pda),
+            remaining_captures: attempt.remaining_captures.unwrap_or(0),
+            breach_reward: attempt.breach_reward.map(|amount| amount as u64),
+            breach_tx_signature: attempt.breach_tx_signature,
+            reward_modifiers: Vec::new(),
+            capture_streak: None,
+            onchain_deferred: Vec::new(),
+        }));
+    }
+
     // Validate Titan can be captured.
     if titan.captured_by.is_some() && titan.capture_count >= titan.max_captures {
         return Err(AppError::TitanAlreadyCaptured);
@@ -348,47 +891,110 @@ async fn submit_signed_transaction(
     let solana = state.services.solana.as_ref()
         .ok_or(AppError::Internal(anyhow::anyhow!("Solana service not available")))?;
 
-    // Submit transaction.
-    let result = solana.submit_signed_transaction(
-        &request.serialized_transaction,
-        &request.player_signature,
-        &player.wallet_address,
-    ).await?;
+    // Shadow-banned: the transaction is never broadcast.
+    if state.services.player.is_shadow_banned(player.player_id).await? {
+        let attempt = state.services.capture
+            .shadow_capture(attempt.id, shown_remaining_captures(&titan))
+            .await?;
+        return Ok(Json(SubmitSignedTransactionResponse {
+            success: true,
+            attempt_id: attempt.id.to_string(),
+            tx_signature: String::new(),
+            mint_address: request.titan_pda,
+            remaining_captures: attempt.remaining_captures.unwrap_or(0),
+            breach_reward: None,
+            breach_tx_signature: None,
+            reward_modifiers: Vec::new(),
+            capture_streak: None,
+            onchain_deferred: Vec::new(),
+        }));
+    }
 
-    tracing::info!(
-        "NFT minted via signed transaction: player={}, sig={}",
-        player.wallet_address, result.signature
-    );
+    // Reward modifiers (evaluated before this capture is logged).
+    let modifiers = state.services.capture
+        .capture_modifiers(&titan, player.player_id)
+        .await?;
+
+    // Submit transaction, unless an earlier submit already landed.
+    let attempt = if attempt.needs_mint() {
+        let transaction = solana.sign_player_transaction(
+            &request.serialized_transaction,
+            &request.player_signature,
+            &player.wallet_address,
+        )?;
+
+        // Tracked before sending, in case it lands after the send times out
+        let tracked = TrackedTransaction::capture_mint(player.player_id, attempt.id, &request.titan_pda);
+        let signature = state.services.transaction.track(tracked, &transaction).await?;
+        let result = solana.send_signed(&transaction).await;
+        state.services.transaction.record_send(&signature, &result).await;
+
+        let result = result.inspect_err(|_| state.metrics.mint_failures.inc())?;
+        state.metrics.nfts_minted.inc();
+
+        tracing::info!(
+            "NFT minted via signed transaction: player={}, sig={}",
+            player.wallet_address, result.signature
+        );
+
+        state.services.capture
+            .mark_minted(attempt.id, &result.signature, &request.titan_pda, None)
+            .await?
+    } else {
+        attempt
+    };
+
+    // Record capture in database.
+    let attempt = state.services.capture
+        .finalize_capture(attempt.id)
+        .await?;
+    state.metrics.captures_confirmed.inc();
+    let remaining_captures = attempt.remaining_captures.unwrap_or(0);
+    let (modifiers, capture_streak) = with_capture_streak(&state, player.player_id, modifiers).await;
 
     // Distribute BREACH rewards.
     let mut breach_reward = None;
     let mut breach_tx_signature = None;
+    let mut onchain_deferred = Vec::new();
     
-    let reward_amount = calculate_breach_reward(titan.threat_class);
+    let reward_amount = modifiers.apply_reward(calculate_breach_reward(titan.threat_class));
     if reward_amount > 0 {
-        match solana.transfer_breach_tokens(&player.wallet_address, reward_amount).await {
-            Ok(transfer_result) => {
-                breach_reward = Some(transfer_result.amount);
-                breach_tx_signature = Some(transfer_result.signature);
-                tracing::info!(
-                    "BREACH reward distributed: player={}, amount={}",
-                    player.wallet_address, reward_amount
-                );
-            }
-            Err(e) => {
-                tracing::warn!("Failed to distribute BREACH reward: {}", e);
+        let reward_deferred = state
+            .services
+            .fee_budget
+            .authorize(player.player_id, OnchainActionPayload::BreachTransfer { amount: reward_amount })
+            .await?;
+
+        if let Some(deferred) = reward_deferred {
+            onchain_deferred.push(deferred);
+        } else {
+            match pay_capture_reward(&state, solana, &player.wallet_address, reward_amount).await {
+                Ok(transfer_result) => {
+                    tracing::info!(
+                        "BREACH reward distributed: player={}, amount={}",
+                        player.wallet_address, reward_amount
+                    );
+                    if let Err(e) = state.services.capture
+                        .record_capture_reward(attempt.id, transfer_result.amount, &transfer_result.signature)
+                        .await
+                    {
+                        tracing::warn!("Failed to record capture reward: {}", e);
+                    }
+                    breach_reward = Some(transfer_result.amount);
+                    breach_tx_signature = Some(transfer_result.signature);
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to distribute BREACH reward: {}", e);
+                    state
+                        .services
+                        .reward
+                        .record_failure_or_warn(player.player_id, reward_amount, RewardReason::Capture, &e)
+                        .await;
+                }
             }
         }
     }
 
-    // Record capture in database.
-    state.services.capture
-        .confirm_capture(request.titan_id, player.player_id)
-        .await?;
-
-    // Compute remaining captures.
-    let remaining_captures = titan.max_captures - titan.capture_count - 1;
-
     // Broadcast WebSocket events.
     let message = WsMessage::TitanCaptured {
         titan_id: request.titan_id.to_string(),
@@ -396,6 +1002,14 @@ async fn submit_signed_transaction(
         remaining_captures,
     };
     state.broadcaster.broadcast_to_neighbors(&titan.geohash, message).await;
+    if remaining_captures > 0 {
+        let update = WsMessage::TitanUpdate {
+            titan_id: request.titan_id.to_string(),
+            capture_count: titan.max_captures - remaining_captures,
+            remaining_captures,
+        };
+        state.broadcaster.broadcast_to_neighbors(&titan.geohash, update).await;
+    }
 
     tracing::info!(
         "Player {} captured Titan {} via signed transaction ({} captures remaining)",
@@ -404,22 +1018,251 @@ async fn submit_signed_transaction(
 
     Ok(Json(SubmitSignedTransactionResponse {
         success: true,
-        tx_signature: result.signature,
-        mint_address: request.titan_pda, // From request payload
+        attempt_id: attempt.id.to_string(),
+        tx_signature: attempt.tx_signature.unwrap_or_default(),
+        mint_address: attempt.mint_address.unwrap_or(request.titan_pda),
         remaining_captures,
         breach_reward,
         breach_tx_signature,
+        reward_modifiers: modifiers.applied,
+        capture_streak,
+        onchain_deferred,
     }))
 }
 
+// ═══════════════════════════════════════════════════════════════════════════════
+// Cooperative capture (Titans that take several players)
+// ═══════════════════════════════════════════════════════════════════════════════
+
+/// Join cooperative capture request
+#[derive(Debug, Deserialize)]
+pub struct JoinCooperativeRequest {
+    pub player_location: PlayerLocation,
+}
+
+/// Join the cooperative capture of a Titan, opening one when there is none
+///
+/// Nearby players are invited until it has all its players; it is cancelled
+/// if it isn't full and confirmed by everyone within
+/// `game.cooperative_capture_timeout_seconds`.
+async fn join_cooperative(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Path(titan_id): Path<Uuid>,
+    Json(request): Json<JoinCooperativeRequest>,
+) -> ApiResult<Json<CooperativeCapture>> {
+    let session = state
+        .services
+        .capture
+        .join_cooperative(
+            player.player_id,
+            &player.wallet_address,
+            CaptureRequest { titan_id, player_location: request.player_location },
+        )
+        .await?;
+
+    if !session.is_full() {
+        if let Some(titan) = state.services.map.get_titan(titan_id).await? {
+            state
+                .broadcaster
+                .broadcast_to_neighbors(&titan.geohash, WsMessage::cooperative_capture_invite(&session))
+                .await;
+        }
+    }
+
+    Ok(Json(session))
+}
+
+/// Confirm cooperative capture request
+#[derive(Debug, Deserialize)]
+pub struct ConfirmCooperativeRequest {
+    #[serde(default)]
+    pub skip_blockchain: bool, // For testing without blockchain
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConfirmCooperativeResponse {
+    /// Everyone confirmed and the Titan is captured; false while waiting on
+    /// the others
+    pub captured: bool,
+    pub session: CooperativeCapture,
+    /// The leader's capture attempt, once captured
+    pub attempt_id: Option<String>,
+    pub remaining_captures: Option<i32>,
+    /// NFT minted to the leader
+    pub mint_address: Option<String>,
+    pub tx_signature: Option<String>,
+    /// Each participant's equal share of the $BREACH reward
+    pub breach_reward_share: Option<u64>,
+    /// On-chain actions queued because a fee budget was exhausted
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub onchain_deferred: Vec<DeferredOnchain>,
+}
+
+/// Confirm a full cooperative capture
+///
+/// The confirm that completes it mints one NFT to the leader and splits the
+/// reward equally between the participants; the others get `captured: false`
+/// and see the capture through `titan_captured`.
+async fn confirm_cooperative(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Path(titan_id): Path<Uuid>,
+    Json(request): Json<ConfirmCooperativeRequest>,
+) -> ApiResult<Json<ConfirmCooperativeResponse>> {
+    let confirm = state
+        .services
+        .capture
+        .confirm_cooperative(player.player_id, titan_id)
+        .await?;
+
+    let session = match confirm {
+        CooperativeConfirm::Waiting(session) => {
+            return Ok(Json(ConfirmCooperativeResponse {
+                captured: false,
+                session,
+                attempt_id: None,
+                remaining_captures: None,
+                mint_address: None,
+                tx_signature: None,
+                breach_reward_share: None,
+                onchain_deferred: Vec::new(),
+            }));
+        }
+        CooperativeConfirm::Settle(session) => session,
+    };
+
+    // Settled or failed, the session is over
+    let settled = settle_cooperative(&state, session, request.skip_blockchain).await;
+    if let Err(e) = state.services.capture.end_cooperative(titan_id).await {
+        tracing::warn!("Failed to clear cooperative capture {}: {}", titan_id, e);
+    }
+    settled.map(Json)
+}
+
+/// Capture the Titan for the leader, then pay every participant their share
+async fn settle_cooperative(
+    state: &AppState,
+    session: CooperativeCapture,
+    skip_blockchain: bool,
+) -> ApiResult<ConfirmCooperativeResponse> {
+    let titan = state.services.map.get_titan(session.titan_id).await?
+        .ok_or(AppError::TitanNotFound)?;
+    let leader = session
+        .participants
+        .iter()
+        .find(|captor| captor.player_id == session.leader_id)
+        .ok_or_else(|| AppError::Internal(anyhow::anyhow!("Cooperative capture lost its leader")))?;
+
+    let mut attempt = state
+        .services
+        .capture
+        .begin_capture_attempt(leader.player_id, titan.id)
+        .await?;
+    if !attempt.is_confirmed() && titan.captured_by.is_some() && titan.capture_count >= titan.max_captures {
+        return Err(AppError::TitanAlreadyCaptured);
+    }
+
+    let mut onchain_deferred = Vec::new();
+    let solana = if skip_blockchain {
+        None
+    } else {
+        state.services.solana.as_ref()
+    };
+
+    // A shadow-banned leader's NFT is never minted; the others still share
+    // the capture
+    let leader_shadow_banned = state.services.player.is_shadow_banned(leader.player_id).await?;
+    if let Some(solana) = solana.filter(|_| attempt.needs_mint() && !leader_shadow_banned) {
+        attempt = mint_capture(
+            state,
+            solana,
+            leader.player_id,
+            &leader.wallet_address,
+            &titan,
+            attempt,
+            &mut onchain_deferred,
+        )
+        .await?;
+    }
+
+    let attempt = state
+        .services
+        .capture
+        .finalize_capture(attempt.id)
+        .await?;
+    state.metrics.captures_confirmed.inc();
+    let remaining_captures = attempt.remaining_captures.unwrap_or(0);
+
+    let reward_share = session.reward_share(calculate_breach_reward(titan.threat_class));
+    let mut breach_reward_share = None;
+    if let Some(solana) = solana {
+        for captor in &session.participants {
+            let paid = distribute_reward(
+                state,
+                solana,
+                captor.player_id,
+                &captor.wallet_address,
+                reward_share,
+                &mut onchain_deferred,
+            )
+            .await?;
+            if let (Some(result), true) = (paid, captor.player_id == leader.player_id) {
+                if let Err(e) = state
+                    .services
+                    .capture
+                    .record_capture_reward(attempt.id, result.amount, &result.signature)
+                    .await
+                {
+                    tracing::warn!("Failed to record capture reward: {}", e);
+                }
+            }
+        }
+        breach_reward_share = Some(reward_share);
+    }
+
+    let message = WsMessage::TitanCaptured {
+        titan_id: titan.id.to_string(),
+        captured_by: leader.wallet_address.clone(),
+        remaining_captures,
+    };
+    state
+        .broadcaster
+        .broadcast_to_neighbors(&titan.geohash, message)
+        .await;
+
+    tracing::info!(
+        "{} players captured Titan {} cooperatively for {}",
+        session.participants.len(),
+        titan.id,
+        leader.wallet_address
+    );
+
+    Ok(ConfirmCooperativeResponse {
+        captured: true,
+        attempt_id: Some(attempt.id.to_string()),
+        remaining_captures: Some(remaining_captures),
+        mint_address: attempt.mint_address.clone(),
+        tx_signature: attempt.tx_signature.clone(),
+        breach_reward_share,
+        onchain_deferred,
+        session,
+    })
+}
+
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         // Core endpoints
         .route("/capture/request", post(request_capture))
         // Testing endpoint (backend-paid)
         .route("/capture/confirm", post(confirm_capture))
+        .route("/capture/confirm-batch", post(confirm_capture_batch))
         // Production endpoints (frontend-signed)
         .route("/capture/build-transaction", post(build_mint_transaction))
         .route("/capture/submit-transaction", post(submit_signed_transaction))
+        // Cooperative capture
+        .route("/capture/cooperative/join/:titan_id", post(join_cooperative))
+        .route("/capture/cooperative/confirm/:titan_id", post(confirm_cooperative))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), limit_captures))
         .with_state(state)
 }
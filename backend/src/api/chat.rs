@@ -4,6 +4,7 @@ use std::sync::Arc;
 
 use axum::{
     extract::{Path, Query, State},
+    middleware::from_fn_with_state,
     routing::{delete, get, post, put},
     Json, Router,
 };
@@ -12,9 +13,10 @@ use uuid::Uuid;
 use crate::error::ApiResult;
 use crate::AppState;
 use crate::middleware::auth::AuthPlayer;
+use crate::middleware::rate_limit::limit_chat_messages;
 use crate::models::{
-    BlockUserRequest, ChatChannel, ChatReport, ChannelResponse, EditMessageRequest,
-    MessageResponse, MessagesQuery, MuteChannelRequest, ReportMessageRequest,
+    BlockUserRequest, ChatChannel, ChatReport, ChannelsPage, ChannelsQuery, EditMessageRequest,
+    MessageResponse, MessagesPage, MessagesQuery, MuteChannelRequest, ReportMessageRequest,
     SendMessageRequest, StartPrivateChatRequest,
 };
 
@@ -25,7 +27,10 @@ pub fn routes(state: Arc<AppState>) -> Router {
         .route("/chat/channels", get(get_channels))
         .route("/chat/channels/private", post(start_private_chat))
         .route("/chat/channels/:id/messages", get(get_messages))
-        .route("/chat/channels/:id/messages", post(send_message))
+        .route(
+            "/chat/channels/:id/messages",
+            post(send_message).route_layer(from_fn_with_state(state.clone(), limit_chat_messages)),
+        )
         .route("/chat/channels/:id/read", post(mark_as_read))
         .route("/chat/channels/:id/mute", post(mute_channel))
         .route("/chat/channels/:id/unmute", post(unmute_channel))
@@ -48,8 +53,9 @@ pub fn routes(state: Arc<AppState>) -> Router {
 async fn get_channels(
     State(state): State<Arc<AppState>>,
     AuthPlayer(player): AuthPlayer,
-) -> ApiResult<Json<Vec<ChannelResponse>>> {
-    let channels = state.services.chat.get_player_channels(player.player_id).await?;
+    Query(query): Query<ChannelsQuery>,
+) -> ApiResult<Json<ChannelsPage>> {
+    let channels = state.services.chat.get_player_channels(player.player_id, query).await?;
     Ok(Json(channels))
 }
 
@@ -64,6 +70,11 @@ async fn start_private_chat(
         .chat
         .get_or_create_private_channel(player.player_id, req.player_id)
         .await?;
+
+    // Live delivery for participants already connected
+    state.broadcaster.subscribe_chat_channel(player.player_id, channel.id).await;
+    state.broadcaster.subscribe_chat_channel(req.player_id, channel.id).await;
+
     Ok(Json(channel))
 }
 
@@ -73,7 +84,7 @@ async fn get_messages(
     AuthPlayer(player): AuthPlayer,
     Path(channel_id): Path<Uuid>,
     Query(query): Query<MessagesQuery>,
-) -> ApiResult<Json<Vec<MessageResponse>>> {
+) -> ApiResult<Json<MessagesPage>> {
     let messages = state
         .services
         .chat
@@ -0,0 +1,39 @@
+//! Program error catalog endpoint
+
+use axum::{routing::get, Json, Router};
+use breach_program_errors::CATALOG;
+use serde::Serialize;
+
+use crate::error::program_error_message_key;
+
+#[derive(Serialize)]
+struct ProgramErrorEntry {
+    program: &'static str,
+    code: u32,
+    name: &'static str,
+    description: &'static str,
+    /// Localization key, `program_error.<program>.<name>`
+    message_key: String,
+}
+
+/// Every custom error the Titan NFT and Game Logic programs can return
+async fn get_catalog() -> Json<Vec<ProgramErrorEntry>> {
+    let entries = CATALOG
+        .iter()
+        .flat_map(|errors| errors.iter())
+        .map(|info| ProgramErrorEntry {
+            program: info.program.as_str(),
+            code: info.code,
+            name: info.name,
+            description: info.description,
+            message_key: program_error_message_key(info),
+        })
+        .collect();
+
+    Json(entries)
+}
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/errors/catalog", get(get_catalog))
+}
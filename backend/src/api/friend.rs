@@ -4,7 +4,7 @@ use std::sync::Arc;
 
 use axum::{
     extract::{Path, State},
-    routing::{delete, get, post},
+    routing::{delete, get, patch, post},
     Json, Router,
 };
 use uuid::Uuid;
@@ -12,9 +12,10 @@ use uuid::Uuid;
 use crate::error::ApiResult;
 use crate::middleware::auth::AuthPlayer;
 use crate::models::{
-    FriendGift, FriendInfo, FriendRequest, FriendRequestWithSender, GiftWithSender,
-    SendFriendRequest,
+    FriendGift, FriendInfo, FriendLocation, FriendRequest, FriendRequestWithSender,
+    FriendSettings, GiftWithSender, SendFriendRequest, UpdateFriendSettings,
 };
+use crate::websocket::WsMessage;
 use crate::AppState;
 
 /// Get all friends
@@ -46,6 +47,20 @@ async fn send_request(
         .friend
         .send_request(player.player_id, req)
         .await?;
+
+    state
+        .broadcaster
+        .broadcast_to_player(
+            request.receiver_id,
+            WsMessage::FriendRequest {
+                request_id: request.id.to_string(),
+                sender_id: request.sender_id.to_string(),
+                message: request.message.clone(),
+                created_at: request.created_at.to_rfc3339(),
+            },
+        )
+        .await;
+
     Ok(Json(request))
 }
 
@@ -104,6 +119,34 @@ async fn remove_friend(
     Ok(Json("Removed"))
 }
 
+/// Update settings for one friend
+async fn update_settings(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Path(friend_id): Path<Uuid>,
+    Json(req): Json<UpdateFriendSettings>,
+) -> ApiResult<Json<FriendSettings>> {
+    let settings = state
+        .services
+        .friend
+        .update_settings(player.player_id, friend_id, req.share_location)
+        .await?;
+    Ok(Json(settings))
+}
+
+/// Get coarse locations of friends sharing with you
+async fn get_friend_locations(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+) -> ApiResult<Json<Vec<FriendLocation>>> {
+    let locations = state
+        .services
+        .friend
+        .get_friend_locations(player.player_id)
+        .await?;
+    Ok(Json(locations))
+}
+
 /// Send gift to friend
 async fn send_gift(
     State(state): State<Arc<AppState>>,
@@ -155,11 +198,13 @@ pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/friends", get(get_friends))
         .route("/friends/count", get(get_friend_count))
+        .route("/friends/locations", get(get_friend_locations))
         .route("/friends/request", post(send_request))
         .route("/friends/requests", get(get_pending_requests))
         .route("/friends/requests/:request_id/accept", post(accept_request))
         .route("/friends/requests/:request_id/reject", post(reject_request))
         .route("/friends/:friend_id", delete(remove_friend))
+        .route("/friends/:friend_id/settings", patch(update_settings))
         .route("/friends/:friend_id/gift", post(send_gift))
         .route("/friends/gifts", get(get_pending_gifts))
         .route("/friends/gifts/:gift_id/open", post(open_gift))
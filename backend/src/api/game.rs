@@ -1,6 +1,6 @@
 //! Game Logic on-chain operations API
 //!
-//! Provides capture records, battle records, and experience distribution operations.
+//! Provides capture records, battle records, experience distribution and reward claim operations.
 
 use std::sync::Arc;
 
@@ -36,6 +36,8 @@ pub struct RecordCaptureResponse {
     pub recent_blockhash: String,
     pub capture_id: u64,
     pub capture_record_pda: String,
+    /// Player capture nonce the transaction consumes
+    pub nonce: u64,
 }
 
 /// Build Record Capture transaction
@@ -62,6 +64,7 @@ async fn build_record_capture(
         recent_blockhash: result.recent_blockhash,
         capture_id: result.capture_id,
         capture_record_pda: result.capture_record_pda,
+        nonce: result.nonce,
     }))
 }
 
@@ -224,6 +227,11 @@ async fn distribute_reward(
     };
     let final_amount = request.amount * multiplier;
 
+    // Shadow-banned players get the same error as a failed transfer
+    if state.services.player.is_shadow_banned(player.player_id).await? {
+        return Err(AppError::Internal(anyhow::anyhow!("Transfer failed")));
+    }
+
     // Use transfer_breach_tokens directly (bypassing on-chain `distribute_reward`)
     let result = solana.transfer_breach_tokens(
         &player.wallet_address,
@@ -238,6 +246,45 @@ async fn distribute_reward(
     }))
 }
 
+// ═══════════════════════════════════════════════════════════════════════════════
+// Claim Rewards API
+// ═══════════════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Serialize)]
+pub struct ClaimRewardsResponse {
+    pub serialized_transaction: String,
+    pub message_to_sign: String,
+    pub recent_blockhash: String,
+    /// Pending balance the claim will pay out
+    pub amount: u64,
+}
+
+/// Build Claim Rewards transaction (player-signed, submit via `/titan/submit`)
+async fn build_claim_rewards(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+) -> ApiResult<Json<ClaimRewardsResponse>> {
+    let solana = state.services.solana.as_ref()
+        .ok_or(AppError::Internal(anyhow::anyhow!("Solana service not available")))?;
+
+    // Fail early instead of handing out a transaction that will revert
+    let amount = solana.get_reward_ledger(&player.wallet_address).await?
+        .map(|ledger| ledger.accrued_amount)
+        .unwrap_or(0);
+    if amount == 0 {
+        return Err(AppError::BadRequest("No pending rewards to claim".to_string()));
+    }
+
+    let result = solana.build_claim_rewards_transaction(&player.wallet_address).await?;
+
+    Ok(Json(ClaimRewardsResponse {
+        serialized_transaction: result.serialized_transaction,
+        message_to_sign: result.message_to_sign,
+        recent_blockhash: result.recent_blockhash,
+        amount,
+    }))
+}
+
 // ═══════════════════════════════════════════════════════════════════════════════
 // Submit dual-signed transaction API
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -291,5 +338,6 @@ pub fn routes(state: Arc<AppState>) -> Router {
         .route("/game/submit", post(submit_dual_signed))
         // Reward distribution endpoint
         .route("/game/reward/distribute", post(distribute_reward))
+        .route("/game/reward/claim/build", post(build_claim_rewards))
         .with_state(state)
 }
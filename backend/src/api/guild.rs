@@ -7,14 +7,15 @@ use axum::{
     routing::{delete, get, post, put},
     Json, Router,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::error::ApiResult;
 use crate::middleware::auth::AuthPlayer;
 use crate::models::{
     CreateGuildRequest, Guild, GuildMember, GuildMemberInfo, GuildRequestWithPlayer, GuildRole,
-    GuildSummary, UpdateGuildRequest,
+    GuildSummary, GuildTreasury, GuildVacation, GuildWar, GuildWarChallengeRequest, GuildWarDetails,
+    StartVacationRequest, TreasuryDepositRequest, TreasuryWithdrawRequest, UpdateGuildRequest,
 };
 use crate::AppState;
 
@@ -32,6 +33,15 @@ fn default_limit() -> i64 {
     20
 }
 
+/// War history query
+#[derive(Debug, Deserialize)]
+pub struct WarHistoryQuery {
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+}
+
 /// Create a new guild
 async fn create_guild(
     State(state): State<Arc<AppState>>,
@@ -130,7 +140,7 @@ async fn request_join(
 /// Get pending join requests
 async fn get_pending_requests(
     State(state): State<Arc<AppState>>,
-    AuthPlayer(player): AuthPlayer,
+    AuthPlayer(_player): AuthPlayer,
     Path(guild_id): Path<Uuid>,
 ) -> ApiResult<Json<Vec<GuildRequestWithPlayer>>> {
     // Verify membership is checked in service
@@ -210,16 +220,143 @@ async fn change_role(
     Ok(Json("Role changed"))
 }
 
+/// Start a guild vacation
+async fn start_vacation(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Json(req): Json<StartVacationRequest>,
+) -> ApiResult<Json<GuildVacation>> {
+    let vacation = state
+        .services
+        .guild
+        .start_vacation(player.player_id, req.weeks)
+        .await?;
+    Ok(Json(vacation))
+}
+
+/// End a guild vacation early
+async fn end_vacation(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+) -> ApiResult<Json<&'static str>> {
+    state.services.guild.end_vacation(player.player_id).await?;
+    Ok(Json("Vacation ended"))
+}
+
+/// Get the guild treasury balance and ledger
+async fn get_treasury(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Path(guild_id): Path<Uuid>,
+) -> ApiResult<Json<GuildTreasury>> {
+    let treasury = state
+        .services
+        .guild
+        .get_treasury(guild_id, player.player_id)
+        .await?;
+    Ok(Json(treasury))
+}
+
+/// Treasury balance after a deposit or withdrawal
+#[derive(Debug, Serialize)]
+pub struct TreasuryBalanceResponse {
+    pub balance: i64,
+}
+
+/// Deposit into the guild treasury
+async fn deposit_to_treasury(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Json(req): Json<TreasuryDepositRequest>,
+) -> ApiResult<Json<TreasuryBalanceResponse>> {
+    let balance = state
+        .services
+        .guild
+        .deposit_to_treasury(player.player_id, req.amount)
+        .await?;
+    Ok(Json(TreasuryBalanceResponse { balance }))
+}
+
+/// Withdraw from the guild treasury
+async fn withdraw_from_treasury(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Json(req): Json<TreasuryWithdrawRequest>,
+) -> ApiResult<Json<TreasuryBalanceResponse>> {
+    let balance = state
+        .services
+        .guild
+        .withdraw_from_treasury(player.player_id, req.amount, &req.reason)
+        .await?;
+    Ok(Json(TreasuryBalanceResponse { balance }))
+}
+
+/// Challenge a guild to a war
+async fn challenge_war(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Path(guild_id): Path<Uuid>,
+    Json(req): Json<GuildWarChallengeRequest>,
+) -> ApiResult<Json<GuildWar>> {
+    let war = state
+        .services
+        .guild
+        .challenge_war(player.player_id, guild_id, req.duration_hours)
+        .await?;
+    Ok(Json(war))
+}
+
+/// Accept a war challenge
+async fn accept_war(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Path(war_id): Path<Uuid>,
+) -> ApiResult<Json<GuildWar>> {
+    let war = state.services.guild.accept_war(player.player_id, war_id).await?;
+    Ok(Json(war))
+}
+
+/// Get a guild's active war and its member scores
+async fn get_active_war(
+    State(state): State<Arc<AppState>>,
+    Path(guild_id): Path<Uuid>,
+) -> ApiResult<Json<Option<GuildWarDetails>>> {
+    let war = state.services.guild.get_active_war(guild_id).await?;
+    Ok(Json(war))
+}
+
+/// Get a guild's finished wars
+async fn get_war_history(
+    State(state): State<Arc<AppState>>,
+    Path(guild_id): Path<Uuid>,
+    Query(query): Query<WarHistoryQuery>,
+) -> ApiResult<Json<Vec<GuildWar>>> {
+    let wars = state
+        .services
+        .guild
+        .get_war_history(guild_id, query.limit.clamp(1, 50), query.offset.max(0))
+        .await?;
+    Ok(Json(wars))
+}
+
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/guild", post(create_guild))
         .route("/guild/me", get(get_my_guild))
         .route("/guild/leave", post(leave_guild))
+        .route("/guild/vacation", post(start_vacation).delete(end_vacation))
+        .route("/guild/treasury/deposit", post(deposit_to_treasury))
+        .route("/guild/treasury/withdraw", post(withdraw_from_treasury))
         .route("/guilds", get(search_guilds))
         .route("/guilds/:guild_id", get(get_guild).put(update_guild))
         .route("/guilds/:guild_id/members", get(get_members))
         .route("/guilds/:guild_id/join", post(request_join))
         .route("/guilds/:guild_id/requests", get(get_pending_requests))
+        .route("/guilds/:guild_id/treasury", get(get_treasury))
+        .route("/guilds/:guild_id/war", get(get_active_war))
+        .route("/guilds/:guild_id/war/challenge", post(challenge_war))
+        .route("/guilds/:guild_id/wars", get(get_war_history))
+        .route("/guild/wars/:war_id/accept", post(accept_war))
         .route("/guild/requests/:request_id/accept", post(accept_request))
         .route("/guild/requests/:request_id/reject", post(reject_request))
         .route("/guild/members/:member_id/kick", delete(kick_member))
@@ -1,12 +1,18 @@
 //! Health check endpoints
 
+use std::fmt::Display;
+use std::future::Future;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use axum::{extract::State, routing::get, Json, Router};
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
 use serde::Serialize;
 
 use crate::AppState;
 
+/// How long each dependency has to answer the deep health check
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
 #[derive(Serialize)]
 struct HealthResponse {
     status: &'static str,
@@ -63,6 +69,110 @@ async fn health_detailed(
     })
 }
 
+#[derive(Debug, Serialize)]
+struct DeepHealthResponse {
+    status: &'static str,
+    version: &'static str,
+    dependencies: DependencyChecks,
+}
+
+#[derive(Debug, Serialize)]
+struct DependencyChecks {
+    postgres: DependencyHealth,
+    redis: DependencyHealth,
+    solana: DependencyHealth,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum DependencyStatus {
+    Up,
+    Down,
+    /// Not configured on this instance, so not checked
+    Disabled,
+}
+
+#[derive(Debug, Serialize)]
+struct DependencyHealth {
+    status: DependencyStatus,
+    latency_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl DependencyHealth {
+    fn disabled() -> Self {
+        Self {
+            status: DependencyStatus::Disabled,
+            latency_ms: None,
+            error: None,
+        }
+    }
+}
+
+/// Time one dependency check, failing it after `PROBE_TIMEOUT`
+async fn probe<E: Display>(check: impl Future<Output = Result<(), E>>) -> DependencyHealth {
+    let started = Instant::now();
+    let result = tokio::time::timeout(PROBE_TIMEOUT, check).await;
+    let latency_ms = Some(started.elapsed().as_secs_f64() * 1000.0);
+
+    let error = match result {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some(format!("No answer within {}s", PROBE_TIMEOUT.as_secs())),
+    };
+    DependencyHealth {
+        status: if error.is_none() { DependencyStatus::Up } else { DependencyStatus::Down },
+        latency_ms,
+        error,
+    }
+}
+
+impl DeepHealthResponse {
+    fn new(dependencies: DependencyChecks) -> Self {
+        let DependencyChecks { postgres, redis, solana } = &dependencies;
+        let down = [postgres, redis, solana]
+            .iter()
+            .any(|dependency| dependency.status == DependencyStatus::Down);
+
+        Self {
+            status: if down { "degraded" } else { "ok" },
+            version: env!("CARGO_PKG_VERSION"),
+            dependencies,
+        }
+    }
+
+    /// 200 only when every configured dependency answered
+    fn status_code(&self) -> StatusCode {
+        if self.status == "ok" {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+    }
+}
+
+/// Check Postgres, Redis and Solana RPC at once, so a load balancer can
+/// tell a running process from one whose dependencies answer
+async fn health_deep(
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<DeepHealthResponse>) {
+    let mut redis = state.db.redis.clone();
+    let (postgres, redis, solana) = tokio::join!(
+        probe(async { sqlx::query("SELECT 1").execute(&state.db.pg).await.map(|_| ()) }),
+        probe(async move { redis::cmd("PING").query_async::<_, String>(&mut redis).await.map(|_| ()) }),
+        async {
+            match &state.services.solana {
+                Some(solana) => probe(solana.check_health()).await,
+                None => DependencyHealth::disabled(),
+            }
+        },
+    );
+
+    let response = DeepHealthResponse::new(DependencyChecks { postgres, redis, solana });
+    (response.status_code(), Json(response))
+}
+
 /// Liveness probe for Kubernetes
 async fn liveness() -> &'static str {
     "OK"
@@ -93,7 +203,52 @@ pub fn routes() -> Router {
 pub fn routes_with_state(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/health/detailed", get(health_detailed))
+        .route("/health/deep", get(health_deep))
         .route("/health/live", get(liveness))
         .route("/health/ready", get(readiness))
         .with_state(state)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn up() -> DependencyHealth {
+        probe(async { Ok::<_, String>(()) }).await
+    }
+
+    #[tokio::test]
+    async fn test_down_redis_fails_deep_health() {
+        // Nothing listens on port 1
+        let client = redis::Client::open("redis://127.0.0.1:1/").unwrap();
+        let redis = probe(async { client.get_multiplexed_tokio_connection().await.map(|_| ()) }).await;
+        assert_eq!(redis.status, DependencyStatus::Down);
+        assert!(redis.error.is_some());
+
+        let response = DeepHealthResponse::new(DependencyChecks {
+            postgres: up().await,
+            redis,
+            solana: DependencyHealth::disabled(),
+        });
+        assert_eq!(response.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = serde_json::to_value(&response).unwrap();
+        assert_eq!(body["status"], "degraded");
+        assert_eq!(body["dependencies"]["redis"]["status"], "down");
+        assert_eq!(body["dependencies"]["postgres"]["status"], "up");
+
+        // A dependency that isn't configured doesn't count against it
+        let response = DeepHealthResponse::new(DependencyChecks {
+            postgres: up().await,
+            redis: up().await,
+            solana: DependencyHealth::disabled(),
+        });
+        assert_eq!(response.status_code(), StatusCode::OK);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_probe_gives_up_on_a_hung_dependency() {
+        let hung = probe(std::future::pending::<Result<(), String>>()).await;
+        assert_eq!(hung.status, DependencyStatus::Down);
+        assert_eq!(hung.error.as_deref(), Some("No answer within 2s"));
+    }
+}
@@ -3,14 +3,17 @@
 use std::sync::Arc;
 
 use axum::{
-    extract::{Query, State},
+    extract::{Path, Query, State},
     routing::get,
     Json, Router,
 };
 
-use crate::error::ApiResult;
+use crate::error::{ApiResult, AppError};
 use crate::middleware::auth::AuthPlayer;
-use crate::models::{LeaderboardQuery, LeaderboardResponse, LeaderboardResponseEntry, LeaderboardType};
+use crate::models::{
+    parse_week_label, LeaderboardBoard, LeaderboardHistoryQuery, LeaderboardHistoryResponse, LeaderboardQuery,
+    LeaderboardResponse, LeaderboardResponseEntry, LeaderboardType,
+};
 use crate::AppState;
 
 /// Get leaderboard by type
@@ -100,10 +103,59 @@ async fn get_top_by_stat(
     Ok(Json(entries))
 }
 
+/// A board as it stood at the end of a past week
+async fn get_history(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Path(board): Path<LeaderboardBoard>,
+    Query(query): Query<LeaderboardHistoryQuery>,
+) -> ApiResult<Json<LeaderboardHistoryResponse>> {
+    let week = match query.week.as_deref() {
+        Some(week) => Some(
+            parse_week_label(week).ok_or_else(|| AppError::BadRequest("week must be an ISO week, YYYY-WW".into()))?,
+        ),
+        None => None,
+    };
+
+    let response = state
+        .services
+        .leaderboard
+        .get_history(board, week.as_deref(), player.player_id)
+        .await?;
+
+    Ok(Json(response))
+}
+
+/// My place on a board last week
+#[derive(Debug, serde::Serialize)]
+pub struct LastWeekRankResponse {
+    pub board: LeaderboardBoard,
+    pub rank: Option<i32>,
+    pub score: Option<i64>,
+}
+
+/// Get my rank on a board in last week's snapshot
+async fn get_my_rank_last_week(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Path(board): Path<LeaderboardBoard>,
+) -> ApiResult<Json<LastWeekRankResponse>> {
+    let (rank, score) = state
+        .services
+        .leaderboard
+        .my_rank_last_week(player.player_id, board)
+        .await?
+        .unzip();
+
+    Ok(Json(LastWeekRankResponse { board, rank, score }))
+}
+
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/leaderboard", get(get_leaderboard))
         .route("/leaderboard/me", get(get_my_ranks))
         .route("/leaderboard/top", get(get_top_by_stat))
+        .route("/leaderboard/:board/history", get(get_history))
+        .route("/leaderboard/:board/history/me", get(get_my_rank_last_week))
         .with_state(state)
 }
@@ -4,9 +4,13 @@ use std::sync::Arc;
 
 use axum::{
     extract::{Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
+use breach_sdk::map::CONTENT_TYPE as COMPACT_MAP_CONTENT_TYPE;
+use chrono::Utc;
 use serde::Deserialize;
 
 use crate::error::ApiResult;
@@ -21,6 +25,27 @@ pub struct NearbyQuery {
     pub lng: f64,
     #[serde(default = "default_radius")]
     pub radius: f64,
+    /// `compact` for the binary snapshot format (same as the Accept header)
+    pub format: Option<String>,
+}
+
+impl NearbyQuery {
+    /// Whether the client asked for `application/x-breach-map-v1`
+    fn wants_compact(&self, headers: &HeaderMap) -> bool {
+        if self.format.as_deref() == Some("compact") {
+            return true;
+        }
+
+        headers
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .map(|accept| {
+                accept
+                    .split(',')
+                    .any(|t| t.split(';').next().unwrap_or("").trim() == COMPACT_MAP_CONTENT_TYPE)
+            })
+            .unwrap_or(false)
+    }
 }
 
 fn default_radius() -> f64 {
@@ -31,7 +56,8 @@ fn default_radius() -> f64 {
 async fn get_nearby_titans(
     State(state): State<Arc<AppState>>,
     Query(query): Query<NearbyQuery>,
-) -> ApiResult<Json<Vec<TitanSpawnResponse>>> {
+    headers: HeaderMap,
+) -> ApiResult<Response> {
     // Cap radius at 50km for performance
     let radius = query.radius.min(50_000.0);
 
@@ -41,7 +67,19 @@ async fn get_nearby_titans(
         .get_nearby_titans(query.lat, query.lng, radius)
         .await?;
 
-    Ok(Json(titans))
+    if query.wants_compact(&headers) {
+        let body = TitanSpawnResponse::encode_compact(&titans, Utc::now());
+        return Ok((
+            [
+                (header::CONTENT_TYPE, COMPACT_MAP_CONTENT_TYPE),
+                (header::VARY, "accept"),
+            ],
+            body,
+        )
+            .into_response());
+    }
+
+    Ok(([(header::VARY, "accept")], Json(titans)).into_response())
 }
 
 /// Query params for POIs
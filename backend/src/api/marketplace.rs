@@ -4,39 +4,54 @@ use std::sync::Arc;
 
 use axum::{
     extract::{Path, Query, State},
+    middleware::from_fn_with_state,
     routing::{delete, get, post},
     Json, Router,
 };
 use uuid::Uuid;
-use sqlx;
-use anyhow;
 
 use crate::error::{ApiResult, AppError};
 use crate::AppState;
 use crate::middleware::auth::AuthPlayer;
+use crate::middleware::rate_limit::limit_offers;
+use crate::websocket::WsMessage;
 use crate::models::{
-    AuctionBid, BidResponse, CreateListingRequest, ListingResponse, ListingStatus, ListingType,
-    MakeOfferRequest, MarketplaceListing, MarketplaceSearchQuery, MarketplaceStatsResponse,
-    MarketplaceTransaction, OfferResponse, PlaceBidRequest, PriceChartResponse, PriceOffer,
-    SearchResultsResponse, TransactionHistoryEntry,
+    BidResponse, CompletePurchaseRequest, CreateListingRequest, EscrowListingRequest, FloorPriceEntry,
+    ListingResponse, ListingType, MakeOfferRequest, MarketplaceActivityPage, MarketplaceActivityQuery, MarketplaceListing, MarketplaceSearchQuery, MarketplaceStatsResponse,
+    MarketplaceTransaction, OfferResponse, PlaceBidRequest, PlaceBidResponse, PriceChartResponse, PriceOffer,
+    SaleOutcome, SearchResultsResponse, SubmitEscrowTransactionRequest, SuggestPriceRequest, SuggestedPriceResponse,
+    TrackedTransaction, TrackedTransactionKind, TransactionHistoryEntry,
 };
 
+/// Matches the Game Logic `Listing::MAX_DURATION_SECONDS` (30 days)
+const MAX_ESCROW_DURATION_HOURS: i64 = 30 * 24;
+
 /// Build marketplace routes
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         // Listings
         .route("/marketplace", get(search_listings))
         .route("/marketplace/listings", post(create_listing))
+        .route("/marketplace/listings/suggest-price", post(suggest_price))
         .route("/marketplace/listings/:id", get(get_listing))
         .route("/marketplace/listings/:id", delete(cancel_listing))
         .route("/marketplace/listings/:id/buy", post(buy_listing))
+        .route("/marketplace/listings/:id/buy/build", post(build_buy_payment))
         .route("/marketplace/listings/:id/purchase/build", post(build_purchase_transaction))
         .route("/marketplace/listings/:id/purchase/complete", post(complete_purchase))
+        // On-chain escrow
+        .route("/marketplace/escrow/list/build", post(build_escrow_listing))
+        .route("/marketplace/escrow/list/submit", post(submit_escrow_listing))
+        .route("/marketplace/listings/:id/cancel/build", post(build_cancel_escrow_listing))
+        .route("/marketplace/listings/:id/cancel/submit", post(submit_cancel_escrow_listing))
         // Auctions
         .route("/marketplace/listings/:id/bids", get(get_bids))
         .route("/marketplace/listings/:id/bids", post(place_bid))
         // Offers
-        .route("/marketplace/offers", post(make_offer))
+        .route(
+            "/marketplace/offers",
+            post(make_offer).route_layer(from_fn_with_state(state.clone(), limit_offers)),
+        )
         .route("/marketplace/offers/received", get(get_received_offers))
         .route("/marketplace/offers/sent", get(get_sent_offers))
         .route("/marketplace/offers/:id/accept", post(accept_offer))
@@ -50,7 +65,9 @@ pub fn routes(state: Arc<AppState>) -> Router {
         // Stats & History
         .route("/marketplace/stats", get(get_stats))
         .route("/marketplace/history", get(get_transaction_history))
+        .route("/marketplace/activity", get(get_activity))
         .route("/marketplace/price-chart", get(get_price_chart))
+        .route("/marketplace/floor-prices", get(get_floor_prices))
         .with_state(state)
 }
 
@@ -100,115 +117,225 @@ async fn cancel_listing(
     Ok(Json(serde_json::json!({"success": true})))
 }
 
-/// Buy a fixed-price listing (legacy - database only)
+/// Build the $BREACH payment for a database fixed-price listing
+async fn build_buy_payment(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Path(id): Path<Uuid>,
+) -> ApiResult<Json<serde_json::Value>> {
+    let (listing, payment) = state.services.marketplace.build_purchase_payment(player.player_id, id).await?;
+
+    Ok(Json(serde_json::json!({
+        "listing_id": listing.id,
+        "titan_id": listing.titan_id,
+        "price": listing.price,
+        "serialized_transaction": payment.serialized_transaction,
+        "message_to_sign": payment.message_to_sign,
+        "recent_blockhash": payment.recent_blockhash,
+    })))
+}
+
+/// Buy a database fixed-price listing, paying with the signed transaction
+/// from `/buy/build` (no body when on-chain settlement is disabled)
 async fn buy_listing(
     State(state): State<Arc<AppState>>,
     AuthPlayer(player): AuthPlayer,
     Path(id): Path<Uuid>,
+    payment: Option<Json<CompletePurchaseRequest>>,
 ) -> ApiResult<Json<MarketplaceTransaction>> {
-    let tx = state.services.marketplace.buy_listing(player.player_id, id).await?;
+    let payment = payment.map(|Json(p)| p);
+    let tx = state.services.marketplace.buy_listing(player.player_id, id, payment.as_ref()).await?;
     Ok(Json(tx))
 }
 
-/// Build on-chain purchase transaction
+/// Build on-chain purchase transaction for an escrow listing
 async fn build_purchase_transaction(
     State(state): State<Arc<AppState>>,
     AuthPlayer(player): AuthPlayer,
     Path(listing_id): Path<Uuid>,
 ) -> ApiResult<Json<serde_json::Value>> {
-    use crate::error::AppError;
-    
-    // Get listing details
-    let listing = state.services.marketplace.get_listing(listing_id, Some(player.player_id)).await?;
-    
-    // Validate listing
-    if listing.status != ListingStatus::Active {
-        return Err(AppError::BadRequest("Listing is not active".into()));
-    }
-    
+    let listing = state.services.marketplace.get_escrow_listing(listing_id).await?;
+
     if listing.listing_type == ListingType::Auction {
         return Err(AppError::BadRequest("Cannot directly buy auction listings".into()));
     }
-    
+
     if listing.seller_id == player.player_id {
         return Err(AppError::BadRequest("Cannot buy your own listing".into()));
     }
-    
-    // Get seller wallet address
-    let seller_wallet = sqlx::query_scalar::<_, String>(
-        "SELECT wallet_address FROM players WHERE id = $1"
-    )
-    .bind(listing.seller_id)
-    .fetch_optional(&state.db.pg)
-    .await?
-    .ok_or(AppError::NotFound("Seller wallet not found".into()))?;
-    
-    // Get Titan on-chain ID
-    let titan_onchain_id = sqlx::query_scalar::<_, Option<i64>>(
-        "SELECT onchain_id FROM player_titans WHERE id = $1"
-    )
-    .bind(listing.titan_id)
-    .fetch_optional(&state.db.pg)
-    .await?
-    .flatten();
-    
-    let titan_onchain_id = titan_onchain_id
-        .ok_or(AppError::BadRequest("Titan not minted on-chain yet".into()))? as u64;
-    
-    // Calculate fees
-    const PLATFORM_FEE_BPS: i64 = 250; // 2.5%
-    let fee = (listing.price * PLATFORM_FEE_BPS) / 10000;
-    let total = listing.price;
-    
-    // Build transfer transaction using Solana service
+
+    let titan_onchain_id = state.services.marketplace.titan_onchain_id(listing.titan_id).await?;
+
     let solana = state.services.solana.as_ref()
-        .ok_or(AppError::Internal(anyhow::anyhow!("Solana service not available")))?;
-    
-    let tx_result = solana.build_transfer_transaction(
-        &seller_wallet,
+        .ok_or_else(|| AppError::ServiceUnavailable("Solana service not available".into()))?;
+
+    let tx_result = solana.build_purchase_listing_transaction(
         &player.wallet_address,
         titan_onchain_id,
     ).await?;
-    
+
     Ok(Json(serde_json::json!({
         "listing_id": listing_id,
         "titan_id": listing.titan_id,
         "titan_onchain_id": titan_onchain_id,
-        "seller_wallet": seller_wallet,
         "buyer_wallet": player.wallet_address,
         "price": listing.price,
-        "fee": fee,
-        "total": total,
+        "escrow_address": tx_result.listing_pda,
         "serialized_transaction": tx_result.serialized_transaction,
         "message_to_sign": tx_result.message_to_sign,
         "recent_blockhash": tx_result.recent_blockhash,
     })))
 }
 
-/// Complete on-chain purchase with signature
-/// 
-/// **LIMITATION**: Current implementation requires seller signature for transfer.
-/// In production, this would need:
-/// 1. Escrow contract to hold NFT when listing is created
-/// 2. Or program-level marketplace with approval mechanism
-/// 3. Or off-chain coordination for seller to sign
-/// 
-/// For now, this endpoint returns an error guiding users to alternative flows.
+/// Submit a signed purchase transaction and record the sale
 async fn complete_purchase(
-    State(_state): State<Arc<AppState>>,
-    AuthPlayer(_player): AuthPlayer,
-    Path(_listing_id): Path<Uuid>,
-    Json(_req): Json<serde_json::Value>,
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Path(listing_id): Path<Uuid>,
+    Json(req): Json<CompletePurchaseRequest>,
+) -> ApiResult<Json<serde_json::Value>> {
+    let listing = state.services.marketplace.get_escrow_listing(listing_id).await?;
+
+    let solana = state.services.solana.as_ref()
+        .ok_or_else(|| AppError::ServiceUnavailable("Solana service not available".into()))?;
+
+    let transaction = solana.sign_user_transaction(
+        &req.serialized_transaction,
+        &req.user_signature,
+        &player.wallet_address,
+    )?;
+    let tracked = TrackedTransaction::listing(TrackedTransactionKind::ListingPurchase, player.player_id, listing_id);
+    let signature = state.services.transaction.track(tracked, &transaction).await?;
+    let result = solana.send_signed(&transaction).await;
+    state.services.transaction.record_send(&signature, &result).await;
+    let result = result?;
+
+    let sold = state.services.marketplace.settle_escrow_listing(
+        &listing,
+        Some(&player.wallet_address),
+        Some(&result.signature),
+    ).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": sold,
+        "tx_signature": result.signature,
+    })))
+}
+
+/// Build on-chain escrow listing transaction
+async fn build_escrow_listing(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Json(req): Json<EscrowListingRequest>,
+) -> ApiResult<Json<serde_json::Value>> {
+    if req.price <= 0 {
+        return Err(AppError::BadRequest("Price must be positive".into()));
+    }
+    if req.duration_hours <= 0 || req.duration_hours > MAX_ESCROW_DURATION_HOURS {
+        return Err(AppError::BadRequest(format!(
+            "Duration must be between 1 and {} hours", MAX_ESCROW_DURATION_HOURS
+        )));
+    }
+
+    let titan_onchain_id = state.services.marketplace.titan_onchain_id(req.titan_id).await?;
+
+    let solana = state.services.solana.as_ref()
+        .ok_or_else(|| AppError::ServiceUnavailable("Solana service not available".into()))?;
+
+    // Ownership is checked against the chain, the program enforces it again
+    let owner = solana.get_titan_owner(titan_onchain_id).await?;
+    if owner.map(|o| o.to_string()).as_deref() != Some(player.wallet_address.as_str()) {
+        return Err(AppError::Forbidden("Titan not owned by you".into()));
+    }
+
+    let tx_result = solana.build_list_titan_transaction(
+        &player.wallet_address,
+        titan_onchain_id,
+        req.price as u64,
+        req.duration_hours * 3600,
+    ).await?;
+
+    Ok(Json(serde_json::to_value(tx_result).map_err(|e| AppError::Internal(e.into()))?))
+}
+
+/// Submit a signed escrow listing transaction and mirror the listing
+async fn submit_escrow_listing(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Json(req): Json<SubmitEscrowTransactionRequest>,
+) -> ApiResult<Json<MarketplaceListing>> {
+    let titan_onchain_id = state.services.marketplace.titan_onchain_id(req.titan_id).await?;
+
+    let solana = state.services.solana.as_ref()
+        .ok_or_else(|| AppError::ServiceUnavailable("Solana service not available".into()))?;
+
+    let result = solana.submit_user_signed_transaction(
+        &req.serialized_transaction,
+        &req.user_signature,
+        &player.wallet_address,
+    ).await?;
+
+    let onchain = solana.get_onchain_listing(titan_onchain_id).await?
+        .ok_or_else(|| AppError::NotFound("On-chain listing not found".into()))?;
+
+    let listing = state.services.marketplace
+        .sync_escrow_listing(&onchain, Some(&result.signature))
+        .await?
+        .ok_or_else(|| AppError::NotFound("Seller or Titan not registered".into()))?;
+
+    Ok(Json(listing))
+}
+
+/// Build on-chain cancel transaction for an escrow listing
+async fn build_cancel_escrow_listing(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Path(listing_id): Path<Uuid>,
+) -> ApiResult<Json<serde_json::Value>> {
+    let listing = state.services.marketplace.get_escrow_listing(listing_id).await?;
+    let titan_onchain_id = state.services.marketplace.titan_onchain_id(listing.titan_id).await?;
+
+    let solana = state.services.solana.as_ref()
+        .ok_or_else(|| AppError::ServiceUnavailable("Solana service not available".into()))?;
+
+    let tx_result = solana.build_cancel_listing_transaction(
+        &player.wallet_address,
+        titan_onchain_id,
+    ).await?;
+
+    Ok(Json(serde_json::to_value(tx_result).map_err(|e| AppError::Internal(e.into()))?))
+}
+
+/// Submit a signed cancel transaction and close the listing
+async fn submit_cancel_escrow_listing(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Path(listing_id): Path<Uuid>,
+    Json(req): Json<CompletePurchaseRequest>,
 ) -> ApiResult<Json<serde_json::Value>> {
-    Err(AppError::BadRequest(
-        "On-chain marketplace purchases require escrow contract (not yet implemented). \
-        Please use off-chain transfer coordination or contact the seller directly.".into()
-    ))
-    
-    // TODO: Implement proper escrow flow:
-    // 1. Seller creates listing → NFT transferred to escrow PDA
-    // 2. Buyer pays → payment held in escrow
-    // 3. On completion → escrow releases NFT to buyer, payment to seller
+    let listing = state.services.marketplace.get_escrow_listing(listing_id).await?;
+
+    let solana = state.services.solana.as_ref()
+        .ok_or_else(|| AppError::ServiceUnavailable("Solana service not available".into()))?;
+
+    let transaction = solana.sign_user_transaction(
+        &req.serialized_transaction,
+        &req.user_signature,
+        &player.wallet_address,
+    )?;
+    let tracked = TrackedTransaction::listing(TrackedTransactionKind::ListingCancel, player.player_id, listing_id);
+    let signature = state.services.transaction.track(tracked, &transaction).await?;
+    let result = solana.send_signed(&transaction).await;
+    state.services.transaction.record_send(&signature, &result).await;
+    let result = result?;
+
+    // Titan is back with the seller, so this settles as cancelled
+    state.services.marketplace.settle_escrow_listing(&listing, None, None).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "tx_signature": result.signature,
+    })))
 }
 
 // ============================================
@@ -230,8 +357,44 @@ async fn place_bid(
     AuthPlayer(player): AuthPlayer,
     Path(id): Path<Uuid>,
     Json(req): Json<PlaceBidRequest>,
-) -> ApiResult<Json<AuctionBid>> {
-    let bid = state.services.marketplace.place_bid(player.player_id, id, req.amount).await?;
+) -> ApiResult<Json<PlaceBidResponse>> {
+    let bid = state.services.marketplace.place_bid(player.player_id, id, req).await?;
+
+    if let Some(previous) = bid.outbid_player_id {
+        let message = match bid.exceeded_proxy_max {
+            Some(max_bid) => WsMessage::ProxyBidExceeded {
+                listing_id: id.to_string(),
+                max_bid,
+                current_bid: bid.current_bid,
+                expires_at: bid.expires_at.to_rfc3339(),
+            },
+            None => WsMessage::Outbid {
+                listing_id: id.to_string(),
+                title: "You've been outbid".to_string(),
+                current_bid: bid.current_bid,
+                expires_at: bid.expires_at.to_rfc3339(),
+            },
+        };
+        state.broadcaster.broadcast_to_player(previous, message).await;
+    }
+
+    // Let everyone following the auction update their countdown
+    if bid.extended {
+        let message = WsMessage::ListingExtended {
+            listing_id: id.to_string(),
+            expires_at: bid.expires_at.to_rfc3339(),
+            current_bid: bid.current_bid,
+        };
+        match state.services.marketplace.auction_watchers(id).await {
+            Ok(watchers) => {
+                for watcher in watchers {
+                    state.broadcaster.broadcast_to_player(watcher, message.clone()).await;
+                }
+            }
+            Err(e) => tracing::warn!("Failed to load watchers of auction {}: {:?}", id, e),
+        }
+    }
+
     Ok(Json(bid))
 }
 
@@ -272,9 +435,9 @@ async fn accept_offer(
     State(state): State<Arc<AppState>>,
     AuthPlayer(player): AuthPlayer,
     Path(id): Path<Uuid>,
-) -> ApiResult<Json<MarketplaceTransaction>> {
-    let tx = state.services.marketplace.accept_offer(player.player_id, id).await?;
-    Ok(Json(tx))
+) -> ApiResult<Json<SaleOutcome>> {
+    let sale = state.services.marketplace.accept_offer(player.player_id, id).await?;
+    Ok(Json(sale))
 }
 
 /// Reject an offer
@@ -345,6 +508,28 @@ async fn get_stats(
     Ok(Json(stats))
 }
 
+/// Get floor prices per element and threat class
+async fn get_floor_prices(
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<Json<Vec<FloorPriceEntry>>> {
+    let floor_prices = state.services.marketplace.get_floor_prices().await?;
+    Ok(Json(floor_prices))
+}
+
+/// Suggest a listing price for one of the player's Titans
+async fn suggest_price(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Json(req): Json<SuggestPriceRequest>,
+) -> ApiResult<Json<SuggestedPriceResponse>> {
+    let suggestion = state
+        .services
+        .marketplace
+        .suggest_price(player.player_id, req.titan_id)
+        .await?;
+    Ok(Json(suggestion))
+}
+
 /// Get transaction history
 async fn get_transaction_history(
     State(state): State<Arc<AppState>>,
@@ -354,6 +539,15 @@ async fn get_transaction_history(
     Ok(Json(history))
 }
 
+/// Recent listings, bids, sales and cancellations across the marketplace
+async fn get_activity(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<MarketplaceActivityQuery>,
+) -> ApiResult<Json<MarketplaceActivityPage>> {
+    let page = state.services.marketplace.get_activity(query).await?;
+    Ok(Json(page))
+}
+
 /// Price chart query params
 #[derive(Debug, serde::Deserialize)]
 pub struct PriceChartQuery {
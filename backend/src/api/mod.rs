@@ -1,10 +1,12 @@
 //! API routes
 
 mod achievement;
+mod admin;
 mod auth;
 mod battle;
 mod capture;
 mod chat;
+mod errors;
 mod friend;
 mod game;
 mod guild;
@@ -13,17 +15,21 @@ mod inventory;
 mod leaderboard;
 mod map;
 mod marketplace;
+mod metrics;
 mod notification;
 mod player;
 mod pvp;
 mod quest;
 mod solana;
+mod time;
 mod titan;
+mod transaction;
 
 use std::sync::Arc;
 
-use axum::Router;
+use axum::{middleware, Router};
 
+use crate::middleware::server_time::server_time_header;
 use crate::AppState;
 
 /// Build all API routes
@@ -31,13 +37,20 @@ pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .merge(health::routes())
         .merge(health::routes_with_state(state.clone()))
-        .nest("/api/v1", api_routes(state))
+        .merge(admin::ops_routes(state.clone()))
+        .merge(metrics::routes(state.clone()))
+        .nest(
+            "/api/v1",
+            api_routes(state).layer(middleware::map_response(server_time_header)),
+        )
 }
 
 /// API v1 routes
 fn api_routes(state: Arc<AppState>) -> Router {
     Router::new()
         // Core routes
+        .merge(time::routes())
+        .merge(errors::routes())
         .merge(auth::routes(state.clone()))
         .merge(map::routes(state.clone()))
         .merge(capture::routes(state.clone()))
@@ -60,5 +73,8 @@ fn api_routes(state: Arc<AppState>) -> Router {
         // Blockchain routes
         .merge(solana::routes(state.clone()))
         .merge(titan::routes(state.clone()))
+        .merge(transaction::routes(state.clone()))
         .merge(game::routes(state.clone()))
+        // Admin routes
+        .merge(admin::routes(state.clone()))
 }
@@ -13,6 +13,7 @@ use uuid::Uuid;
 use crate::error::ApiResult;
 use crate::middleware::auth::AuthPlayer;
 use crate::models::{Notification, NotificationCount};
+use crate::websocket::WsMessage;
 use crate::AppState;
 
 /// Notification query
@@ -73,6 +74,7 @@ async fn mark_read(
         .notification
         .mark_read(player.player_id, notification_id)
         .await?;
+    push_unread_count(&state, player.player_id).await;
     Ok(Json("Marked as read"))
 }
 
@@ -92,9 +94,22 @@ async fn mark_all_read(
         .notification
         .mark_all_read(player.player_id)
         .await?;
+    push_unread_count(&state, player.player_id).await;
     Ok(Json(MarkAllResponse { marked_count: count }))
 }
 
+/// Send the new unread count to the player's sockets, so other devices clear
+/// their badge too
+async fn push_unread_count(state: &AppState, player_id: Uuid) {
+    match state.services.notification.get_counts(player_id).await {
+        Ok(counts) => {
+            let message = WsMessage::NotificationCount { unread: counts.unread };
+            state.broadcaster.broadcast_to_player(player_id, message).await;
+        }
+        Err(e) => tracing::warn!("Failed to count notifications for {}: {:?}", player_id, e),
+    }
+}
+
 /// Delete notification
 async fn delete_notification(
     State(state): State<Arc<AppState>>,
@@ -3,14 +3,15 @@
 use std::sync::Arc;
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     routing::get,
     Json, Router,
 };
+use serde::Deserialize;
 
 use crate::error::{ApiResult, AppError};
 use crate::middleware::auth::AuthPlayer;
-use crate::models::{Player, PlayerStats, UpdatePlayer};
+use crate::models::{PendingRewardsResponse, Player, PlayerStats, PlayerTitansResponse, UpdatePlayer};
 use crate::AppState;
 
 /// Get current player profile
@@ -53,6 +54,40 @@ async fn get_my_stats(
     Ok(Json(stats))
 }
 
+/// Titan collection query
+#[derive(Debug, Deserialize)]
+pub struct PlayerTitansQuery {
+    /// Reconcile with the Titan PDAs before listing
+    #[serde(default)]
+    pub sync: bool,
+}
+
+/// Get current player's Titans, optionally synced with on-chain state
+async fn get_my_titans(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Query(query): Query<PlayerTitansQuery>,
+) -> ApiResult<Json<PlayerTitansResponse>> {
+    let sync_report = if query.sync {
+        let solana = state.services.solana.as_ref()
+            .ok_or(AppError::Internal(anyhow::anyhow!("Solana service not available")))?;
+
+        Some(
+            state
+                .services
+                .inventory
+                .sync_onchain(player.player_id, &player.wallet_address, solana)
+                .await?,
+        )
+    } else {
+        None
+    };
+
+    let titans = state.services.inventory.get_all(player.player_id).await?;
+
+    Ok(Json(PlayerTitansResponse { titans, sync_report }))
+}
+
 /// Get player by ID (public profile)
 async fn get_player(
     State(state): State<Arc<AppState>>,
@@ -68,10 +103,21 @@ async fn get_player(
     Ok(Json(player))
 }
 
+/// Rewards whose transfer failed and are still owed to the player
+async fn get_pending_rewards(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+) -> ApiResult<Json<PendingRewardsResponse>> {
+    let pending = state.services.reward.get_pending(player.player_id).await?;
+    Ok(Json(pending))
+}
+
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/player/me", get(get_me).put(update_me))
         .route("/player/me/stats", get(get_my_stats))
+        .route("/player/titans", get(get_my_titans))
+        .route("/player/rewards/pending", get(get_pending_rewards))
         .route("/player/:player_id", get(get_player))
         // Note: /leaderboard is now handled by leaderboard.rs
         .with_state(state)
@@ -13,9 +13,11 @@ use uuid::Uuid;
 use crate::error::ApiResult;
 use crate::middleware::auth::AuthPlayer;
 use crate::models::{
-    ActionResultResponse, JoinQueueRequest, MatchHistoryEntry, MatchStateResponse,
-    PvpLeaderboardEntry, PvpSeason, PvpStatsResponse, QueueStatusResponse, SubmitActionRequest,
+    ActionResultResponse, JoinQueueRequest, MatchHistoryEntry, MatchStateResponse, PvpChallenge,
+    PvpChallengeRequest, PvpLeaderboardEntry, PvpSeason, PvpStatsResponse, QueueStatusResponse,
+    SubmitActionRequest,
 };
+use crate::websocket::{publish_match_update, WsMessage};
 use crate::AppState;
 
 /// Get current season
@@ -41,7 +43,18 @@ async fn join_queue(
     AuthPlayer(player): AuthPlayer,
     Json(req): Json<JoinQueueRequest>,
 ) -> ApiResult<Json<QueueStatusResponse>> {
-    let status = state.services.pvp.join_queue(player.player_id, req).await?;
+    let (status, found) = state.services.pvp.join_queue(player.player_id, req).await?;
+
+    // Push the match to both players; the opponent was waiting in the queue
+    if let Some(found) = found {
+        for (matched, opponent) in found.pairings() {
+            state
+                .broadcaster
+                .broadcast_to_player(matched.player_id, WsMessage::pvp_match_found(&found, opponent))
+                .await;
+        }
+    }
+
     Ok(Json(status))
 }
 
@@ -63,6 +76,49 @@ async fn get_queue_status(
     Ok(Json(status))
 }
 
+/// Challenge another player to a match
+async fn challenge(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Path(target_id): Path<Uuid>,
+    Json(req): Json<PvpChallengeRequest>,
+) -> ApiResult<Json<PvpChallenge>> {
+    let challenge = state.services.pvp.challenge(player.player_id, target_id, req).await?;
+    state
+        .broadcaster
+        .broadcast_to_player(target_id, WsMessage::pvp_challenge_received(&challenge))
+        .await;
+    Ok(Json(challenge))
+}
+
+/// Accept a challenge; the match starts like a matchmaking one
+async fn accept_challenge(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Path(challenge_id): Path<Uuid>,
+) -> ApiResult<Json<MatchStateResponse>> {
+    let found = state.services.pvp.accept_challenge(player.player_id, challenge_id).await?;
+    for (matched, opponent) in found.pairings() {
+        state
+            .broadcaster
+            .broadcast_to_player(matched.player_id, WsMessage::pvp_match_found(&found, opponent))
+            .await;
+    }
+
+    let match_state = state.services.pvp.get_match_state(player.player_id, found.match_id).await?;
+    Ok(Json(match_state))
+}
+
+/// Turn down a challenge
+async fn reject_challenge(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Path(challenge_id): Path<Uuid>,
+) -> ApiResult<Json<&'static str>> {
+    state.services.pvp.reject_challenge(player.player_id, challenge_id).await?;
+    Ok(Json("Challenge rejected"))
+}
+
 /// Select titan request
 #[derive(Debug, Deserialize)]
 pub struct SelectTitanRequest {
@@ -81,6 +137,7 @@ async fn select_titan(
         .pvp
         .select_titan(player.player_id, match_id, req.titan_id)
         .await?;
+    publish_match_update(&state, match_id).await;
     Ok(Json(state_response))
 }
 
@@ -104,7 +161,9 @@ async fn submit_action(
     AuthPlayer(player): AuthPlayer,
     Json(req): Json<SubmitActionRequest>,
 ) -> ApiResult<Json<ActionResultResponse>> {
+    let match_id = req.match_id;
     let result = state.services.pvp.submit_action(player.player_id, req).await?;
+    publish_match_update(&state, match_id).await;
     Ok(Json(result))
 }
 
@@ -115,6 +174,7 @@ async fn surrender(
     Path(match_id): Path<Uuid>,
 ) -> ApiResult<Json<&'static str>> {
     state.services.pvp.surrender(player.player_id, match_id).await?;
+    publish_match_update(&state, match_id).await;
     Ok(Json("Surrendered"))
 }
 
@@ -176,6 +236,10 @@ pub fn routes(state: Arc<AppState>) -> Router {
         .route("/pvp/stats", get(get_my_stats))
         // Matchmaking
         .route("/pvp/queue", post(join_queue).get(get_queue_status).delete(leave_queue))
+        // Challenges
+        .route("/pvp/challenge/:id", post(challenge))
+        .route("/pvp/challenge/:id/accept", post(accept_challenge))
+        .route("/pvp/challenge/:id/reject", post(reject_challenge))
         // Match
         .route("/pvp/match/:match_id", get(get_match_state))
         .route("/pvp/match/:match_id/titan", post(select_titan))
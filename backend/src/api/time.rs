@@ -0,0 +1,31 @@
+//! Server time endpoint for client clock sync
+
+use axum::{routing::get, Json, Router};
+use serde::Serialize;
+
+use crate::utils::clock::MAX_ALLOWED_SKEW_SECONDS;
+
+#[derive(Serialize)]
+struct TimeResponse {
+    /// Unix milliseconds
+    server_time: i64,
+    /// Unix microseconds, for NTP-style offset estimation
+    server_time_us: i64,
+    max_allowed_skew_seconds: i64,
+}
+
+/// Current server time
+async fn get_time() -> Json<TimeResponse> {
+    let now = chrono::Utc::now();
+
+    Json(TimeResponse {
+        server_time: now.timestamp_millis(),
+        server_time_us: now.timestamp_micros(),
+        max_allowed_skew_seconds: MAX_ALLOWED_SKEW_SECONDS,
+    })
+}
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/time", get(get_time))
+}
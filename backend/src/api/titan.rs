@@ -4,11 +4,16 @@
 
 use std::sync::Arc;
 
-use axum::{extract::State, routing::post, Json, Router};
+use axum::{
+    extract::{Query, State},
+    routing::{get, post},
+    Json, Router,
+};
 use serde::{Deserialize, Serialize};
 
 use crate::error::{ApiResult, AppError};
 use crate::middleware::auth::AuthPlayer;
+use crate::services::fusion::FusePreview;
 use crate::AppState;
 
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -112,6 +117,12 @@ async fn build_fuse(
     AuthPlayer(player): AuthPlayer,
     Json(request): Json<FuseRequest>,
 ) -> ApiResult<Json<FuseTransactionResponse>> {
+    state
+        .services
+        .inventory
+        .ensure_not_escrowed(&[request.titan_a_id, request.titan_b_id])
+        .await?;
+
     let solana = state.services.solana.as_ref()
         .ok_or(AppError::Internal(anyhow::anyhow!("Solana service not available")))?;
 
@@ -130,6 +141,28 @@ async fn build_fuse(
     }))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct FusePreviewQuery {
+    /// On-chain ID of the first Titan
+    pub titan_a: u64,
+    /// On-chain ID of the second Titan
+    pub titan_b: u64,
+}
+
+/// Check fusion eligibility and predict offspring stats from on-chain state
+async fn fuse_preview(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(_player): AuthPlayer,
+    Query(query): Query<FusePreviewQuery>,
+) -> ApiResult<Json<FusePreview>> {
+    let solana = state.services.solana.as_ref()
+        .ok_or(AppError::Internal(anyhow::anyhow!("Solana service not available")))?;
+
+    let preview = solana.fuse_preview(query.titan_a, query.titan_b).await?;
+
+    Ok(Json(preview))
+}
+
 // ═══════════════════════════════════════════════════════════════════════════════
 // Transfer API
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -148,6 +181,12 @@ async fn build_transfer(
     AuthPlayer(player): AuthPlayer,
     Json(request): Json<TransferRequest>,
 ) -> ApiResult<Json<BuildTransactionResponse>> {
+    state
+        .services
+        .inventory
+        .ensure_not_escrowed(&[request.titan_id])
+        .await?;
+
     let solana = state.services.solana.as_ref()
         .ok_or(AppError::Internal(anyhow::anyhow!("Solana service not available")))?;
 
@@ -213,6 +252,8 @@ pub fn routes(state: Arc<AppState>) -> Router {
         .route("/titan/level-up/build", post(build_level_up))
         .route("/titan/evolve/build", post(build_evolve))
         .route("/titan/fuse/build", post(build_fuse))
+        // Read-only checks
+        .route("/titan/fuse/preview", get(fuse_preview))
         .route("/titan/transfer/build", post(build_transfer))
         // Submit transaction endpoint
         .route("/titan/submit", post(submit_transaction))
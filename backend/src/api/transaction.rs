@@ -0,0 +1,34 @@
+//! Tracked transaction API endpoints
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    routing::get,
+    Json, Router,
+};
+
+use crate::error::ApiResult;
+use crate::middleware::auth::AuthPlayer;
+use crate::models::PendingTransaction;
+use crate::AppState;
+
+/// Status of one of the player's tracked transactions
+async fn get_transaction(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    Path(signature): Path<String>,
+) -> ApiResult<Json<PendingTransaction>> {
+    let transaction = state
+        .services
+        .transaction
+        .get_for_player(player.player_id, &signature)
+        .await?;
+    Ok(Json(transaction))
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/transactions/:signature", get(get_transaction))
+        .with_state(state)
+}
@@ -1,6 +1,18 @@
 //! Application configuration management
 
-use serde::Deserialize;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::RwLock;
+
+use crate::models::Element;
+
+/// Live `game` settings, replaced in place when the config is reloaded
+pub type SharedGameConfig = Arc<RwLock<GameConfig>>;
 
 /// Main application configuration
 #[derive(Debug, Clone, Deserialize)]
@@ -11,6 +23,11 @@ pub struct AppConfig {
     pub solana: SolanaConfig,
     pub auth: AuthConfig,
     pub game: GameConfig,
+    pub websocket: WebSocketConfig,
+    pub fee_budget: FeeBudgetConfig,
+    pub capture_modifiers: CaptureModifierConfig,
+    pub maintenance: MaintenanceConfig,
+    pub marketplace: MarketplaceConfig,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -31,6 +48,8 @@ pub struct DatabaseConfig {
 pub struct RedisConfig {
     pub url: String,
     pub pool_size: u32,
+    /// Relay WebSocket broadcasts through Redis pub/sub to other backend instances
+    pub broadcast_relay_enabled: bool,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -41,6 +60,11 @@ pub struct SolanaConfig {
     pub game_program_id: String,
     pub breach_token_mint: String,
     pub backend_keypair_path: String,
+    /// Collect capture mints for this long into one batch transaction (0 = mint each capture on its own)
+    pub mint_batch_window_ms: u64,
+    /// How long reward transfers wait for confirmation before giving up with
+    /// a 503 the client can retry
+    pub confirm_timeout_seconds: u64,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -48,14 +72,355 @@ pub struct AuthConfig {
     pub jwt_secret: String,
     pub jwt_expiry_hours: u64,
     pub signature_expiry_seconds: u64,
+    /// Wallets allowed to use `/api/v1/admin` endpoints
+    #[serde(default)]
+    pub admin_wallets: Vec<String>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameConfig {
     pub capture_radius_meters: f64,
+    /// Least time between a player's captures, and after an escape from a
+    /// single-capture Titan
     pub capture_cooldown_seconds: u64,
+    /// Least time before retrying a multi-capture Titan after it escaped
+    /// (0 = no wait)
+    pub capture_attempt_cooldown_seconds: u64,
+    /// How long a signed capture challenge from `/capture/request` stays
+    /// valid for confirming or building the mint transaction
+    pub capture_challenge_ttl_seconds: u64,
+    /// Players a Titan of threat classes I-V takes to capture together
+    /// (1 = captured alone; more = cooperative capture only)
+    pub max_co_captors: [u8; 5],
+    /// How long a cooperative capture has to fill up and be confirmed by
+    /// everyone before it is cancelled
+    pub cooperative_capture_timeout_seconds: u64,
     pub max_speed_mps: f64,
     pub location_accuracy_threshold: f64,
+    /// WebSocket messages a connection may send in any one-second window
+    pub ws_messages_per_second: u32,
+    /// Close a connection after this many bursts over the limit (0 = never)
+    pub ws_max_rate_violations: u32,
+    /// Accrue capture rewards in the on-chain ledger instead of transferring them
+    pub reward_accrual_enabled: bool,
+    /// Active Titans the hourly spawn cycle keeps in one 5-character geohash region
+    pub max_spawns_per_region: usize,
+    /// Active Titans any spawn cycle lets into one 5-character geohash cell
+    pub max_active_per_geohash: u32,
+    /// Capture API requests one player may make per minute (0 = unlimited)
+    pub capture_rate_limit_per_min: u32,
+    /// Chat messages one player may send per chat rate window (0 = unlimited)
+    pub chat_rate_limit_messages: u32,
+    /// Window the chat message limit refills over
+    pub chat_rate_limit_window_seconds: u64,
+    /// Marketplace offers one player may make per minute (0 = unlimited)
+    pub offer_rate_limit_per_min: u32,
+    /// Creator's cut of a marketplace resale, in basis points of the price
+    /// (500 = 5%)
+    pub royalty_bps: u32,
+    /// Element weight multipliers by UTC time of day for new spawns
+    #[serde(default)]
+    pub spawn_schedule: SpawnSchedule,
+    /// Threat class odds of regular spawns and their lean towards busy POIs
+    #[serde(default)]
+    pub spawn_rarity: SpawnRarity,
+    /// PvP damage multipliers by attacking and defending element, in place
+    /// of the built-in type chart for the pairs listed
+    #[serde(default)]
+    pub element_advantage_table: ElementAdvantageTable,
+    /// Seconds past a PvP turn deadline before the timeout task acts on it
+    pub pvp_turn_grace_seconds: u64,
+    /// Timed-out turns in a row that forfeit a PvP match
+    pub pvp_max_consecutive_skips: u32,
+    /// Seconds a player disconnected mid-match has to reconnect before
+    /// their timed-out turn forfeits the match
+    pub pvp_reconnect_grace_seconds: u64,
+    /// Day of the weekly leaderboard rollover (`mon`, `tue`, ...)
+    pub leaderboard_rollover_weekday: Weekday,
+    /// UTC hour of the rollover on that day (0-23)
+    pub leaderboard_rollover_hour_utc: u32,
+    /// Places kept per board in each weekly snapshot
+    pub leaderboard_snapshot_size: u32,
+    /// Speed violations within `location_violation_window_seconds` that
+    /// quarantine a player's location (0 = never)
+    pub location_violation_limit: u32,
+    pub location_violation_window_seconds: u64,
+    /// How long a quarantined location is distrusted for captures, from the
+    /// last violation
+    pub location_quarantine_seconds: u64,
+    /// Recent fixes per player fitted to a smooth path to catch gradual fake
+    /// movement (0 = off, otherwise at least 4)
+    pub location_trail_points: usize,
+    /// RMS distance in metres from the fitted path above which a trail is
+    /// implausible
+    pub location_trail_max_residual_m: f64,
+    /// Implausible trails after which further ones are refused as speed
+    /// violations (0 = only count them)
+    pub location_trail_violation_limit: u32,
+    /// How recently a friend must have reported a location for it to be
+    /// shared on `GET /friends/locations`
+    pub friend_location_window_minutes: u64,
+}
+
+/// Element spawn weights by UTC time of day, e.g. Volcanic Titans by day
+/// and Abyssal ones by night
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpawnSchedule {
+    pub entries: Vec<SpawnScheduleEntry>,
+}
+
+/// One element's weight multiplier for part of the day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpawnScheduleEntry {
+    /// First UTC hour the entry applies (0-23)
+    pub hour_start: u32,
+    /// Hour it stops applying (1-24); below `hour_start` wraps past midnight
+    pub hour_end: u32,
+    pub element: Element,
+    pub weight_multiplier: f64,
+    /// Only the first this many minutes of each hour
+    #[serde(default)]
+    pub within_minutes: Option<u32>,
+}
+
+impl SpawnSchedule {
+    /// Combined multiplier of the entries for `element` active at `hour:minute`
+    pub fn multiplier(&self, element: Element, hour: u32, minute: u32) -> f64 {
+        self.entries
+            .iter()
+            .filter(|e| e.element == element && e.covers(hour, minute))
+            .map(|e| e.weight_multiplier)
+            .product()
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for (i, entry) in self.entries.iter().enumerate() {
+            if entry.hour_start > 23 || entry.hour_end > 24 || entry.hour_start == entry.hour_end {
+                bail!(
+                    "game.spawn_schedule entry {}: hours {}-{} are not a range within 0-24",
+                    i, entry.hour_start, entry.hour_end
+                );
+            }
+            if !entry.weight_multiplier.is_finite() || entry.weight_multiplier < 0.0 {
+                bail!(
+                    "game.spawn_schedule entry {}: weight_multiplier must be a non-negative number",
+                    i
+                );
+            }
+            if entry.within_minutes.is_some_and(|m| m == 0 || m > 60) {
+                bail!("game.spawn_schedule entry {}: within_minutes must be 1-60", i);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SpawnScheduleEntry {
+    fn covers(&self, hour: u32, minute: u32) -> bool {
+        let in_hours = if self.hour_start < self.hour_end {
+            (self.hour_start..self.hour_end).contains(&hour)
+        } else {
+            hour >= self.hour_start || hour < self.hour_end
+        };
+        in_hours && self.within_minutes.is_none_or(|m| minute < m)
+    }
+}
+
+impl Default for SpawnSchedule {
+    /// Volcanic by day, Abyssal by night, Storm at the top of every hour
+    fn default() -> Self {
+        let entry = |hour_start, hour_end, element, within_minutes| SpawnScheduleEntry {
+            hour_start,
+            hour_end,
+            element,
+            weight_multiplier: 1.5,
+            within_minutes,
+        };
+        Self {
+            entries: vec![
+                entry(6, 18, Element::Volcanic, None),
+                entry(18, 6, Element::Abyssal, None),
+                entry(0, 24, Element::Storm, Some(10)),
+            ],
+        }
+    }
+}
+
+/// Threat class odds of regular (non-event) spawns
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpawnRarity {
+    /// Relative weights of threat classes I-V at a POI of `spawn_weight` 1
+    pub threat_class_weights: [f64; 5],
+    /// How far busier POIs favour rarer classes: class N's weight is scaled
+    /// by `spawn_weight ^ (popularity_bias * (N - 1) / 4)`, so with 1.0 a
+    /// POI of weight 5 makes class V five times likelier (0 = no lean)
+    pub popularity_bias: f64,
+}
+
+impl SpawnRarity {
+    /// Class I-V weights at a POI of `spawn_weight`
+    pub fn weights_for(&self, spawn_weight: f64) -> [f64; 5] {
+        let popularity = spawn_weight.max(0.0);
+        let mut weights = self.threat_class_weights;
+        for (i, weight) in weights.iter_mut().enumerate() {
+            *weight *= popularity.powf(self.popularity_bias * i as f64 / 4.0);
+        }
+        weights
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let weights = &self.threat_class_weights;
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) || !weights.iter().any(|w| *w > 0.0) {
+            bail!("game.spawn_rarity.threat_class_weights must be non-negative with at least one above zero");
+        }
+        if !self.popularity_bias.is_finite() || self.popularity_bias < 0.0 {
+            bail!("game.spawn_rarity.popularity_bias must be 0 or more, got {}", self.popularity_bias);
+        }
+        Ok(())
+    }
+}
+
+impl Default for SpawnRarity {
+    /// Class V at 1% at an ordinary POI
+    fn default() -> Self {
+        Self {
+            threat_class_weights: [60.0, 25.0, 10.0, 4.0, 1.0],
+            popularity_bias: 1.0,
+        }
+    }
+}
+
+/// PvP element multipliers overriding the type chart, e.g. Abyssal dealing
+/// 1.5x to Volcanic and taking 0.75x back
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ElementAdvantageTable {
+    pub entries: Vec<ElementAdvantage>,
+}
+
+/// Multiplier on damage `attacker`'s element deals to `defender`'s
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElementAdvantage {
+    pub attacker: Element,
+    pub defender: Element,
+    pub multiplier: f64,
+}
+
+impl ElementAdvantageTable {
+    /// The listed multiplier for the pair, else the type chart's
+    pub fn multiplier(&self, attacker: Element, defender: Element) -> f64 {
+        self.entries
+            .iter()
+            .find(|e| e.attacker == attacker && e.defender == defender)
+            .map_or_else(|| attacker.effectiveness(defender), |e| e.multiplier)
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for (i, entry) in self.entries.iter().enumerate() {
+            if !entry.multiplier.is_finite() || entry.multiplier <= 0.0 {
+                bail!("game.element_advantage_table entry {}: multiplier must be a positive number", i);
+            }
+            let first = self
+                .entries
+                .iter()
+                .position(|e| e.attacker == entry.attacker && e.defender == entry.defender);
+            if first != Some(i) {
+                bail!(
+                    "game.element_advantage_table entry {}: {:?} against {:?} is already listed",
+                    i, entry.attacker, entry.defender
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebSocketConfig {
+    /// Geohash regions one connection may be subscribed to at once
+    pub max_subscriptions_per_client: usize,
+    /// Recent events kept per region and replayed to clients resuming a session (0 = none)
+    pub resume_replay_events: usize,
+    /// Connections on one instance that may spectate the same PvP match
+    pub max_spectators_per_match: usize,
+    /// Lifetime of a player's Redis presence key without a refresh
+    pub presence_ttl_seconds: u64,
+    /// Least time between presence refreshes from one connection's heartbeats
+    /// and location updates
+    pub presence_refresh_seconds: u64,
+}
+
+/// Limits on SOL the backend spends on players' behalf
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeeBudgetConfig {
+    /// Default per-player daily cap (UTC day), overridable per player
+    pub player_daily_cap_lamports: u64,
+    /// Cap across all players per clock hour
+    pub global_hourly_cap_lamports: u64,
+    /// Alert when the global cap is hit in this many of the last hours
+    pub alert_after_capped_hours: u32,
+}
+
+/// Scheduled maintenance, announced to connected players
+#[derive(Debug, Clone, Deserialize)]
+pub struct MaintenanceConfig {
+    /// Warn this many minutes before a window starts
+    pub notice_minutes: u64,
+    #[serde(default)]
+    pub windows: Vec<MaintenanceWindow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct MaintenanceWindow {
+    pub starts_at: DateTime<Utc>,
+    pub duration_minutes: u64,
+}
+
+/// Marketplace auction rules and payment settlement
+#[derive(Debug, Clone, Deserialize)]
+pub struct MarketplaceConfig {
+    /// A bid this close to an auction's end extends it
+    pub auction_extension_window_seconds: u64,
+    /// Time left on an auction after a late bid extends it
+    pub auction_extension_seconds: u64,
+    /// Most an auction can be extended past its original end time
+    pub auction_max_extension_seconds: u64,
+    /// Step a proxy bid raises by, in basis points of the bid it answers
+    /// (500 = 5%, at least 1 $BREACH)
+    pub auction_bid_increment_bps: u32,
+    /// Check buyers' $BREACH balance and settle sales on-chain (off for
+    /// tests and local play without Solana)
+    pub breach_settlement_enabled: bool,
+    /// How long the winner of an auction or the maker of an accepted offer
+    /// has to pay before the seller may cancel the sale
+    pub payment_window_hours: u64,
+    /// Share of the platform fee on a sale credited to the seller's guild
+    /// treasury, in basis points of the fee (2000 = 20%)
+    pub guild_fee_share_bps: u32,
+}
+
+/// Capture chance and reward modifiers
+#[derive(Debug, Clone, Deserialize)]
+pub struct CaptureModifierConfig {
+    /// Capture chance before modifiers, for threat classes I-V
+    pub base_chance: [f64; 5],
+    /// Bonus for nocturnal species at night (spawn's local solar time)
+    pub night_enabled: bool,
+    /// Capture chance bonus at solar midnight, e.g. 0.25 = +25%
+    pub night_chance_bonus: f64,
+    /// Reward bonus at solar midnight
+    pub night_reward_bonus: f64,
+    /// Reward reduction for spawns many players captured in the last hour
+    pub crowding_enabled: bool,
+    /// Other players' captures in the last hour before rewards decay
+    pub crowding_free_captures: u32,
+    /// Reward multiplier per capture beyond the free ones
+    pub crowding_decay: f64,
+    /// Lowest crowding reward multiplier
+    pub crowding_min_multiplier: f64,
+    /// Reward bonus for captures in quick succession
+    pub streak_enabled: bool,
+    /// Longest gap between captures that keeps a streak going
+    pub streak_break_seconds: u64,
 }
 
 impl AppConfig {
@@ -72,12 +437,68 @@ impl AppConfig {
             .set_default("database.max_connections", 10)?
             .set_default("database.min_connections", 2)?
             .set_default("redis.pool_size", 10)?
+            .set_default("redis.broadcast_relay_enabled", false)?
+            .set_default("solana.mint_batch_window_ms", 0)?
+            .set_default("solana.confirm_timeout_seconds", 30)?
             .set_default("auth.jwt_expiry_hours", 24)?
             .set_default("auth.signature_expiry_seconds", 300)?
             .set_default("game.capture_radius_meters", 50.0)?
             .set_default("game.capture_cooldown_seconds", 300)?
+            .set_default("game.capture_attempt_cooldown_seconds", 30)?
+            .set_default("game.capture_challenge_ttl_seconds", 30)?
+            .set_default("game.max_co_captors", vec![1, 1, 1, 1, 3])?
+            .set_default("game.cooperative_capture_timeout_seconds", 120)?
             .set_default("game.max_speed_mps", 42.0)?
             .set_default("game.location_accuracy_threshold", 100.0)?
+            .set_default("game.ws_messages_per_second", 60)?
+            .set_default("game.ws_max_rate_violations", 5)?
+            .set_default("game.reward_accrual_enabled", false)?
+            .set_default("game.max_spawns_per_region", 20)?
+            .set_default("game.max_active_per_geohash", 5)?
+            .set_default("game.capture_rate_limit_per_min", 30)?
+            .set_default("game.chat_rate_limit_messages", 5)?
+            .set_default("game.chat_rate_limit_window_seconds", 10)?
+            .set_default("game.offer_rate_limit_per_min", 10)?
+            .set_default("game.royalty_bps", 500)?
+            .set_default("game.pvp_turn_grace_seconds", 5)?
+            .set_default("game.pvp_max_consecutive_skips", 3)?
+            .set_default("game.pvp_reconnect_grace_seconds", 60)?
+            .set_default("game.leaderboard_rollover_weekday", "mon")?
+            .set_default("game.leaderboard_rollover_hour_utc", 0)?
+            .set_default("game.leaderboard_snapshot_size", 100)?
+            .set_default("game.location_violation_limit", 3)?
+            .set_default("game.location_violation_window_seconds", 600)?
+            .set_default("game.location_quarantine_seconds", 900)?
+            .set_default("game.location_trail_points", 10)?
+            .set_default("game.location_trail_max_residual_m", 250.0)?
+            .set_default("game.location_trail_violation_limit", 3)?
+            .set_default("game.friend_location_window_minutes", 30)?
+            .set_default("websocket.max_subscriptions_per_client", 25)?
+            .set_default("websocket.resume_replay_events", 50)?
+            .set_default("websocket.max_spectators_per_match", 50)?
+            .set_default("websocket.presence_ttl_seconds", 120)?
+            .set_default("websocket.presence_refresh_seconds", 30)?
+            .set_default("fee_budget.player_daily_cap_lamports", 50_000_000)?
+            .set_default("fee_budget.global_hourly_cap_lamports", 5_000_000_000u64)?
+            .set_default("fee_budget.alert_after_capped_hours", 3)?
+            .set_default("capture_modifiers.base_chance", vec![0.90, 0.80, 0.65, 0.50, 0.35])?
+            .set_default("capture_modifiers.night_enabled", true)?
+            .set_default("capture_modifiers.night_chance_bonus", 0.25)?
+            .set_default("capture_modifiers.night_reward_bonus", 0.10)?
+            .set_default("capture_modifiers.crowding_enabled", true)?
+            .set_default("capture_modifiers.crowding_free_captures", 3)?
+            .set_default("capture_modifiers.crowding_decay", 0.9)?
+            .set_default("capture_modifiers.crowding_min_multiplier", 0.5)?
+            .set_default("capture_modifiers.streak_enabled", true)?
+            .set_default("capture_modifiers.streak_break_seconds", 900)?
+            .set_default("maintenance.notice_minutes", 30)?
+            .set_default("marketplace.auction_extension_window_seconds", 300)?
+            .set_default("marketplace.auction_extension_seconds", 300)?
+            .set_default("marketplace.auction_max_extension_seconds", 3600)?
+            .set_default("marketplace.auction_bid_increment_bps", 500)?
+            .set_default("marketplace.breach_settlement_enabled", true)?
+            .set_default("marketplace.payment_window_hours", 24)?
+            .set_default("marketplace.guild_fee_share_bps", 2000)?
             // Load from config file
             .add_source(config::File::with_name("config/default").required(false))
             .add_source(config::File::with_name("config/local").required(false))
@@ -85,13 +506,200 @@ impl AppConfig {
             .add_source(
                 config::Environment::default()
                     .separator("__")
-                    .prefix("BREACH"),
+                    .prefix("BREACH")
+                    .try_parsing(true)
+                    .list_separator(",")
+                    .with_list_parse_key("auth.admin_wallets")
+                    .with_list_parse_key("game.max_co_captors"),
             )
             .build()?;
 
         let app_config: AppConfig = config.try_deserialize()?;
         Ok(app_config)
     }
+
+    /// Reject values that would only fail later, naming the offending field
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.server.port == 0 {
+            bail!("server.port must not be 0");
+        }
+        if self.database.max_connections == 0 {
+            bail!("database.max_connections must be at least 1");
+        }
+        if self.database.min_connections > self.database.max_connections {
+            bail!(
+                "database.min_connections ({}) exceeds database.max_connections ({})",
+                self.database.min_connections,
+                self.database.max_connections
+            );
+        }
+        self.game.validate()?;
+        if self.websocket.presence_refresh_seconds >= self.websocket.presence_ttl_seconds {
+            bail!(
+                "websocket.presence_refresh_seconds ({}) must be below websocket.presence_ttl_seconds ({})",
+                self.websocket.presence_refresh_seconds,
+                self.websocket.presence_ttl_seconds
+            );
+        }
+        if self.marketplace.auction_bid_increment_bps > 10_000 {
+            bail!(
+                "marketplace.auction_bid_increment_bps must be at most 10000, got {}",
+                self.marketplace.auction_bid_increment_bps
+            );
+        }
+        if self.marketplace.guild_fee_share_bps > 10_000 {
+            bail!(
+                "marketplace.guild_fee_share_bps must be at most 10000, got {}",
+                self.marketplace.guild_fee_share_bps
+            );
+        }
+        if self.solana.confirm_timeout_seconds == 0 {
+            bail!("solana.confirm_timeout_seconds must be at least 1");
+        }
+
+        for (field, value) in [
+            ("solana.titan_program_id", &self.solana.titan_program_id),
+            ("solana.game_program_id", &self.solana.game_program_id),
+            ("solana.breach_token_mint", &self.solana.breach_token_mint),
+        ] {
+            Pubkey::from_str(value).with_context(|| format!("{} is not a valid pubkey: {:?}", field, value))?;
+        }
+
+        Ok(())
+    }
+
+    /// The `game` section behind a lock, for readers that should see reloads
+    pub fn shared_game(&self) -> SharedGameConfig {
+        Arc::new(RwLock::new(self.game.clone()))
+    }
+
+    /// Re-read the config files and environment for a running server and
+    /// return the new `game` section. Other sections only apply at startup;
+    /// changes to them are logged and ignored.
+    pub fn reload(&self) -> anyhow::Result<GameConfig> {
+        let fresh = Self::load()?;
+        fresh.game.validate()?;
+        for section in self.restart_only_changes(&fresh) {
+            tracing::warn!("Config section [{}] changed; restart to apply it", section);
+        }
+        Ok(fresh.game)
+    }
+
+    /// Sections other than `game` that differ from `other`. Only the names
+    /// are reported, so secrets in them never reach the log.
+    fn restart_only_changes(&self, other: &AppConfig) -> Vec<&'static str> {
+        let sections = [
+            ("server", format!("{:?}", self.server), format!("{:?}", other.server)),
+            ("database", format!("{:?}", self.database), format!("{:?}", other.database)),
+            ("redis", format!("{:?}", self.redis), format!("{:?}", other.redis)),
+            ("solana", format!("{:?}", self.solana), format!("{:?}", other.solana)),
+            ("auth", format!("{:?}", self.auth), format!("{:?}", other.auth)),
+            ("websocket", format!("{:?}", self.websocket), format!("{:?}", other.websocket)),
+            ("fee_budget", format!("{:?}", self.fee_budget), format!("{:?}", other.fee_budget)),
+            (
+                "capture_modifiers",
+                format!("{:?}", self.capture_modifiers),
+                format!("{:?}", other.capture_modifiers),
+            ),
+            ("maintenance", format!("{:?}", self.maintenance), format!("{:?}", other.maintenance)),
+            ("marketplace", format!("{:?}", self.marketplace), format!("{:?}", other.marketplace)),
+        ];
+        sections
+            .into_iter()
+            .filter(|(_, before, after)| before != after)
+            .map(|(name, _, _)| name)
+            .collect()
+    }
+}
+
+impl GameConfig {
+    /// Checks for the settings that can change on reload
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !(5.0..=500.0).contains(&self.capture_radius_meters) {
+            bail!("game.capture_radius_meters must be 5-500, got {}", self.capture_radius_meters);
+        }
+        if self.capture_cooldown_seconds > 3600 {
+            bail!("game.capture_cooldown_seconds must be 0-3600, got {}", self.capture_cooldown_seconds);
+        }
+        if self.capture_challenge_ttl_seconds == 0 {
+            bail!("game.capture_challenge_ttl_seconds must be positive");
+        }
+        if self.max_co_captors.contains(&0) {
+            bail!("game.max_co_captors must be at least 1 for every threat class");
+        }
+        if self.max_co_captors.iter().any(|&n| n > 1) && self.cooperative_capture_timeout_seconds == 0 {
+            bail!("game.cooperative_capture_timeout_seconds must be positive when game.max_co_captors has cooperative classes");
+        }
+        if self.max_speed_mps.is_nan() || self.max_speed_mps <= 0.0 {
+            bail!("game.max_speed_mps must be positive, got {}", self.max_speed_mps);
+        }
+        if self.leaderboard_rollover_hour_utc > 23 {
+            bail!(
+                "game.leaderboard_rollover_hour_utc must be 0-23, got {}",
+                self.leaderboard_rollover_hour_utc
+            );
+        }
+        if self.leaderboard_snapshot_size == 0 {
+            bail!("game.leaderboard_snapshot_size must be at least 1");
+        }
+        if self.royalty_bps > 5_000 {
+            bail!("game.royalty_bps must be at most 5000, got {}", self.royalty_bps);
+        }
+        if self.location_violation_limit > 0 && self.location_violation_window_seconds == 0 {
+            bail!("game.location_violation_window_seconds must be positive when game.location_violation_limit is set");
+        }
+        if self.chat_rate_limit_messages > 0 && self.chat_rate_limit_window_seconds == 0 {
+            bail!("game.chat_rate_limit_window_seconds must be positive when game.chat_rate_limit_messages is set");
+        }
+        if self.location_trail_points > 0 && self.location_trail_points < 4 {
+            bail!("game.location_trail_points must be 0 or at least 4, got {}", self.location_trail_points);
+        }
+        if self.location_trail_max_residual_m.is_nan() || self.location_trail_max_residual_m <= 0.0 {
+            bail!("game.location_trail_max_residual_m must be positive, got {}", self.location_trail_max_residual_m);
+        }
+        if self.friend_location_window_minutes == 0 {
+            bail!("game.friend_location_window_minutes must be positive");
+        }
+        self.spawn_schedule.validate()?;
+        self.spawn_rarity.validate()?;
+        self.element_advantage_table.validate()?;
+        Ok(())
+    }
+}
+
+impl GameConfig {
+    /// These settings with the top-level fields in `patch` replaced; unknown
+    /// fields and values of the wrong type are errors. Not validated.
+    pub fn patched(&self, patch: &serde_json::Map<String, serde_json::Value>) -> anyhow::Result<GameConfig> {
+        let mut fields = match serde_json::to_value(self)? {
+            serde_json::Value::Object(fields) => fields,
+            _ => bail!("game config is not an object"),
+        };
+        for (field, value) in patch {
+            if !fields.contains_key(field) {
+                bail!("game.{} is not a game setting", field);
+            }
+            fields.insert(field.clone(), value.clone());
+        }
+        serde_json::from_value(serde_json::Value::Object(fields)).context("invalid game setting")
+    }
+
+    /// Fields that differ from `before`, as `{ field: { "from", "to" } }`
+    pub fn changes_from(&self, before: &GameConfig) -> anyhow::Result<serde_json::Map<String, serde_json::Value>> {
+        let (serde_json::Value::Object(before), serde_json::Value::Object(after)) =
+            (serde_json::to_value(before)?, serde_json::to_value(self)?)
+        else {
+            bail!("game config is not an object");
+        };
+        Ok(after
+            .into_iter()
+            .filter(|(field, value)| before.get(field) != Some(value))
+            .map(|(field, to)| {
+                let from = before.get(&field).cloned().unwrap_or_default();
+                (field, serde_json::json!({ "from": from, "to": to }))
+            })
+            .collect())
+    }
 }
 
 impl Default for AppConfig {
@@ -110,6 +718,7 @@ impl Default for AppConfig {
             redis: RedisConfig {
                 url: "redis://localhost:6379".to_string(),
                 pool_size: 10,
+                broadcast_relay_enabled: false,
             },
             solana: SolanaConfig {
                 rpc_url: "https://api.devnet.solana.com".to_string(),
@@ -118,18 +727,228 @@ impl Default for AppConfig {
                 game_program_id: "DLk2GnDu9AYn7PeLprEDHDYH9UWKENX47UqqfeiQBaSX".to_string(),
                 breach_token_mint: "CSH2Vz4MbgTLzB9SYJ7gBwNsyu7nKpbvEJzKQLgmmjt4".to_string(),
                 backend_keypair_path: "~/.config/solana/backend-keypair.json".to_string(),
+                mint_batch_window_ms: 0,
+                confirm_timeout_seconds: 30,
             },
             auth: AuthConfig {
                 jwt_secret: "development-secret-change-in-production".to_string(),
                 jwt_expiry_hours: 24,
                 signature_expiry_seconds: 300,
+                admin_wallets: Vec::new(),
             },
             game: GameConfig {
                 capture_radius_meters: 50.0,
                 capture_cooldown_seconds: 300,
+                capture_attempt_cooldown_seconds: 30,
+                capture_challenge_ttl_seconds: 30,
+                max_co_captors: [1, 1, 1, 1, 3],
+                cooperative_capture_timeout_seconds: 120,
                 max_speed_mps: 42.0,
                 location_accuracy_threshold: 100.0,
+                ws_messages_per_second: 60,
+                ws_max_rate_violations: 5,
+                reward_accrual_enabled: false,
+                max_spawns_per_region: 20,
+                max_active_per_geohash: 5,
+                capture_rate_limit_per_min: 30,
+                chat_rate_limit_messages: 5,
+                chat_rate_limit_window_seconds: 10,
+                offer_rate_limit_per_min: 10,
+                royalty_bps: 500,
+                spawn_schedule: SpawnSchedule::default(),
+                spawn_rarity: SpawnRarity::default(),
+                element_advantage_table: ElementAdvantageTable::default(),
+                pvp_turn_grace_seconds: 5,
+                pvp_max_consecutive_skips: 3,
+                pvp_reconnect_grace_seconds: 60,
+                leaderboard_rollover_weekday: Weekday::Mon,
+                leaderboard_rollover_hour_utc: 0,
+                leaderboard_snapshot_size: 100,
+                location_violation_limit: 3,
+                location_violation_window_seconds: 600,
+                location_quarantine_seconds: 900,
+                location_trail_points: 10,
+                location_trail_max_residual_m: 250.0,
+                location_trail_violation_limit: 3,
+                friend_location_window_minutes: 30,
+            },
+            websocket: WebSocketConfig {
+                max_subscriptions_per_client: 25,
+                resume_replay_events: 50,
+                max_spectators_per_match: 50,
+                presence_ttl_seconds: 120,
+                presence_refresh_seconds: 30,
+            },
+            fee_budget: FeeBudgetConfig {
+                player_daily_cap_lamports: 50_000_000,       // 0.05 SOL
+                global_hourly_cap_lamports: 5_000_000_000,   // 5 SOL
+                alert_after_capped_hours: 3,
             },
+            capture_modifiers: CaptureModifierConfig {
+                base_chance: [0.90, 0.80, 0.65, 0.50, 0.35],
+                night_enabled: true,
+                night_chance_bonus: 0.25,
+                night_reward_bonus: 0.10,
+                crowding_enabled: true,
+                crowding_free_captures: 3,
+                crowding_decay: 0.9,
+                crowding_min_multiplier: 0.5,
+                streak_enabled: true,
+                streak_break_seconds: 900,
+            },
+            maintenance: MaintenanceConfig {
+                notice_minutes: 30,
+                windows: Vec::new(),
+            },
+            marketplace: MarketplaceConfig {
+                auction_extension_window_seconds: 300,
+                auction_extension_seconds: 300,
+                auction_max_extension_seconds: 3600,
+                auction_bid_increment_bps: 500,
+                breach_settlement_enabled: true,
+                payment_window_hours: 24,
+                guild_fee_share_bps: 2000,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restart_only_changes() {
+        let running = AppConfig::default();
+
+        let mut fresh = AppConfig::default();
+        fresh.game.capture_radius_meters = 80.0;
+        fresh.game.max_speed_mps = 30.0;
+        assert!(running.restart_only_changes(&fresh).is_empty());
+
+        fresh.server.port = 9090;
+        fresh.database.url = "postgres://elsewhere/breach".into();
+        assert_eq!(running.restart_only_changes(&fresh), vec!["server", "database"]);
+    }
+
+    #[test]
+    fn test_game_patch_and_changes() {
+        let running = AppConfig::default().game;
+        let patch = |value: serde_json::Value| running.patched(value.as_object().unwrap());
+
+        let fresh = patch(serde_json::json!({ "capture_radius_meters": 120.0, "leaderboard_rollover_weekday": "fri" }))
+            .unwrap();
+        assert_eq!(fresh.capture_radius_meters, 120.0);
+        assert_eq!(fresh.leaderboard_rollover_weekday, Weekday::Fri);
+        assert_eq!(fresh.capture_cooldown_seconds, running.capture_cooldown_seconds);
+
+        let changes = fresh.changes_from(&running).unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes["capture_radius_meters"], serde_json::json!({ "from": 50.0, "to": 120.0 }));
+        assert!(running.changes_from(&running).unwrap().is_empty());
+
+        assert!(patch(serde_json::json!({ "capture_radius": 120.0 })).unwrap_err().to_string().contains("capture_radius"));
+        assert!(patch(serde_json::json!({ "capture_cooldown_seconds": "soon" })).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(AppConfig::default().validate().is_ok());
+
+        let invalid = |change: fn(&mut AppConfig)| {
+            let mut config = AppConfig::default();
+            change(&mut config);
+            config.validate().unwrap_err().to_string()
+        };
+
+        assert!(invalid(|c| c.server.port = 0).contains("server.port"));
+        assert!(invalid(|c| c.database.min_connections = 20).contains("database.min_connections"));
+        assert!(invalid(|c| c.solana.confirm_timeout_seconds = 0).contains("solana.confirm_timeout_seconds"));
+        assert!(invalid(|c| c.game.max_speed_mps = 0.0).contains("game.max_speed_mps"));
+        assert!(invalid(|c| c.game.capture_radius_meters = 4.0).contains("game.capture_radius_meters"));
+        assert!(invalid(|c| c.game.capture_cooldown_seconds = 7200).contains("game.capture_cooldown_seconds"));
+        assert!(invalid(|c| c.game.capture_challenge_ttl_seconds = 0).contains("game.capture_challenge_ttl_seconds"));
+        assert!(invalid(|c| c.game.max_co_captors[4] = 0).contains("game.max_co_captors"));
+        assert!(invalid(|c| c.game.cooperative_capture_timeout_seconds = 0).contains("game.cooperative_capture_timeout_seconds"));
+        assert!(invalid(|c| c.game.location_violation_window_seconds = 0).contains("game.location_violation_window_seconds"));
+        assert!(invalid(|c| c.game.chat_rate_limit_window_seconds = 0).contains("game.chat_rate_limit_window_seconds"));
+        assert!(invalid(|c| c.game.royalty_bps = 5_001).contains("game.royalty_bps"));
+        assert!(invalid(|c| c.game.leaderboard_rollover_hour_utc = 24).contains("game.leaderboard_rollover_hour_utc"));
+        assert!(invalid(|c| c.game.location_trail_points = 3).contains("game.location_trail_points"));
+        assert!(invalid(|c| c.game.location_trail_max_residual_m = 0.0).contains("game.location_trail_max_residual_m"));
+        assert!(invalid(|c| c.game.friend_location_window_minutes = 0).contains("game.friend_location_window_minutes"));
+        assert!(invalid(|c| c.websocket.presence_refresh_seconds = 120).contains("websocket.presence_refresh_seconds"));
+        assert!(invalid(|c| c.marketplace.guild_fee_share_bps = 10_001).contains("marketplace.guild_fee_share_bps"));
+        assert!(invalid(|c| c.marketplace.auction_bid_increment_bps = 10_001)
+            .contains("marketplace.auction_bid_increment_bps"));
+        assert!(invalid(|c| c.game.capture_radius_meters = f64::NAN).contains("game.capture_radius_meters"));
+        assert!(invalid(|c| c.solana.game_program_id = "not-a-key".into()).contains("solana.game_program_id"));
+        assert!(invalid(|c| c.game.spawn_schedule.entries[0].hour_end = 25).contains("game.spawn_schedule"));
+        assert!(invalid(|c| c.game.spawn_rarity.threat_class_weights = [0.0; 5]).contains("game.spawn_rarity"));
+        assert!(invalid(|c| c.game.spawn_rarity.popularity_bias = -1.0).contains("game.spawn_rarity"));
+        fn advantage(multiplier: f64) -> ElementAdvantage {
+            ElementAdvantage {
+                attacker: Element::Storm,
+                defender: Element::Void,
+                multiplier,
+            }
         }
+        assert!(invalid(|c| c.game.element_advantage_table.entries = vec![advantage(0.0)])
+            .contains("game.element_advantage_table"));
+        assert!(invalid(|c| c.game.element_advantage_table.entries = vec![advantage(1.5), advantage(2.0)])
+            .contains("game.element_advantage_table"));
+    }
+
+    #[test]
+    fn test_spawn_rarity_leans_to_busy_pois() {
+        let rarity = SpawnRarity::default();
+        assert_eq!(rarity.weights_for(1.0), rarity.threat_class_weights);
+
+        let busy = rarity.weights_for(5.0);
+        assert_eq!(busy[0], 60.0);
+        assert!((busy[4] - 5.0).abs() < 1e-9);
+        assert!(busy.windows(2).all(|pair| pair[0] > pair[1]), "{:?}", busy);
+
+        // Quiet POIs get rarer classes less often, and no bias means no lean
+        assert!(rarity.weights_for(0.5)[4] < 1.0);
+        let flat = SpawnRarity { popularity_bias: 0.0, ..rarity };
+        assert_eq!(flat.weights_for(5.0), flat.threat_class_weights);
+    }
+
+    #[test]
+    fn test_element_advantage_table_overrides_chart() {
+        let table: ElementAdvantageTable = serde_json::from_value(serde_json::json!({
+            "entries": [
+                { "attacker": "abyssal", "defender": "volcanic", "multiplier": 1.5 },
+                { "attacker": "volcanic", "defender": "abyssal", "multiplier": 0.75 },
+            ]
+        }))
+        .unwrap();
+        table.validate().unwrap();
+
+        assert_eq!(table.multiplier(Element::Abyssal, Element::Volcanic), 1.5);
+        assert_eq!(table.multiplier(Element::Volcanic, Element::Abyssal), 0.75);
+        // Unlisted pairs keep the chart
+        assert_eq!(table.multiplier(Element::Storm, Element::Abyssal), 2.0);
+        assert_eq!(
+            ElementAdvantageTable::default().multiplier(Element::Abyssal, Element::Volcanic),
+            Element::Abyssal.effectiveness(Element::Volcanic)
+        );
+    }
+
+    #[test]
+    fn test_spawn_schedule_multiplier() {
+        let schedule = SpawnSchedule::default();
+
+        assert_eq!(schedule.multiplier(Element::Volcanic, 12, 30), 1.5);
+        assert_eq!(schedule.multiplier(Element::Volcanic, 18, 30), 1.0);
+        assert_eq!(schedule.multiplier(Element::Abyssal, 23, 30), 1.5);
+        // Wraps past midnight
+        assert_eq!(schedule.multiplier(Element::Abyssal, 3, 30), 1.5);
+        assert_eq!(schedule.multiplier(Element::Abyssal, 6, 0), 1.0);
+        // Top of the hour only
+        assert_eq!(schedule.multiplier(Element::Storm, 14, 5), 1.5);
+        assert_eq!(schedule.multiplier(Element::Storm, 14, 10), 1.0);
+        assert_eq!(schedule.multiplier(Element::Void, 12, 0), 1.0);
     }
 }
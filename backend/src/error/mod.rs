@@ -1,7 +1,7 @@
 //! Error handling for the API
 
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -34,6 +34,14 @@ pub enum AppError {
     #[error("Speed violation detected")]
     SpeedViolation,
 
+    #[error("Device clock is off by {offset_ms}ms (max {max_skew_seconds}s)")]
+    ClockSkew {
+        context: &'static str,
+        offset_ms: i64,
+        max_skew_seconds: i64,
+        server_time: i64,
+    },
+
     // Game errors
     #[error("Titan not found")]
     TitanNotFound,
@@ -44,12 +52,34 @@ pub enum AppError {
     #[error("Titan expired")]
     TitanExpired,
 
-    #[error("Capture on cooldown")]
-    CaptureCooldown,
+    #[error("Titan is held in escrow by an active marketplace listing")]
+    TitanEscrowed,
+
+    #[error("A request with this Idempotency-Key is still in progress")]
+    IdempotencyKeyInUse,
+
+    #[error("Cooperative capture already has all its players")]
+    CooperativeCaptureFull,
+
+    #[error("Titan escaped")]
+    CaptureEscaped { capture_chance: f64, escaped_count: i32 },
+
+    #[error("Too many requests, retry in {retry_after_secs}s")]
+    TooManyRequests { retry_after_secs: u64 },
 
     #[error("Player not found")]
     PlayerNotFound,
 
+    #[error("Insufficient $BREACH balance: {balance} of {required} required")]
+    InsufficientFunds { required: u64, balance: u64 },
+
+    // On-chain errors
+    #[error("{}", .0.description)]
+    ProgramError(&'static breach_program_errors::ErrorInfo),
+
+    #[error("Transaction blockhash expired, build and sign it again")]
+    StaleBlockhash,
+
     // Database errors
     #[error("Database error: {0}")]
     Database(#[from] sqlx::Error),
@@ -74,9 +104,11 @@ pub enum AppError {
     ServiceUnavailable(String),
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let (status, error_code, message) = match &self {
+impl AppError {
+    /// Status and the `error` object of the response body: `code`, `message`
+    /// and, for some errors, `details`
+    pub fn status_and_body(&self) -> (StatusCode, serde_json::Value) {
+        let (status, error_code, message) = match self {
             // 401 Unauthorized
             AppError::InvalidSignature => {
                 (StatusCode::UNAUTHORIZED, "INVALID_SIGNATURE", self.to_string())
@@ -95,6 +127,20 @@ impl IntoResponse for AppError {
             AppError::InvalidLocation => {
                 (StatusCode::BAD_REQUEST, "INVALID_LOCATION", self.to_string())
             }
+            AppError::ClockSkew { .. } => {
+                (StatusCode::BAD_REQUEST, "CLOCK_SKEW", self.to_string())
+            }
+            AppError::ProgramError(_) => {
+                (StatusCode::BAD_REQUEST, "PROGRAM_ERROR", self.to_string())
+            }
+            AppError::StaleBlockhash => {
+                (StatusCode::BAD_REQUEST, "STALE_BLOCKHASH", self.to_string())
+            }
+
+            // 402 Payment Required
+            AppError::InsufficientFunds { .. } => {
+                (StatusCode::PAYMENT_REQUIRED, "INSUFFICIENT_FUNDS", self.to_string())
+            }
 
             // 403 Forbidden
             AppError::TooFarFromTarget => {
@@ -103,9 +149,6 @@ impl IntoResponse for AppError {
             AppError::SpeedViolation => {
                 (StatusCode::FORBIDDEN, "SPEED_VIOLATION", self.to_string())
             }
-            AppError::CaptureCooldown => {
-                (StatusCode::FORBIDDEN, "COOLDOWN", self.to_string())
-            }
 
             // 404 Not Found
             AppError::TitanNotFound => {
@@ -128,10 +171,27 @@ impl IntoResponse for AppError {
             AppError::TitanAlreadyCaptured => {
                 (StatusCode::CONFLICT, "ALREADY_CAPTURED", self.to_string())
             }
+            AppError::TitanEscrowed => {
+                (StatusCode::CONFLICT, "TITAN_ESCROWED", self.to_string())
+            }
+            AppError::IdempotencyKeyInUse => {
+                (StatusCode::CONFLICT, "IDEMPOTENCY_KEY_IN_USE", self.to_string())
+            }
+            AppError::CooperativeCaptureFull => {
+                (StatusCode::CONFLICT, "COOPERATIVE_CAPTURE_FULL", self.to_string())
+            }
+            AppError::CaptureEscaped { .. } => {
+                (StatusCode::CONFLICT, "CAPTURE_ESCAPED", self.to_string())
+            }
             AppError::TitanExpired => {
                 (StatusCode::GONE, "TITAN_EXPIRED", self.to_string())
             }
 
+            // 429 Too Many Requests
+            AppError::TooManyRequests { .. } => {
+                (StatusCode::TOO_MANY_REQUESTS, "TOO_MANY_REQUESTS", self.to_string())
+            }
+
             // 500 Internal Server Error
             AppError::Database(e) => {
                 tracing::error!("Database error: {:?}", e);
@@ -152,16 +212,159 @@ impl IntoResponse for AppError {
             }
         };
 
-        let body = Json(json!({
-            "error": {
-                "code": error_code,
-                "message": message,
-            }
-        }));
+        let mut error = json!({
+            "code": error_code,
+            "message": message,
+        });
+
+        // Clock skew carries details so the client can prompt a clock fix
+        if let AppError::ClockSkew { context, offset_ms, max_skew_seconds, server_time } = self {
+            error["details"] = json!({
+                "context": context,
+                "offset_ms": offset_ms,
+                "max_allowed_skew_seconds": max_skew_seconds,
+                "server_time": server_time,
+            });
+        }
 
-        (status, body).into_response()
+        // Insufficient funds carries the amounts so the client can show the shortfall
+        if let AppError::InsufficientFunds { required, balance } = self {
+            error["details"] = json!({
+                "required": required,
+                "balance": balance,
+            });
+        }
+
+        // Program errors carry the catalog entry so the client can localize
+        if let AppError::ProgramError(info) = self {
+            error["details"] = json!({
+                "program": info.program.as_str(),
+                "code": info.code,
+                "name": info.name,
+                "message_key": program_error_message_key(info),
+            });
+        }
+
+        // Escapes carry the rolled chance so the client can show the odds
+        if let AppError::CaptureEscaped { capture_chance, escaped_count } = self {
+            error["details"] = json!({
+                "capture_chance": capture_chance,
+                "escaped_count": escaped_count,
+            });
+        }
+
+        // Rate limits and cooldowns say how long to wait, in the body and in Retry-After
+        if let AppError::TooManyRequests { retry_after_secs } = self {
+            error["details"] = json!({ "retry_after_secs": retry_after_secs });
+        }
+
+        (status, error)
     }
 }
 
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let (status, error) = self.status_and_body();
+        let body = Json(json!({ "error": error }));
+
+        let mut response = (status, body).into_response();
+        if let AppError::TooManyRequests { retry_after_secs } = self {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+        }
+        response
+    }
+}
+
+/// Client localization key for a program error
+pub fn program_error_message_key(info: &breach_program_errors::ErrorInfo) -> String {
+    format!("program_error.{}.{}", info.program.as_str(), info.name)
+}
+
 /// Result type alias for API handlers
 pub type ApiResult<T> = Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_clock_skew_response_details() {
+        let err = AppError::ClockSkew {
+            context: "capture",
+            offset_ms: -95_000,
+            max_skew_seconds: 30,
+            server_time: 1_700_000_000_000,
+        };
+
+        let response = err.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(json["error"]["code"], "CLOCK_SKEW");
+        assert_eq!(json["error"]["details"]["context"], "capture");
+        assert_eq!(json["error"]["details"]["offset_ms"], -95_000);
+        assert_eq!(json["error"]["details"]["max_allowed_skew_seconds"], 30);
+        assert_eq!(json["error"]["details"]["server_time"], 1_700_000_000_000i64);
+    }
+
+    #[tokio::test]
+    async fn test_too_many_requests_sets_retry_after() {
+        let response = AppError::TooManyRequests { retry_after_secs: 42 }.into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "42");
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(json["error"]["code"], "TOO_MANY_REQUESTS");
+        assert_eq!(json["error"]["message"], "Too many requests, retry in 42s");
+        assert_eq!(json["error"]["details"]["retry_after_secs"], 42);
+    }
+
+    #[tokio::test]
+    async fn test_capture_escaped_response_details() {
+        let err = AppError::CaptureEscaped { capture_chance: 0.35, escaped_count: 2 };
+
+        let response = err.into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(json["error"]["code"], "CAPTURE_ESCAPED");
+        assert_eq!(json["error"]["details"]["capture_chance"], 0.35);
+        assert_eq!(json["error"]["details"]["escaped_count"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_other_errors_have_no_details() {
+        let response = AppError::TitanNotFound.into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(json["error"]["code"], "TITAN_NOT_FOUND");
+        assert!(json["error"].get("details").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_program_error_response_details() {
+        let info = breach_program_errors::find(breach_program_errors::game_logic::LISTING_EXPIRED).unwrap();
+
+        let response = AppError::ProgramError(info).into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(json["error"]["code"], "PROGRAM_ERROR");
+        assert_eq!(json["error"]["message"], "Listing has expired");
+        assert_eq!(json["error"]["details"]["program"], "game_logic");
+        assert_eq!(json["error"]["details"]["code"], 7701);
+        assert_eq!(json["error"]["details"]["name"], "ListingExpired");
+        assert_eq!(json["error"]["details"]["message_key"], "program_error.game_logic.ListingExpired");
+    }
+}
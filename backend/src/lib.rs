@@ -7,6 +7,7 @@ pub mod api;
 pub mod config;
 pub mod db;
 pub mod error;
+pub mod metrics;
 pub mod middleware;
 pub mod models;
 pub mod scheduler;
@@ -14,17 +15,47 @@ pub mod services;
 pub mod utils;
 pub mod websocket;
 
+use std::sync::Arc;
+
+use axum::{http::HeaderName, Router};
+use tower_http::cors::{Any, CorsLayer};
+use tower_http::trace::TraceLayer;
+
+use crate::config::SharedGameConfig;
+use crate::middleware::server_time::SERVER_TIME_HEADER;
+
 // Re-exports for convenience
 pub use config::AppConfig;
 pub use db::Database;
 pub use error::{ApiResult, AppError};
+pub use metrics::Metrics;
 pub use services::Services;
 pub use websocket::Broadcaster;
 
 /// Application state shared across all handlers
 pub struct AppState {
+    /// As loaded at startup; read `game` for the live game settings
     pub config: AppConfig,
+    /// `config.game`, replaced when the server reloads its config on SIGHUP
+    pub game: SharedGameConfig,
     pub db: Database,
     pub services: Services,
     pub broadcaster: Broadcaster,
+    /// Shared with the services that record metrics
+    pub metrics: Metrics,
+}
+
+/// Build the full HTTP router (API + WebSocket) with its middleware stack
+pub fn app(state: Arc<AppState>) -> Router {
+    Router::new()
+        .merge(api::routes(state.clone()))
+        .merge(websocket::routes(state))
+        .layer(
+            CorsLayer::new()
+                .allow_origin(Any)
+                .allow_methods(Any)
+                .allow_headers(Any)
+                .expose_headers([HeaderName::from_static(SERVER_TIME_HEADER)]),
+        )
+        .layer(TraceLayer::new_for_http())
 }
@@ -5,14 +5,16 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
 
-use axum::Router;
-use tower_http::cors::{Any, CorsLayer};
-use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use breach_backend::{
-    api, config::AppConfig, db::Database, scheduler, services::Services, 
-    websocket, websocket::Broadcaster, AppState,
+    config::AppConfig,
+    db::Database,
+    metrics::Metrics,
+    scheduler,
+    services::{ConfigChangeSource, GameConfigService, Services},
+    websocket::{self, Broadcaster, RedisRelay},
+    AppState,
 };
 
 #[tokio::main]
@@ -30,6 +32,7 @@ async fn main() -> anyhow::Result<()> {
 
     // Load configuration
     let config = AppConfig::load()?;
+    config.validate()?;
     tracing::info!("✅ Configuration loaded");
 
     // Initialize database connections
@@ -37,35 +40,52 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("✅ Database connected");
 
     // Initialize services
-    let services = Services::new(&config, db.clone());
+    let game = config.shared_game();
+    let metrics = Metrics::default();
+    let services = Services::new(&config, game.clone(), db.clone(), metrics.clone());
     tracing::info!("✅ Services initialized");
 
     // Create broadcaster for real-time updates
-    let broadcaster = Broadcaster::new();
-    tracing::info!("✅ WebSocket broadcaster initialized");
+    let broadcaster = if config.redis.broadcast_relay_enabled {
+        let relay = RedisRelay::new(&config.redis.url, db.redis.clone())?;
+        tracing::info!("✅ WebSocket broadcaster initialized (Redis relay)");
+        Broadcaster::with_relay(relay)
+    } else {
+        tracing::info!("✅ WebSocket broadcaster initialized");
+        Broadcaster::new()
+    }
+    .with_subscription_limit(config.websocket.max_subscriptions_per_client)
+    .with_replay_buffer(config.websocket.resume_replay_events)
+    .with_spectator_limit(config.websocket.max_spectators_per_match)
+    .with_presence(
+        services.presence.clone(),
+        std::time::Duration::from_secs(config.websocket.presence_refresh_seconds),
+    );
 
     // Create shared state
     let state = Arc::new(AppState {
         config: config.clone(),
+        game,
         db,
         services,
         broadcaster,
+        metrics,
     });
 
+    // Reload game settings on SIGHUP
+    #[cfg(unix)]
+    tokio::spawn(reload_on_sighup(state.services.game_config.clone()));
+
     // Start background tasks
     scheduler::start_background_tasks(state.clone());
+    websocket::start_relay_listener(state.clone());
+    websocket::start_notification_forwarder(state.clone());
+    websocket::start_quest_forwarder(state.clone());
+    websocket::start_marketplace_forwarder(state.clone());
+    websocket::start_guild_war_forwarder(state.clone());
 
     // Build router
-    let app = Router::new()
-        .merge(api::routes(state.clone()))
-        .merge(websocket::routes(state.clone()))
-        .layer(
-            CorsLayer::new()
-                .allow_origin(Any)
-                .allow_methods(Any)
-                .allow_headers(Any),
-        )
-        .layer(TraceLayer::new_for_http());
+    let app = breach_backend::app(state);
 
     // Start server
     let addr = SocketAddr::from(([0, 0, 0, 0], config.server.port));
@@ -76,3 +96,24 @@ async fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Re-read the config on every SIGHUP and swap in the new `game` settings
+#[cfg(unix)]
+async fn reload_on_sighup(game_config: GameConfigService) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            tracing::warn!("⚠️ SIGHUP handler not installed, config reload disabled: {}", e);
+            return;
+        }
+    };
+
+    while hangups.recv().await.is_some() {
+        match game_config.reload(ConfigChangeSource::Sighup, None).await {
+            Ok(_) => tracing::info!("🔄 Game config reloaded"),
+            Err(e) => tracing::error!("Config reload failed, keeping current settings: {}", e),
+        }
+    }
+}
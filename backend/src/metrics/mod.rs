@@ -0,0 +1,213 @@
+//! Prometheus metrics
+//!
+//! A small in-process registry of gauges, counters and histograms, rendered
+//! in the Prometheus text exposition format by `GET /metrics`. Gauges are set
+//! by the scheduler's metrics task; counters and histograms are updated where
+//! the events happen.
+
+use std::fmt::Write;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Upper bounds (seconds) of the Solana RPC latency buckets
+const RPC_LATENCY_BUCKETS: [f64; 10] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// Shared handle to the metrics registry
+#[derive(Clone, Default)]
+pub struct Metrics(Arc<Registry>);
+
+impl Deref for Metrics {
+    type Target = Registry;
+
+    fn deref(&self) -> &Registry {
+        &self.0
+    }
+}
+
+/// Every metric the backend exports
+pub struct Registry {
+    pub active_titans: Gauge,
+    pub online_players: Gauge,
+    pub total_players: Gauge,
+    pub websocket_connections: Gauge,
+    pub captures_confirmed: Counter,
+    pub nfts_minted: Counter,
+    pub mint_failures: Counter,
+    /// $BREACH paid out or accrued, in smallest units
+    pub breach_distributed: Counter,
+    pub pvp_matches_completed: Counter,
+    pub solana_rpc_latency: Histogram,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self {
+            active_titans: Gauge::default(),
+            online_players: Gauge::default(),
+            total_players: Gauge::default(),
+            websocket_connections: Gauge::default(),
+            captures_confirmed: Counter::default(),
+            nfts_minted: Counter::default(),
+            mint_failures: Counter::default(),
+            breach_distributed: Counter::default(),
+            pvp_matches_completed: Counter::default(),
+            solana_rpc_latency: Histogram::new(&RPC_LATENCY_BUCKETS),
+        }
+    }
+}
+
+impl Registry {
+    /// All metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let gauges = [
+            ("breach_active_titans", "Titans on the map that can still be captured", &self.active_titans),
+            ("breach_online_players", "Players with a location update in the last 5 minutes", &self.online_players),
+            ("breach_players", "Registered players", &self.total_players),
+            ("breach_websocket_connections", "Open WebSocket connections on this instance", &self.websocket_connections),
+        ];
+        for (name, help, gauge) in gauges {
+            write_family(&mut out, name, help, "gauge");
+            let _ = writeln!(out, "{} {}", name, gauge.get());
+        }
+
+        let counters = [
+            ("breach_captures_confirmed_total", "Captures confirmed", &self.captures_confirmed),
+            ("breach_nfts_minted_total", "Titan NFTs minted", &self.nfts_minted),
+            ("breach_mint_failures_total", "Titan NFT mints that failed", &self.mint_failures),
+            ("breach_token_distributed_total", "$BREACH paid out or accrued, in smallest units", &self.breach_distributed),
+            ("breach_pvp_matches_completed_total", "PvP matches completed", &self.pvp_matches_completed),
+        ];
+        for (name, help, counter) in counters {
+            write_family(&mut out, name, help, "counter");
+            let _ = writeln!(out, "{} {}", name, counter.get());
+        }
+
+        let name = "breach_solana_rpc_latency_seconds";
+        write_family(&mut out, name, "Solana send_and_confirm_transaction latency", "histogram");
+        self.solana_rpc_latency.write(&mut out, name);
+        out
+    }
+}
+
+fn write_family(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// Monotonic count
+#[derive(Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, n: u64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Value that goes up and down
+#[derive(Default)]
+pub struct Gauge(AtomicI64);
+
+impl Gauge {
+    pub fn set(&self, value: i64) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> i64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Distribution of durations over fixed buckets
+pub struct Histogram {
+    bounds: &'static [f64],
+    /// Observations per bucket (not cumulative); the last is `+Inf`
+    buckets: Vec<AtomicU64>,
+    sum_micros: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            buckets: (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect(),
+            sum_micros: AtomicU64::new(0),
+            count: AtomicU64::new(0),
+        }
+    }
+
+    pub fn observe(&self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let bucket = self
+            .bounds
+            .iter()
+            .position(|&bound| seconds <= bound)
+            .unwrap_or(self.bounds.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum_micros.fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn write(&self, out: &mut String, name: &str) {
+        let mut cumulative = 0;
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let le = self.bounds.get(i).map_or("+Inf".to_string(), |bound| bound.to_string());
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, le, cumulative);
+        }
+        let sum = self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let _ = writeln!(out, "{}_sum {}", name, sum);
+        let _ = writeln!(out, "{}_count {}", name, self.count.load(Ordering::Relaxed));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_families() {
+        let metrics = Metrics::default();
+        metrics.active_titans.set(12);
+        metrics.captures_confirmed.inc();
+        metrics.breach_distributed.add(500);
+
+        let text = metrics.render();
+        assert!(text.contains("# TYPE breach_active_titans gauge\nbreach_active_titans 12\n"), "{}", text);
+        assert!(text.contains("# TYPE breach_captures_confirmed_total counter\nbreach_captures_confirmed_total 1\n"));
+        assert!(text.contains("breach_token_distributed_total 500\n"));
+        assert!(text.contains("breach_pvp_matches_completed_total 0\n"));
+        assert!(text.contains("# TYPE breach_solana_rpc_latency_seconds histogram\n"));
+    }
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let metrics = Metrics::default();
+        let latency = &metrics.solana_rpc_latency;
+        latency.observe(Duration::from_millis(80));
+        latency.observe(Duration::from_millis(400));
+        latency.observe(Duration::from_secs(90));
+
+        let text = metrics.render();
+        let name = "breach_solana_rpc_latency_seconds";
+        assert!(text.contains(&format!("{}_bucket{{le=\"0.05\"}} 0\n", name)), "{}", text);
+        assert!(text.contains(&format!("{}_bucket{{le=\"0.1\"}} 1\n", name)));
+        assert!(text.contains(&format!("{}_bucket{{le=\"0.5\"}} 2\n", name)));
+        assert!(text.contains(&format!("{}_bucket{{le=\"60\"}} 2\n", name)));
+        assert!(text.contains(&format!("{}_bucket{{le=\"+Inf\"}} 3\n", name)));
+        assert!(text.contains(&format!("{}_sum 90.48\n", name)));
+        assert!(text.contains(&format!("{}_count 3\n", name)));
+    }
+}
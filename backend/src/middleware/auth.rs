@@ -38,10 +38,42 @@ impl FromRequestParts<Arc<AppState>> for AuthPlayer {
         // Verify token
         let session = state.services.auth.verify_token(token)?;
 
+        // Banned players' tokens stop working before they expire
+        if state.services.player.is_banned(session.player_id).await? {
+            return Err(AppError::Forbidden("Account banned".into()));
+        }
+
         Ok(AuthPlayer(session))
     }
 }
 
+/// Extractor for an authenticated admin (wallet listed in `auth.admin_wallets`)
+pub struct AdminPlayer(pub PlayerSession);
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for AdminPlayer {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let AuthPlayer(session) = AuthPlayer::from_request_parts(parts, state).await?;
+
+        if !state
+            .config
+            .auth
+            .admin_wallets
+            .iter()
+            .any(|w| w == &session.wallet_address)
+        {
+            return Err(AppError::Forbidden("Admin access required".into()));
+        }
+
+        Ok(AdminPlayer(session))
+    }
+}
+
 /// Optional auth extractor (for endpoints that work with or without auth)
 pub struct OptionalAuthPlayer(pub Option<PlayerSession>);
 
@@ -62,7 +94,10 @@ impl FromRequestParts<Arc<AppState>> for OptionalAuthPlayer {
         if let Some(header) = auth_header {
             if let Some(token) = header.strip_prefix("Bearer ") {
                 if let Ok(session) = state.services.auth.verify_token(token) {
-                    return Ok(OptionalAuthPlayer(Some(session)));
+                    // A banned player is treated as anonymous
+                    if !state.services.player.is_banned(session.player_id).await? {
+                        return Ok(OptionalAuthPlayer(Some(session)));
+                    }
                 }
             }
         }
@@ -1,3 +1,5 @@
 //! Middleware
 
 pub mod auth;
+pub mod rate_limit;
+pub mod server_time;
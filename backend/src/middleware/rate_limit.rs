@@ -0,0 +1,274 @@
+//! Per-player request rate limits backed by Redis
+//!
+//! Each player gets a token bucket per scope (capture, chat, offers) holding
+//! `limit` requests and refilling over `window`, tracked as a generic cell
+//! rate algorithm: the only state is the time the bucket is next full, kept
+//! in Redis so the limit holds across every backend instance. The bucket math
+//! runs here; Redis only swaps the state in when nobody else changed it.
+//!
+//! Limits fail open: when Redis can't be reached the request is logged and
+//! let through, so a Redis outage doesn't take the API down with it.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use redis::aio::ConnectionManager;
+use uuid::Uuid;
+
+use crate::error::{ApiResult, AppError};
+use crate::middleware::auth::AuthPlayer;
+use crate::AppState;
+
+/// Set the key to the new state if it still holds the state read, with an
+/// expiry; returns 1 when swapped.
+/// KEYS[1] = bucket key; ARGV = state read ('' if none), new state, ttl (ms)
+const COMPARE_AND_SET_SCRIPT: &str = r#"
+local current = redis.call('GET', KEYS[1]) or ''
+if current ~= ARGV[1] then
+    return 0
+end
+redis.call('SET', KEYS[1], ARGV[2], 'PX', ARGV[3])
+return 1
+"#;
+
+/// Swaps to try before letting a contended request through
+const MAX_SWAP_ATTEMPTS: usize = 3;
+
+/// Token bucket of `limit` requests refilling over `window`
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    limit: u32,
+    window_ms: i64,
+}
+
+impl Bucket {
+    /// Time one request takes to refill
+    fn interval_ms(&self) -> i64 {
+        (self.window_ms / self.limit.max(1) as i64).max(1)
+    }
+
+    /// Admit a request at `now_ms` against `full_at`, when the bucket is next
+    /// full (`None` = full). Returns the new `full_at`, or the milliseconds
+    /// until the request would fit.
+    fn admit(&self, full_at: Option<i64>, now_ms: i64) -> Result<i64, u64> {
+        let next_full_at = full_at.unwrap_or(now_ms).max(now_ms) + self.interval_ms();
+        let used_ms = next_full_at - now_ms;
+        if used_ms > self.window_ms {
+            return Err((used_ms - self.window_ms) as u64);
+        }
+        Ok(next_full_at)
+    }
+}
+
+/// Token-bucket limit of `limit` requests per player per `window`
+pub struct RateLimiter {
+    scope: &'static str,
+    bucket: Bucket,
+}
+
+impl RateLimiter {
+    pub fn new(scope: &'static str, limit: u32, window: Duration) -> Self {
+        Self {
+            scope,
+            bucket: Bucket {
+                limit,
+                window_ms: window.as_millis() as i64,
+            },
+        }
+    }
+
+    /// Count a request from `player_id`; rejected requests don't use up the
+    /// bucket. Redis errors let the request through.
+    pub async fn check(&self, redis: &mut ConnectionManager, player_id: Uuid) -> ApiResult<()> {
+        match self.check_at(redis, player_id, chrono::Utc::now().timestamp_millis()).await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(retry_after_ms)) => Err(AppError::TooManyRequests {
+                retry_after_secs: retry_after_ms.div_ceil(1000),
+            }),
+            Err(e) => {
+                tracing::warn!("Rate limit {} for {} not checked: {:?}", self.scope, player_id, e);
+                Ok(())
+            }
+        }
+    }
+
+    /// `Ok(Err(ms))` when the request is over the limit, `ms` until it fits
+    async fn check_at(
+        &self,
+        redis: &mut ConnectionManager,
+        player_id: Uuid,
+        now_ms: i64,
+    ) -> redis::RedisResult<Result<(), u64>> {
+        let key = format!("ratelimit:{}:{}", self.scope, player_id);
+        for _ in 0..MAX_SWAP_ATTEMPTS {
+            let current: Option<String> = redis::cmd("GET").arg(&key).query_async(redis).await?;
+            let full_at = current.as_deref().and_then(|state| state.parse().ok());
+            let next_full_at = match self.bucket.admit(full_at, now_ms) {
+                Ok(next_full_at) => next_full_at,
+                Err(retry_after_ms) => return Ok(Err(retry_after_ms)),
+            };
+
+            let swapped: i32 = redis::Script::new(COMPARE_AND_SET_SCRIPT)
+                .key(&key)
+                .arg(current.unwrap_or_default())
+                .arg(next_full_at)
+                .arg(next_full_at - now_ms)
+                .invoke_async(redis)
+                .await?;
+            if swapped == 1 {
+                return Ok(Ok(()));
+            }
+        }
+
+        tracing::warn!("Rate limit {} for {} contended, request let through", self.scope, player_id);
+        Ok(Ok(()))
+    }
+}
+
+/// Count a request against `limit` per `window` (0 = unlimited)
+async fn enforce(
+    state: &AppState,
+    player_id: Uuid,
+    scope: &'static str,
+    limit: u32,
+    window: Duration,
+) -> ApiResult<()> {
+    if limit == 0 {
+        return Ok(());
+    }
+    RateLimiter::new(scope, limit, window)
+        .check(&mut state.db.redis.clone(), player_id)
+        .await
+}
+
+/// Capture routes: `game.capture_rate_limit_per_min` requests per player per minute (0 = unlimited)
+pub async fn limit_captures(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    request: Request,
+    next: Next,
+) -> ApiResult<Response> {
+    let limit = state.game.read().await.capture_rate_limit_per_min;
+    enforce(&state, player.player_id, "capture", limit, Duration::from_secs(60)).await?;
+
+    Ok(next.run(request).await)
+}
+
+/// Sending chat messages: `game.chat_rate_limit_messages` per player per
+/// `game.chat_rate_limit_window_seconds` (0 = unlimited)
+pub async fn limit_chat_messages(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    request: Request,
+    next: Next,
+) -> ApiResult<Response> {
+    let (limit, window) = {
+        let game = state.game.read().await;
+        (game.chat_rate_limit_messages, game.chat_rate_limit_window_seconds)
+    };
+    enforce(&state, player.player_id, "chat", limit, Duration::from_secs(window)).await?;
+
+    Ok(next.run(request).await)
+}
+
+/// Making marketplace offers: `game.offer_rate_limit_per_min` per player per
+/// minute (0 = unlimited)
+pub async fn limit_offers(
+    State(state): State<Arc<AppState>>,
+    AuthPlayer(player): AuthPlayer,
+    request: Request,
+    next: Next,
+) -> ApiResult<Response> {
+    let limit = state.game.read().await.offer_rate_limit_per_min;
+    enforce(&state, player.player_id, "offer", limit, Duration::from_secs(60)).await?;
+
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Admit requests at each time in turn, carrying the bucket state along
+    struct MockClock {
+        bucket: Bucket,
+        full_at: Option<i64>,
+    }
+
+    impl MockClock {
+        fn new(limit: u32, window_ms: i64) -> Self {
+            Self {
+                bucket: Bucket { limit, window_ms },
+                full_at: None,
+            }
+        }
+
+        fn request(&mut self, now_ms: i64) -> Result<(), u64> {
+            let next_full_at = self.bucket.admit(self.full_at, now_ms)?;
+            self.full_at = Some(next_full_at);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_burst_up_to_limit() {
+        // 5 messages per 10s
+        let mut clock = MockClock::new(5, 10_000);
+        for _ in 0..5 {
+            assert_eq!(clock.request(1_000), Ok(()));
+        }
+        // One request refills every 2s
+        assert_eq!(clock.request(1_000), Err(2_000));
+        assert_eq!(clock.request(2_500), Err(500));
+    }
+
+    #[test]
+    fn test_refills_over_window() {
+        let mut clock = MockClock::new(5, 10_000);
+        for _ in 0..5 {
+            clock.request(0).unwrap();
+        }
+
+        assert_eq!(clock.request(2_000), Ok(()));
+        assert_eq!(clock.request(2_000), Err(2_000));
+
+        // A full window later the bucket is full again
+        for _ in 0..5 {
+            assert_eq!(clock.request(14_000), Ok(()));
+        }
+        assert!(clock.request(14_000).is_err());
+    }
+
+    #[test]
+    fn test_rejected_requests_use_nothing() {
+        let mut clock = MockClock::new(2, 60_000);
+        clock.request(0).unwrap();
+        clock.request(0).unwrap();
+        for now in [1_000, 5_000, 29_000] {
+            assert!(clock.request(now).is_err());
+        }
+        assert_eq!(clock.request(30_000), Ok(()));
+    }
+
+    #[test]
+    fn test_steady_rate_under_limit() {
+        // One request every refill interval never runs out
+        let mut clock = MockClock::new(30, 60_000);
+        for i in 0..500 {
+            assert_eq!(clock.request(i * 2_000), Ok(()), "request {}", i);
+        }
+    }
+
+    #[test]
+    fn test_limit_above_window_ms() {
+        // More requests than milliseconds in the window still refill
+        let bucket = Bucket { limit: 5_000, window_ms: 1_000 };
+        assert_eq!(bucket.interval_ms(), 1);
+        assert_eq!(bucket.admit(None, 0), Ok(1));
+    }
+}
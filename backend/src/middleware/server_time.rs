@@ -0,0 +1,15 @@
+//! Server time header for client clock sync
+
+use axum::{http::HeaderValue, response::Response};
+
+/// Response header carrying the server clock (Unix milliseconds)
+pub const SERVER_TIME_HEADER: &str = "x-server-time";
+
+/// Stamp a response with the current server time
+pub async fn server_time_header(mut response: Response) -> Response {
+    let now = chrono::Utc::now().timestamp_millis();
+    response
+        .headers_mut()
+        .insert(SERVER_TIME_HEADER, HeaderValue::from(now));
+    response
+}
@@ -0,0 +1,467 @@
+//! Capture chance, reward modifiers and capture attempts
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::config::CaptureModifierConfig;
+
+use super::species::Species;
+
+/// Base chance that a capture attempt succeeds, by threat class
+pub fn base_capture_chance(threat_class: i16, config: &CaptureModifierConfig) -> f64 {
+    let index = (threat_class.clamp(1, 5) - 1) as usize;
+    config.base_chance[index]
+}
+
+/// Night strength at a local solar hour: 1.0 at solar midnight, fading to
+/// 0.0 at 06:00 and 18:00, and 0.0 through the day
+pub fn night_factor(solar_hour: f64) -> f64 {
+    (2.0 * PI * solar_hour / 24.0).cos().max(0.0)
+}
+
+/// Reward multiplier after `recent_captures` other players captured the spawn
+/// in the last hour
+pub fn crowding_multiplier(recent_captures: i64, config: &CaptureModifierConfig) -> f64 {
+    let extra = recent_captures - config.crowding_free_captures as i64;
+    if extra <= 0 {
+        return 1.0;
+    }
+
+    config
+        .crowding_decay
+        .powi(extra.min(i32::MAX as i64) as i32)
+        .max(config.crowding_min_multiplier)
+}
+
+/// Reward multiplier for the `streak`th capture in a row
+pub fn streak_multiplier(streak: u32) -> f64 {
+    match streak {
+        0..=2 => 1.0,
+        3..=9 => 1.5,
+        _ => 2.0,
+    }
+}
+
+/// Why a modifier applied
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CaptureModifierSource {
+    /// Nocturnal species captured at night
+    NightActivity { solar_hour: f64 },
+    /// Spawn captured by many other players in the last hour
+    Crowding { recent_captures: i64 },
+    /// Player's captures in a row without a long gap, this one included
+    Streak { streak: u32 },
+}
+
+/// A modifier applied to a capture, shown in the capture response breakdown
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct AppliedModifier {
+    #[serde(flatten)]
+    pub source: CaptureModifierSource,
+    pub chance_multiplier: f64,
+    pub reward_multiplier: f64,
+}
+
+/// Modifiers in effect for one capture
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CaptureModifiers {
+    pub applied: Vec<AppliedModifier>,
+    /// Capture chance before modifiers
+    pub base_chance: f64,
+}
+
+impl CaptureModifiers {
+    /// Evaluate both modifiers; disabled or neutral ones are left out
+    pub fn evaluate(
+        species_id: i32,
+        threat_class: i16,
+        solar_hour: f64,
+        recent_captures: i64,
+        config: &CaptureModifierConfig,
+    ) -> Self {
+        let mut applied = Vec::new();
+
+        if config.night_enabled && Species::is_nocturnal(species_id) {
+            let factor = night_factor(solar_hour);
+            if factor > 0.0 {
+                applied.push(AppliedModifier {
+                    source: CaptureModifierSource::NightActivity { solar_hour },
+                    chance_multiplier: 1.0 + config.night_chance_bonus * factor,
+                    reward_multiplier: 1.0 + config.night_reward_bonus * factor,
+                });
+            }
+        }
+
+        if config.crowding_enabled {
+            let multiplier = crowding_multiplier(recent_captures, config);
+            if multiplier < 1.0 {
+                applied.push(AppliedModifier {
+                    source: CaptureModifierSource::Crowding { recent_captures },
+                    chance_multiplier: 1.0,
+                    reward_multiplier: multiplier,
+                });
+            }
+        }
+
+        Self {
+            applied,
+            base_chance: base_capture_chance(threat_class, config),
+        }
+    }
+
+    /// Add the capture streak bonus; streaks too short for one are left out
+    pub fn with_streak(mut self, streak: u32) -> Self {
+        let multiplier = streak_multiplier(streak);
+        if multiplier > 1.0 {
+            self.applied.push(AppliedModifier {
+                source: CaptureModifierSource::Streak { streak },
+                chance_multiplier: 1.0,
+                reward_multiplier: multiplier,
+            });
+        }
+        self
+    }
+
+    /// Capture chance after modifiers, capped at 1.0
+    pub fn capture_chance(&self) -> f64 {
+        let multiplier: f64 = self.applied.iter().map(|m| m.chance_multiplier).product();
+        (self.base_chance * multiplier).min(1.0)
+    }
+
+    /// Apply reward multipliers to a base reward
+    pub fn apply_reward(&self, base_reward: u64) -> u64 {
+        let multiplier: f64 = self.applied.iter().map(|m| m.reward_multiplier).product();
+        (base_reward as f64 * multiplier).round() as u64
+    }
+}
+
+/// Where a capture confirm got to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "capture_attempt_state", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureAttemptState {
+    /// Recorded, nothing minted yet
+    Pending,
+    /// NFT minted, capture not yet written
+    Minted,
+    /// Capture written; retries return the stored result
+    Confirmed,
+    /// Spawn was gone when the capture was finalized
+    Failed,
+}
+
+/// One player's confirm of one spawn, keyed by (player_id, titan_spawn_id)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CaptureAttempt {
+    pub id: Uuid,
+    pub player_id: Uuid,
+    pub titan_spawn_id: Uuid,
+    pub state: CaptureAttemptState,
+    pub mint_address: Option<String>,
+    pub token_account: Option<String>,
+    pub tx_signature: Option<String>,
+    pub remaining_captures: Option<i32>,
+    pub breach_reward: Option<i64>,
+    pub breach_tx_signature: Option<String>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl CaptureAttempt {
+    /// Whether the NFT still has to be minted; a retry after a successful
+    /// mint must not mint again
+    pub fn needs_mint(&self) -> bool {
+        self.state == CaptureAttemptState::Pending
+    }
+
+    pub fn is_confirmed(&self) -> bool {
+        self.state == CaptureAttemptState::Confirmed
+    }
+}
+
+/// Cooperative capture of one spawn, held in Redis (`coop_capture:{titan_id}`)
+/// until it is settled or times out
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CooperativeCapture {
+    pub titan_id: Uuid,
+    /// Player who opened it; the single NFT is minted to them
+    pub leader_id: Uuid,
+    /// Players it takes; no one joins past this many
+    pub required: u8,
+    /// Leader first, then by player ID
+    pub participants: Vec<CooperativeCaptor>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// One player in a cooperative capture
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CooperativeCaptor {
+    pub player_id: Uuid,
+    pub wallet_address: String,
+    pub confirmed: bool,
+}
+
+impl CooperativeCapture {
+    /// Read the session hash: `leader`, `required`, `expires_at` (unix
+    /// seconds), `p:{player_id}` = wallet and `c:{player_id}` per confirm.
+    /// `None` for a missing or malformed session.
+    pub fn from_hash(titan_id: Uuid, fields: &HashMap<String, String>) -> Option<Self> {
+        let leader_id = fields.get("leader")?.parse().ok()?;
+        let required = fields.get("required")?.parse().ok()?;
+        let expires_at = DateTime::from_timestamp(fields.get("expires_at")?.parse().ok()?, 0)?;
+
+        let mut participants: Vec<_> = fields
+            .iter()
+            .filter_map(|(field, wallet)| {
+                let player_id: Uuid = field.strip_prefix("p:")?.parse().ok()?;
+                Some(CooperativeCaptor {
+                    player_id,
+                    wallet_address: wallet.clone(),
+                    confirmed: fields.contains_key(&format!("c:{}", player_id)),
+                })
+            })
+            .collect();
+        participants.sort_by_key(|captor| (captor.player_id != leader_id, captor.player_id));
+
+        Some(Self {
+            titan_id,
+            leader_id,
+            required,
+            participants,
+            expires_at,
+        })
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.participants.len() >= self.required as usize
+    }
+
+    /// Each participant's equal share of `reward`; the remainder is not paid
+    pub fn reward_share(&self, reward: u64) -> u64 {
+        reward / self.participants.len().max(1) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+
+    const NOCTURNAL: i32 = 3_409; // Void V, variant 9
+    const DIURNAL: i32 = 3_401; // Void V, variant 1
+
+    fn config() -> CaptureModifierConfig {
+        AppConfig::default().capture_modifiers
+    }
+
+    #[test]
+    fn test_base_chance_by_threat_class() {
+        let cfg = config();
+        assert_eq!(base_capture_chance(1, &cfg), 0.90);
+        assert_eq!(base_capture_chance(5, &cfg), 0.35);
+        // Out-of-range classes clamp
+        assert_eq!(base_capture_chance(0, &cfg), 0.90);
+        assert_eq!(base_capture_chance(9, &cfg), 0.35);
+    }
+
+    #[test]
+    fn test_night_factor_curve() {
+        assert!((night_factor(0.0) - 1.0).abs() < 1e-9);
+        assert!((night_factor(3.0) - 0.5f64.sqrt()).abs() < 1e-9);
+        assert!((night_factor(21.0) - 0.5f64.sqrt()).abs() < 1e-9);
+        assert!(night_factor(6.0) < 1e-9);
+        assert_eq!(night_factor(12.0), 0.0);
+        assert_eq!(night_factor(15.0), 0.0);
+    }
+
+    #[test]
+    fn test_night_bonus_only_for_nocturnal() {
+        let cfg = config();
+
+        let night = CaptureModifiers::evaluate(NOCTURNAL, 5, 0.0, 0, &cfg);
+        assert_eq!(night.applied.len(), 1);
+        assert!((night.capture_chance() - 0.35 * 1.25).abs() < 1e-9);
+        assert_eq!(night.apply_reward(1_000), 1_100);
+
+        // Same hour, non-nocturnal species: unaffected
+        let diurnal = CaptureModifiers::evaluate(DIURNAL, 5, 0.0, 0, &cfg);
+        assert!(diurnal.applied.is_empty());
+        assert_eq!(diurnal.capture_chance(), 0.35);
+        assert_eq!(diurnal.apply_reward(1_000), 1_000);
+
+        // Nocturnal species at noon: unaffected
+        assert!(CaptureModifiers::evaluate(NOCTURNAL, 5, 12.0, 0, &cfg).applied.is_empty());
+    }
+
+    #[test]
+    fn test_capture_chance_capped() {
+        let mut cfg = config();
+        cfg.night_chance_bonus = 1.0;
+        let mods = CaptureModifiers::evaluate(3_009, 1, 0.0, 0, &cfg); // Void I
+        assert_eq!(mods.capture_chance(), 1.0);
+    }
+
+    #[test]
+    fn test_crowding_decay() {
+        let cfg = config();
+
+        // Free captures don't reduce rewards
+        for n in 0..=3 {
+            assert_eq!(crowding_multiplier(n, &cfg), 1.0);
+        }
+        assert!((crowding_multiplier(4, &cfg) - 0.9).abs() < 1e-9);
+        assert!((crowding_multiplier(5, &cfg) - 0.81).abs() < 1e-9);
+        assert!((crowding_multiplier(6, &cfg) - 0.729).abs() < 1e-9);
+
+        // Monotonic down to the floor
+        let mut last = 1.0;
+        for n in 0..50 {
+            let m = crowding_multiplier(n, &cfg);
+            assert!(m <= last && m >= cfg.crowding_min_multiplier);
+            last = m;
+        }
+        assert_eq!(crowding_multiplier(50, &cfg), 0.5);
+        assert_eq!(crowding_multiplier(i64::MAX, &cfg), 0.5);
+    }
+
+    #[test]
+    fn test_crowding_breakdown() {
+        let cfg = config();
+        let mods = CaptureModifiers::evaluate(DIURNAL, 2, 12.0, 5, &cfg);
+        assert_eq!(
+            mods.applied,
+            vec![AppliedModifier {
+                source: CaptureModifierSource::Crowding { recent_captures: 5 },
+                chance_multiplier: 1.0,
+                reward_multiplier: crowding_multiplier(5, &cfg),
+            }]
+        );
+        // Crowding never changes the capture chance
+        assert_eq!(mods.capture_chance(), 0.80);
+        assert_eq!(mods.apply_reward(1_000), 810);
+
+        let json = serde_json::to_value(mods.applied[0]).unwrap();
+        assert_eq!(json["kind"], "crowding");
+        assert_eq!(json["recent_captures"], 5);
+    }
+
+    #[test]
+    fn test_modifiers_toggle() {
+        let mut cfg = config();
+        cfg.night_enabled = false;
+        cfg.crowding_enabled = false;
+
+        let mods = CaptureModifiers::evaluate(NOCTURNAL, 5, 0.0, 20, &cfg);
+        assert!(mods.applied.is_empty());
+        assert_eq!(mods.apply_reward(1_000), 1_000);
+    }
+
+    #[test]
+    fn test_both_modifiers_stack() {
+        let cfg = config();
+        let mods = CaptureModifiers::evaluate(NOCTURNAL, 5, 0.0, 4, &cfg);
+        assert_eq!(mods.applied.len(), 2);
+        // 1.10 * 0.9
+        assert_eq!(mods.apply_reward(1_000), 990);
+    }
+
+    #[test]
+    fn test_streak_multiplier_tiers() {
+        assert_eq!(streak_multiplier(0), 1.0);
+        assert_eq!(streak_multiplier(2), 1.0);
+        assert_eq!(streak_multiplier(3), 1.5);
+        assert_eq!(streak_multiplier(9), 1.5);
+        assert_eq!(streak_multiplier(10), 2.0);
+        assert_eq!(streak_multiplier(u32::MAX), 2.0);
+    }
+
+    #[test]
+    fn test_streak_stacks_with_crowding() {
+        let cfg = config();
+        let short = CaptureModifiers::evaluate(DIURNAL, 2, 12.0, 0, &cfg).with_streak(2);
+        assert!(short.applied.is_empty());
+
+        let mods = CaptureModifiers::evaluate(DIURNAL, 2, 12.0, 4, &cfg).with_streak(10);
+        assert_eq!(mods.applied.len(), 2);
+        // 0.9 * 2.0, and the chance is untouched
+        assert_eq!(mods.apply_reward(1_000), 1_800);
+        assert_eq!(mods.capture_chance(), 0.80);
+
+        let json = serde_json::to_value(mods.applied[1]).unwrap();
+        assert_eq!(json["kind"], "streak");
+        assert_eq!(json["streak"], 10);
+    }
+
+    #[test]
+    fn test_attempt_mints_only_while_pending() {
+        let now = Utc::now();
+        let mut attempt = CaptureAttempt {
+            id: Uuid::new_v4(),
+            player_id: Uuid::new_v4(),
+            titan_spawn_id: Uuid::new_v4(),
+            state: CaptureAttemptState::Pending,
+            mint_address: None,
+            token_account: None,
+            tx_signature: None,
+            remaining_captures: None,
+            breach_reward: None,
+            breach_tx_signature: None,
+            error: None,
+            created_at: now,
+            updated_at: now,
+        };
+        assert!(attempt.needs_mint());
+
+        // A retry after a successful mint resumes at finalize
+        attempt.state = CaptureAttemptState::Minted;
+        assert!(!attempt.needs_mint());
+        assert!(!attempt.is_confirmed());
+
+        attempt.state = CaptureAttemptState::Confirmed;
+        assert!(!attempt.needs_mint());
+        assert!(attempt.is_confirmed());
+
+        assert_eq!(serde_json::to_value(attempt.state).unwrap(), "confirmed");
+    }
+
+    #[test]
+    fn test_cooperative_capture_from_hash() {
+        let titan_id = Uuid::new_v4();
+        let (leader, other) = (Uuid::from_u128(2), Uuid::from_u128(1));
+        let mut fields: HashMap<String, String> = [
+            ("leader".to_string(), leader.to_string()),
+            ("required".to_string(), "3".to_string()),
+            ("expires_at".to_string(), "1700000120".to_string()),
+            (format!("p:{}", leader), "LeaderWallet".to_string()),
+            (format!("p:{}", other), "OtherWallet".to_string()),
+            (format!("c:{}", other), "1".to_string()),
+        ]
+        .into_iter()
+        .collect();
+
+        let session = CooperativeCapture::from_hash(titan_id, &fields).unwrap();
+        assert_eq!(session.leader_id, leader);
+        assert_eq!(session.expires_at.timestamp(), 1_700_000_120);
+        assert!(!session.is_full());
+
+        // Leader first, whatever the IDs
+        let ids: Vec<_> = session.participants.iter().map(|c| c.player_id).collect();
+        assert_eq!(ids, vec![leader, other]);
+        assert!(!session.participants[0].confirmed);
+        assert!(session.participants[1].confirmed);
+        assert_eq!(session.participants[1].wallet_address, "OtherWallet");
+
+        // Equal shares, remainder dropped
+        assert_eq!(session.reward_share(20_000_000_001), 10_000_000_000);
+
+        fields.remove("leader");
+        assert_eq!(CooperativeCapture::from_hash(titan_id, &fields), None);
+    }
+}
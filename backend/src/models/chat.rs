@@ -91,6 +91,19 @@ pub struct ChatReport {
     pub created_at: DateTime<Utc>,
 }
 
+/// Admin mute or ban; `expires_at` is None for bans
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct PlayerSanction {
+    pub id: Uuid,
+    pub player_id: Uuid,
+    pub sanction_type: String,
+    pub reason: Option<String>,
+    pub report_id: Option<Uuid>,
+    pub issued_by: Option<Uuid>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
 // ============================================
 // API Request/Response Models
 // ============================================
@@ -178,6 +191,49 @@ pub struct ReportMessageRequest {
     pub description: Option<String>,
 }
 
+/// Admin report list query
+#[derive(Debug, Deserialize)]
+pub struct ReportsQuery {
+    #[serde(default = "default_report_status")]
+    pub status: String,
+    #[serde(default = "default_message_limit")]
+    pub limit: i64,
+}
+
+fn default_report_status() -> String {
+    "pending".to_string()
+}
+
+/// What an admin does about a report
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ModerationAction {
+    Dismiss,
+    DeleteMessage,
+    MutePlayer { hours: i64 },
+    BanPlayer,
+}
+
+/// Resolve report request: `{ "action": "mute_player", "hours": 24 }`
+#[derive(Debug, Deserialize)]
+pub struct ResolveReportRequest {
+    #[serde(flatten)]
+    pub action: ModerationAction,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+/// A resolved report and what was done about it
+#[derive(Debug, Serialize)]
+pub struct ResolvedReport {
+    pub report: ChatReport,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sanction: Option<PlayerSanction>,
+    /// Channel of the message deleted by `delete_message`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_from_channel: Option<Uuid>,
+}
+
 /// Block user request
 #[derive(Debug, Deserialize)]
 pub struct BlockUserRequest {
@@ -204,6 +260,34 @@ fn default_message_limit() -> i64 {
     50
 }
 
+/// Channel list pagination query
+#[derive(Debug, Deserialize)]
+pub struct ChannelsQuery {
+    #[serde(default = "default_channel_limit")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+}
+
+fn default_channel_limit() -> i64 {
+    50
+}
+
+/// A page of channels; pass `next_offset` as `offset` for the next one
+#[derive(Debug, Serialize)]
+pub struct ChannelsPage {
+    pub channels: Vec<ChannelResponse>,
+    pub next_offset: Option<i64>,
+}
+
+/// A page of messages, oldest first; pass `next_cursor` as `before_id` for
+/// the older messages before them
+#[derive(Debug, Serialize)]
+pub struct MessagesPage {
+    pub messages: Vec<MessageResponse>,
+    pub next_cursor: Option<Uuid>,
+}
+
 /// Online status response
 #[derive(Debug, Serialize)]
 pub struct OnlineStatusResponse {
@@ -0,0 +1,315 @@
+//! On-chain fee budget models
+
+use chrono::{DateTime, DurationRound, NaiveDate, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::models::Element;
+
+/// Base fee per signature
+pub const SIGNATURE_FEE_LAMPORTS: u64 = 5_000;
+
+/// Rent-exempt minimum for an account of `data_len` bytes
+pub const fn rent_exempt_lamports(data_len: u64) -> u64 {
+    // (account overhead + data) * lamports per byte-year * 2 years
+    (128 + data_len) * 3_480 * 2
+}
+
+/// Rent paid for a new associated token account (165 bytes)
+pub const ATA_RENT_LAMPORTS: u64 = rent_exempt_lamports(165);
+
+/// On-chain actions the backend pays for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "onchain_action_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum OnchainAction {
+    /// Titan NFT mint plus Game Logic capture record
+    CaptureMint,
+    RecordBattle,
+    AddExperience,
+    /// $BREACH reward transfer (may create the recipient's token account)
+    BreachTransfer,
+}
+
+impl OnchainAction {
+    /// Worst-case lamports the backend pays for this action
+    pub fn estimated_lamports(self) -> u64 {
+        match self {
+            // TitanData (150) + CaptureRecord (83) accounts, two transactions
+            OnchainAction::CaptureMint => {
+                rent_exempt_lamports(150) + rent_exempt_lamports(83) + 2 * SIGNATURE_FEE_LAMPORTS
+            }
+            // BattleRecord (122)
+            OnchainAction::RecordBattle => rent_exempt_lamports(122) + SIGNATURE_FEE_LAMPORTS,
+            OnchainAction::AddExperience => SIGNATURE_FEE_LAMPORTS,
+            OnchainAction::BreachTransfer => ATA_RENT_LAMPORTS + SIGNATURE_FEE_LAMPORTS,
+        }
+    }
+}
+
+/// Deferred action status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "onchain_action_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum OnchainActionStatus {
+    Pending,
+    Completed,
+    Failed,
+}
+
+/// Arguments needed to replay a deferred action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OnchainActionPayload {
+    CaptureMint {
+        element: Element,
+        threat_class: u8,
+        species_id: u32,
+        genes: Vec<u8>,
+        geohash: String,
+    },
+    RecordBattle {
+        titan_mint: String,
+        battle_type: u8,
+        result: u8,
+    },
+    AddExperience {
+        titan_mint: String,
+        xp: u64,
+    },
+    BreachTransfer {
+        amount: u64,
+    },
+}
+
+impl OnchainActionPayload {
+    pub fn action(&self) -> OnchainAction {
+        match self {
+            OnchainActionPayload::CaptureMint { .. } => OnchainAction::CaptureMint,
+            OnchainActionPayload::RecordBattle { .. } => OnchainAction::RecordBattle,
+            OnchainActionPayload::AddExperience { .. } => OnchainAction::AddExperience,
+            OnchainActionPayload::BreachTransfer { .. } => OnchainAction::BreachTransfer,
+        }
+    }
+}
+
+/// Deferred action row
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct PendingOnchainAction {
+    pub id: Uuid,
+    pub player_id: Uuid,
+    pub action: OnchainAction,
+    pub payload: sqlx::types::Json<OnchainActionPayload>,
+    pub status: OnchainActionStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub tx_signature: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub processed_at: Option<DateTime<Utc>>,
+}
+
+/// Outcome of a budget check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetDecision {
+    Allowed,
+    PlayerCapExceeded,
+    GlobalCapExceeded,
+}
+
+impl BudgetDecision {
+    pub fn is_allowed(self) -> bool {
+        matches!(self, BudgetDecision::Allowed)
+    }
+
+    pub fn reason(self) -> Option<&'static str> {
+        match self {
+            BudgetDecision::Allowed => None,
+            BudgetDecision::PlayerCapExceeded => Some("player_daily_cap"),
+            BudgetDecision::GlobalCapExceeded => Some("global_hourly_cap"),
+        }
+    }
+}
+
+/// Current spend against both caps
+#[derive(Debug, Clone, Copy)]
+pub struct BudgetUsage {
+    pub player_spent: u64,
+    pub player_cap: u64,
+    pub global_spent: u64,
+    pub global_cap: u64,
+}
+
+impl BudgetUsage {
+    /// Whether an action costing `cost` fits both caps (global checked first)
+    pub fn decide(&self, cost: u64) -> BudgetDecision {
+        if self.global_spent.saturating_add(cost) > self.global_cap {
+            BudgetDecision::GlobalCapExceeded
+        } else if self.player_spent.saturating_add(cost) > self.player_cap {
+            BudgetDecision::PlayerCapExceeded
+        } else {
+            BudgetDecision::Allowed
+        }
+    }
+}
+
+/// UTC day a spend counts against (player caps reset at UTC midnight)
+pub fn budget_day(at: DateTime<Utc>) -> NaiveDate {
+    at.date_naive()
+}
+
+/// Clock hour a spend counts against for the global cap
+pub fn budget_hour(at: DateTime<Utc>) -> DateTime<Utc> {
+    at.duration_trunc(TimeDelta::hours(1)).unwrap_or(at)
+}
+
+/// Returned in place of on-chain results when an action was queued
+#[derive(Debug, Clone, Serialize)]
+pub struct DeferredOnchain {
+    pub queued_action_id: Uuid,
+    pub action: OnchainAction,
+    pub reason: &'static str,
+    pub message: &'static str,
+}
+
+impl DeferredOnchain {
+    pub const MESSAGE: &'static str = "On-chain quota exceeded, processing later";
+
+    pub fn new(queued_action_id: Uuid, action: OnchainAction, decision: BudgetDecision) -> Self {
+        Self {
+            queued_action_id,
+            action,
+            reason: decision.reason().unwrap_or("deferred"),
+            message: Self::MESSAGE,
+        }
+    }
+}
+
+/// Player spend for the admin API
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct PlayerFeeSpend {
+    pub player_id: Uuid,
+    pub wallet_address: String,
+    pub lamports: i64,
+    pub actions: i32,
+    pub deferred: i32,
+    pub daily_cap_lamports: i64,
+    pub has_override: bool,
+}
+
+/// Spend counters for metrics and the admin API
+#[derive(Debug, Clone, Serialize)]
+pub struct FeeBudgetSummary {
+    pub day: NaiveDate,
+    pub hour: DateTime<Utc>,
+    pub today_lamports: i64,
+    pub hour_lamports: i64,
+    pub hour_cap_hits: i32,
+    pub global_hourly_cap_lamports: u64,
+    pub player_daily_cap_lamports: u64,
+    /// Hours in the alert window in which the global cap was hit
+    pub capped_hours: i64,
+    pub pending_actions: i64,
+    pub top_spenders: Vec<PlayerFeeSpend>,
+}
+
+/// Set or clear a player's daily cap override
+#[derive(Debug, Deserialize)]
+pub struct SetFeeCapRequest {
+    /// `None` removes the override
+    pub daily_cap_lamports: Option<u64>,
+    pub note: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn usage(player_spent: u64, global_spent: u64) -> BudgetUsage {
+        BudgetUsage {
+            player_spent,
+            player_cap: 10_000_000,
+            global_spent,
+            global_cap: 100_000_000,
+        }
+    }
+
+    #[test]
+    fn test_estimates() {
+        assert_eq!(ATA_RENT_LAMPORTS, 2_039_280);
+        assert_eq!(OnchainAction::AddExperience.estimated_lamports(), SIGNATURE_FEE_LAMPORTS);
+        assert!(
+            OnchainAction::CaptureMint.estimated_lamports()
+                > OnchainAction::RecordBattle.estimated_lamports()
+        );
+    }
+
+    #[test]
+    fn test_player_cap_defers() {
+        let cost = OnchainAction::CaptureMint.estimated_lamports();
+
+        assert_eq!(usage(0, 0).decide(cost), BudgetDecision::Allowed);
+        // Exactly reaching the cap is allowed
+        assert_eq!(usage(10_000_000 - cost, 0).decide(cost), BudgetDecision::Allowed);
+        assert_eq!(usage(10_000_000 - cost + 1, 0).decide(cost), BudgetDecision::PlayerCapExceeded);
+
+        // Looping capture-and-release stops at the cap
+        let mut spent = 0;
+        let mut allowed = 0;
+        while usage(spent, 0).decide(cost).is_allowed() {
+            spent += cost;
+            allowed += 1;
+        }
+        assert_eq!(allowed, 10_000_000 / cost);
+        assert_eq!(BudgetDecision::PlayerCapExceeded.reason(), Some("player_daily_cap"));
+    }
+
+    #[test]
+    fn test_global_cap_defers() {
+        let cost = OnchainAction::RecordBattle.estimated_lamports();
+
+        assert_eq!(usage(0, 100_000_000 - cost).decide(cost), BudgetDecision::Allowed);
+        assert_eq!(usage(0, 100_000_000).decide(cost), BudgetDecision::GlobalCapExceeded);
+        // Global cap wins when both are exceeded
+        assert_eq!(usage(10_000_000, 100_000_000).decide(cost), BudgetDecision::GlobalCapExceeded);
+        assert_eq!(BudgetDecision::GlobalCapExceeded.reason(), Some("global_hourly_cap"));
+    }
+
+    #[test]
+    fn test_zero_cap_blocks_everything() {
+        let mut u = usage(0, 0);
+        u.player_cap = 0;
+        assert_eq!(u.decide(1), BudgetDecision::PlayerCapExceeded);
+        assert_eq!(u.decide(0), BudgetDecision::Allowed);
+    }
+
+    #[test]
+    fn test_midnight_reset_boundary() {
+        let before = Utc.with_ymd_and_hms(2026, 1, 20, 23, 59, 59).unwrap();
+        let midnight = Utc.with_ymd_and_hms(2026, 1, 21, 0, 0, 0).unwrap();
+
+        assert_eq!(budget_day(before), NaiveDate::from_ymd_opt(2026, 1, 20).unwrap());
+        assert_eq!(budget_day(midnight), NaiveDate::from_ymd_opt(2026, 1, 21).unwrap());
+        assert_eq!(budget_day(midnight - TimeDelta::nanoseconds(1)), budget_day(before));
+
+        assert_eq!(budget_hour(before), Utc.with_ymd_and_hms(2026, 1, 20, 23, 0, 0).unwrap());
+        assert_eq!(budget_hour(midnight), midnight);
+    }
+
+    #[test]
+    fn test_payload_round_trip() {
+        let payload = OnchainActionPayload::CaptureMint {
+            element: Element::Storm,
+            threat_class: 3,
+            species_id: 42,
+            genes: vec![1; 32],
+            geohash: "xn76urx".into(),
+        };
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["type"], "capture_mint");
+
+        let back: OnchainActionPayload = serde_json::from_value(json).unwrap();
+        assert_eq!(back.action(), OnchainAction::CaptureMint);
+    }
+}
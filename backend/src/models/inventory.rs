@@ -24,6 +24,16 @@ pub struct PlayerTitan {
     pub capture_location_lng: Option<f64>,
     pub battles_participated: i32,
     pub battles_won: i32,
+    pub level: i32,
+    pub experience: i64,
+    /// Active marketplace listing holding the Titan
+    pub escrowed_listing_id: Option<Uuid>,
+    /// Titan PDA owner was not the player's wallet at the last sync
+    pub onchain_owner_mismatch: bool,
+    /// Titan PDA did not exist at the last sync
+    pub onchain_missing: bool,
+    /// Player who captured the Titan, paid a royalty when it is resold
+    pub creator_id: Uuid,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -39,6 +49,28 @@ pub struct AddTitanRequest {
     pub capture_location: Option<super::LocationInput>,
 }
 
+/// Player Titan collection response
+#[derive(Debug, Serialize)]
+pub struct PlayerTitansResponse {
+    pub titans: Vec<PlayerTitan>,
+    /// Present when the collection was synced with on-chain state
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sync_report: Option<TitanSyncReport>,
+}
+
+/// Outcome of reconciling a collection with the Titan PDAs
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TitanSyncReport {
+    /// Minted Titans whose PDA was read
+    pub checked: u32,
+    /// Level or experience refreshed from chain
+    pub updated: u32,
+    /// PDA owned by another wallet
+    pub mismatched: u32,
+    /// PDA no longer exists
+    pub missing: u32,
+}
+
 /// Update Titan request
 #[derive(Debug, Deserialize)]
 pub struct UpdateTitanRequest {
@@ -88,7 +120,7 @@ impl TitanStats {
         };
 
         // Extract stats from genes (6 bytes)
-        let base_health = genes.get(0).copied().unwrap_or(100) as f64;
+        let base_health = genes.first().copied().unwrap_or(100) as f64;
         let base_attack = genes.get(1).copied().unwrap_or(100) as f64;
         let base_defense = genes.get(2).copied().unwrap_or(100) as f64;
         let base_speed = genes.get(3).copied().unwrap_or(100) as f64;
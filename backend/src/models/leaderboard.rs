@@ -1,6 +1,6 @@
 //! Leaderboard data models
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc, Weekday};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
@@ -69,3 +69,130 @@ pub struct LeaderboardResponse {
     pub my_rank: Option<i32>,
     pub my_score: Option<i64>,
 }
+
+/// Board kept in the weekly leaderboard snapshots
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "leaderboard_board", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardBoard {
+    Captures,
+    Experience,
+    PvpElo,
+    /// Guilds by `weekly_xp`, reset at each rollover
+    GuildWeeklyXp,
+}
+
+impl LeaderboardBoard {
+    pub const ALL: [LeaderboardBoard; 4] = [
+        LeaderboardBoard::Captures,
+        LeaderboardBoard::Experience,
+        LeaderboardBoard::PvpElo,
+        LeaderboardBoard::GuildWeeklyXp,
+    ];
+
+    /// Ranks guilds rather than players
+    pub fn is_guild(self) -> bool {
+        self == LeaderboardBoard::GuildWeeklyXp
+    }
+}
+
+/// One place on a snapshot board
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct LeaderboardSnapshotEntry {
+    pub rank: i32,
+    /// Player, or guild on the guild board
+    pub subject_id: Uuid,
+    pub name: Option<String>,
+    pub score: i64,
+}
+
+/// Leaderboard history query parameters
+#[derive(Debug, Deserialize)]
+pub struct LeaderboardHistoryQuery {
+    /// ISO week `YYYY-WW`; the latest snapshot when absent
+    #[serde(default)]
+    pub week: Option<String>,
+}
+
+/// A board as it stood at the end of a week
+#[derive(Debug, Serialize)]
+pub struct LeaderboardHistoryResponse {
+    pub board: LeaderboardBoard,
+    pub period: String,
+    pub taken_at: DateTime<Utc>,
+    pub entries: Vec<LeaderboardSnapshotEntry>,
+    /// The player's place that week (their guild's on the guild board)
+    pub my_rank: Option<i32>,
+    pub my_score: Option<i64>,
+}
+
+/// Snapshots taken at a weekly rollover
+#[derive(Debug, Clone)]
+pub struct WeeklySnapshot {
+    pub period: String,
+    pub boards: Vec<LeaderboardBoard>,
+}
+
+/// ISO week label `YYYY-WW` of a moment
+pub fn week_label(at: DateTime<Utc>) -> String {
+    let week = at.iso_week();
+    format!("{}-{:02}", week.year(), week.week())
+}
+
+/// Check and normalise a `YYYY-WW` week label
+pub fn parse_week_label(label: &str) -> Option<String> {
+    let (year, week) = label.split_once('-')?;
+    let date = NaiveDate::from_isoywd_opt(year.parse().ok()?, week.parse().ok()?, Weekday::Mon)?;
+    Some(format!("{}-{:02}", date.iso_week().year(), date.iso_week().week()))
+}
+
+/// Latest weekly rollover, on `weekday` at `hour` UTC, at or before `now`
+pub fn last_rollover(weekday: Weekday, hour: u32, now: DateTime<Utc>) -> DateTime<Utc> {
+    let days_back = (7 + now.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+    let date = now.date_naive() - Duration::days(days_back as i64);
+    let rollover = Utc.from_utc_datetime(&date.and_hms_opt(hour.min(23), 0, 0).unwrap());
+    if rollover > now {
+        rollover - Duration::days(7)
+    } else {
+        rollover
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> DateTime<Utc> {
+        time.parse().unwrap()
+    }
+
+    #[test]
+    fn test_last_rollover() {
+        // 2026-10-12 is a Monday
+        let monday = at("2026-10-12T00:00:00Z");
+        assert_eq!(last_rollover(Weekday::Mon, 0, monday), monday);
+        assert_eq!(last_rollover(Weekday::Mon, 0, at("2026-10-17T15:30:00Z")), monday);
+        // Before the hour on the day, the previous week's
+        assert_eq!(
+            last_rollover(Weekday::Mon, 6, at("2026-10-12T05:59:59Z")),
+            at("2026-10-05T06:00:00Z")
+        );
+        assert_eq!(
+            last_rollover(Weekday::Fri, 12, at("2026-10-12T00:00:00Z")),
+            at("2026-10-09T12:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_week_labels() {
+        assert_eq!(week_label(at("2026-10-11T23:59:59Z")), "2026-41");
+        // ISO years start on the Monday of the week with the first Thursday
+        assert_eq!(week_label(at("2027-01-01T00:00:00Z")), "2026-53");
+
+        assert_eq!(parse_week_label("2026-41").as_deref(), Some("2026-41"));
+        assert_eq!(parse_week_label("2026-7").as_deref(), Some("2026-07"));
+        assert_eq!(parse_week_label("2025-53"), None);
+        assert_eq!(parse_week_label("2026-00"), None);
+        assert_eq!(parse_week_label("last-week"), None);
+    }
+}
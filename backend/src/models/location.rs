@@ -5,6 +5,9 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
+use crate::error::ApiResult;
+use crate::utils::clock::{check_skew, SkewContext};
+
 /// Location verification status
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -35,6 +38,31 @@ impl VerificationFlag {
     }
 }
 
+/// Where a location update came from, recorded with its violations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocationSource {
+    WebSocket,
+    Http,
+}
+
+impl LocationSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LocationSource::WebSocket => "websocket",
+            LocationSource::Http => "http",
+        }
+    }
+}
+
+/// Outcome of checking a location update against the previous fix
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MovementCheck {
+    Accepted,
+    /// Faster than `max`; the violation was recorded, and `quarantined` is
+    /// set once the player has too many in the window
+    SpeedViolation { speed: f64, max: f64, quarantined: bool },
+}
+
 /// Location verification result
 #[derive(Debug, Clone, Serialize)]
 pub struct LocationVerification {
@@ -74,6 +102,13 @@ pub struct LocationReport {
     pub sensor_data: Option<SensorData>,
 }
 
+impl LocationReport {
+    /// Reject reports stamped by a badly skewed device clock
+    pub fn check_timestamp(&self) -> ApiResult<()> {
+        check_skew(SkewContext::LocationReport, self.timestamp)
+    }
+}
+
 /// Device sensor data for verification
 #[derive(Debug, Clone, Deserialize)]
 pub struct SensorData {
@@ -109,3 +144,39 @@ pub enum Punishment {
     AccountSuspension { duration_days: i64 },
     PermanentBan,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report_at(timestamp: DateTime<Utc>) -> LocationReport {
+        LocationReport {
+            lat: 35.6762,
+            lng: 139.6503,
+            accuracy: 10.0,
+            speed: None,
+            heading: None,
+            altitude: None,
+            timestamp,
+            device_id: None,
+            sensor_data: None,
+        }
+    }
+
+    #[test]
+    fn test_location_report_timestamp_skew() {
+        assert!(report_at(Utc::now()).check_timestamp().is_ok());
+
+        let err = report_at(Utc::now() + chrono::Duration::hours(1))
+            .check_timestamp()
+            .unwrap_err();
+        match err {
+            crate::error::AppError::ClockSkew { context, offset_ms, max_skew_seconds, .. } => {
+                assert_eq!(context, "location_report");
+                assert!(offset_ms > 3_500_000);
+                assert_eq!(max_skew_seconds, SkewContext::LocationReport.max_skew_seconds());
+            }
+            other => panic!("expected ClockSkew, got {:?}", other),
+        }
+    }
+}
@@ -1,7 +1,8 @@
 //! Marketplace data models
 
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::de::{DeserializeOwned, IntoDeserializer};
+use serde::{Deserialize, Deserializer, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
@@ -20,6 +21,10 @@ pub enum ListingStatus {
     Sold,
     Cancelled,
     Expired,
+    /// Auction won or offer accepted, waiting on the buyer's payment
+    AwaitingPayment,
+    /// A buyer's payment is being sent
+    Settling,
 }
 
 /// Listing type
@@ -29,6 +34,8 @@ pub enum ListingStatus {
 pub enum ListingType {
     FixedPrice,
     Auction,
+    /// Fixed-price sale of several Titans together
+    Bundle,
 }
 
 /// Transaction type
@@ -41,6 +48,18 @@ pub enum TransactionType {
     OfferAccepted,
 }
 
+/// Marketplace event in the activity feed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "marketplace_activity_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum MarketplaceActivityType {
+    ListingCreated,
+    BidPlaced,
+    /// Purchase, auction win or accepted offer
+    SaleCompleted,
+    ListingCancelled,
+}
+
 /// Offer status
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -74,6 +93,8 @@ pub struct MarketplaceListing {
     pub final_price: Option<i64>,
     pub views: i32,
     pub favorites: i32,
+    pub escrow_address: Option<String>,
+    pub listing_tx_signature: Option<String>,
 }
 
 /// Auction bid
@@ -87,6 +108,27 @@ pub struct AuctionBid {
     pub created_at: DateTime<Utc>,
 }
 
+/// Result of a bid, with the auction's end time after any anti-sniping extension
+#[derive(Debug, Serialize)]
+pub struct PlaceBidResponse {
+    /// The bidder's bid; not winning when the leader's proxy outbid it
+    #[serde(flatten)]
+    pub bid: AuctionBid,
+    /// Winning bid after any proxy raises
+    pub current_bid: i64,
+    /// The bidder's active proxy maximum, if they lead with one
+    pub max_bid: Option<i64>,
+    pub expires_at: DateTime<Utc>,
+    /// The bid landed in the final minutes and pushed `expires_at` out
+    pub extended: bool,
+    /// Previous top bidder, displaced by this bid (never the bidder themselves)
+    #[serde(skip)]
+    pub outbid_player_id: Option<Uuid>,
+    /// Proxy maximum of the outbid player, whose proxy this bid went past
+    #[serde(skip)]
+    pub exceeded_proxy_max: Option<i64>,
+}
+
 /// Marketplace transaction
 #[derive(Debug, Clone, Serialize, FromRow)]
 pub struct MarketplaceTransaction {
@@ -101,6 +143,20 @@ pub struct MarketplaceTransaction {
     pub seller_receives: i64,
     pub tx_signature: Option<String>,
     pub created_at: DateTime<Utc>,
+    /// Titan's creator, paid a royalty out of the seller's share of a resale
+    pub royalty_recipient_id: Option<Uuid>,
+    pub royalty_amount: i64,
+}
+
+/// Result of ending an auction with a winner or accepting an offer
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SaleOutcome {
+    /// The Titan changed hands
+    Completed(MarketplaceTransaction),
+    /// The Titan is held until the buyer pays through the listing's buy
+    /// endpoints, by its `expires_at`
+    AwaitingPayment(MarketplaceListing),
 }
 
 /// Price offer
@@ -118,6 +174,33 @@ pub struct PriceOffer {
     pub message: Option<String>,
 }
 
+/// Titan of a bundle listing, as selected with its listing
+#[derive(Debug, Clone, FromRow)]
+pub struct BundleTitanRow {
+    pub listing_id: Uuid,
+    pub id: Uuid,
+    pub element: Element,
+    pub threat_class: i16,
+    pub species_id: i32,
+    pub level: i32,
+    pub nickname: Option<String>,
+    pub genes: Vec<u8>,
+}
+
+impl From<BundleTitanRow> for TitanListingInfo {
+    fn from(row: BundleTitanRow) -> Self {
+        Self {
+            id: row.id,
+            element: row.element,
+            threat_class: row.threat_class,
+            species_id: Some(row.species_id),
+            level: row.level,
+            nickname: row.nickname,
+            genes: row.genes,
+        }
+    }
+}
+
 /// Listing favorite
 #[derive(Debug, Clone, Serialize, FromRow)]
 pub struct ListingFavorite {
@@ -127,14 +210,76 @@ pub struct ListingFavorite {
     pub created_at: DateTime<Utc>,
 }
 
+/// Listing joined with its seller and Titan, as selected by the listing views
+#[derive(Debug, Clone, FromRow)]
+pub struct ListingRow {
+    pub id: Uuid,
+    pub seller_id: Uuid,
+    pub seller_username: Option<String>,
+    pub titan_id: Uuid,
+    pub listing_type: ListingType,
+    pub price: i64,
+    pub min_price: Option<i64>,
+    pub status: ListingStatus,
+    pub expires_at: DateTime<Utc>,
+    pub views: i32,
+    pub favorites: i32,
+    pub created_at: DateTime<Utc>,
+    pub element: Element,
+    pub threat_class: i16,
+    pub species_id: i32,
+    pub level: i32,
+    pub nickname: Option<String>,
+    pub genes: Vec<u8>,
+    /// Highest bid, 0 when there are none
+    pub current_bid: i64,
+    pub bid_count: i32,
+    pub is_favorited: bool,
+}
+
+impl From<ListingRow> for ListingResponse {
+    fn from(row: ListingRow) -> Self {
+        Self {
+            id: row.id,
+            seller_id: row.seller_id,
+            seller_username: row.seller_username,
+            titan_id: row.titan_id,
+            titan: TitanListingInfo {
+                id: row.titan_id,
+                element: row.element,
+                threat_class: row.threat_class,
+                species_id: Some(row.species_id),
+                level: row.level,
+                nickname: row.nickname,
+                genes: row.genes,
+            },
+            bundle_titans: None,
+            listing_type: row.listing_type,
+            price: row.price,
+            min_price: row.min_price,
+            current_bid: (row.current_bid > 0).then_some(row.current_bid),
+            bid_count: row.bid_count,
+            status: row.status,
+            expires_at: row.expires_at,
+            views: row.views,
+            favorites: row.favorites,
+            is_favorited: row.is_favorited,
+            created_at: row.created_at,
+        }
+    }
+}
+
 // ============================================
 // API Request/Response Models
 // ============================================
 
-/// Create listing request
+/// Create listing request; a bundle sends `titan_ids` instead of `titan_id`
 #[derive(Debug, Deserialize)]
 pub struct CreateListingRequest {
-    pub titan_id: Uuid,
+    #[serde(default)]
+    pub titan_id: Option<Uuid>,
+    #[serde(default)]
+    pub titan_ids: Vec<Uuid>,
     pub listing_type: ListingType,
     pub price: i64,
     #[serde(default)]
@@ -155,6 +300,8 @@ pub struct ListingResponse {
     pub seller_username: Option<String>,
     pub titan_id: Uuid,
     pub titan: TitanListingInfo,
+    /// Every Titan of a bundle, `titan` first; `None` for other listings
+    pub bundle_titans: Option<Vec<TitanListingInfo>>,
     pub listing_type: ListingType,
     pub price: i64,
     pub min_price: Option<i64>,
@@ -180,10 +327,14 @@ pub struct TitanListingInfo {
     pub genes: Vec<u8>,
 }
 
-/// Place bid request
+/// Place bid request: either a bid of `amount`, or a proxy bid of up to
+/// `max_bid` that is raised automatically
 #[derive(Debug, Deserialize)]
 pub struct PlaceBidRequest {
-    pub amount: i64,
+    #[serde(default)]
+    pub amount: Option<i64>,
+    #[serde(default)]
+    pub max_bid: Option<i64>,
 }
 
 /// Bid response
@@ -245,6 +396,14 @@ pub struct MarketplaceSearchQuery {
     pub min_level: Option<i32>,
     #[serde(default)]
     pub listing_type: Option<ListingType>,
+    /// Bundles holding a Titan of each of these elements, e.g. `volcanic,storm`
+    #[serde(default, deserialize_with = "comma_separated")]
+    pub bundle_elements: Vec<Element>,
+    /// Bundles whose Titans' threat classes add up to at least this
+    #[serde(default)]
+    pub min_threat_sum: Option<i32>,
+    #[serde(default)]
+    pub max_threat_sum: Option<i32>,
     #[serde(default)]
     pub sort_by: Option<String>,  // price_asc, price_desc, newest, ending_soon
     #[serde(default = "default_limit")]
@@ -257,6 +416,20 @@ fn default_limit() -> i64 {
     20
 }
 
+/// A comma-separated query value, such as `volcanic,storm`
+fn comma_separated<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    String::deserialize(deserializer)?
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| T::deserialize(value.into_deserializer()))
+        .collect()
+}
+
 /// Search results response
 #[derive(Debug, Serialize)]
 pub struct SearchResultsResponse {
@@ -281,6 +454,42 @@ pub struct TransactionHistoryEntry {
     pub created_at: DateTime<Utc>,
 }
 
+/// Activity feed pagination query
+#[derive(Debug, Deserialize)]
+pub struct MarketplaceActivityQuery {
+    /// Continue after this entry, from a previous page's `next_cursor`
+    #[serde(default)]
+    pub after_id: Option<Uuid>,
+    #[serde(default = "default_activity_limit")]
+    pub limit: i64,
+}
+
+fn default_activity_limit() -> i64 {
+    50
+}
+
+/// Activity feed entry
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct MarketplaceActivity {
+    pub id: Uuid,
+    pub activity_type: MarketplaceActivityType,
+    pub listing_id: Uuid,
+    pub titan_id: Uuid,
+    /// Listing price, bid, sale price or price at cancellation
+    pub price: i64,
+    /// Seller, bidder or buyer, as named when it happened
+    pub player_username: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A page of activity, newest first; pass `next_cursor` as `after_id` for
+/// the older activity after it
+#[derive(Debug, Serialize)]
+pub struct MarketplaceActivityPage {
+    pub activities: Vec<MarketplaceActivity>,
+    pub next_cursor: Option<Uuid>,
+}
+
 /// Marketplace stats response
 #[derive(Debug, Serialize)]
 pub struct MarketplaceStatsResponse {
@@ -292,6 +501,38 @@ pub struct MarketplaceStatsResponse {
     pub avg_price: Option<i64>,
 }
 
+/// Floor and recent sales for one element and threat class
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct FloorPriceEntry {
+    pub element: Element,
+    pub threat_class: i16,
+    /// Cheapest active listing
+    pub floor_price: Option<i64>,
+    pub avg_price_7d: Option<i64>,
+    /// $BREACH traded in the last 7 days
+    pub volume_7d: i64,
+    pub sales_7d: i64,
+}
+
+/// Suggest listing price request
+#[derive(Debug, Deserialize)]
+pub struct SuggestPriceRequest {
+    pub titan_id: Uuid,
+}
+
+/// Price suggested for listing a Titan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuggestedPriceResponse {
+    pub titan_id: Uuid,
+    pub element: Element,
+    pub threat_class: i16,
+    /// Median of comparable sales; `None` without any
+    pub suggested_price: Option<i64>,
+    /// Sales of the same element and threat class in the last 30 days
+    pub comparable_sales: i64,
+    pub floor_price: Option<i64>,
+}
+
 /// Price history entry
 #[derive(Debug, Serialize, FromRow)]
 pub struct PriceHistoryEntry {
@@ -333,3 +574,28 @@ pub struct CompletePurchaseRequest {
     pub serialized_transaction: String,
     pub user_signature: String,
 }
+
+/// Build on-chain escrow listing request
+#[derive(Debug, Deserialize)]
+pub struct EscrowListingRequest {
+    pub titan_id: Uuid,
+    pub price: i64,
+    #[serde(default = "default_duration_hours")]
+    pub duration_hours: i64,
+}
+
+/// Submit a user-signed escrow transaction
+#[derive(Debug, Deserialize)]
+pub struct SubmitEscrowTransactionRequest {
+    pub titan_id: Uuid,
+    pub serialized_transaction: String,
+    pub user_signature: String,
+}
+
+/// Result of reconciling on-chain escrow listings into the database
+#[derive(Debug, Default, Serialize)]
+pub struct EscrowReconcileSummary {
+    pub imported: u32,
+    pub sold: u32,
+    pub cancelled: u32,
+}
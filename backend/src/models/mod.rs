@@ -2,7 +2,9 @@
 
 mod achievement;
 mod battle;
+mod capture;
 mod chat;
+mod fee_budget;
 mod inventory;
 mod leaderboard;
 mod location;
@@ -11,12 +13,18 @@ mod player;
 mod poi;
 mod pvp;
 mod quest;
+mod reward;
 mod social;
+mod spawn_event;
+mod species;
 mod titan;
+mod transaction;
 
 pub use achievement::*;
 pub use battle::*;
+pub use capture::*;
 pub use chat::*;
+pub use fee_budget::*;
 pub use inventory::*;
 pub use leaderboard::*;
 pub use location::*;
@@ -25,5 +33,9 @@ pub use player::*;
 pub use poi::*;
 pub use pvp::*;
 pub use quest::*;
+pub use reward::*;
 pub use social::*;
+pub use spawn_event::*;
+pub use species::*;
 pub use titan::*;
+pub use transaction::*;
@@ -1,6 +1,6 @@
 //! PvP data models
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
@@ -139,6 +139,9 @@ pub struct QueueEntry {
 #[derive(Debug, Deserialize)]
 pub struct JoinQueueRequest {
     pub titan_id: Uuid,
+    /// Only matched with players queued for the same format
+    #[serde(default)]
+    pub match_format: MatchFormat,
 }
 
 /// Queue status response
@@ -153,6 +156,47 @@ pub struct QueueStatusResponse {
     pub opponent_id: Option<Uuid>,
 }
 
+// ==========================================
+// CHALLENGES
+// ==========================================
+
+/// Challenge status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "pvp_challenge_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PvpChallengeStatus {
+    Pending,
+    Accepted,
+    Rejected,
+    Expired,
+}
+
+/// A player's invitation to another to play a match, bypassing the queue
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PvpChallenge {
+    pub id: Uuid,
+    pub challenger_id: Uuid,
+    pub challenger_username: Option<String>,
+    pub target_id: Uuid,
+    /// The Titan the challenger means to fight with
+    pub titan_id: Uuid,
+    pub match_format: MatchFormat,
+    pub status: PvpChallengeStatus,
+    /// Set once accepted
+    pub match_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    /// Unanswered by then, the challenge lapses without telling anyone
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Challenge request
+#[derive(Debug, Deserialize)]
+pub struct PvpChallengeRequest {
+    pub titan_id: Uuid,
+    #[serde(default)]
+    pub match_format: MatchFormat,
+}
+
 // ==========================================
 // PVP MATCHES
 // ==========================================
@@ -169,6 +213,65 @@ pub enum PvpMatchStatus {
     Abandoned,
 }
 
+/// How many rounds a match lasts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "pvp_match_format", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum MatchFormat {
+    /// The first KO ends the match
+    #[default]
+    Single,
+    /// Best of three rounds
+    Bo3,
+}
+
+impl MatchFormat {
+    /// Round wins that take the match
+    pub fn rounds_to_win(self) -> i32 {
+        match self {
+            MatchFormat::Single => 1,
+            MatchFormat::Bo3 => 2,
+        }
+    }
+}
+
+/// Where a match's series stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromRow)]
+pub struct MatchRounds {
+    pub match_format: MatchFormat,
+    /// The round being played, from 1
+    pub round_number: i32,
+    pub player1_rounds_won: i32,
+    pub player2_rounds_won: i32,
+}
+
+impl MatchRounds {
+    /// Count a KO win for one side; true when it takes the match, otherwise
+    /// the next round starts
+    pub fn record_round_win(&mut self, player1_won: bool) -> bool {
+        let won = if player1_won {
+            &mut self.player1_rounds_won
+        } else {
+            &mut self.player2_rounds_won
+        };
+        *won += 1;
+        let decided = *won >= self.match_format.rounds_to_win();
+        if !decided {
+            self.round_number += 1;
+        }
+        decided
+    }
+
+    /// (mine, opponent's) round wins
+    pub fn rounds_won(&self, is_player1: bool) -> (i32, i32) {
+        if is_player1 {
+            (self.player1_rounds_won, self.player2_rounds_won)
+        } else {
+            (self.player2_rounds_won, self.player1_rounds_won)
+        }
+    }
+}
+
 /// PvP Match
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct PvpMatch {
@@ -197,6 +300,99 @@ pub struct PvpMatch {
     pub started_at: Option<DateTime<Utc>>,
     pub ended_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    /// Turns each player has let run out in a row
+    pub player1_consecutive_skips: i32,
+    pub player2_consecutive_skips: i32,
+}
+
+impl PvpMatch {
+    /// Missed turns in a row for one of the match's players
+    pub fn consecutive_skips(&self, player_id: Uuid) -> i32 {
+        if player_id == self.player1_id {
+            self.player1_consecutive_skips
+        } else {
+            self.player2_consecutive_skips
+        }
+    }
+}
+
+/// A turn that ran out, as resolved by the timeout task
+#[derive(Debug, Clone)]
+pub struct TurnTimeout {
+    pub match_id: Uuid,
+    /// Player whose turn ran out
+    pub player_id: Uuid,
+    pub opponent_id: Uuid,
+    pub consecutive_skips: i32,
+    /// The skip limit was reached and the opponent won
+    pub forfeited: bool,
+}
+
+/// What the timeout task does with a turn that ran out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TurnTimeoutAction {
+    /// The player is disconnected and still has time to come back: the
+    /// turn waits for them
+    Hold,
+    /// Defend on the player's behalf, forfeiting once they have let `skips`
+    /// turns in a row run out
+    Defend { skips: i32, forfeit: bool },
+    /// The player never came back from a disconnect
+    ForfeitDisconnected,
+}
+
+impl TurnTimeoutAction {
+    /// Decide for a player with `skips` missed turns in a row so far, who
+    /// dropped at `disconnected_at` if they are disconnected
+    pub fn decide(
+        disconnected_at: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+        reconnect_grace: Duration,
+        skips: i32,
+        max_skips: i32,
+    ) -> Self {
+        match disconnected_at {
+            Some(at) if now - at < reconnect_grace => Self::Hold,
+            Some(_) => Self::ForfeitDisconnected,
+            None => Self::Defend {
+                skips: skips + 1,
+                forfeit: skips + 1 >= max_skips,
+            },
+        }
+    }
+}
+
+/// A match matchmaking just created, for telling both players
+#[derive(Debug, Clone)]
+pub struct FoundMatch {
+    pub match_id: Uuid,
+    /// Both players must pick a Titan by then or the match is cancelled
+    pub ready_deadline: DateTime<Utc>,
+    pub players: [MatchedPlayer; 2],
+}
+
+/// One side of a found match
+#[derive(Debug, Clone)]
+pub struct MatchedPlayer {
+    pub player_id: Uuid,
+    pub username: Option<String>,
+    pub elo: i32,
+}
+
+impl FoundMatch {
+    /// Each player with their opponent
+    pub fn pairings(&self) -> [(&MatchedPlayer, &MatchedPlayer); 2] {
+        let [first, second] = &self.players;
+        [(first, second), (second, first)]
+    }
+}
+
+/// A match cancelled because a player hadn't picked a Titan by its
+/// ready deadline
+#[derive(Debug, Clone)]
+pub struct CancelledMatch {
+    pub match_id: Uuid,
+    pub player_ids: [Uuid; 2],
 }
 
 /// Match state for client
@@ -212,12 +408,49 @@ pub struct MatchStateResponse {
     pub is_my_turn: bool,
     pub turn_number: i32,
     pub turn_deadline: Option<DateTime<Utc>>,
+    /// Turns missed in a row; reaching the server's limit forfeits the match
+    pub my_consecutive_skips: i32,
+    pub opponent_consecutive_skips: i32,
     pub my_titan: Option<TitanBattleInfo>,
     pub opponent_titan: Option<TitanBattleInfo>,
+    pub match_format: MatchFormat,
+    pub round_number: i32,
+    pub my_rounds_won: i32,
+    pub opponent_rounds_won: i32,
+}
+
+/// Match state pushed to spectators, from neither player's side
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpectatorMatchState {
+    pub match_id: Uuid,
+    pub status: PvpMatchStatus,
+    pub turn_number: i32,
+    pub current_turn: Option<Uuid>,
+    pub turn_deadline: Option<DateTime<Utc>>,
+    pub winner_id: Option<Uuid>,
+    pub player1: SpectatorSide,
+    pub player2: SpectatorSide,
+}
+
+impl SpectatorMatchState {
+    /// No further updates follow
+    pub fn is_over(&self) -> bool {
+        matches!(self.status, PvpMatchStatus::Completed | PvpMatchStatus::Abandoned)
+    }
+}
+
+/// One player in a spectated match
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpectatorSide {
+    pub player_id: Uuid,
+    pub username: Option<String>,
+    pub elo: i32,
+    pub hp: i32,
+    pub titan: Option<TitanBattleInfo>,
 }
 
 /// Titan info for battle
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TitanBattleInfo {
     pub id: Uuid,
     pub species_id: i32,
@@ -228,7 +461,7 @@ pub struct TitanBattleInfo {
 }
 
 /// Computed battle stats
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TitanBattleStats {
     pub max_hp: i32,
     pub attack: i32,
@@ -242,14 +475,41 @@ pub struct TitanBattleStats {
 // ==========================================
 
 /// PvP action type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PvpActionType {
+    Attack,
+    Special,
+    Defend,
+    Item,
+    /// Bring in another of the player's Titans; costs the turn
+    Switch { titan_id: Uuid },
+}
+
+impl PvpActionType {
+    /// Kind stored in `pvp_battle_turns`
+    pub fn kind(&self) -> PvpActionKind {
+        match self {
+            PvpActionType::Attack => PvpActionKind::Attack,
+            PvpActionType::Special => PvpActionKind::Special,
+            PvpActionType::Defend => PvpActionKind::Defend,
+            PvpActionType::Item => PvpActionKind::Item,
+            PvpActionType::Switch { .. } => PvpActionKind::Switch,
+        }
+    }
+}
+
+/// Battle turn action as stored (`pvp_action_type`); a switch's Titan is in
+/// `switched_titan_id`
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "pvp_action_type", rename_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
-pub enum PvpActionType {
+pub enum PvpActionKind {
     Attack,
     Special,
     Defend,
     Item,
+    Switch,
 }
 
 /// Battle turn record
@@ -258,12 +518,13 @@ pub struct PvpBattleTurn {
     pub id: Uuid,
     pub match_id: Uuid,
     pub turn_number: i32,
-    pub player1_action: Option<PvpActionType>,
+    pub player1_action: Option<PvpActionKind>,
     pub player1_damage: Option<i32>,
-    pub player2_action: Option<PvpActionType>,
+    pub player2_action: Option<PvpActionKind>,
     pub player2_damage: Option<i32>,
     pub player1_hp_after: Option<i32>,
     pub player2_hp_after: Option<i32>,
+    pub switched_titan_id: Option<Uuid>,
     pub submitted_at: DateTime<Utc>,
 }
 
@@ -280,11 +541,31 @@ pub struct ActionResultResponse {
     pub success: bool,
     pub my_action: PvpActionType,
     pub my_damage: i32,
+    /// Element multiplier applied to `my_damage` (2.0 super effective, 0.5
+    /// resisted, or per `game.element_advantage_table`); 1.0 for actions
+    /// that deal no damage
+    pub effectiveness: f64,
+    /// The same element multiplier, or None when the action was not an
+    /// attack or special
+    pub element_advantage: Option<f64>,
+    /// `my_damage` was a critical hit (x1.5); more likely the faster the
+    /// attacking Titan is than the defender
+    pub critical: bool,
+    /// The opponent was defending, so `my_damage` was halved (and their
+    /// defend is used up)
+    pub opponent_defended: bool,
     pub opponent_action: Option<PvpActionType>,
     pub opponent_damage: Option<i32>,
     pub my_hp_after: i32,
     pub opponent_hp_after: i32,
     pub turn_complete: bool,
+    /// A KO ended the round; with `match_ended` false both sides are healed
+    /// and `round_number` has started
+    pub round_ended: bool,
+    pub round_winner_id: Option<Uuid>,
+    pub round_number: i32,
+    pub my_rounds_won: i32,
+    pub opponent_rounds_won: i32,
     pub match_ended: bool,
     pub winner_id: Option<Uuid>,
 }
@@ -326,3 +607,55 @@ pub struct MatchHistoryEntry {
     pub duration_seconds: Option<f64>,
     pub ended_at: Option<DateTime<Utc>>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_turn_timeout_action() {
+        let now = Utc::now();
+        let grace = Duration::seconds(60);
+        let decide = |disconnected_at, skips| TurnTimeoutAction::decide(disconnected_at, now, grace, skips, 3);
+
+        assert_eq!(decide(None, 0), TurnTimeoutAction::Defend { skips: 1, forfeit: false });
+        assert_eq!(decide(None, 1), TurnTimeoutAction::Defend { skips: 2, forfeit: false });
+        assert_eq!(decide(None, 2), TurnTimeoutAction::Defend { skips: 3, forfeit: true });
+        // A disconnect holds the turn instead of defending it, even at the limit
+        assert_eq!(decide(Some(now - Duration::seconds(10)), 2), TurnTimeoutAction::Hold);
+        assert_eq!(
+            decide(Some(now - Duration::seconds(60)), 0),
+            TurnTimeoutAction::ForfeitDisconnected
+        );
+    }
+
+    fn rounds(match_format: MatchFormat) -> MatchRounds {
+        MatchRounds {
+            match_format,
+            round_number: 1,
+            player1_rounds_won: 0,
+            player2_rounds_won: 0,
+        }
+    }
+
+    #[test]
+    fn test_single_ends_on_first_ko() {
+        let mut single = rounds(MatchFormat::Single);
+        assert!(single.record_round_win(false));
+        assert_eq!(single.rounds_won(false), (1, 0));
+        assert_eq!(single.round_number, 1);
+    }
+
+    #[test]
+    fn test_bo3_takes_two_round_wins() {
+        let mut series = rounds(MatchFormat::Bo3);
+        assert!(!series.record_round_win(true));
+        assert_eq!(series.round_number, 2);
+        assert!(!series.record_round_win(false));
+        assert_eq!(series.round_number, 3);
+        assert_eq!(series.rounds_won(true), (1, 1));
+        assert!(series.record_round_win(true));
+        assert_eq!(series.rounds_won(true), (2, 1));
+        assert_eq!(series.round_number, 3);
+    }
+}
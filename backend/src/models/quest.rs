@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
-use super::Element;
+use super::{BattleType, Element};
 
 /// Quest types enum
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
@@ -21,6 +21,37 @@ pub enum QuestType {
     Streak,
 }
 
+/// Lowest threat class a `capture_rare` quest counts ("Class III+")
+pub const RARE_QUEST_MIN_THREAT_CLASS: i16 = 3;
+
+/// Something a player did that can advance their daily quests
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuestEvent {
+    TitanCaptured { element: Element, threat_class: i16 },
+    BattleWon { battle_type: BattleType },
+    PvpMatchWon,
+    DistanceWalked { meters: i32 },
+}
+
+impl QuestEvent {
+    /// Progress the event adds to a quest of `quest_type` (restricted to
+    /// `element` when set); 0 when it doesn't count toward it
+    pub fn progress_for(&self, quest_type: QuestType, element: Option<Element>) -> i32 {
+        match (*self, quest_type) {
+            (QuestEvent::TitanCaptured { .. }, QuestType::Capture) => 1,
+            (QuestEvent::TitanCaptured { element: captured, .. }, QuestType::CaptureElement) => {
+                i32::from(element.is_none_or(|wanted| wanted == captured))
+            }
+            (QuestEvent::TitanCaptured { threat_class, .. }, QuestType::CaptureRare) => {
+                i32::from(threat_class >= RARE_QUEST_MIN_THREAT_CLASS)
+            }
+            (QuestEvent::BattleWon { .. } | QuestEvent::PvpMatchWon, QuestType::Battle) => 1,
+            (QuestEvent::DistanceWalked { meters }, QuestType::Walk) => meters.max(0),
+            _ => 0,
+        }
+    }
+}
+
 /// Quest template definition
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct QuestTemplate {
@@ -79,3 +110,44 @@ pub struct QuestRewardResponse {
     pub new_total_xp: i64,
     pub new_level: i32,
 }
+
+/// A quest advanced by a `QuestEvent`, pushed to the player as it happens
+#[derive(Debug, Clone, Serialize)]
+pub struct QuestUpdate {
+    pub player_id: Uuid,
+    pub quest_id: Uuid,
+    pub title: String,
+    pub progress: i32,
+    pub target_count: i32,
+    /// Finished by this event; the rewards below were paid with it
+    pub completed: bool,
+    pub xp_reward: i32,
+    pub breach_reward: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_progress() {
+        let captured = QuestEvent::TitanCaptured { element: Element::Storm, threat_class: 2 };
+        assert_eq!(captured.progress_for(QuestType::Capture, None), 1);
+        assert_eq!(captured.progress_for(QuestType::CaptureElement, Some(Element::Storm)), 1);
+        assert_eq!(captured.progress_for(QuestType::CaptureElement, Some(Element::Void)), 0);
+        assert_eq!(captured.progress_for(QuestType::CaptureRare, None), 0);
+        assert_eq!(captured.progress_for(QuestType::Battle, None), 0);
+
+        let rare = QuestEvent::TitanCaptured { element: Element::Void, threat_class: 3 };
+        assert_eq!(rare.progress_for(QuestType::CaptureRare, None), 1);
+
+        let won = QuestEvent::BattleWon { battle_type: BattleType::Wild };
+        assert_eq!(won.progress_for(QuestType::Battle, None), 1);
+        assert_eq!(QuestEvent::PvpMatchWon.progress_for(QuestType::Battle, None), 1);
+        assert_eq!(won.progress_for(QuestType::Capture, None), 0);
+
+        let walked = QuestEvent::DistanceWalked { meters: 250 };
+        assert_eq!(walked.progress_for(QuestType::Walk, None), 250);
+        assert_eq!(walked.progress_for(QuestType::VisitPoi, None), 0);
+    }
+}
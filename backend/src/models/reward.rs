@@ -0,0 +1,86 @@
+//! Failed reward models
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Wait before the first retry of a failed reward; doubles with each attempt
+pub const REWARD_RETRY_BASE_SECS: i64 = 60;
+
+/// Longest wait between retries
+pub const REWARD_RETRY_MAX_SECS: i64 = 6 * 3600;
+
+/// What earned a reward
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "reward_reason", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RewardReason {
+    Capture,
+    Battle,
+    /// Creator's cut of a marketplace resale
+    Royalty,
+    /// Final rank in a PvP season
+    PvpSeason,
+}
+
+/// Where a failed reward stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "failed_reward_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum FailedRewardStatus {
+    /// Waiting for its next retry
+    Pending,
+    Paid,
+    /// Out of attempts, left for manual review
+    Failed,
+}
+
+/// A $BREACH reward whose transfer failed
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct FailedReward {
+    pub id: Uuid,
+    pub player_id: Uuid,
+    pub amount: i64,
+    pub reason: RewardReason,
+    pub status: FailedRewardStatus,
+    /// Transfers tried so far, the original one included
+    pub attempts: i32,
+    #[serde(skip)]
+    pub last_error: Option<String>,
+    pub next_retry_at: DateTime<Utc>,
+    pub tx_signature: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub paid_at: Option<DateTime<Utc>>,
+}
+
+/// Rewards still owed to the player
+#[derive(Debug, Serialize)]
+pub struct PendingRewardsResponse {
+    pub rewards: Vec<FailedReward>,
+    /// Sum of the rewards' amounts
+    pub total_owed: i64,
+}
+
+/// Wait before retrying a reward that has failed `attempts` times:
+/// `REWARD_RETRY_BASE_SECS` doubled per attempt after the first, capped at
+/// `REWARD_RETRY_MAX_SECS`
+pub fn reward_retry_delay(attempts: i32) -> TimeDelta {
+    let doublings = (attempts.max(1) - 1).min(20) as u32;
+    TimeDelta::seconds((REWARD_RETRY_BASE_SECS << doublings).min(REWARD_RETRY_MAX_SECS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reward_retry_delay() {
+        assert_eq!(reward_retry_delay(1), TimeDelta::minutes(1));
+        assert_eq!(reward_retry_delay(2), TimeDelta::minutes(2));
+        assert_eq!(reward_retry_delay(4), TimeDelta::minutes(8));
+        // Capped, however many attempts
+        assert_eq!(reward_retry_delay(10), TimeDelta::hours(6));
+        assert_eq!(reward_retry_delay(i32::MAX), TimeDelta::hours(6));
+    }
+}
@@ -52,6 +52,8 @@ pub struct FriendInfo {
     pub is_online: bool,
     pub last_active_at: Option<DateTime<Utc>>,
     pub friendship_date: DateTime<Utc>,
+    /// Whether you share your location with this friend
+    pub share_location: bool,
 }
 
 /// Friend request with sender info
@@ -73,6 +75,29 @@ pub struct SendFriendRequest {
     pub message: Option<String>,
 }
 
+/// Update friend settings input
+#[derive(Debug, Deserialize)]
+pub struct UpdateFriendSettings {
+    pub share_location: bool,
+}
+
+/// Your settings for one friend
+#[derive(Debug, Clone, Serialize)]
+pub struct FriendSettings {
+    pub friend_id: Uuid,
+    pub share_location: bool,
+}
+
+/// A friend's coarse last known location, never their coordinates
+#[derive(Debug, Clone, Serialize)]
+pub struct FriendLocation {
+    pub player_id: Uuid,
+    pub username: Option<String>,
+    /// 5-character geohash (a cell of about 5 km)
+    pub geohash: String,
+    pub last_seen: DateTime<Utc>,
+}
+
 /// Friend gift
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct FriendGift {
@@ -124,6 +149,24 @@ impl GuildRole {
             GuildRole::Member => false,
         }
     }
+
+    /// Next rank down, `None` for members and the leader
+    pub fn demoted(&self) -> Option<GuildRole> {
+        match self {
+            GuildRole::CoLeader => Some(GuildRole::Elder),
+            GuildRole::Elder => Some(GuildRole::Member),
+            GuildRole::Leader | GuildRole::Member => None,
+        }
+    }
+}
+
+/// What happens on a second consecutive missed week
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "guild_inactivity_policy", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum InactivityPolicy {
+    Demote,
+    Kick,
 }
 
 /// Guild
@@ -147,6 +190,22 @@ pub struct Guild {
     pub season_points: i64,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub activity_min_weekly_xp: Option<i64>,
+    pub activity_min_weekly_captures: Option<i32>,
+    pub inactivity_policy: InactivityPolicy,
+    pub auto_kick_enabled: bool,
+    pub activity_week_started_at: Option<DateTime<Utc>>,
+}
+
+impl Guild {
+    pub fn activity_rules(&self) -> GuildActivityRules {
+        GuildActivityRules {
+            min_weekly_xp: self.activity_min_weekly_xp,
+            min_weekly_captures: self.activity_min_weekly_captures,
+            policy: self.inactivity_policy,
+            auto_kick_enabled: self.auto_kick_enabled,
+        }
+    }
 }
 
 /// Guild summary (for listings)
@@ -178,6 +237,9 @@ pub struct GuildMember {
     pub contribution_battles: i32,
     pub joined_at: DateTime<Utc>,
     pub last_active_at: DateTime<Utc>,
+    pub week_start_xp: i64,
+    pub week_start_captures: i32,
+    pub missed_weeks: i32,
 }
 
 /// Guild member with profile
@@ -192,6 +254,13 @@ pub struct GuildMemberInfo {
     pub is_online: bool,
     pub joined_at: DateTime<Utc>,
     pub last_active_at: DateTime<Utc>,
+    /// Progress in the current activity week
+    pub weekly_xp: i64,
+    pub weekly_captures: i32,
+    pub missed_weeks: i32,
+    pub on_vacation: bool,
+    #[sqlx(skip)]
+    pub standing: MemberStanding,
 }
 
 /// Create guild input
@@ -212,6 +281,208 @@ pub struct UpdateGuildRequest {
     pub banner: Option<String>,
     pub min_level: Option<i32>,
     pub is_public: Option<bool>,
+    /// Leader only; 0 disables the requirement
+    pub min_weekly_xp: Option<i64>,
+    /// Leader only; 0 disables the requirement
+    pub min_weekly_captures: Option<i32>,
+    /// Leader only
+    pub inactivity_policy: Option<InactivityPolicy>,
+    /// Leader only; required for the `kick` policy
+    pub auto_kick_enabled: Option<bool>,
+}
+
+/// Weekly activity rules for a guild
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GuildActivityRules {
+    pub min_weekly_xp: Option<i64>,
+    pub min_weekly_captures: Option<i32>,
+    pub policy: InactivityPolicy,
+    pub auto_kick_enabled: bool,
+}
+
+/// Outcome of evaluating one member for a finished week
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivityOutcome {
+    /// Not subject to requirements (leader, vacation, requirements off)
+    Exempt,
+    /// Requirement met
+    Met,
+    /// First miss, warning with a one-week grace period
+    Warn,
+    /// Second consecutive miss, demoted to the given role
+    Demote(GuildRole),
+    /// Second consecutive miss, removed from the guild
+    Kick,
+    /// Second consecutive miss but already at the lowest rank
+    Flagged,
+}
+
+impl ActivityOutcome {
+    /// Consecutive missed weeks after this outcome
+    pub fn missed_weeks(&self, previous: i32) -> i32 {
+        match self {
+            ActivityOutcome::Exempt | ActivityOutcome::Met => 0,
+            ActivityOutcome::Demote(_) | ActivityOutcome::Kick => 0,
+            ActivityOutcome::Warn | ActivityOutcome::Flagged => previous + 1,
+        }
+    }
+}
+
+/// Member standing against the guild's activity requirements
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemberStanding {
+    #[default]
+    NotTracked,
+    OnVacation,
+    OnTrack,
+    AtRisk,
+    Warned,
+}
+
+impl GuildActivityRules {
+    /// Bounds for leader-configured requirements
+    pub const MAX_WEEKLY_XP: i64 = 20_000;
+    pub const MAX_WEEKLY_CAPTURES: i32 = 50;
+
+    pub fn is_enabled(&self) -> bool {
+        self.min_weekly_xp.is_some() || self.min_weekly_captures.is_some()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(xp) = self.min_weekly_xp {
+            if !(1..=Self::MAX_WEEKLY_XP).contains(&xp) {
+                return Err(format!("Weekly XP requirement must be 1-{}", Self::MAX_WEEKLY_XP));
+            }
+        }
+        if let Some(captures) = self.min_weekly_captures {
+            if !(1..=Self::MAX_WEEKLY_CAPTURES).contains(&captures) {
+                return Err(format!("Weekly capture requirement must be 1-{}", Self::MAX_WEEKLY_CAPTURES));
+            }
+        }
+        if self.policy == InactivityPolicy::Kick && !self.auto_kick_enabled {
+            return Err("Kick policy requires auto-kick to be enabled".into());
+        }
+        Ok(())
+    }
+
+    /// Every configured requirement must be met
+    pub fn is_met(&self, weekly_xp: i64, weekly_captures: i32) -> bool {
+        self.min_weekly_xp.is_none_or(|min| weekly_xp >= min)
+            && self.min_weekly_captures.is_none_or(|min| weekly_captures >= min)
+    }
+
+    /// Evaluate a member at the end of a week
+    pub fn evaluate(
+        &self,
+        role: GuildRole,
+        weekly_xp: i64,
+        weekly_captures: i32,
+        missed_weeks: i32,
+        on_vacation: bool,
+    ) -> ActivityOutcome {
+        if !self.is_enabled() || role == GuildRole::Leader || on_vacation {
+            return ActivityOutcome::Exempt;
+        }
+        if self.is_met(weekly_xp, weekly_captures) {
+            return ActivityOutcome::Met;
+        }
+        if missed_weeks == 0 {
+            return ActivityOutcome::Warn;
+        }
+
+        if self.policy == InactivityPolicy::Kick && self.auto_kick_enabled {
+            return ActivityOutcome::Kick;
+        }
+        match role.demoted() {
+            Some(new_role) => ActivityOutcome::Demote(new_role),
+            None => ActivityOutcome::Flagged,
+        }
+    }
+
+    /// Standing of a member partway through the current week
+    pub fn standing(
+        &self,
+        role: GuildRole,
+        weekly_xp: i64,
+        weekly_captures: i32,
+        missed_weeks: i32,
+        on_vacation: bool,
+    ) -> MemberStanding {
+        if !self.is_enabled() || role == GuildRole::Leader {
+            MemberStanding::NotTracked
+        } else if on_vacation {
+            MemberStanding::OnVacation
+        } else if self.is_met(weekly_xp, weekly_captures) {
+            MemberStanding::OnTrack
+        } else if missed_weeks > 0 {
+            MemberStanding::Warned
+        } else {
+            MemberStanding::AtRisk
+        }
+    }
+}
+
+/// Guild vacation (exempt from activity requirements)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct GuildVacation {
+    pub id: Uuid,
+    pub player_id: Uuid,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl GuildVacation {
+    /// Yearly vacation allowance
+    pub const MAX_WEEKS_PER_YEAR: i64 = 4;
+
+    /// Whether `requested_weeks` fits in the allowance given seconds already used this year
+    pub fn fits_allowance(used_seconds: i64, requested_weeks: i64) -> bool {
+        requested_weeks > 0
+            && used_seconds + requested_weeks * 7 * 86_400 <= Self::MAX_WEEKS_PER_YEAR * 7 * 86_400
+    }
+}
+
+/// Start vacation input
+#[derive(Debug, Deserialize)]
+pub struct StartVacationRequest {
+    pub weeks: i64,
+}
+
+/// Guild treasury ledger entry
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct GuildTreasuryEntry {
+    pub id: i64,
+    pub player_id: Option<Uuid>,
+    /// deposit, marketplace_fee, war_bonus or withdrawal
+    pub kind: String,
+    pub amount: i64,
+    pub balance_after: i64,
+    pub reason: Option<String>,
+    pub marketplace_transaction_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Guild treasury balance with recent ledger entries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildTreasury {
+    pub guild_id: Uuid,
+    pub balance: i64,
+    pub entries: Vec<GuildTreasuryEntry>,
+}
+
+/// Treasury deposit input
+#[derive(Debug, Deserialize)]
+pub struct TreasuryDepositRequest {
+    pub amount: i64,
+}
+
+/// Treasury withdrawal input
+#[derive(Debug, Deserialize)]
+pub struct TreasuryWithdrawRequest {
+    pub amount: i64,
+    pub reason: String,
 }
 
 /// Guild join request
@@ -239,6 +510,117 @@ pub struct GuildRequestWithPlayer {
     pub created_at: DateTime<Utc>,
 }
 
+/// Guild war status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "guild_war_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum GuildWarStatus {
+    /// Waiting for the challenged guild to accept
+    Pending,
+    Active,
+    Completed,
+    /// Not accepted in time
+    Expired,
+}
+
+/// Guild war: a capture race between two guilds. A side whose guild
+/// disbanded has no ID; its name is kept.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct GuildWar {
+    pub id: Uuid,
+    pub challenger_guild_id: Option<Uuid>,
+    pub defender_guild_id: Option<Uuid>,
+    pub challenger_name: String,
+    pub defender_name: String,
+    pub status: GuildWarStatus,
+    pub duration_hours: i32,
+    pub challenger_score: i32,
+    pub defender_score: i32,
+    /// Set once completed; `None` for a tie
+    pub winner_guild_id: Option<Uuid>,
+    pub challenged_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+impl GuildWar {
+    /// Season points for winning a war
+    pub const WIN_SEASON_POINTS: i64 = 100;
+
+    /// Season points for each side of a tie
+    pub const TIE_SEASON_POINTS: i64 = 50;
+
+    /// $BREACH paid into the winner's treasury
+    pub const WIN_TREASURY_BONUS: i64 = 500;
+
+    /// Hours a challenge can wait for an answer
+    pub const CHALLENGE_TTL_HOURS: i64 = 24;
+
+    /// Guilds still standing on either side
+    pub fn guild_ids(&self) -> impl Iterator<Item = Uuid> {
+        self.challenger_guild_id.into_iter().chain(self.defender_guild_id)
+    }
+
+    /// The winning guild: the higher score, or the only guild left if the
+    /// other disbanded; `None` for a tie
+    pub fn winner(&self) -> Option<Uuid> {
+        match (self.challenger_guild_id, self.defender_guild_id) {
+            (Some(challenger), Some(defender)) => match self.challenger_score.cmp(&self.defender_score) {
+                std::cmp::Ordering::Greater => Some(challenger),
+                std::cmp::Ordering::Less => Some(defender),
+                std::cmp::Ordering::Equal => None,
+            },
+            (remaining, None) | (None, remaining) => remaining,
+        }
+    }
+}
+
+/// A member's captures for their guild in a war
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct GuildWarScoreEntry {
+    pub player_id: Uuid,
+    pub username: Option<String>,
+    pub guild_id: Uuid,
+    pub captures: i32,
+}
+
+/// A war with each member's captures, highest first
+#[derive(Debug, Clone, Serialize)]
+pub struct GuildWarDetails {
+    #[serde(flatten)]
+    pub war: GuildWar,
+    pub scores: Vec<GuildWarScoreEntry>,
+}
+
+/// War challenge input
+#[derive(Debug, Deserialize)]
+pub struct GuildWarChallengeRequest {
+    #[serde(default = "default_war_hours")]
+    pub duration_hours: i32,
+}
+
+fn default_war_hours() -> i32 {
+    24
+}
+
+/// What happened to a war
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuildWarEvent {
+    Started,
+    Scored,
+    Ended,
+}
+
+/// A war event for the members of both guilds
+#[derive(Debug, Clone)]
+pub struct GuildWarUpdate {
+    pub event: GuildWarEvent,
+    pub war: GuildWar,
+    pub recipients: Vec<Uuid>,
+}
+
 // ==========================================
 // NOTIFICATIONS
 // ==========================================
@@ -257,10 +639,14 @@ pub enum NotificationType {
     GuildPromoted,
     GuildDemoted,
     GuildKicked,
+    GuildInactivityWarning,
     AchievementUnlocked,
     LevelUp,
     RareCapture,
     System,
+    Outbid,
+    PvpMatchFound,
+    GuildWar,
 }
 
 /// Notification
@@ -275,6 +661,8 @@ pub struct Notification {
     pub is_read: bool,
     pub created_at: DateTime<Utc>,
     pub expires_at: Option<DateTime<Utc>>,
+    /// When the player marked it read
+    pub read_at: Option<DateTime<Utc>>,
 }
 
 /// Notification count response
@@ -283,3 +671,185 @@ pub struct NotificationCount {
     pub total: i64,
     pub unread: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn war(challenger: Option<Uuid>, defender: Option<Uuid>, scores: (i32, i32)) -> GuildWar {
+        GuildWar {
+            id: Uuid::new_v4(),
+            challenger_guild_id: challenger,
+            defender_guild_id: defender,
+            challenger_name: "Alpha".into(),
+            defender_name: "Bravo".into(),
+            status: GuildWarStatus::Active,
+            duration_hours: 24,
+            challenger_score: scores.0,
+            defender_score: scores.1,
+            winner_guild_id: None,
+            challenged_by: None,
+            created_at: Utc::now(),
+            starts_at: None,
+            ends_at: None,
+            ended_at: None,
+        }
+    }
+
+    #[test]
+    fn test_war_winner() {
+        let (alpha, bravo) = (Uuid::new_v4(), Uuid::new_v4());
+
+        assert_eq!(war(Some(alpha), Some(bravo), (5, 3)).winner(), Some(alpha));
+        assert_eq!(war(Some(alpha), Some(bravo), (2, 3)).winner(), Some(bravo));
+        assert_eq!(war(Some(alpha), Some(bravo), (4, 4)).winner(), None);
+
+        // A guild that disbanded forfeits, whatever the score
+        assert_eq!(war(None, Some(bravo), (9, 0)).winner(), Some(bravo));
+        assert_eq!(war(Some(alpha), None, (0, 9)).winner(), Some(alpha));
+        assert_eq!(war(None, None, (1, 0)).winner(), None);
+        assert_eq!(war(Some(alpha), None, (0, 0)).guild_ids().collect::<Vec<_>>(), vec![alpha]);
+    }
+
+    fn rules(policy: InactivityPolicy, auto_kick_enabled: bool) -> GuildActivityRules {
+        GuildActivityRules {
+            min_weekly_xp: Some(500),
+            min_weekly_captures: None,
+            policy,
+            auto_kick_enabled,
+        }
+    }
+
+    // ========================================
+    // Grace Period Tests
+    // ========================================
+
+    #[test]
+    fn test_first_miss_warns() {
+        let rules = rules(InactivityPolicy::Demote, false);
+        let outcome = rules.evaluate(GuildRole::Elder, 100, 0, 0, false);
+
+        assert_eq!(outcome, ActivityOutcome::Warn);
+        assert_eq!(outcome.missed_weeks(0), 1);
+    }
+
+    #[test]
+    fn test_grace_week_met_resets() {
+        let rules = rules(InactivityPolicy::Demote, false);
+
+        let week1 = rules.evaluate(GuildRole::Elder, 100, 0, 0, false);
+        let missed = week1.missed_weeks(0);
+        let week2 = rules.evaluate(GuildRole::Elder, 600, 0, missed, false);
+
+        assert_eq!(week2, ActivityOutcome::Met);
+        assert_eq!(week2.missed_weeks(missed), 0);
+    }
+
+    #[test]
+    fn test_second_consecutive_miss_acts() {
+        let rules = rules(InactivityPolicy::Demote, false);
+
+        let week1 = rules.evaluate(GuildRole::Elder, 0, 0, 0, false);
+        let missed = week1.missed_weeks(0);
+        let week2 = rules.evaluate(GuildRole::Elder, 0, 0, missed, false);
+
+        assert_eq!(week2, ActivityOutcome::Demote(GuildRole::Member));
+        assert_eq!(week2.missed_weeks(missed), 0);
+    }
+
+    // ========================================
+    // Vacation Tests
+    // ========================================
+
+    #[test]
+    fn test_vacation_exempt() {
+        let rules = rules(InactivityPolicy::Kick, true);
+
+        let outcome = rules.evaluate(GuildRole::Member, 0, 0, 1, true);
+        assert_eq!(outcome, ActivityOutcome::Exempt);
+        assert_eq!(outcome.missed_weeks(1), 0);
+        assert_eq!(rules.standing(GuildRole::Member, 0, 0, 1, true), MemberStanding::OnVacation);
+    }
+
+    #[test]
+    fn test_vacation_allowance() {
+        let week = 7 * 86_400;
+        assert!(GuildVacation::fits_allowance(0, 4));
+        assert!(!GuildVacation::fits_allowance(0, 5));
+        assert!(GuildVacation::fits_allowance(3 * week, 1));
+        assert!(!GuildVacation::fits_allowance(3 * week + 1, 1));
+        assert!(!GuildVacation::fits_allowance(0, 0));
+    }
+
+    // ========================================
+    // Policy Tests
+    // ========================================
+
+    #[test]
+    fn test_kick_policy() {
+        let rules = rules(InactivityPolicy::Kick, true);
+        assert_eq!(rules.evaluate(GuildRole::Elder, 0, 0, 1, false), ActivityOutcome::Kick);
+        assert_eq!(rules.evaluate(GuildRole::Member, 0, 0, 1, false), ActivityOutcome::Kick);
+    }
+
+    #[test]
+    fn test_kick_policy_without_opt_in_demotes() {
+        let rules = rules(InactivityPolicy::Kick, false);
+        assert!(rules.validate().is_err());
+        assert_eq!(
+            rules.evaluate(GuildRole::CoLeader, 0, 0, 1, false),
+            ActivityOutcome::Demote(GuildRole::Elder)
+        );
+    }
+
+    #[test]
+    fn test_demote_policy_lowest_rank_flagged() {
+        let rules = rules(InactivityPolicy::Demote, true);
+        let outcome = rules.evaluate(GuildRole::Member, 0, 0, 1, false);
+
+        assert_eq!(outcome, ActivityOutcome::Flagged);
+        assert_eq!(outcome.missed_weeks(1), 2);
+        assert_eq!(rules.standing(GuildRole::Member, 0, 0, 2, false), MemberStanding::Warned);
+    }
+
+    #[test]
+    fn test_leader_and_disabled_exempt() {
+        let rules = rules(InactivityPolicy::Kick, true);
+        assert_eq!(rules.evaluate(GuildRole::Leader, 0, 0, 1, false), ActivityOutcome::Exempt);
+
+        let disabled = GuildActivityRules {
+            min_weekly_xp: None,
+            min_weekly_captures: None,
+            policy: InactivityPolicy::Demote,
+            auto_kick_enabled: false,
+        };
+        assert_eq!(disabled.evaluate(GuildRole::Member, 0, 0, 1, false), ActivityOutcome::Exempt);
+        assert_eq!(disabled.standing(GuildRole::Member, 0, 0, 0, false), MemberStanding::NotTracked);
+    }
+
+    #[test]
+    fn test_requirement_bounds() {
+        let mut rules = rules(InactivityPolicy::Demote, false);
+        assert!(rules.validate().is_ok());
+
+        rules.min_weekly_xp = Some(GuildActivityRules::MAX_WEEKLY_XP + 1);
+        assert!(rules.validate().is_err());
+
+        rules.min_weekly_xp = None;
+        rules.min_weekly_captures = Some(GuildActivityRules::MAX_WEEKLY_CAPTURES + 1);
+        assert!(rules.validate().is_err());
+    }
+
+    #[test]
+    fn test_all_requirements_must_be_met() {
+        let rules = GuildActivityRules {
+            min_weekly_xp: Some(500),
+            min_weekly_captures: Some(5),
+            policy: InactivityPolicy::Demote,
+            auto_kick_enabled: false,
+        };
+        assert!(rules.is_met(500, 5));
+        assert!(!rules.is_met(500, 4));
+        assert!(!rules.is_met(499, 5));
+    }
+}
@@ -0,0 +1,87 @@
+//! Timed spawn events
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::titan::TitanSpawn;
+
+/// A limited-time special spawn run (holiday event, tournament kick-off)
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct SpawnEvent {
+    pub id: Uuid,
+    pub name: String,
+    pub start_at: DateTime<Utc>,
+    pub end_at: DateTime<Utc>,
+    pub spawn_config: sqlx::types::Json<SpawnEventConfig>,
+    /// Geohash prefixes the event covers; empty means everywhere
+    pub geohash_filter: Vec<String>,
+    /// When the scheduler ran the event's spawns
+    pub started_at: Option<DateTime<Utc>>,
+    /// When the scheduler expired the event's Titans
+    pub ended_at: Option<DateTime<Utc>>,
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Overrides for an event's spawn run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpawnEventConfig {
+    /// Species the event spawns. Each Titan takes a pool species of its
+    /// rolled threat class, or the normal terrain roll when there is none
+    #[serde(default)]
+    pub species_pool: Vec<i32>,
+    /// Relative weights of threat classes I-V
+    #[serde(default = "default_threat_class_weights")]
+    pub threat_class_weights: [f64; 5],
+    /// Titans the run spawns, at the highest-weight POIs without one
+    #[serde(default = "default_event_spawns")]
+    pub max_spawns: u32,
+}
+
+fn default_threat_class_weights() -> [f64; 5] {
+    [0.0, 0.0, 0.0, 3.0, 1.0]
+}
+
+fn default_event_spawns() -> u32 {
+    20
+}
+
+impl Default for SpawnEventConfig {
+    fn default() -> Self {
+        Self {
+            species_pool: Vec::new(),
+            threat_class_weights: default_threat_class_weights(),
+            max_spawns: default_event_spawns(),
+        }
+    }
+}
+
+/// Create a spawn event
+#[derive(Debug, Deserialize)]
+pub struct CreateSpawnEventRequest {
+    pub name: String,
+    pub start_at: DateTime<Utc>,
+    pub end_at: DateTime<Utc>,
+    #[serde(default)]
+    pub spawn_config: SpawnEventConfig,
+    #[serde(default)]
+    pub geohash_filter: Vec<String>,
+}
+
+/// An event the scheduler just started, with the Titans it spawned
+#[derive(Debug, Clone)]
+pub struct StartedSpawnEvent {
+    pub event: SpawnEvent,
+    /// Each Titan with its POI's name
+    pub spawns: Vec<(TitanSpawn, String)>,
+}
+
+/// An event the scheduler just ended, with the Titans it expired
+#[derive(Debug, Clone)]
+pub struct EndedSpawnEvent {
+    pub event: SpawnEvent,
+    /// ID and geohash of each expired Titan
+    pub expired: Vec<(Uuid, String)>,
+}
@@ -0,0 +1,99 @@
+//! Species catalog
+//!
+//! Species IDs are generated by the spawn service as
+//! `element * 1000 + (threat_class - 1) * 100 + variant` with variants 1-10.
+
+use serde::Serialize;
+
+use super::titan::Element;
+
+/// Variants active at night, indexed by `Element::as_u8`
+const NOCTURNAL_VARIANTS: [&[i32]; 6] = [
+    &[3, 7, 9, 10], // Abyssal
+    &[10],          // Volcanic
+    &[8],           // Storm
+    &[2, 5, 9, 10], // Void
+    &[6, 9],        // Parasitic
+    &[4, 10],       // Ossified
+];
+
+/// Catalog entry for a species
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Species {
+    pub id: i32,
+    pub element: Element,
+    pub threat_class: i16,
+    pub variant: i32,
+    /// Stronger at night: capture chance and reward bonus by local solar time
+    pub nocturnal: bool,
+}
+
+impl Species {
+    /// Look up a species; `None` for IDs the spawn service can't produce
+    pub fn from_id(id: i32) -> Option<Self> {
+        if id < 0 {
+            return None;
+        }
+
+        let element = Element::from_u8(u8::try_from(id / 1000).ok()?)?;
+        let threat_class = (id % 1000 / 100 + 1) as i16;
+        let variant = id % 100;
+
+        if !(1..=5).contains(&threat_class) || !(1..=10).contains(&variant) {
+            return None;
+        }
+
+        Some(Self {
+            id,
+            element,
+            threat_class,
+            variant,
+            nocturnal: NOCTURNAL_VARIANTS[element.as_u8() as usize].contains(&variant),
+        })
+    }
+
+    /// Whether a species is tagged nocturnal (unknown species are not)
+    pub fn is_nocturnal(id: i32) -> bool {
+        Self::from_id(id).is_some_and(|s| s.nocturnal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_species_id() {
+        let species = Species::from_id(3_409).unwrap();
+        assert_eq!(species.element, Element::Void);
+        assert_eq!(species.threat_class, 5);
+        assert_eq!(species.variant, 9);
+        assert!(species.nocturnal);
+
+        let species = Species::from_id(1_001).unwrap();
+        assert_eq!(species.element, Element::Volcanic);
+        assert_eq!(species.threat_class, 1);
+        assert!(!species.nocturnal);
+    }
+
+    #[test]
+    fn test_nocturnal_flag_per_element() {
+        assert!(Species::is_nocturnal(3)); // Abyssal I, variant 3
+        assert!(!Species::is_nocturnal(4)); // Abyssal I, variant 4
+        assert!(Species::is_nocturnal(1_210)); // Volcanic III, variant 10
+        assert!(!Species::is_nocturnal(1_209));
+        assert!(Species::is_nocturnal(2_308)); // Storm IV, variant 8
+        assert!(Species::is_nocturnal(5_004)); // Ossified I, variant 4
+        assert!(!Species::is_nocturnal(4_005)); // Parasitic I, variant 5
+    }
+
+    #[test]
+    fn test_unknown_species() {
+        assert_eq!(Species::from_id(-1), None);
+        assert_eq!(Species::from_id(0), None); // variant 0
+        assert_eq!(Species::from_id(11), None); // variant 11
+        assert_eq!(Species::from_id(501), None); // threat class 6
+        assert_eq!(Species::from_id(6_001), None); // element 6
+        assert!(!Species::is_nocturnal(6_003));
+    }
+}
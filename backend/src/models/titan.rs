@@ -5,6 +5,11 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
+use crate::error::ApiResult;
+use crate::utils::clock::{check_skew, SkewContext};
+
+use super::capture::AppliedModifier;
+
 /// Titan element types
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "element_type", rename_all = "lowercase")]
@@ -43,6 +48,37 @@ impl Element {
     }
 }
 
+/// Damage multipliers, `TYPE_CHART[attacker][defender]` by `Element::as_u8`.
+/// Abyssal, Volcanic, Parasitic, Ossified and Storm each overpower the next
+/// in a ring (Storm overpowers Abyssal) and resist their own element; Void
+/// is neutral to everything but itself.
+const TYPE_CHART: [[f64; 6]; 6] = [
+    // Aby  Vol  Sto  Voi  Par  Oss
+    [0.5, 2.0, 0.5, 1.0, 1.0, 1.0], // Abyssal
+    [0.5, 0.5, 1.0, 1.0, 2.0, 1.0], // Volcanic
+    [2.0, 1.0, 0.5, 1.0, 1.0, 0.5], // Storm
+    [1.0, 1.0, 1.0, 2.0, 1.0, 1.0], // Void
+    [1.0, 0.5, 1.0, 1.0, 0.5, 2.0], // Parasitic
+    [1.0, 1.0, 2.0, 1.0, 0.5, 0.5], // Ossified
+];
+
+impl Element {
+    pub const ALL: [Element; 6] = [
+        Element::Abyssal,
+        Element::Volcanic,
+        Element::Storm,
+        Element::Void,
+        Element::Parasitic,
+        Element::Ossified,
+    ];
+
+    /// Multiplier on damage this element deals to `defender`: 2.0 super
+    /// effective, 0.5 resisted, 1.0 otherwise
+    pub fn effectiveness(self, defender: Element) -> f64 {
+        TYPE_CHART[self.as_u8() as usize][defender.as_u8() as usize]
+    }
+}
+
 /// Titan threat class (rarity)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "int2")]
@@ -92,6 +128,27 @@ pub struct TitanSpawnResponse {
     pub is_available: bool,
 }
 
+impl TitanSpawnResponse {
+    /// Compact snapshot record (drops distance, POI name and availability)
+    pub fn to_compact(&self) -> breach_sdk::map::CompactTitan {
+        breach_sdk::map::CompactTitan {
+            id: *self.id.as_bytes(),
+            lat_e6: breach_sdk::map::CompactTitan::to_microdegrees(self.location.lat),
+            lng_e6: breach_sdk::map::CompactTitan::to_microdegrees(self.location.lng),
+            element: self.element.as_u8(),
+            threat_class: self.threat_class.clamp(0, u8::MAX as i16) as u8,
+            species_id: self.species_id.clamp(0, u16::MAX as i32) as u16,
+            expires_at: self.expires_at.timestamp(),
+        }
+    }
+
+    /// Encode spawns as an `application/x-breach-map-v1` snapshot
+    pub fn encode_compact(titans: &[TitanSpawnResponse], generated_at: DateTime<Utc>) -> Vec<u8> {
+        let compact: Vec<_> = titans.iter().map(Self::to_compact).collect();
+        breach_sdk::map::encode(generated_at.timestamp(), &compact)
+    }
+}
+
 /// Geographic point
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GeoPoint {
@@ -106,6 +163,16 @@ pub struct CaptureRequest {
     pub player_location: PlayerLocation,
 }
 
+impl CaptureRequest {
+    /// Reject capture taps from a badly skewed device clock
+    pub fn check_tap_time(&self) -> ApiResult<()> {
+        match self.player_location.timestamp {
+            Some(tapped_at) => check_skew(SkewContext::Capture, tapped_at),
+            None => Ok(()),
+        }
+    }
+}
+
 /// Capture authorization response
 #[derive(Debug, Serialize)]
 pub struct CaptureAuthorization {
@@ -116,6 +183,45 @@ pub struct CaptureAuthorization {
     pub error: Option<String>,
     pub distance: Option<f64>,
     pub max_distance: Option<f64>,
+    /// Chance the attempt succeeds, after modifiers
+    pub capture_chance: Option<f64>,
+    /// Modifiers applied to this capture's chance and reward
+    pub modifiers: Vec<AppliedModifier>,
+    /// Present back on confirm / build-transaction (authorized requests only)
+    pub challenge: Option<CaptureChallenge>,
+}
+
+/// Server-signed proof that a capture was authorized by `/capture/request`
+///
+/// Signed with the backend keypair over the requesting player, Titan, issue
+/// time and nonce; valid for `game.capture_challenge_ttl_seconds` and
+/// accepted once.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureChallenge {
+    pub titan_id: Uuid,
+    pub issued_at: DateTime<Utc>,
+    pub nonce: String,
+    /// Base58 ed25519 signature of `message`
+    pub signature: String,
+}
+
+impl CaptureChallenge {
+    /// Bytes the backend signs; binds the challenge to the player it was issued to
+    pub fn message(&self, player_id: Uuid) -> String {
+        format!(
+            "capture_challenge:{}:{}:{}:{}",
+            player_id,
+            self.titan_id,
+            self.issued_at.timestamp_millis(),
+            self.nonce
+        )
+    }
+
+    /// Whether the challenge was issued at most `ttl_seconds` before `now`
+    pub fn is_fresh(&self, now: DateTime<Utc>, ttl_seconds: u64) -> bool {
+        let age = now - self.issued_at;
+        age >= chrono::Duration::zero() && age <= chrono::Duration::seconds(ttl_seconds as i64)
+    }
 }
 
 /// Titan data for capture
@@ -195,6 +301,62 @@ mod tests {
         assert_eq!(element, Element::Storm);
     }
 
+    #[test]
+    fn test_element_effectiveness_every_pair() {
+        use Element::*;
+        let super_effective = [
+            (Abyssal, Volcanic),
+            (Volcanic, Parasitic),
+            (Parasitic, Ossified),
+            (Ossified, Storm),
+            (Storm, Abyssal),
+            (Void, Void),
+        ];
+        let resisted = [
+            (Volcanic, Abyssal),
+            (Parasitic, Volcanic),
+            (Ossified, Parasitic),
+            (Storm, Ossified),
+            (Abyssal, Storm),
+            (Abyssal, Abyssal),
+            (Volcanic, Volcanic),
+            (Storm, Storm),
+            (Parasitic, Parasitic),
+            (Ossified, Ossified),
+        ];
+
+        for attacker in Element::ALL {
+            for defender in Element::ALL {
+                let expected = if super_effective.contains(&(attacker, defender)) {
+                    2.0
+                } else if resisted.contains(&(attacker, defender)) {
+                    0.5
+                } else {
+                    1.0
+                };
+                assert_eq!(
+                    attacker.effectiveness(defender),
+                    expected,
+                    "{:?} attacking {:?}",
+                    attacker,
+                    defender
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_element_effectiveness_is_never_mutual() {
+        // No pair overpowers each other both ways
+        for attacker in Element::ALL {
+            for defender in Element::ALL {
+                if attacker != defender && attacker.effectiveness(defender) == 2.0 {
+                    assert_eq!(defender.effectiveness(attacker), 0.5, "{:?} vs {:?}", attacker, defender);
+                }
+            }
+        }
+    }
+
     // ========================================
     // ThreatClass Tests
     // ========================================
@@ -295,4 +457,139 @@ mod tests {
         assert_eq!(req.titan_id.to_string(), "550e8400-e29b-41d4-a716-446655440000");
         assert_eq!(req.player_location.lat, 35.6762);
     }
+
+    #[test]
+    fn test_capture_request_tap_time_skew() {
+        let mut req = CaptureRequest {
+            titan_id: Uuid::new_v4(),
+            player_location: PlayerLocation {
+                lat: 35.6762,
+                lng: 139.6503,
+                accuracy: 10.0,
+                speed: None,
+                heading: None,
+                altitude: None,
+                timestamp: None,
+            },
+        };
+
+        // No tap time, nothing to check
+        assert!(req.check_tap_time().is_ok());
+
+        req.player_location.timestamp = Some(Utc::now());
+        assert!(req.check_tap_time().is_ok());
+
+        req.player_location.timestamp = Some(Utc::now() - chrono::Duration::minutes(10));
+        match req.check_tap_time() {
+            Err(crate::error::AppError::ClockSkew { context, offset_ms, .. }) => {
+                assert_eq!(context, "capture");
+                assert!(offset_ms <= -600_000);
+            }
+            other => panic!("expected ClockSkew, got {:?}", other),
+        }
+    }
+
+    // ========================================
+    // Compact Map Snapshot Tests
+    // ========================================
+
+    fn spawn_fixture(count: usize) -> Vec<TitanSpawnResponse> {
+        let now = Utc::now();
+        (0..count)
+            .map(|i| TitanSpawnResponse {
+                id: Uuid::new_v4(),
+                location: GeoPoint {
+                    lat: 35.6762 + (i as f64) * 0.000137,
+                    lng: 139.6503 - (i as f64) * 0.000211,
+                },
+                element: Element::from_u8((i % 6) as u8).unwrap(),
+                threat_class: (i % 5 + 1) as i16,
+                species_id: (i * 13 % 400) as i32,
+                distance: Some(12.5 + i as f64),
+                expires_at: now + chrono::Duration::minutes((i % 60) as i64),
+                poi_name: if i % 4 == 0 { Some(format!("Shibuya Crossing {}", i)) } else { None },
+                is_available: true,
+            })
+            .collect()
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        use std::io::Write;
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_compact_snapshot_round_trip() {
+        let spawns = spawn_fixture(50);
+        let now = Utc::now();
+        let bytes = TitanSpawnResponse::encode_compact(&spawns, now);
+
+        let snapshot = breach_sdk::map::decode(&bytes).unwrap();
+        assert_eq!(snapshot.generated_at, now.timestamp());
+        assert_eq!(snapshot.titans.len(), spawns.len());
+
+        for (spawn, compact) in spawns.iter().zip(&snapshot.titans) {
+            assert_eq!(Uuid::from_bytes(compact.id), spawn.id);
+            assert!((compact.lat() - spawn.location.lat).abs() < 0.000_001);
+            assert!((compact.lng() - spawn.location.lng).abs() < 0.000_001);
+            assert_eq!(Element::from_u8(compact.element), Some(spawn.element));
+            assert_eq!(compact.threat_class as i16, spawn.threat_class);
+            assert_eq!(compact.species_id as i32, spawn.species_id);
+            assert_eq!(compact.expires_at, spawn.expires_at.timestamp());
+        }
+    }
+
+    #[test]
+    fn test_compact_snapshot_size() {
+        let spawns = spawn_fixture(500);
+        let json = serde_json::to_vec(&spawns).unwrap();
+        let compact = TitanSpawnResponse::encode_compact(&spawns, Utc::now());
+
+        assert!(
+            compact.len() * 4 < json.len(),
+            "compact {} bytes vs JSON {} bytes",
+            compact.len(),
+            json.len()
+        );
+    }
+
+    #[test]
+    fn test_compact_snapshot_size_gzipped() {
+        let spawns = spawn_fixture(500);
+        let json_gz = gzip(&serde_json::to_vec(&spawns).unwrap());
+        let compact_gz = gzip(&TitanSpawnResponse::encode_compact(&spawns, Utc::now()));
+
+        // The win must survive transport compression (random ids bound the ratio)
+        assert!(
+            compact_gz.len() < json_gz.len(),
+            "gzipped compact {} bytes vs gzipped JSON {} bytes",
+            compact_gz.len(),
+            json_gz.len()
+        );
+    }
+
+    #[test]
+    fn test_capture_challenge_message_and_freshness() {
+        let now = Utc::now();
+        let challenge = CaptureChallenge {
+            titan_id: Uuid::nil(),
+            issued_at: now,
+            nonce: "abc".into(),
+            signature: String::new(),
+        };
+
+        let player = Uuid::nil();
+        assert_eq!(
+            challenge.message(player),
+            format!("capture_challenge:{}:{}:{}:abc", player, Uuid::nil(), now.timestamp_millis())
+        );
+
+        assert!(challenge.is_fresh(now, 30));
+        assert!(challenge.is_fresh(now + chrono::Duration::seconds(30), 30));
+        assert!(!challenge.is_fresh(now + chrono::Duration::seconds(31), 30));
+        // Issued in the future: tampered
+        assert!(!challenge.is_fresh(now - chrono::Duration::seconds(5), 30));
+    }
 }
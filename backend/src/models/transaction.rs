@@ -0,0 +1,78 @@
+//! Tracked transaction models
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Player-signed transactions followed up once they finalize
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "tracked_transaction_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum TrackedTransactionKind {
+    /// Titan NFT mint for a capture attempt (`related_id`)
+    CaptureMint,
+    /// Purchase of an escrow listing (`related_id`)
+    ListingPurchase,
+    /// Seller cancelling an escrow listing (`related_id`)
+    ListingCancel,
+}
+
+/// Tracked transaction status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "tracked_transaction_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum TrackedTransactionStatus {
+    Pending,
+    Confirmed,
+    Failed,
+    /// Never landed before its blockhash expired
+    Expired,
+}
+
+/// Tracked transaction row
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct PendingTransaction {
+    pub signature: String,
+    pub kind: TrackedTransactionKind,
+    pub player_id: Uuid,
+    pub related_id: Uuid,
+    #[serde(skip_serializing)]
+    pub mint_address: Option<String>,
+    #[serde(skip_serializing)]
+    pub recent_blockhash: String,
+    pub status: TrackedTransactionStatus,
+    pub attempts: i32,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// What a player-signed transaction is for, recorded before it is sent
+#[derive(Debug, Clone)]
+pub struct TrackedTransaction {
+    pub kind: TrackedTransactionKind,
+    pub player_id: Uuid,
+    pub related_id: Uuid,
+    pub mint_address: Option<String>,
+}
+
+impl TrackedTransaction {
+    pub fn capture_mint(player_id: Uuid, attempt_id: Uuid, mint_address: &str) -> Self {
+        Self {
+            kind: TrackedTransactionKind::CaptureMint,
+            player_id,
+            related_id: attempt_id,
+            mint_address: Some(mint_address.to_string()),
+        }
+    }
+
+    pub fn listing(kind: TrackedTransactionKind, player_id: Uuid, listing_id: Uuid) -> Self {
+        Self {
+            kind,
+            player_id,
+            related_id: listing_id,
+            mint_address: None,
+        }
+    }
+}
@@ -1,11 +1,14 @@
 //! Background task scheduler
 
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::Duration;
 
+use chrono::{DateTime, Utc};
 use tokio::time::interval;
 
-use crate::websocket::{Location, WsMessage};
+use crate::config::MaintenanceWindow;
+use crate::websocket::{publish_match_update, WsMessage, RARE_SPAWN_MIN_THREAT_CLASS};
 use crate::AppState;
 
 /// Start all background tasks
@@ -16,6 +19,12 @@ pub fn start_background_tasks(state: Arc<AppState>) {
         spawn_cycle_task(spawn_state).await;
     });
 
+    // Timed spawn events
+    let event_state = state.clone();
+    tokio::spawn(async move {
+        spawn_event_task(event_state).await;
+    });
+
     // Cleanup expired Titans task
     let cleanup_state = state.clone();
     tokio::spawn(async move {
@@ -34,36 +43,116 @@ pub fn start_background_tasks(state: Arc<AppState>) {
         websocket_cleanup_task(ws_state).await;
     });
 
+    // Guild weekly activity evaluation task
+    let guild_state = state.clone();
+    tokio::spawn(async move {
+        guild_activity_task(guild_state).await;
+    });
+
+    // Guild war challenges and results
+    let war_state = state.clone();
+    tokio::spawn(async move {
+        guild_war_task(war_state).await;
+    });
+
+    // Marketplace escrow reconciliation task
+    let escrow_state = state.clone();
+    tokio::spawn(async move {
+        marketplace_escrow_task(escrow_state).await;
+    });
+
+    // Deferred on-chain action queue
+    let fee_state = state.clone();
+    tokio::spawn(async move {
+        fee_budget_task(fee_state).await;
+    });
+
+    // Failed reward transfers
+    let reward_state = state.clone();
+    tokio::spawn(async move {
+        reward_retry_task(reward_state).await;
+    });
+
+    // Pending transaction confirmations
+    let transaction_state = state.clone();
+    tokio::spawn(async move {
+        pending_transaction_task(transaction_state).await;
+    });
+
+    // PvP matchmaking
+    let matchmaking_state = state.clone();
+    tokio::spawn(async move {
+        pvp_matchmaking_task(matchmaking_state).await;
+    });
+
+    // PvP ready and turn deadlines
+    let pvp_state = state.clone();
+    tokio::spawn(async move {
+        pvp_timeout_task(pvp_state).await;
+    });
+
+    // PvP season finalization and rewards
+    let season_state = state.clone();
+    tokio::spawn(async move {
+        pvp_season_task(season_state).await;
+    });
+
+    // Weekly leaderboard snapshots
+    let leaderboard_state = state.clone();
+    tokio::spawn(async move {
+        leaderboard_snapshot_task(leaderboard_state).await;
+    });
+
+    // Scheduled maintenance announcements
+    let maintenance_state = state.clone();
+    tokio::spawn(async move {
+        maintenance_task(maintenance_state).await;
+    });
+
     tracing::info!("✅ Background tasks started");
 }
 
-/// Periodic Titan spawn cycle
+/// Run the full (global) spawn cycle every this many hourly cycles
+const GLOBAL_SPAWN_CYCLE_EVERY: u64 = 24;
+
+/// Periodic Titan spawn cycle: hourly in regions with players online, and
+/// across the whole world once a day (including at startup). Regions nobody
+/// visits are left to expire through the cleanup task.
 async fn spawn_cycle_task(state: Arc<AppState>) {
     let mut interval = interval(Duration::from_secs(3600)); // Every hour
+    let mut cycle: u64 = 0;
 
     loop {
         interval.tick().await;
 
-        tracing::info!("Running spawn cycle...");
-
-        match state.services.spawn.run_spawn_cycle(None).await {
+        let result = if cycle.is_multiple_of(GLOBAL_SPAWN_CYCLE_EVERY) {
+            tracing::info!("Running global spawn cycle...");
+            state.services.spawn.run_spawn_cycle(None).await
+        } else {
+            let regions = state.broadcaster.active_regions().await;
+            tracing::info!("Running spawn cycle for {} active regions...", regions.len());
+            state.services.spawn.run_spawn_cycle_for_regions(regions).await
+        };
+        cycle += 1;
+
+        match result {
             Ok(spawns) => {
                 tracing::info!("Spawn cycle complete: {} new Titans", spawns.len());
 
                 // Broadcast new spawns via WebSocket
                 for titan in spawns {
-                    let message = WsMessage::TitanSpawn {
-                        titan_id: titan.id.to_string(),
-                        poi_name: None, // Could fetch from POI if needed
-                        location: Location {
-                            lat: titan.location_lat,
-                            lng: titan.location_lng,
-                        },
-                        element: format!("{:?}", titan.element).to_lowercase(),
-                        threat_class: titan.threat_class,
-                        species_id: titan.species_id,
-                        expires_at: titan.expires_at.to_rfc3339(),
+                    // Rare spawns are announced with the POI's name
+                    let poi_name = match titan.poi_id {
+                        Some(poi_id) if titan.threat_class >= RARE_SPAWN_MIN_THREAT_CLASS => {
+                            sqlx::query_scalar::<_, String>("SELECT name FROM pois WHERE id = $1")
+                                .bind(poi_id)
+                                .fetch_optional(&state.db.pg)
+                                .await
+                                .unwrap_or_default()
+                        }
+                        _ => None,
                     };
+                    let message = WsMessage::titan_spawn(&titan, poi_name);
 
                     // Broadcast to the titan's geohash region and neighbors
                     state.broadcaster.broadcast_to_neighbors(&titan.geohash, message).await;
@@ -76,6 +165,51 @@ async fn spawn_cycle_task(state: Arc<AppState>) {
     }
 }
 
+/// Start and end timed spawn events, announcing their Titans
+async fn spawn_event_task(state: Arc<AppState>) {
+    let mut interval = interval(Duration::from_secs(60)); // Every minute
+
+    loop {
+        interval.tick().await;
+
+        match state.services.event_spawn.start_due_events().await {
+            Ok(started) => {
+                for started in started {
+                    tracing::info!(
+                        "Spawn event {:?} started: {} Titans",
+                        started.event.name,
+                        started.spawns.len()
+                    );
+                    for (titan, poi_name) in started.spawns {
+                        let message =
+                            WsMessage::event_titan_spawn(&titan, Some(poi_name), &started.event.name);
+                        state.broadcaster.broadcast_to_neighbors(&titan.geohash, message).await;
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::error!("Starting spawn events failed: {:?}", e);
+            }
+        }
+
+        match state.services.event_spawn.end_due_events().await {
+            Ok(ended) => {
+                for ended in ended {
+                    for (titan_id, geohash) in ended.expired {
+                        let message = WsMessage::TitanExpired {
+                            titan_id: titan_id.to_string(),
+                        };
+                        state.broadcaster.broadcast(&geohash, message).await;
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::error!("Ending spawn events failed: {:?}", e);
+            }
+        }
+    }
+}
+
 /// Cleanup expired Titans and old location data
 async fn cleanup_task(state: Arc<AppState>) {
     let mut interval = interval(Duration::from_secs(300)); // Every 5 minutes
@@ -137,7 +271,7 @@ async fn cleanup_task(state: Arc<AppState>) {
     }
 }
 
-/// Collect and log metrics
+/// Refresh the gauges served at `/metrics`
 async fn metrics_task(state: Arc<AppState>) {
     let mut interval = interval(Duration::from_secs(60)); // Every minute
 
@@ -174,12 +308,27 @@ async fn metrics_task(state: Arc<AppState>) {
         // WebSocket connections
         let ws_connections = state.broadcaster.get_total_connections().await;
 
-        if let (Ok((titans,)), Ok((active,)), Ok((total,))) =
-            (active_titans, active_players, total_players)
-        {
+        let metrics = &state.metrics;
+        for (gauge, count) in [
+            (&metrics.active_titans, active_titans),
+            (&metrics.online_players, active_players),
+            (&metrics.total_players, total_players),
+        ] {
+            match count {
+                Ok((count,)) => gauge.set(count),
+                Err(e) => tracing::warn!("Failed to collect metrics: {}", e),
+            }
+        }
+        metrics.websocket_connections.set(ws_connections as i64);
+
+        // On-chain fee spend
+        if let Ok(fees) = state.services.fee_budget.summary().await {
             tracing::info!(
-                "Metrics: {} active Titans, {} online players, {} total players, {} WebSocket connections",
-                titans, active, total, ws_connections
+                "Metrics: fee spend {} lamports today, {}/{} lamports this hour, {} deferred actions pending",
+                fees.today_lamports,
+                fees.hour_lamports,
+                fees.global_hourly_cap_lamports,
+                fees.pending_actions
             );
         }
     }
@@ -198,3 +347,456 @@ async fn websocket_cleanup_task(state: Arc<AppState>) {
         }
     }
 }
+
+/// Evaluate guild activity requirements for finished weeks
+async fn guild_activity_task(state: Arc<AppState>) {
+    let mut interval = interval(Duration::from_secs(3600)); // Every hour
+
+    loop {
+        interval.tick().await;
+
+        match state.services.guild.run_activity_evaluations().await {
+            Ok(count) if count > 0 => {
+                tracing::info!("Evaluated weekly activity for {} guilds", count);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                tracing::error!("Guild activity evaluation failed: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Expire unanswered war challenges and close finished guild wars
+async fn guild_war_task(state: Arc<AppState>) {
+    let mut interval = interval(Duration::from_secs(60)); // Every minute
+
+    loop {
+        interval.tick().await;
+
+        match state.services.guild.close_expired_wars().await {
+            Ok(closed) if closed > 0 => {
+                tracing::info!("Closed {} guild wars", closed);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                tracing::error!("Guild war closing failed: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Match queued players as their search ranges widen, and push each match to
+/// both players
+async fn pvp_matchmaking_task(state: Arc<AppState>) {
+    let mut interval = interval(Duration::from_secs(5)); // Every 5 seconds
+
+    loop {
+        interval.tick().await;
+
+        let matches = match state.services.pvp.run_matchmaking_cycle().await {
+            Ok(matches) => matches,
+            Err(e) => {
+                tracing::error!("PvP matchmaking cycle failed: {:?}", e);
+                continue;
+            }
+        };
+
+        for found in matches {
+            for (player, opponent) in found.pairings() {
+                let message = WsMessage::pvp_match_found(&found, opponent);
+                state.broadcaster.broadcast_to_player(player.player_id, message).await;
+            }
+        }
+    }
+}
+
+/// Cancel PvP matches nobody got ready for, and defend turns that ran out,
+/// forfeiting matches after too many in a row
+async fn pvp_timeout_task(state: Arc<AppState>) {
+    let mut interval = interval(Duration::from_secs(5)); // Every 5 seconds
+
+    loop {
+        interval.tick().await;
+
+        // Matches nobody got ready for
+        match state.services.pvp.cancel_unready_matches().await {
+            Ok(cancelled) => {
+                for cancelled in cancelled {
+                    let message = WsMessage::PvpMatchCancelled {
+                        match_id: cancelled.match_id.to_string(),
+                    };
+                    for player_id in cancelled.player_ids {
+                        state.broadcaster.broadcast_to_player(player_id, message.clone()).await;
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::error!("PvP ready deadline check failed: {:?}", e);
+            }
+        }
+
+        let (grace, max_skips, reconnect_grace) = {
+            let game = state.game.read().await;
+            (
+                chrono::Duration::seconds(game.pvp_turn_grace_seconds as i64),
+                game.pvp_max_consecutive_skips as i32,
+                chrono::Duration::seconds(game.pvp_reconnect_grace_seconds as i64),
+            )
+        };
+
+        let timeouts = match state
+            .services
+            .pvp
+            .resolve_turn_timeouts(grace, max_skips, reconnect_grace)
+            .await
+        {
+            Ok(timeouts) => timeouts,
+            Err(e) => {
+                tracing::error!("PvP turn timeout check failed: {:?}", e);
+                continue;
+            }
+        };
+
+        for timeout in timeouts {
+            let message = WsMessage::PvpTurnTimeout {
+                match_id: timeout.match_id.to_string(),
+                player_id: timeout.player_id.to_string(),
+                consecutive_skips: timeout.consecutive_skips,
+                max_consecutive_skips: max_skips,
+                match_ended: timeout.forfeited,
+                winner_id: timeout.forfeited.then(|| timeout.opponent_id.to_string()),
+            };
+            for player_id in [timeout.player_id, timeout.opponent_id] {
+                state.broadcaster.broadcast_to_player(player_id, message.clone()).await;
+            }
+            publish_match_update(&state, timeout.match_id).await;
+        }
+    }
+}
+
+/// Close the PvP season once it has ended, pay its rewards and open the next
+async fn pvp_season_task(state: Arc<AppState>) {
+    let Some(solana) = state.services.solana.as_ref() else {
+        return;
+    };
+
+    let mut interval = interval(Duration::from_secs(3600)); // Every hour
+
+    loop {
+        interval.tick().await;
+
+        let seasons = match state.services.pvp.seasons_to_finalize().await {
+            Ok(seasons) => seasons,
+            Err(e) => {
+                tracing::error!("PvP season check failed: {:?}", e);
+                continue;
+            }
+        };
+        for season_id in seasons {
+            match state.services.pvp.finalize_season(season_id, solana).await {
+                Ok(summary) => {
+                    tracing::info!(
+                        "PvP season {} finalized: {} ranked, {} rewards paid, {} queued for retry",
+                        season_id, summary.ranked, summary.paid, summary.queued
+                    );
+                }
+                Err(e) => {
+                    tracing::error!("PvP season {} finalization failed: {:?}", season_id, e);
+                }
+            }
+        }
+    }
+}
+
+/// Snapshot the leaderboards once the weekly rollover has passed and tell
+/// every client. The snapshot is taken once per week whichever instance
+/// gets there first, and only that one announces it.
+async fn leaderboard_snapshot_task(state: Arc<AppState>) {
+    let mut interval = interval(Duration::from_secs(300)); // Every 5 minutes
+
+    loop {
+        interval.tick().await;
+
+        let (weekday, hour, size) = {
+            let game = state.game.read().await;
+            (
+                game.leaderboard_rollover_weekday,
+                game.leaderboard_rollover_hour_utc,
+                game.leaderboard_snapshot_size as i64,
+            )
+        };
+
+        match state
+            .services
+            .leaderboard
+            .take_weekly_snapshots(weekday, hour, size, Utc::now())
+            .await
+        {
+            Ok(Some(snapshot)) => {
+                state
+                    .broadcaster
+                    .broadcast_global(WsMessage::LeaderboardRollover {
+                        period: snapshot.period,
+                        boards: snapshot.boards,
+                    })
+                    .await;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                tracing::error!("Leaderboard snapshot failed: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Reconcile on-chain escrow listings with the marketplace tables
+async fn marketplace_escrow_task(state: Arc<AppState>) {
+    let Some(solana) = state.services.solana.as_ref() else {
+        return;
+    };
+
+    let mut interval = interval(Duration::from_secs(60)); // Every minute
+
+    loop {
+        interval.tick().await;
+
+        match state.services.marketplace.reconcile_escrow_listings(solana).await {
+            Ok(summary) => {
+                if summary.imported + summary.sold + summary.cancelled > 0 {
+                    tracing::info!(
+                        "Escrow reconcile: {} imported, {} sold, {} cancelled",
+                        summary.imported, summary.sold, summary.cancelled
+                    );
+                }
+            }
+            Err(e) => {
+                tracing::error!("Escrow reconcile failed: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Retry deferred on-chain actions and alert on repeated global cap hits
+async fn fee_budget_task(state: Arc<AppState>) {
+    let Some(solana) = state.services.solana.as_ref() else {
+        return;
+    };
+
+    let mut interval = interval(Duration::from_secs(60)); // Every minute
+    let mut last_alert_hour = None;
+
+    loop {
+        interval.tick().await;
+
+        match state.services.fee_budget.process_queue(solana).await {
+            Ok(summary) => {
+                if summary.completed + summary.failed > 0 {
+                    tracing::info!(
+                        "Fee queue: {} completed, {} failed, {} still deferred",
+                        summary.completed, summary.failed, summary.still_deferred
+                    );
+                }
+            }
+            Err(e) => {
+                tracing::error!("Fee queue processing failed: {:?}", e);
+            }
+        }
+
+        // Alert at most once per hour
+        let hour = crate::models::budget_hour(chrono::Utc::now());
+        if last_alert_hour == Some(hour) {
+            continue;
+        }
+        match state.services.fee_budget.should_alert().await {
+            Ok(true) => {
+                tracing::error!(
+                    "ALERT: global on-chain fee cap ({} lamports/hour) hit in each of the last {} hours",
+                    state.config.fee_budget.global_hourly_cap_lamports,
+                    state.config.fee_budget.alert_after_capped_hours
+                );
+                last_alert_hour = Some(hour);
+            }
+            Ok(false) => {}
+            Err(e) => {
+                tracing::error!("Fee budget alert check failed: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Retry failed $BREACH reward transfers whose backoff has passed
+async fn reward_retry_task(state: Arc<AppState>) {
+    let Some(solana) = state.services.solana.as_ref() else {
+        return;
+    };
+
+    let mut interval = interval(Duration::from_secs(60)); // Every minute
+
+    loop {
+        interval.tick().await;
+
+        match state.services.reward.retry_due(solana).await {
+            Ok(summary) => {
+                if summary.paid + summary.failed + summary.retrying > 0 {
+                    tracing::info!(
+                        "Reward retries: {} paid, {} failed, {} retrying",
+                        summary.paid, summary.failed, summary.retrying
+                    );
+                }
+            }
+            Err(e) => {
+                tracing::error!("Reward retry failed: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Follow up transactions whose send didn't resolve and tell the players
+/// whose transactions confirmed late
+async fn pending_transaction_task(state: Arc<AppState>) {
+    let Some(solana) = state.services.solana.as_ref() else {
+        return;
+    };
+
+    let mut interval = interval(Duration::from_secs(15));
+
+    loop {
+        interval.tick().await;
+
+        match state.services.transaction.poll_pending(solana).await {
+            Ok(summary) => {
+                if !summary.confirmed.is_empty() || summary.failed + summary.expired > 0 {
+                    tracing::info!(
+                        "Pending transactions: {} confirmed, {} failed, {} expired, {} still pending",
+                        summary.confirmed.len(), summary.failed, summary.expired, summary.still_pending
+                    );
+                }
+                for transaction in summary.confirmed {
+                    let message = WsMessage::TransactionConfirmed {
+                        signature: transaction.signature,
+                        kind: transaction.kind,
+                        related_id: transaction.related_id.to_string(),
+                    };
+                    state.broadcaster.broadcast_to_player(transaction.player_id, message).await;
+                }
+            }
+            Err(e) => {
+                tracing::error!("Pending transaction poll failed: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Point in a maintenance window worth announcing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum MaintenanceStage {
+    /// Within the notice period before the window
+    Upcoming,
+    /// The window has started
+    Started,
+}
+
+/// Stage of a window at `now`; `None` before the notice period and after the window
+fn maintenance_stage(
+    window: &MaintenanceWindow,
+    notice_minutes: u64,
+    now: DateTime<Utc>,
+) -> Option<MaintenanceStage> {
+    let ends_at = window.starts_at + chrono::Duration::minutes(window.duration_minutes as i64);
+    let notice_at = window.starts_at - chrono::Duration::minutes(notice_minutes as i64);
+
+    if now >= ends_at || now < notice_at {
+        None
+    } else if now >= window.starts_at {
+        Some(MaintenanceStage::Started)
+    } else {
+        Some(MaintenanceStage::Upcoming)
+    }
+}
+
+fn maintenance_announcement(window: &MaintenanceWindow, stage: MaintenanceStage) -> WsMessage {
+    let starts_at = window.starts_at.format("%H:%M UTC");
+    let (title, body, severity) = match stage {
+        MaintenanceStage::Upcoming => (
+            "Scheduled maintenance",
+            format!(
+                "The game goes down for maintenance at {} for about {} minutes.",
+                starts_at, window.duration_minutes
+            ),
+            "warning",
+        ),
+        MaintenanceStage::Started => (
+            "Maintenance in progress",
+            format!(
+                "Maintenance has started and should take about {} minutes.",
+                window.duration_minutes
+            ),
+            "critical",
+        ),
+    };
+
+    WsMessage::SystemAnnouncement {
+        title: title.to_string(),
+        body,
+        severity: severity.to_string(),
+    }
+}
+
+/// Announce configured maintenance windows: once when the notice period
+/// begins and once when the window starts. Every instance runs this, so each
+/// only notifies its own connections.
+async fn maintenance_task(state: Arc<AppState>) {
+    let config = &state.config.maintenance;
+    if config.windows.is_empty() {
+        return;
+    }
+
+    let mut interval = interval(Duration::from_secs(60)); // Every minute
+    let mut announced = HashSet::new();
+
+    loop {
+        interval.tick().await;
+
+        let now = Utc::now();
+        for (index, window) in config.windows.iter().enumerate() {
+            let Some(stage) = maintenance_stage(window, config.notice_minutes, now) else {
+                continue;
+            };
+            if announced.insert((index, stage)) {
+                tracing::info!("Announcing maintenance at {} ({:?})", window.starts_at, stage);
+                state
+                    .broadcaster
+                    .broadcast_instance(maintenance_announcement(window, stage))
+                    .await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maintenance_stages() {
+        let window = MaintenanceWindow {
+            starts_at: "2026-02-01T03:00:00Z".parse().unwrap(),
+            duration_minutes: 60,
+        };
+        let at = |time: &str| format!("2026-02-01T{}Z", time).parse::<DateTime<Utc>>().unwrap();
+
+        assert_eq!(maintenance_stage(&window, 30, at("02:29:59")), None);
+        assert_eq!(maintenance_stage(&window, 30, at("02:30:00")), Some(MaintenanceStage::Upcoming));
+        assert_eq!(maintenance_stage(&window, 30, at("03:00:00")), Some(MaintenanceStage::Started));
+        assert_eq!(maintenance_stage(&window, 30, at("04:00:00")), None);
+
+        match maintenance_announcement(&window, MaintenanceStage::Upcoming) {
+            WsMessage::SystemAnnouncement { body, severity, .. } => {
+                assert!(body.contains("03:00 UTC"), "{}", body);
+                assert_eq!(severity, "warning");
+            }
+            other => panic!("expected system_announcement, got {:?}", other),
+        }
+    }
+}
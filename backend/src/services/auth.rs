@@ -8,6 +8,7 @@ use serde::{Deserialize, Serialize};
 use crate::config::AppConfig;
 use crate::error::{ApiResult, AppError};
 use crate::models::PlayerSession;
+use crate::utils::clock::{check_skew_unix, SkewContext};
 
 /// Authentication service
 #[derive(Clone)]
@@ -31,6 +32,19 @@ pub struct AuthRequest {
     pub wallet_address: String,
     pub signature: String,
     pub message: String,
+    /// Client clock (Unix seconds) when the message was signed
+    #[serde(default)]
+    pub issued_at: Option<i64>,
+}
+
+impl AuthRequest {
+    /// Reject signatures made on a badly skewed device clock
+    pub fn check_issued_at(&self) -> ApiResult<()> {
+        match self.issued_at {
+            Some(issued_at) => check_skew_unix(SkewContext::Signature, issued_at),
+            None => Ok(()),
+        }
+    }
 }
 
 /// Authentication response
@@ -192,7 +206,7 @@ mod tests {
         let diff = challenge.expires_at - now;
         
         // Should expire in ~300 seconds (5 minutes)
-        assert!(diff >= 299 && diff <= 301);
+        assert!((299..=301).contains(&diff));
     }
 
     // ========================================
@@ -352,4 +366,31 @@ mod tests {
         assert!(json.contains("1700000000"));
         assert!(json.contains("player-123"));
     }
+
+    // ========================================
+    // Clock Skew Tests
+    // ========================================
+
+    #[test]
+    fn test_auth_request_issued_at_skew() {
+        let mut req = AuthRequest {
+            wallet_address: "TestWallet123456789".to_string(),
+            signature: "sig".to_string(),
+            message: "msg".to_string(),
+            issued_at: None,
+        };
+        assert!(req.check_issued_at().is_ok());
+
+        req.issued_at = Some(Utc::now().timestamp());
+        assert!(req.check_issued_at().is_ok());
+
+        req.issued_at = Some(Utc::now().timestamp() - 3600);
+        match req.check_issued_at() {
+            Err(AppError::ClockSkew { context, max_skew_seconds, .. }) => {
+                assert_eq!(context, "signature");
+                assert_eq!(max_skew_seconds, 300);
+            }
+            other => panic!("expected ClockSkew, got {:?}", other),
+        }
+    }
 }
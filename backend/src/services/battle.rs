@@ -1,24 +1,28 @@
 //! Battle service
 
-use rand::Rng;
+use rand::SeedableRng;
 use uuid::Uuid;
 
 use crate::db::Database;
 use crate::error::{ApiResult, AppError};
 use crate::models::{
-    Battle, BattleAction, BattleResultResponse, BattleStatus, BattleSummary, BattleType,
-    LocationInput, PlayerTitan,
+    Battle, BattleAction, BattleResultResponse, BattleStatus, BattleSummary, BattleType, Element,
+    LocationInput, PlayerTitan, QuestEvent,
 };
 
+use super::battle_math::{self, Fighter, Move};
+use super::QuestService;
+
 /// Battle service
 #[derive(Clone)]
 pub struct BattleService {
     db: Database,
+    quest: QuestService,
 }
 
 impl BattleService {
-    pub fn new(db: Database) -> Self {
-        Self { db }
+    pub fn new(db: Database, quest: QuestService) -> Self {
+        Self { db, quest }
     }
 
     /// Start a wild battle
@@ -112,14 +116,32 @@ impl BattleService {
             return Err(AppError::Forbidden("Not in this battle".into()));
         }
 
-        // Calculate damage based on action type (generate before await)
-        let base_damage = {
-            let mut rng = rand::thread_rng();
-            match action_type {
-                "attack" => rng.gen_range(20..40),
-                "special" => rng.gen_range(35..55),
-                "defend" => 0,
-                _ => rng.gen_range(10..25),
+        // Calculate damage with the same rules as PvP; the wild Titan never
+        // defends
+        let base_damage = match action_type {
+            "defend" => 0,
+            action_type => {
+                let attack = if action_type == "special" { Move::Special } else { Move::Attack };
+                let attacker: Option<(Element, i16)> =
+                    sqlx::query_as("SELECT element, threat_class FROM player_titans WHERE id = $1")
+                        .bind(battle.player1_titan_id)
+                        .fetch_optional(&self.db.pg)
+                        .await?;
+                let defender: Option<(Element, i16)> =
+                    sqlx::query_as("SELECT element, threat_class FROM titan_spawns WHERE id = $1")
+                        .bind(battle.wild_titan_id)
+                        .fetch_optional(&self.db.pg)
+                        .await?;
+                let fighter = |titan: Option<(Element, i16)>| match titan {
+                    Some((element, threat_class)) => Fighter::new(Some(element), threat_class),
+                    None => Fighter::new(None, 1),
+                };
+
+                let (attacker, defender) = (fighter(attacker), fighter(defender));
+
+                let mut rng = rand::rngs::StdRng::from_entropy();
+                let effectiveness = attacker.effectiveness(&defender);
+                battle_math::roll_hit(&mut rng, attack, &attacker, &defender, effectiveness, false).damage
             }
         };
 
@@ -233,6 +255,11 @@ impl BattleService {
             .await?;
         }
 
+        if player_wins {
+            let event = QuestEvent::BattleWon { battle_type: battle.battle_type };
+            self.quest.record_event_or_warn(player_id, event).await;
+        }
+
         tracing::info!(
             "Battle {} ended: winner={:?}, xp={}, breach={}",
             battle_id,
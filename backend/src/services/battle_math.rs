@@ -0,0 +1,262 @@
+//! Battle damage rules
+//!
+//! One damage formula for PvP matches and wild battles: a move's rolled
+//! power, scaled by the attacker's attack (or special) against the
+//! defender's defense, the element multiplier and critical hits. Callers pick
+//! the multiplier (the built-in chart, or PvP's configured table). Rolls take
+//! the caller's RNG so they can be seeded.
+
+use rand::Rng;
+
+use crate::models::{Element, TitanBattleStats};
+
+/// HP a Titan enters a match with
+pub const TITAN_MAX_HP: i32 = 100;
+
+/// Multiplier on the first attack against a defending Titan
+pub const DEFEND_DAMAGE_MULTIPLIER: f64 = 0.5;
+
+/// Multiplier on a critical hit
+pub const CRIT_DAMAGE_MULTIPLIER: f64 = 1.5;
+
+/// Critical hit chance between Titans of equal speed; it scales with the
+/// attacker's speed over the defender's, within the bounds below
+const BASE_CRIT_CHANCE: f64 = 0.0625;
+const MIN_CRIT_CHANCE: f64 = 0.02;
+const MAX_CRIT_CHANCE: f64 = 0.25;
+
+/// Bounds on the attack / defense ratio, so no matchup is hopeless
+const MIN_STAT_RATIO: f64 = 0.5;
+const MAX_STAT_RATIO: f64 = 2.0;
+
+/// A move that deals damage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Move {
+    /// Scales with `attack`
+    Attack,
+    /// Stronger, and scales with `special`
+    Special,
+}
+
+impl Move {
+    /// Power rolled before any modifier
+    fn power(self) -> std::ops::Range<i32> {
+        match self {
+            Move::Attack => 15..25,
+            Move::Special => 25..40,
+        }
+    }
+}
+
+/// A Titan in battle; a missing element (no Titan on that side) is neutral
+/// to everything
+#[derive(Debug, Clone)]
+pub struct Fighter {
+    pub element: Option<Element>,
+    pub stats: TitanBattleStats,
+}
+
+impl Fighter {
+    pub fn new(element: Option<Element>, threat_class: i16) -> Self {
+        Self {
+            element,
+            stats: battle_stats(threat_class),
+        }
+    }
+
+    /// Multiplier on damage this Titan deals to `defender` by the built-in
+    /// type chart
+    pub fn effectiveness(&self, defender: &Fighter) -> f64 {
+        match (self.element, defender.element) {
+            (Some(attacker), Some(defender)) => attacker.effectiveness(defender),
+            _ => 1.0,
+        }
+    }
+}
+
+/// Battle stats of a Titan of `threat_class`
+pub fn battle_stats(threat_class: i16) -> TitanBattleStats {
+    let base = 50 + threat_class as i32 * 10;
+    TitanBattleStats {
+        max_hp: TITAN_MAX_HP,
+        attack: base,
+        defense: base,
+        speed: base,
+        special: base + threat_class as i32 * 5,
+    }
+}
+
+/// One rolled hit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    pub damage: i32,
+    /// Element multiplier applied to `damage`
+    pub effectiveness: f64,
+    pub critical: bool,
+}
+
+/// Chance that `attacker` lands a critical hit on `defender`
+pub fn crit_chance(attacker: &TitanBattleStats, defender: &TitanBattleStats) -> f64 {
+    let ratio = attacker.speed.max(1) as f64 / defender.speed.max(1) as f64;
+    (BASE_CRIT_CHANCE * ratio).clamp(MIN_CRIT_CHANCE, MAX_CRIT_CHANCE)
+}
+
+/// Roll a hit of `attack` on `defender` with element multiplier
+/// `effectiveness`; the defender takes half of it while `defending`
+pub fn roll_hit(
+    rng: &mut impl Rng,
+    attack: Move,
+    attacker: &Fighter,
+    defender: &Fighter,
+    effectiveness: f64,
+    defending: bool,
+) -> Hit {
+    let power = rng.gen_range(attack.power());
+    let critical = rng.gen_bool(crit_chance(&attacker.stats, &defender.stats));
+    Hit {
+        damage: damage(power, attack, attacker, defender, effectiveness, critical, defending),
+        effectiveness,
+        critical,
+    }
+}
+
+/// Damage of a hit with rolled `power`; at least 1
+pub fn damage(
+    power: i32,
+    attack: Move,
+    attacker: &Fighter,
+    defender: &Fighter,
+    effectiveness: f64,
+    critical: bool,
+    defending: bool,
+) -> i32 {
+    let offense = match attack {
+        Move::Attack => attacker.stats.attack,
+        Move::Special => attacker.stats.special,
+    };
+    let ratio = (offense as f64 / defender.stats.defense.max(1) as f64).clamp(MIN_STAT_RATIO, MAX_STAT_RATIO);
+    let crit = if critical { CRIT_DAMAGE_MULTIPLIER } else { 1.0 };
+    let mitigation = if defending { DEFEND_DAMAGE_MULTIPLIER } else { 1.0 };
+
+    ((power as f64 * ratio * effectiveness * crit * mitigation).round() as i32).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    fn fighter(element: Element, threat_class: i16) -> Fighter {
+        Fighter::new(Some(element), threat_class)
+    }
+
+    #[test]
+    fn test_damage_by_matchup() {
+        let volcanic = fighter(Element::Volcanic, 3);
+        let cases = [
+            // (attacker, defender, expected damage of a 20-power attack)
+            (Element::Volcanic, Element::Parasitic, 40),
+            (Element::Volcanic, Element::Abyssal, 10),
+            (Element::Volcanic, Element::Volcanic, 10),
+            (Element::Abyssal, Element::Volcanic, 40),
+            (Element::Storm, Element::Abyssal, 40),
+            (Element::Void, Element::Storm, 20),
+            (Element::Void, Element::Void, 40),
+        ];
+        for (attacker, defender, expected) in cases {
+            let (attacker, defender) = (fighter(attacker, 3), fighter(defender, 3));
+            assert_eq!(
+                damage(20, Move::Attack, &attacker, &defender, attacker.effectiveness(&defender), false, false),
+                expected,
+                "{:?} vs {:?}",
+                attacker.element,
+                defender.element
+            );
+            assert_eq!(
+                attacker.effectiveness(&defender),
+                expected as f64 / 20.0,
+                "{:?} vs {:?}",
+                attacker.element,
+                defender.element
+            );
+        }
+
+        // Without a Titan on one side the hit is neutral
+        let missing = Fighter::new(None, 3);
+        assert_eq!(damage(20, Move::Attack, &missing, &volcanic, missing.effectiveness(&volcanic), false, false), 20);
+        assert_eq!(damage(20, Move::Attack, &volcanic, &missing, volcanic.effectiveness(&missing), false, false), 20);
+
+        // A configured multiplier stands in for the chart
+        assert_eq!(damage(20, Move::Attack, &volcanic, &volcanic, 1.5, false, false), 30);
+    }
+
+    #[test]
+    fn test_damage_scales_with_stats() {
+        let weak = fighter(Element::Void, 1);
+        let strong = fighter(Element::Storm, 5);
+
+        // 100 attack into 60 defense, and back
+        assert_eq!(damage(20, Move::Attack, &strong, &weak, strong.effectiveness(&weak), false, false), 33);
+        assert_eq!(damage(20, Move::Attack, &weak, &strong, weak.effectiveness(&strong), false, false), 12);
+        // Special uses the special stat: 125 into 60 defense hits the ratio cap
+        assert_eq!(damage(20, Move::Special, &strong, &weak, strong.effectiveness(&weak), false, false), 40);
+        assert_eq!(damage(1, Move::Attack, &weak, &strong, weak.effectiveness(&strong), false, true), 1);
+    }
+
+    #[test]
+    fn test_defend_and_crit() {
+        let attacker = fighter(Element::Void, 3);
+        let defender = fighter(Element::Storm, 3);
+        let cases = [
+            // (critical, defending, damage of a 20-power attack)
+            (false, false, 20),
+            (false, true, 10),
+            (true, false, 30),
+            (true, true, 15),
+        ];
+        for (critical, defending, expected) in cases {
+            assert_eq!(
+                damage(20, Move::Attack, &attacker, &defender, attacker.effectiveness(&defender), critical, defending),
+                expected,
+                "critical {} defending {}",
+                critical,
+                defending
+            );
+        }
+    }
+
+    #[test]
+    fn test_crit_chance_follows_speed() {
+        let (slow, fast) = (battle_stats(1), battle_stats(5));
+        assert_eq!(crit_chance(&slow, &slow), BASE_CRIT_CHANCE);
+        assert!(crit_chance(&fast, &slow) > BASE_CRIT_CHANCE);
+        assert!(crit_chance(&slow, &fast) < BASE_CRIT_CHANCE);
+
+        let frozen = TitanBattleStats { speed: 0, ..battle_stats(1) };
+        assert_eq!(crit_chance(&fast, &frozen), MAX_CRIT_CHANCE);
+        assert_eq!(crit_chance(&frozen, &fast), MIN_CRIT_CHANCE);
+    }
+
+    #[test]
+    fn test_rolls_repeat_with_a_seed() {
+        let (attacker, defender) = (fighter(Element::Abyssal, 2), fighter(Element::Ossified, 4));
+        let effectiveness = attacker.effectiveness(&defender);
+        let roll = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..20)
+                .map(|_| roll_hit(&mut rng, Move::Special, &attacker, &defender, effectiveness, false))
+                .collect::<Vec<_>>()
+        };
+
+        let hits = roll(7);
+        assert_eq!(hits, roll(7));
+        for hit in &hits {
+            assert_eq!(hit.effectiveness, effectiveness);
+            let most = damage(39, Move::Special, &attacker, &defender, effectiveness, hit.critical, false);
+            let least = damage(25, Move::Special, &attacker, &defender, effectiveness, hit.critical, false);
+            assert!((least..=most).contains(&hit.damage), "{:?}", hit);
+        }
+    }
+}
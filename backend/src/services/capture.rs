@@ -1,28 +1,201 @@
 //! Capture authorization service
 
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use redis::AsyncCommands;
 use sha2::{Digest, Sha256};
+use solana_sdk::signature::{Keypair, Signature, Signer};
 use uuid::Uuid;
 
-use crate::config::AppConfig;
+use crate::config::{AppConfig, CaptureModifierConfig, SharedGameConfig};
 use crate::db::Database;
 use crate::error::{ApiResult, AppError};
 use crate::models::{
-    CaptureAuthorization, CaptureRequest, TitanCaptureData, TitanSpawn,
+    base_capture_chance, AppliedModifier, CaptureAttempt, CaptureAttemptState, CaptureAuthorization, CaptureChallenge, CaptureModifiers,
+    CaptureRequest, CooperativeCapture, Element, GuildWarEvent, QuestEvent, TitanCaptureData, TitanSpawn,
 };
 use crate::services::location::haversine_distance;
+use crate::services::{GuildService, LocationService, QuestService};
+use crate::utils::geo::local_solar_hour;
+
+/// Redis key marking a recent escape: `capture_attempt:{player_id}:{titan_id}`
+fn capture_attempt_key(player_id: Uuid, titan_id: Uuid) -> String {
+    format!("capture_attempt:{}:{}", player_id, titan_id)
+}
+
+/// Whole seconds until `ends_at`, rounded up; `None` once it has passed
+fn cooldown_remaining_secs(ends_at: DateTime<Utc>, now: DateTime<Utc>) -> Option<u64> {
+    let remaining_ms = (ends_at - now).num_milliseconds();
+    (remaining_ms > 0).then(|| (remaining_ms as u64).div_ceil(1000))
+}
+
+/// Redis key marking a capture challenge as used: `capture_challenge:{nonce}`
+fn capture_challenge_key(nonce: &str) -> String {
+    format!("capture_challenge:{}", nonce)
+}
+
+/// Redis key for a confirm's `Idempotency-Key`, scoped to the player:
+/// `capture_confirm:{player_id}:{key}`
+fn confirm_idempotency_key(player_id: Uuid, key: &str) -> String {
+    format!("capture_confirm:{}:{}", player_id, key)
+}
+
+/// Held under the idempotency key while the first confirm runs
+const CONFIRM_IN_PROGRESS: &str = "in_progress";
+
+/// How long a confirm may hold its idempotency key before a retry can run
+const CONFIRM_IN_PROGRESS_TTL_SECS: u64 = 120;
+
+/// How long a confirm's response is replayed for its idempotency key
+const CONFIRM_RESPONSE_TTL_SECS: u64 = 600;
+
+/// Redis hash with a cooperative capture of a spawn: `coop_capture:{titan_id}`
+fn cooperative_capture_key(titan_id: Uuid) -> String {
+    format!("coop_capture:{}", titan_id)
+}
+
+/// Join a cooperative capture, opening it when there is none: ARGV is player
+/// ID, wallet, players required, timeout seconds and now (unix seconds). A new
+/// session expires with its timeout. Returns `joined` or `full`, followed by
+/// the session hash.
+const COOPERATIVE_JOIN_SCRIPT: &str = r#"
+if redis.call('EXISTS', KEYS[1]) == 0 then
+    redis.call('HSET', KEYS[1], 'leader', ARGV[1], 'required', ARGV[3],
+        'expires_at', tonumber(ARGV[5]) + tonumber(ARGV[4]))
+    redis.call('EXPIRE', KEYS[1], ARGV[4])
+end
+local status = 'joined'
+local field = 'p:' .. ARGV[1]
+if redis.call('HEXISTS', KEYS[1], field) == 0 then
+    local joined = 0
+    for _, name in ipairs(redis.call('HKEYS', KEYS[1])) do
+        if string.sub(name, 1, 2) == 'p:' then joined = joined + 1 end
+    end
+    if joined >= tonumber(redis.call('HGET', KEYS[1], 'required')) then
+        status = 'full'
+    else
+        redis.call('HSET', KEYS[1], field, ARGV[2])
+    end
+end
+local reply = redis.call('HGETALL', KEYS[1])
+table.insert(reply, 1, status)
+return reply
+"#;
+
+/// Confirm a cooperative capture for player ARGV[1]. Returns `missing`,
+/// `not_joined` or `not_full` alone; otherwise `settle` for the one confirm
+/// that completes it or `waiting`, followed by the session hash.
+const COOPERATIVE_CONFIRM_SCRIPT: &str = r#"
+if redis.call('EXISTS', KEYS[1]) == 0 then return {'missing'} end
+if redis.call('HEXISTS', KEYS[1], 'p:' .. ARGV[1]) == 0 then return {'not_joined'} end
+local joined, confirmed = 0, 0
+for _, name in ipairs(redis.call('HKEYS', KEYS[1])) do
+    if string.sub(name, 1, 2) == 'p:' then joined = joined + 1 end
+end
+if joined < tonumber(redis.call('HGET', KEYS[1], 'required')) then return {'not_full'} end
+redis.call('HSET', KEYS[1], 'c:' .. ARGV[1], 1)
+for _, name in ipairs(redis.call('HKEYS', KEYS[1])) do
+    if string.sub(name, 1, 2) == 'c:' then confirmed = confirmed + 1 end
+end
+local status = 'waiting'
+if confirmed == joined and redis.call('HSETNX', KEYS[1], 'settling', 1) == 1 then
+    status = 'settle'
+end
+local reply = redis.call('HGETALL', KEYS[1])
+table.insert(reply, 1, status)
+return reply
+"#;
+
+/// Outcome of a player's cooperative capture confirm
+#[derive(Debug)]
+pub enum CooperativeConfirm {
+    /// Others still have to confirm
+    Waiting(CooperativeCapture),
+    /// Everyone confirmed; this caller mints and pays out
+    Settle(CooperativeCapture),
+}
+
+/// Redis hash with a player's capture streak: `capture_streak:{player_id}`
+fn capture_streak_key(player_id: Uuid) -> String {
+    format!("capture_streak:{}", player_id)
+}
+
+/// Count a capture into the streak hash (`streak`, `last_capture_at` in unix
+/// seconds): one more when the last capture is at most ARGV[2] seconds old,
+/// otherwise a new streak of one. The hash expires with the streak.
+const STREAK_SCRIPT: &str = r#"
+local now = tonumber(ARGV[1])
+local break_seconds = tonumber(ARGV[2])
+local last = tonumber(redis.call('HGET', KEYS[1], 'last_capture_at'))
+local streak = tonumber(redis.call('HGET', KEYS[1], 'streak')) or 0
+if last == nil or now - last > break_seconds then
+    streak = 0
+end
+streak = streak + 1
+redis.call('HSET', KEYS[1], 'streak', streak, 'last_capture_at', now)
+redis.call('EXPIRE', KEYS[1], break_seconds)
+return streak
+"#;
 
 /// Capture authorization service
 #[derive(Clone)]
 pub struct CaptureService {
     config: AppConfig,
+    /// Reloadable; read this rather than `config.game`
+    game: SharedGameConfig,
     db: Database,
+    location: LocationService,
+    quest: QuestService,
+    /// Scores captures in guild wars
+    guild: GuildService,
+    /// Signs capture challenges (the backend keypair)
+    challenge_signer: Arc<Keypair>,
+    /// Rolls escapes; seeded from OS entropy so clients can't predict rolls
+    escape_rng: Arc<Mutex<StdRng>>,
 }
 
 impl CaptureService {
-    pub fn new(config: AppConfig, db: Database) -> Self {
-        Self { config, db }
+    pub fn new(
+        config: AppConfig,
+        game: SharedGameConfig,
+        db: Database,
+        location: LocationService,
+        quest: QuestService,
+        guild: GuildService,
+        challenge_signer: Arc<Keypair>,
+    ) -> Self {
+        Self {
+            config,
+            game,
+            db,
+            location,
+            quest,
+            guild,
+            challenge_signer,
+            escape_rng: Arc::new(Mutex::new(StdRng::from_entropy())),
+        }
+    }
+
+    /// Chance a capture roll succeeds: the threat class's base chance scaled
+    /// by the capture modifiers and items in play, with escapes growing rarer
+    /// as the Titan's HP drops. `remaining_hp` is a fraction of full HP; a
+    /// Titan at none escapes half as often as a healthy one.
+    pub fn capture_success_rate(
+        threat_class: i16,
+        modifiers: &[AppliedModifier],
+        remaining_hp: f64,
+        config: &CaptureModifierConfig,
+    ) -> f64 {
+        let multiplier: f64 = modifiers.iter().map(|m| m.chance_multiplier).product();
+        let chance = (base_capture_chance(threat_class, config) * multiplier).min(1.0);
+        let escape_chance = (1.0 - chance) * (0.5 + 0.5 * remaining_hp.clamp(0.0, 1.0));
+        1.0 - escape_chance
     }
 
     /// Process a capture request and generate authorization
@@ -32,6 +205,9 @@ impl CaptureService {
         wallet_address: &str,
         request: CaptureRequest,
     ) -> ApiResult<CaptureAuthorization> {
+        // 0. Reject taps from badly skewed device clocks
+        request.check_tap_time()?;
+
         // 1. Get the Titan
         let titan = self.get_titan(request.titan_id).await?;
 
@@ -45,6 +221,9 @@ impl CaptureService {
                 error: Some("Titan already captured".to_string()),
                 distance: None,
                 max_distance: None,
+                capture_chance: None,
+                modifiers: Vec::new(),
+                challenge: None,
             });
         }
 
@@ -57,10 +236,46 @@ impl CaptureService {
                 error: Some("Titan expired".to_string()),
                 distance: None,
                 max_distance: None,
+                capture_chance: None,
+                modifiers: Vec::new(),
+                challenge: None,
+            });
+        }
+
+        // Titans that take several players only go through cooperative capture
+        let co_captors = self.co_captors(titan.threat_class).await;
+        if co_captors > 1 {
+            return Ok(CaptureAuthorization {
+                authorized: false,
+                signature: None,
+                expires_at: None,
+                titan: None,
+                error: Some(format!("Titan takes a cooperative capture by {} players", co_captors)),
+                distance: None,
+                max_distance: None,
+                capture_chance: None,
+                modifiers: Vec::new(),
+                challenge: None,
+            });
+        }
+
+        // 3. Calculate distance, unless repeated speed violations have made
+        // the player's location untrustworthy
+        if self.location.is_quarantined(player_id).await? {
+            return Ok(CaptureAuthorization {
+                authorized: false,
+                signature: None,
+                expires_at: None,
+                titan: None,
+                error: Some("Location quarantined after speed violations".to_string()),
+                distance: None,
+                max_distance: None,
+                capture_chance: None,
+                modifiers: Vec::new(),
+                challenge: None,
             });
         }
 
-        // 3. Calculate distance
         let distance = haversine_distance(
             request.player_location.lat,
             request.player_location.lng,
@@ -68,7 +283,7 @@ impl CaptureService {
             titan.location_lng,
         );
 
-        let max_distance = self.config.game.capture_radius_meters;
+        let max_distance = self.game.read().await.capture_radius_meters;
 
         if distance > max_distance {
             return Ok(CaptureAuthorization {
@@ -79,24 +294,45 @@ impl CaptureService {
                 error: Some("Too far from Titan".to_string()),
                 distance: Some(distance),
                 max_distance: Some(max_distance),
+                capture_chance: None,
+                modifiers: Vec::new(),
+                challenge: None,
             });
         }
 
         // 4. Check cooldown
-        let on_cooldown = self.check_player_cooldown(player_id).await?;
-        if on_cooldown {
-            return Ok(CaptureAuthorization {
-                authorized: false,
-                signature: None,
-                expires_at: None,
-                titan: None,
-                error: Some("Capture on cooldown".to_string()),
-                distance: Some(distance),
-                max_distance: Some(max_distance),
-            });
+        if let Some(retry_after_secs) = self.check_player_cooldown(player_id).await? {
+            return Err(AppError::TooManyRequests { retry_after_secs });
+        }
+
+        // 5. Check the cooldown for retrying this Titan after an escape
+        if let Some(retry_after_secs) = self.check_attempt_cooldown(player_id, titan.id).await? {
+            return Err(AppError::TooManyRequests { retry_after_secs });
         }
 
-        // 5. Generate signature
+        // 6. Roll the capture chance. Wild spawns don't take damage, so they
+        // roll at full HP. An escape from a Titan with captures to spare only
+        // starts its attempt cooldown; otherwise the global one
+        let modifiers = self.capture_modifiers(&titan, player_id).await?;
+        let capture_chance = Self::capture_success_rate(
+            titan.threat_class,
+            &modifiers.applied,
+            1.0,
+            &self.config.capture_modifiers,
+        );
+
+        if self.roll_escape(capture_chance) {
+            if titan.max_captures > 1 {
+                self.start_attempt_cooldown(player_id, titan.id).await?;
+            } else {
+                self.start_cooldown(player_id).await?;
+            }
+
+            let escaped_count = self.record_escape(titan.id).await?;
+            return Err(AppError::CaptureEscaped { capture_chance, escaped_count });
+        }
+
+        // 7. Generate signature
         let expires_at = Utc::now() + Duration::seconds(self.config.auth.signature_expiry_seconds as i64);
         let signature = self.generate_capture_signature(
             wallet_address,
@@ -104,7 +340,7 @@ impl CaptureService {
             expires_at.timestamp(),
         );
 
-        // 6. Return authorization
+        // 8. Return authorization
         Ok(CaptureAuthorization {
             authorized: true,
             signature: Some(signature),
@@ -119,9 +355,321 @@ impl CaptureService {
             error: None,
             distance: Some(distance),
             max_distance: Some(max_distance),
+            capture_chance: Some(capture_chance),
+            modifiers: modifiers.applied,
+            challenge: Some(self.issue_challenge(player_id, titan.id)),
         })
     }
 
+    /// Players it takes to capture a Titan of `threat_class`
+    async fn co_captors(&self, threat_class: i16) -> u8 {
+        let index = (threat_class.clamp(1, 5) - 1) as usize;
+        self.game.read().await.max_co_captors[index]
+    }
+
+    /// Join the cooperative capture of a Titan that takes several players,
+    /// opening one when there is none. Joining a capture already joined just
+    /// returns it.
+    pub async fn join_cooperative(
+        &self,
+        player_id: Uuid,
+        wallet_address: &str,
+        request: CaptureRequest,
+    ) -> ApiResult<CooperativeCapture> {
+        request.check_tap_time()?;
+
+        let titan = self.get_titan(request.titan_id).await?;
+        if titan.captured_by.is_some() && titan.capture_count >= titan.max_captures {
+            return Err(AppError::TitanAlreadyCaptured);
+        }
+        if Utc::now() > titan.expires_at {
+            return Err(AppError::TitanExpired);
+        }
+
+        let required = self.co_captors(titan.threat_class).await;
+        if required <= 1 {
+            return Err(AppError::BadRequest(
+                "Titan can be captured alone; use /capture/request".into(),
+            ));
+        }
+
+        if self.location.is_quarantined(player_id).await? {
+            return Err(AppError::BadRequest(
+                "Location quarantined after speed violations".into(),
+            ));
+        }
+
+        let distance = haversine_distance(
+            request.player_location.lat,
+            request.player_location.lng,
+            titan.location_lat,
+            titan.location_lng,
+        );
+        let (max_distance, timeout) = {
+            let game = self.game.read().await;
+            (game.capture_radius_meters, game.cooperative_capture_timeout_seconds)
+        };
+        if distance > max_distance {
+            return Err(AppError::TooFarFromTarget);
+        }
+
+        if let Some(retry_after_secs) = self.check_player_cooldown(player_id).await? {
+            return Err(AppError::TooManyRequests { retry_after_secs });
+        }
+
+        let mut conn = self.db.redis.clone();
+        let reply: Vec<String> = redis::Script::new(COOPERATIVE_JOIN_SCRIPT)
+            .key(cooperative_capture_key(titan.id))
+            .arg(player_id.to_string())
+            .arg(wallet_address)
+            .arg(required)
+            .arg(timeout)
+            .arg(Utc::now().timestamp())
+            .invoke_async(&mut conn)
+            .await
+            .map_err(|e| AppError::Internal(e.into()))?;
+
+        let (status, session) = parse_cooperative_reply(titan.id, &reply)?;
+        match (status, session) {
+            ("joined", Some(session)) => Ok(session),
+            ("full", _) => Err(AppError::CooperativeCaptureFull),
+            _ => Err(AppError::Internal(anyhow::anyhow!("Unexpected cooperative join reply: {:?}", reply))),
+        }
+    }
+
+    /// Confirm a joined cooperative capture once it has all its players. The
+    /// last confirm to arrive gets `Settle` and must finish the capture and
+    /// then `end_cooperative`.
+    pub async fn confirm_cooperative(
+        &self,
+        player_id: Uuid,
+        titan_id: Uuid,
+    ) -> ApiResult<CooperativeConfirm> {
+        let mut conn = self.db.redis.clone();
+        let reply: Vec<String> = redis::Script::new(COOPERATIVE_CONFIRM_SCRIPT)
+            .key(cooperative_capture_key(titan_id))
+            .arg(player_id.to_string())
+            .invoke_async(&mut conn)
+            .await
+            .map_err(|e| AppError::Internal(e.into()))?;
+
+        match parse_cooperative_reply(titan_id, &reply)? {
+            ("settle", Some(session)) => Ok(CooperativeConfirm::Settle(session)),
+            ("waiting", Some(session)) => Ok(CooperativeConfirm::Waiting(session)),
+            ("missing", _) => Err(AppError::NotFound(
+                "No cooperative capture for this Titan; it may have timed out".into(),
+            )),
+            ("not_joined", _) => Err(AppError::Forbidden(
+                "Not a participant of this cooperative capture".into(),
+            )),
+            ("not_full", _) => Err(AppError::BadRequest(
+                "Cooperative capture is still waiting for players".into(),
+            )),
+            _ => Err(AppError::Internal(anyhow::anyhow!("Unexpected cooperative confirm reply: {:?}", reply))),
+        }
+    }
+
+    /// Remove a settled cooperative capture
+    pub async fn end_cooperative(&self, titan_id: Uuid) -> ApiResult<()> {
+        let mut conn = self.db.redis.clone();
+        let _: () = conn
+            .del(cooperative_capture_key(titan_id))
+            .await
+            .map_err(|e| AppError::Internal(e.into()))?;
+        Ok(())
+    }
+
+    /// Roll against `capture_chance`; true when the Titan escapes
+    fn roll_escape(&self, capture_chance: f64) -> bool {
+        let roll: f64 = self.escape_rng.lock().unwrap().gen();
+        roll >= capture_chance
+    }
+
+    /// Count an escape against the spawn and return its new escape count
+    async fn record_escape(&self, titan_id: Uuid) -> ApiResult<i32> {
+        let escaped_count = sqlx::query_scalar::<_, i32>(
+            r#"
+            UPDATE titan_spawns SET escaped_count = escaped_count + 1
+            WHERE id = $1
+            RETURNING escaped_count
+            "#,
+        )
+        .bind(titan_id)
+        .fetch_one(&self.db.pg)
+        .await?;
+
+        Ok(escaped_count)
+    }
+
+    /// Sign a challenge for an authorized capture
+    fn issue_challenge(&self, player_id: Uuid, titan_id: Uuid) -> CaptureChallenge {
+        let mut challenge = CaptureChallenge {
+            titan_id,
+            issued_at: Utc::now(),
+            nonce: Uuid::new_v4().simple().to_string(),
+            signature: String::new(),
+        };
+        challenge.signature = self
+            .challenge_signer
+            .sign_message(challenge.message(player_id).as_bytes())
+            .to_string();
+        challenge
+    }
+
+    /// Check a challenge presented on confirm / build-transaction: issued by
+    /// this backend to this player for this Titan, still fresh, and not used
+    /// before. A second use is only accepted to resume the capture attempt
+    /// the first one started (a confirm retried after a timeout).
+    pub async fn verify_challenge(
+        &self,
+        player_id: Uuid,
+        titan_id: Uuid,
+        challenge: &CaptureChallenge,
+    ) -> ApiResult<()> {
+        let ttl = self.game.read().await.capture_challenge_ttl_seconds;
+        if challenge.titan_id != titan_id || !challenge.is_fresh(Utc::now(), ttl) {
+            return Err(AppError::InvalidSignature);
+        }
+
+        let signature =
+            Signature::from_str(&challenge.signature).map_err(|_| AppError::InvalidSignature)?;
+        let signer = self.challenge_signer.pubkey();
+        if !signature.verify(signer.as_ref(), challenge.message(player_id).as_bytes()) {
+            return Err(AppError::InvalidSignature);
+        }
+
+        let mut conn = self.db.redis.clone();
+        let first_use: Option<String> = redis::cmd("SET")
+            .arg(capture_challenge_key(&challenge.nonce))
+            .arg(player_id.to_string())
+            .arg("NX")
+            .arg("EX")
+            .arg(ttl)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| AppError::Internal(e.into()))?;
+
+        if first_use.is_none() && !self.has_capture_attempt(player_id, titan_id).await? {
+            return Err(AppError::InvalidSignature);
+        }
+        Ok(())
+    }
+
+    async fn has_capture_attempt(&self, player_id: Uuid, titan_spawn_id: Uuid) -> ApiResult<bool> {
+        let exists = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS(SELECT 1 FROM capture_attempts WHERE player_id = $1 AND titan_spawn_id = $2)
+            "#,
+        )
+        .bind(player_id)
+        .bind(titan_spawn_id)
+        .fetch_one(&self.db.pg)
+        .await?;
+
+        Ok(exists)
+    }
+
+    /// Run a confirm at most once per player and `Idempotency-Key`
+    ///
+    /// The first call claims the key and stores its response for
+    /// 10 minutes; repeats get the stored response without running `confirm`
+    /// again. A repeat while the first call is still running fails with
+    /// `IdempotencyKeyInUse`, and a failed confirm releases the key so the
+    /// client can retry it.
+    pub async fn confirm_once<T, F, Fut>(
+        &self,
+        player_id: Uuid,
+        key: &str,
+        confirm: F,
+    ) -> ApiResult<serde_json::Value>
+    where
+        T: serde::Serialize,
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = ApiResult<T>>,
+    {
+        let redis_key = confirm_idempotency_key(player_id, key);
+        let mut conn = self.db.redis.clone();
+
+        let claimed: Option<String> = redis::cmd("SET")
+            .arg(&redis_key)
+            .arg(CONFIRM_IN_PROGRESS)
+            .arg("NX")
+            .arg("EX")
+            .arg(CONFIRM_IN_PROGRESS_TTL_SECS)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| AppError::Internal(e.into()))?;
+
+        if claimed.is_none() {
+            let stored: Option<String> = conn
+                .get(&redis_key)
+                .await
+                .map_err(|e| AppError::Internal(e.into()))?;
+            return match stored {
+                Some(stored) if stored != CONFIRM_IN_PROGRESS => serde_json::from_str(&stored)
+                    .map_err(|e| AppError::Internal(e.into())),
+                // Still running, or it finished failing between SET and GET
+                _ => Err(AppError::IdempotencyKeyInUse),
+            };
+        }
+
+        let result = confirm().await.and_then(|response| {
+            serde_json::to_value(response).map_err(|e| AppError::Internal(e.into()))
+        });
+
+        let stored = match &result {
+            Ok(response) => conn
+                .set_ex::<_, _, ()>(&redis_key, response.to_string(), CONFIRM_RESPONSE_TTL_SECS)
+                .await,
+            Err(_) => conn.del::<_, ()>(&redis_key).await,
+        };
+        if let Err(e) = stored {
+            tracing::warn!("Failed to store confirm result for idempotency key: {}", e);
+        }
+
+        result
+    }
+
+    /// Time-of-day and crowding modifiers for a player capturing a spawn
+    pub async fn capture_modifiers(
+        &self,
+        titan: &TitanSpawn,
+        player_id: Uuid,
+    ) -> ApiResult<CaptureModifiers> {
+        let config = &self.config.capture_modifiers;
+        let solar_hour = local_solar_hour(Utc::now(), titan.location_lng);
+
+        let recent_captures = if config.crowding_enabled {
+            self.recent_capture_count(titan.id, player_id).await?
+        } else {
+            0
+        };
+
+        Ok(CaptureModifiers::evaluate(
+            titan.species_id,
+            titan.threat_class,
+            solar_hour,
+            recent_captures,
+            config,
+        ))
+    }
+
+    /// Other players who captured this spawn in the last hour
+    async fn recent_capture_count(&self, spawn_id: Uuid, player_id: Uuid) -> ApiResult<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(DISTINCT player_id) FROM spawn_captures
+            WHERE spawn_id = $1 AND player_id <> $2 AND captured_at > NOW() - INTERVAL '1 hour'
+            "#,
+        )
+        .bind(spawn_id)
+        .bind(player_id)
+        .fetch_one(&self.db.pg)
+        .await?;
+
+        Ok(count)
+    }
+
     /// Get a Titan by ID
     async fn get_titan(&self, titan_id: Uuid) -> ApiResult<TitanSpawn> {
         sqlx::query_as::<_, TitanSpawn>(
@@ -135,8 +683,8 @@ impl CaptureService {
         .ok_or(AppError::TitanNotFound)
     }
 
-    /// Check if player is on cooldown
-    async fn check_player_cooldown(&self, player_id: Uuid) -> ApiResult<bool> {
+    /// Seconds left on the player's capture cooldown, `None` when off cooldown
+    async fn check_player_cooldown(&self, player_id: Uuid) -> ApiResult<Option<u64>> {
         let result = sqlx::query_scalar::<_, Option<chrono::DateTime<chrono::Utc>>>(
             r#"
             SELECT last_capture_at FROM players WHERE id = $1
@@ -146,14 +694,72 @@ impl CaptureService {
         .fetch_one(&self.db.pg)
         .await?;
 
-        if let Some(last_capture) = result {
-            let cooldown = Duration::seconds(self.config.game.capture_cooldown_seconds as i64);
-            if Utc::now() - last_capture < cooldown {
-                return Ok(true);
-            }
+        let cooldown = Duration::seconds(self.game.read().await.capture_cooldown_seconds as i64);
+        Ok(result
+            .and_then(|last_capture| cooldown_remaining_secs(last_capture + cooldown, Utc::now())))
+    }
+
+    /// Start the capture cooldown without a capture (failed attempt)
+    async fn start_cooldown(&self, player_id: Uuid) -> ApiResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE players SET last_capture_at = NOW() WHERE id = $1
+            "#,
+        )
+        .bind(player_id)
+        .execute(&self.db.pg)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Count a confirmed capture into the player's streak and return its
+    /// length; `None` when streaks are disabled
+    pub async fn record_capture_streak(&self, player_id: Uuid) -> ApiResult<Option<u32>> {
+        let config = &self.config.capture_modifiers;
+        if !config.streak_enabled || config.streak_break_seconds == 0 {
+            return Ok(None);
         }
 
-        Ok(false)
+        let mut conn = self.db.redis.clone();
+        let streak: u32 = redis::Script::new(STREAK_SCRIPT)
+            .key(capture_streak_key(player_id))
+            .arg(Utc::now().timestamp())
+            .arg(config.streak_break_seconds)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(|e| AppError::Internal(e.into()))?;
+        Ok(Some(streak))
+    }
+
+    /// Seconds left on the player's attempt cooldown for one Titan, `None` when off cooldown
+    async fn check_attempt_cooldown(
+        &self,
+        player_id: Uuid,
+        titan_id: Uuid,
+    ) -> ApiResult<Option<u64>> {
+        let mut conn = self.db.redis.clone();
+        let ttl: i64 = conn
+            .ttl(capture_attempt_key(player_id, titan_id))
+            .await
+            .map_err(|e| AppError::Internal(e.into()))?;
+        // -2 = no key, -1 = no expiry (never set by us)
+        Ok((ttl > 0).then_some(ttl as u64))
+    }
+
+    /// Start the attempt cooldown for one Titan after an escape
+    async fn start_attempt_cooldown(&self, player_id: Uuid, titan_id: Uuid) -> ApiResult<()> {
+        let seconds = self.game.read().await.capture_attempt_cooldown_seconds;
+        if seconds == 0 {
+            return Ok(());
+        }
+
+        let mut conn = self.db.redis.clone();
+        let _: () = conn
+            .set_ex(capture_attempt_key(player_id, titan_id), "1", seconds)
+            .await
+            .map_err(|e| AppError::Internal(e.into()))?;
+        Ok(())
     }
 
     /// Generate a capture authorization signature
@@ -214,23 +820,171 @@ impl CaptureService {
         signature == expected
     }
 
-    /// Mark a Titan as captured (called after blockchain confirmation)
-    pub async fn confirm_capture(
+    /// Record (or resume) a player's confirm of a spawn before anything is
+    /// minted; a failed attempt starts over from its last minted state
+    pub async fn begin_capture_attempt(
         &self,
-        titan_id: Uuid,
         player_id: Uuid,
-    ) -> ApiResult<()> {
-        // Update Titan
+        titan_spawn_id: Uuid,
+    ) -> ApiResult<CaptureAttempt> {
+        let mut tx = self.db.pg.begin().await?;
+
         sqlx::query(
             r#"
-            UPDATE titan_spawns 
-            SET captured_by = $2, captured_at = NOW(), capture_count = capture_count + 1
-            WHERE id = $1
+            INSERT INTO capture_attempts (player_id, titan_spawn_id) VALUES ($1, $2)
+            ON CONFLICT (player_id, titan_spawn_id) DO NOTHING
             "#,
         )
-        .bind(titan_id)
         .bind(player_id)
-        .execute(&self.db.pg)
+        .bind(titan_spawn_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let mut attempt = sqlx::query_as::<_, CaptureAttempt>(
+            r#"
+            SELECT * FROM capture_attempts
+            WHERE player_id = $1 AND titan_spawn_id = $2
+            FOR UPDATE
+            "#,
+        )
+        .bind(player_id)
+        .bind(titan_spawn_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if attempt.state == CaptureAttemptState::Failed {
+            attempt = sqlx::query_as::<_, CaptureAttempt>(
+                r#"
+                UPDATE capture_attempts
+                SET state = CASE WHEN mint_address IS NULL
+                        THEN 'pending'::capture_attempt_state
+                        ELSE 'minted'::capture_attempt_state END,
+                    error = NULL, updated_at = NOW()
+                WHERE id = $1
+                RETURNING *
+                "#,
+            )
+            .bind(attempt.id)
+            .fetch_one(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(attempt)
+    }
+
+    /// Record a successful mint so a retry doesn't mint again
+    pub async fn mark_minted(
+        &self,
+        attempt_id: Uuid,
+        tx_signature: &str,
+        mint_address: &str,
+        token_account: Option<&str>,
+    ) -> ApiResult<CaptureAttempt> {
+        let attempt = sqlx::query_as::<_, CaptureAttempt>(
+            r#"
+            UPDATE capture_attempts
+            SET state = 'minted', tx_signature = $2, mint_address = $3, token_account = $4,
+                updated_at = NOW()
+            WHERE id = $1 AND state = 'pending'
+            RETURNING *
+            "#,
+        )
+        .bind(attempt_id)
+        .bind(tx_signature)
+        .bind(mint_address)
+        .bind(token_account)
+        .fetch_optional(&self.db.pg)
+        .await?;
+
+        match attempt {
+            Some(attempt) => Ok(attempt),
+            None => self.get_capture_attempt(attempt_id).await,
+        }
+    }
+
+    /// Close a shadow-banned player's attempt without capturing anything:
+    /// the spawn stays free and nothing is minted or paid. `remaining_captures`
+    /// is what the player is shown, as if the capture had happened.
+    pub async fn shadow_capture(&self, attempt_id: Uuid, remaining_captures: i32) -> ApiResult<CaptureAttempt> {
+        let attempt = sqlx::query_as::<_, CaptureAttempt>(
+            r#"
+            UPDATE capture_attempts
+            SET state = 'failed', error = 'shadow_banned', remaining_captures = $2, updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(attempt_id)
+        .bind(remaining_captures)
+        .fetch_optional(&self.db.pg)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Capture attempt not found".to_string()))?;
+
+        tracing::info!("Shadow-banned capture attempt {} skipped", attempt_id);
+
+        Ok(attempt)
+    }
+
+    /// Write the capture (spawn count, capture log, player stats, guild war
+    /// score) and confirm the attempt in one transaction, then count it
+    /// toward the player's quests; a confirmed attempt is returned as is
+    pub async fn finalize_capture(&self, attempt_id: Uuid) -> ApiResult<CaptureAttempt> {
+        let mut tx = self.db.pg.begin().await?;
+
+        let attempt = sqlx::query_as::<_, CaptureAttempt>(
+            r#"
+            SELECT * FROM capture_attempts WHERE id = $1 FOR UPDATE
+            "#,
+        )
+        .bind(attempt_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Capture attempt not found".to_string()))?;
+
+        if attempt.is_confirmed() {
+            return Ok(attempt);
+        }
+
+        // Claim a capture slot; None when the spawn filled up meanwhile
+        let claimed = sqlx::query_as::<_, (i32, Element, i16)>(
+            r#"
+            UPDATE titan_spawns
+            SET captured_by = $2, captured_at = NOW(), capture_count = capture_count + 1
+            WHERE id = $1 AND capture_count < max_captures
+            RETURNING max_captures - capture_count, element, threat_class
+            "#,
+        )
+        .bind(attempt.titan_spawn_id)
+        .bind(attempt.player_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some((remaining_captures, element, threat_class)) = claimed else {
+            sqlx::query(
+                r#"
+                UPDATE capture_attempts
+                SET state = 'failed', error = 'Titan already captured', updated_at = NOW()
+                WHERE id = $1
+                "#,
+            )
+            .bind(attempt_id)
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+
+            return Err(AppError::TitanAlreadyCaptured);
+        };
+
+        // Log the capture for the crowding modifier
+        sqlx::query(
+            r#"
+            INSERT INTO spawn_captures (spawn_id, player_id) VALUES ($1, $2)
+            "#,
+        )
+        .bind(attempt.titan_spawn_id)
+        .bind(attempt.player_id)
+        .execute(&mut *tx)
         .await?;
 
         // Update player stats
@@ -241,10 +995,133 @@ impl CaptureService {
             WHERE id = $1
             "#,
         )
-        .bind(player_id)
+        .bind(attempt.player_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let war = GuildService::record_war_capture(&mut tx, attempt.player_id).await?;
+
+        let attempt = sqlx::query_as::<_, CaptureAttempt>(
+            r#"
+            UPDATE capture_attempts
+            SET state = 'confirmed', remaining_captures = $2, updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(attempt_id)
+        .bind(remaining_captures)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        if let Some(war) = war {
+            self.guild.publish_war(GuildWarEvent::Scored, war).await;
+        }
+        self.quest
+            .record_event_or_warn(attempt.player_id, QuestEvent::TitanCaptured { element, threat_class })
+            .await;
+        Ok(attempt)
+    }
+
+    /// Store the $BREACH reward paid for a confirmed capture so retries
+    /// report it instead of paying again
+    pub async fn record_capture_reward(
+        &self,
+        attempt_id: Uuid,
+        breach_reward: u64,
+        breach_tx_signature: &str,
+    ) -> ApiResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE capture_attempts
+            SET breach_reward = $2, breach_tx_signature = $3, updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(attempt_id)
+        .bind(breach_reward as i64)
+        .bind(breach_tx_signature)
         .execute(&self.db.pg)
         .await?;
 
         Ok(())
     }
+
+    async fn get_capture_attempt(&self, attempt_id: Uuid) -> ApiResult<CaptureAttempt> {
+        sqlx::query_as::<_, CaptureAttempt>(
+            r#"
+            SELECT * FROM capture_attempts WHERE id = $1
+            "#,
+        )
+        .bind(attempt_id)
+        .fetch_optional(&self.db.pg)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Capture attempt not found".to_string()))
+    }
+}
+
+/// Split a cooperative script reply into its status and the session hash
+/// that follows it
+fn parse_cooperative_reply(
+    titan_id: Uuid,
+    reply: &[String],
+) -> ApiResult<(&str, Option<CooperativeCapture>)> {
+    let Some((status, hash)) = reply.split_first() else {
+        return Err(AppError::Internal(anyhow::anyhow!("Empty cooperative capture reply")));
+    };
+    if hash.is_empty() {
+        return Ok((status, None));
+    }
+
+    let fields: HashMap<String, String> = hash
+        .chunks_exact(2)
+        .map(|pair| (pair[0].clone(), pair[1].clone()))
+        .collect();
+    let session = CooperativeCapture::from_hash(titan_id, &fields).ok_or_else(|| {
+        AppError::Internal(anyhow::anyhow!("Malformed cooperative capture {}", titan_id))
+    })?;
+    Ok((status, Some(session)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cooldown_remaining_rounds_up() {
+        let now = Utc::now();
+        assert_eq!(cooldown_remaining_secs(now + Duration::seconds(40), now), Some(40));
+        assert_eq!(cooldown_remaining_secs(now + Duration::milliseconds(39_001), now), Some(40));
+        assert_eq!(cooldown_remaining_secs(now + Duration::milliseconds(1), now), Some(1));
+        assert_eq!(cooldown_remaining_secs(now, now), None);
+        assert_eq!(cooldown_remaining_secs(now - Duration::seconds(5), now), None);
+    }
+
+    #[test]
+    fn test_capture_success_rate() {
+        let config = AppConfig::default().capture_modifiers;
+        let night = AppliedModifier {
+            source: crate::models::CaptureModifierSource::NightActivity { solar_hour: 0.0 },
+            chance_multiplier: 1.2,
+            reward_multiplier: 1.0,
+        };
+
+        // Full HP rolls at the modified base chance
+        let rate = CaptureService::capture_success_rate(5, &[], 1.0, &config);
+        assert!((rate - 0.35).abs() < 1e-9);
+        let rate = CaptureService::capture_success_rate(5, &[night], 1.0, &config);
+        assert!((rate - 0.42).abs() < 1e-9);
+
+        // A Titan out of HP escapes half as often
+        let rate = CaptureService::capture_success_rate(5, &[], 0.0, &config);
+        assert!((rate - 0.675).abs() < 1e-9);
+        let rate = CaptureService::capture_success_rate(5, &[], -3.0, &config);
+        assert!((rate - 0.675).abs() < 1e-9);
+
+        // Never more than certain
+        let boosted = AppliedModifier { chance_multiplier: 5.0, ..night };
+        assert_eq!(CaptureService::capture_success_rate(1, &[boosted], 1.0, &config), 1.0);
+    }
 }
@@ -1,94 +1,99 @@
 //! Chat service - Real-time messaging functionality
 
-use chrono::{Duration, Utc};
-use redis::AsyncCommands;
-use sqlx::Row;
+use chrono::{DateTime, Duration, Utc};
 use uuid::Uuid;
 
 use crate::db::Database;
 use crate::error::{ApiResult, AppError};
 use crate::models::{
-    ChatChannel, ChatChannelType, ChatMessage, ChatReport, ChannelResponse, LastMessageInfo,
-    MessageResponse, MessagesQuery, ParticipantInfo, ReplyInfo, ReportMessageRequest,
-    SendMessageRequest,
+    ChatChannel, ChatChannelType, ChatMessage, ChatReport, ChannelResponse, ChannelsPage,
+    ChannelsQuery, LastMessageInfo, MessageResponse, MessagesPage, MessagesQuery, ModerationAction,
+    ParticipantInfo, PlayerSanction, ReplyInfo, ReportMessageRequest, ReportsQuery, ResolveReportRequest,
+    ResolvedReport, SendMessageRequest,
 };
 
+use super::{PlayerService, PresenceService};
+
 /// Maximum message length
 const MAX_MESSAGE_LENGTH: usize = 1000;
 
-/// Redis key prefix for online status
-const ONLINE_STATUS_PREFIX: &str = "player:online:";
+/// Most channels returned per page
+const MAX_PAGE_SIZE: i64 = 100;
+
+/// Longest mute an admin can give (30 days)
+const MAX_MUTE_HOURS: i64 = 720;
 
-/// Online status TTL in seconds (5 minutes)
-const ONLINE_STATUS_TTL: i64 = 300;
+/// Values of `chat_reports.status`
+const REPORT_STATUSES: [&str; 4] = ["pending", "reviewed", "action_taken", "dismissed"];
 
 /// Chat service
 #[derive(Clone)]
 pub struct ChatService {
     db: Database,
+    presence: PresenceService,
+    player: PlayerService,
 }
 
 impl ChatService {
-    pub fn new(db: Database) -> Self {
-        Self { db }
-    }
-    
-    /// Set player online status in Redis
-    pub async fn set_player_online(&self, player_id: Uuid) -> ApiResult<()> {
-        let key = format!("{}{}", ONLINE_STATUS_PREFIX, player_id);
-        let mut conn = self.db.redis.clone();
-        let _: () = conn.set_ex(&key, "1", ONLINE_STATUS_TTL as u64).await
-            .map_err(|e| AppError::Internal(e.into()))?;
-        Ok(())
-    }
-    
-    /// Check if a player is online
-    pub async fn is_player_online(&self, player_id: Uuid) -> bool {
-        let key = format!("{}{}", ONLINE_STATUS_PREFIX, player_id);
-        let mut conn = self.db.redis.clone();
-        let result: Result<Option<String>, _> = conn.get(&key).await;
-        result.map(|v| v.is_some()).unwrap_or(false)
-    }
-    
-    /// Refresh player online status (heartbeat)
-    pub async fn refresh_online_status(&self, player_id: Uuid) -> ApiResult<()> {
-        self.set_player_online(player_id).await
-    }
-    
-    /// Set player offline
-    pub async fn set_player_offline(&self, player_id: Uuid) -> ApiResult<()> {
-        let key = format!("{}{}", ONLINE_STATUS_PREFIX, player_id);
-        let mut conn = self.db.redis.clone();
-        let _: () = conn.del(&key).await
-            .map_err(|e| AppError::Internal(e.into()))?;
-        Ok(())
+    pub fn new(db: Database, presence: PresenceService, player: PlayerService) -> Self {
+        Self { db, presence, player }
     }
 
     // ============================================
     // Channels
     // ============================================
 
-    /// Get player's channels with unread counts
-    pub async fn get_player_channels(&self, player_id: Uuid) -> ApiResult<Vec<ChannelResponse>> {
-        let rows = sqlx::query(
+    /// Get a page of the player's channels, most recently active first, with
+    /// unread counts, last-message previews and private chat partners
+    pub async fn get_player_channels(
+        &self,
+        player_id: Uuid,
+        query: ChannelsQuery,
+    ) -> ApiResult<ChannelsPage> {
+        let limit = query.limit.clamp(1, MAX_PAGE_SIZE);
+        let offset = query.offset.max(0);
+
+        // One extra row tells whether there is another page
+        let mut rows = sqlx::query!(
             r#"
             SELECT 
-                c.id, c.channel_type, c.name, c.guild_id, c.participant1_id, c.participant2_id,
-                c.last_message_at, c.created_at,
-                g.name as guild_name,
-                COALESCE(rs.muted, FALSE) as is_muted,
-                -- Get participant info for private channels
-                CASE 
-                    WHEN c.channel_type = 'private' THEN
-                        CASE 
-                            WHEN c.participant1_id = $1 THEN c.participant2_id 
-                            ELSE c.participant1_id 
-                        END
-                    ELSE NULL
-                END as other_participant_id
+                c.id, c.channel_type as "channel_type: ChatChannelType", c.name, c.guild_id,
+                c.created_at,
+                g.name as "guild_name?",
+                COALESCE(rs.muted, FALSE) as "is_muted!",
+                unread.count as "unread_count!",
+                last.content as "last_content?",
+                last.created_at as "last_sent_at?",
+                last.username as "last_sender_username?",
+                op.id as "participant_id?",
+                op.username as "participant_username?",
+                COALESCE(op.level, 1) as "participant_level!"
             FROM chat_channels c
             LEFT JOIN guilds g ON c.guild_id = g.id
             LEFT JOIN chat_read_status rs ON rs.channel_id = c.id AND rs.player_id = $1
+            LEFT JOIN chat_messages last_read ON last_read.id = rs.last_read_message_id
+            -- The other participant of a private channel
+            LEFT JOIN players op ON c.channel_type = 'private' AND op.id = CASE 
+                WHEN c.participant1_id = $1 THEN c.participant2_id 
+                ELSE c.participant1_id 
+            END
+            -- Same rules as get_unread_count()
+            CROSS JOIN LATERAL (
+                SELECT COUNT(*)::INT as count
+                FROM chat_messages m
+                WHERE m.channel_id = c.id
+                  AND m.sender_id != $1
+                  AND m.is_deleted = FALSE
+                  AND (rs.last_read_message_id IS NULL OR m.created_at > last_read.created_at)
+            ) unread
+            LEFT JOIN LATERAL (
+                SELECT m.content, m.created_at, p.username
+                FROM chat_messages m
+                JOIN players p ON m.sender_id = p.id
+                WHERE m.channel_id = c.id AND m.is_deleted = FALSE
+                ORDER BY m.created_at DESC
+                LIMIT 1
+            ) last ON TRUE
             WHERE c.is_active = TRUE
               AND (
                   c.channel_type IN ('world', 'trade', 'help')
@@ -97,93 +102,80 @@ impl ChatService {
                       SELECT guild_id FROM guild_members WHERE player_id = $1
                   ))
               )
-            ORDER BY c.last_message_at DESC NULLS LAST
-            "#
+            ORDER BY c.last_message_at DESC NULLS LAST, c.id
+            LIMIT $2 OFFSET $3
+            "#,
+            player_id,
+            limit + 1,
+            offset
         )
-        .bind(player_id)
         .fetch_all(&self.db.pg)
         .await?;
 
-        let mut channels = Vec::new();
-
-        for row in rows {
-            let channel_id: Uuid = row.get("id");
-            let channel_type: ChatChannelType = row.get("channel_type");
-            
-            // Get unread count
-            let unread_count: i32 = sqlx::query_scalar(
-                "SELECT get_unread_count($1, $2)::INT"
-            )
-            .bind(player_id)
-            .bind(channel_id)
-            .fetch_one(&self.db.pg)
-            .await
-            .unwrap_or(0);
-
-            // Get last message
-            let last_message: Option<LastMessageInfo> = sqlx::query(
-                r#"
-                SELECT m.content, m.created_at, p.username
-                FROM chat_messages m
-                JOIN players p ON m.sender_id = p.id
-                WHERE m.channel_id = $1 AND m.is_deleted = FALSE
-                ORDER BY m.created_at DESC
-                LIMIT 1
-                "#
-            )
-            .bind(channel_id)
-            .fetch_optional(&self.db.pg)
-            .await?
-            .map(|r| LastMessageInfo {
-                sender_username: r.get("username"),
-                content_preview: truncate_string(r.get::<String, _>("content"), 50),
-                sent_at: r.get("created_at"),
-            });
-
-            // Get private chat participant info
-            let participant: Option<ParticipantInfo> = if channel_type == ChatChannelType::Private {
-                let other_id: Option<Uuid> = row.get("other_participant_id");
-                if let Some(pid) = other_id {
-                    let player_row = sqlx::query(
-                        "SELECT id, username, level FROM players WHERE id = $1"
-                    )
-                    .bind(pid)
-                    .fetch_optional(&self.db.pg)
-                    .await?;
-                    
-                    if let Some(r) = player_row {
-                        let is_online = self.is_player_online(pid).await;
-                        Some(ParticipantInfo {
-                            id: r.get("id"),
-                            username: r.get("username"),
-                            level: r.get("level"),
-                            is_online,
-                        })
-                    } else {
-                        None
-                    }
-                } else {
-                    None
+        let next_offset = (rows.len() as i64 > limit).then_some(offset + limit);
+        rows.truncate(limit as usize);
+
+        let participant_ids: Vec<Uuid> = rows.iter().filter_map(|row| row.participant_id).collect();
+        let online = self.presence.get_online(&participant_ids).await;
+
+        let channels = rows
+            .into_iter()
+            .map(|row| {
+                let last_message = match (row.last_content, row.last_sent_at) {
+                    (Some(content), Some(sent_at)) => Some(LastMessageInfo {
+                        sender_username: row.last_sender_username,
+                        content_preview: truncate_string(content, 50),
+                        sent_at,
+                    }),
+                    _ => None,
+                };
+
+                let participant = row.participant_id.map(|id| ParticipantInfo {
+                    id,
+                    username: row.participant_username,
+                    level: row.participant_level,
+                    is_online: online.contains(&id),
+                });
+
+                ChannelResponse {
+                    id: row.id,
+                    channel_type: row.channel_type,
+                    name: row.name,
+                    guild_id: row.guild_id,
+                    guild_name: row.guild_name,
+                    participant,
+                    unread_count: row.unread_count,
+                    last_message,
+                    is_muted: row.is_muted,
+                    created_at: row.created_at,
                 }
-            } else {
-                None
-            };
+            })
+            .collect();
 
-            channels.push(ChannelResponse {
-                id: channel_id,
-                channel_type,
-                name: row.get("name"),
-                guild_id: row.get("guild_id"),
-                guild_name: row.get("guild_name"),
-                participant,
-                unread_count,
-                last_message,
-                is_muted: row.get("is_muted"),
-                created_at: row.get("created_at"),
-            });
-        }
+        Ok(ChannelsPage { channels, next_offset })
+    }
+
+    /// IDs of every channel the player can read, for live subscriptions
+    pub async fn get_player_channel_ids(&self, player_id: Uuid) -> ApiResult<Vec<Uuid>> {
+        let ids = sqlx::query_scalar!(
+            r#"
+            SELECT c.id
+            FROM chat_channels c
+            WHERE c.is_active = TRUE
+              AND (
+                  c.channel_type IN ('world', 'trade', 'help')
+                  OR (c.channel_type = 'private' AND (c.participant1_id = $1 OR c.participant2_id = $1))
+                  OR (c.channel_type = 'guild' AND c.guild_id IN (
+                      SELECT guild_id FROM guild_members WHERE player_id = $1
+                  ))
+              )
+            "#,
+            player_id
+        )
+        .fetch_all(&self.db.pg)
+        .await?;
 
-        Ok(channels)
+        Ok(ids)
     }
 
     /// Get or create private channel
@@ -197,11 +189,11 @@ impl ChatService {
         }
 
         // Check if blocked
-        let is_blocked: bool = sqlx::query_scalar(
-            "SELECT EXISTS(SELECT 1 FROM chat_blocked_users WHERE blocker_id = $1 AND blocked_id = $2)"
+        let is_blocked = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM chat_blocked_users WHERE blocker_id = $1 AND blocked_id = $2) as "blocked!""#,
+            other_player_id,
+            player_id
         )
-        .bind(other_player_id)
-        .bind(player_id)
         .fetch_one(&self.db.pg)
         .await?;
 
@@ -210,18 +202,24 @@ impl ChatService {
         }
 
         // Get or create channel
-        let channel_id: Uuid = sqlx::query_scalar(
-            "SELECT get_or_create_private_channel($1, $2)"
+        let channel_id = sqlx::query_scalar!(
+            r#"SELECT get_or_create_private_channel($1, $2) as "id!""#,
+            player_id,
+            other_player_id
         )
-        .bind(player_id)
-        .bind(other_player_id)
         .fetch_one(&self.db.pg)
         .await?;
 
-        let channel = sqlx::query_as::<_, ChatChannel>(
-            "SELECT * FROM chat_channels WHERE id = $1"
+        let channel = sqlx::query_as!(
+            ChatChannel,
+            r#"
+            SELECT id, channel_type as "channel_type: ChatChannelType", name, participant1_id,
+                   participant2_id, guild_id, is_active, created_at, last_message_at
+            FROM chat_channels
+            WHERE id = $1
+            "#,
+            channel_id
         )
-        .bind(channel_id)
         .fetch_one(&self.db.pg)
         .await?;
 
@@ -231,10 +229,16 @@ impl ChatService {
     /// Get guild channel (creates if not exists)
     pub async fn get_guild_channel(&self, guild_id: Uuid) -> ApiResult<ChatChannel> {
         // Try to get existing channel
-        let existing = sqlx::query_as::<_, ChatChannel>(
-            "SELECT * FROM chat_channels WHERE guild_id = $1 AND channel_type = 'guild'"
+        let existing = sqlx::query_as!(
+            ChatChannel,
+            r#"
+            SELECT id, channel_type as "channel_type: ChatChannelType", name, participant1_id,
+                   participant2_id, guild_id, is_active, created_at, last_message_at
+            FROM chat_channels
+            WHERE guild_id = $1 AND channel_type = 'guild'
+            "#,
+            guild_id
         )
-        .bind(guild_id)
         .fetch_optional(&self.db.pg)
         .await?;
 
@@ -243,25 +247,24 @@ impl ChatService {
         }
 
         // Get guild name
-        let guild_name: Option<String> = sqlx::query_scalar(
-            "SELECT name FROM guilds WHERE id = $1"
-        )
-        .bind(guild_id)
-        .fetch_optional(&self.db.pg)
+        let guild_name = sqlx::query_scalar!("SELECT name FROM guilds WHERE id = $1", guild_id)
+            .fetch_optional(&self.db.pg)
         .await?;
 
         let guild_name = guild_name.ok_or_else(|| AppError::NotFound("Guild not found".into()))?;
 
         // Create channel
-        let channel = sqlx::query_as::<_, ChatChannel>(
+        let channel = sqlx::query_as!(
+            ChatChannel,
             r#"
             INSERT INTO chat_channels (channel_type, name, guild_id)
             VALUES ('guild', $1, $2)
-            RETURNING *
-            "#
+            RETURNING id, channel_type as "channel_type: ChatChannelType", name, participant1_id,
+                      participant2_id, guild_id, is_active, created_at, last_message_at
+            "#,
+            format!("{} Guild Chat", guild_name),
+            guild_id
         )
-        .bind(format!("{} Guild Chat", guild_name))
-        .bind(guild_id)
         .fetch_one(&self.db.pg)
         .await?;
 
@@ -294,11 +297,24 @@ impl ChatService {
         // Verify channel access
         self.verify_channel_access(sender_id, channel_id).await?;
 
+        if let Some(until) = self.muted_until(sender_id).await? {
+            return Err(AppError::Forbidden(format!(
+                "You are muted until {}",
+                until.to_rfc3339()
+            )));
+        }
+
         // Check if blocked in channel (private only)
-        let channel = sqlx::query_as::<_, ChatChannel>(
-            "SELECT * FROM chat_channels WHERE id = $1"
+        let channel = sqlx::query_as!(
+            ChatChannel,
+            r#"
+            SELECT id, channel_type as "channel_type: ChatChannelType", name, participant1_id,
+                   participant2_id, guild_id, is_active, created_at, last_message_at
+            FROM chat_channels
+            WHERE id = $1
+            "#,
+            channel_id
         )
-        .bind(channel_id)
         .fetch_one(&self.db.pg)
         .await?;
 
@@ -310,11 +326,11 @@ impl ChatService {
             };
 
             if let Some(oid) = other_id {
-                let is_blocked: bool = sqlx::query_scalar(
-                    "SELECT EXISTS(SELECT 1 FROM chat_blocked_users WHERE blocker_id = $1 AND blocked_id = $2)"
+                let is_blocked = sqlx::query_scalar!(
+                    r#"SELECT EXISTS(SELECT 1 FROM chat_blocked_users WHERE blocker_id = $1 AND blocked_id = $2) as "blocked!""#,
+                    oid,
+                    sender_id
                 )
-                .bind(oid)
-                .bind(sender_id)
                 .fetch_one(&self.db.pg)
                 .await?;
 
@@ -325,23 +341,24 @@ impl ChatService {
         }
 
         // Insert message
-        let message = sqlx::query_as::<_, ChatMessage>(
+        let message = sqlx::query_as!(
+            ChatMessage,
             r#"
             INSERT INTO chat_messages (channel_id, sender_id, content, reply_to_id)
             VALUES ($1, $2, $3, $4)
-            RETURNING *
-            "#
+            RETURNING id, channel_id, sender_id, content, is_system, is_edited, is_deleted, reply_to_id,
+                      created_at, edited_at
+            "#,
+            channel_id,
+            sender_id,
+            content,
+            req.reply_to_id
         )
-        .bind(channel_id)
-        .bind(sender_id)
-        .bind(content)
-        .bind(req.reply_to_id)
         .fetch_one(&self.db.pg)
         .await?;
 
         // Update channel last message time
-        sqlx::query("UPDATE chat_channels SET last_message_at = NOW() WHERE id = $1")
-            .bind(channel_id)
+        sqlx::query!("UPDATE chat_channels SET last_message_at = NOW() WHERE id = $1", channel_id)
             .execute(&self.db.pg)
             .await?;
 
@@ -357,54 +374,66 @@ impl ChatService {
         player_id: Uuid,
         channel_id: Uuid,
         query: MessagesQuery,
-    ) -> ApiResult<Vec<MessageResponse>> {
+    ) -> ApiResult<MessagesPage> {
         // Verify access
         self.verify_channel_access(player_id, channel_id).await?;
 
         // Get blocked user list
-        let blocked_ids: Vec<Uuid> = sqlx::query_scalar(
-            "SELECT blocked_id FROM chat_blocked_users WHERE blocker_id = $1"
+        let blocked_ids = sqlx::query_scalar!(
+            "SELECT blocked_id FROM chat_blocked_users WHERE blocker_id = $1",
+            player_id
         )
-        .bind(player_id)
         .fetch_all(&self.db.pg)
         .await?;
 
         let messages = if let Some(before_id) = query.before_id {
-            sqlx::query_as::<_, ChatMessage>(
+            sqlx::query_as!(
+                ChatMessage,
                 r#"
-                SELECT * FROM chat_messages
+                SELECT id, channel_id, sender_id, content, is_system, is_edited, is_deleted,
+                       reply_to_id, created_at, edited_at
+                FROM chat_messages
                 WHERE channel_id = $1
                   AND is_deleted = FALSE
                   AND sender_id != ALL($2)
                   AND created_at < (SELECT created_at FROM chat_messages WHERE id = $3)
                 ORDER BY created_at DESC
                 LIMIT $4
-                "#
+                "#,
+                channel_id,
+                &blocked_ids,
+                before_id,
+                query.limit
             )
-            .bind(channel_id)
-            .bind(&blocked_ids)
-            .bind(before_id)
-            .bind(query.limit)
             .fetch_all(&self.db.pg)
             .await?
         } else {
-            sqlx::query_as::<_, ChatMessage>(
+            sqlx::query_as!(
+                ChatMessage,
                 r#"
-                SELECT * FROM chat_messages
+                SELECT id, channel_id, sender_id, content, is_system, is_edited, is_deleted,
+                       reply_to_id, created_at, edited_at
+                FROM chat_messages
                 WHERE channel_id = $1
                   AND is_deleted = FALSE
                   AND sender_id != ALL($2)
                 ORDER BY created_at DESC
                 LIMIT $3
-                "#
+                "#,
+                channel_id,
+                &blocked_ids,
+                query.limit
             )
-            .bind(channel_id)
-            .bind(&blocked_ids)
-            .bind(query.limit)
             .fetch_all(&self.db.pg)
             .await?
         };
 
+        // A full page may have older messages behind it
+        let next_cursor = match messages.last() {
+            Some(oldest) if messages.len() as i64 >= query.limit => Some(oldest.id),
+            _ => None,
+        };
+
         let mut responses = Vec::new();
         for msg in messages {
             responses.push(self.build_message_response(msg).await?);
@@ -413,7 +442,7 @@ impl ChatService {
         // Return in chronological order
         responses.reverse();
 
-        Ok(responses)
+        Ok(MessagesPage { messages: responses, next_cursor })
     }
 
     /// Edit a message
@@ -432,11 +461,17 @@ impl ChatService {
         }
 
         // Verify ownership
-        let message = sqlx::query_as::<_, ChatMessage>(
-            "SELECT * FROM chat_messages WHERE id = $1 AND sender_id = $2 AND is_deleted = FALSE"
+        let message = sqlx::query_as!(
+            ChatMessage,
+            r#"
+            SELECT id, channel_id, sender_id, content, is_system, is_edited, is_deleted,
+                   reply_to_id, created_at, edited_at
+            FROM chat_messages
+            WHERE id = $1 AND sender_id = $2 AND is_deleted = FALSE
+            "#,
+            message_id,
+            player_id
         )
-        .bind(message_id)
-        .bind(player_id)
         .fetch_optional(&self.db.pg)
         .await?;
 
@@ -449,16 +484,18 @@ impl ChatService {
         }
 
         // Update message
-        let updated = sqlx::query_as::<_, ChatMessage>(
+        let updated = sqlx::query_as!(
+            ChatMessage,
             r#"
             UPDATE chat_messages
             SET content = $1, is_edited = TRUE, edited_at = NOW()
             WHERE id = $2
-            RETURNING *
-            "#
+            RETURNING id, channel_id, sender_id, content, is_system, is_edited, is_deleted, reply_to_id,
+                      created_at, edited_at
+            "#,
+            new_content,
+            message_id
         )
-        .bind(new_content)
-        .bind(message_id)
         .fetch_one(&self.db.pg)
         .await?;
 
@@ -467,15 +504,15 @@ impl ChatService {
 
     /// Delete a message
     pub async fn delete_message(&self, player_id: Uuid, message_id: Uuid) -> ApiResult<()> {
-        let result = sqlx::query(
+        let result = sqlx::query!(
             r#"
             UPDATE chat_messages
             SET is_deleted = TRUE
             WHERE id = $1 AND sender_id = $2 AND is_deleted = FALSE
-            "#
+            "#,
+            message_id,
+            player_id
         )
-        .bind(message_id)
-        .bind(player_id)
         .execute(&self.db.pg)
         .await?;
 
@@ -488,10 +525,10 @@ impl ChatService {
     
     /// Get channel ID for a message
     pub async fn get_message_channel_id(&self, message_id: Uuid) -> ApiResult<Uuid> {
-        let channel_id: Option<Uuid> = sqlx::query_scalar(
-            "SELECT channel_id FROM chat_messages WHERE id = $1"
+        let channel_id = sqlx::query_scalar!(
+            "SELECT channel_id FROM chat_messages WHERE id = $1",
+            message_id
         )
-        .bind(message_id)
         .fetch_optional(&self.db.pg)
         .await?;
         
@@ -505,24 +542,24 @@ impl ChatService {
     /// Mark channel as read
     pub async fn mark_as_read(&self, player_id: Uuid, channel_id: Uuid) -> ApiResult<()> {
         // Get latest message ID
-        let last_message_id: Option<Uuid> = sqlx::query_scalar(
-            "SELECT id FROM chat_messages WHERE channel_id = $1 ORDER BY created_at DESC LIMIT 1"
+        let last_message_id = sqlx::query_scalar!(
+            "SELECT id FROM chat_messages WHERE channel_id = $1 ORDER BY created_at DESC LIMIT 1",
+            channel_id
         )
-        .bind(channel_id)
         .fetch_optional(&self.db.pg)
         .await?;
 
-        sqlx::query(
+        sqlx::query!(
             r#"
             INSERT INTO chat_read_status (channel_id, player_id, last_read_message_id, last_read_at)
             VALUES ($1, $2, $3, NOW())
             ON CONFLICT (channel_id, player_id)
             DO UPDATE SET last_read_message_id = $3, last_read_at = NOW()
-            "#
+            "#,
+            channel_id,
+            player_id,
+            last_message_id
         )
-        .bind(channel_id)
-        .bind(player_id)
-        .bind(last_message_id)
         .execute(&self.db.pg)
         .await?;
 
@@ -538,17 +575,17 @@ impl ChatService {
     ) -> ApiResult<()> {
         let muted_until = duration_hours.map(|h| Utc::now() + Duration::hours(h));
 
-        sqlx::query(
+        sqlx::query!(
             r#"
             INSERT INTO chat_read_status (channel_id, player_id, muted, muted_until)
             VALUES ($1, $2, TRUE, $3)
             ON CONFLICT (channel_id, player_id)
             DO UPDATE SET muted = TRUE, muted_until = $3
-            "#
+            "#,
+            channel_id,
+            player_id,
+            muted_until
         )
-        .bind(channel_id)
-        .bind(player_id)
-        .bind(muted_until)
         .execute(&self.db.pg)
         .await?;
 
@@ -557,15 +594,15 @@ impl ChatService {
 
     /// Unmute a channel
     pub async fn unmute_channel(&self, player_id: Uuid, channel_id: Uuid) -> ApiResult<()> {
-        sqlx::query(
+        sqlx::query!(
             r#"
             UPDATE chat_read_status
             SET muted = FALSE, muted_until = NULL
             WHERE channel_id = $1 AND player_id = $2
-            "#
+            "#,
+            channel_id,
+            player_id
         )
-        .bind(channel_id)
-        .bind(player_id)
         .execute(&self.db.pg)
         .await?;
 
@@ -582,11 +619,11 @@ impl ChatService {
             return Err(AppError::BadRequest("Cannot block yourself".into()));
         }
 
-        sqlx::query(
-            "INSERT INTO chat_blocked_users (blocker_id, blocked_id) VALUES ($1, $2) ON CONFLICT DO NOTHING"
+        sqlx::query!(
+            "INSERT INTO chat_blocked_users (blocker_id, blocked_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+            blocker_id,
+            blocked_id
         )
-        .bind(blocker_id)
-        .bind(blocked_id)
         .execute(&self.db.pg)
         .await?;
 
@@ -595,10 +632,12 @@ impl ChatService {
 
     /// Unblock a user
     pub async fn unblock_user(&self, blocker_id: Uuid, blocked_id: Uuid) -> ApiResult<()> {
-        sqlx::query("DELETE FROM chat_blocked_users WHERE blocker_id = $1 AND blocked_id = $2")
-            .bind(blocker_id)
-            .bind(blocked_id)
-            .execute(&self.db.pg)
+        sqlx::query!(
+            "DELETE FROM chat_blocked_users WHERE blocker_id = $1 AND blocked_id = $2",
+            blocker_id,
+            blocked_id
+        )
+        .execute(&self.db.pg)
             .await?;
 
         Ok(())
@@ -606,10 +645,10 @@ impl ChatService {
 
     /// Get blocked users
     pub async fn get_blocked_users(&self, player_id: Uuid) -> ApiResult<Vec<Uuid>> {
-        let blocked = sqlx::query_scalar(
-            "SELECT blocked_id FROM chat_blocked_users WHERE blocker_id = $1"
+        let blocked = sqlx::query_scalar!(
+            "SELECT blocked_id FROM chat_blocked_users WHERE blocker_id = $1",
+            player_id
         )
-        .bind(player_id)
         .fetch_all(&self.db.pg)
         .await?;
 
@@ -628,10 +667,16 @@ impl ChatService {
         req: ReportMessageRequest,
     ) -> ApiResult<ChatReport> {
         // Get message and sender
-        let message = sqlx::query_as::<_, ChatMessage>(
-            "SELECT * FROM chat_messages WHERE id = $1"
+        let message = sqlx::query_as!(
+            ChatMessage,
+            r#"
+            SELECT id, channel_id, sender_id, content, is_system, is_edited, is_deleted,
+                   reply_to_id, created_at, edited_at
+            FROM chat_messages
+            WHERE id = $1
+            "#,
+            message_id
         )
-        .bind(message_id)
         .fetch_optional(&self.db.pg)
         .await?;
 
@@ -641,34 +686,224 @@ impl ChatService {
             return Err(AppError::BadRequest("Cannot report your own message".into()));
         }
 
-        let report = sqlx::query_as::<_, ChatReport>(
+        let report = sqlx::query_as!(
+            ChatReport,
             r#"
             INSERT INTO chat_reports (reporter_id, reported_id, message_id, reason, description)
             VALUES ($1, $2, $3, $4, $5)
-            RETURNING *
-            "#
+            RETURNING id, reporter_id, reported_id, message_id, reason, description, status,
+                      admin_notes, reviewed_by, reviewed_at, created_at
+            "#,
+            reporter_id,
+            message.sender_id,
+            message_id,
+            req.reason,
+            req.description
         )
-        .bind(reporter_id)
-        .bind(message.sender_id)
-        .bind(message_id)
-        .bind(&req.reason)
-        .bind(&req.description)
         .fetch_one(&self.db.pg)
         .await?;
 
         Ok(report)
     }
 
+    // ============================================
+    // Moderation
+    // ============================================
+
+    /// Reports with `status`, oldest first so the queue is worked in order
+    pub async fn get_reports(&self, query: ReportsQuery) -> ApiResult<Vec<ChatReport>> {
+        if !REPORT_STATUSES.contains(&query.status.as_str()) {
+            return Err(AppError::Validation(format!(
+                "Status must be one of {}",
+                REPORT_STATUSES.join(", ")
+            )));
+        }
+
+        let reports = sqlx::query_as!(
+            ChatReport,
+            r#"
+            SELECT id, reporter_id, reported_id, message_id, reason, description, status,
+                   admin_notes, reviewed_by, reviewed_at, created_at
+            FROM chat_reports
+            WHERE status = $1
+            ORDER BY created_at
+            LIMIT $2
+            "#,
+            query.status,
+            query.limit.clamp(1, MAX_PAGE_SIZE)
+        )
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        Ok(reports)
+    }
+
+    /// Act on a pending report: dismiss it, delete the reported message, or
+    /// mute or ban its sender
+    pub async fn resolve_report(
+        &self,
+        admin_id: Uuid,
+        report_id: Uuid,
+        req: ResolveReportRequest,
+    ) -> ApiResult<ResolvedReport> {
+        if let ModerationAction::MutePlayer { hours } = req.action {
+            if !(1..=MAX_MUTE_HOURS).contains(&hours) {
+                return Err(AppError::Validation(format!(
+                    "Mute must be 1-{} hours",
+                    MAX_MUTE_HOURS
+                )));
+            }
+        }
+
+        let mut tx = self.db.pg.begin().await?;
+
+        // Locked so two admins can't resolve the same report
+        let report = sqlx::query_as!(
+            ChatReport,
+            r#"
+            SELECT id, reporter_id, reported_id, message_id, reason, description, status,
+                   admin_notes, reviewed_by, reviewed_at, created_at
+            FROM chat_reports WHERE id = $1 FOR UPDATE
+            "#,
+            report_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Report not found".into()))?;
+
+        if report.status != "pending" {
+            return Err(AppError::BadRequest("Report already resolved".into()));
+        }
+
+        let mut sanction = None;
+        let mut deleted_from_channel = None;
+        match req.action {
+            ModerationAction::Dismiss => {}
+            ModerationAction::DeleteMessage => {
+                let message_id = report
+                    .message_id
+                    .ok_or_else(|| AppError::BadRequest("Reported message no longer exists".into()))?;
+                deleted_from_channel = sqlx::query_scalar!(
+                    r#"
+                    UPDATE chat_messages SET is_deleted = TRUE
+                    WHERE id = $1 AND is_deleted = FALSE
+                    RETURNING channel_id
+                    "#,
+                    message_id
+                )
+                .fetch_optional(&mut *tx)
+                .await?;
+            }
+            ModerationAction::MutePlayer { hours } => {
+                let expires_at = Utc::now() + Duration::hours(hours);
+                sanction = Some(
+                    Self::insert_sanction(&mut tx, &report, admin_id, "mute", Some(expires_at)).await?,
+                );
+            }
+            ModerationAction::BanPlayer => {
+                sanction = Some(Self::insert_sanction(&mut tx, &report, admin_id, "ban", None).await?);
+                // Inside the transaction so a failed ban leaves the report pending
+                PlayerService::ban_player_in(&mut tx, report.reported_id, &format!("Chat report: {}", report.reason))
+                    .await?;
+            }
+        }
+
+        let status = match req.action {
+            ModerationAction::Dismiss => "dismissed",
+            _ => "action_taken",
+        };
+        let report = sqlx::query_as!(
+            ChatReport,
+            r#"
+            UPDATE chat_reports
+            SET status = $2, admin_notes = $3, reviewed_by = $4, reviewed_at = NOW()
+            WHERE id = $1
+            RETURNING id, reporter_id, reported_id, message_id, reason, description, status,
+                      admin_notes, reviewed_by, reviewed_at, created_at
+            "#,
+            report_id,
+            status,
+            req.notes,
+            admin_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        // Only a committed ban is cached, or a rolled-back one would stick
+        if matches!(req.action, ModerationAction::BanPlayer) {
+            self.player.cache_ban(report.reported_id).await?;
+        }
+
+        tracing::info!(
+            "Chat report {} resolved by {}: {:?}",
+            report_id,
+            admin_id,
+            req.action
+        );
+
+        Ok(ResolvedReport { report, sanction, deleted_from_channel })
+    }
+
+    /// When the player's chat mute ends, if they're muted
+    pub async fn muted_until(&self, player_id: Uuid) -> ApiResult<Option<DateTime<Utc>>> {
+        let until = sqlx::query_scalar!(
+            r#"
+            SELECT MAX(expires_at) FROM player_sanctions
+            WHERE player_id = $1 AND sanction_type = 'mute' AND expires_at > NOW()
+            "#,
+            player_id
+        )
+        .fetch_one(&self.db.pg)
+        .await?;
+
+        Ok(until)
+    }
+
+    async fn insert_sanction(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        report: &ChatReport,
+        admin_id: Uuid,
+        sanction_type: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> ApiResult<PlayerSanction> {
+        let sanction = sqlx::query_as!(
+            PlayerSanction,
+            r#"
+            INSERT INTO player_sanctions (player_id, sanction_type, reason, report_id, issued_by, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, player_id, sanction_type, reason, report_id, issued_by, expires_at, created_at
+            "#,
+            report.reported_id,
+            sanction_type,
+            report.reason,
+            report.id,
+            admin_id,
+            expires_at
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(sanction)
+    }
+
     // ============================================
     // Helper Methods
     // ============================================
 
     /// Verify channel access
     async fn verify_channel_access(&self, player_id: Uuid, channel_id: Uuid) -> ApiResult<()> {
-        let channel = sqlx::query_as::<_, ChatChannel>(
-            "SELECT * FROM chat_channels WHERE id = $1 AND is_active = TRUE"
+        let channel = sqlx::query_as!(
+            ChatChannel,
+            r#"
+            SELECT id, channel_type as "channel_type: ChatChannelType", name, participant1_id,
+                   participant2_id, guild_id, is_active, created_at, last_message_at
+            FROM chat_channels
+            WHERE id = $1 AND is_active = TRUE
+            "#,
+            channel_id
         )
-        .bind(channel_id)
         .fetch_optional(&self.db.pg)
         .await?;
 
@@ -682,11 +917,11 @@ impl ChatService {
             }
             ChatChannelType::Guild => {
                 if let Some(guild_id) = channel.guild_id {
-                    sqlx::query_scalar::<_, bool>(
-                        "SELECT EXISTS(SELECT 1 FROM guild_members WHERE guild_id = $1 AND player_id = $2)"
+                    sqlx::query_scalar!(
+                        r#"SELECT EXISTS(SELECT 1 FROM guild_members WHERE guild_id = $1 AND player_id = $2) as "member!""#,
+                        guild_id,
+                        player_id
                     )
-                    .bind(guild_id)
-                    .bind(player_id)
                     .fetch_one(&self.db.pg)
                     .await
                     .unwrap_or(false)
@@ -705,34 +940,34 @@ impl ChatService {
 
     /// Build message response with sender info
     async fn build_message_response(&self, msg: ChatMessage) -> ApiResult<MessageResponse> {
-        let sender_info = sqlx::query(
-            "SELECT username, level FROM players WHERE id = $1"
+        let sender_info = sqlx::query!(
+            r#"SELECT username, COALESCE(level, 1) as "level!" FROM players WHERE id = $1"#,
+            msg.sender_id
         )
-        .bind(msg.sender_id)
         .fetch_optional(&self.db.pg)
         .await?;
 
         let (sender_username, sender_level) = sender_info
-            .map(|r| (r.get::<Option<String>, _>("username"), Some(r.get::<i32, _>("level"))))
+            .map(|r| (r.username, Some(r.level)))
             .unwrap_or((None, None));
 
         // Get reply info
         let reply_to = if let Some(reply_id) = msg.reply_to_id {
-            sqlx::query(
+            sqlx::query!(
                 r#"
                 SELECT m.id, m.content, p.username
                 FROM chat_messages m
                 JOIN players p ON m.sender_id = p.id
                 WHERE m.id = $1
-                "#
+                "#,
+                reply_id
             )
-            .bind(reply_id)
             .fetch_optional(&self.db.pg)
             .await?
             .map(|r| ReplyInfo {
-                id: r.get("id"),
-                sender_username: r.get("username"),
-                content_preview: truncate_string(r.get::<String, _>("content"), 50),
+                id: r.id,
+                sender_username: r.username,
+                content_preview: truncate_string(r.content, 50),
             })
         } else {
             None
@@ -0,0 +1,265 @@
+//! Timed spawn events
+
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::error::{ApiResult, AppError};
+use crate::models::{
+    CreateSpawnEventRequest, EndedSpawnEvent, NotificationType, SpawnEvent, Species,
+    StartedSpawnEvent,
+};
+
+use super::spawn::filter_bounds;
+use super::{NotificationService, SpawnService};
+
+/// Longest event name, in characters
+const MAX_EVENT_NAME: usize = 100;
+
+/// Most Titans one event may spawn
+const MAX_EVENT_SPAWNS: u32 = 500;
+
+/// Players seen this recently in an event's area are notified when it starts
+const NOTIFY_ACTIVE_WITHIN_HOURS: i32 = 24;
+
+/// Starts and ends timed spawn events; the scheduler polls it every minute
+#[derive(Clone)]
+pub struct EventSpawnService {
+    db: Database,
+    spawn: SpawnService,
+    notification: NotificationService,
+}
+
+impl EventSpawnService {
+    pub fn new(db: Database, spawn: SpawnService, notification: NotificationService) -> Self {
+        Self {
+            db,
+            spawn,
+            notification,
+        }
+    }
+
+    /// Schedule an event
+    pub async fn create_event(
+        &self,
+        req: CreateSpawnEventRequest,
+        created_by: &str,
+    ) -> ApiResult<SpawnEvent> {
+        let name = req.name.trim();
+        if name.is_empty() || name.chars().count() > MAX_EVENT_NAME {
+            return Err(AppError::Validation(format!(
+                "Name must be 1-{} characters",
+                MAX_EVENT_NAME
+            )));
+        }
+        if req.end_at <= req.start_at || req.end_at <= chrono::Utc::now() {
+            return Err(AppError::Validation(
+                "end_at must be after start_at and in the future".into(),
+            ));
+        }
+        if let Some(prefix) = req
+            .geohash_filter
+            .iter()
+            .find(|prefix| prefix.is_empty() || geohash::decode_bbox(prefix).is_err())
+        {
+            return Err(AppError::Validation(format!("Invalid geohash prefix {:?}", prefix)));
+        }
+
+        let config = &req.spawn_config;
+        if config.max_spawns == 0 || config.max_spawns > MAX_EVENT_SPAWNS {
+            return Err(AppError::Validation(format!(
+                "max_spawns must be 1-{}",
+                MAX_EVENT_SPAWNS
+            )));
+        }
+        let weights = &config.threat_class_weights;
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) || weights.iter().sum::<f64>() <= 0.0 {
+            return Err(AppError::Validation(
+                "threat_class_weights must be non-negative with at least one above zero".into(),
+            ));
+        }
+        if let Some(id) = config.species_pool.iter().find(|&&id| Species::from_id(id).is_none()) {
+            return Err(AppError::Validation(format!("Unknown species {}", id)));
+        }
+
+        let event = sqlx::query_as::<_, SpawnEvent>(
+            r#"
+            INSERT INTO spawn_events (name, start_at, end_at, spawn_config, geohash_filter, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+            "#,
+        )
+        .bind(name)
+        .bind(req.start_at)
+        .bind(req.end_at)
+        .bind(sqlx::types::Json(&req.spawn_config))
+        .bind(&req.geohash_filter)
+        .bind(created_by)
+        .fetch_one(&self.db.pg)
+        .await?;
+
+        Ok(event)
+    }
+
+    /// Events that haven't ended, soonest first, then the 50 most recently ended
+    pub async fn list_events(&self) -> ApiResult<Vec<SpawnEvent>> {
+        let events = sqlx::query_as::<_, SpawnEvent>(
+            r#"
+            (SELECT * FROM spawn_events WHERE ended_at IS NULL ORDER BY start_at)
+            UNION ALL
+            (SELECT * FROM spawn_events WHERE ended_at IS NOT NULL ORDER BY ended_at DESC LIMIT 50)
+            "#,
+        )
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        Ok(events)
+    }
+
+    /// Cancel an event: one that hasn't started is deleted, a running one
+    /// ends at the next scheduler poll
+    pub async fn cancel_event(&self, event_id: Uuid) -> ApiResult<()> {
+        let deleted = sqlx::query("DELETE FROM spawn_events WHERE id = $1 AND started_at IS NULL")
+            .bind(event_id)
+            .execute(&self.db.pg)
+            .await?;
+        if deleted.rows_affected() > 0 {
+            return Ok(());
+        }
+
+        let ended = sqlx::query(
+            r#"
+            UPDATE spawn_events SET end_at = GREATEST(start_at + INTERVAL '1 second', NOW())
+            WHERE id = $1 AND ended_at IS NULL
+            "#,
+        )
+        .bind(event_id)
+        .execute(&self.db.pg)
+        .await?;
+        if ended.rows_affected() == 0 {
+            return Err(AppError::NotFound("Event not found or already ended".into()));
+        }
+
+        Ok(())
+    }
+
+    /// Claim events whose start time has come and run their spawns. Each
+    /// event is claimed by exactly one instance.
+    pub async fn start_due_events(&self) -> ApiResult<Vec<StartedSpawnEvent>> {
+        let events = sqlx::query_as::<_, SpawnEvent>(
+            r#"
+            UPDATE spawn_events SET started_at = NOW()
+            WHERE started_at IS NULL AND start_at <= NOW() AND end_at > NOW()
+            RETURNING *
+            "#,
+        )
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        let mut started = Vec::with_capacity(events.len());
+        for event in events {
+            let spawns = self.spawn.run_event_spawns(&event).await?;
+            self.notify_players(&event).await;
+            started.push(StartedSpawnEvent { event, spawns });
+        }
+
+        Ok(started)
+    }
+
+    /// Close events whose end time has passed and expire their remaining
+    /// Titans (those not captured out, which last until the event's end)
+    pub async fn end_due_events(&self) -> ApiResult<Vec<EndedSpawnEvent>> {
+        let events = sqlx::query_as::<_, SpawnEvent>(
+            r#"
+            UPDATE spawn_events SET ended_at = NOW()
+            WHERE ended_at IS NULL AND end_at <= NOW()
+            RETURNING *
+            "#,
+        )
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        let mut ended = Vec::with_capacity(events.len());
+        for event in events {
+            let expired: Vec<(Uuid, String)> = sqlx::query_as(
+                r#"
+                UPDATE titan_spawns SET expires_at = LEAST(expires_at, NOW())
+                WHERE event_id = $1
+                  AND (captured_by IS NULL OR capture_count < max_captures)
+                RETURNING id, geohash
+                "#,
+            )
+            .bind(event.id)
+            .fetch_all(&self.db.pg)
+            .await?;
+
+            tracing::info!("Event {:?} ended, expired {} Titans", event.name, expired.len());
+            ended.push(EndedSpawnEvent { event, expired });
+        }
+
+        Ok(ended)
+    }
+
+    /// In-game notification for players recently seen in the event's area.
+    /// Failures are logged; the spawns have already happened.
+    async fn notify_players(&self, event: &SpawnEvent) {
+        let players = match self.players_in_area(event).await {
+            Ok(players) => players,
+            Err(e) => {
+                tracing::warn!("Could not find players for event {}: {:?}", event.id, e);
+                return;
+            }
+        };
+
+        let message = format!(
+            "Special Titans are appearing until {}.",
+            event.end_at.format("%b %-d %H:%M UTC")
+        );
+        let data = serde_json::json!({ "event_id": event.id });
+        let hours_left = (event.end_at - chrono::Utc::now()).num_hours() as i32 + 1;
+
+        for player_id in players {
+            if let Err(e) = self
+                .notification
+                .create(
+                    player_id,
+                    NotificationType::System,
+                    &event.name,
+                    &message,
+                    Some(data.clone()),
+                    Some(hours_left),
+                )
+                .await
+            {
+                tracing::warn!("Event notification for {} failed: {:?}", player_id, e);
+            }
+        }
+    }
+
+    async fn players_in_area(&self, event: &SpawnEvent) -> ApiResult<Vec<Uuid>> {
+        let mut players = Vec::new();
+        for bounds in filter_bounds(&event.geohash_filter) {
+            let ids: Vec<Uuid> = sqlx::query_scalar(
+                r#"
+                SELECT id FROM players
+                WHERE last_location_at > NOW() - make_interval(hours => $5)
+                  AND ($1::float8 IS NULL OR (last_location_lat >= $1 AND last_location_lat < $2
+                       AND last_location_lng >= $3 AND last_location_lng < $4))
+                "#,
+            )
+            .bind(bounds.map(|b| b.min().y))
+            .bind(bounds.map(|b| b.max().y))
+            .bind(bounds.map(|b| b.min().x))
+            .bind(bounds.map(|b| b.max().x))
+            .bind(NOTIFY_ACTIVE_WITHIN_HOURS)
+            .fetch_all(&self.db.pg)
+            .await?;
+            players.extend(ids);
+        }
+
+        // Overlapping prefixes
+        players.sort();
+        players.dedup();
+
+        Ok(players)
+    }
+}
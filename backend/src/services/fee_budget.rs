@@ -0,0 +1,458 @@
+//! On-chain fee budget service
+//!
+//! Tracks estimated lamports the backend spends on each player's behalf and
+//! defers on-chain actions to a queue once the per-player daily or global
+//! hourly cap is reached.
+
+use chrono::{DateTime, TimeDelta, Utc};
+use uuid::Uuid;
+
+use crate::config::AppConfig;
+use crate::db::Database;
+use crate::error::{ApiResult, AppError};
+use crate::models::{
+    budget_day, budget_hour, BudgetDecision, BudgetUsage, DeferredOnchain, FeeBudgetSummary,
+    OnchainActionPayload, OnchainActionStatus, PendingOnchainAction, PlayerFeeSpend,
+};
+use crate::services::SolanaService;
+
+/// Queue attempts before an action is marked failed
+const MAX_ATTEMPTS: i32 = 5;
+
+/// Queue rows processed per run
+const QUEUE_BATCH_SIZE: i64 = 50;
+
+/// Queue processing summary
+#[derive(Debug, Default)]
+pub struct FeeQueueSummary {
+    pub completed: u32,
+    pub failed: u32,
+    pub still_deferred: u32,
+}
+
+/// On-chain fee budget service
+#[derive(Clone)]
+pub struct FeeBudgetService {
+    config: AppConfig,
+    db: Database,
+}
+
+impl FeeBudgetService {
+    pub fn new(config: AppConfig, db: Database) -> Self {
+        Self { config, db }
+    }
+
+    /// Check the budget for an on-chain action.
+    ///
+    /// Returns `None` when the caller may go ahead (the spend is recorded), or
+    /// the queued action when the budget is exhausted.
+    pub async fn authorize(
+        &self,
+        player_id: Uuid,
+        payload: OnchainActionPayload,
+    ) -> ApiResult<Option<DeferredOnchain>> {
+        let decision = self.reserve(player_id, &payload, Utc::now(), true).await?;
+        if decision.is_allowed() {
+            return Ok(None);
+        }
+
+        let action = payload.action();
+        let queued_action_id = self.enqueue(player_id, payload).await?;
+
+        tracing::info!(
+            "On-chain {:?} for player {} deferred ({})",
+            action,
+            player_id,
+            decision.reason().unwrap_or_default()
+        );
+
+        Ok(Some(DeferredOnchain::new(queued_action_id, action, decision)))
+    }
+
+    /// Record the estimated spend if it fits both caps
+    async fn reserve(
+        &self,
+        player_id: Uuid,
+        payload: &OnchainActionPayload,
+        now: DateTime<Utc>,
+        count_deferral: bool,
+    ) -> ApiResult<BudgetDecision> {
+        let cost = payload.action().estimated_lamports();
+        let day = budget_day(now);
+        let hour = budget_hour(now);
+
+        let mut tx = self.db.pg.begin().await?;
+
+        let player_cap = self.player_cap(&mut tx, player_id).await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO onchain_fee_spend (player_id, day) VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(player_id)
+        .bind(day)
+        .execute(&mut *tx)
+        .await?;
+
+        let player_spent: i64 = sqlx::query_scalar(
+            r#"SELECT lamports FROM onchain_fee_spend WHERE player_id = $1 AND day = $2 FOR UPDATE"#,
+        )
+        .bind(player_id)
+        .bind(day)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(r#"INSERT INTO onchain_fee_hourly (hour) VALUES ($1) ON CONFLICT DO NOTHING"#)
+            .bind(hour)
+            .execute(&mut *tx)
+            .await?;
+
+        let global_spent: i64 = sqlx::query_scalar(
+            r#"SELECT lamports FROM onchain_fee_hourly WHERE hour = $1 FOR UPDATE"#,
+        )
+        .bind(hour)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let usage = BudgetUsage {
+            player_spent: player_spent.max(0) as u64,
+            player_cap,
+            global_spent: global_spent.max(0) as u64,
+            global_cap: self.config.fee_budget.global_hourly_cap_lamports,
+        };
+        let decision = usage.decide(cost);
+
+        match decision {
+            BudgetDecision::Allowed => {
+                sqlx::query(
+                    r#"
+                    UPDATE onchain_fee_spend SET lamports = lamports + $3, actions = actions + 1
+                    WHERE player_id = $1 AND day = $2
+                    "#,
+                )
+                .bind(player_id)
+                .bind(day)
+                .bind(cost as i64)
+                .execute(&mut *tx)
+                .await?;
+
+                sqlx::query(
+                    r#"UPDATE onchain_fee_hourly SET lamports = lamports + $2, actions = actions + 1 WHERE hour = $1"#,
+                )
+                .bind(hour)
+                .bind(cost as i64)
+                .execute(&mut *tx)
+                .await?;
+            }
+            _ if count_deferral => {
+                sqlx::query(
+                    r#"UPDATE onchain_fee_spend SET deferred = deferred + 1 WHERE player_id = $1 AND day = $2"#,
+                )
+                .bind(player_id)
+                .bind(day)
+                .execute(&mut *tx)
+                .await?;
+
+                if decision == BudgetDecision::GlobalCapExceeded {
+                    sqlx::query(r#"UPDATE onchain_fee_hourly SET cap_hits = cap_hits + 1 WHERE hour = $1"#)
+                        .bind(hour)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+            }
+            _ => {}
+        }
+
+        tx.commit().await?;
+
+        Ok(decision)
+    }
+
+    /// Daily cap for a player (partner override or default)
+    async fn player_cap(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        player_id: Uuid,
+    ) -> ApiResult<u64> {
+        let cap: Option<i64> = sqlx::query_scalar(
+            r#"SELECT daily_cap_lamports FROM onchain_fee_caps WHERE player_id = $1"#,
+        )
+        .bind(player_id)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        Ok(cap
+            .map(|c| c.max(0) as u64)
+            .unwrap_or(self.config.fee_budget.player_daily_cap_lamports))
+    }
+
+    /// Queue an action for later processing
+    async fn enqueue(&self, player_id: Uuid, payload: OnchainActionPayload) -> ApiResult<Uuid> {
+        let id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO pending_onchain_actions (player_id, action, payload)
+            VALUES ($1, $2, $3)
+            RETURNING id
+            "#,
+        )
+        .bind(player_id)
+        .bind(payload.action())
+        .bind(sqlx::types::Json(&payload))
+        .fetch_one(&self.db.pg)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// Retry queued actions whose budget has freed up
+    pub async fn process_queue(&self, solana: &SolanaService) -> ApiResult<FeeQueueSummary> {
+        let pending = sqlx::query_as::<_, PendingOnchainAction>(
+            r#"
+            SELECT * FROM pending_onchain_actions
+            WHERE status = 'pending'
+            ORDER BY created_at
+            LIMIT $1
+            "#,
+        )
+        .bind(QUEUE_BATCH_SIZE)
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        let mut summary = FeeQueueSummary::default();
+
+        for action in pending {
+            let decision = self
+                .reserve(action.player_id, &action.payload, Utc::now(), false)
+                .await?;
+            if !decision.is_allowed() {
+                summary.still_deferred += 1;
+                continue;
+            }
+
+            let wallet: Option<String> = sqlx::query_scalar(
+                r#"SELECT wallet_address FROM players WHERE id = $1"#,
+            )
+            .bind(action.player_id)
+            .fetch_optional(&self.db.pg)
+            .await?;
+
+            let result = match wallet {
+                Some(wallet) => execute(solana, &wallet, &action.payload).await,
+                None => Err(AppError::PlayerNotFound),
+            };
+
+            let attempts = action.attempts + 1;
+            let (status, signature, error) = match result {
+                Ok(signature) => {
+                    summary.completed += 1;
+                    (OnchainActionStatus::Completed, Some(signature), None)
+                }
+                Err(e) if attempts >= MAX_ATTEMPTS => {
+                    summary.failed += 1;
+                    (OnchainActionStatus::Failed, None, Some(e.to_string()))
+                }
+                Err(e) => (OnchainActionStatus::Pending, None, Some(e.to_string())),
+            };
+
+            sqlx::query(
+                r#"
+                UPDATE pending_onchain_actions
+                SET status = $2, attempts = $3, tx_signature = $4, last_error = $5,
+                    processed_at = CASE WHEN $2 = 'pending'::onchain_action_status THEN NULL ELSE NOW() END
+                WHERE id = $1
+                "#,
+            )
+            .bind(action.id)
+            .bind(status)
+            .bind(attempts)
+            .bind(signature)
+            .bind(error)
+            .execute(&self.db.pg)
+            .await?;
+        }
+
+        Ok(summary)
+    }
+
+    /// Set (or clear with `None`) a player's daily cap override
+    pub async fn set_player_cap(
+        &self,
+        player_id: Uuid,
+        daily_cap_lamports: Option<u64>,
+        note: Option<String>,
+        updated_by: &str,
+    ) -> ApiResult<()> {
+        match daily_cap_lamports {
+            Some(cap) => {
+                let cap = i64::try_from(cap)
+                    .map_err(|_| AppError::Validation("daily_cap_lamports too large".into()))?;
+
+                sqlx::query(
+                    r#"
+                    INSERT INTO onchain_fee_caps (player_id, daily_cap_lamports, note, updated_by)
+                    VALUES ($1, $2, $3, $4)
+                    ON CONFLICT (player_id) DO UPDATE
+                    SET daily_cap_lamports = $2, note = $3, updated_by = $4, updated_at = NOW()
+                    "#,
+                )
+                .bind(player_id)
+                .bind(cap)
+                .bind(note)
+                .bind(updated_by)
+                .execute(&self.db.pg)
+                .await
+                .map_err(|e| match e {
+                    sqlx::Error::Database(ref db) if db.is_foreign_key_violation() => {
+                        AppError::PlayerNotFound
+                    }
+                    e => e.into(),
+                })?;
+            }
+            None => {
+                sqlx::query(r#"DELETE FROM onchain_fee_caps WHERE player_id = $1"#)
+                    .bind(player_id)
+                    .execute(&self.db.pg)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Today's spend for a player
+    pub async fn get_player_spend(&self, player_id: Uuid) -> ApiResult<PlayerFeeSpend> {
+        sqlx::query_as::<_, PlayerFeeSpend>(&format!(
+            "{} WHERE p.id = $1",
+            self.player_spend_select()
+        ))
+        .bind(budget_day(Utc::now()))
+        .bind(player_id)
+        .fetch_optional(&self.db.pg)
+        .await?
+        .ok_or(AppError::PlayerNotFound)
+    }
+
+    /// Spend counters for metrics and the admin API
+    pub async fn summary(&self) -> ApiResult<FeeBudgetSummary> {
+        let now = Utc::now();
+        let day = budget_day(now);
+        let hour = budget_hour(now);
+
+        let today_lamports: i64 = sqlx::query_scalar(
+            r#"SELECT COALESCE(SUM(lamports), 0)::BIGINT FROM onchain_fee_spend WHERE day = $1"#,
+        )
+        .bind(day)
+        .fetch_one(&self.db.pg)
+        .await?;
+
+        let (hour_lamports, hour_cap_hits): (i64, i32) = sqlx::query_as(
+            r#"SELECT lamports, cap_hits FROM onchain_fee_hourly WHERE hour = $1"#,
+        )
+        .bind(hour)
+        .fetch_optional(&self.db.pg)
+        .await?
+        .unwrap_or((0, 0));
+
+        let pending_actions: i64 = sqlx::query_scalar(
+            r#"SELECT COUNT(*) FROM pending_onchain_actions WHERE status = 'pending'"#,
+        )
+        .fetch_one(&self.db.pg)
+        .await?;
+
+        let top_spenders = sqlx::query_as::<_, PlayerFeeSpend>(&format!(
+            "{} WHERE s.player_id IS NOT NULL ORDER BY s.lamports DESC LIMIT 20",
+            self.player_spend_select()
+        ))
+        .bind(day)
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        Ok(FeeBudgetSummary {
+            day,
+            hour,
+            today_lamports,
+            hour_lamports,
+            hour_cap_hits,
+            global_hourly_cap_lamports: self.config.fee_budget.global_hourly_cap_lamports,
+            player_daily_cap_lamports: self.config.fee_budget.player_daily_cap_lamports,
+            capped_hours: self.capped_hours().await?,
+            pending_actions,
+            top_spenders,
+        })
+    }
+
+    /// Hours in the alert window in which the global cap was hit
+    pub async fn capped_hours(&self) -> ApiResult<i64> {
+        let window = self.config.fee_budget.alert_after_capped_hours.max(1) as i64;
+        let since = budget_hour(Utc::now()) - TimeDelta::hours(window - 1);
+
+        let count: i64 = sqlx::query_scalar(
+            r#"SELECT COUNT(*) FROM onchain_fee_hourly WHERE hour >= $1 AND cap_hits > 0"#,
+        )
+        .bind(since)
+        .fetch_one(&self.db.pg)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Whether the global cap was hit in every hour of the alert window
+    pub async fn should_alert(&self) -> ApiResult<bool> {
+        let threshold = self.config.fee_budget.alert_after_capped_hours.max(1) as i64;
+        Ok(self.capped_hours().await? >= threshold)
+    }
+
+    /// Player spend query; binds `$1` = day
+    fn player_spend_select(&self) -> String {
+        format!(
+            r#"
+            SELECT p.id AS player_id, p.wallet_address,
+                   COALESCE(s.lamports, 0) AS lamports,
+                   COALESCE(s.actions, 0) AS actions,
+                   COALESCE(s.deferred, 0) AS deferred,
+                   COALESCE(c.daily_cap_lamports, {}) AS daily_cap_lamports,
+                   c.player_id IS NOT NULL AS has_override
+            FROM players p
+            LEFT JOIN onchain_fee_spend s ON s.player_id = p.id AND s.day = $1
+            LEFT JOIN onchain_fee_caps c ON c.player_id = p.id
+            "#,
+            self.config.fee_budget.player_daily_cap_lamports
+        )
+    }
+}
+
+/// Run a queued action, returning its (first) transaction signature
+async fn execute(
+    solana: &SolanaService,
+    wallet: &str,
+    payload: &OnchainActionPayload,
+) -> ApiResult<String> {
+    match payload {
+        OnchainActionPayload::CaptureMint { element, threat_class, species_id, genes, geohash } => {
+            let mut genes_array = [0u8; 32];
+            let len = genes.len().min(32);
+            genes_array[..len].copy_from_slice(&genes[..len]);
+
+            let result = solana
+                .mint_titan_nft(wallet, *element, *threat_class, *species_id, genes_array)
+                .await?;
+
+            if let Err(e) = solana.record_capture(wallet, &result.mint_address, geohash).await {
+                tracing::warn!("Failed to record deferred capture on-chain: {}", e);
+            }
+
+            Ok(result.signature)
+        }
+        OnchainActionPayload::RecordBattle { titan_mint, battle_type, result } => {
+            solana.record_battle(wallet, titan_mint, *battle_type, *result).await
+        }
+        OnchainActionPayload::AddExperience { titan_mint, xp } => {
+            solana.add_titan_experience(titan_mint, *xp).await
+        }
+        OnchainActionPayload::BreachTransfer { amount } => {
+            Ok(solana.transfer_breach_tokens(wallet, *amount).await?.signature)
+        }
+    }
+}
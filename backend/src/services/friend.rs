@@ -1,28 +1,59 @@
 //! Friend service
 
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
+use crate::config::SharedGameConfig;
 use crate::db::Database;
 use crate::error::{ApiResult, AppError};
 use crate::models::{
-    FriendGift, FriendInfo, FriendRequest, FriendRequestStatus, FriendRequestWithSender,
-    GiftWithSender, NotificationType, SendFriendRequest,
+    FriendGift, FriendInfo, FriendLocation, FriendRequest, FriendRequestStatus,
+    FriendRequestWithSender, FriendSettings, GiftWithSender, NotificationType, SendFriendRequest,
 };
 
+use super::{NotificationService, PresenceService};
+
+/// Geohash characters a shared location is cut to (cells of about 5 km)
+pub const FRIEND_LOCATION_PRECISION: usize = 5;
+
+/// Coarse geohash of a location shared with friends; `None` for coordinates
+/// off the globe
+pub fn coarse_geohash(lat: f64, lng: f64) -> Option<String> {
+    geohash::encode(geohash::Coord { x: lng, y: lat }, FRIEND_LOCATION_PRECISION).ok()
+}
+
+/// A sharing friend's last known location, before it is made coarse
+#[derive(sqlx::FromRow)]
+struct SharedLocation {
+    player_id: Uuid,
+    username: Option<String>,
+    lat: f64,
+    lng: f64,
+    last_seen: DateTime<Utc>,
+}
+
 /// Friend service
 #[derive(Clone)]
 pub struct FriendService {
+    game: SharedGameConfig,
     db: Database,
+    presence: PresenceService,
+    notification: NotificationService,
 }
 
 impl FriendService {
-    pub fn new(db: Database) -> Self {
-        Self { db }
+    pub fn new(
+        game: SharedGameConfig,
+        db: Database,
+        presence: PresenceService,
+        notification: NotificationService,
+    ) -> Self {
+        Self { game, db, presence, notification }
     }
 
-    /// Get all friends for a player
+    /// Get all friends for a player, online ones first
     pub async fn get_friends(&self, player_id: Uuid) -> ApiResult<Vec<FriendInfo>> {
-        let friends = sqlx::query_as::<_, FriendInfo>(
+        let mut friends = sqlx::query_as::<_, FriendInfo>(
             r#"
             SELECT 
                 CASE WHEN f.player1_id = $1 THEN f.player2_id ELSE f.player1_id END as player_id,
@@ -30,19 +61,29 @@ impl FriendService {
                 p.wallet_address,
                 p.level,
                 p.titans_captured,
-                CASE WHEN p.last_location_at > NOW() - INTERVAL '5 minutes' THEN true ELSE false END as is_online,
+                false as is_online,
                 p.last_location_at as last_active_at,
-                f.created_at as friendship_date
+                f.created_at as friendship_date,
+                CASE WHEN f.player1_id = $1 THEN f.player1_shares_location
+                     ELSE f.player2_shares_location END as share_location
             FROM friendships f
             JOIN players p ON p.id = CASE WHEN f.player1_id = $1 THEN f.player2_id ELSE f.player1_id END
             WHERE f.player1_id = $1 OR f.player2_id = $1
-            ORDER BY is_online DESC, p.username
+            ORDER BY p.username
             "#,
         )
         .bind(player_id)
         .fetch_all(&self.db.pg)
         .await?;
 
+        let ids: Vec<Uuid> = friends.iter().map(|f| f.player_id).collect();
+        let online = self.presence.get_online(&ids).await;
+        for friend in &mut friends {
+            friend.is_online = online.contains(&friend.player_id);
+        }
+        // Stable, so each group stays in username order
+        friends.sort_by_key(|f| !f.is_online);
+
         Ok(friends)
     }
 
@@ -311,6 +352,92 @@ impl FriendService {
         Ok(())
     }
 
+    /// Share (or stop sharing) your location with a friend
+    pub async fn update_settings(
+        &self,
+        player_id: Uuid,
+        friend_id: Uuid,
+        share_location: bool,
+    ) -> ApiResult<FriendSettings> {
+        // Each side's flag is on the one friendship row
+        let result = sqlx::query(
+            r#"
+            UPDATE friendships SET
+                player1_shares_location = CASE WHEN player1_id = $1 THEN $3 ELSE player1_shares_location END,
+                player2_shares_location = CASE WHEN player2_id = $1 THEN $3 ELSE player2_shares_location END
+            WHERE (player1_id = $1 AND player2_id = $2) OR (player1_id = $2 AND player2_id = $1)
+            "#,
+        )
+        .bind(player_id)
+        .bind(friend_id)
+        .bind(share_location)
+        .execute(&self.db.pg)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Friendship not found".into()));
+        }
+
+        Ok(FriendSettings { friend_id, share_location })
+    }
+
+    /// Coarse last known locations of the friends sharing with a player who
+    /// reported one within `game.friend_location_window_minutes`
+    pub async fn get_friend_locations(&self, player_id: Uuid) -> ApiResult<Vec<FriendLocation>> {
+        let window = self.game.read().await.friend_location_window_minutes;
+        let shared = sqlx::query_as::<_, SharedLocation>(
+            r#"
+            SELECT
+                p.id as player_id,
+                p.username,
+                p.last_location_lat as lat,
+                p.last_location_lng as lng,
+                p.last_location_at as last_seen
+            FROM friendships f
+            JOIN players p ON p.id = CASE WHEN f.player1_id = $1 THEN f.player2_id ELSE f.player1_id END
+            WHERE ((f.player1_id = $1 AND f.player2_shares_location)
+                OR (f.player2_id = $1 AND f.player1_shares_location))
+              AND p.last_location_lat IS NOT NULL
+              AND p.last_location_lng IS NOT NULL
+              AND p.last_location_at > NOW() - make_interval(mins => $2)
+            ORDER BY p.last_location_at DESC
+            "#,
+        )
+        .bind(player_id)
+        .bind(window as i32)
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        Ok(shared
+            .into_iter()
+            .filter_map(|friend| {
+                Some(FriendLocation {
+                    geohash: coarse_geohash(friend.lat, friend.lng)?,
+                    player_id: friend.player_id,
+                    username: friend.username,
+                    last_seen: friend.last_seen,
+                })
+            })
+            .collect())
+    }
+
+    /// Friends a player currently shares their location with
+    pub async fn location_recipients(&self, player_id: Uuid) -> ApiResult<Vec<Uuid>> {
+        let recipients = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT CASE WHEN player1_id = $1 THEN player2_id ELSE player1_id END
+            FROM friendships
+            WHERE (player1_id = $1 AND player1_shares_location)
+               OR (player2_id = $1 AND player2_shares_location)
+            "#,
+        )
+        .bind(player_id)
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        Ok(recipients)
+    }
+
     /// Send daily gift to friend
     pub async fn send_gift(&self, sender_id: Uuid, receiver_id: Uuid) -> ApiResult<FriendGift> {
         // Verify friendship
@@ -433,20 +560,36 @@ impl FriendService {
         message: &str,
         data: Option<serde_json::Value>,
     ) -> ApiResult<()> {
-        sqlx::query(
-            r#"
-            INSERT INTO notifications (player_id, notification_type, title, message, data)
-            VALUES ($1, $2, $3, $4, $5)
-            "#,
-        )
-        .bind(player_id)
-        .bind(notification_type)
-        .bind(title)
-        .bind(message)
-        .bind(data)
-        .execute(&self.db.pg)
-        .await?;
+        self.notification
+            .create(player_id, notification_type, title, message, data, None)
+            .await?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coarse_geohash_hides_coordinates() {
+        let cases = [
+            // (lat, lng, geohash)
+            (35.659_5, 139.700_5, "xn76f"),
+            (51.501_4, -0.141_9, "gcpuu"),
+            (-33.856_8, 151.215_3, "r3gx2"),
+        ];
+        for (lat, lng, expected) in cases {
+            let geohash = coarse_geohash(lat, lng).unwrap();
+            assert_eq!(geohash, expected);
+            assert_eq!(geohash.len(), FRIEND_LOCATION_PRECISION);
+
+            // Anywhere in the same cell gives the same answer
+            let nearby = coarse_geohash(lat + 0.001, lng - 0.001).unwrap();
+            assert_eq!(nearby, geohash);
+        }
+
+        assert_eq!(coarse_geohash(91.0, 0.0), None);
+    }
+}
@@ -0,0 +1,217 @@
+//! Fusion rules and offspring prediction
+//!
+//! Mirrors `titan_nft`'s `fuse` instruction so a fusion that would fail
+//! on-chain is rejected before the player signs, and the offspring's possible
+//! stats can be shown up front.
+
+use serde::Serialize;
+
+use crate::services::solana::OnchainTitan;
+
+/// Minimum level of both parents (contract `FUSION_MIN_LEVEL`)
+pub const FUSION_MIN_LEVEL: u8 = 20;
+
+/// Offspring gene mutation offset range (contract: `randomness % 64 - 32`)
+const MUTATION_MIN: i16 = -32;
+const MUTATION_MAX: i16 = 31;
+
+/// Inclusive range of a predicted value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct StatRange {
+    pub min: u8,
+    pub max: u8,
+}
+
+/// Offspring values that don't depend on the fusion's randomness
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OffspringPreview {
+    pub species_id: u16,
+    pub element_type: u8,
+    pub threat_class: u8,
+    pub generation: u8,
+    pub power: StatRange,
+    pub fortitude: StatRange,
+    pub velocity: StatRange,
+    pub resonance: StatRange,
+    /// Per gene [ATK, SPD, DEF, GRW, SKL, MUT]
+    pub genes: [StatRange; 6],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FusePreview {
+    pub titan_a_id: u64,
+    pub titan_b_id: u64,
+    pub eligible: bool,
+    /// Why the contract would reject the fusion
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Set only when eligible
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offspring: Option<OffspringPreview>,
+}
+
+/// Check the contract's fusion rules and predict the offspring
+pub fn preview(titan_a: &OnchainTitan, titan_b: &OnchainTitan) -> FusePreview {
+    let reason = ineligible_reason(titan_a, titan_b);
+    FusePreview {
+        titan_a_id: titan_a.titan_id,
+        titan_b_id: titan_b.titan_id,
+        eligible: reason.is_none(),
+        offspring: reason.is_none().then(|| offspring(titan_a, titan_b)),
+        reason,
+    }
+}
+
+/// Same checks, in the same order, as the `fuse` instruction
+fn ineligible_reason(titan_a: &OnchainTitan, titan_b: &OnchainTitan) -> Option<String> {
+    if titan_a.titan_id == titan_b.titan_id {
+        return Some("A Titan cannot be fused with itself".to_string());
+    }
+    if titan_a.level < FUSION_MIN_LEVEL || titan_b.level < FUSION_MIN_LEVEL {
+        return Some(format!(
+            "Both Titans must be level {} or higher (levels {} and {})",
+            FUSION_MIN_LEVEL, titan_a.level, titan_b.level
+        ));
+    }
+    if titan_a.element_type != titan_b.element_type {
+        return Some("Both Titans must have the same element".to_string());
+    }
+    None
+}
+
+fn offspring(titan_a: &OnchainTitan, titan_b: &OnchainTitan) -> OffspringPreview {
+    let genes: [StatRange; 6] =
+        std::array::from_fn(|i| offspring_gene_range(titan_a.genes[i], titan_b.genes[i]));
+    let stat = |parent_a: u8, parent_b: u8, gene: StatRange| StatRange {
+        min: offspring_stat(parent_a, parent_b, gene.min),
+        max: offspring_stat(parent_a, parent_b, gene.max),
+    };
+
+    OffspringPreview {
+        species_id: titan_a.species_id,
+        element_type: titan_a.element_type,
+        threat_class: titan_a.threat_class.min(titan_b.threat_class),
+        generation: titan_a.generation.max(titan_b.generation).saturating_add(1),
+        power: stat(titan_a.power, titan_b.power, genes[0]),
+        fortitude: stat(titan_a.fortitude, titan_b.fortitude, genes[2]),
+        velocity: stat(titan_a.velocity, titan_b.velocity, genes[1]),
+        resonance: stat(titan_a.resonance, titan_b.resonance, genes[4]),
+        genes,
+    }
+}
+
+/// Possible offspring gene: either parent's gene, or their average shifted
+/// by a mutation of -32..=31 (contract `calculate_offspring_genes`)
+pub fn offspring_gene_range(parent_a: u8, parent_b: u8) -> StatRange {
+    let avg = (parent_a as i16 + parent_b as i16) / 2;
+    let mutated_min = (avg + MUTATION_MIN).clamp(0, 255) as u8;
+    let mutated_max = (avg + MUTATION_MAX).clamp(0, 255) as u8;
+    StatRange {
+        min: parent_a.min(parent_b).min(mutated_min),
+        max: parent_a.max(parent_b).max(mutated_max),
+    }
+}
+
+/// Offspring stat for a given gene (contract `calculate_offspring_stat`);
+/// non-decreasing in `gene`, so a gene range maps to a stat range
+pub fn offspring_stat(parent_a: u8, parent_b: u8, gene: u8) -> u8 {
+    let avg = ((parent_a as u16 + parent_b as u16) / 2) as i16;
+    let gene_bonus = (gene as i16 - 128) / 25;
+    (avg + gene_bonus).clamp(1, 255) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Packed `TitanData` account bytes as stored on-chain
+    fn titan_account(titan_id: u64, level: u8, element: u8, stats: [u8; 4], genes: [u8; 6]) -> Vec<u8> {
+        let mut data = Vec::with_capacity(OnchainTitan::SIZE);
+        data.extend_from_slice(&OnchainTitan::DISCRIMINATOR);
+        data.extend_from_slice(&titan_id.to_le_bytes());
+        data.extend_from_slice(&7u16.to_le_bytes()); // species
+        data.push(3); // threat class
+        data.push(element);
+        data.extend_from_slice(&stats);
+        data.extend_from_slice(&genes);
+        data.push(level);
+        data.extend_from_slice(&0u32.to_le_bytes()); // experience
+        data.push(10); // link strength
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        data.extend_from_slice(&[1u8; 32]); // original owner
+        data.extend_from_slice(&[2u8; 32]); // owner
+        data.extend_from_slice(&0u64.to_le_bytes()); // capture location
+        data.push(1); // generation
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.push(255); // bump
+        data.push(0); // listed
+        data.resize(OnchainTitan::SIZE, 0);
+        data
+    }
+
+    fn titan(titan_id: u64, level: u8, element: u8, stats: [u8; 4], genes: [u8; 6]) -> OnchainTitan {
+        OnchainTitan::from_account_data(&titan_account(titan_id, level, element, stats, genes)).unwrap()
+    }
+
+    #[test]
+    fn test_gene_range() {
+        // Mutation around the average reaches past both parents
+        assert_eq!(offspring_gene_range(100, 140), StatRange { min: 88, max: 151 });
+        // Far-apart parents bound the range themselves
+        assert_eq!(offspring_gene_range(10, 250), StatRange { min: 10, max: 250 });
+        // Clamped at the byte limits
+        assert_eq!(offspring_gene_range(0, 4), StatRange { min: 0, max: 33 });
+        assert_eq!(offspring_gene_range(255, 255), StatRange { min: 223, max: 255 });
+    }
+
+    #[test]
+    fn test_offspring_stat_matches_contract() {
+        assert_eq!(offspring_stat(50, 70, 128), 60);
+        // (255 - 128) / 25 = +5, (0 - 128) / 25 = -5 (truncated toward zero)
+        assert_eq!(offspring_stat(50, 70, 255), 65);
+        assert_eq!(offspring_stat(50, 70, 0), 55);
+        assert_eq!(offspring_stat(50, 70, 104), 60);
+        assert_eq!(offspring_stat(1, 2, 0), 1);
+        assert_eq!(offspring_stat(255, 255, 255), 255);
+    }
+
+    #[test]
+    fn test_preview_from_account_bytes() {
+        let a = titan(11, 25, 2, [50, 60, 70, 80], [100, 200, 30, 128, 255, 0]);
+        let b = titan(12, 20, 2, [70, 40, 90, 100], [140, 180, 30, 128, 250, 10]);
+
+        let preview = preview(&a, &b);
+        assert!(preview.eligible);
+        let offspring = preview.offspring.unwrap();
+        assert_eq!(offspring.threat_class, 3);
+        assert_eq!(offspring.generation, 2);
+        assert_eq!(offspring.genes[0], StatRange { min: 88, max: 151 });
+        assert_eq!(offspring.genes[2], StatRange { min: 0, max: 61 });
+        // Power: average 60, gene 88..=151 gives -1..=+0
+        assert_eq!(offspring.power, StatRange { min: 59, max: 60 });
+        // Fortitude uses gene[2]: average 50, gene 0..=61 gives -5..=-2
+        assert_eq!(offspring.fortitude, StatRange { min: 45, max: 48 });
+        // Velocity uses gene[1] (158..=221): +1..=+3 on 80
+        assert_eq!(offspring.velocity, StatRange { min: 81, max: 83 });
+        // Resonance uses gene[4] (220..=255): +3..=+5 on 90
+        assert_eq!(offspring.resonance, StatRange { min: 93, max: 95 });
+    }
+
+    #[test]
+    fn test_contract_rules() {
+        let genes = [128; 6];
+        let a = titan(1, 20, 0, [50; 4], genes);
+
+        let same = preview(&a, &a);
+        assert!(!same.eligible);
+        assert!(same.offspring.is_none());
+
+        let low = preview(&a, &titan(2, 19, 0, [50; 4], genes));
+        assert!(low.reason.unwrap().contains("level 20"));
+
+        let other_element = preview(&a, &titan(2, 30, 1, [50; 4], genes));
+        assert!(!other_element.eligible);
+        assert!(other_element.reason.unwrap().contains("element"));
+    }
+}
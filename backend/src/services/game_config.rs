@@ -0,0 +1,117 @@
+//! Live game config service
+//!
+//! Applies admin changes and reloads to the shared `game` settings, one at a
+//! time, recording each change in `game_config_audit_log`.
+
+use serde::Serialize;
+
+use crate::config::{AppConfig, GameConfig, SharedGameConfig};
+use crate::db::Database;
+use crate::error::{ApiResult, AppError};
+
+/// What changed the settings, as recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigChangeSource {
+    /// `PATCH /admin/config`
+    Patch,
+    /// `POST /admin/config/reload`
+    Reload,
+    /// SIGHUP to the process
+    Sighup,
+}
+
+impl ConfigChangeSource {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Patch => "patch",
+            Self::Reload => "reload",
+            Self::Sighup => "sighup",
+        }
+    }
+}
+
+/// Settings after a change, and what it changed
+#[derive(Debug, Clone, Serialize)]
+pub struct GameConfigUpdate {
+    pub config: GameConfig,
+    /// `{ field: { "from": ..., "to": ... } }`; empty when nothing changed
+    pub changes: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Live game config service
+#[derive(Clone)]
+pub struct GameConfigService {
+    config: AppConfig,
+    game: SharedGameConfig,
+    db: Database,
+}
+
+impl GameConfigService {
+    pub fn new(config: AppConfig, game: SharedGameConfig, db: Database) -> Self {
+        Self { config, game, db }
+    }
+
+    /// The settings in effect
+    pub async fn current(&self) -> GameConfig {
+        self.game.read().await.clone()
+    }
+
+    /// Replace the top-level settings in `patch`
+    pub async fn update(
+        &self,
+        patch: &serde_json::Map<String, serde_json::Value>,
+        changed_by: &str,
+    ) -> ApiResult<GameConfigUpdate> {
+        self.apply(ConfigChangeSource::Patch, Some(changed_by), |current| {
+            current.patched(patch)
+        })
+        .await
+    }
+
+    /// Re-read the config files and environment; `changed_by` is `None` for
+    /// a SIGHUP
+    pub async fn reload(&self, source: ConfigChangeSource, changed_by: Option<&str>) -> ApiResult<GameConfigUpdate> {
+        let fresh = self
+            .config
+            .reload()
+            .map_err(|e| AppError::Validation(format!("Config reload failed: {:#}", e)))?;
+        self.apply(source, changed_by, |_| Ok(fresh)).await
+    }
+
+    /// Swap in the settings `change` makes from the current ones. The lock is
+    /// held until the audit row is written, so a failed write changes nothing
+    /// and concurrent changes can't interleave.
+    async fn apply(
+        &self,
+        source: ConfigChangeSource,
+        changed_by: Option<&str>,
+        change: impl FnOnce(&GameConfig) -> anyhow::Result<GameConfig>,
+    ) -> ApiResult<GameConfigUpdate> {
+        let mut game = self.game.write().await;
+
+        let fresh = change(&game).map_err(|e| AppError::Validation(format!("{:#}", e)))?;
+        fresh.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+        let changes = fresh.changes_from(&game)?;
+
+        if !changes.is_empty() {
+            sqlx::query(
+                "INSERT INTO game_config_audit_log (source, changed_by, changes) VALUES ($1, $2, $3)",
+            )
+            .bind(source.as_str())
+            .bind(changed_by)
+            .bind(serde_json::Value::Object(changes.clone()))
+            .execute(&self.db.pg)
+            .await?;
+
+            tracing::info!(
+                "Game config changed by {} ({}): {}",
+                changed_by.unwrap_or("SIGHUP"),
+                source.as_str(),
+                changes.keys().cloned().collect::<Vec<_>>().join(", ")
+            );
+            *game = fresh;
+        }
+
+        Ok(GameConfigUpdate { config: game.clone(), changes })
+    }
+}
@@ -1,23 +1,42 @@
 //! Guild service
 
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
 use crate::db::Database;
 use crate::error::{ApiResult, AppError};
 use crate::models::{
-    CreateGuildRequest, FriendRequestStatus, Guild, GuildMember, GuildMemberInfo, GuildRequest,
-    GuildRequestWithPlayer, GuildRole, GuildSummary, NotificationType, UpdateGuildRequest,
+    ActivityOutcome, CreateGuildRequest, FriendRequestStatus, Guild, GuildActivityRules,
+    GuildMember, GuildMemberInfo, GuildRequest, GuildRequestWithPlayer, GuildRole, GuildSummary,
+    GuildTreasury, GuildTreasuryEntry, GuildVacation, GuildWar, GuildWarDetails, GuildWarEvent,
+    GuildWarScoreEntry, GuildWarStatus, GuildWarUpdate, InactivityPolicy, NotificationType, UpdateGuildRequest,
 };
 
+use super::NotificationService;
+
+/// Ledger entries returned with a treasury balance
+const TREASURY_LOG_LIMIT: i64 = 50;
+
+/// Buffered war updates before slow forwarders miss some
+const LIVE_CHANNEL_CAPACITY: usize = 256;
+
 /// Guild service
 #[derive(Clone)]
 pub struct GuildService {
     db: Database,
+    notification: NotificationService,
+    live: broadcast::Sender<GuildWarUpdate>,
 }
 
 impl GuildService {
-    pub fn new(db: Database) -> Self {
-        Self { db }
+    pub fn new(db: Database, notification: NotificationService) -> Self {
+        let (live, _) = broadcast::channel(LIVE_CHANNEL_CAPACITY);
+        Self { db, notification, live }
+    }
+
+    /// War starts, score changes and results as they happen
+    pub fn subscribe_wars(&self) -> broadcast::Receiver<GuildWarUpdate> {
+        self.live.subscribe()
     }
 
     /// Create a new guild
@@ -27,10 +46,16 @@ impl GuildService {
         req: CreateGuildRequest,
     ) -> ApiResult<Guild> {
         // Check if player is already in a guild
-        let existing: Option<GuildMember> = sqlx::query_as(
-            r#"SELECT * FROM guild_members WHERE player_id = $1"#,
+        let existing = sqlx::query_as!(
+            GuildMember,
+            r#"
+            SELECT id, guild_id, player_id, role as "role: GuildRole", contribution_xp,
+                   contribution_captures, contribution_battles, joined_at, last_active_at,
+                   week_start_xp, week_start_captures, missed_weeks
+            FROM guild_members WHERE player_id = $1
+            "#,
+            leader_id
         )
-        .bind(leader_id)
         .fetch_optional(&self.db.pg)
         .await?;
 
@@ -47,40 +72,47 @@ impl GuildService {
         }
 
         // Create guild
-        let guild = sqlx::query_as::<_, Guild>(
+        let guild = sqlx::query_as!(
+            Guild,
             r#"
             INSERT INTO guilds (name, tag, description, leader_id, min_level, is_public)
             VALUES ($1, $2, $3, $4, $5, $6)
-            RETURNING *
+            RETURNING id, name, tag, description, icon, banner, leader_id, min_level, is_public,
+                      max_members, total_captures, total_battles, total_breach, weekly_xp,
+                      season_rank, season_points, created_at, updated_at,
+                      activity_min_weekly_xp, activity_min_weekly_captures,
+                      inactivity_policy as "inactivity_policy: InactivityPolicy",
+                      auto_kick_enabled, activity_week_started_at
             "#,
+            req.name,
+            req.tag.to_uppercase(),
+            req.description,
+            leader_id,
+            req.min_level.unwrap_or(1),
+            req.is_public.unwrap_or(true)
         )
-        .bind(&req.name)
-        .bind(&req.tag.to_uppercase())
-        .bind(&req.description)
-        .bind(leader_id)
-        .bind(req.min_level.unwrap_or(1))
-        .bind(req.is_public.unwrap_or(true))
         .fetch_one(&self.db.pg)
         .await?;
 
         // Add leader as member
-        sqlx::query(
+        sqlx::query!(
             r#"
-            INSERT INTO guild_members (guild_id, player_id, role)
-            VALUES ($1, $2, 'leader')
+            INSERT INTO guild_members (guild_id, player_id, role, week_start_xp, week_start_captures)
+            SELECT $1, id, 'leader', COALESCE(experience, 0), COALESCE(titans_captured, 0)
+            FROM players WHERE id = $2
             "#,
+            guild.id,
+            leader_id
         )
-        .bind(guild.id)
-        .bind(leader_id)
         .execute(&self.db.pg)
         .await?;
 
         // Update player's guild_id
-        sqlx::query(
+        sqlx::query!(
             r#"UPDATE players SET guild_id = $1 WHERE id = $2"#,
+            guild.id,
+            leader_id
         )
-        .bind(guild.id)
-        .bind(leader_id)
         .execute(&self.db.pg)
         .await?;
 
@@ -94,10 +126,19 @@ impl GuildService {
 
     /// Get guild by ID
     pub async fn get_guild(&self, guild_id: Uuid) -> ApiResult<Option<Guild>> {
-        let guild = sqlx::query_as::<_, Guild>(
-            r#"SELECT * FROM guilds WHERE id = $1"#,
+        let guild = sqlx::query_as!(
+            Guild,
+            r#"
+            SELECT id, name, tag, description, icon, banner, leader_id, min_level, is_public,
+                   max_members, total_captures, total_battles, total_breach, weekly_xp,
+                   season_rank, season_points, created_at, updated_at,
+                   activity_min_weekly_xp, activity_min_weekly_captures,
+                   inactivity_policy as "inactivity_policy: InactivityPolicy",
+                   auto_kick_enabled, activity_week_started_at
+            FROM guilds WHERE id = $1
+            "#,
+            guild_id
         )
-        .bind(guild_id)
         .fetch_optional(&self.db.pg)
         .await?;
 
@@ -113,12 +154,13 @@ impl GuildService {
     ) -> ApiResult<Vec<GuildSummary>> {
         let guilds = if let Some(q) = query {
             let pattern = format!("%{}%", q);
-            sqlx::query_as::<_, GuildSummary>(
+            sqlx::query_as!(
+                GuildSummary,
                 r#"
                 SELECT 
                     g.id, g.name, g.tag, g.description, g.icon,
                     p.username as leader_username,
-                    (SELECT COUNT(*) FROM guild_members WHERE guild_id = g.id) as member_count,
+                    (SELECT COUNT(*) FROM guild_members WHERE guild_id = g.id) as "member_count!",
                     g.max_members, g.min_level, g.is_public, g.weekly_xp, g.season_rank
                 FROM guilds g
                 JOIN players p ON p.id = g.leader_id
@@ -126,19 +168,20 @@ impl GuildService {
                 ORDER BY g.season_points DESC
                 LIMIT $2 OFFSET $3
                 "#,
+                pattern,
+                limit,
+                offset
             )
-            .bind(&pattern)
-            .bind(limit)
-            .bind(offset)
             .fetch_all(&self.db.pg)
             .await?
         } else {
-            sqlx::query_as::<_, GuildSummary>(
+            sqlx::query_as!(
+                GuildSummary,
                 r#"
                 SELECT 
                     g.id, g.name, g.tag, g.description, g.icon,
                     p.username as leader_username,
-                    (SELECT COUNT(*) FROM guild_members WHERE guild_id = g.id) as member_count,
+                    (SELECT COUNT(*) FROM guild_members WHERE guild_id = g.id) as "member_count!",
                     g.max_members, g.min_level, g.is_public, g.weekly_xp, g.season_rank
                 FROM guilds g
                 JOIN players p ON p.id = g.leader_id
@@ -146,9 +189,9 @@ impl GuildService {
                 ORDER BY g.season_points DESC
                 LIMIT $1 OFFSET $2
                 "#,
+                limit,
+                offset
             )
-            .bind(limit)
-            .bind(offset)
             .fetch_all(&self.db.pg)
             .await?
         };
@@ -158,37 +201,81 @@ impl GuildService {
 
     /// Get guild members
     pub async fn get_members(&self, guild_id: Uuid) -> ApiResult<Vec<GuildMemberInfo>> {
-        let members = sqlx::query_as::<_, GuildMemberInfo>(
+        let mut members: Vec<GuildMemberInfo> = sqlx::query!(
             r#"
             SELECT 
                 gm.player_id,
                 p.username,
-                p.level,
-                gm.role,
+                COALESCE(p.level, 1) as "level!",
+                gm.role as "role: GuildRole",
                 gm.contribution_xp,
                 gm.contribution_captures,
-                CASE WHEN p.last_location_at > NOW() - INTERVAL '5 minutes' THEN true ELSE false END as is_online,
+                CASE WHEN p.last_location_at > NOW() - INTERVAL '5 minutes' THEN true ELSE false END as "is_online!",
                 gm.joined_at,
-                gm.last_active_at
+                gm.last_active_at,
+                COALESCE(p.experience, 0) - gm.week_start_xp as "weekly_xp!",
+                COALESCE(p.titans_captured, 0) - gm.week_start_captures as "weekly_captures!",
+                gm.missed_weeks,
+                EXISTS(
+                    SELECT 1 FROM guild_vacations v
+                    WHERE v.player_id = gm.player_id AND v.starts_at <= NOW() AND v.ends_at > NOW()
+                ) as "on_vacation!"
             FROM guild_members gm
             JOIN players p ON p.id = gm.player_id
             WHERE gm.guild_id = $1
             ORDER BY gm.role, gm.contribution_xp DESC
             "#,
+            guild_id
         )
-        .bind(guild_id)
         .fetch_all(&self.db.pg)
-        .await?;
+        .await?
+        .into_iter()
+        .map(|r| GuildMemberInfo {
+            player_id: r.player_id,
+            username: r.username,
+            level: r.level,
+            role: r.role,
+            contribution_xp: r.contribution_xp,
+            contribution_captures: r.contribution_captures,
+            is_online: r.is_online,
+            joined_at: r.joined_at,
+            last_active_at: r.last_active_at,
+            weekly_xp: r.weekly_xp,
+            weekly_captures: r.weekly_captures,
+            missed_weeks: r.missed_weeks,
+            on_vacation: r.on_vacation,
+            standing: Default::default(),
+        })
+        .collect();
+
+        if let Some(guild) = self.get_guild(guild_id).await? {
+            let rules = guild.activity_rules();
+            for member in &mut members {
+                member.standing = rules.standing(
+                    member.role,
+                    member.weekly_xp,
+                    member.weekly_captures,
+                    member.missed_weeks,
+                    member.on_vacation,
+                );
+            }
+        }
 
         Ok(members)
     }
 
     /// Get player's guild membership
     pub async fn get_membership(&self, player_id: Uuid) -> ApiResult<Option<GuildMember>> {
-        let member = sqlx::query_as::<_, GuildMember>(
-            r#"SELECT * FROM guild_members WHERE player_id = $1"#,
+        let member = sqlx::query_as!(
+            GuildMember,
+            r#"
+            SELECT id, guild_id, player_id, role as "role: GuildRole", contribution_xp,
+                   contribution_captures, contribution_battles, joined_at, last_active_at,
+                   week_start_xp, week_start_captures, missed_weeks
+            FROM guild_members WHERE player_id = $1
+            "#,
+            player_id
         )
-        .bind(player_id)
         .fetch_optional(&self.db.pg)
         .await?;
 
@@ -203,7 +290,7 @@ impl GuildService {
         message: Option<String>,
     ) -> ApiResult<GuildRequest> {
         // Check if already in a guild
-        if let Some(_) = self.get_membership(player_id).await? {
+        if self.get_membership(player_id).await?.is_some() {
             return Err(AppError::BadRequest("Already in a guild".into()));
         }
 
@@ -212,10 +299,10 @@ impl GuildService {
             .ok_or(AppError::NotFound("Guild not found".into()))?;
 
         // Check player level
-        let player_level: i32 = sqlx::query_scalar(
-            r#"SELECT level FROM players WHERE id = $1"#,
+        let player_level = sqlx::query_scalar!(
+            r#"SELECT COALESCE(level, 1) as "level!" FROM players WHERE id = $1"#,
+            player_id
         )
-        .bind(player_id)
         .fetch_one(&self.db.pg)
         .await?;
 
@@ -227,10 +314,10 @@ impl GuildService {
         }
 
         // Check member count
-        let member_count: i64 = sqlx::query_scalar(
-            r#"SELECT COUNT(*) FROM guild_members WHERE guild_id = $1"#,
+        let member_count = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM guild_members WHERE guild_id = $1"#,
+            guild_id
         )
-        .bind(guild_id)
         .fetch_one(&self.db.pg)
         .await?;
 
@@ -254,7 +341,8 @@ impl GuildService {
         }
 
         // Create join request
-        let request = sqlx::query_as::<_, GuildRequest>(
+        let request = sqlx::query_as!(
+            GuildRequest,
             r#"
             INSERT INTO guild_requests (guild_id, player_id, message)
             VALUES ($1, $2, $3)
@@ -262,12 +350,13 @@ impl GuildService {
                 message = EXCLUDED.message,
                 status = 'pending',
                 created_at = NOW()
-            RETURNING *
+            RETURNING id, guild_id, player_id, message, status as "status: FriendRequestStatus",
+                      reviewed_by, created_at, reviewed_at
             "#,
+            guild_id,
+            player_id,
+            message
         )
-        .bind(guild_id)
-        .bind(player_id)
-        .bind(&message)
         .fetch_one(&self.db.pg)
         .await?;
 
@@ -285,14 +374,15 @@ impl GuildService {
 
     /// Get pending join requests
     pub async fn get_pending_requests(&self, guild_id: Uuid) -> ApiResult<Vec<GuildRequestWithPlayer>> {
-        let requests = sqlx::query_as::<_, GuildRequestWithPlayer>(
+        let requests = sqlx::query_as!(
+            GuildRequestWithPlayer,
             r#"
             SELECT 
                 gr.id,
                 gr.player_id,
                 p.username,
-                p.level,
-                p.titans_captured,
+                COALESCE(p.level, 1) as "level!",
+                COALESCE(p.titans_captured, 0) as "titans_captured!",
                 gr.message,
                 gr.created_at
             FROM guild_requests gr
@@ -300,8 +390,8 @@ impl GuildService {
             WHERE gr.guild_id = $1 AND gr.status = 'pending'
             ORDER BY gr.created_at DESC
             "#,
+            guild_id
         )
-        .bind(guild_id)
         .fetch_all(&self.db.pg)
         .await?;
 
@@ -316,10 +406,15 @@ impl GuildService {
         accept: bool,
     ) -> ApiResult<()> {
         // Get request
-        let request: GuildRequest = sqlx::query_as(
-            r#"SELECT * FROM guild_requests WHERE id = $1 AND status = 'pending'"#,
+        let request = sqlx::query_as!(
+            GuildRequest,
+            r#"
+            SELECT id, guild_id, player_id, message, status as "status: FriendRequestStatus",
+                   reviewed_by, created_at, reviewed_at
+            FROM guild_requests WHERE id = $1 AND status = 'pending'
+            "#,
+            request_id
         )
-        .bind(request_id)
         .fetch_optional(&self.db.pg)
         .await?
         .ok_or(AppError::NotFound("Request not found".into()))?;
@@ -339,16 +434,16 @@ impl GuildService {
         };
 
         // Update request
-        sqlx::query(
+        sqlx::query!(
             r#"
             UPDATE guild_requests 
             SET status = $2, reviewed_by = $3, reviewed_at = NOW()
             WHERE id = $1
             "#,
+            request_id,
+            new_status as FriendRequestStatus,
+            reviewer_id
         )
-        .bind(request_id)
-        .bind(new_status)
-        .bind(reviewer_id)
         .execute(&self.db.pg)
         .await?;
 
@@ -370,23 +465,24 @@ impl GuildService {
 
     /// Add member to guild
     async fn add_member(&self, guild_id: Uuid, player_id: Uuid, role: GuildRole) -> ApiResult<()> {
-        sqlx::query(
+        sqlx::query!(
             r#"
-            INSERT INTO guild_members (guild_id, player_id, role)
-            VALUES ($1, $2, $3)
+            INSERT INTO guild_members (guild_id, player_id, role, week_start_xp, week_start_captures)
+            SELECT $1, id, $3, COALESCE(experience, 0), COALESCE(titans_captured, 0)
+            FROM players WHERE id = $2
             "#,
+            guild_id,
+            player_id,
+            role as GuildRole
         )
-        .bind(guild_id)
-        .bind(player_id)
-        .bind(role)
         .execute(&self.db.pg)
         .await?;
 
-        sqlx::query(
+        sqlx::query!(
             r#"UPDATE players SET guild_id = $1 WHERE id = $2"#,
+            guild_id,
+            player_id
         )
-        .bind(guild_id)
-        .bind(player_id)
         .execute(&self.db.pg)
         .await?;
 
@@ -407,17 +503,17 @@ impl GuildService {
             ));
         }
 
-        sqlx::query(
+        sqlx::query!(
             r#"DELETE FROM guild_members WHERE player_id = $1"#,
+            player_id
         )
-        .bind(player_id)
         .execute(&self.db.pg)
         .await?;
 
-        sqlx::query(
+        sqlx::query!(
             r#"UPDATE players SET guild_id = NULL WHERE id = $1"#,
+            player_id
         )
-        .bind(player_id)
         .execute(&self.db.pg)
         .await?;
 
@@ -442,17 +538,17 @@ impl GuildService {
             return Err(AppError::Forbidden("Cannot kick this member".into()));
         }
 
-        sqlx::query(
+        sqlx::query!(
             r#"DELETE FROM guild_members WHERE player_id = $1"#,
+            target_id
         )
-        .bind(target_id)
         .execute(&self.db.pg)
         .await?;
 
-        sqlx::query(
+        sqlx::query!(
             r#"UPDATE players SET guild_id = NULL WHERE id = $1"#,
+            target_id
         )
-        .bind(target_id)
         .execute(&self.db.pg)
         .await?;
 
@@ -506,11 +602,11 @@ impl GuildService {
             return Err(AppError::Forbidden("No permission to change roles".into()));
         }
 
-        sqlx::query(
+        sqlx::query!(
             r#"UPDATE guild_members SET role = $2 WHERE player_id = $1"#,
+            target_id,
+            new_role as GuildRole
         )
-        .bind(target_id)
-        .bind(new_role)
         .execute(&self.db.pg)
         .await?;
 
@@ -546,7 +642,40 @@ impl GuildService {
             return Err(AppError::Forbidden("No permission".into()));
         }
 
-        let guild = sqlx::query_as::<_, Guild>(
+        let changes_activity = req.min_weekly_xp.is_some()
+            || req.min_weekly_captures.is_some()
+            || req.inactivity_policy.is_some()
+            || req.auto_kick_enabled.is_some();
+
+        // Activity requirements are leader-only
+        if changes_activity && member.role != GuildRole::Leader {
+            return Err(AppError::Forbidden("Only the leader can change activity requirements".into()));
+        }
+
+        let current = self.get_guild(guild_id).await?
+            .ok_or(AppError::NotFound("Guild not found".into()))?;
+
+        // 0 clears a requirement
+        let rules = GuildActivityRules {
+            min_weekly_xp: match req.min_weekly_xp {
+                Some(0) => None,
+                Some(xp) => Some(xp),
+                None => current.activity_min_weekly_xp,
+            },
+            min_weekly_captures: match req.min_weekly_captures {
+                Some(0) => None,
+                Some(captures) => Some(captures),
+                None => current.activity_min_weekly_captures,
+            },
+            policy: req.inactivity_policy.unwrap_or(current.inactivity_policy),
+            auto_kick_enabled: req.auto_kick_enabled.unwrap_or(current.auto_kick_enabled),
+        };
+        rules.validate().map_err(AppError::BadRequest)?;
+
+        let was_enabled = current.activity_rules().is_enabled();
+
+        let guild = sqlx::query_as!(
+            Guild,
             r#"
             UPDATE guilds SET
                 description = COALESCE($2, description),
@@ -554,23 +683,989 @@ impl GuildService {
                 banner = COALESCE($4, banner),
                 min_level = COALESCE($5, min_level),
                 is_public = COALESCE($6, is_public),
+                activity_min_weekly_xp = $7,
+                activity_min_weekly_captures = $8,
+                inactivity_policy = $9,
+                auto_kick_enabled = $10,
                 updated_at = NOW()
             WHERE id = $1
-            RETURNING *
+            RETURNING id, name, tag, description, icon, banner, leader_id, min_level, is_public,
+                      max_members, total_captures, total_battles, total_breach, weekly_xp,
+                      season_rank, season_points, created_at, updated_at,
+                      activity_min_weekly_xp, activity_min_weekly_captures,
+                      inactivity_policy as "inactivity_policy: InactivityPolicy",
+                      auto_kick_enabled, activity_week_started_at
             "#,
+            guild_id,
+            req.description,
+            req.icon,
+            req.banner,
+            req.min_level,
+            req.is_public,
+            rules.min_weekly_xp,
+            rules.min_weekly_captures,
+            rules.policy as InactivityPolicy,
+            rules.auto_kick_enabled
         )
-        .bind(guild_id)
-        .bind(&req.description)
-        .bind(&req.icon)
-        .bind(&req.banner)
-        .bind(req.min_level)
-        .bind(req.is_public)
         .fetch_one(&self.db.pg)
         .await?;
 
+        if !changes_activity {
+            return Ok(guild);
+        }
+
+        // Start (or stop) tracking weeks
+        let guild = match (was_enabled, rules.is_enabled()) {
+            (false, true) => self.start_activity_week(guild_id).await?,
+            (true, false) => {
+                sqlx::query_as!(
+                    Guild,
+                    r#"
+                    UPDATE guilds SET activity_week_started_at = NULL WHERE id = $1
+                    RETURNING id, name, tag, description, icon, banner, leader_id, min_level, is_public,
+                              max_members, total_captures, total_battles, total_breach,
+                              weekly_xp, season_rank, season_points, created_at,
+                              updated_at, activity_min_weekly_xp,
+                              activity_min_weekly_captures,
+                              inactivity_policy as "inactivity_policy: InactivityPolicy",
+                              auto_kick_enabled, activity_week_started_at
+                    "#,
+                    guild_id
+                )
+                .fetch_one(&self.db.pg)
+                .await?
+            }
+            _ => guild,
+        };
+
+        self.log_activity(
+            guild_id,
+            Some(player_id),
+            "activity_requirements",
+            Some(serde_json::json!({
+                "min_weekly_xp": rules.min_weekly_xp,
+                "min_weekly_captures": rules.min_weekly_captures,
+                "policy": rules.policy,
+                "auto_kick_enabled": rules.auto_kick_enabled,
+            })),
+        ).await?;
+
         Ok(guild)
     }
 
+    // ============================================
+    // Activity Requirements
+    // ============================================
+
+    /// Start a new activity week: snapshot member baselines
+    async fn start_activity_week(&self, guild_id: Uuid) -> ApiResult<Guild> {
+        sqlx::query!(
+            r#"
+            UPDATE guild_members gm SET
+                week_start_xp = COALESCE(p.experience, 0),
+                week_start_captures = COALESCE(p.titans_captured, 0)
+            FROM players p
+            WHERE p.id = gm.player_id AND gm.guild_id = $1
+            "#,
+            guild_id
+        )
+        .execute(&self.db.pg)
+        .await?;
+
+        let guild = sqlx::query_as!(
+            Guild,
+            r#"
+            UPDATE guilds SET activity_week_started_at = NOW() WHERE id = $1
+            RETURNING id, name, tag, description, icon, banner, leader_id, min_level, is_public,
+                      max_members, total_captures, total_battles, total_breach, weekly_xp,
+                      season_rank, season_points, created_at, updated_at,
+                      activity_min_weekly_xp, activity_min_weekly_captures,
+                      inactivity_policy as "inactivity_policy: InactivityPolicy",
+                      auto_kick_enabled, activity_week_started_at
+            "#,
+            guild_id
+        )
+        .fetch_one(&self.db.pg)
+        .await?;
+
+        Ok(guild)
+    }
+
+    /// Evaluate every guild whose activity week has ended.
+    ///
+    /// Returns the number of guilds evaluated.
+    pub async fn run_activity_evaluations(&self) -> ApiResult<u32> {
+        let guilds = sqlx::query_as!(
+            Guild,
+            r#"
+            SELECT id, name, tag, description, icon, banner, leader_id, min_level, is_public,
+                   max_members, total_captures, total_battles, total_breach, weekly_xp,
+                   season_rank, season_points, created_at, updated_at,
+                   activity_min_weekly_xp, activity_min_weekly_captures,
+                   inactivity_policy as "inactivity_policy: InactivityPolicy",
+                   auto_kick_enabled, activity_week_started_at
+            FROM guilds
+            WHERE activity_week_started_at IS NOT NULL
+              AND activity_week_started_at <= NOW() - INTERVAL '7 days'
+            "#
+        )
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        let mut evaluated = 0;
+        for guild in guilds {
+            if let Err(e) = self.evaluate_guild_activity(&guild).await {
+                tracing::error!("Guild {} activity evaluation failed: {:?}", guild.id, e);
+                continue;
+            }
+            evaluated += 1;
+        }
+
+        Ok(evaluated)
+    }
+
+    /// Evaluate a guild's members for the week that just ended
+    async fn evaluate_guild_activity(&self, guild: &Guild) -> ApiResult<()> {
+        let rules = guild.activity_rules();
+        let week_started_at = guild.activity_week_started_at.unwrap_or_else(chrono::Utc::now);
+
+        let members = sqlx::query!(
+            r#"
+            SELECT
+                gm.player_id,
+                gm.role as "role: GuildRole",
+                gm.missed_weeks,
+                COALESCE(p.experience, 0) - gm.week_start_xp as "weekly_xp!",
+                COALESCE(p.titans_captured, 0) - gm.week_start_captures as "weekly_captures!",
+                EXISTS(
+                    SELECT 1 FROM guild_vacations v
+                    WHERE v.player_id = gm.player_id AND v.starts_at < NOW() AND v.ends_at > $2
+                ) as "on_vacation!",
+                gm.joined_at > $2 as "joined_mid_week!"
+            FROM guild_members gm
+            JOIN players p ON p.id = gm.player_id
+            WHERE gm.guild_id = $1
+            "#,
+            guild.id,
+            week_started_at
+        )
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        for m in members {
+            // Members who joined mid-week start with the next one
+            let outcome = if m.joined_mid_week {
+                ActivityOutcome::Exempt
+            } else {
+                rules.evaluate(m.role, m.weekly_xp, m.weekly_captures, m.missed_weeks, m.on_vacation)
+            };
+
+            self.apply_activity_outcome(guild.id, m.player_id, outcome, m.weekly_xp, m.weekly_captures, m.missed_weeks)
+                .await?;
+        }
+
+        self.start_activity_week(guild.id).await?;
+
+        Ok(())
+    }
+
+    /// Apply a weekly evaluation outcome to a member
+    async fn apply_activity_outcome(
+        &self,
+        guild_id: Uuid,
+        player_id: Uuid,
+        outcome: ActivityOutcome,
+        weekly_xp: i64,
+        weekly_captures: i32,
+        missed_weeks: i32,
+    ) -> ApiResult<()> {
+        let details = serde_json::json!({
+            "weekly_xp": weekly_xp,
+            "weekly_captures": weekly_captures,
+            "missed_weeks": outcome.missed_weeks(missed_weeks),
+        });
+
+        match outcome {
+            ActivityOutcome::Exempt | ActivityOutcome::Met => {}
+            ActivityOutcome::Warn | ActivityOutcome::Flagged => {
+                self.log_activity(guild_id, Some(player_id), "inactivity_warning", Some(details)).await?;
+                self.create_notification(
+                    player_id,
+                    NotificationType::GuildInactivityWarning,
+                    "Guild Activity Warning",
+                    "You missed your guild's weekly activity requirement. Meet it this week to keep your place.",
+                    Some(serde_json::json!({ "guild_id": guild_id })),
+                ).await?;
+            }
+            ActivityOutcome::Demote(new_role) => {
+                sqlx::query!(
+                    r#"UPDATE guild_members SET role = $2 WHERE player_id = $1"#,
+                    player_id,
+                    new_role as GuildRole
+                )
+                .execute(&self.db.pg)
+                .await?;
+
+                self.log_activity(guild_id, Some(player_id), "inactivity_demote", Some(details)).await?;
+                self.create_notification(
+                    player_id,
+                    NotificationType::GuildDemoted,
+                    "Role Changed",
+                    &format!("You were demoted to {:?} for missing the weekly activity requirement", new_role),
+                    Some(serde_json::json!({ "guild_id": guild_id, "new_role": new_role })),
+                ).await?;
+            }
+            ActivityOutcome::Kick => {
+                sqlx::query!(
+                    r#"DELETE FROM guild_members WHERE player_id = $1"#,
+                    player_id
+                )
+                .execute(&self.db.pg)
+                .await?;
+
+                sqlx::query!(
+                    r#"UPDATE players SET guild_id = NULL WHERE id = $1"#,
+                    player_id
+                )
+                .execute(&self.db.pg)
+                .await?;
+
+                self.log_activity(guild_id, Some(player_id), "inactivity_kick", Some(details)).await?;
+                self.create_notification(
+                    player_id,
+                    NotificationType::GuildKicked,
+                    "Removed from Guild",
+                    "You were removed from the guild for missing the weekly activity requirement twice.",
+                    Some(serde_json::json!({ "guild_id": guild_id })),
+                ).await?;
+
+                return Ok(());
+            }
+        }
+
+        sqlx::query!(
+            r#"UPDATE guild_members SET missed_weeks = $2 WHERE player_id = $1"#,
+            player_id,
+            outcome.missed_weeks(missed_weeks)
+        )
+        .execute(&self.db.pg)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Start a vacation (exempt from activity requirements)
+    pub async fn start_vacation(&self, player_id: Uuid, weeks: i64) -> ApiResult<GuildVacation> {
+        let member = self.get_membership(player_id).await?
+            .ok_or(AppError::NotFound("Not in a guild".into()))?;
+
+        let active = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM guild_vacations WHERE player_id = $1 AND ends_at > NOW()) as "active!""#,
+            player_id
+        )
+        .fetch_one(&self.db.pg)
+        .await?;
+
+        if active {
+            return Err(AppError::BadRequest("Already on vacation".into()));
+        }
+
+        let used_seconds = sqlx::query_scalar!(
+            r#"
+            SELECT COALESCE(SUM(EXTRACT(EPOCH FROM (ends_at - starts_at))), 0)::BIGINT as "seconds!"
+            FROM guild_vacations
+            WHERE player_id = $1 AND starts_at > NOW() - INTERVAL '1 year'
+            "#,
+            player_id
+        )
+        .fetch_one(&self.db.pg)
+        .await?;
+
+        if !GuildVacation::fits_allowance(used_seconds, weeks) {
+            return Err(AppError::BadRequest(format!(
+                "Vacation exceeds the {} week yearly allowance",
+                GuildVacation::MAX_WEEKS_PER_YEAR
+            )));
+        }
+
+        let vacation = sqlx::query_as!(
+            GuildVacation,
+            r#"
+            INSERT INTO guild_vacations (player_id, ends_at)
+            VALUES ($1, NOW() + make_interval(weeks => $2))
+            RETURNING id, player_id, starts_at, ends_at, created_at
+            "#,
+            player_id,
+            weeks as i32
+        )
+        .fetch_one(&self.db.pg)
+        .await?;
+
+        self.log_activity(
+            member.guild_id,
+            Some(player_id),
+            "vacation_start",
+            Some(serde_json::json!({ "ends_at": vacation.ends_at })),
+        ).await?;
+
+        Ok(vacation)
+    }
+
+    /// End an active vacation early
+    pub async fn end_vacation(&self, player_id: Uuid) -> ApiResult<()> {
+        let member = self.get_membership(player_id).await?
+            .ok_or(AppError::NotFound("Not in a guild".into()))?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE guild_vacations SET ends_at = NOW()
+            WHERE player_id = $1 AND starts_at <= NOW() AND ends_at > NOW()
+            "#,
+            player_id
+        )
+        .execute(&self.db.pg)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Not on vacation".into()));
+        }
+
+        self.log_activity(member.guild_id, Some(player_id), "vacation_end", None).await?;
+
+        Ok(())
+    }
+
+    /// Get a guild's treasury balance and recent ledger entries (members only)
+    pub async fn get_treasury(&self, guild_id: Uuid, viewer_id: Uuid) -> ApiResult<GuildTreasury> {
+        let member = self.get_membership(viewer_id).await?;
+        if member.map(|m| m.guild_id) != Some(guild_id) {
+            return Err(AppError::Forbidden("Not a member of this guild".into()));
+        }
+
+        let balance = sqlx::query_scalar!("SELECT breach_treasury FROM guilds WHERE id = $1", guild_id)
+            .fetch_optional(&self.db.pg)
+            .await?
+            .ok_or(AppError::NotFound("Guild not found".into()))?;
+
+        let entries = sqlx::query_as!(
+            GuildTreasuryEntry,
+            r#"
+            SELECT id, player_id, kind, amount, balance_after, reason,
+                   marketplace_transaction_id, created_at
+            FROM guild_treasury_log
+            WHERE guild_id = $1
+            ORDER BY created_at DESC, id DESC
+            LIMIT $2
+            "#,
+            guild_id,
+            TREASURY_LOG_LIMIT
+        )
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        Ok(GuildTreasury { guild_id, balance, entries })
+    }
+
+    /// Move $BREACH from a member's in-game balance into their guild's treasury
+    pub async fn deposit_to_treasury(&self, player_id: Uuid, amount: i64) -> ApiResult<i64> {
+        if amount <= 0 {
+            return Err(AppError::Validation("Amount must be positive".into()));
+        }
+
+        let mut tx = self.db.pg.begin().await?;
+
+        let debited = sqlx::query!(
+            r#"
+            UPDATE players SET breach_earned = breach_earned - $2
+            WHERE id = $1 AND breach_earned >= $2
+            "#,
+            player_id,
+            amount
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if debited.rows_affected() == 0 {
+            return Err(AppError::BadRequest("Insufficient $BREACH balance".into()));
+        }
+
+        let balance = Self::credit_treasury(&mut tx, player_id, amount, "deposit", None)
+            .await?
+            .ok_or(AppError::NotFound("Not in a guild".into()))?;
+
+        tx.commit().await?;
+
+        Ok(balance)
+    }
+
+    /// Credit a player's guild treasury inside `tx`, returning the new
+    /// balance or `None` when the player has no guild
+    pub async fn credit_treasury(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        player_id: Uuid,
+        amount: i64,
+        kind: &str,
+        marketplace_transaction_id: Option<Uuid>,
+    ) -> ApiResult<Option<i64>> {
+        if amount <= 0 {
+            return Ok(None);
+        }
+
+        let credited = sqlx::query!(
+            r#"
+            UPDATE guilds SET breach_treasury = breach_treasury + $2
+            WHERE id = (SELECT guild_id FROM guild_members WHERE player_id = $1)
+            RETURNING id, breach_treasury
+            "#,
+            player_id,
+            amount
+        )
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        let Some(credited) = credited else {
+            return Ok(None);
+        };
+        let balance = credited.breach_treasury;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO guild_treasury_log
+            (guild_id, player_id, kind, amount, balance_after, marketplace_transaction_id)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            credited.id,
+            player_id,
+            kind,
+            amount,
+            balance,
+            marketplace_transaction_id
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(Some(balance))
+    }
+
+    /// Withdraw from the guild treasury to the actor's in-game balance (leader/co-leader only)
+    pub async fn withdraw_from_treasury(
+        &self,
+        actor_id: Uuid,
+        amount: i64,
+        reason: &str,
+    ) -> ApiResult<i64> {
+        if amount <= 0 {
+            return Err(AppError::Validation("Amount must be positive".into()));
+        }
+        let reason = reason.trim();
+        if reason.is_empty() || reason.len() > 200 {
+            return Err(AppError::Validation("Reason must be 1-200 characters".into()));
+        }
+
+        let actor = self.get_membership(actor_id).await?
+            .ok_or(AppError::Forbidden("Not in a guild".into()))?;
+
+        if !actor.role.can_manage() {
+            return Err(AppError::Forbidden("Only leaders can withdraw from the treasury".into()));
+        }
+
+        let mut tx = self.db.pg.begin().await?;
+
+        // The balance guard and the debit are one statement, so concurrent
+        // withdrawals serialize on the guild row and can't overdraw it
+        let balance = sqlx::query_scalar!(
+            r#"
+            UPDATE guilds SET breach_treasury = breach_treasury - $2
+            WHERE id = $1 AND breach_treasury >= $2
+            RETURNING breach_treasury
+            "#,
+            actor.guild_id,
+            amount
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(AppError::BadRequest("Insufficient treasury balance".into()))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO guild_treasury_log (guild_id, player_id, kind, amount, balance_after, reason)
+            VALUES ($1, $2, 'withdrawal', $3, $4, $5)
+            "#,
+            actor.guild_id,
+            actor_id,
+            amount,
+            balance,
+            reason
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!("UPDATE players SET breach_earned = breach_earned + $2 WHERE id = $1", actor_id, amount)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        self.log_activity(
+            actor.guild_id,
+            Some(actor_id),
+            "treasury_withdrawal",
+            Some(serde_json::json!({ "amount": amount, "reason": reason, "balance": balance })),
+        ).await?;
+
+        Ok(balance)
+    }
+
+    /// Challenge another guild to a war lasting `duration_hours` from when
+    /// it's accepted (leader/co-leader only)
+    pub async fn challenge_war(
+        &self,
+        actor_id: Uuid,
+        target_guild_id: Uuid,
+        duration_hours: i32,
+    ) -> ApiResult<GuildWar> {
+        if !(1..=168).contains(&duration_hours) {
+            return Err(AppError::Validation("Wars last 1-168 hours".into()));
+        }
+
+        let actor = self.get_membership(actor_id).await?
+            .ok_or(AppError::Forbidden("Not in a guild".into()))?;
+        if !actor.role.can_manage() {
+            return Err(AppError::Forbidden("Only leaders can declare war".into()));
+        }
+        if actor.guild_id == target_guild_id {
+            return Err(AppError::BadRequest("A guild can't challenge itself".into()));
+        }
+
+        let challenger = self.get_guild(actor.guild_id).await?
+            .ok_or(AppError::NotFound("Guild not found".into()))?;
+        let defender = self.get_guild(target_guild_id).await?
+            .ok_or(AppError::NotFound("Guild not found".into()))?;
+
+        if Self::any_at_war(&self.db.pg, &[challenger.id, defender.id]).await? {
+            return Err(AppError::BadRequest("One of the guilds is already at war".into()));
+        }
+        let pending = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM guild_wars
+                WHERE status = 'pending'
+                  AND ((challenger_guild_id = $1 AND defender_guild_id = $2)
+                    OR (challenger_guild_id = $2 AND defender_guild_id = $1))
+            ) as "pending!"
+            "#,
+            challenger.id,
+            defender.id
+        )
+        .fetch_one(&self.db.pg)
+        .await?;
+        if pending {
+            return Err(AppError::BadRequest("A challenge between these guilds is already pending".into()));
+        }
+
+        let war = sqlx::query_as!(
+            GuildWar,
+            r#"
+            INSERT INTO guild_wars
+                (challenger_guild_id, defender_guild_id, challenger_name, defender_name,
+                 duration_hours, challenged_by)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, challenger_guild_id, defender_guild_id, challenger_name, defender_name,
+                      status as "status: GuildWarStatus", duration_hours, challenger_score, defender_score,
+                      winner_guild_id, challenged_by, created_at, starts_at, ends_at, ended_at
+            "#,
+            challenger.id,
+            defender.id,
+            &challenger.name,
+            &defender.name,
+            duration_hours,
+            actor_id
+        )
+        .fetch_one(&self.db.pg)
+        .await?;
+
+        self.log_activity(
+            challenger.id,
+            Some(actor_id),
+            "war_challenge",
+            Some(serde_json::json!({ "war_id": war.id, "guild_id": defender.id })),
+        ).await?;
+        self.notify_leaders(
+            defender.id,
+            NotificationType::GuildWar,
+            "War Declared",
+            &format!("{} challenged your guild to a {}-hour war", challenger.name, duration_hours),
+            Some(serde_json::json!({ "war_id": war.id, "guild_id": challenger.id })),
+        ).await?;
+
+        Ok(war)
+    }
+
+    /// Accept a challenge to the actor's guild; the war starts now
+    /// (leader/co-leader only)
+    pub async fn accept_war(&self, actor_id: Uuid, war_id: Uuid) -> ApiResult<GuildWar> {
+        let actor = self.get_membership(actor_id).await?
+            .ok_or(AppError::Forbidden("Not in a guild".into()))?;
+        if !actor.role.can_manage() {
+            return Err(AppError::Forbidden("Only leaders can accept a war".into()));
+        }
+
+        let mut tx = self.db.pg.begin().await?;
+
+        let war = sqlx::query_as!(
+            GuildWar,
+            r#"
+            SELECT id, challenger_guild_id, defender_guild_id, challenger_name, defender_name,
+                   status as "status: GuildWarStatus", duration_hours, challenger_score, defender_score,
+                   winner_guild_id, challenged_by, created_at, starts_at, ends_at, ended_at
+            FROM guild_wars WHERE id = $1 FOR UPDATE
+            "#,
+            war_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .filter(|war| war.defender_guild_id == Some(actor.guild_id))
+        .ok_or(AppError::NotFound("War challenge not found".into()))?;
+
+        if war.status != GuildWarStatus::Pending
+            || war.created_at < chrono::Utc::now() - chrono::Duration::hours(GuildWar::CHALLENGE_TTL_HOURS)
+        {
+            return Err(AppError::BadRequest("Challenge is no longer open".into()));
+        }
+        let Some(challenger_id) = war.challenger_guild_id else {
+            return Err(AppError::BadRequest("The challenging guild no longer exists".into()));
+        };
+
+        // Lock both guilds so two wars can't start for one of them at once
+        sqlx::query!(
+            "SELECT id FROM guilds WHERE id = ANY($1) ORDER BY id FOR UPDATE",
+            &[challenger_id, actor.guild_id][..]
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        if Self::any_at_war(&mut *tx, &[challenger_id, actor.guild_id]).await? {
+            return Err(AppError::BadRequest("One of the guilds is already at war".into()));
+        }
+
+        let war = sqlx::query_as!(
+            GuildWar,
+            r#"
+            UPDATE guild_wars
+            SET status = 'active', starts_at = NOW(),
+                ends_at = NOW() + make_interval(hours => duration_hours)
+            WHERE id = $1
+            RETURNING id, challenger_guild_id, defender_guild_id, challenger_name, defender_name,
+                      status as "status: GuildWarStatus", duration_hours, challenger_score, defender_score,
+                      winner_guild_id, challenged_by, created_at, starts_at, ends_at, ended_at
+            "#,
+            war_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        self.log_activity(actor.guild_id, Some(actor_id), "war_accept", Some(serde_json::json!({ "war_id": war.id }))).await?;
+        self.notify_leaders(
+            challenger_id,
+            NotificationType::GuildWar,
+            "War Accepted",
+            &format!("{} accepted your challenge; the war has begun", war.defender_name),
+            Some(serde_json::json!({ "war_id": war.id })),
+        ).await?;
+        self.publish_war(GuildWarEvent::Started, war.clone()).await;
+
+        Ok(war)
+    }
+
+    /// A guild's active war with each member's captures, if it's at war
+    pub async fn get_active_war(&self, guild_id: Uuid) -> ApiResult<Option<GuildWarDetails>> {
+        let war = sqlx::query_as!(
+            GuildWar,
+            r#"
+            SELECT id, challenger_guild_id, defender_guild_id, challenger_name, defender_name,
+                   status as "status: GuildWarStatus", duration_hours, challenger_score, defender_score,
+                   winner_guild_id, challenged_by, created_at, starts_at, ends_at, ended_at
+            FROM guild_wars
+            WHERE status = 'active' AND (challenger_guild_id = $1 OR defender_guild_id = $1)
+            "#,
+            guild_id
+        )
+        .fetch_optional(&self.db.pg)
+        .await?;
+
+        let Some(war) = war else {
+            return Ok(None);
+        };
+
+        let scores = sqlx::query_as!(
+            GuildWarScoreEntry,
+            r#"
+            SELECT s.player_id, p.username, s.guild_id, s.captures
+            FROM guild_war_scores s
+            JOIN players p ON p.id = s.player_id
+            WHERE s.war_id = $1
+            ORDER BY s.captures DESC, s.updated_at
+            "#,
+            war.id
+        )
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        Ok(Some(GuildWarDetails { war, scores }))
+    }
+
+    /// A guild's finished wars, most recent first
+    pub async fn get_war_history(&self, guild_id: Uuid, limit: i64, offset: i64) -> ApiResult<Vec<GuildWar>> {
+        let wars = sqlx::query_as!(
+            GuildWar,
+            r#"
+            SELECT id, challenger_guild_id, defender_guild_id, challenger_name, defender_name,
+                   status as "status: GuildWarStatus", duration_hours, challenger_score, defender_score,
+                   winner_guild_id, challenged_by, created_at, starts_at, ends_at, ended_at
+            FROM guild_wars
+            WHERE status = 'completed' AND (challenger_guild_id = $1 OR defender_guild_id = $1)
+            ORDER BY ended_at DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            guild_id,
+            limit,
+            offset
+        )
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        Ok(wars)
+    }
+
+    /// Count a confirmed capture inside `tx` toward the war of the player's
+    /// guild, returning the war with its new score if there is one
+    pub async fn record_war_capture(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        player_id: Uuid,
+    ) -> ApiResult<Option<GuildWar>> {
+        // A guild is in at most one active war
+        let war = sqlx::query_as!(
+            GuildWar,
+            r#"
+            WITH war AS (
+                SELECT w.id, m.guild_id
+                FROM guild_members m
+                JOIN guild_wars w ON w.status = 'active' AND w.ends_at > NOW()
+                    AND m.guild_id IN (w.challenger_guild_id, w.defender_guild_id)
+                WHERE m.player_id = $1
+                LIMIT 1
+            ), scored AS (
+                INSERT INTO guild_war_scores (war_id, player_id, guild_id, captures)
+                SELECT id, $1, guild_id, 1 FROM war
+                ON CONFLICT (war_id, player_id, guild_id)
+                DO UPDATE SET captures = guild_war_scores.captures + 1, updated_at = NOW()
+            )
+            UPDATE guild_wars w SET
+                challenger_score = w.challenger_score + (war.guild_id = w.challenger_guild_id)::INT,
+                defender_score = w.defender_score + (war.guild_id = w.defender_guild_id)::INT
+            FROM war
+            WHERE w.id = war.id
+            RETURNING w.id, w.challenger_guild_id, w.defender_guild_id, w.challenger_name, w.defender_name,
+                      w.status as "status: GuildWarStatus", w.duration_hours, w.challenger_score,
+                      w.defender_score, w.winner_guild_id, w.challenged_by, w.created_at, w.starts_at,
+                      w.ends_at, w.ended_at
+            "#,
+            player_id
+        )
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        Ok(war)
+    }
+
+    /// Expire unanswered challenges and close the wars whose window has
+    /// passed, returning how many wars were closed
+    pub async fn close_expired_wars(&self) -> ApiResult<u32> {
+        sqlx::query!(
+            r#"
+            UPDATE guild_wars SET status = 'expired', ended_at = NOW()
+            WHERE status = 'pending' AND created_at < NOW() - make_interval(hours => $1)
+            "#,
+            GuildWar::CHALLENGE_TTL_HOURS as i32
+        )
+        .execute(&self.db.pg)
+        .await?;
+
+        let mut closed = 0;
+        while let Some(war) = self.close_next_war().await? {
+            self.announce_war_result(&war).await;
+            self.publish_war(GuildWarEvent::Ended, war).await;
+            closed += 1;
+        }
+
+        Ok(closed)
+    }
+
+    /// Close one ended war and award its winner, or both sides of a tie
+    async fn close_next_war(&self) -> ApiResult<Option<GuildWar>> {
+        let mut tx = self.db.pg.begin().await?;
+
+        let war = sqlx::query_as!(
+            GuildWar,
+            r#"
+            SELECT id, challenger_guild_id, defender_guild_id, challenger_name, defender_name,
+                   status as "status: GuildWarStatus", duration_hours, challenger_score, defender_score,
+                   winner_guild_id, challenged_by, created_at, starts_at, ends_at, ended_at
+            FROM guild_wars
+            WHERE status = 'active' AND ends_at <= NOW()
+            ORDER BY ends_at
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+            "#
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(war) = war else {
+            return Ok(None);
+        };
+
+        let winner = war.winner();
+        let awards: Vec<(Uuid, i64)> = match winner {
+            Some(winner) => vec![(winner, GuildWar::WIN_SEASON_POINTS)],
+            None => war.guild_ids().map(|id| (id, GuildWar::TIE_SEASON_POINTS)).collect(),
+        };
+        for (guild_id, points) in awards {
+            sqlx::query!("UPDATE guilds SET season_points = season_points + $2 WHERE id = $1", guild_id, points)
+                .execute(&mut *tx)
+                .await?;
+        }
+        if let Some(winner) = winner {
+            let reason = format!("War: {} vs {}", war.challenger_name, war.defender_name);
+            Self::credit_guild_treasury(&mut tx, winner, GuildWar::WIN_TREASURY_BONUS, "war_bonus", &reason).await?;
+        }
+
+        let war = sqlx::query_as!(
+            GuildWar,
+            r#"
+            UPDATE guild_wars SET status = 'completed', winner_guild_id = $2, ended_at = NOW()
+            WHERE id = $1
+            RETURNING id, challenger_guild_id, defender_guild_id, challenger_name, defender_name,
+                      status as "status: GuildWarStatus", duration_hours, challenger_score, defender_score,
+                      winner_guild_id, challenged_by, created_at, starts_at, ends_at, ended_at
+            "#,
+            war.id,
+            winner
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(war))
+    }
+
+    /// Tell the members of each guild still standing how their war ended
+    async fn announce_war_result(&self, war: &GuildWar) {
+        let score = format!(
+            "{} {} - {} {}",
+            war.challenger_name, war.challenger_score, war.defender_score, war.defender_name
+        );
+
+        for guild_id in war.guild_ids() {
+            let (title, details) = match war.winner_guild_id {
+                Some(winner) if winner == guild_id => ("War Won", "victory"),
+                Some(_) => ("War Lost", "defeat"),
+                None => ("War Drawn", "draw"),
+            };
+            let data = serde_json::json!({ "war_id": war.id, "result": details });
+
+            if let Err(e) = self.log_activity(guild_id, None, "war_end", Some(data.clone())).await {
+                tracing::warn!("Failed to log war {} result: {}", war.id, e);
+            }
+            let members = match sqlx::query_scalar!("SELECT player_id FROM guild_members WHERE guild_id = $1", guild_id)
+                .fetch_all(&self.db.pg)
+                .await
+            {
+                Ok(members) => members,
+                Err(e) => {
+                    tracing::warn!("Failed to notify guild {} of war {}: {}", guild_id, war.id, e);
+                    continue;
+                }
+            };
+            for member_id in members {
+                if let Err(e) = self
+                    .create_notification(member_id, NotificationType::GuildWar, title, &score, Some(data.clone()))
+                    .await
+                {
+                    tracing::warn!("Failed to notify {} of war {}: {}", member_id, war.id, e);
+                }
+            }
+        }
+    }
+
+    /// Push a war update to the members of both guilds
+    pub async fn publish_war(&self, event: GuildWarEvent, war: GuildWar) {
+        let guild_ids: Vec<Uuid> = war.guild_ids().collect();
+        let recipients = sqlx::query_scalar!("SELECT player_id FROM guild_members WHERE guild_id = ANY($1)", &guild_ids)
+            .fetch_all(&self.db.pg)
+            .await;
+
+        match recipients {
+            // Ignore send errors (no forwarder running)
+            Ok(recipients) => {
+                let _ = self.live.send(GuildWarUpdate { event, war, recipients });
+            }
+            Err(e) => tracing::warn!("Failed to push war {} update: {}", war.id, e),
+        }
+    }
+
+    /// Whether any of `guild_ids` is in an active war
+    async fn any_at_war<'e>(executor: impl sqlx::PgExecutor<'e>, guild_ids: &[Uuid]) -> ApiResult<bool> {
+        let at_war = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM guild_wars
+                WHERE status = 'active'
+                  AND (challenger_guild_id = ANY($1) OR defender_guild_id = ANY($1))
+            ) as "at_war!"
+            "#,
+            guild_ids
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(at_war)
+    }
+
+    /// Credit a guild's treasury inside `tx`, returning the new balance
+    async fn credit_guild_treasury(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        guild_id: Uuid,
+        amount: i64,
+        kind: &str,
+        reason: &str,
+    ) -> ApiResult<i64> {
+        let balance = sqlx::query_scalar!(
+            "UPDATE guilds SET breach_treasury = breach_treasury + $2 WHERE id = $1 RETURNING breach_treasury",
+            guild_id,
+            amount
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO guild_treasury_log (guild_id, kind, amount, balance_after, reason)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            guild_id,
+            kind,
+            amount,
+            balance,
+            reason
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(balance)
+    }
+
     /// Helper: Log activity
     async fn log_activity(
         &self,
@@ -579,16 +1674,16 @@ impl GuildService {
         activity_type: &str,
         details: Option<serde_json::Value>,
     ) -> ApiResult<()> {
-        sqlx::query(
+        sqlx::query!(
             r#"
             INSERT INTO guild_activity (guild_id, player_id, activity_type, details)
             VALUES ($1, $2, $3, $4)
             "#,
+            guild_id,
+            player_id,
+            activity_type,
+            details
         )
-        .bind(guild_id)
-        .bind(player_id)
-        .bind(activity_type)
-        .bind(details)
         .execute(&self.db.pg)
         .await?;
 
@@ -604,19 +1699,9 @@ impl GuildService {
         message: &str,
         data: Option<serde_json::Value>,
     ) -> ApiResult<()> {
-        sqlx::query(
-            r#"
-            INSERT INTO notifications (player_id, notification_type, title, message, data)
-            VALUES ($1, $2, $3, $4, $5)
-            "#,
-        )
-        .bind(player_id)
-        .bind(notification_type)
-        .bind(title)
-        .bind(message)
-        .bind(data)
-        .execute(&self.db.pg)
-        .await?;
+        self.notification
+            .create(player_id, notification_type, title, message, data, None)
+            .await?;
 
         Ok(())
     }
@@ -630,13 +1715,13 @@ impl GuildService {
         message: &str,
         data: Option<serde_json::Value>,
     ) -> ApiResult<()> {
-        let leaders: Vec<Uuid> = sqlx::query_scalar(
+        let leaders: Vec<Uuid> = sqlx::query_scalar!(
             r#"
             SELECT player_id FROM guild_members 
             WHERE guild_id = $1 AND (role = 'leader' OR role = 'co_leader')
             "#,
+            guild_id
         )
-        .bind(guild_id)
         .fetch_all(&self.db.pg)
         .await?;
 
@@ -6,8 +6,9 @@ use crate::db::Database;
 use crate::error::{ApiResult, AppError};
 use crate::models::{
     AddTitanRequest, Element, ElementCount, InventorySummary, PlayerTitan, ThreatClassCount,
-    TitanDetailResponse, TitanStats, UpdateTitanRequest,
+    TitanDetailResponse, TitanStats, TitanSyncReport, UpdateTitanRequest,
 };
+use crate::services::SolanaService;
 
 /// Inventory service
 #[derive(Clone)]
@@ -102,9 +103,9 @@ impl InventoryService {
             r#"
             INSERT INTO player_titans (
                 player_id, mint_address, species_id, element, threat_class, genes,
-                captured_at, capture_location_lat, capture_location_lng
+                captured_at, capture_location_lat, capture_location_lng, creator_id
             )
-            VALUES ($1, $2, $3, $4, $5, $6, NOW(), $7, $8)
+            VALUES ($1, $2, $3, $4, $5, $6, NOW(), $7, $8, $1)
             RETURNING *
             "#,
         )
@@ -156,6 +157,92 @@ impl InventoryService {
         Ok(titan)
     }
 
+    /// Reject on-chain Titans that an active marketplace listing holds in
+    /// escrow; the seller can't fuse or transfer them away from a buyer
+    pub async fn ensure_not_escrowed(&self, onchain_ids: &[u64]) -> ApiResult<()> {
+        let ids: Vec<i64> = onchain_ids.iter().map(|&id| id as i64).collect();
+        let escrowed: bool = sqlx::query_scalar(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM player_titans
+                WHERE onchain_id = ANY($1) AND escrowed_listing_id IS NOT NULL
+            )
+            "#,
+        )
+        .bind(&ids)
+        .fetch_one(&self.db.pg)
+        .await?;
+
+        if escrowed {
+            return Err(AppError::TitanEscrowed);
+        }
+        Ok(())
+    }
+
+    /// Reconcile a player's minted Titans with their on-chain PDAs: refresh
+    /// level and experience, and flag Titans now owned by another wallet or
+    /// whose PDA no longer exists
+    pub async fn sync_onchain(
+        &self,
+        player_id: Uuid,
+        wallet_address: &str,
+        solana: &SolanaService,
+    ) -> ApiResult<TitanSyncReport> {
+        let titans: Vec<(Uuid, i64, i32, i64)> = sqlx::query_as(
+            r#"
+            SELECT id, onchain_id, level, experience FROM player_titans
+            WHERE player_id = $1 AND onchain_id IS NOT NULL
+            "#,
+        )
+        .bind(player_id)
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        let onchain_ids: Vec<u64> = titans.iter().map(|&(_, onchain_id, _, _)| onchain_id as u64).collect();
+        let accounts = solana.get_titan_accounts(&onchain_ids).await?;
+
+        let mut report = TitanSyncReport::default();
+        for ((id, _, level, experience), account) in titans.into_iter().zip(accounts) {
+            report.checked += 1;
+
+            // A missing PDA keeps the last known level and experience
+            let (new_level, new_experience, mismatch, missing) = match account {
+                Some(titan) => (
+                    titan.level as i32,
+                    titan.experience as i64,
+                    titan.owner.to_string() != wallet_address,
+                    false,
+                ),
+                None => (level, experience, false, true),
+            };
+
+            if (new_level, new_experience) != (level, experience) {
+                report.updated += 1;
+            }
+            report.mismatched += mismatch as u32;
+            report.missing += missing as u32;
+
+            sqlx::query(
+                r#"
+                UPDATE player_titans
+                SET level = $2, experience = $3,
+                    onchain_owner_mismatch = $4, onchain_missing = $5, onchain_synced_at = NOW(),
+                    updated_at = CASE WHEN level <> $2 OR experience <> $3 THEN NOW() ELSE updated_at END
+                WHERE id = $1
+                "#,
+            )
+            .bind(id)
+            .bind(new_level)
+            .bind(new_experience)
+            .bind(mismatch)
+            .bind(missing)
+            .execute(&self.db.pg)
+            .await?;
+        }
+
+        Ok(report)
+    }
+
     /// Get inventory summary
     pub async fn get_summary(&self, player_id: Uuid) -> ApiResult<InventorySummary> {
         // Total count
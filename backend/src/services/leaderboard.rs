@@ -1,10 +1,32 @@
 //! Leaderboard service
 
+use chrono::{DateTime, Duration, Utc, Weekday};
 use uuid::Uuid;
 
 use crate::db::Database;
-use crate::error::ApiResult;
-use crate::models::{LeaderboardResponse, LeaderboardResponseEntry, LeaderboardType};
+use crate::error::{ApiResult, AppError};
+use crate::models::{
+    last_rollover, week_label, LeaderboardBoard, LeaderboardHistoryResponse, LeaderboardResponse,
+    LeaderboardResponseEntry, LeaderboardSnapshotEntry, LeaderboardType, WeeklySnapshot,
+};
+
+/// Live standings of a snapshot board: `subject_id`, `name`, `score`
+fn live_board_query(board: LeaderboardBoard) -> &'static str {
+    match board {
+        LeaderboardBoard::Captures => {
+            "SELECT id AS subject_id, username AS name, titans_captured::BIGINT AS score FROM players WHERE is_banned = false"
+        }
+        LeaderboardBoard::Experience => {
+            "SELECT id AS subject_id, username AS name, experience::BIGINT AS score FROM players WHERE is_banned = false"
+        }
+        LeaderboardBoard::PvpElo => {
+            "SELECT player_id AS subject_id, username AS name, elo_rating::BIGINT AS score FROM pvp_leaderboard"
+        }
+        LeaderboardBoard::GuildWeeklyXp => {
+            "SELECT id AS subject_id, name, weekly_xp::BIGINT AS score FROM guilds"
+        }
+    }
+}
 
 /// Leaderboard service
 #[derive(Clone)]
@@ -142,6 +164,165 @@ impl LeaderboardService {
         Ok(())
     }
 
+    // ==========================================
+    // WEEKLY SNAPSHOTS
+    // ==========================================
+
+    /// Snapshot the top `size` of every board for the week that ended at the
+    /// last rollover before `now`, then reset the weekly counters. `None`
+    /// when that week's snapshots were already taken.
+    pub async fn take_weekly_snapshots(
+        &self,
+        weekday: Weekday,
+        hour: u32,
+        size: i64,
+        now: DateTime<Utc>,
+    ) -> ApiResult<Option<WeeklySnapshot>> {
+        let rollover = last_rollover(weekday, hour, now);
+        let period = week_label(rollover - Duration::days(7));
+
+        let mut tx = self.db.pg.begin().await?;
+        let mut boards = Vec::new();
+
+        for board in LeaderboardBoard::ALL {
+            // Taken already, here or on another instance
+            let snapshot_id: Option<Uuid> = sqlx::query_scalar(
+                r#"
+                INSERT INTO leaderboard_snapshots (board, period, taken_at)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (board, period) DO NOTHING
+                RETURNING id
+                "#,
+            )
+            .bind(board)
+            .bind(&period)
+            .bind(now)
+            .fetch_optional(&mut *tx)
+            .await?;
+            let Some(snapshot_id) = snapshot_id else {
+                continue;
+            };
+
+            let query = format!(
+                r#"
+                INSERT INTO leaderboard_snapshot_entries (snapshot_id, rank, subject_id, name, score)
+                SELECT $1, ROW_NUMBER() OVER (ORDER BY score DESC, subject_id), subject_id, name, score
+                FROM ({}) live
+                ORDER BY score DESC, subject_id
+                LIMIT $2
+                "#,
+                live_board_query(board)
+            );
+            sqlx::query(&query)
+                .bind(snapshot_id)
+                .bind(size)
+                .execute(&mut *tx)
+                .await?;
+
+            boards.push(board);
+        }
+
+        if boards.is_empty() {
+            return Ok(None);
+        }
+
+        // A new week starts from zero
+        if boards.contains(&LeaderboardBoard::GuildWeeklyXp) {
+            sqlx::query("UPDATE guilds SET weekly_xp = 0").execute(&mut *tx).await?;
+        }
+
+        tx.commit().await?;
+
+        tracing::info!("Took leaderboard snapshots for week {}: {:?}", period, boards);
+
+        Ok(Some(WeeklySnapshot { period, boards }))
+    }
+
+    /// A board as it stood at the end of `week` (the latest snapshot when
+    /// `None`), with the player's place on it
+    pub async fn get_history(
+        &self,
+        board: LeaderboardBoard,
+        week: Option<&str>,
+        player_id: Uuid,
+    ) -> ApiResult<LeaderboardHistoryResponse> {
+        let (snapshot_id, period, taken_at): (Uuid, String, DateTime<Utc>) = sqlx::query_as(
+            r#"
+            SELECT id, period, taken_at FROM leaderboard_snapshots
+            WHERE board = $1 AND ($2::VARCHAR IS NULL OR period = $2)
+            ORDER BY taken_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(board)
+        .bind(week)
+        .fetch_optional(&self.db.pg)
+        .await?
+        .ok_or_else(|| AppError::NotFound("No leaderboard snapshot for that week".into()))?;
+
+        let entries = sqlx::query_as::<_, LeaderboardSnapshotEntry>(
+            r#"
+            SELECT rank, subject_id, name, score FROM leaderboard_snapshot_entries
+            WHERE snapshot_id = $1
+            ORDER BY rank
+            "#,
+        )
+        .bind(snapshot_id)
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        let (my_rank, my_score) = self.snapshot_rank(snapshot_id, board, player_id).await?.unzip();
+
+        Ok(LeaderboardHistoryResponse {
+            board,
+            period,
+            taken_at,
+            entries,
+            my_rank,
+            my_score,
+        })
+    }
+
+    /// The player's rank and score on a board in last week's snapshot (their
+    /// guild's on the guild board); `None` when they were outside it
+    pub async fn my_rank_last_week(&self, player_id: Uuid, board: LeaderboardBoard) -> ApiResult<Option<(i32, i64)>> {
+        let snapshot_id: Option<Uuid> = sqlx::query_scalar(
+            "SELECT id FROM leaderboard_snapshots WHERE board = $1 ORDER BY taken_at DESC LIMIT 1",
+        )
+        .bind(board)
+        .fetch_optional(&self.db.pg)
+        .await?;
+
+        match snapshot_id {
+            Some(snapshot_id) => self.snapshot_rank(snapshot_id, board, player_id).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn snapshot_rank(
+        &self,
+        snapshot_id: Uuid,
+        board: LeaderboardBoard,
+        player_id: Uuid,
+    ) -> ApiResult<Option<(i32, i64)>> {
+        let rank = sqlx::query_as(
+            r#"
+            SELECT rank, score FROM leaderboard_snapshot_entries
+            WHERE snapshot_id = $1
+              AND subject_id = CASE WHEN $3
+                  THEN (SELECT guild_id FROM guild_members WHERE player_id = $2)
+                  ELSE $2 END
+            "#,
+        )
+        .bind(snapshot_id)
+        .bind(player_id)
+        .bind(board.is_guild())
+        .fetch_optional(&self.db.pg)
+        .await?;
+
+        Ok(rank)
+    }
+
     /// Get top players for a specific stat
     pub async fn get_top_by_stat(
         &self,
@@ -1,25 +1,41 @@
 //! Location verification service
 
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
 use uuid::Uuid;
 
-use crate::config::AppConfig;
+use crate::config::SharedGameConfig;
 use crate::db::Database;
-use crate::error::ApiResult;
+use crate::error::{ApiResult, AppError};
 use crate::models::{
-    LocationReport, LocationVerification, PlayerLocation, VerificationFlag, VerificationStatus,
+    LocationReport, LocationSource, LocationVerification, MovementCheck, PlayerLocation,
+    QuestEvent, VerificationFlag, VerificationStatus,
 };
+use crate::services::QuestService;
+use crate::utils::geo::{implied_speed, trail_fit_residual};
+
+/// A previous position: latitude, longitude and when it was reported
+pub type Fix = (f64, f64, DateTime<Utc>);
+
+/// Idle time after which a player's trail is dropped and started over
+const TRAIL_TTL_SECS: u64 = 3600;
+
+/// Redis list of a player's recent fixes, newest first:
+/// `location_trail:{player_id}` holding `lat,lng,unix_ms`
+fn location_trail_key(player_id: Uuid) -> String {
+    format!("location_trail:{}", player_id)
+}
 
 /// Location verification service
 #[derive(Clone)]
 pub struct LocationService {
-    config: AppConfig,
+    game: SharedGameConfig,
     db: Database,
+    quest: QuestService,
 }
 
 impl LocationService {
-    pub fn new(config: AppConfig, db: Database) -> Self {
-        Self { config, db }
+    pub fn new(game: SharedGameConfig, db: Database, quest: QuestService) -> Self {
+        Self { game, db, quest }
     }
 
     /// Verify a player's reported location
@@ -29,39 +45,39 @@ impl LocationService {
         location: &PlayerLocation,
     ) -> ApiResult<LocationVerification> {
         let mut flags = Vec::new();
+        let game = self.game.read().await.clone();
 
         // 1. Check GPS accuracy
-        if location.accuracy > self.config.game.location_accuracy_threshold {
+        if location.accuracy > game.location_accuracy_threshold {
             flags.push(VerificationFlag::LowAccuracy);
         }
 
         // 2. Get last known location
         let last_location = self.get_last_location(player_id).await?;
+        let mut walked = 0.0;
 
         if let Some(last) = last_location {
+            // Speed check, shared with WebSocket location updates
+            let check = self
+                .check_movement(player_id, Some(last), location.lat, location.lng, LocationSource::Http)
+                .await?;
+            if let MovementCheck::SpeedViolation { speed, max, .. } = check {
+                flags.push(VerificationFlag::SpeedViolation { speed, max });
+            }
+
+            // Teleport check (>50km in <5 minutes)
             let distance = haversine_distance(last.0, last.1, location.lat, location.lng);
-            let time_diff = Utc::now() - last.2;
-            let time_seconds = time_diff.num_seconds() as f64;
-
-            if time_seconds > 0.0 {
-                let speed = distance / time_seconds;
-
-                // Speed check
-                if speed > self.config.game.max_speed_mps {
-                    flags.push(VerificationFlag::SpeedViolation {
-                        speed,
-                        max: self.config.game.max_speed_mps,
-                    });
-                }
-
-                // Teleport check (>50km in <5 minutes)
-                if distance > 50_000.0 && time_seconds < 300.0 {
-                    flags.push(VerificationFlag::PossibleTeleport { distance });
-                }
+            let time_seconds = (Utc::now() - last.2).num_seconds() as f64;
+            if time_seconds > 0.0 && distance > 50_000.0 && time_seconds < 300.0 {
+                flags.push(VerificationFlag::PossibleTeleport { distance });
             }
+            walked = distance;
         }
 
-        // 3. Store the location
+        // 3. Trail plausibility, refused only after repeated violations
+        self.check_trail(player_id, location.lat, location.lng, Utc::now()).await?;
+
+        // 4. Store the location
         self.store_location(player_id, location, &flags).await?;
 
         // Determine status
@@ -73,15 +89,214 @@ impl LocationService {
             VerificationStatus::Suspicious
         };
 
+        // Only clean moves count toward walking quests
+        if status == VerificationStatus::Valid && walked >= 1.0 {
+            let event = QuestEvent::DistanceWalked { meters: walked as i32 };
+            self.quest.record_event_or_warn(player_id, event).await;
+        }
+
         Ok(LocationVerification { status, flags })
     }
 
-    /// Get last known location for a player
-    async fn get_last_location(
+    /// Check a move from `previous` to `lat`/`lng` against the speed limit,
+    /// forgiving up to the GPS accuracy threshold of distance. A violation is
+    /// recorded for review; `None` for `previous` is always accepted.
+    pub async fn check_movement(
         &self,
         player_id: Uuid,
-    ) -> ApiResult<Option<(f64, f64, chrono::DateTime<chrono::Utc>)>> {
-        let result = sqlx::query_as::<_, (f64, f64, chrono::DateTime<chrono::Utc>)>(
+        previous: Option<Fix>,
+        lat: f64,
+        lng: f64,
+        source: LocationSource,
+    ) -> ApiResult<MovementCheck> {
+        let Some((from_lat, from_lng, from_at)) = previous else {
+            return Ok(MovementCheck::Accepted);
+        };
+        let (max, allowance) = {
+            let game = self.game.read().await;
+            (game.max_speed_mps, game.location_accuracy_threshold)
+        };
+
+        let elapsed = (Utc::now() - from_at).num_milliseconds() as f64 / 1000.0;
+        let speed = implied_speed((from_lat, from_lng), (lat, lng), elapsed, allowance);
+        if speed <= max {
+            return Ok(MovementCheck::Accepted);
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO player_location_violations
+            (player_id, source, from_lat, from_lng, to_lat, to_lng, distance_m, elapsed_seconds, speed_mps)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+        )
+        .bind(player_id)
+        .bind(source.as_str())
+        .bind(from_lat)
+        .bind(from_lng)
+        .bind(lat)
+        .bind(lng)
+        .bind(haversine_distance(from_lat, from_lng, lat, lng))
+        .bind(elapsed)
+        .bind(speed)
+        .execute(&self.db.pg)
+        .await?;
+
+        tracing::warn!(
+            "Speed violation by {} over {}: {:.0} m/s (max {:.0})",
+            player_id,
+            source.as_str(),
+            speed,
+            max
+        );
+
+        let quarantined = self.is_quarantined(player_id).await?;
+        Ok(MovementCheck::SpeedViolation {
+            speed,
+            max,
+            quarantined,
+        })
+    }
+
+    /// Add a fix to the player's trail and, once the trail is full, test that
+    /// it follows a smooth path. An implausible trail is counted on the player
+    /// and the trail started over; once the count reaches the limit, each
+    /// further one is refused with `SpeedViolation`.
+    pub async fn check_trail(
+        &self,
+        player_id: Uuid,
+        lat: f64,
+        lng: f64,
+        at: DateTime<Utc>,
+    ) -> ApiResult<()> {
+        let (points, max_residual, limit) = {
+            let game = self.game.read().await;
+            (
+                game.location_trail_points,
+                game.location_trail_max_residual_m,
+                game.location_trail_violation_limit,
+            )
+        };
+        if points == 0 {
+            return Ok(());
+        }
+
+        let key = location_trail_key(player_id);
+        let mut conn = self.db.redis.clone();
+        let (entries,): (Vec<String>,) = redis::pipe()
+            .atomic()
+            .cmd("LPUSH")
+            .arg(&key)
+            .arg(format!("{},{},{}", lat, lng, at.timestamp_millis()))
+            .ignore()
+            .cmd("LTRIM")
+            .arg(&key)
+            .arg(0)
+            .arg(points as i64 - 1)
+            .ignore()
+            .cmd("EXPIRE")
+            .arg(&key)
+            .arg(TRAIL_TTL_SECS)
+            .ignore()
+            .cmd("LRANGE")
+            .arg(&key)
+            .arg(0)
+            .arg(-1)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| AppError::Internal(e.into()))?;
+
+        if entries.len() < points {
+            return Ok(());
+        }
+        let mut trail: Vec<(f64, f64, f64)> = entries.iter().rev().filter_map(|e| parse_trail_entry(e)).collect();
+        let Some(&(_, _, start)) = trail.first() else {
+            return Ok(());
+        };
+        for fix in &mut trail {
+            fix.2 -= start;
+        }
+        let Some(residual) = trail_fit_residual(&trail) else {
+            return Ok(());
+        };
+        if residual <= max_residual {
+            return Ok(());
+        }
+
+        redis::cmd("DEL")
+            .arg(&key)
+            .query_async::<_, ()>(&mut conn)
+            .await
+            .map_err(|e| AppError::Internal(e.into()))?;
+        let count = sqlx::query_scalar::<_, i32>(
+            r#"
+            UPDATE players
+            SET suspicious_movement_count = suspicious_movement_count + 1
+            WHERE id = $1
+            RETURNING suspicious_movement_count
+            "#,
+        )
+        .bind(player_id)
+        .fetch_optional(&self.db.pg)
+        .await?
+        .unwrap_or(0);
+
+        tracing::warn!(
+            "Implausible trail for {}: {:.0} m off the fitted path (max {:.0}), {} so far",
+            player_id,
+            residual,
+            max_residual,
+            count
+        );
+
+        if limit > 0 && count as u32 >= limit {
+            return Err(AppError::SpeedViolation);
+        }
+        Ok(())
+    }
+
+    /// Whether the player's location is quarantined: they reached the
+    /// violation limit within the window, and the quarantine period since the
+    /// last violation that kept them there hasn't passed
+    pub async fn is_quarantined(&self, player_id: Uuid) -> ApiResult<bool> {
+        let (limit, window, quarantine) = {
+            let game = self.game.read().await;
+            (
+                game.location_violation_limit,
+                game.location_violation_window_seconds,
+                game.location_quarantine_seconds,
+            )
+        };
+        if limit == 0 {
+            return Ok(false);
+        }
+
+        let quarantined = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM player_location_violations v
+                WHERE v.player_id = $1
+                  AND v.created_at > NOW() - make_interval(secs => $3)
+                  AND (SELECT COUNT(*) FROM player_location_violations w
+                       WHERE w.player_id = $1
+                         AND w.created_at <= v.created_at
+                         AND w.created_at > v.created_at - make_interval(secs => $2)) >= $4
+            )
+            "#,
+        )
+        .bind(player_id)
+        .bind(window as f64)
+        .bind(quarantine as f64)
+        .bind(limit as i64)
+        .fetch_one(&self.db.pg)
+        .await?;
+
+        Ok(quarantined)
+    }
+
+    /// Get last known location for a player
+    pub async fn get_last_location(&self, player_id: Uuid) -> ApiResult<Option<Fix>> {
+        let result = sqlx::query_as::<_, Fix>(
             r#"
             SELECT location_lat, location_lng, timestamp
             FROM player_locations
@@ -155,6 +370,8 @@ impl LocationService {
         player_id: Uuid,
         report: LocationReport,
     ) -> ApiResult<LocationVerification> {
+        report.check_timestamp()?;
+
         let location = PlayerLocation {
             lat: report.lat,
             lng: report.lng,
@@ -180,7 +397,7 @@ impl LocationService {
         .await?;
 
         if let Some(Some(last_capture)) = result.map(Some) {
-            let cooldown = Duration::seconds(self.config.game.capture_cooldown_seconds as i64);
+            let cooldown = Duration::seconds(self.game.read().await.capture_cooldown_seconds as i64);
             if Utc::now() - last_capture < cooldown {
                 return Ok(true); // On cooldown
             }
@@ -190,6 +407,15 @@ impl LocationService {
     }
 }
 
+/// `(lat, lng, seconds)` from a trail entry, `None` if malformed
+fn parse_trail_entry(entry: &str) -> Option<(f64, f64, f64)> {
+    let mut parts = entry.split(',');
+    let lat = parts.next()?.parse().ok()?;
+    let lng = parts.next()?.parse().ok()?;
+    let millis: i64 = parts.next()?.parse().ok()?;
+    Some((lat, lng, millis as f64 / 1000.0))
+}
+
 /// Calculate distance between two points using Haversine formula
 pub fn haversine_distance(lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> f64 {
     const EARTH_RADIUS: f64 = 6_371_000.0; // meters
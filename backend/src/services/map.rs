@@ -6,6 +6,14 @@ use crate::db::Database;
 use crate::error::ApiResult;
 use crate::models::{GeoPoint, POI, POIResponse, TitanSpawn, TitanSpawnResponse};
 
+/// A Titan with its POI's name
+#[derive(sqlx::FromRow)]
+struct RegionTitanRow {
+    #[sqlx(flatten)]
+    titan: TitanSpawn,
+    poi_name: Option<String>,
+}
+
 /// Map service for spatial queries
 #[derive(Clone)]
 pub struct MapService {
@@ -113,6 +121,51 @@ impl MapService {
         Ok(responses)
     }
 
+    /// Active Titans in a geohash region, rarest first, with their POI names
+    pub async fn get_region_titans(
+        &self,
+        geohash_prefix: &str,
+        limit: i64,
+    ) -> ApiResult<Vec<TitanSpawnResponse>> {
+        let rows = sqlx::query_as::<_, RegionTitanRow>(
+            r#"
+            SELECT t.id, t.poi_id, t.location_lat, t.location_lng, t.geohash,
+                   t.element, t.threat_class, t.species_id, t.genes,
+                   t.spawned_at, t.expires_at, t.captured_by, t.captured_at,
+                   t.capture_count, t.max_captures, p.name AS poi_name
+            FROM titan_spawns t
+            LEFT JOIN pois p ON p.id = t.poi_id
+            WHERE t.geohash LIKE $1 || '%'
+              AND t.expires_at > NOW()
+              AND (t.captured_by IS NULL OR t.capture_count < t.max_captures)
+            ORDER BY t.threat_class DESC, t.spawned_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(geohash_prefix)
+        .bind(limit)
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|RegionTitanRow { titan: t, poi_name }| TitanSpawnResponse {
+                id: t.id,
+                location: GeoPoint {
+                    lat: t.location_lat,
+                    lng: t.location_lng,
+                },
+                element: t.element,
+                threat_class: t.threat_class,
+                species_id: t.species_id,
+                distance: None,
+                expires_at: t.expires_at,
+                poi_name,
+                is_available: true,
+            })
+            .collect())
+    }
+
     /// Get POIs in a bounding box
     pub async fn get_pois_in_bounds(
         &self,
@@ -1,93 +1,354 @@
 //! Marketplace service - NFT trading functionality
 
-use chrono::{Duration, Utc};
-use sqlx::Row;
+use std::collections::HashSet;
+
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{Postgres, QueryBuilder};
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
+use crate::config::{AppConfig, SharedGameConfig};
 use crate::db::Database;
 use crate::error::{ApiResult, AppError};
+use crate::services::guild::GuildService;
+use crate::services::{NotificationService, RewardService};
+use crate::services::solana::{OnchainListing, SimpleTransactionResult, SolanaService};
 use crate::models::{
-    AuctionBid, BidResponse, CreateListingRequest, Element, ListingResponse, ListingStatus,
-    ListingType, MakeOfferRequest, MarketplaceListing, MarketplaceSearchQuery,
-    MarketplaceStatsResponse, MarketplaceTransaction, OfferResponse, PriceChartResponse,
-    PriceHistoryEntry, PriceOffer, SearchResultsResponse, TitanListingInfo,
-    TransactionHistoryEntry,
+    AuctionBid, BidResponse, BundleTitanRow, CompletePurchaseRequest, CreateListingRequest, Element, EscrowReconcileSummary,
+    FloorPriceEntry, ListingResponse, ListingRow, ListingStatus, ListingType, MakeOfferRequest,
+    MarketplaceActivity, MarketplaceActivityPage, MarketplaceActivityQuery, MarketplaceActivityType,
+    MarketplaceListing, MarketplaceSearchQuery, MarketplaceStatsResponse,
+    MarketplaceTransaction, NotificationType, OfferResponse, PlaceBidRequest, PlaceBidResponse, PriceChartResponse,
+    PriceHistoryEntry, PriceOffer, RewardReason, SaleOutcome, SearchResultsResponse, SuggestedPriceResponse,
+    TransactionHistoryEntry, TransactionType,
 };
 
 /// Platform fee in basis points (250 = 2.5%)
 const PLATFORM_FEE_BPS: i64 = 250;
 
+/// How long floor prices and price suggestions are cached in Redis
+const PRICE_CACHE_SECS: u64 = 300;
+
+/// Sales this recent are comparable for a price suggestion
+const COMPARABLE_SALE_DAYS: i32 = 30;
+
+/// Most Titans a bundle listing may hold
+pub const MAX_BUNDLE_TITANS: usize = 5;
+
+/// Most activity entries one feed page returns
+const MAX_ACTIVITY_PAGE: i64 = 100;
+
+/// Activity waiting to be pushed to feed subscribers
+const LIVE_CHANNEL_CAPACITY: usize = 256;
+
+/// Redis cache of the floor price matrix
+const FLOOR_PRICES_KEY: &str = "marketplace:floor_prices";
+
+/// Redis cache of the comparable sales for an element and threat class:
+/// `marketplace:suggested_price:{element}:{threat_class}`
+fn suggested_price_key(element: Element, threat_class: i16) -> String {
+    format!("marketplace:suggested_price:{:?}:{}", element, threat_class).to_lowercase()
+}
+
 /// Marketplace service
 #[derive(Clone)]
 pub struct MarketplaceService {
+    config: AppConfig,
+    game: SharedGameConfig,
     db: Database,
+    solana: Option<SolanaService>,
+    notification: NotificationService,
+    reward: RewardService,
+    /// Activity logged on this instance, for the WebSocket feed
+    live: broadcast::Sender<MarketplaceActivity>,
+}
+
+/// Platform fee and seller's share of a sale price
+pub fn sale_split(price: i64) -> (i64, i64) {
+    let fee = (price * PLATFORM_FEE_BPS) / 10000;
+    (fee, price - fee)
+}
+
+/// What a listing sells for: the winning bid or accepted offer of a sale
+/// awaiting payment, otherwise its asking price
+pub fn sale_price(listing: &MarketplaceListing) -> i64 {
+    listing.final_price.unwrap_or(listing.price)
+}
+
+/// Creator's royalty on a resale at `price`
+pub fn royalty_amount(price: i64, royalty_bps: u32) -> i64 {
+    (price.max(0) * royalty_bps as i64) / 10000
+}
+
+/// A creator's royalty on one Titan of a sale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SaleRoyalty {
+    pub titan_id: Uuid,
+    pub creator_id: Uuid,
+    pub amount: i64,
+}
+
+/// Royalties owed on a sale at `price` of `titans` (Titan, creator) by
+/// `seller_id`: the price is split evenly across the Titans, the first ones
+/// taking any remainder, and each pays its creator unless the seller is the
+/// creator
+pub fn sale_royalties(price: i64, seller_id: Uuid, titans: &[(Uuid, Uuid)], royalty_bps: u32) -> Vec<SaleRoyalty> {
+    let count = titans.len() as i64;
+    if count == 0 {
+        return Vec::new();
+    }
+    let (share, remainder) = (price / count, price % count);
+
+    titans
+        .iter()
+        .enumerate()
+        .map(|(i, &(titan_id, creator_id))| SaleRoyalty {
+            titan_id,
+            creator_id,
+            amount: royalty_amount(share + i64::from((i as i64) < remainder), royalty_bps),
+        })
+        .filter(|royalty| royalty.creator_id != seller_id && royalty.amount > 0)
+        .collect()
+}
+
+/// Total of a sale's royalties
+fn total_royalty(royalties: &[SaleRoyalty]) -> i64 {
+    royalties.iter().map(|royalty| royalty.amount).sum()
+}
+
+/// Slice of a platform fee credited to the seller's guild treasury
+pub fn guild_fee_share(fee: i64, share_bps: u32) -> i64 {
+    (fee.max(0) * share_bps as i64) / 10000
+}
+
+/// Median of sale prices, rounded down between the two middle ones
+pub fn median_price(prices: &mut [i64]) -> Option<i64> {
+    if prices.is_empty() {
+        return None;
+    }
+    prices.sort_unstable();
+    let mid = prices.len() / 2;
+    Some(if prices.len() % 2 == 1 {
+        prices[mid]
+    } else {
+        (prices[mid - 1] + prices[mid]) / 2
+    })
+}
+
+/// Titans a new listing holds: `titan_id` alone, or 2 to
+/// `MAX_BUNDLE_TITANS` distinct `titan_ids` for a bundle
+pub fn listing_titans(req: &CreateListingRequest) -> ApiResult<Vec<Uuid>> {
+    if req.listing_type != ListingType::Bundle {
+        return match (req.titan_id, req.titan_ids.is_empty()) {
+            (Some(titan_id), true) => Ok(vec![titan_id]),
+            _ => Err(AppError::BadRequest("Send titan_id; titan_ids is for bundles".into())),
+        };
+    }
+
+    if req.titan_id.is_some() {
+        return Err(AppError::BadRequest("A bundle lists titan_ids, not titan_id".into()));
+    }
+    if !(2..=MAX_BUNDLE_TITANS).contains(&req.titan_ids.len()) {
+        return Err(AppError::BadRequest(format!(
+            "A bundle holds 2 to {} Titans",
+            MAX_BUNDLE_TITANS
+        )));
+    }
+    if req.titan_ids.iter().collect::<HashSet<_>>().len() != req.titan_ids.len() {
+        return Err(AppError::BadRequest("A Titan can only be in a bundle once".into()));
+    }
+    Ok(req.titan_ids.clone())
+}
+
+/// Reject a purchase the buyer's $BREACH balance can't cover
+pub fn check_funds(balance: u64, price: i64) -> ApiResult<()> {
+    let required = price.max(0) as u64;
+    if balance < required {
+        return Err(AppError::InsufficientFunds { required, balance });
+    }
+    Ok(())
+}
+
+/// End time for an auction that receives a bid at `now`: a bid within
+/// `window` of the end pushes it out so `extension` remains, but never past
+/// `original_expires_at + max_extension`. `None` when the bid is outside the
+/// window or the end doesn't move.
+pub fn extended_auction_expiry(
+    expires_at: DateTime<Utc>,
+    original_expires_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+    window: Duration,
+    extension: Duration,
+    max_extension: Duration,
+) -> Option<DateTime<Utc>> {
+    if expires_at - now > window {
+        return None;
+    }
+    let extended = (now + extension).min(original_expires_at + max_extension);
+    (extended > expires_at).then_some(extended)
+}
+
+/// Smallest raise over `current` a proxy bid makes: `increment_bps` of it,
+/// at least 1
+pub fn bid_increment(current: i64, increment_bps: u32) -> i64 {
+    (current.max(0) * increment_bps as i64 / 10000).max(1)
+}
+
+/// Amounts a bid settles at against the leader's proxy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProxyOutcome {
+    /// The challenger's bid
+    pub challenger_bid: i64,
+    /// Bid the leader's proxy raises to, if it moves
+    pub leader_bid: Option<i64>,
+    /// The challenger leads afterwards
+    pub challenger_leads: bool,
+}
+
+/// Settle a bid opening at `opening` and going up to `challenger_max` against
+/// the `highest` bid and the leader's proxy maximum. The leader keeps ties;
+/// whoever leads pays one increment over the other's maximum, capped at
+/// their own.
+pub fn resolve_proxy_bid(
+    opening: i64,
+    challenger_max: i64,
+    highest: Option<i64>,
+    leader_max: Option<i64>,
+    increment_bps: u32,
+) -> ProxyOutcome {
+    match leader_max {
+        Some(leader_max) if challenger_max <= leader_max => ProxyOutcome {
+            challenger_bid: challenger_max,
+            leader_bid: Some(
+                leader_max.min(challenger_max + bid_increment(challenger_max, increment_bps)),
+            ),
+            challenger_leads: false,
+        },
+        Some(leader_max) => ProxyOutcome {
+            challenger_bid: opening
+                .max(challenger_max.min(leader_max + bid_increment(leader_max, increment_bps))),
+            leader_bid: (highest < Some(leader_max)).then_some(leader_max),
+            challenger_leads: true,
+        },
+        None => ProxyOutcome {
+            challenger_bid: opening,
+            leader_bid: None,
+            challenger_leads: true,
+        },
+    }
 }
 
 impl MarketplaceService {
-    pub fn new(db: Database) -> Self {
-        Self { db }
+    pub fn new(
+        config: AppConfig,
+        game: SharedGameConfig,
+        db: Database,
+        solana: Option<SolanaService>,
+        notification: NotificationService,
+        reward: RewardService,
+    ) -> Self {
+        let (live, _) = broadcast::channel(LIVE_CHANNEL_CAPACITY);
+        Self { config, game, db, solana, notification, reward, live }
+    }
+
+    /// Activity as it is logged
+    pub fn subscribe_activity(&self) -> broadcast::Receiver<MarketplaceActivity> {
+        self.live.subscribe()
     }
 
     // ============================================
     // Listings
     // ============================================
 
-    /// Create a new listing
+    /// Create a new listing. A bundle holds every one of its Titans in
+    /// escrow, and `titan_id` is the first of them.
     pub async fn create_listing(
         &self,
         seller_id: Uuid,
         req: CreateListingRequest,
     ) -> ApiResult<MarketplaceListing> {
-        // Verify Titan ownership
-        let titan = sqlx::query_scalar::<_, Uuid>(
-            "SELECT id FROM player_titans WHERE id = $1 AND player_id = $2"
-        )
-        .bind(req.titan_id)
-        .bind(seller_id)
-        .fetch_optional(&self.db.pg)
-        .await?;
+        let titan_ids = listing_titans(&req)?;
 
-        if titan.is_none() {
-            return Err(AppError::NotFound("Titan not found or not owned by you".into()));
+        // Validate auction parameters
+        if req.listing_type == ListingType::Auction && req.min_price.is_none() {
+            return Err(AppError::BadRequest("Auction requires min_price".into()));
         }
 
-        // Check if already listed
-        let existing = sqlx::query_scalar::<_, i64>(
-            "SELECT COUNT(*) FROM marketplace_listings WHERE titan_id = $1 AND status = 'active'"
+        let mut tx = self.db.pg.begin().await?;
+
+        // Verify Titan ownership, locking the Titans against a concurrent listing
+        let titans = sqlx::query!(
+            r#"
+            SELECT id, escrowed_listing_id FROM player_titans
+            WHERE id = ANY($1) AND player_id = $2
+            FOR UPDATE
+            "#,
+            &titan_ids,
+            seller_id
         )
-        .bind(req.titan_id)
-        .fetch_one(&self.db.pg)
+        .fetch_all(&mut *tx)
         .await?;
 
-        if existing > 0 {
-            return Err(AppError::BadRequest("Titan is already listed".into()));
+        if titans.len() != titan_ids.len() {
+            return Err(AppError::NotFound("Titan not found or not owned by you".into()));
         }
 
-        // Validate auction parameters
-        if req.listing_type == ListingType::Auction && req.min_price.is_none() {
-            return Err(AppError::BadRequest("Auction requires min_price".into()));
+        // Check if already listed, on its own or in a bundle
+        if titans.iter().any(|titan| titan.escrowed_listing_id.is_some()) {
+            return Err(AppError::BadRequest("Titan is already listed".into()));
         }
 
         // Create listing
         let expires_at = Utc::now() + Duration::hours(req.duration_hours);
         
-        let listing = sqlx::query_as::<_, MarketplaceListing>(
+        let listing = sqlx::query_as!(
+            MarketplaceListing,
             r#"
             INSERT INTO marketplace_listings 
             (seller_id, titan_id, listing_type, price, min_price, expires_at)
             VALUES ($1, $2, $3, $4, $5, $6)
-            RETURNING *
-            "#
+            RETURNING id, seller_id, titan_id, listing_type as "listing_type: ListingType", price,
+                      min_price, status as "status: ListingStatus", created_at, expires_at,
+                      sold_at, cancelled_at, buyer_id, final_price, views, favorites,
+                      escrow_address, listing_tx_signature
+            "#,
+            seller_id,
+            titan_ids[0],
+            req.listing_type as ListingType,
+            req.price,
+            req.min_price,
+            expires_at
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if req.listing_type == ListingType::Bundle {
+            sqlx::query!(
+                r#"
+                INSERT INTO listing_bundle_titans (listing_id, titan_id, position)
+                SELECT $1, titan_id, (position - 1)::SMALLINT
+                FROM UNNEST($2::UUID[]) WITH ORDINALITY AS t(titan_id, position)
+                "#,
+                listing.id,
+                &titan_ids
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let activity = Self::log_activity(
+            &mut tx,
+            MarketplaceActivityType::ListingCreated,
+            listing.id,
+            listing.titan_id,
+            listing.price,
+            seller_id,
         )
-        .bind(seller_id)
-        .bind(req.titan_id)
-        .bind(req.listing_type)
-        .bind(req.price)
-        .bind(req.min_price)
-        .bind(expires_at)
-        .fetch_one(&self.db.pg)
         .await?;
 
+        tx.commit().await?;
+        self.publish_activity(activity);
+
         Ok(listing)
     }
 
@@ -98,66 +359,47 @@ impl MarketplaceService {
         viewer_id: Option<Uuid>,
     ) -> ApiResult<ListingResponse> {
         // Increment view count
-        sqlx::query("UPDATE marketplace_listings SET views = views + 1 WHERE id = $1")
-            .bind(listing_id)
-            .execute(&self.db.pg)
-            .await?;
+        sqlx::query!(
+            "UPDATE marketplace_listings SET views = views + 1 WHERE id = $1",
+            listing_id
+        )
+        .execute(&self.db.pg)
+        .await?;
 
         // Get listing details
-        let row = sqlx::query(
+        let row = sqlx::query_as!(
+            ListingRow,
             r#"
             SELECT 
-                l.id, l.seller_id, l.titan_id, l.listing_type, l.price, l.min_price,
-                l.status, l.expires_at, l.views, l.favorites, l.created_at,
+                l.id, l.seller_id, l.titan_id,
+                l.listing_type as "listing_type: ListingType", l.price, l.min_price,
+                l.status as "status: ListingStatus", l.expires_at, l.views, l.favorites, l.created_at,
                 p.username as seller_username,
-                pt.element, pt.threat_class, pt.species_id, pt.level, pt.nickname, pt.genes,
-                COALESCE((SELECT MAX(amount) FROM auction_bids WHERE listing_id = l.id), 0) as current_bid,
-                (SELECT COUNT(*) FROM auction_bids WHERE listing_id = l.id)::INT as bid_count,
+                pt.element as "element: Element", pt.threat_class, pt.species_id, pt.level,
+                pt.nickname, pt.genes,
+                COALESCE((SELECT MAX(amount) FROM auction_bids WHERE listing_id = l.id), 0) as "current_bid!",
+                (SELECT COUNT(*) FROM auction_bids WHERE listing_id = l.id)::INT as "bid_count!",
                 CASE WHEN $2::UUID IS NOT NULL THEN
                     EXISTS(SELECT 1 FROM listing_favorites WHERE listing_id = l.id AND player_id = $2)
-                ELSE FALSE END as is_favorited
+                ELSE FALSE END as "is_favorited!"
             FROM marketplace_listings l
             JOIN players p ON l.seller_id = p.id
             JOIN player_titans pt ON l.titan_id = pt.id
             WHERE l.id = $1
-            "#
+            "#,
+            listing_id,
+            viewer_id
         )
-        .bind(listing_id)
-        .bind(viewer_id)
         .fetch_optional(&self.db.pg)
         .await?;
 
         let row = row.ok_or_else(|| AppError::NotFound("Listing not found".into()))?;
 
-        Ok(ListingResponse {
-            id: row.get("id"),
-            seller_id: row.get("seller_id"),
-            seller_username: row.get("seller_username"),
-            titan_id: row.get("titan_id"),
-            titan: TitanListingInfo {
-                id: row.get("titan_id"),
-                element: row.get("element"),
-                threat_class: row.get("threat_class"),
-                species_id: row.get("species_id"),
-                level: row.get("level"),
-                nickname: row.get("nickname"),
-                genes: row.get("genes"),
-            },
-            listing_type: row.get("listing_type"),
-            price: row.get("price"),
-            min_price: row.get("min_price"),
-            current_bid: {
-                let bid: i64 = row.get("current_bid");
-                if bid > 0 { Some(bid) } else { None }
-            },
-            bid_count: row.get("bid_count"),
-            status: row.get("status"),
-            expires_at: row.get("expires_at"),
-            views: row.get("views"),
-            favorites: row.get("favorites"),
-            is_favorited: row.get("is_favorited"),
-            created_at: row.get("created_at"),
-        })
+        let mut listings = [ListingResponse::from(row)];
+        self.attach_bundle_titans(&mut listings).await?;
+        let [listing] = listings;
+
+        Ok(listing)
     }
 
     /// Search listings
@@ -166,70 +408,43 @@ impl MarketplaceService {
         query: MarketplaceSearchQuery,
         viewer_id: Option<Uuid>,
     ) -> ApiResult<SearchResultsResponse> {
-        // Build dynamic query with proper parameter tracking
-        let mut param_idx = 2; // $1 is viewer_id
-        let mut conditions = Vec::new();
-        
-        // Track which parameters to bind
-        let mut bind_element = false;
-        let mut bind_min_threat = false;
-        let mut bind_max_threat = false;
-        let mut bind_min_price = false;
-        let mut bind_max_price = false;
-        let mut bind_min_level = false;
-        let mut bind_listing_type = false;
-
-        if query.element.is_some() {
-            conditions.push(format!("pt.element = ${}", param_idx));
-            param_idx += 1;
-            bind_element = true;
-        }
-        if query.min_threat_class.is_some() {
-            conditions.push(format!("pt.threat_class >= ${}", param_idx));
-            param_idx += 1;
-            bind_min_threat = true;
-        }
-        if query.max_threat_class.is_some() {
-            conditions.push(format!("pt.threat_class <= ${}", param_idx));
-            param_idx += 1;
-            bind_max_threat = true;
-        }
-        if query.min_price.is_some() {
-            conditions.push(format!("l.price >= ${}", param_idx));
-            param_idx += 1;
-            bind_min_price = true;
-        }
-        if query.max_price.is_some() {
-            conditions.push(format!("l.price <= ${}", param_idx));
-            param_idx += 1;
-            bind_max_price = true;
-        }
-        if query.min_level.is_some() {
-            conditions.push(format!("pt.level >= ${}", param_idx));
-            param_idx += 1;
-            bind_min_level = true;
-        }
-        if query.listing_type.is_some() {
-            conditions.push(format!("l.listing_type = ${}", param_idx));
-            bind_listing_type = true;
-        }
+        let rows: Vec<ListingRow> = Self::search_query(&query, viewer_id)
+            .build_query_as()
+            .fetch_all(&self.db.pg)
+            .await?;
 
-        // Build WHERE clause
-        let where_clause = if conditions.is_empty() {
-            String::new()
-        } else {
-            format!(" AND {}", conditions.join(" AND "))
-        };
+        let has_more = rows.len() as i64 > query.limit;
+        let mut listings: Vec<ListingResponse> = rows
+            .into_iter()
+            .take(query.limit as usize)
+            .map(ListingResponse::from)
+            .collect();
+        self.attach_bundle_titans(&mut listings).await?;
 
-        // Sorting
-        let order = match query.sort_by.as_deref() {
-            Some("price_asc") => "l.price ASC",
-            Some("price_desc") => "l.price DESC",
-            Some("ending_soon") => "l.expires_at ASC",
-            _ => "l.created_at DESC",
-        };
+        // Get total count
+        let total_count = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM marketplace_listings WHERE status = 'active'"#
+        )
+        .fetch_one(&self.db.pg)
+        .await?;
+
+        Ok(SearchResultsResponse {
+            listings,
+            total_count,
+            has_more,
+        })
+    }
 
-        let sql = format!(
+    /// Build the listing search query.
+    ///
+    /// Filters are optional, so this is the one listing query that can't be
+    /// checked at compile time; `tests/schema_tests.rs` runs it against
+    /// the migrated schema instead.
+    pub fn search_query(
+        query: &MarketplaceSearchQuery,
+        viewer_id: Option<Uuid>,
+    ) -> QueryBuilder<'static, Postgres> {
+        let mut qb = QueryBuilder::new(
             r#"
             SELECT 
                 l.id, l.seller_id, l.titan_id, l.listing_type, l.price, l.min_price,
@@ -238,111 +453,146 @@ impl MarketplaceService {
                 pt.element, pt.threat_class, pt.species_id, pt.level, pt.nickname, pt.genes,
                 COALESCE((SELECT MAX(amount) FROM auction_bids WHERE listing_id = l.id), 0) as current_bid,
                 (SELECT COUNT(*) FROM auction_bids WHERE listing_id = l.id)::INT as bid_count,
-                CASE WHEN $1::UUID IS NOT NULL THEN
-                    EXISTS(SELECT 1 FROM listing_favorites WHERE listing_id = l.id AND player_id = $1)
-                ELSE FALSE END as is_favorited
+                EXISTS(SELECT 1 FROM listing_favorites WHERE listing_id = l.id AND player_id = "#,
+        );
+        qb.push_bind(viewer_id);
+        qb.push(
+            r#") as is_favorited
             FROM marketplace_listings l
             JOIN players p ON l.seller_id = p.id
             JOIN player_titans pt ON l.titan_id = pt.id
-            WHERE l.status = 'active'{}
-            ORDER BY {} LIMIT {} OFFSET {}
-            "#,
-            where_clause, order, query.limit + 1, query.offset
+            WHERE l.status = 'active'"#,
         );
 
-        // Build query with only the necessary bindings
-        let mut db_query = sqlx::query(&sql).bind(viewer_id);
-        
-        if bind_element {
-            db_query = db_query.bind(query.element);
+        if let Some(element) = query.element {
+            qb.push(" AND pt.element = ").push_bind(element);
         }
-        if bind_min_threat {
-            db_query = db_query.bind(query.min_threat_class);
+        if let Some(min_threat) = query.min_threat_class {
+            qb.push(" AND pt.threat_class >= ").push_bind(min_threat);
         }
-        if bind_max_threat {
-            db_query = db_query.bind(query.max_threat_class);
+        if let Some(max_threat) = query.max_threat_class {
+            qb.push(" AND pt.threat_class <= ").push_bind(max_threat);
         }
-        if bind_min_price {
-            db_query = db_query.bind(query.min_price);
+        if let Some(min_price) = query.min_price {
+            qb.push(" AND l.price >= ").push_bind(min_price);
         }
-        if bind_max_price {
-            db_query = db_query.bind(query.max_price);
+        if let Some(max_price) = query.max_price {
+            qb.push(" AND l.price <= ").push_bind(max_price);
         }
-        if bind_min_level {
-            db_query = db_query.bind(query.min_level);
+        if let Some(min_level) = query.min_level {
+            qb.push(" AND pt.level >= ").push_bind(min_level);
         }
-        if bind_listing_type {
-            db_query = db_query.bind(query.listing_type);
+        if let Some(listing_type) = query.listing_type {
+            qb.push(" AND l.listing_type = ").push_bind(listing_type);
         }
 
-        let rows = db_query.fetch_all(&self.db.pg).await?;
+        // Bundle composition
+        if !query.bundle_elements.is_empty() || query.min_threat_sum.is_some() || query.max_threat_sum.is_some() {
+            qb.push(" AND l.listing_type = 'bundle'");
+        }
+        for element in &query.bundle_elements {
+            qb.push(
+                r#" AND EXISTS(SELECT 1 FROM listing_bundle_titans b
+                    JOIN player_titans bt ON b.titan_id = bt.id
+                    WHERE b.listing_id = l.id AND bt.element = "#,
+            )
+            .push_bind(*element)
+            .push(")");
+        }
+        let threat_sum = r#" AND (SELECT SUM(bt.threat_class) FROM listing_bundle_titans b
+                    JOIN player_titans bt ON b.titan_id = bt.id
+                    WHERE b.listing_id = l.id)"#;
+        if let Some(min_sum) = query.min_threat_sum {
+            qb.push(threat_sum).push(" >= ").push_bind(min_sum);
+        }
+        if let Some(max_sum) = query.max_threat_sum {
+            qb.push(threat_sum).push(" <= ").push_bind(max_sum);
+        }
 
-        let has_more = rows.len() as i64 > query.limit;
-        let listings: Vec<ListingResponse> = rows
-            .into_iter()
-            .take(query.limit as usize)
-            .map(|row| ListingResponse {
-                id: row.get("id"),
-                seller_id: row.get("seller_id"),
-                seller_username: row.get("seller_username"),
-                titan_id: row.get("titan_id"),
-                titan: TitanListingInfo {
-                    id: row.get("titan_id"),
-                    element: row.get("element"),
-                    threat_class: row.get("threat_class"),
-                    species_id: row.get("species_id"),
-                    level: row.get("level"),
-                    nickname: row.get("nickname"),
-                    genes: row.get("genes"),
-                },
-                listing_type: row.get("listing_type"),
-                price: row.get("price"),
-                min_price: row.get("min_price"),
-                current_bid: {
-                    let bid: i64 = row.get("current_bid");
-                    if bid > 0 { Some(bid) } else { None }
-                },
-                bid_count: row.get("bid_count"),
-                status: row.get("status"),
-                expires_at: row.get("expires_at"),
-                views: row.get("views"),
-                favorites: row.get("favorites"),
-                is_favorited: row.get("is_favorited"),
-                created_at: row.get("created_at"),
-            })
+        // Sorting
+        let order = match query.sort_by.as_deref() {
+            Some("price_asc") => "l.price ASC",
+            Some("price_desc") => "l.price DESC",
+            Some("ending_soon") => "l.expires_at ASC",
+            _ => "l.created_at DESC",
+        };
+
+        qb.push(" ORDER BY ").push(order);
+        qb.push(" LIMIT ").push_bind(query.limit + 1);
+        qb.push(" OFFSET ").push_bind(query.offset);
+
+        qb
+    }
+
+    /// Fill in `bundle_titans` for the bundles among `listings`
+    async fn attach_bundle_titans(&self, listings: &mut [ListingResponse]) -> ApiResult<()> {
+        let bundle_ids: Vec<Uuid> = listings
+            .iter()
+            .filter(|l| l.listing_type == ListingType::Bundle)
+            .map(|l| l.id)
             .collect();
+        if bundle_ids.is_empty() {
+            return Ok(());
+        }
 
-        // Get total count
-        let total_count = sqlx::query_scalar::<_, i64>(
-            "SELECT COUNT(*) FROM marketplace_listings WHERE status = 'active'"
+        let rows = sqlx::query_as!(
+            BundleTitanRow,
+            r#"
+            SELECT b.listing_id, pt.id, pt.element as "element: Element", pt.threat_class, pt.species_id,
+                   pt.level, pt.nickname, pt.genes
+            FROM listing_bundle_titans b
+            JOIN player_titans pt ON b.titan_id = pt.id
+            WHERE b.listing_id = ANY($1)
+            ORDER BY b.listing_id, b.position
+            "#,
+            &bundle_ids
         )
-        .fetch_one(&self.db.pg)
+        .fetch_all(&self.db.pg)
         .await?;
 
-        Ok(SearchResultsResponse {
-            listings,
-            total_count,
-            has_more,
-        })
+        for row in rows {
+            if let Some(listing) = listings.iter_mut().find(|l| l.id == row.listing_id) {
+                listing.bundle_titans.get_or_insert_with(Vec::new).push(row.into());
+            }
+        }
+
+        Ok(())
     }
 
-    /// Cancel listing
+    /// Cancel a listing, or a sale whose buyer didn't pay in time
     pub async fn cancel_listing(&self, seller_id: Uuid, listing_id: Uuid) -> ApiResult<()> {
-        let result = sqlx::query(
+        let mut tx = self.db.pg.begin().await?;
+
+        let cancelled = sqlx::query!(
             r#"
             UPDATE marketplace_listings
             SET status = 'cancelled', cancelled_at = NOW()
-            WHERE id = $1 AND seller_id = $2 AND status = 'active'
-            "#
+            WHERE id = $1 AND seller_id = $2 AND escrow_address IS NULL
+              AND (status = 'active' OR (status = 'awaiting_payment' AND expires_at < NOW()))
+            RETURNING titan_id, price
+            "#,
+            listing_id,
+            seller_id
         )
-        .bind(listing_id)
-        .bind(seller_id)
-        .execute(&self.db.pg)
+        .fetch_optional(&mut *tx)
         .await?;
 
-        if result.rows_affected() == 0 {
-            return Err(AppError::NotFound("Listing not found or already sold".into()));
-        }
+        let Some(cancelled) = cancelled else {
+            return Err(AppError::NotFound("Listing not found, already sold, or held in on-chain escrow".into()));
+        };
+
+        let activity = Self::log_activity(
+            &mut tx,
+            MarketplaceActivityType::ListingCancelled,
+            listing_id,
+            cancelled.titan_id,
+            cancelled.price,
+            seller_id,
+        )
+        .await?;
+
+        tx.commit().await?;
+        self.publish_activity(activity);
 
         Ok(())
     }
@@ -351,115 +601,316 @@ impl MarketplaceService {
     // Purchases
     // ============================================
 
-    /// Buy a fixed-price listing
-    pub async fn buy_listing(&self, buyer_id: Uuid, listing_id: Uuid) -> ApiResult<MarketplaceTransaction> {
-        let mut tx = self.db.pg.begin().await?;
+    /// Build the $BREACH payment for a fixed-price listing, or for a sale
+    /// awaiting the buyer's payment, for the buyer to sign and send back with
+    /// `buy_listing`
+    pub async fn build_purchase_payment(
+        &self,
+        buyer_id: Uuid,
+        listing_id: Uuid,
+    ) -> ApiResult<(MarketplaceListing, SimpleTransactionResult)> {
+        let solana = self.settlement()?
+            .ok_or_else(|| AppError::BadRequest("On-chain settlement is disabled".into()))?;
+
+        let listing = self.payable_listing(buyer_id, listing_id).await?;
+        let price = sale_price(&listing);
+
+        let buyer_wallet = self.wallet_address(buyer_id).await?;
+        let seller_wallet = self.wallet_address(listing.seller_id).await?;
+        check_funds(solana.get_breach_balance(&buyer_wallet).await?, price)?;
+
+        // The royalty is collected with the fee and forwarded to the creator
+        let royalty = total_royalty(&self.sale_royalties(&listing, price).await?);
+        let (fee, seller_receives) = sale_split(price);
+        let payment = solana
+            .transfer_breach_between(
+                &buyer_wallet,
+                &seller_wallet,
+                (seller_receives - royalty) as u64,
+                (fee + royalty) as u64,
+            )
+            .await?;
 
-        // Get and lock listing
-        let listing = sqlx::query_as::<_, MarketplaceListing>(
-            r#"
-            SELECT * FROM marketplace_listings
-            WHERE id = $1 AND status = 'active' AND listing_type = 'fixed_price'
-            FOR UPDATE
-            "#
+        Ok((listing, payment))
+    }
+
+    /// Buy a fixed-price or bundle listing, or pay for an auction won or an
+    /// offer accepted.
+    ///
+    /// With settlement enabled `payment` is the buyer-signed transaction from
+    /// `build_purchase_payment`. The listing is claimed (`settling`) before
+    /// the payment is sent and the sale is committed once it lands, with its
+    /// signature on the transaction record; a failed payment releases the
+    /// claim. A resale's royalties are then sent to the Titans' creators, or
+    /// queued for retry.
+    pub async fn buy_listing(
+        &self,
+        buyer_id: Uuid,
+        listing_id: Uuid,
+        payment: Option<&CompletePurchaseRequest>,
+    ) -> ApiResult<MarketplaceTransaction> {
+        let listing = self.payable_listing(buyer_id, listing_id).await?;
+        let price = sale_price(&listing);
+        let royalties = self.sale_royalties(&listing, price).await?;
+        let settlement = self.settlement()?;
+
+        let payment = match settlement {
+            Some(solana) => {
+                let payment = payment
+                    .ok_or_else(|| AppError::BadRequest("Signed payment transaction required".into()))?;
+                let buyer_wallet = self.wallet_address(buyer_id).await?;
+                let seller_wallet = self.wallet_address(listing.seller_id).await?;
+                check_funds(solana.get_breach_balance(&buyer_wallet).await?, price)?;
+
+                let royalty_amount = total_royalty(&royalties);
+                let (fee, seller_receives) = sale_split(price);
+                solana
+                    .verify_breach_payment(
+                        &payment.serialized_transaction,
+                        &buyer_wallet,
+                        &seller_wallet,
+                        (seller_receives - royalty_amount) as u64,
+                        (fee + royalty_amount) as u64,
+                    )
+                    .await?;
+                Some((solana, payment, buyer_wallet))
+            }
+            None => None,
+        };
+
+        // Nobody else can buy, cancel or end the listing while the payment is in flight
+        self.claim_listing(&listing).await?;
+
+        let signature = match payment {
+            Some((solana, payment, buyer_wallet)) => {
+                match solana
+                    .submit_user_signed_transaction(&payment.serialized_transaction, &payment.user_signature, &buyer_wallet)
+                    .await
+                {
+                    Ok(result) => Some(result.signature),
+                    Err(e) => {
+                        self.release_claim(listing_id).await?;
+                        return Err(e);
+                    }
+                }
+            }
+            None => None,
+        };
+
+        let transaction_type = self.sale_type(&listing).await?;
+        let mut tx = self.db.pg.begin().await?;
+        let transaction = Self::record_sale(
+            &mut tx,
+            &listing,
+            buyer_id,
+            price,
+            transaction_type,
+            signature.as_deref(),
+            &royalties,
         )
-        .bind(listing_id)
-        .fetch_optional(&mut *tx)
         .await?;
+        self.credit_guild_fee_share(&mut tx, &transaction).await?;
+        let activity = Self::log_sale(&mut tx, &transaction).await?;
+
+        if let Err(e) = tx.commit().await {
+            // The buyer has paid; the listing stays `settling` for an operator
+            tracing::error!(
+                "Paid sale of listing {} to {} failed to commit (payment {:?}): {}",
+                listing_id,
+                buyer_id,
+                signature,
+                e
+            );
+            return Err(e.into());
+        }
+        self.publish_activity(activity);
+
+        if let Some(solana) = settlement {
+            for royalty in &royalties {
+                self.pay_royalty(solana, royalty.creator_id, royalty.amount).await;
+            }
+        }
+
+        Ok(transaction)
+    }
 
-        let listing = listing.ok_or_else(|| AppError::NotFound("Listing not found or not available".into()))?;
+    /// Listing `buyer_id` can pay for: an active fixed-price or bundle
+    /// listing of someone else's, or a sale awaiting their payment
+    async fn payable_listing(&self, buyer_id: Uuid, listing_id: Uuid) -> ApiResult<MarketplaceListing> {
+        let listing = sqlx::query_as!(
+            MarketplaceListing,
+            r#"
+            SELECT id, seller_id, titan_id, listing_type as "listing_type: ListingType", price,
+                   min_price, status as "status: ListingStatus", created_at, expires_at,
+                   sold_at, cancelled_at, buyer_id, final_price, views, favorites,
+                   escrow_address, listing_tx_signature
+            FROM marketplace_listings
+            WHERE id = $1 AND escrow_address IS NULL
+              AND ((status = 'active' AND listing_type IN ('fixed_price', 'bundle'))
+                   OR (status = 'awaiting_payment' AND buyer_id = $2))
+            "#,
+            listing_id,
+            buyer_id
+        )
+        .fetch_optional(&self.db.pg)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Listing not found or not available".into()))?;
 
         // Cannot buy your own listing
         if listing.seller_id == buyer_id {
             return Err(AppError::BadRequest("Cannot buy your own listing".into()));
         }
 
-        // Calculate fees
-        let fee = (listing.price * PLATFORM_FEE_BPS) / 10000;
-        let seller_receives = listing.price - fee;
+        Ok(listing)
+    }
 
-        // Update listing status
-        sqlx::query(
+    /// Hold a payable listing for its buyer while their payment is sent
+    async fn claim_listing(&self, listing: &MarketplaceListing) -> ApiResult<()> {
+        let claimed = sqlx::query!(
             r#"
-            UPDATE marketplace_listings
-            SET status = 'sold', sold_at = NOW(), buyer_id = $1, final_price = $2
-            WHERE id = $3
-            "#
+            UPDATE marketplace_listings SET status = 'settling'
+            WHERE id = $1 AND status = $2 AND buyer_id IS NOT DISTINCT FROM $3
+            "#,
+            listing.id,
+            listing.status as ListingStatus,
+            listing.buyer_id
         )
-        .bind(buyer_id)
-        .bind(listing.price)
-        .bind(listing_id)
-        .execute(&mut *tx)
+        .execute(&self.db.pg)
         .await?;
 
-        // Transfer Titan ownership
-        sqlx::query(
-            "UPDATE player_titans SET player_id = $1 WHERE id = $2"
-        )
-        .bind(buyer_id)
-        .bind(listing.titan_id)
-        .execute(&mut *tx)
-        .await?;
+        if claimed.rows_affected() == 0 {
+            return Err(AppError::NotFound("Listing not found or not available".into()));
+        }
+        Ok(())
+    }
 
-        // Create transaction record
-        let transaction = sqlx::query_as::<_, MarketplaceTransaction>(
+    /// Give a claimed listing back after its payment failed: a sale awaiting
+    /// its buyer keeps waiting, anything else is open to buyers again
+    async fn release_claim(&self, listing_id: Uuid) -> ApiResult<()> {
+        sqlx::query!(
             r#"
-            INSERT INTO marketplace_transactions
-            (listing_id, seller_id, buyer_id, titan_id, transaction_type, price, fee, seller_receives)
-            VALUES ($1, $2, $3, $4, 'purchase', $5, $6, $7)
-            RETURNING *
-            "#
+            UPDATE marketplace_listings
+            SET status = CASE WHEN buyer_id IS NULL THEN 'active' ELSE 'awaiting_payment' END::listing_status
+            WHERE id = $1 AND status = 'settling'
+            "#,
+            listing_id
         )
-        .bind(listing_id)
-        .bind(listing.seller_id)
-        .bind(buyer_id)
-        .bind(listing.titan_id)
-        .bind(listing.price)
-        .bind(fee)
-        .bind(seller_receives)
-        .fetch_one(&mut *tx)
+        .execute(&self.db.pg)
         .await?;
+        Ok(())
+    }
 
-        // Record price history
-        sqlx::query(
-            r#"
-            INSERT INTO price_history (element, threat_class, species_id, price, transaction_type)
-            SELECT pt.element, pt.threat_class, pt.species_id, $1, 'purchase'
-            FROM player_titans pt WHERE pt.id = $2
-            "#
+    /// How a paid listing was sold
+    async fn sale_type(&self, listing: &MarketplaceListing) -> ApiResult<TransactionType> {
+        if listing.listing_type == ListingType::Auction {
+            return Ok(TransactionType::AuctionWin);
+        }
+        let from_offer = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM price_offers WHERE listing_id = $1) as "exists!""#,
+            listing.id
         )
-        .bind(listing.price)
-        .bind(listing.titan_id)
-        .execute(&mut *tx)
+        .fetch_one(&self.db.pg)
         .await?;
 
-        tx.commit().await?;
-
-        Ok(transaction)
+        Ok(if from_offer { TransactionType::OfferAccepted } else { TransactionType::Purchase })
     }
 
-    // ============================================
-    // Auctions
-    // ============================================
-
-    /// Place a bid on an auction
-    pub async fn place_bid(
+    /// Hold a sale for its buyer's payment, for `payment_window_hours`
+    async fn await_payment(
         &self,
-        bidder_id: Uuid,
+        tx: &mut sqlx::Transaction<'_, Postgres>,
         listing_id: Uuid,
-        amount: i64,
-    ) -> ApiResult<AuctionBid> {
+        buyer_id: Uuid,
+        price: i64,
+    ) -> ApiResult<MarketplaceListing> {
+        let pay_by = Utc::now() + Duration::hours(self.config.marketplace.payment_window_hours as i64);
+        let listing = sqlx::query_as!(
+            MarketplaceListing,
+            r#"
+            UPDATE marketplace_listings
+            SET status = 'awaiting_payment', buyer_id = $2, final_price = $3, expires_at = $4
+            WHERE id = $1
+            RETURNING id, seller_id, titan_id, listing_type as "listing_type: ListingType", price,
+                      min_price, status as "status: ListingStatus", created_at, expires_at,
+                      sold_at, cancelled_at, buyer_id, final_price, views, favorites,
+                      escrow_address, listing_tx_signature
+            "#,
+            listing_id,
+            buyer_id,
+            price,
+            pay_by
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(listing)
+    }
+
+    /// Tell a buyer their sale is waiting on their payment
+    async fn notify_payment_due(&self, listing: &MarketplaceListing, title: &str) {
+        let (Some(buyer_id), Some(price)) = (listing.buyer_id, listing.final_price) else {
+            return;
+        };
+        let message = format!(
+            "Pay {} $BREACH by {} to receive your Titan.",
+            price,
+            listing.expires_at.format("%Y-%m-%d %H:%M UTC")
+        );
+        if let Err(e) = self
+            .create_notification(
+                buyer_id,
+                NotificationType::System,
+                title,
+                &message,
+                Some(serde_json::json!({ "listing_id": listing.id, "amount": price })),
+            )
+            .await
+        {
+            tracing::warn!("Failed to notify buyer {} of payment due: {:?}", buyer_id, e);
+        }
+    }
+
+    // ============================================
+    // Auctions
+    // ============================================
+
+    /// Place a bid on an auction.
+    ///
+    /// A bid of `amount` is placed as is. A bid with `max_bid` is a proxy: it
+    /// opens at the lowest amount that leads and is raised one
+    /// `marketplace.auction_bid_increment_bps` step past each later bid, up
+    /// to `max_bid`. Only the leader's proxy is active; a bid past its
+    /// maximum deactivates it.
+    pub async fn place_bid(
+        &self,
+        bidder_id: Uuid,
+        listing_id: Uuid,
+        req: PlaceBidRequest,
+    ) -> ApiResult<PlaceBidResponse> {
+        let increment_bps = self.config.marketplace.auction_bid_increment_bps;
+        // A plain bid goes no higher than itself
+        let (amount, max_bid) = match (req.amount, req.max_bid) {
+            (Some(amount), None) => (Some(amount), amount),
+            (None, Some(max_bid)) => (None, max_bid),
+            _ => return Err(AppError::BadRequest("Send either amount or max_bid".into())),
+        };
+
         let mut tx = self.db.pg.begin().await?;
 
         // Get and lock listing
-        let listing = sqlx::query_as::<_, MarketplaceListing>(
+        let listing = sqlx::query_as!(
+            MarketplaceListing,
             r#"
-            SELECT * FROM marketplace_listings
+            SELECT id, seller_id, titan_id, listing_type as "listing_type: ListingType", price,
+                   min_price, status as "status: ListingStatus", created_at, expires_at,
+                   sold_at, cancelled_at, buyer_id, final_price, views, favorites,
+                   escrow_address, listing_tx_signature
+            FROM marketplace_listings
             WHERE id = $1 AND status = 'active' AND listing_type = 'auction'
             FOR UPDATE
-            "#
+            "#,
+            listing_id
         )
-        .bind(listing_id)
         .fetch_optional(&mut *tx)
         .await?;
 
@@ -476,88 +927,305 @@ impl MarketplaceService {
 
         // Check minimum bid
         let min_price = listing.min_price.unwrap_or(listing.price);
-        if amount < min_price {
+        if max_bid < min_price {
             return Err(AppError::BadRequest(format!("Bid must be at least {}", min_price)));
         }
 
         // Get current highest bid
-        let current_highest: Option<i64> = sqlx::query_scalar(
-            "SELECT MAX(amount) FROM auction_bids WHERE listing_id = $1"
+        let current_highest: Option<i64> = sqlx::query_scalar!(
+            "SELECT MAX(amount) FROM auction_bids WHERE listing_id = $1",
+            listing_id
         )
-        .bind(listing_id)
         .fetch_one(&mut *tx)
         .await?;
 
         if let Some(highest) = current_highest {
-            if amount <= highest {
+            if max_bid <= highest {
                 return Err(AppError::BadRequest(format!("Bid must be higher than {}", highest)));
             }
         }
 
+        // The winner pays when the auction ends, but only bids they can cover count
+        self.ensure_funds(bidder_id, max_bid).await?;
+
+        // The current top bidder, about to be outbid
+        let previous_bidder = sqlx::query_scalar!(
+            "SELECT bidder_id FROM auction_bids WHERE listing_id = $1 AND is_winning = TRUE",
+            listing_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        // The leader raising their own maximum places no bid
+        if previous_bidder == Some(bidder_id) && amount.is_none() {
+            Self::set_proxy(&mut tx, listing_id, bidder_id, max_bid).await?;
+            let bid = sqlx::query_as!(
+                AuctionBid,
+                r#"
+                SELECT id, listing_id, bidder_id, amount, is_winning, created_at
+                FROM auction_bids WHERE listing_id = $1 AND is_winning = TRUE
+                "#,
+                listing_id
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            tx.commit().await?;
+
+            return Ok(PlaceBidResponse {
+                current_bid: bid.amount,
+                bid,
+                max_bid: Some(max_bid),
+                expires_at: listing.expires_at,
+                extended: false,
+                outbid_player_id: None,
+                exceeded_proxy_max: None,
+            });
+        }
+
+        let leader_max = match previous_bidder.filter(|&previous| previous != bidder_id) {
+            Some(leader) => sqlx::query_scalar!(
+                "SELECT max_bid FROM auction_proxy_bids WHERE listing_id = $1 AND bidder_id = $2 AND active",
+                listing_id,
+                leader
+            )
+            .fetch_optional(&mut *tx)
+            .await?,
+            None => None,
+        };
+
+        let opening = amount.unwrap_or_else(|| {
+            let lowest = current_highest.map_or(min_price, |highest| {
+                highest + bid_increment(highest, increment_bps)
+            });
+            lowest.min(max_bid)
+        });
+        let outcome = resolve_proxy_bid(opening, max_bid, current_highest, leader_max, increment_bps);
+
         // Cancel previous winning bid marker
-        sqlx::query(
-            "UPDATE auction_bids SET is_winning = FALSE WHERE listing_id = $1 AND is_winning = TRUE"
+        sqlx::query!(
+            "UPDATE auction_bids SET is_winning = FALSE WHERE listing_id = $1 AND is_winning = TRUE",
+            listing_id
         )
-        .bind(listing_id)
         .execute(&mut *tx)
         .await?;
 
-        // Create bid
-        let bid = sqlx::query_as::<_, AuctionBid>(
-            r#"
-            INSERT INTO auction_bids (listing_id, bidder_id, amount, is_winning)
-            VALUES ($1, $2, $3, TRUE)
-            RETURNING *
-            "#
+        // Bids go in the order they were made: the side that ends up behind first
+        let bid = if outcome.challenger_leads {
+            if let (Some(leader), Some(leader_bid)) = (previous_bidder, outcome.leader_bid) {
+                Self::insert_bid(&mut tx, listing_id, leader, leader_bid, false).await?;
+            }
+            Self::insert_bid(&mut tx, listing_id, bidder_id, outcome.challenger_bid, true).await?
+        } else {
+            let bid = Self::insert_bid(&mut tx, listing_id, bidder_id, outcome.challenger_bid, false).await?;
+            if let (Some(leader), Some(leader_bid)) = (previous_bidder, outcome.leader_bid) {
+                Self::insert_bid(&mut tx, listing_id, leader, leader_bid, true).await?;
+            }
+            bid
+        };
+        let current_bid = if outcome.challenger_leads {
+            outcome.challenger_bid
+        } else {
+            outcome.leader_bid.unwrap_or(outcome.challenger_bid)
+        };
+
+        // The leader's proxy is used up; a proxy that leads takes its place
+        let outbid_player_id = previous_bidder
+            .filter(|&previous| previous != bidder_id && outcome.challenger_leads);
+        if let Some(previous) = outbid_player_id {
+            sqlx::query!(
+                r#"
+                UPDATE auction_proxy_bids SET active = FALSE, updated_at = NOW()
+                WHERE listing_id = $1 AND bidder_id = $2 AND active
+                "#,
+                listing_id,
+                previous
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        let proxy_max = (amount.is_none() && outcome.challenger_leads).then_some(max_bid);
+        if let Some(proxy_max) = proxy_max {
+            Self::set_proxy(&mut tx, listing_id, bidder_id, proxy_max).await?;
+        }
+
+        // Anti-sniping: a bid in the final minutes keeps the auction open a
+        // little longer, up to a cap past the original end time
+        let original_expires_at = sqlx::query_scalar!(
+            "SELECT original_expires_at FROM marketplace_listings WHERE id = $1",
+            listing_id
         )
-        .bind(listing_id)
-        .bind(bidder_id)
-        .bind(amount)
         .fetch_one(&mut *tx)
+        .await?
+        .unwrap_or(listing.expires_at);
+
+        let rules = &self.config.marketplace;
+        let extended_to = extended_auction_expiry(
+            listing.expires_at,
+            original_expires_at,
+            Utc::now(),
+            Duration::seconds(rules.auction_extension_window_seconds as i64),
+            Duration::seconds(rules.auction_extension_seconds as i64),
+            Duration::seconds(rules.auction_max_extension_seconds as i64),
+        );
+
+        if let Some(expires_at) = extended_to {
+            sqlx::query!(
+                r#"
+                UPDATE marketplace_listings
+                SET expires_at = $2, original_expires_at = $3, extension_count = extension_count + 1
+                WHERE id = $1
+                "#,
+                listing_id,
+                expires_at,
+                original_expires_at
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let activity = Self::log_activity(
+            &mut tx,
+            MarketplaceActivityType::BidPlaced,
+            listing_id,
+            listing.titan_id,
+            current_bid,
+            bidder_id,
+        )
         .await?;
 
         tx.commit().await?;
+        self.publish_activity(activity);
+
+        // Raising your own winning bid doesn't outbid anyone
+        let exceeded_proxy_max = leader_max.filter(|_| outbid_player_id.is_some());
+        if let Some(previous) = outbid_player_id {
+            let body = match exceeded_proxy_max {
+                Some(max) => format!("Someone bid past your maximum of {} on an auction you were leading.", max),
+                None => format!("Someone bid {} on an auction you were leading.", current_bid),
+            };
+            if let Err(e) = self
+                .create_notification(
+                    previous,
+                    NotificationType::Outbid,
+                    "You've been outbid",
+                    &body,
+                    Some(serde_json::json!({ "listing_id": listing_id, "amount": current_bid })),
+                )
+                .await
+            {
+                tracing::warn!("Failed to notify outbid player {}: {:?}", previous, e);
+            }
+        }
+
+        Ok(PlaceBidResponse {
+            bid,
+            current_bid,
+            max_bid: proxy_max,
+            expires_at: extended_to.unwrap_or(listing.expires_at),
+            extended: extended_to.is_some(),
+            outbid_player_id,
+            exceeded_proxy_max,
+        })
+    }
+
+    /// Record a bid
+    async fn insert_bid(
+        tx: &mut sqlx::Transaction<'_, Postgres>,
+        listing_id: Uuid,
+        bidder_id: Uuid,
+        amount: i64,
+        is_winning: bool,
+    ) -> ApiResult<AuctionBid> {
+        let bid = sqlx::query_as!(
+            AuctionBid,
+            r#"
+            INSERT INTO auction_bids (listing_id, bidder_id, amount, is_winning)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, listing_id, bidder_id, amount, is_winning, created_at
+            "#,
+            listing_id,
+            bidder_id,
+            amount,
+            is_winning
+        )
+        .fetch_one(&mut **tx)
+        .await?;
 
         Ok(bid)
     }
 
+    /// Make `max_bid` the bidder's active proxy maximum on an auction
+    async fn set_proxy(
+        tx: &mut sqlx::Transaction<'_, Postgres>,
+        listing_id: Uuid,
+        bidder_id: Uuid,
+        max_bid: i64,
+    ) -> ApiResult<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO auction_proxy_bids (listing_id, bidder_id, max_bid)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (listing_id, bidder_id)
+            DO UPDATE SET max_bid = EXCLUDED.max_bid, active = TRUE, updated_at = NOW()
+            "#,
+            listing_id,
+            bidder_id,
+            max_bid
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Players following an auction: everyone who favorited it or bid on it
+    pub async fn auction_watchers(&self, listing_id: Uuid) -> ApiResult<Vec<Uuid>> {
+        let watchers = sqlx::query_scalar!(
+            r#"
+            SELECT player_id as "player_id!" FROM listing_favorites WHERE listing_id = $1
+            UNION
+            SELECT bidder_id FROM auction_bids WHERE listing_id = $1
+            "#,
+            listing_id
+        )
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        Ok(watchers)
+    }
+
     /// Get bids for a listing
     pub async fn get_listing_bids(&self, listing_id: Uuid) -> ApiResult<Vec<BidResponse>> {
-        let rows = sqlx::query(
+        let bids = sqlx::query_as!(
+            BidResponse,
             r#"
-            SELECT b.*, p.username as bidder_username
+            SELECT b.id, b.listing_id, b.bidder_id, p.username as "bidder_username?",
+                   b.amount, b.is_winning, b.created_at
             FROM auction_bids b
             JOIN players p ON b.bidder_id = p.id
             WHERE b.listing_id = $1
             ORDER BY b.amount DESC
-            "#
+            "#,
+            listing_id
         )
-        .bind(listing_id)
         .fetch_all(&self.db.pg)
         .await?;
 
-        let bids = rows.into_iter().map(|row| BidResponse {
-            id: row.get("id"),
-            listing_id: row.get("listing_id"),
-            bidder_id: row.get("bidder_id"),
-            bidder_username: row.get("bidder_username"),
-            amount: row.get("amount"),
-            is_winning: row.get("is_winning"),
-            created_at: row.get("created_at"),
-        }).collect();
-
         Ok(bids)
     }
 
-    /// End auction and complete sale
-    pub async fn end_auction(&self, listing_id: Uuid) -> ApiResult<Option<MarketplaceTransaction>> {
+    /// End an auction. With settlement enabled the winner's bid is held for
+    /// their payment through `buy_listing`; otherwise the sale completes here.
+    /// `None` when there were no bids and the auction expired.
+    pub async fn end_auction(&self, listing_id: Uuid) -> ApiResult<Option<SaleOutcome>> {
         let mut tx = self.db.pg.begin().await?;
 
         // Get listing
-        let listing = sqlx::query_as::<_, MarketplaceListing>(
-            "SELECT * FROM marketplace_listings WHERE id = $1 AND listing_type = 'auction' FOR UPDATE"
+        let listing = sqlx::query_as!(
+            MarketplaceListing,
+            r#"SELECT id, seller_id, titan_id, listing_type as "listing_type: ListingType", price, min_price, status as "status: ListingStatus", created_at, expires_at, sold_at, cancelled_at, buyer_id, final_price, views, favorites, escrow_address, listing_tx_signature FROM marketplace_listings WHERE id = $1 AND listing_type = 'auction' FOR UPDATE"#,
+            listing_id
         )
-        .bind(listing_id)
         .fetch_optional(&mut *tx)
         .await?;
 
@@ -567,74 +1235,57 @@ impl MarketplaceService {
             return Err(AppError::BadRequest("Auction is not active".into()));
         }
 
+        // Includes any anti-sniping extension
+        if listing.expires_at > Utc::now() {
+            return Err(AppError::BadRequest("Auction has not ended".into()));
+        }
+
         // Get winning bid
-        let winning_bid = sqlx::query_as::<_, AuctionBid>(
-            "SELECT * FROM auction_bids WHERE listing_id = $1 AND is_winning = TRUE"
+        let winning_bid = sqlx::query_as!(
+            AuctionBid,
+            "SELECT id, listing_id, bidder_id, amount, is_winning, created_at FROM auction_bids WHERE listing_id = $1 AND is_winning = TRUE",
+            listing_id
         )
-        .bind(listing_id)
         .fetch_optional(&mut *tx)
         .await?;
 
-        match winning_bid {
-            Some(bid) => {
-                // Has winner - complete transaction
-                let fee = (bid.amount * PLATFORM_FEE_BPS) / 10000;
-                let seller_receives = bid.amount - fee;
-
-                // Update listing
-                sqlx::query(
-                    r#"
-                    UPDATE marketplace_listings
-                    SET status = 'sold', sold_at = NOW(), buyer_id = $1, final_price = $2
-                    WHERE id = $3
-                    "#
-                )
-                .bind(bid.bidder_id)
-                .bind(bid.amount)
-                .bind(listing_id)
-                .execute(&mut *tx)
-                .await?;
+        let Some(bid) = winning_bid else {
+            // No bids - mark as expired
+            sqlx::query!(
+                "UPDATE marketplace_listings SET status = 'expired' WHERE id = $1",
+                listing_id
+            )
+            .execute(&mut *tx)
+            .await?;
 
-                // Transfer ownership
-                sqlx::query("UPDATE player_titans SET player_id = $1 WHERE id = $2")
-                    .bind(bid.bidder_id)
-                    .bind(listing.titan_id)
-                    .execute(&mut *tx)
-                    .await?;
+            tx.commit().await?;
+            return Ok(None);
+        };
 
-                // Create transaction record
-                let transaction = sqlx::query_as::<_, MarketplaceTransaction>(
-                    r#"
-                    INSERT INTO marketplace_transactions
-                    (listing_id, seller_id, buyer_id, titan_id, transaction_type, price, fee, seller_receives)
-                    VALUES ($1, $2, $3, $4, 'auction_win', $5, $6, $7)
-                    RETURNING *
-                    "#
-                )
-                .bind(listing_id)
-                .bind(listing.seller_id)
-                .bind(bid.bidder_id)
-                .bind(listing.titan_id)
-                .bind(bid.amount)
-                .bind(fee)
-                .bind(seller_receives)
-                .fetch_one(&mut *tx)
-                .await?;
+        if self.settlement()?.is_some() {
+            let awaiting = self.await_payment(&mut tx, listing_id, bid.bidder_id, bid.amount).await?;
+            tx.commit().await?;
+            self.notify_payment_due(&awaiting, "You won an auction").await;
+            return Ok(Some(SaleOutcome::AwaitingPayment(awaiting)));
+        }
 
-                tx.commit().await?;
-                Ok(Some(transaction))
-            }
-            None => {
-                // No bids - mark as expired
-                sqlx::query("UPDATE marketplace_listings SET status = 'expired' WHERE id = $1")
-                    .bind(listing_id)
-                    .execute(&mut *tx)
-                    .await?;
+        let royalties = self.sale_royalties(&listing, bid.amount).await?;
+        let transaction = Self::record_sale(
+            &mut tx,
+            &listing,
+            bid.bidder_id,
+            bid.amount,
+            TransactionType::AuctionWin,
+            None,
+            &royalties,
+        )
+        .await?;
+        self.credit_guild_fee_share(&mut tx, &transaction).await?;
+        let activity = Self::log_sale(&mut tx, &transaction).await?;
 
-                tx.commit().await?;
-                Ok(None)
-            }
-        }
+        tx.commit().await?;
+        self.publish_activity(activity);
+        Ok(Some(SaleOutcome::Completed(transaction)))
     }
 
     // ============================================
@@ -644,10 +1295,10 @@ impl MarketplaceService {
     /// Make an offer on a Titan (not listed)
     pub async fn make_offer(&self, offerer_id: Uuid, req: MakeOfferRequest) -> ApiResult<PriceOffer> {
         // Verify Titan exists and not owned by offerer
-        let owner_id: Option<Uuid> = sqlx::query_scalar(
-            "SELECT player_id FROM player_titans WHERE id = $1"
+        let owner_id: Option<Uuid> = sqlx::query_scalar!(
+            "SELECT player_id FROM player_titans WHERE id = $1",
+            req.titan_id
         )
-        .bind(req.titan_id)
         .fetch_optional(&self.db.pg)
         .await?;
 
@@ -659,99 +1310,124 @@ impl MarketplaceService {
 
         let expires_at = Utc::now() + Duration::hours(req.expires_in_hours);
 
-        let offer = sqlx::query_as::<_, PriceOffer>(
+        let offer = sqlx::query_as!(
+            PriceOffer,
             r#"
             INSERT INTO price_offers (titan_id, offerer_id, owner_id, amount, expires_at, message)
             VALUES ($1, $2, $3, $4, $5, $6)
-            RETURNING *
-            "#
+            RETURNING id, titan_id, offerer_id, owner_id, amount, status, created_at, expires_at,
+                      responded_at, message
+            "#,
+            req.titan_id,
+            offerer_id,
+            owner_id,
+            req.amount,
+            expires_at,
+            req.message
         )
-        .bind(req.titan_id)
-        .bind(offerer_id)
-        .bind(owner_id)
-        .bind(req.amount)
-        .bind(expires_at)
-        .bind(req.message)
         .fetch_one(&self.db.pg)
         .await?;
 
         Ok(offer)
     }
 
-    /// Accept an offer
-    pub async fn accept_offer(&self, owner_id: Uuid, offer_id: Uuid) -> ApiResult<MarketplaceTransaction> {
+    /// Accept an offer. The Titan is sold through a listing of its own: with
+    /// settlement enabled it is held for the offerer's payment through
+    /// `buy_listing`; otherwise the sale completes here.
+    pub async fn accept_offer(&self, owner_id: Uuid, offer_id: Uuid) -> ApiResult<SaleOutcome> {
         let mut tx = self.db.pg.begin().await?;
 
         // Get and validate offer
-        let offer = sqlx::query_as::<_, PriceOffer>(
-            "SELECT * FROM price_offers WHERE id = $1 AND owner_id = $2 AND status = 'pending' FOR UPDATE"
+        let offer = sqlx::query_as!(
+            PriceOffer,
+            "SELECT id, titan_id, offerer_id, owner_id, amount, status, created_at, expires_at, responded_at, message FROM price_offers WHERE id = $1 AND owner_id = $2 AND status = 'pending' FOR UPDATE",
+            offer_id,
+            owner_id
         )
-        .bind(offer_id)
-        .bind(owner_id)
         .fetch_optional(&mut *tx)
         .await?;
 
         let offer = offer.ok_or_else(|| AppError::NotFound("Offer not found".into()))?;
 
         if offer.expires_at < Utc::now() {
-            sqlx::query("UPDATE price_offers SET status = 'expired' WHERE id = $1")
-                .bind(offer_id)
-                .execute(&mut *tx)
-                .await?;
+            sqlx::query!(
+                "UPDATE price_offers SET status = 'expired' WHERE id = $1",
+                offer_id
+            )
+            .execute(&mut *tx)
+            .await?;
             return Err(AppError::BadRequest("Offer has expired".into()));
         }
 
-        // Calculate fees
-        let fee = (offer.amount * PLATFORM_FEE_BPS) / 10000;
-        let seller_receives = offer.amount - fee;
+        // A listed Titan is promised to the listing's buyer
+        let escrowed = sqlx::query_scalar!(
+            "SELECT escrowed_listing_id FROM player_titans WHERE id = $1",
+            offer.titan_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if escrowed.is_some() {
+            return Err(AppError::TitanEscrowed);
+        }
+
+        // The offer's listing escrows the Titan until it is paid for
+        let listing_id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO marketplace_listings (seller_id, titan_id, listing_type, price, expires_at)
+            VALUES ($1, $2, 'fixed_price', $3, NOW())
+            RETURNING id
+            "#,
+            owner_id,
+            offer.titan_id,
+            offer.amount
+        )
+        .fetch_one(&mut *tx)
+        .await?;
 
         // Update offer status
-        sqlx::query("UPDATE price_offers SET status = 'accepted', responded_at = NOW() WHERE id = $1")
-            .bind(offer_id)
-            .execute(&mut *tx)
-            .await?;
+        sqlx::query!(
+            "UPDATE price_offers SET status = 'accepted', responded_at = NOW(), listing_id = $2 WHERE id = $1",
+            offer_id,
+            listing_id
+        )
+        .execute(&mut *tx)
+        .await?;
 
-        // Transfer ownership
-        sqlx::query("UPDATE player_titans SET player_id = $1 WHERE id = $2")
-            .bind(offer.offerer_id)
-            .bind(offer.titan_id)
-            .execute(&mut *tx)
-            .await?;
+        let listing = self.await_payment(&mut tx, listing_id, offer.offerer_id, offer.amount).await?;
 
-        // Create virtual listing (for transaction record)
-        let listing_id = Uuid::new_v4();
+        if self.settlement()?.is_some() {
+            tx.commit().await?;
+            self.notify_payment_due(&listing, "Your offer was accepted").await;
+            return Ok(SaleOutcome::AwaitingPayment(listing));
+        }
 
-        // Create transaction record
-        let transaction = sqlx::query_as::<_, MarketplaceTransaction>(
-            r#"
-            INSERT INTO marketplace_transactions
-            (id, listing_id, seller_id, buyer_id, titan_id, transaction_type, price, fee, seller_receives)
-            VALUES ($1, $1, $2, $3, $4, 'offer_accepted', $5, $6, $7)
-            RETURNING *
-            "#
+        let royalties = self.sale_royalties(&listing, offer.amount).await?;
+        let transaction = Self::record_sale(
+            &mut tx,
+            &listing,
+            offer.offerer_id,
+            offer.amount,
+            TransactionType::OfferAccepted,
+            None,
+            &royalties,
         )
-        .bind(listing_id)
-        .bind(owner_id)
-        .bind(offer.offerer_id)
-        .bind(offer.titan_id)
-        .bind(offer.amount)
-        .bind(fee)
-        .bind(seller_receives)
-        .fetch_one(&mut *tx)
         .await?;
+        self.credit_guild_fee_share(&mut tx, &transaction).await?;
+        let activity = Self::log_sale(&mut tx, &transaction).await?;
 
         tx.commit().await?;
+        self.publish_activity(activity);
 
-        Ok(transaction)
+        Ok(SaleOutcome::Completed(transaction))
     }
 
     /// Reject an offer
     pub async fn reject_offer(&self, owner_id: Uuid, offer_id: Uuid) -> ApiResult<()> {
-        let result = sqlx::query(
-            "UPDATE price_offers SET status = 'rejected', responded_at = NOW() WHERE id = $1 AND owner_id = $2 AND status = 'pending'"
+        let result = sqlx::query!(
+            "UPDATE price_offers SET status = 'rejected', responded_at = NOW() WHERE id = $1 AND owner_id = $2 AND status = 'pending'",
+            offer_id,
+            owner_id
         )
-        .bind(offer_id)
-        .bind(owner_id)
         .execute(&self.db.pg)
         .await?;
 
@@ -764,62 +1440,40 @@ impl MarketplaceService {
 
     /// Get offers received
     pub async fn get_received_offers(&self, owner_id: Uuid) -> ApiResult<Vec<OfferResponse>> {
-        let rows = sqlx::query(
+        let offers = sqlx::query_as!(
+            OfferResponse,
             r#"
-            SELECT o.*, p.username as offerer_username
+            SELECT o.id, o.titan_id, o.offerer_id, p.username as "offerer_username?",
+                   o.owner_id, o.amount, o.status, o.message, o.expires_at, o.created_at
             FROM price_offers o
             JOIN players p ON o.offerer_id = p.id
             WHERE o.owner_id = $1 AND o.status = 'pending' AND o.expires_at > NOW()
             ORDER BY o.created_at DESC
-            "#
+            "#,
+            owner_id
         )
-        .bind(owner_id)
         .fetch_all(&self.db.pg)
         .await?;
 
-        let offers = rows.into_iter().map(|row| OfferResponse {
-            id: row.get("id"),
-            titan_id: row.get("titan_id"),
-            offerer_id: row.get("offerer_id"),
-            offerer_username: row.get("offerer_username"),
-            owner_id: row.get("owner_id"),
-            amount: row.get("amount"),
-            status: row.get("status"),
-            message: row.get("message"),
-            expires_at: row.get("expires_at"),
-            created_at: row.get("created_at"),
-        }).collect();
-
         Ok(offers)
     }
 
     /// Get offers sent
     pub async fn get_sent_offers(&self, offerer_id: Uuid) -> ApiResult<Vec<OfferResponse>> {
-        let rows = sqlx::query(
+        let offers = sqlx::query_as!(
+            OfferResponse,
             r#"
-            SELECT o.*, NULL as offerer_username
+            SELECT o.id, o.titan_id, o.offerer_id, NULL::TEXT as offerer_username,
+                   o.owner_id, o.amount, o.status, o.message, o.expires_at, o.created_at
             FROM price_offers o
             WHERE o.offerer_id = $1
             ORDER BY o.created_at DESC
-            "#
+            "#,
+            offerer_id
         )
-        .bind(offerer_id)
         .fetch_all(&self.db.pg)
         .await?;
 
-        let offers = rows.into_iter().map(|row| OfferResponse {
-            id: row.get("id"),
-            titan_id: row.get("titan_id"),
-            offerer_id: row.get("offerer_id"),
-            offerer_username: None,
-            owner_id: row.get("owner_id"),
-            amount: row.get("amount"),
-            status: row.get("status"),
-            message: row.get("message"),
-            expires_at: row.get("expires_at"),
-            created_at: row.get("created_at"),
-        }).collect();
-
         Ok(offers)
     }
 
@@ -829,11 +1483,11 @@ impl MarketplaceService {
 
     /// Add listing to favorites
     pub async fn add_favorite(&self, player_id: Uuid, listing_id: Uuid) -> ApiResult<()> {
-        sqlx::query(
-            "INSERT INTO listing_favorites (player_id, listing_id) VALUES ($1, $2) ON CONFLICT DO NOTHING"
+        sqlx::query!(
+            "INSERT INTO listing_favorites (player_id, listing_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+            player_id,
+            listing_id
         )
-        .bind(player_id)
-        .bind(listing_id)
         .execute(&self.db.pg)
         .await?;
 
@@ -842,11 +1496,13 @@ impl MarketplaceService {
 
     /// Remove listing from favorites
     pub async fn remove_favorite(&self, player_id: Uuid, listing_id: Uuid) -> ApiResult<()> {
-        sqlx::query("DELETE FROM listing_favorites WHERE player_id = $1 AND listing_id = $2")
-            .bind(player_id)
-            .bind(listing_id)
-            .execute(&self.db.pg)
-            .await?;
+        sqlx::query!(
+            "DELETE FROM listing_favorites WHERE player_id = $1 AND listing_id = $2",
+            player_id,
+            listing_id
+        )
+        .execute(&self.db.pg)
+        .await?;
 
         Ok(())
     }
@@ -854,57 +1510,33 @@ impl MarketplaceService {
     /// Get player's favorite listings
     pub async fn get_favorites(&self, player_id: Uuid) -> ApiResult<Vec<ListingResponse>> {
         // Simplified version, reuse search_listings response format
-        let rows = sqlx::query(
+        let rows = sqlx::query_as!(
+            ListingRow,
             r#"
             SELECT 
-                l.id, l.seller_id, l.titan_id, l.listing_type, l.price, l.min_price,
-                l.status, l.expires_at, l.views, l.favorites, l.created_at,
+                l.id, l.seller_id, l.titan_id,
+                l.listing_type as "listing_type: ListingType", l.price, l.min_price,
+                l.status as "status: ListingStatus", l.expires_at, l.views, l.favorites, l.created_at,
                 p.username as seller_username,
-                pt.element, pt.threat_class, pt.species_id, pt.level, pt.nickname, pt.genes,
-                COALESCE((SELECT MAX(amount) FROM auction_bids WHERE listing_id = l.id), 0) as current_bid,
-                (SELECT COUNT(*) FROM auction_bids WHERE listing_id = l.id)::INT as bid_count,
-                TRUE as is_favorited
+                pt.element as "element: Element", pt.threat_class, pt.species_id, pt.level,
+                pt.nickname, pt.genes,
+                COALESCE((SELECT MAX(amount) FROM auction_bids WHERE listing_id = l.id), 0) as "current_bid!",
+                (SELECT COUNT(*) FROM auction_bids WHERE listing_id = l.id)::INT as "bid_count!",
+                TRUE as "is_favorited!"
             FROM listing_favorites f
             JOIN marketplace_listings l ON f.listing_id = l.id
             JOIN players p ON l.seller_id = p.id
             JOIN player_titans pt ON l.titan_id = pt.id
             WHERE f.player_id = $1
             ORDER BY f.created_at DESC
-            "#
+            "#,
+            player_id
         )
-        .bind(player_id)
         .fetch_all(&self.db.pg)
         .await?;
 
-        let listings = rows.into_iter().map(|row| ListingResponse {
-            id: row.get("id"),
-            seller_id: row.get("seller_id"),
-            seller_username: row.get("seller_username"),
-            titan_id: row.get("titan_id"),
-            titan: TitanListingInfo {
-                id: row.get("titan_id"),
-                element: row.get("element"),
-                threat_class: row.get("threat_class"),
-                species_id: row.get("species_id"),
-                level: row.get("level"),
-                nickname: row.get("nickname"),
-                genes: row.get("genes"),
-            },
-            listing_type: row.get("listing_type"),
-            price: row.get("price"),
-            min_price: row.get("min_price"),
-            current_bid: {
-                let bid: i64 = row.get("current_bid");
-                if bid > 0 { Some(bid) } else { None }
-            },
-            bid_count: row.get("bid_count"),
-            status: row.get("status"),
-            expires_at: row.get("expires_at"),
-            views: row.get("views"),
-            favorites: row.get("favorites"),
-            is_favorited: true,
-            created_at: row.get("created_at"),
-        }).collect();
+        let mut listings: Vec<ListingResponse> = rows.into_iter().map(ListingResponse::from).collect();
+        self.attach_bundle_titans(&mut listings).await?;
 
         Ok(listings)
     }
@@ -915,13 +1547,14 @@ impl MarketplaceService {
 
     /// Get marketplace stats
     pub async fn get_stats(&self) -> ApiResult<MarketplaceStatsResponse> {
-        let row = sqlx::query(
+        let stats = sqlx::query_as!(
+            MarketplaceStatsResponse,
             r#"
             SELECT 
-                (SELECT COUNT(*) FROM marketplace_listings)::INT as total_listings,
-                (SELECT COUNT(*) FROM marketplace_listings WHERE status = 'active')::INT as active_listings,
-                COALESCE((SELECT SUM(price) FROM marketplace_transactions WHERE created_at > NOW() - INTERVAL '24 hours'), 0)::BIGINT as total_volume_24h,
-                (SELECT COUNT(*) FROM marketplace_transactions WHERE created_at > NOW() - INTERVAL '24 hours')::INT as total_sales_24h,
+                (SELECT COUNT(*) FROM marketplace_listings)::INT as "total_listings!",
+                (SELECT COUNT(*) FROM marketplace_listings WHERE status = 'active')::INT as "active_listings!",
+                COALESCE((SELECT SUM(price) FROM marketplace_transactions WHERE created_at > NOW() - INTERVAL '24 hours'), 0)::BIGINT as "total_volume_24h!",
+                (SELECT COUNT(*) FROM marketplace_transactions WHERE created_at > NOW() - INTERVAL '24 hours')::INT as "total_sales_24h!",
                 (SELECT MIN(price) FROM marketplace_listings WHERE status = 'active') as floor_price,
                 (SELECT AVG(price)::BIGINT FROM marketplace_transactions WHERE created_at > NOW() - INTERVAL '7 days') as avg_price
             "#
@@ -929,27 +1562,150 @@ impl MarketplaceService {
         .fetch_one(&self.db.pg)
         .await?;
 
-        Ok(MarketplaceStatsResponse {
-            total_listings: row.get("total_listings"),
-            active_listings: row.get("active_listings"),
-            total_volume_24h: row.get("total_volume_24h"),
-            total_sales_24h: row.get("total_sales_24h"),
-            floor_price: row.get("floor_price"),
-            avg_price: row.get("avg_price"),
+        Ok(stats)
+    }
+
+    /// Floor price, 7-day average and volume per element and threat class,
+    /// cached in Redis for 5 minutes
+    pub async fn get_floor_prices(&self) -> ApiResult<Vec<FloorPriceEntry>> {
+        if let Some(cached) = self.cached(FLOOR_PRICES_KEY).await {
+            return Ok(cached);
+        }
+
+        let entries = sqlx::query_as!(
+            FloorPriceEntry,
+            r#"
+            WITH floors AS (
+                SELECT pt.element, pt.threat_class, MIN(l.price) AS floor_price
+                FROM marketplace_listings l
+                JOIN player_titans pt ON l.titan_id = pt.id
+                WHERE l.status = 'active' AND l.listing_type <> 'bundle'
+                GROUP BY pt.element, pt.threat_class
+            ),
+            sales AS (
+                SELECT element, threat_class, AVG(price)::BIGINT AS avg_price_7d,
+                       SUM(price)::BIGINT AS volume_7d, COUNT(*) AS sales_7d
+                FROM price_history
+                WHERE recorded_at > NOW() - INTERVAL '7 days'
+                GROUP BY element, threat_class
+            )
+            SELECT COALESCE(f.element, s.element) AS "element!: Element",
+                   COALESCE(f.threat_class, s.threat_class) AS "threat_class!",
+                   f.floor_price, s.avg_price_7d,
+                   COALESCE(s.volume_7d, 0) AS "volume_7d!", COALESCE(s.sales_7d, 0) AS "sales_7d!"
+            FROM floors f
+            FULL OUTER JOIN sales s ON f.element = s.element AND f.threat_class = s.threat_class
+            ORDER BY 1, 2
+            "#
+        )
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        self.cache(FLOOR_PRICES_KEY, &entries).await;
+        Ok(entries)
+    }
+
+    /// Listing price for one of the player's Titans: the median of sales of
+    /// the same element and threat class in the last 30 days. The sales are
+    /// cached in Redis for 5 minutes per element and threat class.
+    pub async fn suggest_price(&self, player_id: Uuid, titan_id: Uuid) -> ApiResult<SuggestedPriceResponse> {
+        let titan = sqlx::query!(
+            r#"SELECT element as "element: Element", threat_class FROM player_titans WHERE id = $1 AND player_id = $2"#,
+            titan_id,
+            player_id
+        )
+        .fetch_optional(&self.db.pg)
+        .await?
+        .ok_or(AppError::NotFound("Titan not found".into()))?;
+        let (element, threat_class) = (titan.element, titan.threat_class);
+
+        let key = suggested_price_key(element, threat_class);
+        let (mut prices, floor_price): (Vec<i64>, Option<i64>) = match self.cached(&key).await {
+            Some(cached) => cached,
+            None => {
+                let prices: Vec<i64> = sqlx::query_scalar!(
+                    r#"
+                    SELECT price FROM price_history
+                    WHERE element = $1 AND threat_class = $2
+                      AND recorded_at > NOW() - make_interval(days => $3)
+                    "#,
+                    element as Element,
+                    threat_class,
+                    COMPARABLE_SALE_DAYS
+                )
+                .fetch_all(&self.db.pg)
+                .await?;
+
+                let floor_price: Option<i64> = sqlx::query_scalar!(
+                    r#"
+                    SELECT MIN(l.price) FROM marketplace_listings l
+                    JOIN player_titans pt ON l.titan_id = pt.id
+                    WHERE l.status = 'active' AND l.listing_type <> 'bundle'
+                      AND pt.element = $1 AND pt.threat_class = $2
+                    "#,
+                    element as Element,
+                    threat_class
+                )
+                .fetch_one(&self.db.pg)
+                .await?;
+
+                let comparables = (prices, floor_price);
+                self.cache(&key, &comparables).await;
+                comparables
+            }
+        };
+
+        Ok(SuggestedPriceResponse {
+            titan_id,
+            element,
+            threat_class,
+            comparable_sales: prices.len() as i64,
+            suggested_price: median_price(&mut prices),
+            floor_price,
         })
     }
 
+    /// A cached price lookup; a miss when Redis is unavailable
+    async fn cached<T: serde::de::DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let mut conn = self.db.redis.clone();
+        match redis::cmd("GET").arg(key).query_async::<_, Option<String>>(&mut conn).await {
+            Ok(cached) => cached.and_then(|json| serde_json::from_str(&json).ok()),
+            Err(e) => {
+                tracing::warn!("Price cache {} unavailable: {}", key, e);
+                None
+            }
+        }
+    }
+
+    async fn cache<T: serde::Serialize>(&self, key: &str, value: &T) {
+        let Ok(json) = serde_json::to_string(value) else {
+            return;
+        };
+        let mut conn = self.db.redis.clone();
+        let cached: redis::RedisResult<()> = redis::cmd("SET")
+            .arg(key)
+            .arg(json)
+            .arg("EX")
+            .arg(PRICE_CACHE_SECS)
+            .query_async(&mut conn)
+            .await;
+        if let Err(e) = cached {
+            tracing::warn!("Failed to cache {}: {}", key, e);
+        }
+    }
+
     /// Get transaction history for a player
     pub async fn get_transaction_history(&self, player_id: Uuid) -> ApiResult<Vec<TransactionHistoryEntry>> {
-        let rows = sqlx::query(
+        let history = sqlx::query_as!(
+            TransactionHistoryEntry,
             r#"
             SELECT 
-                t.id, t.listing_id, t.transaction_type, t.price, t.fee,
-                CASE WHEN t.seller_id = $1 THEN t.buyer_id ELSE t.seller_id END as counterparty_id,
+                t.id, t.listing_id, t.transaction_type as "transaction_type: TransactionType", t.price, t.fee,
+                CASE WHEN t.seller_id = $1 THEN t.buyer_id ELSE t.seller_id END as "counterparty_id!",
                 CASE WHEN t.seller_id = $1 THEN pb.username ELSE ps.username END as counterparty_username,
-                pt.element as titan_element,
-                pt.level as titan_level,
-                t.seller_id = $1 as is_seller,
+                pt.element as "titan_element?: Element",
+                pt.level as "titan_level?",
+                t.seller_id = $1 as "is_seller!",
                 t.created_at
             FROM marketplace_transactions t
             JOIN players ps ON t.seller_id = ps.id
@@ -958,26 +1714,12 @@ impl MarketplaceService {
             WHERE t.seller_id = $1 OR t.buyer_id = $1
             ORDER BY t.created_at DESC
             LIMIT 100
-            "#
+            "#,
+            player_id
         )
-        .bind(player_id)
         .fetch_all(&self.db.pg)
         .await?;
 
-        let history = rows.into_iter().map(|row| TransactionHistoryEntry {
-            id: row.get("id"),
-            listing_id: row.get("listing_id"),
-            transaction_type: row.get("transaction_type"),
-            price: row.get("price"),
-            fee: row.get("fee"),
-            counterparty_id: row.get("counterparty_id"),
-            counterparty_username: row.get("counterparty_username"),
-            titan_element: row.get("titan_element"),
-            titan_level: row.get("titan_level"),
-            is_seller: row.get("is_seller"),
-            created_at: row.get("created_at"),
-        }).collect();
-
         Ok(history)
     }
 
@@ -988,27 +1730,22 @@ impl MarketplaceService {
         threat_class: Option<i16>,
         days: i32,
     ) -> ApiResult<PriceChartResponse> {
-        let mut sql = String::from(
-            "SELECT price, recorded_at FROM price_history WHERE recorded_at > NOW() - $1::INTERVAL"
-        );
-
-        if element.is_some() {
-            sql.push_str(" AND element = $2");
-        }
-        if threat_class.is_some() {
-            sql.push_str(&format!(" AND threat_class = ${}", if element.is_some() { 3 } else { 2 }));
-        }
-
-        sql.push_str(" ORDER BY recorded_at ASC");
-
-        let interval = format!("{} days", days);
-
-        let data_points = sqlx::query_as::<_, PriceHistoryEntry>(&sql)
-            .bind(&interval)
-            .bind(element)
-            .bind(threat_class)
-            .fetch_all(&self.db.pg)
-            .await?;
+        let data_points = sqlx::query_as!(
+            PriceHistoryEntry,
+            r#"
+            SELECT price, recorded_at
+            FROM price_history
+            WHERE recorded_at > NOW() - make_interval(days => $1)
+              AND ($2::element_type IS NULL OR element = $2)
+              AND ($3::SMALLINT IS NULL OR threat_class = $3)
+            ORDER BY recorded_at ASC
+            "#,
+            days,
+            element as Option<Element>,
+            threat_class
+        )
+        .fetch_all(&self.db.pg)
+        .await?;
 
         let prices: Vec<i64> = data_points.iter().map(|p| p.price).collect();
         
@@ -1034,56 +1771,817 @@ impl MarketplaceService {
 
     /// Get player's active listings
     pub async fn get_my_listings(&self, player_id: Uuid) -> ApiResult<Vec<ListingResponse>> {
-        let rows = sqlx::query(
+        let rows = sqlx::query_as!(
+            ListingRow,
             r#"
             SELECT 
-                l.id, l.seller_id, l.titan_id, l.listing_type, l.price, l.min_price,
-                l.status, l.expires_at, l.views, l.favorites, l.created_at,
-                NULL as seller_username,
-                pt.element, pt.threat_class, pt.species_id, pt.level, pt.nickname, pt.genes,
-                COALESCE((SELECT MAX(amount) FROM auction_bids WHERE listing_id = l.id), 0) as current_bid,
-                (SELECT COUNT(*) FROM auction_bids WHERE listing_id = l.id)::INT as bid_count,
-                FALSE as is_favorited
+                l.id, l.seller_id, l.titan_id,
+                l.listing_type as "listing_type: ListingType", l.price, l.min_price,
+                l.status as "status: ListingStatus", l.expires_at, l.views, l.favorites, l.created_at,
+                NULL::TEXT as seller_username,
+                pt.element as "element: Element", pt.threat_class, pt.species_id, pt.level,
+                pt.nickname, pt.genes,
+                COALESCE((SELECT MAX(amount) FROM auction_bids WHERE listing_id = l.id), 0) as "current_bid!",
+                (SELECT COUNT(*) FROM auction_bids WHERE listing_id = l.id)::INT as "bid_count!",
+                FALSE as "is_favorited!"
             FROM marketplace_listings l
             JOIN player_titans pt ON l.titan_id = pt.id
             WHERE l.seller_id = $1
             ORDER BY l.created_at DESC
-            "#
+            "#,
+            player_id
         )
-        .bind(player_id)
         .fetch_all(&self.db.pg)
         .await?;
 
-        let listings = rows.into_iter().map(|row| ListingResponse {
-            id: row.get("id"),
-            seller_id: row.get("seller_id"),
-            seller_username: None,
-            titan_id: row.get("titan_id"),
-            titan: TitanListingInfo {
-                id: row.get("titan_id"),
-                element: row.get("element"),
-                threat_class: row.get("threat_class"),
-                species_id: row.get("species_id"),
-                level: row.get("level"),
-                nickname: row.get("nickname"),
-                genes: row.get("genes"),
-            },
-            listing_type: row.get("listing_type"),
-            price: row.get("price"),
-            min_price: row.get("min_price"),
-            current_bid: {
-                let bid: i64 = row.get("current_bid");
-                if bid > 0 { Some(bid) } else { None }
-            },
-            bid_count: row.get("bid_count"),
-            status: row.get("status"),
-            expires_at: row.get("expires_at"),
-            views: row.get("views"),
-            favorites: row.get("favorites"),
-            is_favorited: false,
-            created_at: row.get("created_at"),
-        }).collect();
+        let mut listings: Vec<ListingResponse> = rows.into_iter().map(ListingResponse::from).collect();
+        self.attach_bundle_titans(&mut listings).await?;
 
         Ok(listings)
     }
+
+    /// Solana service for $BREACH settlement, `None` when settlement is disabled
+    fn settlement(&self) -> ApiResult<Option<&SolanaService>> {
+        if !self.config.marketplace.breach_settlement_enabled {
+            return Ok(None);
+        }
+        self.solana
+            .as_ref()
+            .map(Some)
+            .ok_or_else(|| AppError::ServiceUnavailable("Solana service not available".into()))
+    }
+
+    /// Reject a buyer whose $BREACH balance is below `price`
+    async fn ensure_funds(&self, buyer_id: Uuid, price: i64) -> ApiResult<()> {
+        let Some(solana) = self.settlement()? else {
+            return Ok(());
+        };
+        let wallet = self.wallet_address(buyer_id).await?;
+        check_funds(solana.get_breach_balance(&wallet).await?, price)
+    }
+
+    /// Credit the seller's guild treasury with its share of a sale's fee
+    async fn credit_guild_fee_share(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        transaction: &MarketplaceTransaction,
+    ) -> ApiResult<()> {
+        let share = guild_fee_share(transaction.fee, self.config.marketplace.guild_fee_share_bps);
+        GuildService::credit_treasury(tx, transaction.seller_id, share, "marketplace_fee", Some(transaction.id))
+            .await?;
+        Ok(())
+    }
+
+    /// Royalties owed to the creators of a listing's Titans on its sale at
+    /// `price`; a bundle splits the price across its Titans
+    async fn sale_royalties(&self, listing: &MarketplaceListing, price: i64) -> ApiResult<Vec<SaleRoyalty>> {
+        // The listed Titan, or every Titan of a bundle in listing order
+        let titans: Vec<(Uuid, Uuid)> = sqlx::query!(
+            r#"
+            SELECT pt.id, pt.creator_id
+            FROM player_titans pt
+            LEFT JOIN listing_bundle_titans b ON b.titan_id = pt.id AND b.listing_id = $1
+            WHERE pt.id = $2 OR b.listing_id IS NOT NULL
+            ORDER BY b.position NULLS FIRST
+            "#,
+            listing.id,
+            listing.titan_id
+        )
+        .fetch_all(&self.db.pg)
+        .await?
+        .into_iter()
+        .map(|row| (row.id, row.creator_id))
+        .collect();
+
+        let royalty_bps = self.game.read().await.royalty_bps;
+        Ok(sale_royalties(price, listing.seller_id, &titans, royalty_bps))
+    }
+
+    /// Send a committed sale's royalty to the creator; a failed transfer is
+    /// queued for retry with the other failed rewards
+    async fn pay_royalty(&self, solana: &SolanaService, creator_id: Uuid, amount: i64) {
+        let result = match self.wallet_address(creator_id).await {
+            Ok(wallet) => solana.transfer_breach_tokens(&wallet, amount as u64).await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            tracing::warn!("Royalty of {} to creator {} failed: {}", amount, creator_id, e);
+            self.reward
+                .record_failure_or_warn(creator_id, amount as u64, RewardReason::Royalty, &e)
+                .await;
+        }
+    }
+
+    async fn wallet_address(&self, player_id: Uuid) -> ApiResult<String> {
+        sqlx::query_scalar!("SELECT wallet_address FROM players WHERE id = $1", player_id)
+            .fetch_optional(&self.db.pg)
+            .await?
+            .ok_or(AppError::PlayerNotFound)
+    }
+
+    /// Mark a listing sold, move the Titan (every Titan of a bundle) and write the transaction + price history rows
+    async fn record_sale(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        listing: &MarketplaceListing,
+        buyer_id: Uuid,
+        price: i64,
+        transaction_type: TransactionType,
+        tx_signature: Option<&str>,
+        royalties: &[SaleRoyalty],
+    ) -> ApiResult<MarketplaceTransaction> {
+        // Calculate fees; the creators' royalties come out of the seller's share
+        let fee = (price * PLATFORM_FEE_BPS) / 10000;
+        let royalty_amount = total_royalty(royalties);
+        let seller_receives = price - fee - royalty_amount;
+        let creators: HashSet<Uuid> = royalties.iter().map(|royalty| royalty.creator_id).collect();
+        let royalty_recipient_id = match creators.len() {
+            1 => creators.into_iter().next(),
+            _ => None,
+        };
+
+        // Update listing status
+        sqlx::query!(
+            r#"
+            UPDATE marketplace_listings
+            SET status = 'sold', sold_at = NOW(), buyer_id = $1, final_price = $2
+            WHERE id = $3
+            "#,
+            buyer_id,
+            price,
+            listing.id
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        // Transfer Titan ownership
+        sqlx::query!(
+            "UPDATE player_titans SET player_id = $1 WHERE id = $2",
+            buyer_id,
+            listing.titan_id
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        // The rest of a bundle goes with it, under the one transaction
+        if listing.listing_type == ListingType::Bundle {
+            sqlx::query!(
+                r#"
+                UPDATE player_titans SET player_id = $1
+                WHERE id IN (SELECT titan_id FROM listing_bundle_titans WHERE listing_id = $2)
+                "#,
+                buyer_id,
+                listing.id
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        // Create transaction record
+        let transaction = sqlx::query_as!(
+            MarketplaceTransaction,
+            r#"
+            INSERT INTO marketplace_transactions
+            (listing_id, seller_id, buyer_id, titan_id, transaction_type, price, fee, seller_receives, tx_signature,
+             royalty_recipient_id, royalty_amount)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            RETURNING id, listing_id, seller_id, buyer_id, titan_id,
+                      transaction_type as "transaction_type: TransactionType", price, fee, seller_receives,
+                      tx_signature, created_at, royalty_recipient_id, royalty_amount
+            "#,
+            listing.id,
+            listing.seller_id,
+            buyer_id,
+            listing.titan_id,
+            transaction_type as TransactionType,
+            price,
+            fee,
+            seller_receives,
+            tx_signature,
+            royalty_recipient_id,
+            royalty_amount
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        // Each Titan's share of the royalty
+        for royalty in royalties {
+            sqlx::query!(
+                r#"
+                INSERT INTO marketplace_royalties (transaction_id, titan_id, creator_id, amount)
+                VALUES ($1, $2, $3, $4)
+                "#,
+                transaction.id,
+                royalty.titan_id,
+                royalty.creator_id,
+                royalty.amount
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        // Record price history; a bundle's price isn't any one Titan's
+        if listing.listing_type == ListingType::Bundle {
+            return Ok(transaction);
+        }
+        sqlx::query!(
+            r#"
+            INSERT INTO price_history (element, threat_class, species_id, price, transaction_type)
+            SELECT pt.element, pt.threat_class, pt.species_id, $1, $3
+            FROM player_titans pt WHERE pt.id = $2
+            "#,
+            price,
+            listing.titan_id,
+            transaction_type as TransactionType
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(transaction)
+    }
+
+    // ============================================
+    // Activity Feed
+    // ============================================
+
+    /// Recent marketplace activity, newest first
+    pub async fn get_activity(&self, query: MarketplaceActivityQuery) -> ApiResult<MarketplaceActivityPage> {
+        let limit = query.limit.clamp(1, MAX_ACTIVITY_PAGE);
+
+        // (created_at, id) orders entries logged in the same instant
+        let activities = sqlx::query_as!(
+            MarketplaceActivity,
+            r#"
+            SELECT id, activity_type as "activity_type: MarketplaceActivityType", listing_id, titan_id,
+                   price, player_username, created_at
+            FROM marketplace_activity_log
+            WHERE $1::UUID IS NULL
+               OR (created_at, id) < (SELECT created_at, id FROM marketplace_activity_log WHERE id = $1)
+            ORDER BY created_at DESC, id DESC
+            LIMIT $2
+            "#,
+            query.after_id,
+            limit
+        )
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        // A full page may have older activity behind it
+        let next_cursor = match activities.last() {
+            Some(oldest) if activities.len() as i64 >= limit => Some(oldest.id),
+            _ => None,
+        };
+
+        Ok(MarketplaceActivityPage { activities, next_cursor })
+    }
+
+    /// Append to the activity log; publish the entry once `tx` commits
+    async fn log_activity(
+        tx: &mut sqlx::Transaction<'_, Postgres>,
+        activity_type: MarketplaceActivityType,
+        listing_id: Uuid,
+        titan_id: Uuid,
+        price: i64,
+        player_id: Uuid,
+    ) -> ApiResult<MarketplaceActivity> {
+        let activity = sqlx::query_as!(
+            MarketplaceActivity,
+            r#"
+            INSERT INTO marketplace_activity_log
+            (activity_type, listing_id, titan_id, price, player_id, player_username)
+            VALUES ($1, $2, $3, $4, $5, (SELECT username FROM players WHERE id = $5))
+            RETURNING id, activity_type as "activity_type: MarketplaceActivityType", listing_id, titan_id,
+                      price, player_username, created_at
+            "#,
+            activity_type as MarketplaceActivityType,
+            listing_id,
+            titan_id,
+            price,
+            player_id
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(activity)
+    }
+
+    /// Log a completed sale under its buyer
+    async fn log_sale(
+        tx: &mut sqlx::Transaction<'_, Postgres>,
+        transaction: &MarketplaceTransaction,
+    ) -> ApiResult<MarketplaceActivity> {
+        Self::log_activity(
+            tx,
+            MarketplaceActivityType::SaleCompleted,
+            transaction.listing_id,
+            transaction.titan_id,
+            transaction.price,
+            transaction.buyer_id,
+        )
+        .await
+    }
+
+    /// Hand committed activity to the WebSocket feed
+    fn publish_activity(&self, activity: MarketplaceActivity) {
+        // Ignore send errors (no forwarder running)
+        let _ = self.live.send(activity);
+    }
+
+    // ============================================
+    // On-chain Escrow
+    // ============================================
+
+    /// Get the on-chain Titan ID for a player Titan
+    pub async fn titan_onchain_id(&self, titan_id: Uuid) -> ApiResult<u64> {
+        let onchain_id: Option<i64> = sqlx::query_scalar!(
+            "SELECT onchain_id FROM player_titans WHERE id = $1",
+            titan_id
+        )
+        .fetch_optional(&self.db.pg)
+        .await?
+        .flatten();
+
+        onchain_id
+            .map(|id| id as u64)
+            .ok_or_else(|| AppError::BadRequest("Titan not minted on-chain yet".into()))
+    }
+
+    /// Get an active escrow-backed listing
+    pub async fn get_escrow_listing(&self, listing_id: Uuid) -> ApiResult<MarketplaceListing> {
+        sqlx::query_as!(
+            MarketplaceListing,
+            r#"
+            SELECT id, seller_id, titan_id, listing_type as "listing_type: ListingType", price,
+                   min_price, status as "status: ListingStatus", created_at, expires_at,
+                   sold_at, cancelled_at, buyer_id, final_price, views, favorites,
+                   escrow_address, listing_tx_signature
+            FROM marketplace_listings
+            WHERE id = $1 AND status = 'active' AND escrow_address IS NOT NULL
+            "#,
+            listing_id
+        )
+        .fetch_optional(&self.db.pg)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Escrow listing not found or not active".into()))
+    }
+
+    /// Mirror an on-chain escrow listing into the marketplace tables.
+    ///
+    /// Returns `None` when the seller or Titan is unknown to the backend.
+    pub async fn sync_escrow_listing(
+        &self,
+        onchain: &OnchainListing,
+        tx_signature: Option<&str>,
+    ) -> ApiResult<Option<MarketplaceListing>> {
+        // Already mirrored
+        let existing = sqlx::query_as!(
+            MarketplaceListing,
+            r#"SELECT id, seller_id, titan_id, listing_type as "listing_type: ListingType", price, min_price, status as "status: ListingStatus", created_at, expires_at, sold_at, cancelled_at, buyer_id, final_price, views, favorites, escrow_address, listing_tx_signature FROM marketplace_listings WHERE escrow_address = $1 AND status = 'active'"#,
+            onchain.address
+        )
+        .fetch_optional(&self.db.pg)
+        .await?;
+
+        if existing.is_some() {
+            return Ok(existing);
+        }
+
+        let seller_id: Option<Uuid> = sqlx::query_scalar!(
+            "SELECT id FROM players WHERE wallet_address = $1",
+            onchain.seller
+        )
+        .fetch_optional(&self.db.pg)
+        .await?;
+
+        let titan_id: Option<Uuid> = sqlx::query_scalar!(
+            "SELECT id FROM player_titans WHERE onchain_id = $1",
+            onchain.titan_id as i64
+        )
+        .fetch_optional(&self.db.pg)
+        .await?;
+
+        let (Some(seller_id), Some(titan_id)) = (seller_id, titan_id) else {
+            tracing::warn!(
+                "Skipping escrow listing {}: unknown seller {} or titan #{}",
+                onchain.address, onchain.seller, onchain.titan_id
+            );
+            return Ok(None);
+        };
+
+        let expires_at = chrono::DateTime::from_timestamp(onchain.expires_at, 0)
+            .unwrap_or_else(Utc::now);
+
+        let mut tx = self.db.pg.begin().await?;
+
+        // The chain is the source of truth for ownership
+        sqlx::query!(
+            "UPDATE player_titans SET player_id = $1 WHERE id = $2",
+            seller_id,
+            titan_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // Retire any database-only listing for the same Titan
+        sqlx::query!(
+            r#"
+            UPDATE marketplace_listings
+            SET status = 'cancelled', cancelled_at = NOW()
+            WHERE titan_id = $1 AND status = 'active' AND escrow_address IS NULL
+            "#,
+            titan_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let listing = sqlx::query_as!(
+            MarketplaceListing,
+            r#"
+            INSERT INTO marketplace_listings
+            (seller_id, titan_id, listing_type, price, expires_at, escrow_address, listing_tx_signature)
+            VALUES ($1, $2, 'fixed_price', $3, $4, $5, $6)
+            RETURNING id, seller_id, titan_id, listing_type as "listing_type: ListingType", price,
+                      min_price, status as "status: ListingStatus", created_at, expires_at,
+                      sold_at, cancelled_at, buyer_id, final_price, views, favorites,
+                      escrow_address, listing_tx_signature
+            "#,
+            seller_id,
+            titan_id,
+            onchain.price as i64,
+            expires_at,
+            onchain.address,
+            tx_signature
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let activity = Self::log_activity(
+            &mut tx,
+            MarketplaceActivityType::ListingCreated,
+            listing.id,
+            listing.titan_id,
+            listing.price,
+            seller_id,
+        )
+        .await?;
+
+        tx.commit().await?;
+        self.publish_activity(activity);
+
+        Ok(Some(listing))
+    }
+
+    /// Settle an escrow listing whose on-chain account has been closed.
+    ///
+    /// `new_owner_wallet` is the Titan owner after settlement; if it is a
+    /// different player than the seller the listing is recorded as sold,
+    /// otherwise it was cancelled. Returns true if it was recorded as a sale.
+    pub async fn settle_escrow_listing(
+        &self,
+        listing: &MarketplaceListing,
+        new_owner_wallet: Option<&str>,
+        tx_signature: Option<&str>,
+    ) -> ApiResult<bool> {
+        let buyer_id: Option<Uuid> = match new_owner_wallet {
+            Some(wallet) => sqlx::query_scalar!("SELECT id FROM players WHERE wallet_address = $1", wallet)
+                .fetch_optional(&self.db.pg)
+                .await?,
+            None => None,
+        };
+
+        let mut tx = self.db.pg.begin().await?;
+
+        // Lock and re-check, another path may have settled it already
+        let locked = sqlx::query_as!(
+            MarketplaceListing,
+            r#"SELECT id, seller_id, titan_id, listing_type as "listing_type: ListingType", price, min_price, status as "status: ListingStatus", created_at, expires_at, sold_at, cancelled_at, buyer_id, final_price, views, favorites, escrow_address, listing_tx_signature FROM marketplace_listings WHERE id = $1 AND status = 'active' FOR UPDATE"#,
+            listing.id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(locked) = locked else {
+            return Ok(false);
+        };
+
+        let (sold, activity) = match buyer_id {
+            Some(buyer_id) if buyer_id != locked.seller_id => {
+                // The escrow program paid the seller, there's no royalty to take
+                let transaction = Self::record_sale(&mut tx, &locked, buyer_id, locked.price, TransactionType::Purchase, tx_signature, &[]).await?;
+                self.credit_guild_fee_share(&mut tx, &transaction).await?;
+                (true, Self::log_sale(&mut tx, &transaction).await?)
+            }
+            _ => {
+                sqlx::query!(
+                    "UPDATE marketplace_listings SET status = 'cancelled', cancelled_at = NOW() WHERE id = $1",
+                    locked.id
+                )
+                .execute(&mut *tx)
+                .await?;
+                let activity = Self::log_activity(
+                    &mut tx,
+                    MarketplaceActivityType::ListingCancelled,
+                    locked.id,
+                    locked.titan_id,
+                    locked.price,
+                    locked.seller_id,
+                )
+                .await?;
+                (false, activity)
+            }
+        };
+
+        tx.commit().await?;
+        self.publish_activity(activity);
+
+        Ok(sold)
+    }
+
+    /// Reconcile on-chain escrow listings with the marketplace tables
+    pub async fn reconcile_escrow_listings(
+        &self,
+        solana: &SolanaService,
+    ) -> ApiResult<EscrowReconcileSummary> {
+        let mut summary = EscrowReconcileSummary::default();
+
+        let onchain = solana.get_onchain_listings().await?;
+        let open: HashSet<&str> = onchain.iter().map(|l| l.address.as_str()).collect();
+
+        // Import listings created directly on-chain
+        for listing in &onchain {
+            let known: bool = sqlx::query_scalar!(
+                r#"SELECT EXISTS(SELECT 1 FROM marketplace_listings WHERE escrow_address = $1 AND status = 'active') as "exists!""#,
+                listing.address
+            )
+            .fetch_one(&self.db.pg)
+            .await?;
+
+            if !known && self.sync_escrow_listing(listing, None).await?.is_some() {
+                summary.imported += 1;
+            }
+        }
+
+        // Settle listings whose escrow account has been closed
+        let active = sqlx::query_as!(
+            MarketplaceListing,
+            r#"SELECT id, seller_id, titan_id, listing_type as "listing_type: ListingType", price, min_price, status as "status: ListingStatus", created_at, expires_at, sold_at, cancelled_at, buyer_id, final_price, views, favorites, escrow_address, listing_tx_signature FROM marketplace_listings WHERE status = 'active' AND escrow_address IS NOT NULL"#
+        )
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        for listing in active {
+            let Some(address) = listing.escrow_address.as_deref() else {
+                continue;
+            };
+            if open.contains(address) {
+                continue;
+            }
+
+            let onchain_id = self.titan_onchain_id(listing.titan_id).await?;
+            let owner = solana.get_titan_owner(onchain_id).await?.map(|p| p.to_string());
+
+            if self.settle_escrow_listing(&listing, owner.as_deref(), None).await? {
+                summary.sold += 1;
+            } else {
+                summary.cancelled += 1;
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Helper: Create notification
+    async fn create_notification(
+        &self,
+        player_id: Uuid,
+        notification_type: NotificationType,
+        title: &str,
+        message: &str,
+        data: Option<serde_json::Value>,
+    ) -> ApiResult<()> {
+        self.notification
+            .create(player_id, notification_type, title, message, data, None)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_funds() {
+        assert!(check_funds(1_000, 1_000).is_ok());
+        assert!(matches!(
+            check_funds(0, 1_000),
+            Err(AppError::InsufficientFunds { required: 1_000, balance: 0 })
+        ));
+
+        // 2.5% to the treasury
+        assert_eq!(sale_split(1_000), (25, 975));
+    }
+
+    #[test]
+    fn test_listing_titans() {
+        let titans: Vec<Uuid> = (0..6).map(|_| Uuid::new_v4()).collect();
+        let request = |listing_type, titan_id: Option<Uuid>, titan_ids: &[Uuid]| CreateListingRequest {
+            titan_id,
+            titan_ids: titan_ids.to_vec(),
+            listing_type,
+            price: 1_000,
+            min_price: None,
+            duration_hours: 72,
+        };
+
+        let single = request(ListingType::FixedPrice, Some(titans[0]), &[]);
+        assert_eq!(listing_titans(&single).unwrap(), vec![titans[0]]);
+        assert!(listing_titans(&request(ListingType::FixedPrice, None, &titans[..2])).is_err());
+
+        let bundle = request(ListingType::Bundle, None, &titans[..5]);
+        assert_eq!(listing_titans(&bundle).unwrap(), titans[..5]);
+        // 2 to 5 distinct Titans
+        assert!(listing_titans(&request(ListingType::Bundle, None, &titans[..1])).is_err());
+        assert!(listing_titans(&request(ListingType::Bundle, None, &titans)).is_err());
+        assert!(listing_titans(&request(ListingType::Bundle, None, &[titans[0], titans[0]])).is_err());
+        assert!(listing_titans(&request(ListingType::Bundle, Some(titans[0]), &titans[1..3])).is_err());
+    }
+
+    #[test]
+    fn test_median_price() {
+        assert_eq!(median_price(&mut []), None);
+        assert_eq!(median_price(&mut [700]), Some(700));
+        assert_eq!(median_price(&mut [900, 100, 500]), Some(500));
+        // Even counts take the midpoint, rounded down
+        assert_eq!(median_price(&mut [400, 100, 301, 10_000]), Some(350));
+    }
+
+    #[test]
+    fn test_royalty_amount() {
+        // 5% of a 10_000 resale, on top of the 2.5% fee
+        assert_eq!(royalty_amount(10_000, 500), 500);
+        let (fee, seller_receives) = sale_split(10_000);
+        assert_eq!((fee, seller_receives - royalty_amount(10_000, 500)), (250, 9_250));
+
+        // Rounds down
+        assert_eq!(royalty_amount(19, 500), 0);
+        assert_eq!(royalty_amount(10_000, 0), 0);
+    }
+
+    #[test]
+    fn test_sale_royalties() {
+        let seller = Uuid::new_v4();
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let creator = Uuid::new_v4();
+
+        // A single Titan pays on the whole price
+        let single = sale_royalties(10_000, seller, &[(a, creator)], 500);
+        assert_eq!(single, vec![SaleRoyalty { titan_id: a, creator_id: creator, amount: 500 }]);
+
+        // A bundle splits the price, the first Titans taking the remainder,
+        // and skips Titans the seller created
+        let bundle = sale_royalties(30_002, seller, &[(a, creator), (b, creator), (c, seller)], 1000);
+        assert_eq!(
+            bundle.iter().map(|r| (r.titan_id, r.amount)).collect::<Vec<_>>(),
+            vec![(a, 1_000), (b, 1_000)]
+        );
+
+        // Zero royalties aren't owed
+        assert!(sale_royalties(19, seller, &[(a, creator)], 500).is_empty());
+        assert!(sale_royalties(10_000, seller, &[], 500).is_empty());
+    }
+
+    #[test]
+    fn test_guild_fee_share() {
+        // 20% of the 2.5% fee on a 10_000 sale
+        let (fee, _) = sale_split(10_000);
+        assert_eq!(guild_fee_share(fee, 2000), 50);
+
+        // Rounds down, never more than the fee
+        assert_eq!(guild_fee_share(25, 2000), 5);
+        assert_eq!(guild_fee_share(3, 2000), 0);
+        assert_eq!(guild_fee_share(fee, 10_000), fee);
+        assert_eq!(guild_fee_share(fee, 0), 0);
+    }
+
+    #[test]
+    fn test_late_bid_extends_auction() {
+        let now = Utc::now();
+        let expires_at = now + Duration::seconds(30);
+        let window = Duration::minutes(5);
+        let cap = Duration::hours(1);
+
+        // Bid at T-30s: five minutes remain afterwards
+        assert_eq!(
+            extended_auction_expiry(expires_at, expires_at, now, window, window, cap),
+            Some(now + window)
+        );
+
+        // Bid with ten minutes left: no change
+        let expires_at = now + Duration::minutes(10);
+        assert_eq!(extended_auction_expiry(expires_at, expires_at, now, window, window, cap), None);
+    }
+
+    #[test]
+    fn test_last_second_bid_with_short_extension() {
+        let now = Utc::now();
+        let window = Duration::seconds(30);
+        let extension = Duration::seconds(30);
+        let cap = Duration::hours(1);
+
+        // Bid at T-1s: 30 seconds remain afterwards
+        let expires_at = now + Duration::seconds(1);
+        assert_eq!(
+            extended_auction_expiry(expires_at, expires_at, now, window, extension, cap),
+            Some(now + extension)
+        );
+
+        // Bid at T-45s is outside the window
+        let expires_at = now + Duration::seconds(45);
+        assert_eq!(extended_auction_expiry(expires_at, expires_at, now, window, extension, cap), None);
+
+        // A shorter extension than the time left never pulls the end in
+        let expires_at = now + Duration::seconds(20);
+        assert_eq!(
+            extended_auction_expiry(expires_at, expires_at, now, window, Duration::seconds(10), cap),
+            None
+        );
+    }
+
+    #[test]
+    fn test_extension_cap() {
+        let now = Utc::now();
+        let window = Duration::minutes(5);
+        let cap = Duration::hours(1);
+
+        // 58 minutes of extensions so far: only two more minutes allowed
+        let original = now - Duration::minutes(58) + Duration::seconds(30);
+        let expires_at = now + Duration::seconds(30);
+        assert_eq!(
+            extended_auction_expiry(expires_at, original, now, window, window, cap),
+            Some(original + cap)
+        );
+
+        // Already at the cap
+        let expires_at = original + cap;
+        assert_eq!(extended_auction_expiry(expires_at, original, now, window, window, cap), None);
+    }
+
+    #[test]
+    fn test_bid_increment() {
+        assert_eq!(bid_increment(1_000, 500), 50);
+        assert_eq!(bid_increment(1_030, 500), 51);
+        // Never less than 1
+        assert_eq!(bid_increment(10, 500), 1);
+        assert_eq!(bid_increment(1_000, 0), 1);
+    }
+
+    #[test]
+    fn test_proxy_defends_lead() {
+        // Leader's proxy up to 2_000, highest bid 1_000: a 1_500 bid is
+        // answered one step (75) higher
+        let outcome = resolve_proxy_bid(1_500, 1_500, Some(1_000), Some(2_000), 500);
+        assert_eq!(
+            outcome,
+            ProxyOutcome { challenger_bid: 1_500, leader_bid: Some(1_575), challenger_leads: false }
+        );
+
+        // Capped at the leader's maximum, which keeps a tie
+        let outcome = resolve_proxy_bid(1_050, 2_000, Some(1_000), Some(2_000), 500);
+        assert_eq!(
+            outcome,
+            ProxyOutcome { challenger_bid: 2_000, leader_bid: Some(2_000), challenger_leads: false }
+        );
+    }
+
+    #[test]
+    fn test_proxy_exceeded() {
+        // A proxy up to 3_000 beats a proxy up to 2_000 by one step over it
+        let outcome = resolve_proxy_bid(1_050, 3_000, Some(1_000), Some(2_000), 500);
+        assert_eq!(
+            outcome,
+            ProxyOutcome { challenger_bid: 2_100, leader_bid: Some(2_000), challenger_leads: true }
+        );
+
+        // Just past it: the challenger's maximum is all it takes
+        let outcome = resolve_proxy_bid(1_050, 2_001, Some(1_000), Some(2_000), 500);
+        assert_eq!(outcome.challenger_bid, 2_001);
+        assert!(outcome.challenger_leads);
+
+        // A plain bid is placed as is; a proxy already at its maximum doesn't bid again
+        let outcome = resolve_proxy_bid(2_500, 2_500, Some(2_000), Some(2_000), 500);
+        assert_eq!(
+            outcome,
+            ProxyOutcome { challenger_bid: 2_500, leader_bid: None, challenger_leads: true }
+        );
+    }
+
+    #[test]
+    fn test_no_proxy_opening_bid() {
+        let outcome = resolve_proxy_bid(1_050, 5_000, Some(1_000), None, 500);
+        assert_eq!(
+            outcome,
+            ProxyOutcome { challenger_bid: 1_050, leader_bid: None, challenger_leads: true }
+        );
+    }
 }
@@ -0,0 +1,167 @@
+//! Capture mint queue
+//!
+//! Collects Titan mints from concurrent capture confirmations and sends them
+//! through `mint_titan_nft_batch`, so a capture rush becomes a few batch
+//! transactions instead of many single mints racing for the next `titan_id`.
+
+use std::time::Duration;
+
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+
+use crate::error::{ApiResult, AppError};
+use crate::services::solana::{MintParams, MintResult, SolanaService, MAX_MINT_BATCH};
+
+/// Mints waiting for the flusher
+const QUEUE_CAPACITY: usize = 256;
+
+struct QueuedMint {
+    params: MintParams,
+    reply: oneshot::Sender<ApiResult<MintResult>>,
+}
+
+/// Handle to the background flusher
+#[derive(Clone)]
+pub struct MintQueue {
+    tx: mpsc::Sender<QueuedMint>,
+}
+
+impl MintQueue {
+    /// Start the flusher; a batch is sent when it is full or `window` after
+    /// its first mint arrived. Must be called inside a Tokio runtime.
+    pub fn spawn(solana: SolanaService, window: Duration) -> Self {
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        tokio::spawn(run(solana, rx, window));
+        Self { tx }
+    }
+
+    /// Queue a mint and wait for its batch to land
+    pub async fn mint(&self, params: MintParams) -> ApiResult<MintResult> {
+        let (reply, result) = oneshot::channel();
+        self.tx
+            .send(QueuedMint { params, reply })
+            .await
+            .map_err(|_| AppError::Internal(anyhow::anyhow!("Mint queue stopped")))?;
+
+        result
+            .await
+            .map_err(|_| AppError::Internal(anyhow::anyhow!("Mint queue dropped the request")))?
+    }
+}
+
+async fn run(solana: SolanaService, mut rx: mpsc::Receiver<QueuedMint>, window: Duration) {
+    while let Some(first) = rx.recv().await {
+        let batch = collect_batch(first, &mut rx, window).await;
+        flush(&solana, batch).await;
+    }
+}
+
+/// Gather mints until the batch is full or the window closes
+async fn collect_batch(
+    first: QueuedMint,
+    rx: &mut mpsc::Receiver<QueuedMint>,
+    window: Duration,
+) -> Vec<QueuedMint> {
+    let deadline = Instant::now() + window;
+    let mut batch = vec![first];
+
+    while batch.len() < MAX_MINT_BATCH {
+        match tokio::time::timeout_at(deadline, rx.recv()).await {
+            Ok(Some(mint)) => batch.push(mint),
+            // Window closed, or every sender is gone
+            Ok(None) | Err(_) => break,
+        }
+    }
+
+    batch
+}
+
+async fn flush(solana: &SolanaService, batch: Vec<QueuedMint>) {
+    let (params, replies): (Vec<_>, Vec<_>) =
+        batch.into_iter().map(|m| (m.params, m.reply)).unzip();
+
+    // A lone mint keeps using the single-mint instruction
+    if let [single] = params.as_slice() {
+        let result = solana
+            .mint_titan_nft(
+                &single.player_wallet,
+                single.element,
+                single.threat_class,
+                single.species_id,
+                single.genes,
+            )
+            .await;
+        if let Some(reply) = replies.into_iter().next() {
+            let _ = reply.send(result);
+        }
+        return;
+    }
+
+    match solana.mint_titan_nft_batch(params).await {
+        Ok(results) => {
+            for (reply, result) in replies.into_iter().zip(results) {
+                let _ = reply.send(Ok(result));
+            }
+        }
+        Err(e) => {
+            // The batch is atomic: nothing was minted for anyone
+            tracing::error!("Batch mint failed: {}", e);
+            let message = e.to_string();
+            for reply in replies {
+                let _ = reply.send(Err(AppError::Internal(anyhow::anyhow!(
+                    "Batch mint failed: {}",
+                    message
+                ))));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Element;
+
+    fn queued() -> (QueuedMint, oneshot::Receiver<ApiResult<MintResult>>) {
+        let (reply, rx) = oneshot::channel();
+        let params = MintParams {
+            player_wallet: "11111111111111111111111111111111".into(),
+            element: Element::Storm,
+            threat_class: 1,
+            species_id: 1,
+            genes: [0; 32],
+        };
+        (QueuedMint { params, reply }, rx)
+    }
+
+    #[tokio::test]
+    async fn test_batch_closes_when_full() {
+        let (tx, mut rx) = mpsc::channel(16);
+        for _ in 0..MAX_MINT_BATCH + 2 {
+            tx.send(queued().0).await.unwrap();
+        }
+
+        let first = rx.recv().await.unwrap();
+        let batch = collect_batch(first, &mut rx, Duration::from_secs(60)).await;
+        assert_eq!(batch.len(), MAX_MINT_BATCH);
+    }
+
+    #[tokio::test]
+    async fn test_batch_closes_after_window() {
+        let (tx, mut rx) = mpsc::channel(16);
+        tx.send(queued().0).await.unwrap();
+        tx.send(queued().0).await.unwrap();
+
+        let late = tx.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            late.send(queued().0).await.unwrap();
+        });
+
+        let first = rx.recv().await.unwrap();
+        let batch = collect_batch(first, &mut rx, Duration::from_millis(200)).await;
+        assert_eq!(batch.len(), 2);
+        // The late mint starts the next batch
+        assert!(rx.recv().await.is_some());
+    }
+}
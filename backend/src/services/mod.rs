@@ -3,43 +3,64 @@
 pub mod auth;
 mod achievement;
 mod battle;
+pub mod battle_math;
 mod capture;
 mod chat;
+mod event_spawn;
+mod fee_budget;
 mod friend;
+pub mod fusion;
+mod game_config;
 mod guild;
 mod inventory;
 mod leaderboard;
 mod location;
 mod map;
 mod marketplace;
+mod mint_queue;
 mod notification;
 mod player;
+mod presence;
 mod pvp;
 mod quest;
+mod reward;
 pub mod solana;
 mod spawn;
+mod transaction;
 
 pub use achievement::AchievementService;
 pub use auth::AuthService;
 pub use battle::BattleService;
-pub use capture::CaptureService;
+pub use capture::{CaptureService, CooperativeConfirm};
 pub use chat::ChatService;
-pub use friend::FriendService;
+pub use event_spawn::EventSpawnService;
+pub use fee_budget::FeeBudgetService;
+pub use friend::{coarse_geohash, FriendService};
+pub use game_config::{ConfigChangeSource, GameConfigService, GameConfigUpdate};
 pub use guild::GuildService;
 pub use inventory::InventoryService;
 pub use leaderboard::LeaderboardService;
 pub use location::LocationService;
 pub use map::MapService;
 pub use marketplace::MarketplaceService;
+pub use mint_queue::MintQueue;
 pub use notification::NotificationService;
 pub use player::PlayerService;
-pub use pvp::PvpService;
+pub use presence::PresenceService;
+pub use pvp::{PvpService, SeasonFinalization};
 pub use quest::QuestService;
+pub use reward::{RewardRetrySummary, RewardService, RewardTransfer};
 pub use solana::SolanaService;
 pub use spawn::SpawnService;
+pub use transaction::{TransactionPollSummary, TransactionService};
 
-use crate::config::AppConfig;
+use std::sync::Arc;
+
+use solana_sdk::signature::Keypair;
+
+use crate::config::{AppConfig, SharedGameConfig};
 use crate::db::Database;
+use crate::metrics::Metrics;
 
 /// Container for all services
 #[derive(Clone)]
@@ -49,23 +70,33 @@ pub struct Services {
     pub battle: BattleService,
     pub capture: CaptureService,
     pub chat: ChatService,
+    pub event_spawn: EventSpawnService,
+    pub fee_budget: FeeBudgetService,
     pub friend: FriendService,
+    /// Live `game` settings, changed by admins and reloads
+    pub game_config: GameConfigService,
     pub guild: GuildService,
     pub inventory: InventoryService,
     pub leaderboard: LeaderboardService,
     pub location: LocationService,
     pub map: MapService,
     pub marketplace: MarketplaceService,
+    /// Batches capture mints when `solana.mint_batch_window_ms` is set
+    pub mint_queue: Option<MintQueue>,
     pub notification: NotificationService,
     pub player: PlayerService,
+    pub presence: PresenceService,
     pub pvp: PvpService,
     pub quest: QuestService,
+    /// Failed $BREACH reward transfers owed to players
+    pub reward: RewardService,
     pub solana: Option<SolanaService>,
     pub spawn: SpawnService,
+    pub transaction: TransactionService,
 }
 
 impl Services {
-    pub fn new(config: &AppConfig, db: Database) -> Self {
+    pub fn new(config: &AppConfig, game: SharedGameConfig, db: Database, metrics: Metrics) -> Self {
         // Try to create Solana service, log warning if it fails
         let solana = match SolanaService::new(&config.solana) {
             Ok(svc) => {
@@ -75,32 +106,96 @@ impl Services {
             Err(e) => {
                 tracing::warn!("⚠️ Solana service not available: {}. Blockchain features disabled.", e);
                 // Fall back to service without keypair for basic RPC operations
-                match SolanaService::new_without_keypair(&config.solana) {
-                    Ok(svc) => Some(svc),
-                    Err(_) => None,
-                }
+                SolanaService::new_without_keypair(&config.solana).ok()
             }
+        }
+        .map(|svc| svc.with_metrics(metrics.clone()));
+
+        let mint_queue = match &solana {
+            Some(svc) if config.solana.mint_batch_window_ms > 0 => Some(MintQueue::spawn(
+                svc.clone(),
+                std::time::Duration::from_millis(config.solana.mint_batch_window_ms),
+            )),
+            _ => None,
         };
 
+        Self {
+            mint_queue,
+            ..Self::with_solana(config, game, db, metrics, solana)
+        }
+    }
+
+    /// Services with blockchain features disabled (integration tests, offline sandbox)
+    pub fn sandbox(config: &AppConfig, game: SharedGameConfig, db: Database, metrics: Metrics) -> Self {
+        Self::with_solana(config, game, db, metrics, None)
+    }
+
+    fn with_solana(
+        config: &AppConfig,
+        game: SharedGameConfig,
+        db: Database,
+        metrics: Metrics,
+        solana: Option<SolanaService>,
+    ) -> Self {
+        let notification = NotificationService::new(db.clone());
+        let quest = QuestService::new(db.clone());
+        let presence = PresenceService::new(config, db.clone());
+        let spawn = SpawnService::new(game.clone(), db.clone());
+        let location = LocationService::new(game.clone(), db.clone(), quest.clone());
+        let player = PlayerService::new(db.clone());
+        // Capture challenges are signed with the backend keypair; without
+        // Solana a per-process key still proves the challenge came from here
+        let challenge_signer = solana
+            .as_ref()
+            .map(SolanaService::backend_keypair)
+            .unwrap_or_else(|| Arc::new(Keypair::new()));
+        let guild = GuildService::new(db.clone(), notification.clone());
+        let capture = CaptureService::new(
+            config.clone(),
+            game.clone(),
+            db.clone(),
+            location.clone(),
+            quest.clone(),
+            guild.clone(),
+            challenge_signer,
+        );
+        let reward = RewardService::new(db.clone());
+        let marketplace = MarketplaceService::new(
+            config.clone(),
+            game.clone(),
+            db.clone(),
+            solana.clone(),
+            notification.clone(),
+            reward.clone(),
+        );
+        let transaction = TransactionService::new(db.clone(), capture.clone(), marketplace.clone());
+
         Self {
             auth: AuthService::new(config.clone()),
             achievement: AchievementService::new(db.clone()),
-            battle: BattleService::new(db.clone()),
-            capture: CaptureService::new(config.clone(), db.clone()),
-            chat: ChatService::new(db.clone()),
-            friend: FriendService::new(db.clone()),
-            guild: GuildService::new(db.clone()),
+            battle: BattleService::new(db.clone(), quest.clone()),
+            capture,
+            chat: ChatService::new(db.clone(), presence.clone(), player.clone()),
+            event_spawn: EventSpawnService::new(db.clone(), spawn.clone(), notification.clone()),
+            fee_budget: FeeBudgetService::new(config.clone(), db.clone()),
+            friend: FriendService::new(game.clone(), db.clone(), presence.clone(), notification.clone()),
+            game_config: GameConfigService::new(config.clone(), game.clone(), db.clone()),
+            guild,
             inventory: InventoryService::new(db.clone()),
             leaderboard: LeaderboardService::new(db.clone()),
-            location: LocationService::new(config.clone(), db.clone()),
+            location,
             map: MapService::new(db.clone()),
-            marketplace: MarketplaceService::new(db.clone()),
-            notification: NotificationService::new(db.clone()),
-            player: PlayerService::new(db.clone()),
-            pvp: PvpService::new(db.clone()),
-            quest: QuestService::new(db.clone()),
+            marketplace,
+            mint_queue: None,
+            notification: notification.clone(),
+            player,
+            presence,
+            pvp: PvpService::new(game.clone(), db.clone(), notification, quest.clone(), reward.clone(), metrics),
+            quest,
+            reward,
             solana,
-            spawn: SpawnService::new(config.clone(), db.clone()),
+            spawn,
+            transaction,
         }
     }
 }
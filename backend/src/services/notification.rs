@@ -1,20 +1,32 @@
 //! Notification service
 
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
 use crate::db::Database;
 use crate::error::ApiResult;
 use crate::models::{Notification, NotificationCount, NotificationType};
 
+/// Created notifications waiting to be pushed to online players
+const LIVE_CHANNEL_CAPACITY: usize = 256;
+
 /// Notification service
 #[derive(Clone)]
 pub struct NotificationService {
     db: Database,
+    /// Every notification created on this instance, for the WebSocket push
+    live: broadcast::Sender<Notification>,
 }
 
 impl NotificationService {
     pub fn new(db: Database) -> Self {
-        Self { db }
+        let (live, _) = broadcast::channel(LIVE_CHANNEL_CAPACITY);
+        Self { db, live }
+    }
+
+    /// Notifications as they are created
+    pub fn subscribe(&self) -> broadcast::Receiver<Notification> {
+        self.live.subscribe()
     }
 
     /// Get notifications for a player
@@ -91,7 +103,7 @@ impl NotificationService {
     pub async fn mark_read(&self, player_id: Uuid, notification_id: Uuid) -> ApiResult<()> {
         sqlx::query(
             r#"
-            UPDATE notifications SET is_read = true
+            UPDATE notifications SET is_read = true, read_at = COALESCE(read_at, NOW())
             WHERE id = $1 AND player_id = $2
             "#,
         )
@@ -107,7 +119,7 @@ impl NotificationService {
     pub async fn mark_all_read(&self, player_id: Uuid) -> ApiResult<i64> {
         let result = sqlx::query(
             r#"
-            UPDATE notifications SET is_read = true
+            UPDATE notifications SET is_read = true, read_at = NOW()
             WHERE player_id = $1 AND is_read = false
             "#,
         )
@@ -147,7 +159,8 @@ impl NotificationService {
         Ok(result.rows_affected() as i64)
     }
 
-    /// Create notification (helper for other services)
+    /// Create notification (helper for other services); online players also
+    /// get it over their WebSocket
     pub async fn create(
         &self,
         player_id: Uuid,
@@ -177,6 +190,9 @@ impl NotificationService {
         .fetch_one(&self.db.pg)
         .await?;
 
+        // No receivers just means nothing is forwarding (tests, scripts)
+        let _ = self.live.send(notification.clone());
+
         Ok(notification)
     }
 
@@ -6,6 +6,20 @@ use crate::db::Database;
 use crate::error::{ApiResult, AppError};
 use crate::models::{CreatePlayer, Player, PlayerStats, UpdatePlayer};
 
+/// How long a player's ban and shadow-ban flags are cached in Redis
+const FLAG_CACHE_SECS: u64 = 300;
+
+/// Redis cache of a player's shadow-ban flag: `shadow_banned:{player_id}`
+/// holding `1` or `0`
+fn shadow_ban_key(player_id: Uuid) -> String {
+    format!("shadow_banned:{}", player_id)
+}
+
+/// Redis cache of a player's ban flag: `banned:{player_id}` holding `1` or `0`
+fn ban_key(player_id: Uuid) -> String {
+    format!("banned:{}", player_id)
+}
+
 /// Player service
 #[derive(Clone)]
 pub struct PlayerService {
@@ -194,9 +208,21 @@ impl PlayerService {
         Ok(players)
     }
 
-    /// Ban a player
+    /// Ban a player. Their tokens stop working from the next request on.
     pub async fn ban_player(&self, player_id: Uuid, reason: &str) -> ApiResult<()> {
-        sqlx::query(
+        let mut tx = self.db.pg.begin().await?;
+        Self::ban_player_in(&mut tx, player_id, reason).await?;
+        tx.commit().await?;
+        self.cache_ban(player_id).await
+    }
+
+    /// Ban a player under `tx`. Call `cache_ban` once it has committed.
+    pub async fn ban_player_in(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        player_id: Uuid,
+        reason: &str,
+    ) -> ApiResult<()> {
+        let updated = sqlx::query(
             r#"
             UPDATE players 
             SET is_banned = true, ban_reason = $2, updated_at = NOW()
@@ -205,11 +231,102 @@ impl PlayerService {
         )
         .bind(player_id)
         .bind(reason)
-        .execute(&self.db.pg)
+        .execute(&mut **tx)
         .await?;
+        if updated.rows_affected() == 0 {
+            return Err(AppError::PlayerNotFound);
+        }
 
         tracing::warn!("Player {} banned: {}", player_id, reason);
 
         Ok(())
     }
+
+    /// Cache a committed ban so it applies from the next request on
+    pub async fn cache_ban(&self, player_id: Uuid) -> ApiResult<()> {
+        let mut conn = self.db.redis.clone();
+        redis::cmd("SET")
+            .arg(ban_key(player_id))
+            .arg("1")
+            .arg("EX")
+            .arg(FLAG_CACHE_SECS)
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Shadow-ban a player, or lift it. Their captures and rewards are
+    /// skipped from the next request on; the player isn't told.
+    pub async fn set_shadow_ban(&self, player_id: Uuid, banned: bool) -> ApiResult<()> {
+        let updated = sqlx::query(
+            r#"
+            UPDATE players SET shadow_banned = $2, updated_at = NOW() WHERE id = $1
+            "#,
+        )
+        .bind(player_id)
+        .bind(banned)
+        .execute(&self.db.pg)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Err(AppError::PlayerNotFound);
+        }
+
+        let mut conn = self.db.redis.clone();
+        redis::cmd("SET")
+            .arg(shadow_ban_key(player_id))
+            .arg(if banned { "1" } else { "0" })
+            .arg("EX")
+            .arg(FLAG_CACHE_SECS)
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+
+        tracing::warn!("Player {} shadow ban set to {}", player_id, banned);
+
+        Ok(())
+    }
+
+    /// Whether the player is banned, cached in Redis for 5 minutes. Falls
+    /// back to Postgres when Redis is unavailable.
+    pub async fn is_banned(&self, player_id: Uuid) -> ApiResult<bool> {
+        let query = "SELECT is_banned FROM players WHERE id = $1";
+        self.cached_flag(player_id, ban_key(player_id), query).await
+    }
+
+    /// Whether the player is shadow-banned, cached in Redis for 5 minutes.
+    /// Falls back to Postgres when Redis is unavailable.
+    pub async fn is_shadow_banned(&self, player_id: Uuid) -> ApiResult<bool> {
+        let query = "SELECT shadow_banned FROM players WHERE id = $1";
+        self.cached_flag(player_id, shadow_ban_key(player_id), query).await
+    }
+
+    /// A boolean `query` selects for the player, cached at `key`
+    async fn cached_flag(&self, player_id: Uuid, key: String, query: &'static str) -> ApiResult<bool> {
+        let mut conn = self.db.redis.clone();
+        match redis::cmd("GET").arg(&key).query_async::<_, Option<String>>(&mut conn).await {
+            Ok(Some(cached)) => return Ok(cached == "1"),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Cache {} unavailable: {}", key, e),
+        }
+
+        let flag = sqlx::query_scalar::<_, Option<bool>>(query)
+            .bind(player_id)
+            .fetch_optional(&self.db.pg)
+            .await?
+            .flatten()
+            .unwrap_or(false);
+
+        let cached: redis::RedisResult<()> = redis::cmd("SET")
+            .arg(&key)
+            .arg(if flag { "1" } else { "0" })
+            .arg("EX")
+            .arg(FLAG_CACHE_SECS)
+            .query_async(&mut conn)
+            .await;
+        if let Err(e) = cached {
+            tracing::warn!("Failed to cache {}: {}", key, e);
+        }
+
+        Ok(flag)
+    }
 }
@@ -0,0 +1,77 @@
+//! Player online presence in Redis, shared by every backend instance
+
+use std::collections::HashSet;
+
+use redis::AsyncCommands;
+use uuid::Uuid;
+
+use crate::config::AppConfig;
+use crate::db::Database;
+use crate::error::{ApiResult, AppError};
+
+/// Redis key prefix for presence: `presence:{player_id}`
+const PRESENCE_PREFIX: &str = "presence:";
+
+fn presence_key(player_id: Uuid) -> String {
+    format!("{}{}", PRESENCE_PREFIX, player_id)
+}
+
+/// Presence service. A player is online while their key exists; WebSocket
+/// connections write it and refresh it on heartbeats and location updates.
+#[derive(Clone)]
+pub struct PresenceService {
+    db: Database,
+    ttl_seconds: u64,
+}
+
+impl PresenceService {
+    pub fn new(config: &AppConfig, db: Database) -> Self {
+        Self {
+            db,
+            ttl_seconds: config.websocket.presence_ttl_seconds,
+        }
+    }
+
+    /// Mark a player online, or extend their presence
+    pub async fn set_online(&self, player_id: Uuid) -> ApiResult<()> {
+        let mut conn = self.db.redis.clone();
+        let _: () = conn
+            .set_ex(presence_key(player_id), "1", self.ttl_seconds)
+            .await
+            .map_err(|e| AppError::Internal(e.into()))?;
+        Ok(())
+    }
+
+    /// Mark a player offline
+    pub async fn set_offline(&self, player_id: Uuid) -> ApiResult<()> {
+        let mut conn = self.db.redis.clone();
+        let _: () = conn
+            .del(presence_key(player_id))
+            .await
+            .map_err(|e| AppError::Internal(e.into()))?;
+        Ok(())
+    }
+
+    /// Whether a player is online; false when Redis is unreachable
+    pub async fn is_online(&self, player_id: Uuid) -> bool {
+        let mut conn = self.db.redis.clone();
+        let result: Result<bool, _> = conn.exists(presence_key(player_id)).await;
+        result.unwrap_or(false)
+    }
+
+    /// The subset of `player_ids` that is online, in one Redis round trip
+    pub async fn get_online(&self, player_ids: &[Uuid]) -> HashSet<Uuid> {
+        if player_ids.is_empty() {
+            return HashSet::new();
+        }
+        let keys: Vec<String> = player_ids.iter().map(|&id| presence_key(id)).collect();
+        let mut conn = self.db.redis.clone();
+        let values: Vec<Option<String>> = conn.mget(&keys).await.unwrap_or_default();
+        player_ids
+            .iter()
+            .zip(values)
+            .filter(|(_, value)| value.is_some())
+            .map(|(id, _)| *id)
+            .collect()
+    }
+}
@@ -1,27 +1,67 @@
 //! PvP matchmaking and battle service
 
-use chrono::{Duration, Utc};
-use rand::{Rng, SeedableRng};
+use chrono::{DateTime, Duration, Utc};
+use rand::SeedableRng;
 use uuid::Uuid;
 
+use crate::config::SharedGameConfig;
 use crate::db::Database;
 use crate::error::{ApiResult, AppError};
+use crate::metrics::Metrics;
 use crate::models::{
-    ActionResultResponse, JoinQueueRequest, MatchHistoryEntry, MatchStateResponse,
-    PlayerPvpStats, PvpActionType, PvpLeaderboardEntry, PvpMatch,
+    ActionResultResponse, CancelledMatch, Element, FoundMatch, JoinQueueRequest, MatchFormat, MatchHistoryEntry,
+    MatchRounds, MatchStateResponse, MatchedPlayer, NotificationType, PlayerPvpStats, PvpActionKind, PvpActionType,
+    PvpChallenge, PvpChallengeRequest, PvpChallengeStatus, PvpLeaderboardEntry, PvpMatch,
     PvpMatchStatus, PvpStatsResponse, PvpSeason, QueueEntry, QueueStatus, QueueStatusResponse,
-    RankTier, SubmitActionRequest, TitanBattleInfo, TitanBattleStats,
+    QuestEvent, RankTier, RewardReason, SpectatorMatchState, SpectatorSide, SubmitActionRequest, TitanBattleInfo,
+    TurnTimeout, TurnTimeoutAction,
 };
 
+use super::battle_math::{self, Fighter, Move, TITAN_MAX_HP};
+use super::{NotificationService, QuestService, RewardService, RewardTransfer};
+
+/// Seconds a challenged player has to accept
+const CHALLENGE_TTL_SECONDS: i64 = 60;
+
+/// Players ranked, and eligible for rewards, when a season closes
+const SEASON_RANKED_PLAYERS: i64 = 100;
+
+/// Season rewards sent at once
+const SEASON_PAYOUT_BATCH: i64 = 20;
+
+/// Season finalization summary
+#[derive(Debug, Default)]
+pub struct SeasonFinalization {
+    /// Players given a final rank; 0 when the season was already closed
+    pub ranked: u64,
+    /// The season opened in its place, if it was the active one
+    pub next_season_id: Option<i32>,
+    pub paid: u32,
+    /// Rewards whose transfer failed, left to the failed reward retries
+    pub queued: u32,
+}
+
 /// PvP Service
 #[derive(Clone)]
 pub struct PvpService {
+    game: SharedGameConfig,
     db: Database,
+    notification: NotificationService,
+    quest: QuestService,
+    reward: RewardService,
+    metrics: Metrics,
 }
 
 impl PvpService {
-    pub fn new(db: Database) -> Self {
-        Self { db }
+    pub fn new(
+        game: SharedGameConfig,
+        db: Database,
+        notification: NotificationService,
+        quest: QuestService,
+        reward: RewardService,
+        metrics: Metrics,
+    ) -> Self {
+        Self { game, db, notification, quest, reward, metrics }
     }
 
     // ==========================================
@@ -30,8 +70,12 @@ impl PvpService {
 
     /// Get current active season
     pub async fn get_current_season(&self) -> ApiResult<PvpSeason> {
-        let season = sqlx::query_as::<_, PvpSeason>(
-            r#"SELECT * FROM pvp_seasons WHERE is_active = true LIMIT 1"#,
+        let season = sqlx::query_as!(
+            PvpSeason,
+            r#"
+            SELECT id, name, start_date, end_date, is_active, rewards, created_at
+            FROM pvp_seasons WHERE is_active = true LIMIT 1
+            "#
         )
         .fetch_optional(&self.db.pg)
         .await?
@@ -40,6 +84,189 @@ impl PvpService {
         Ok(season)
     }
 
+    /// Seasons that need finalizing: the active season once it has ended,
+    /// and closed seasons with rewards not yet sent
+    pub async fn seasons_to_finalize(&self) -> ApiResult<Vec<i32>> {
+        let seasons = sqlx::query_scalar!(
+            r#"
+            SELECT s.id FROM pvp_seasons s
+            WHERE (s.is_active AND s.closed_at IS NULL AND s.end_date < NOW())
+               OR EXISTS (
+                   SELECT 1 FROM pvp_season_rankings r
+                   WHERE r.season_id = s.id AND r.reward_amount > 0 AND r.paid_at IS NULL
+               )
+            ORDER BY s.id
+            "#
+        )
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        Ok(seasons)
+    }
+
+    /// Close an ended season and pay its rewards: take the final top 100 by
+    /// Elo with the reward each rank earns under `pvp_season_rewards`, open
+    /// the next season if this was the active one, then send the rewards in
+    /// batches. Safe to run again, and from several instances: a season is
+    /// only ranked once and each reward is only sent once.
+    pub async fn finalize_season(
+        &self,
+        season_id: i32,
+        transfer: &dyn RewardTransfer,
+    ) -> ApiResult<SeasonFinalization> {
+        let (ranked, next_season_id) = self.close_season(season_id).await?;
+        let (paid, queued) = self.pay_season_rewards(season_id, transfer).await?;
+
+        Ok(SeasonFinalization { ranked, next_season_id, paid, queued })
+    }
+
+    /// Take a season's final rankings and close it, returning the players
+    /// ranked and the season opened in its place
+    async fn close_season(&self, season_id: i32) -> ApiResult<(u64, Option<i32>)> {
+        let mut tx = self.db.pg.begin().await?;
+
+        let season = sqlx::query!(
+            "SELECT end_date, is_active, closed_at FROM pvp_seasons WHERE id = $1 FOR UPDATE",
+            season_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(AppError::NotFound("Season not found".into()))?;
+        if season.closed_at.is_some() {
+            return Ok((0, None));
+        }
+        if season.end_date > Utc::now() {
+            return Err(AppError::BadRequest("Season has not ended".into()));
+        }
+
+        // Ties go to the player with more wins, then to who got there first
+        let ranked = sqlx::query!(
+            r#"
+            INSERT INTO pvp_season_rankings
+                (season_id, player_id, rank, elo_rating, rank_tier, matches_played, matches_won,
+                 reward_amount)
+            SELECT $1, ranked.player_id, ranked.rank, ranked.elo_rating, ranked.rank_tier,
+                   ranked.matches_played, ranked.matches_won, COALESCE(tier.breach_amount, 0)
+            FROM (
+                SELECT player_id, elo_rating, rank_tier, matches_played, matches_won,
+                       ROW_NUMBER() OVER (
+                           ORDER BY elo_rating DESC, matches_won DESC, last_match_at ASC, player_id
+                       )::INT AS rank
+                FROM player_pvp_stats
+                WHERE season_id = $1 AND matches_played > 0
+                ORDER BY rank
+                LIMIT $2
+            ) ranked
+            LEFT JOIN LATERAL (
+                SELECT breach_amount FROM pvp_season_rewards
+                WHERE ranked.rank BETWEEN min_rank AND max_rank
+                ORDER BY breach_amount DESC
+                LIMIT 1
+            ) tier ON true
+            "#,
+            season_id,
+            SEASON_RANKED_PLAYERS
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        sqlx::query!("UPDATE pvp_seasons SET is_active = false, closed_at = NOW() WHERE id = $1", season_id)
+            .execute(&mut *tx)
+            .await?;
+
+        // The next season runs as long as this one did, with the same rewards
+        let next_season_id = if season.is_active {
+            let id = sqlx::query_scalar!(
+                r#"
+                INSERT INTO pvp_seasons (name, start_date, end_date, is_active, rewards)
+                SELECT 'Season ' || ((SELECT COUNT(*) FROM pvp_seasons) + 1), NOW(),
+                       NOW() + (end_date - start_date), true, rewards
+                FROM pvp_seasons WHERE id = $1
+                RETURNING id
+                "#,
+                season_id
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            Some(id)
+        } else {
+            None
+        };
+
+        tx.commit().await?;
+
+        tracing::info!(
+            "PvP season {} closed with {} ranked players, season {:?} opened",
+            season_id, ranked, next_season_id
+        );
+        Ok((ranked, next_season_id))
+    }
+
+    /// Send a closed season's unsent rewards, returning how many were paid
+    /// and how many were queued for retry
+    async fn pay_season_rewards(&self, season_id: i32, transfer: &dyn RewardTransfer) -> ApiResult<(u32, u32)> {
+        let (mut paid, mut queued) = (0, 0);
+
+        loop {
+            // Claim a batch first so no other run sends it too
+            let batch: Vec<(Uuid, i64, String)> = sqlx::query!(
+                r#"
+                UPDATE pvp_season_rankings r SET paid_at = NOW()
+                FROM players p
+                WHERE p.id = r.player_id AND r.season_id = $1 AND r.player_id IN (
+                    SELECT player_id FROM pvp_season_rankings
+                    WHERE season_id = $1 AND reward_amount > 0 AND paid_at IS NULL
+                    ORDER BY rank
+                    LIMIT $2
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING r.player_id, r.reward_amount, p.wallet_address
+                "#,
+                season_id,
+                SEASON_PAYOUT_BATCH
+            )
+            .fetch_all(&self.db.pg)
+            .await?
+            .into_iter()
+            .map(|row| (row.player_id, row.reward_amount, row.wallet_address))
+            .collect();
+            if batch.is_empty() {
+                break;
+            }
+
+            let results = futures_util::future::join_all(
+                batch.iter().map(|(_, amount, wallet)| transfer.transfer(wallet, *amount as u64)),
+            )
+            .await;
+
+            for ((player_id, amount, _), result) in batch.into_iter().zip(results) {
+                match result {
+                    Ok(signature) => {
+                        sqlx::query!(
+                            "UPDATE pvp_season_rankings SET tx_signature = $3 WHERE season_id = $1 AND player_id = $2",
+                            season_id,
+                            player_id,
+                            signature
+                        )
+                        .execute(&self.db.pg)
+                        .await?;
+                        paid += 1;
+                    }
+                    Err(e) => {
+                        tracing::warn!("Season {} reward of {} to {} failed: {}", season_id, amount, player_id, e);
+                        self.reward
+                            .record_failure_or_warn(player_id, amount as u64, RewardReason::PvpSeason, &e)
+                            .await;
+                        queued += 1;
+                    }
+                }
+            }
+        }
+
+        Ok((paid, queued))
+    }
+
     // ==========================================
     // PLAYER STATS
     // ==========================================
@@ -49,11 +276,17 @@ impl PvpService {
         let season = self.get_current_season().await?;
 
         // Try to get existing stats
-        let existing = sqlx::query_as::<_, PlayerPvpStats>(
-            r#"SELECT * FROM player_pvp_stats WHERE player_id = $1 AND season_id = $2"#,
+        let existing = sqlx::query_as!(
+            PlayerPvpStats,
+            r#"
+            SELECT id, player_id, season_id, elo_rating, peak_rating, matches_played, matches_won,
+                   matches_lost, win_streak, max_win_streak, rank_tier, rank_division,
+                   rank_points, last_match_at, created_at, updated_at
+            FROM player_pvp_stats WHERE player_id = $1 AND season_id = $2
+            "#,
+            player_id,
+            season.id
         )
-        .bind(player_id)
-        .bind(season.id)
         .fetch_optional(&self.db.pg)
         .await?;
 
@@ -62,15 +295,18 @@ impl PvpService {
         }
 
         // Create new stats
-        let stats = sqlx::query_as::<_, PlayerPvpStats>(
+        let stats = sqlx::query_as!(
+            PlayerPvpStats,
             r#"
             INSERT INTO player_pvp_stats (player_id, season_id)
             VALUES ($1, $2)
-            RETURNING *
+            RETURNING id, player_id, season_id, elo_rating, peak_rating, matches_played, matches_won,
+                      matches_lost, win_streak, max_win_streak, rank_tier, rank_division,
+                      rank_points, last_match_at, created_at, updated_at
             "#,
+            player_id,
+            season.id
         )
-        .bind(player_id)
-        .bind(season.id)
         .fetch_one(&self.db.pg)
         .await?;
 
@@ -82,14 +318,14 @@ impl PvpService {
         let stats = self.get_or_create_stats(player_id).await?;
 
         // Get global rank
-        let rank: Option<i64> = sqlx::query_scalar(
+        let rank: Option<i64> = sqlx::query_scalar!(
             r#"
             SELECT COUNT(*) + 1 FROM player_pvp_stats
             WHERE season_id = $1 AND elo_rating > $2
             "#,
+            stats.season_id,
+            stats.elo_rating
         )
-        .bind(stats.season_id)
-        .bind(stats.elo_rating)
         .fetch_one(&self.db.pg)
         .await?;
 
@@ -127,30 +363,27 @@ impl PvpService {
     // MATCHMAKING
     // ==========================================
 
-    /// Join matchmaking queue
+    /// Join matchmaking queue; also returns the match when someone waiting
+    /// was matched right away
     pub async fn join_queue(
         &self,
         player_id: Uuid,
         req: JoinQueueRequest,
-    ) -> ApiResult<QueueStatusResponse> {
-        // Verify player owns titan
-        let titan_owned: bool = sqlx::query_scalar(
-            r#"SELECT EXISTS(SELECT 1 FROM player_titans WHERE id = $1 AND player_id = $2)"#,
-        )
-        .bind(req.titan_id)
-        .bind(player_id)
-        .fetch_one(&self.db.pg)
-        .await?;
-
-        if !titan_owned {
-            return Err(AppError::BadRequest("Titan not found".into()));
-        }
+    ) -> ApiResult<(QueueStatusResponse, Option<FoundMatch>)> {
+        // Verify player owns titan and it isn't listed
+        self.check_titan_available(player_id, req.titan_id).await?;
 
         // Check if already in queue or match
-        let existing: Option<QueueEntry> = sqlx::query_as(
-            r#"SELECT * FROM matchmaking_queue WHERE player_id = $1 AND status = 'searching'"#,
+        let existing: Option<QueueEntry> = sqlx::query_as!(
+            QueueEntry,
+            r#"
+            SELECT id, player_id, titan_id, elo_rating, elo_range, search_start_time,
+                   status as "status: QueueStatus", matched_with, match_id, created_at,
+                   updated_at
+            FROM matchmaking_queue WHERE player_id = $1 AND status = 'searching'
+            "#,
+            player_id
         )
-        .bind(player_id)
         .fetch_optional(&self.db.pg)
         .await?;
 
@@ -159,20 +392,7 @@ impl PvpService {
         }
 
         // Check if in active match
-        let in_match: bool = sqlx::query_scalar(
-            r#"
-            SELECT EXISTS(
-                SELECT 1 FROM pvp_matches 
-                WHERE (player1_id = $1 OR player2_id = $1)
-                  AND status IN ('preparing', 'titan_select', 'active')
-            )
-            "#,
-        )
-        .bind(player_id)
-        .fetch_one(&self.db.pg)
-        .await?;
-
-        if in_match {
+        if self.in_active_match(player_id).await? {
             return Err(AppError::BadRequest("Already in a match".into()));
         }
 
@@ -180,13 +400,14 @@ impl PvpService {
         let stats = self.get_or_create_stats(player_id).await?;
 
         // Add to queue
-        sqlx::query(
+        sqlx::query!(
             r#"
-            INSERT INTO matchmaking_queue (player_id, titan_id, elo_rating)
-            VALUES ($1, $2, $3)
+            INSERT INTO matchmaking_queue (player_id, titan_id, elo_rating, match_format)
+            VALUES ($1, $2, $3, $4)
             ON CONFLICT (player_id) DO UPDATE SET
                 titan_id = EXCLUDED.titan_id,
                 elo_rating = EXCLUDED.elo_rating,
+                match_format = EXCLUDED.match_format,
                 elo_range = 100,
                 status = 'searching',
                 search_start_time = NOW(),
@@ -194,30 +415,31 @@ impl PvpService {
                 match_id = NULL,
                 updated_at = NOW()
             "#,
+            player_id,
+            req.titan_id,
+            stats.elo_rating,
+            req.match_format as MatchFormat
         )
-        .bind(player_id)
-        .bind(req.titan_id)
-        .bind(stats.elo_rating)
         .execute(&self.db.pg)
         .await?;
 
         // Try to find a match immediately
-        self.try_find_match(player_id).await?;
+        let found = self.try_find_match(player_id).await?;
 
         // Return queue status
-        self.get_queue_status(player_id).await
+        Ok((self.get_queue_status(player_id).await?, found))
     }
 
     /// Leave matchmaking queue
     pub async fn leave_queue(&self, player_id: Uuid) -> ApiResult<()> {
-        sqlx::query(
+        sqlx::query!(
             r#"
             UPDATE matchmaking_queue 
             SET status = 'cancelled', updated_at = NOW()
             WHERE player_id = $1 AND status = 'searching'
             "#,
+            player_id
         )
-        .bind(player_id)
         .execute(&self.db.pg)
         .await?;
 
@@ -226,10 +448,16 @@ impl PvpService {
 
     /// Get queue status
     pub async fn get_queue_status(&self, player_id: Uuid) -> ApiResult<QueueStatusResponse> {
-        let entry: Option<QueueEntry> = sqlx::query_as(
-            r#"SELECT * FROM matchmaking_queue WHERE player_id = $1 ORDER BY created_at DESC LIMIT 1"#,
+        let entry: Option<QueueEntry> = sqlx::query_as!(
+            QueueEntry,
+            r#"
+            SELECT id, player_id, titan_id, elo_rating, elo_range, search_start_time,
+                   status as "status: QueueStatus", matched_with, match_id, created_at,
+                   updated_at
+            FROM matchmaking_queue WHERE player_id = $1 ORDER BY created_at DESC LIMIT 1
+            "#,
+            player_id
         )
-        .bind(player_id)
         .fetch_optional(&self.db.pg)
         .await?;
 
@@ -260,12 +488,18 @@ impl PvpService {
     }
 
     /// Try to find a match for player (called by scheduler too)
-    pub async fn try_find_match(&self, player_id: Uuid) -> ApiResult<Option<Uuid>> {
+    pub async fn try_find_match(&self, player_id: Uuid) -> ApiResult<Option<FoundMatch>> {
         // Get player's queue entry
-        let entry: Option<QueueEntry> = sqlx::query_as(
-            r#"SELECT * FROM matchmaking_queue WHERE player_id = $1 AND status = 'searching'"#,
+        let entry: Option<QueueEntry> = sqlx::query_as!(
+            QueueEntry,
+            r#"
+            SELECT id, player_id, titan_id, elo_rating, elo_range, search_start_time,
+                   status as "status: QueueStatus", matched_with, match_id, created_at,
+                   updated_at
+            FROM matchmaking_queue WHERE player_id = $1 AND status = 'searching'
+            "#,
+            player_id
         )
-        .bind(player_id)
         .fetch_optional(&self.db.pg)
         .await?;
 
@@ -278,21 +512,34 @@ impl PvpService {
         let wait_seconds = (Utc::now() - entry.search_start_time).num_seconds();
         let search_range = 100 + (wait_seconds as i32 / 10) * 50; // +50 every 10 seconds
 
-        // Find opponent in range
-        let opponent: Option<QueueEntry> = sqlx::query_as(
+        let match_format = sqlx::query_scalar!(
+            r#"SELECT match_format as "match_format: MatchFormat" FROM matchmaking_queue WHERE id = $1"#,
+            entry.id
+        )
+        .fetch_one(&self.db.pg)
+        .await?;
+
+        // Find opponent in range, queued for the same format
+        let opponent: Option<QueueEntry> = sqlx::query_as!(
+            QueueEntry,
             r#"
-            SELECT * FROM matchmaking_queue 
+            SELECT id, player_id, titan_id, elo_rating, elo_range, search_start_time,
+                   status as "status: QueueStatus", matched_with, match_id, created_at,
+                   updated_at
+            FROM matchmaking_queue 
             WHERE status = 'searching'
               AND player_id != $1
+              AND match_format = $4
               AND ABS(elo_rating - $2) <= $3
             ORDER BY ABS(elo_rating - $2), search_start_time
             LIMIT 1
             FOR UPDATE SKIP LOCKED
             "#,
+            player_id,
+            entry.elo_rating,
+            search_range,
+            match_format as MatchFormat
         )
-        .bind(player_id)
-        .bind(entry.elo_rating)
-        .bind(search_range)
         .fetch_optional(&self.db.pg)
         .await?;
 
@@ -302,65 +549,130 @@ impl PvpService {
         };
 
         // Create match
-        let match_id = self.create_match(player_id, opponent.player_id).await?;
+        let pvp_match = self
+            .create_match(player_id, opponent.player_id, match_format)
+            .await?;
+        let match_id = pvp_match.id;
 
         // Update queue entries
-        sqlx::query(
+        sqlx::query!(
             r#"
             UPDATE matchmaking_queue 
             SET status = 'matched', matched_with = $2, match_id = $3, updated_at = NOW()
             WHERE player_id = $1
             "#,
+            player_id,
+            opponent.player_id,
+            match_id
         )
-        .bind(player_id)
-        .bind(opponent.player_id)
-        .bind(match_id)
         .execute(&self.db.pg)
         .await?;
 
-        sqlx::query(
+        sqlx::query!(
             r#"
             UPDATE matchmaking_queue 
             SET status = 'matched', matched_with = $2, match_id = $3, updated_at = NOW()
             WHERE player_id = $1
             "#,
+            opponent.player_id,
+            player_id,
+            match_id
         )
-        .bind(opponent.player_id)
-        .bind(player_id)
-        .bind(match_id)
         .execute(&self.db.pg)
         .await?;
 
         tracing::info!("PvP match created: {} vs {}", player_id, opponent.player_id);
 
-        Ok(Some(match_id))
+        Ok(Some(self.announce_match(&pvp_match).await?))
+    }
+
+    /// Notify both players of a new match, and describe it for the push
+    async fn announce_match(&self, pvp_match: &PvpMatch) -> ApiResult<FoundMatch> {
+        let match_id = pvp_match.id;
+        let pairing = [
+            (pvp_match.player1_id, pvp_match.player2_id),
+            (pvp_match.player2_id, pvp_match.player1_id),
+        ];
+        for (player, opponent) in pairing {
+            if let Err(e) = self
+                .notification
+                .create(
+                    player,
+                    NotificationType::PvpMatchFound,
+                    "Match Found",
+                    "An opponent is ready. Pick your Titan!",
+                    Some(serde_json::json!({ "match_id": match_id, "opponent_id": opponent })),
+                    Some(1),
+                )
+                .await
+            {
+                tracing::warn!("Failed to notify {} of match {}: {:?}", player, match_id, e);
+            }
+        }
+
+        let usernames = sqlx::query!(
+            r#"SELECT id, username FROM players WHERE id = ANY($1)"#,
+            &[pvp_match.player1_id, pvp_match.player2_id][..]
+        )
+        .fetch_all(&self.db.pg)
+        .await?;
+        let username = |id: Uuid| {
+            usernames
+                .iter()
+                .find(|player| player.id == id)
+                .and_then(|player| player.username.clone())
+        };
+
+        Ok(FoundMatch {
+            match_id,
+            ready_deadline: pvp_match.ready_deadline.unwrap_or_else(Utc::now),
+            players: [
+                MatchedPlayer {
+                    player_id: pvp_match.player1_id,
+                    username: username(pvp_match.player1_id),
+                    elo: pvp_match.player1_elo,
+                },
+                MatchedPlayer {
+                    player_id: pvp_match.player2_id,
+                    username: username(pvp_match.player2_id),
+                    elo: pvp_match.player2_elo,
+                },
+            ],
+        })
     }
 
-    /// Run matchmaking cycle (called by scheduler)
-    pub async fn run_matchmaking_cycle(&self) -> ApiResult<i32> {
+    /// Run matchmaking cycle (called by scheduler); returns the matches made
+    pub async fn run_matchmaking_cycle(&self) -> ApiResult<Vec<FoundMatch>> {
         // Get all searching players
-        let searching: Vec<Uuid> = sqlx::query_scalar(
-            r#"SELECT player_id FROM matchmaking_queue WHERE status = 'searching' ORDER BY search_start_time"#,
+        let searching: Vec<Uuid> = sqlx::query_scalar!(
+            r#"SELECT player_id FROM matchmaking_queue WHERE status = 'searching' ORDER BY search_start_time"#
         )
         .fetch_all(&self.db.pg)
         .await?;
 
-        let mut matches_created = 0;
+        let mut matches_created = Vec::new();
 
         for player_id in searching {
-            if let Some(_) = self.try_find_match(player_id).await? {
-                matches_created += 1;
+            if let Some(found) = self.try_find_match(player_id).await? {
+                matches_created.push(found);
             }
         }
 
         // Clean up expired entries
-        sqlx::query(
+        sqlx::query!(
             r#"
             UPDATE matchmaking_queue
             SET status = 'expired', updated_at = NOW()
             WHERE status = 'searching'
               AND search_start_time < NOW() - INTERVAL '5 minutes'
-            "#,
+            "#
+        )
+        .execute(&self.db.pg)
+        .await?;
+
+        // Challenges nobody answered lapse without telling anyone
+        sqlx::query!(
+            r#"UPDATE pvp_challenges SET status = 'expired' WHERE status = 'pending' AND expires_at < NOW()"#
         )
         .execute(&self.db.pg)
         .await?;
@@ -368,37 +680,266 @@ impl PvpService {
         Ok(matches_created)
     }
 
+    /// Whether the player is in a match that hasn't finished
+    async fn in_active_match(&self, player_id: Uuid) -> ApiResult<bool> {
+        let in_match = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM pvp_matches
+                WHERE (player1_id = $1 OR player2_id = $1)
+                  AND status IN ('preparing', 'titan_select', 'active')
+            ) as "in_match!"
+            "#,
+            player_id
+        )
+        .fetch_one(&self.db.pg)
+        .await?;
+
+        Ok(in_match)
+    }
+
+    // ==========================================
+    // CHALLENGES
+    // ==========================================
+
+    /// Challenge another player to a match; they have
+    /// `CHALLENGE_TTL_SECONDS` to accept
+    pub async fn challenge(
+        &self,
+        challenger_id: Uuid,
+        target_id: Uuid,
+        req: PvpChallengeRequest,
+    ) -> ApiResult<PvpChallenge> {
+        if challenger_id == target_id {
+            return Err(AppError::BadRequest("Can't challenge yourself".into()));
+        }
+        self.check_titan_available(challenger_id, req.titan_id).await?;
+
+        let target_exists = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM players WHERE id = $1) as "exists!""#,
+            target_id
+        )
+        .fetch_one(&self.db.pg)
+        .await?;
+        if !target_exists {
+            return Err(AppError::PlayerNotFound);
+        }
+
+        if self.in_active_match(challenger_id).await? {
+            return Err(AppError::BadRequest("Already in a match".into()));
+        }
+        if self.in_active_match(target_id).await? {
+            return Err(AppError::BadRequest("Player is already in a match".into()));
+        }
+
+        let pending = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM pvp_challenges
+                WHERE challenger_id = $1 AND target_id = $2
+                  AND status = 'pending' AND expires_at > NOW()
+            ) as "pending!"
+            "#,
+            challenger_id,
+            target_id
+        )
+        .fetch_one(&self.db.pg)
+        .await?;
+        if pending {
+            return Err(AppError::BadRequest("Challenge already pending".into()));
+        }
+
+        let challenge = sqlx::query_as!(
+            PvpChallenge,
+            r#"
+            WITH challenge AS (
+                INSERT INTO pvp_challenges (challenger_id, target_id, titan_id, match_format, expires_at)
+                VALUES ($1, $2, $3, $4, NOW() + make_interval(secs => $5))
+                RETURNING *
+            )
+            SELECT c.id as "id!", c.challenger_id as "challenger_id!", p.username AS challenger_username,
+                   c.target_id as "target_id!", c.titan_id as "titan_id!",
+                   c.match_format as "match_format!: MatchFormat", c.status as "status!: PvpChallengeStatus",
+                   c.match_id, c.created_at as "created_at!", c.expires_at as "expires_at!"
+            FROM challenge c JOIN players p ON p.id = c.challenger_id
+            "#,
+            challenger_id,
+            target_id,
+            req.titan_id,
+            req.match_format as MatchFormat,
+            CHALLENGE_TTL_SECONDS as f64
+        )
+        .fetch_one(&self.db.pg)
+        .await?;
+
+        tracing::info!("PvP challenge {}: {} challenged {}", challenge.id, challenger_id, target_id);
+
+        Ok(challenge)
+    }
+
+    /// Accept a challenge made to the player, starting the match straight
+    /// away; both players then pick their Titans as after matchmaking
+    pub async fn accept_challenge(&self, player_id: Uuid, challenge_id: Uuid) -> ApiResult<FoundMatch> {
+        let mut tx = self.db.pg.begin().await?;
+        let (challenger_id, match_format) = Self::claim_challenge(&mut tx, player_id, challenge_id).await?;
+
+        if self.in_active_match(challenger_id).await? || self.in_active_match(player_id).await? {
+            return Err(AppError::BadRequest("Player is already in a match".into()));
+        }
+
+        let pvp_match = self.create_match(challenger_id, player_id, match_format).await?;
+        sqlx::query!(
+            "UPDATE pvp_challenges SET status = 'accepted', match_id = $2, responded_at = NOW() WHERE id = $1",
+            challenge_id,
+            pvp_match.id
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        // Neither can be matched into a second game from the queue
+        for player in [challenger_id, player_id] {
+            self.leave_queue(player).await?;
+        }
+
+        tracing::info!("PvP match created from challenge {}: {} vs {}", challenge_id, challenger_id, player_id);
+
+        self.announce_match(&pvp_match).await
+    }
+
+    /// Turn down a challenge made to the player
+    pub async fn reject_challenge(&self, player_id: Uuid, challenge_id: Uuid) -> ApiResult<()> {
+        let mut tx = self.db.pg.begin().await?;
+        Self::claim_challenge(&mut tx, player_id, challenge_id).await?;
+        sqlx::query!("UPDATE pvp_challenges SET status = 'rejected', responded_at = NOW() WHERE id = $1", challenge_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Lock a challenge the player can still answer, returning its
+    /// challenger and format
+    async fn claim_challenge(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        player_id: Uuid,
+        challenge_id: Uuid,
+    ) -> ApiResult<(Uuid, MatchFormat)> {
+        let challenge = sqlx::query!(
+            r#"
+            SELECT challenger_id, match_format as "match_format: MatchFormat",
+                   status as "status: PvpChallengeStatus", expires_at
+            FROM pvp_challenges
+            WHERE id = $1 AND target_id = $2
+            FOR UPDATE
+            "#,
+            challenge_id,
+            player_id
+        )
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        match challenge {
+            None => Err(AppError::NotFound("Challenge not found".into())),
+            Some(c) if c.status != PvpChallengeStatus::Pending || c.expires_at <= Utc::now() => {
+                Err(AppError::BadRequest("Challenge is no longer open".into()))
+            }
+            Some(c) => Ok((c.challenger_id, c.match_format)),
+        }
+    }
+
     // ==========================================
     // MATCH MANAGEMENT
     // ==========================================
 
     /// Create a new match
-    async fn create_match(&self, player1_id: Uuid, player2_id: Uuid) -> ApiResult<Uuid> {
+    async fn create_match(
+        &self,
+        player1_id: Uuid,
+        player2_id: Uuid,
+        match_format: MatchFormat,
+    ) -> ApiResult<PvpMatch> {
         let season = self.get_current_season().await?;
         let stats1 = self.get_or_create_stats(player1_id).await?;
         let stats2 = self.get_or_create_stats(player2_id).await?;
 
         let ready_deadline = Utc::now() + Duration::seconds(30);
 
-        let match_data = sqlx::query_as::<_, PvpMatch>(
+        let match_data = sqlx::query_as!(
+            PvpMatch,
             r#"
             INSERT INTO pvp_matches (
                 season_id, player1_id, player2_id, player1_elo, player2_elo,
-                ready_deadline
-            ) VALUES ($1, $2, $3, $4, $5, $6)
-            RETURNING *
+                ready_deadline, match_format
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, season_id, player1_id, player2_id, player1_elo, player2_elo,
+                      player1_titan_id, player2_titan_id,
+                      status as "status: PvpMatchStatus", player1_hp, player2_hp,
+                      current_turn, turn_number, turn_deadline, winner_id, loser_id,
+                      win_reason, winner_elo_change, loser_elo_change,
+                      winner_breach_reward, winner_xp_reward, ready_deadline, started_at,
+                      ended_at, created_at, player1_consecutive_skips, player2_consecutive_skips
             "#,
+            season.id,
+            player1_id,
+            player2_id,
+            stats1.elo_rating,
+            stats2.elo_rating,
+            ready_deadline,
+            match_format as MatchFormat
         )
-        .bind(season.id)
-        .bind(player1_id)
-        .bind(player2_id)
-        .bind(stats1.elo_rating)
-        .bind(stats2.elo_rating)
-        .bind(ready_deadline)
         .fetch_one(&self.db.pg)
         .await?;
 
-        Ok(match_data.id)
+        Ok(match_data)
+    }
+
+    /// Abandon matches where a player hadn't picked a Titan by the ready
+    /// deadline (called by scheduler). No ratings change, and both players
+    /// leave the queue free to join again.
+    pub async fn cancel_unready_matches(&self) -> ApiResult<Vec<CancelledMatch>> {
+        let cancelled = sqlx::query!(
+            r#"
+            UPDATE pvp_matches SET status = 'abandoned', ended_at = NOW()
+            WHERE status IN ('preparing', 'titan_select') AND ready_deadline < NOW()
+            RETURNING id, player1_id, player2_id
+            "#
+        )
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        if cancelled.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let match_ids: Vec<Uuid> = cancelled.iter().map(|cancelled| cancelled.id).collect();
+        sqlx::query!(
+            r#"
+            UPDATE matchmaking_queue SET status = 'cancelled', updated_at = NOW()
+            WHERE match_id = ANY($1) AND status = 'matched'
+            "#,
+            &match_ids
+        )
+        .execute(&self.db.pg)
+        .await?;
+
+        for cancelled in &cancelled {
+            tracing::info!(
+                "PvP match {} cancelled: {} vs {} not ready",
+                cancelled.id,
+                cancelled.player1_id,
+                cancelled.player2_id
+            );
+        }
+
+        Ok(cancelled
+            .into_iter()
+            .map(|cancelled| CancelledMatch {
+                match_id: cancelled.id,
+                player_ids: [cancelled.player1_id, cancelled.player2_id],
+            })
+            .collect())
     }
 
     /// Get match state
@@ -407,10 +948,19 @@ impl PvpService {
         player_id: Uuid,
         match_id: Uuid,
     ) -> ApiResult<MatchStateResponse> {
-        let pvp_match: PvpMatch = sqlx::query_as(
-            r#"SELECT * FROM pvp_matches WHERE id = $1"#,
+        let pvp_match: PvpMatch = sqlx::query_as!(
+            PvpMatch,
+            r#"
+            SELECT id, season_id, player1_id, player2_id, player1_elo, player2_elo, player1_titan_id,
+                   player2_titan_id, status as "status: PvpMatchStatus", player1_hp,
+                   player2_hp, current_turn, turn_number, turn_deadline, winner_id,
+                   loser_id, win_reason, winner_elo_change, loser_elo_change,
+                   winner_breach_reward, winner_xp_reward, ready_deadline, started_at,
+                   ended_at, created_at, player1_consecutive_skips, player2_consecutive_skips
+            FROM pvp_matches WHERE id = $1
+            "#,
+            match_id
         )
-        .bind(match_id)
         .fetch_optional(&self.db.pg)
         .await?
         .ok_or(AppError::NotFound("Match not found".into()))?;
@@ -429,10 +979,7 @@ impl PvpService {
             pvp_match.player1_id
         };
 
-        let opponent_username: Option<String> = sqlx::query_scalar(
-            r#"SELECT username FROM players WHERE id = $1"#,
-        )
-        .bind(opponent_id)
+        let opponent_username: Option<String> = sqlx::query_scalar!(r#"SELECT username FROM players WHERE id = $1"#, opponent_id)
         .fetch_one(&self.db.pg)
         .await?;
 
@@ -462,6 +1009,9 @@ impl PvpService {
             pvp_match.player1_elo
         };
 
+        let rounds = Self::get_rounds(&self.db.pg, match_id).await?;
+        let (my_rounds_won, opponent_rounds_won) = rounds.rounds_won(is_player1);
+
         Ok(MatchStateResponse {
             match_id: pvp_match.id,
             status: pvp_match.status,
@@ -473,8 +1023,191 @@ impl PvpService {
             is_my_turn,
             turn_number: pvp_match.turn_number,
             turn_deadline: pvp_match.turn_deadline,
+            my_consecutive_skips: pvp_match.consecutive_skips(player_id),
+            opponent_consecutive_skips: pvp_match.consecutive_skips(opponent_id),
             my_titan,
             opponent_titan,
+            match_format: rounds.match_format,
+            round_number: rounds.round_number,
+            my_rounds_won,
+            opponent_rounds_won,
+        })
+    }
+
+    /// Format and round score of a match
+    async fn get_rounds<'e>(executor: impl sqlx::PgExecutor<'e>, match_id: Uuid) -> ApiResult<MatchRounds> {
+        let rounds = sqlx::query_as!(
+            MatchRounds,
+            r#"
+            SELECT match_format as "match_format: MatchFormat", round_number, player1_rounds_won,
+                   player2_rounds_won
+            FROM pvp_matches WHERE id = $1
+            "#,
+            match_id
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(rounds)
+    }
+
+    /// Match state for spectators; anyone may read a match this way
+    pub async fn get_spectator_state(&self, match_id: Uuid) -> ApiResult<SpectatorMatchState> {
+        let pvp_match: PvpMatch = sqlx::query_as!(
+            PvpMatch,
+            r#"
+            SELECT id, season_id, player1_id, player2_id, player1_elo, player2_elo, player1_titan_id,
+                   player2_titan_id, status as "status: PvpMatchStatus", player1_hp,
+                   player2_hp, current_turn, turn_number, turn_deadline, winner_id,
+                   loser_id, win_reason, winner_elo_change, loser_elo_change,
+                   winner_breach_reward, winner_xp_reward, ready_deadline, started_at,
+                   ended_at, created_at, player1_consecutive_skips, player2_consecutive_skips
+            FROM pvp_matches WHERE id = $1
+            "#,
+            match_id
+        )
+        .fetch_optional(&self.db.pg)
+        .await?
+        .ok_or(AppError::NotFound("Match not found".into()))?;
+
+        let usernames = sqlx::query!(
+            "SELECT id, username FROM players WHERE id = ANY($1)",
+            &[pvp_match.player1_id, pvp_match.player2_id][..]
+        )
+        .fetch_all(&self.db.pg)
+        .await?;
+        let username = |player_id: Uuid| {
+            usernames
+                .iter()
+                .find(|player| player.id == player_id)
+                .and_then(|player| player.username.clone())
+        };
+
+        Ok(SpectatorMatchState {
+            match_id: pvp_match.id,
+            status: pvp_match.status,
+            turn_number: pvp_match.turn_number,
+            current_turn: pvp_match.current_turn,
+            turn_deadline: pvp_match.turn_deadline,
+            winner_id: pvp_match.winner_id,
+            player1: SpectatorSide {
+                player_id: pvp_match.player1_id,
+                username: username(pvp_match.player1_id),
+                elo: pvp_match.player1_elo,
+                hp: pvp_match.player1_hp,
+                titan: self.get_titan_battle_info(pvp_match.player1_titan_id).await?,
+            },
+            player2: SpectatorSide {
+                player_id: pvp_match.player2_id,
+                username: username(pvp_match.player2_id),
+                elo: pvp_match.player2_elo,
+                hp: pvp_match.player2_hp,
+                titan: self.get_titan_battle_info(pvp_match.player2_titan_id).await?,
+            },
+        })
+    }
+
+    /// A titan can enter PvP when the player owns it and no marketplace
+    /// listing holds it in escrow
+    async fn check_titan_available(&self, player_id: Uuid, titan_id: Uuid) -> ApiResult<()> {
+        let titan = sqlx::query!(
+            "SELECT escrowed_listing_id FROM player_titans WHERE id = $1 AND player_id = $2",
+            titan_id,
+            player_id
+        )
+        .fetch_optional(&self.db.pg)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Titan not found".into()))?;
+
+        if titan.escrowed_listing_id.is_some() {
+            return Err(AppError::TitanEscrowed);
+        }
+        Ok(())
+    }
+
+    /// Make `titan_id` the player's active Titan, benching the current one
+    /// with its HP, and return the HP the incoming Titan has
+    async fn switch_titan(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        player_id: Uuid,
+        pvp_match: &PvpMatch,
+        titan_id: Uuid,
+    ) -> ApiResult<i32> {
+        self.check_titan_available(player_id, titan_id).await?;
+
+        let is_player1 = pvp_match.player1_id == player_id;
+        let (active, hp) = if is_player1 {
+            (pvp_match.player1_titan_id, pvp_match.player1_hp)
+        } else {
+            (pvp_match.player2_titan_id, pvp_match.player2_hp)
+        };
+        if active == Some(titan_id) {
+            return Err(AppError::BadRequest("Titan is already in battle".into()));
+        }
+
+        let benched_hp: Option<i32> = sqlx::query_scalar!(
+            "SELECT hp FROM pvp_match_titans WHERE match_id = $1 AND titan_id = $2",
+            pvp_match.id,
+            titan_id
+        )
+        .fetch_optional(&mut **tx)
+        .await?;
+        let incoming_hp = benched_hp.unwrap_or(TITAN_MAX_HP);
+        if incoming_hp == 0 {
+            return Err(AppError::BadRequest("Titan has been knocked out".into()));
+        }
+
+        if let Some(active) = active {
+            sqlx::query!(
+                r#"
+                INSERT INTO pvp_match_titans (match_id, titan_id, player_id, hp)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (match_id, titan_id) DO UPDATE SET hp = EXCLUDED.hp
+                "#,
+                pvp_match.id,
+                active,
+                player_id,
+                hp
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+        sqlx::query!(
+            r#"
+            UPDATE pvp_matches SET
+                player1_titan_id = CASE WHEN $2 THEN $3 ELSE player1_titan_id END,
+                player2_titan_id = CASE WHEN $2 THEN player2_titan_id ELSE $3 END
+            WHERE id = $1
+            "#,
+            pvp_match.id,
+            is_player1,
+            titan_id
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(incoming_hp)
+    }
+
+    /// A Titan in battle, by its element and threat class; neutral while the
+    /// side has no Titan in battle
+    async fn fighter(&self, titan_id: Option<Uuid>) -> ApiResult<Fighter> {
+        let titan = match titan_id {
+            Some(titan_id) => {
+                sqlx::query!(
+                    r#"SELECT element as "element: Element", threat_class FROM player_titans WHERE id = $1"#,
+                    titan_id
+                )
+                .fetch_optional(&self.db.pg)
+                .await?
+            }
+            None => None,
+        };
+
+        Ok(match titan {
+            Some(titan) => Fighter::new(Some(titan.element), titan.threat_class),
+            None => Fighter::new(None, 1),
         })
     }
 
@@ -485,35 +1218,25 @@ impl PvpService {
             None => return Ok(None),
         };
 
-        let titan: Option<(Uuid, i32, String, i16, Option<String>, Option<i32>, Option<i32>, Option<i32>, Option<i32>)> = sqlx::query_as(
+        let titan = sqlx::query!(
             r#"
-            SELECT id, species_id, element::TEXT, threat_class, nickname,
-                   power_level, attack_iv, defense_iv, speed_iv
+            SELECT id, species_id, element::TEXT as "element!", threat_class, nickname
             FROM player_titans WHERE id = $1
             "#,
+            titan_id
         )
-        .bind(titan_id)
         .fetch_optional(&self.db.pg)
         .await?;
 
         match titan {
-            Some((id, species_id, element, threat_class, nickname, power, atk, def, spd)) => {
-                let base = 50 + (threat_class as i32) * 10;
-                Ok(Some(TitanBattleInfo {
-                    id,
-                    species_id,
-                    element,
-                    threat_class,
-                    nickname,
-                    stats: TitanBattleStats {
-                        max_hp: 100 + power.unwrap_or(0) / 10,
-                        attack: base + atk.unwrap_or(0),
-                        defense: base + def.unwrap_or(0),
-                        speed: base + spd.unwrap_or(0),
-                        special: base + (threat_class as i32) * 5,
-                    },
-                }))
-            }
+            Some(t) => Ok(Some(TitanBattleInfo {
+                id: t.id,
+                species_id: t.species_id,
+                element: t.element,
+                threat_class: t.threat_class,
+                nickname: t.nickname,
+                stats: battle_math::battle_stats(t.threat_class),
+            })),
             None => Ok(None),
         }
     }
@@ -525,24 +1248,23 @@ impl PvpService {
         match_id: Uuid,
         titan_id: Uuid,
     ) -> ApiResult<MatchStateResponse> {
-        // Verify ownership
-        let owned: bool = sqlx::query_scalar(
-            r#"SELECT EXISTS(SELECT 1 FROM player_titans WHERE id = $1 AND player_id = $2)"#,
-        )
-        .bind(titan_id)
-        .bind(player_id)
-        .fetch_one(&self.db.pg)
-        .await?;
-
-        if !owned {
-            return Err(AppError::BadRequest("Titan not found".into()));
-        }
+        // Verify ownership, and that no listing holds the titan
+        self.check_titan_available(player_id, titan_id).await?;
 
         // Get match
-        let pvp_match: PvpMatch = sqlx::query_as(
-            r#"SELECT * FROM pvp_matches WHERE id = $1"#,
+        let pvp_match: PvpMatch = sqlx::query_as!(
+            PvpMatch,
+            r#"
+            SELECT id, season_id, player1_id, player2_id, player1_elo, player2_elo, player1_titan_id,
+                   player2_titan_id, status as "status: PvpMatchStatus", player1_hp,
+                   player2_hp, current_turn, turn_number, turn_deadline, winner_id,
+                   loser_id, win_reason, winner_elo_change, loser_elo_change,
+                   winner_breach_reward, winner_xp_reward, ready_deadline, started_at,
+                   ended_at, created_at, player1_consecutive_skips, player2_consecutive_skips
+            FROM pvp_matches WHERE id = $1
+            "#,
+            match_id
         )
-        .bind(match_id)
         .fetch_optional(&self.db.pg)
         .await?
         .ok_or(AppError::NotFound("Match not found".into()))?;
@@ -555,34 +1277,43 @@ impl PvpService {
         let is_player1 = pvp_match.player1_id == player_id;
         
         if is_player1 {
-            sqlx::query(
+            sqlx::query!(
                 r#"UPDATE pvp_matches SET player1_titan_id = $2 WHERE id = $1"#,
+                match_id,
+                titan_id
             )
-            .bind(match_id)
-            .bind(titan_id)
             .execute(&self.db.pg)
             .await?;
         } else {
-            sqlx::query(
+            sqlx::query!(
                 r#"UPDATE pvp_matches SET player2_titan_id = $2 WHERE id = $1"#,
+                match_id,
+                titan_id
             )
-            .bind(match_id)
-            .bind(titan_id)
             .execute(&self.db.pg)
             .await?;
         }
 
         // Check if both players ready
-        let updated: PvpMatch = sqlx::query_as(
-            r#"SELECT * FROM pvp_matches WHERE id = $1"#,
+        let updated: PvpMatch = sqlx::query_as!(
+            PvpMatch,
+            r#"
+            SELECT id, season_id, player1_id, player2_id, player1_elo, player2_elo, player1_titan_id,
+                   player2_titan_id, status as "status: PvpMatchStatus", player1_hp,
+                   player2_hp, current_turn, turn_number, turn_deadline, winner_id,
+                   loser_id, win_reason, winner_elo_change, loser_elo_change,
+                   winner_breach_reward, winner_xp_reward, ready_deadline, started_at,
+                   ended_at, created_at, player1_consecutive_skips, player2_consecutive_skips
+            FROM pvp_matches WHERE id = $1
+            "#,
+            match_id
         )
-        .bind(match_id)
         .fetch_one(&self.db.pg)
         .await?;
 
         if updated.player1_titan_id.is_some() && updated.player2_titan_id.is_some() {
             // Start battle
-            sqlx::query(
+            sqlx::query!(
                 r#"
                 UPDATE pvp_matches SET 
                     status = 'active',
@@ -591,17 +1322,17 @@ impl PvpService {
                     started_at = NOW()
                 WHERE id = $1
                 "#,
-            )
-            .bind(match_id)
-            .bind(pvp_match.player1_id)  // Player 1 goes first
+                match_id,
+                pvp_match.player1_id
+            )  // Player 1 goes first
             .execute(&self.db.pg)
             .await?;
         } else {
             // Update status to titan_select
-            sqlx::query(
+            sqlx::query!(
                 r#"UPDATE pvp_matches SET status = 'titan_select' WHERE id = $1"#,
+                match_id
             )
-            .bind(match_id)
             .execute(&self.db.pg)
             .await?;
         }
@@ -615,12 +1346,39 @@ impl PvpService {
         player_id: Uuid,
         req: SubmitActionRequest,
     ) -> ApiResult<ActionResultResponse> {
-        // Get match
-        let pvp_match: PvpMatch = sqlx::query_as(
-            r#"SELECT * FROM pvp_matches WHERE id = $1 FOR UPDATE"#,
+        self.play_action(player_id, req, false).await
+    }
+
+    /// Play `req` for the player whose turn it is. A `timed_out` action is
+    /// the timeout task's, played only once the turn has run out, and counts
+    /// towards the player's timeouts in a row instead of clearing them.
+    async fn play_action(
+        &self,
+        player_id: Uuid,
+        req: SubmitActionRequest,
+        timed_out: bool,
+    ) -> ApiResult<ActionResultResponse> {
+        // There are no consumable items yet, so an item would waste the turn
+        if req.action == PvpActionType::Item {
+            return Err(AppError::BadRequest("Items can't be used in battle yet".into()));
+        }
+
+        // The match stays locked until the action is resolved
+        let mut tx = self.db.pg.begin().await?;
+        let pvp_match: PvpMatch = sqlx::query_as!(
+            PvpMatch,
+            r#"
+            SELECT id, season_id, player1_id, player2_id, player1_elo, player2_elo, player1_titan_id,
+                   player2_titan_id, status as "status: PvpMatchStatus", player1_hp,
+                   player2_hp, current_turn, turn_number, turn_deadline, winner_id,
+                   loser_id, win_reason, winner_elo_change, loser_elo_change,
+                   winner_breach_reward, winner_xp_reward, ready_deadline, started_at,
+                   ended_at, created_at, player1_consecutive_skips, player2_consecutive_skips
+            FROM pvp_matches WHERE id = $1 FOR UPDATE
+            "#,
+            req.match_id
         )
-        .bind(req.match_id)
-        .fetch_optional(&self.db.pg)
+        .fetch_optional(&mut *tx)
         .await?
         .ok_or(AppError::NotFound("Match not found".into()))?;
 
@@ -632,56 +1390,142 @@ impl PvpService {
             return Err(AppError::BadRequest("Not your turn".into()));
         }
 
+        if timed_out && pvp_match.turn_deadline.is_none_or(|deadline| deadline > Utc::now()) {
+            return Err(AppError::BadRequest("Turn has not run out".into()));
+        }
+
         let is_player1 = pvp_match.player1_id == player_id;
         
+        // A defend lasts until the opponent's next attack or the defender's
+        // next action, whichever comes first
+        let defending = sqlx::query!(
+            r#"SELECT player1_defending, player2_defending FROM pvp_matches WHERE id = $1"#,
+            req.match_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let (player1_defending, player2_defending) = (defending.player1_defending, defending.player2_defending);
+
         // Calculate damage
-        let mut rng = rand::rngs::StdRng::from_entropy();
-        let base_damage = match req.action {
-            PvpActionType::Attack => rng.gen_range(15..25),
-            PvpActionType::Special => rng.gen_range(25..40),
-            PvpActionType::Defend => 0,
-            PvpActionType::Item => 0,
+        let attack = match req.action {
+            PvpActionType::Attack => Some(Move::Attack),
+            PvpActionType::Special => Some(Move::Special),
+            PvpActionType::Defend | PvpActionType::Item | PvpActionType::Switch { .. } => None,
         };
+        let (hit, opponent_defended) = match attack {
+            Some(attack) => {
+                let (attacker, defender) = if is_player1 {
+                    (pvp_match.player1_titan_id, pvp_match.player2_titan_id)
+                } else {
+                    (pvp_match.player2_titan_id, pvp_match.player1_titan_id)
+                };
+                let (attacker, defender) = (self.fighter(attacker).await?, self.fighter(defender).await?);
+                let opponent_defended = if is_player1 { player2_defending } else { player1_defending };
+                let effectiveness = match (attacker.element, defender.element) {
+                    (Some(attacker), Some(defender)) => {
+                        self.game.read().await.element_advantage_table.multiplier(attacker, defender)
+                    }
+                    _ => 1.0,
+                };
+                let mut rng = rand::rngs::StdRng::from_entropy();
+                let hit = battle_math::roll_hit(&mut rng, attack, &attacker, &defender, effectiveness, opponent_defended);
+                (Some(hit), opponent_defended)
+            }
+            None => (None, false),
+        };
+        let base_damage = hit.map_or(0, |hit| hit.damage);
+
+        let defending = matches!(req.action, PvpActionType::Defend);
+        let (player1_defending, player2_defending) = if is_player1 {
+            (defending, player2_defending && !opponent_defended)
+        } else {
+            (player1_defending && !opponent_defended, defending)
+        };
+        sqlx::query!(
+            r#"UPDATE pvp_matches SET player1_defending = $2, player2_defending = $3 WHERE id = $1"#,
+            req.match_id,
+            player1_defending,
+            player2_defending
+        )
+        .execute(&mut *tx)
+        .await?;
 
         // Apply damage
-        let (new_p1_hp, new_p2_hp) = if is_player1 {
+        let (mut new_p1_hp, mut new_p2_hp) = if is_player1 {
             (pvp_match.player1_hp, (pvp_match.player2_hp - base_damage).max(0))
         } else {
             ((pvp_match.player1_hp - base_damage).max(0), pvp_match.player2_hp)
         };
 
+        // The incoming Titan fights on with its own HP
+        let switched_titan_id = match req.action {
+            PvpActionType::Switch { titan_id } => {
+                let hp = self.switch_titan(&mut tx, player_id, &pvp_match, titan_id).await?;
+                if is_player1 {
+                    new_p1_hp = hp;
+                } else {
+                    new_p2_hp = hp;
+                }
+                Some(titan_id)
+            }
+            _ => None,
+        };
+
         // Record turn
-        sqlx::query(
+        let mut rounds = Self::get_rounds(&mut *tx, req.match_id).await?;
+        let action = req.action.kind();
+        sqlx::query!(
             r#"
             INSERT INTO pvp_battle_turns (
                 match_id, turn_number,
                 player1_action, player1_damage,
                 player2_action, player2_damage,
-                player1_hp_after, player2_hp_after
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-            "#,
-        )
-        .bind(req.match_id)
-        .bind(pvp_match.turn_number + 1)
-        .bind(if is_player1 { Some(req.action) } else { None })
-        .bind(if is_player1 { Some(base_damage) } else { None })
-        .bind(if !is_player1 { Some(req.action) } else { None })
-        .bind(if !is_player1 { Some(base_damage) } else { None })
-        .bind(new_p1_hp)
-        .bind(new_p2_hp)
-        .execute(&self.db.pg)
+                player1_hp_after, player2_hp_after, switched_titan_id, round_number
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            "#,
+            req.match_id,
+            pvp_match.turn_number + 1,
+            if is_player1 { Some(action) } else { None } as Option<PvpActionKind>,
+            if is_player1 { Some(base_damage) } else { None },
+            if !is_player1 { Some(action) } else { None } as Option<PvpActionKind>,
+            if !is_player1 { Some(base_damage) } else { None },
+            new_p1_hp,
+            new_p2_hp,
+            switched_titan_id,
+            rounds.round_number
+        )
+        .execute(&mut *tx)
         .await?;
 
-        // Check for KO
-        let match_ended = new_p1_hp == 0 || new_p2_hp == 0;
-        let winner_id = if match_ended {
+        // A KO wins the round; the match once someone has won enough rounds
+        let round_ended = new_p1_hp == 0 || new_p2_hp == 0;
+        let round_winner_id = if round_ended {
             Some(if new_p1_hp == 0 { pvp_match.player2_id } else { pvp_match.player1_id })
         } else {
             None
         };
+        let match_ended = round_ended && rounds.record_round_win(new_p2_hp == 0);
+        let winner_id = round_winner_id.filter(|_| match_ended);
+
+        if round_ended {
+            sqlx::query!(
+                "UPDATE pvp_matches SET player1_rounds_won = $2, player2_rounds_won = $3 WHERE id = $1",
+                req.match_id,
+                rounds.player1_rounds_won,
+                rounds.player2_rounds_won
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
 
+        let mut match_end = None;
         if match_ended {
-            self.end_match(req.match_id, winner_id.unwrap(), "ko").await?;
+            match_end = Self::record_match_end(&mut tx, req.match_id, winner_id.unwrap(), "ko").await?;
+        } else if round_ended {
+            let round_loser = if new_p1_hp == 0 { pvp_match.player1_id } else { pvp_match.player2_id };
+            Self::start_next_round(&mut tx, req.match_id, rounds.round_number, round_loser).await?;
+            new_p1_hp = TITAN_MAX_HP;
+            new_p2_hp = TITAN_MAX_HP;
         } else {
             // Switch turn
             let next_turn = if is_player1 {
@@ -690,23 +1534,43 @@ impl PvpService {
                 pvp_match.player1_id
             };
 
-            sqlx::query(
+            let timeouts = if timed_out { pvp_match.consecutive_skips(player_id) + 1 } else { 0 };
+
+            // Guarded on the turn number, so a turn that was already resolved
+            // isn't played twice
+            let switched = sqlx::query!(
                 r#"
                 UPDATE pvp_matches SET 
                     player1_hp = $2,
                     player2_hp = $3,
                     current_turn = $4,
                     turn_number = turn_number + 1,
-                    turn_deadline = NOW() + INTERVAL '30 seconds'
-                WHERE id = $1
+                    turn_deadline = NOW() + INTERVAL '30 seconds',
+                    player1_consecutive_skips = CASE WHEN $5 THEN $7 ELSE player1_consecutive_skips END,
+                    player2_consecutive_skips = CASE WHEN $5 THEN player2_consecutive_skips ELSE $7 END
+                WHERE id = $1 AND turn_number = $6 AND status = 'active'
                 "#,
+                req.match_id,
+                new_p1_hp,
+                new_p2_hp,
+                next_turn,
+                is_player1,
+                pvp_match.turn_number,
+                timeouts
             )
-            .bind(req.match_id)
-            .bind(new_p1_hp)
-            .bind(new_p2_hp)
-            .bind(next_turn)
-            .execute(&self.db.pg)
-            .await?;
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+                > 0;
+
+            if !switched {
+                return Err(AppError::BadRequest("Not your turn".into()));
+            }
+        }
+
+        tx.commit().await?;
+        if let Some((loser_id, winner_change)) = match_end {
+            self.announce_match_end(req.match_id, winner_id.unwrap(), loser_id, winner_change).await;
         }
 
         let (my_hp, opponent_hp) = if is_player1 {
@@ -714,33 +1578,109 @@ impl PvpService {
         } else {
             (new_p2_hp, new_p1_hp)
         };
+        let (my_rounds_won, opponent_rounds_won) = rounds.rounds_won(is_player1);
 
         Ok(ActionResultResponse {
             success: true,
             my_action: req.action,
             my_damage: base_damage,
+            effectiveness: hit.map_or(1.0, |hit| hit.effectiveness),
+            element_advantage: hit.map(|hit| hit.effectiveness),
+            critical: hit.is_some_and(|hit| hit.critical),
+            opponent_defended,
             opponent_action: None,
             opponent_damage: None,
             my_hp_after: my_hp,
             opponent_hp_after: opponent_hp,
             turn_complete: true,
+            round_ended,
+            round_winner_id,
+            round_number: rounds.round_number,
+            my_rounds_won,
+            opponent_rounds_won,
             match_ended,
             winner_id,
         })
     }
 
-    /// End match and update ELO
+    /// Heal both sides (benched Titans too) and start `round_number`, the
+    /// previous round's loser moving first
+    async fn start_next_round(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        match_id: Uuid,
+        round_number: i32,
+        first_turn: Uuid,
+    ) -> ApiResult<()> {
+        sqlx::query!(
+            r#"
+            UPDATE pvp_matches SET
+                round_number = $2,
+                player1_hp = $3,
+                player2_hp = $3,
+                player1_defending = false,
+                player2_defending = false,
+                current_turn = $4,
+                turn_number = turn_number + 1,
+                turn_deadline = NOW() + INTERVAL '30 seconds'
+            WHERE id = $1
+            "#,
+            match_id,
+            round_number,
+            TITAN_MAX_HP,
+            first_turn
+        )
+        .execute(&mut **tx)
+        .await?;
+        sqlx::query!(r#"UPDATE pvp_match_titans SET hp = $2 WHERE match_id = $1"#, match_id, TITAN_MAX_HP)
+            .execute(&mut **tx)
+            .await?;
+
+        tracing::info!("PvP match {}: round {} started", match_id, round_number);
+
+        Ok(())
+    }
+
+    /// End match and update ELO; a match that already ended is left as is
     pub async fn end_match(
         &self,
         match_id: Uuid,
         winner_id: Uuid,
         reason: &str,
     ) -> ApiResult<()> {
-        let pvp_match: PvpMatch = sqlx::query_as(
-            r#"SELECT * FROM pvp_matches WHERE id = $1"#,
+        let mut tx = self.db.pg.begin().await?;
+        let ended = Self::record_match_end(&mut tx, match_id, winner_id, reason).await?;
+        tx.commit().await?;
+
+        if let Some((loser_id, winner_change)) = ended {
+            self.announce_match_end(match_id, winner_id, loser_id, winner_change).await;
+        }
+
+        Ok(())
+    }
+
+    /// Complete an active match under `tx`, settling ELO and rewards.
+    /// Returns the loser and the winner's ELO change, or None when the match
+    /// was no longer active.
+    async fn record_match_end(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        match_id: Uuid,
+        winner_id: Uuid,
+        reason: &str,
+    ) -> ApiResult<Option<(Uuid, i32)>> {
+        let pvp_match: PvpMatch = sqlx::query_as!(
+            PvpMatch,
+            r#"
+            SELECT id, season_id, player1_id, player2_id, player1_elo, player2_elo, player1_titan_id,
+                   player2_titan_id, status as "status: PvpMatchStatus", player1_hp,
+                   player2_hp, current_turn, turn_number, turn_deadline, winner_id,
+                   loser_id, win_reason, winner_elo_change, loser_elo_change,
+                   winner_breach_reward, winner_xp_reward, ready_deadline, started_at,
+                   ended_at, created_at, player1_consecutive_skips, player2_consecutive_skips
+            FROM pvp_matches WHERE id = $1
+            "#,
+            match_id
         )
-        .bind(match_id)
-        .fetch_one(&self.db.pg)
+        .fetch_one(&mut **tx)
         .await?;
 
         let loser_id = if winner_id == pvp_match.player1_id {
@@ -773,7 +1713,7 @@ impl PvpService {
         let winner_xp = base_xp + winner_change * 2;
 
         // Update match
-        sqlx::query(
+        let ended = sqlx::query!(
             r#"
             UPDATE pvp_matches SET 
                 status = 'completed',
@@ -785,25 +1725,32 @@ impl PvpService {
                 winner_breach_reward = $7,
                 winner_xp_reward = $8,
                 ended_at = NOW()
-            WHERE id = $1
+            WHERE id = $1 AND status = 'active'
             "#,
+            match_id,
+            winner_id,
+            loser_id,
+            reason,
+            winner_change,
+            loser_change,
+            winner_breach,
+            winner_xp
         )
-        .bind(match_id)
-        .bind(winner_id)
-        .bind(loser_id)
-        .bind(reason)
-        .bind(winner_change)
-        .bind(loser_change)
-        .bind(winner_breach)
-        .bind(winner_xp)
-        .execute(&self.db.pg)
-        .await?;
+        .execute(&mut **tx)
+        .await?
+        .rows_affected()
+            > 0;
+
+        // Another path (a timeout, a surrender) ended it first
+        if !ended {
+            return Ok(None);
+        }
 
         // Update winner stats
         let winner_new_elo = winner_elo + winner_change;
         let winner_tier = RankTier::from_elo(winner_new_elo);
         
-        sqlx::query(
+        sqlx::query!(
             r#"
             UPDATE player_pvp_stats SET 
                 elo_rating = elo_rating + $2,
@@ -818,20 +1765,20 @@ impl PvpService {
                 updated_at = NOW()
             WHERE player_id = $1 AND season_id = $5
             "#,
+            winner_id,
+            winner_change,
+            winner_tier.to_str(),
+            get_division(winner_new_elo),
+            pvp_match.season_id
         )
-        .bind(winner_id)
-        .bind(winner_change)
-        .bind(winner_tier.to_str())
-        .bind(get_division(winner_new_elo))
-        .bind(pvp_match.season_id)
-        .execute(&self.db.pg)
+        .execute(&mut **tx)
         .await?;
 
         // Update loser stats
         let loser_new_elo = (loser_elo + loser_change).max(0);
         let loser_tier = RankTier::from_elo(loser_new_elo);
 
-        sqlx::query(
+        sqlx::query!(
             r#"
             UPDATE player_pvp_stats SET 
                 elo_rating = GREATEST(0, elo_rating + $2),
@@ -844,44 +1791,59 @@ impl PvpService {
                 updated_at = NOW()
             WHERE player_id = $1 AND season_id = $5
             "#,
+            loser_id,
+            loser_change,
+            loser_tier.to_str(),
+            get_division(loser_new_elo),
+            pvp_match.season_id
         )
-        .bind(loser_id)
-        .bind(loser_change)
-        .bind(loser_tier.to_str())
-        .bind(get_division(loser_new_elo))
-        .bind(pvp_match.season_id)
-        .execute(&self.db.pg)
+        .execute(&mut **tx)
         .await?;
 
         // Award rewards to winner
-        sqlx::query(
+        sqlx::query!(
             r#"
             UPDATE players SET 
                 breach_earned = breach_earned + $2,
                 experience = experience + $3
             WHERE id = $1
             "#,
+            winner_id,
+            winner_breach,
+            i64::from(winner_xp)
         )
-        .bind(winner_id)
-        .bind(winner_breach)
-        .bind(winner_xp)
-        .execute(&self.db.pg)
+        .execute(&mut **tx)
         .await?;
 
+        Ok(Some((loser_id, winner_change)))
+    }
+
+    /// Follow-up to a committed match end
+    async fn announce_match_end(&self, match_id: Uuid, winner_id: Uuid, loser_id: Uuid, winner_change: i32) {
+        self.quest.record_event_or_warn(winner_id, QuestEvent::PvpMatchWon).await;
+
+        self.metrics.pvp_matches_completed.inc();
         tracing::info!(
             "PvP match {} ended: {} beat {} ({} ELO change)",
             match_id, winner_id, loser_id, winner_change
         );
-
-        Ok(())
     }
 
     /// Surrender match
     pub async fn surrender(&self, player_id: Uuid, match_id: Uuid) -> ApiResult<()> {
-        let pvp_match: PvpMatch = sqlx::query_as(
-            r#"SELECT * FROM pvp_matches WHERE id = $1"#,
+        let pvp_match: PvpMatch = sqlx::query_as!(
+            PvpMatch,
+            r#"
+            SELECT id, season_id, player1_id, player2_id, player1_elo, player2_elo, player1_titan_id,
+                   player2_titan_id, status as "status: PvpMatchStatus", player1_hp,
+                   player2_hp, current_turn, turn_number, turn_deadline, winner_id,
+                   loser_id, win_reason, winner_elo_change, loser_elo_change,
+                   winner_breach_reward, winner_xp_reward, ready_deadline, started_at,
+                   ended_at, created_at, player1_consecutive_skips, player2_consecutive_skips
+            FROM pvp_matches WHERE id = $1
+            "#,
+            match_id
         )
-        .bind(match_id)
         .fetch_optional(&self.db.pg)
         .await?
         .ok_or(AppError::NotFound("Match not found".into()))?;
@@ -903,6 +1865,233 @@ impl PvpService {
         self.end_match(match_id, winner_id, "surrender").await
     }
 
+    // ==========================================
+    // DISCONNECTS
+    // ==========================================
+
+    /// Active match the player is in, if any
+    pub async fn active_match_id(&self, player_id: Uuid) -> ApiResult<Option<Uuid>> {
+        let match_id = sqlx::query_scalar!(
+            r#"
+            SELECT id FROM pvp_matches
+            WHERE status = 'active' AND (player1_id = $1 OR player2_id = $1)
+            ORDER BY started_at DESC
+            LIMIT 1
+            "#,
+            player_id
+        )
+        .fetch_optional(&self.db.pg)
+        .await?;
+
+        Ok(match_id)
+    }
+
+    /// Start the player's reconnect grace timer after their connection
+    /// dropped mid-match. A timer already running is kept. False when the
+    /// match isn't active or the player isn't in it.
+    pub async fn mark_disconnected(&self, player_id: Uuid, match_id: Uuid) -> ApiResult<bool> {
+        let marked = sqlx::query!(
+            r#"
+            UPDATE pvp_matches SET
+                player1_disconnected_at = CASE WHEN player1_id = $2
+                    THEN COALESCE(player1_disconnected_at, NOW()) ELSE player1_disconnected_at END,
+                player2_disconnected_at = CASE WHEN player2_id = $2
+                    THEN COALESCE(player2_disconnected_at, NOW()) ELSE player2_disconnected_at END
+            WHERE id = $1 AND status = 'active' AND (player1_id = $2 OR player2_id = $2)
+            "#,
+            match_id,
+            player_id
+        )
+        .execute(&self.db.pg)
+        .await?
+        .rows_affected()
+            > 0;
+
+        if marked {
+            tracing::info!("PvP match {}: {} disconnected", match_id, player_id);
+        }
+
+        Ok(marked)
+    }
+
+    /// Clear the player's reconnect grace timer; back on their turn, they
+    /// get a fresh turn deadline. False when no timer was running.
+    pub async fn mark_reconnected(&self, player_id: Uuid, match_id: Uuid) -> ApiResult<bool> {
+        let cleared = sqlx::query!(
+            r#"
+            UPDATE pvp_matches SET
+                player1_disconnected_at = CASE WHEN player1_id = $2 THEN NULL ELSE player1_disconnected_at END,
+                player2_disconnected_at = CASE WHEN player2_id = $2 THEN NULL ELSE player2_disconnected_at END,
+                turn_deadline = CASE WHEN current_turn = $2
+                    THEN GREATEST(turn_deadline, NOW() + INTERVAL '30 seconds') ELSE turn_deadline END
+            WHERE id = $1 AND status = 'active'
+              AND ((player1_id = $2 AND player1_disconnected_at IS NOT NULL)
+                   OR (player2_id = $2 AND player2_disconnected_at IS NOT NULL))
+            "#,
+            match_id,
+            player_id
+        )
+        .execute(&self.db.pg)
+        .await?
+        .rows_affected()
+            > 0;
+
+        if cleared {
+            tracing::info!("PvP match {}: {} reconnected", match_id, player_id);
+        }
+
+        Ok(cleared)
+    }
+
+    // ==========================================
+    // TURN TIMEOUTS
+    // ==========================================
+
+    /// Resolve turns whose deadline passed more than `grace` ago (called by
+    /// scheduler). The player defends and the turn passes to the opponent,
+    /// who wins by `timeout_forfeit` once the player has let `max_skips`
+    /// turns in a row run out.
+    /// A disconnected player's turn waits for them instead, until they have
+    /// been gone `reconnect_grace` and forfeit by disconnect.
+    pub async fn resolve_turn_timeouts(
+        &self,
+        grace: Duration,
+        max_skips: i32,
+        reconnect_grace: Duration,
+    ) -> ApiResult<Vec<TurnTimeout>> {
+        let expired: Vec<PvpMatch> = sqlx::query_as!(
+            PvpMatch,
+            r#"
+            SELECT id, season_id, player1_id, player2_id, player1_elo, player2_elo, player1_titan_id,
+                   player2_titan_id, status as "status: PvpMatchStatus", player1_hp,
+                   player2_hp, current_turn, turn_number, turn_deadline, winner_id,
+                   loser_id, win_reason, winner_elo_change, loser_elo_change,
+                   winner_breach_reward, winner_xp_reward, ready_deadline, started_at,
+                   ended_at, created_at, player1_consecutive_skips, player2_consecutive_skips
+            FROM pvp_matches
+            WHERE status = 'active' AND turn_deadline < $1
+            ORDER BY turn_deadline
+            LIMIT 100
+            "#,
+            Utc::now() - grace
+        )
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        let mut timeouts = Vec::new();
+
+        for pvp_match in expired {
+            let Some(player_id) = pvp_match.current_turn else {
+                continue;
+            };
+
+            let is_player1 = player_id == pvp_match.player1_id;
+            let opponent_id = if is_player1 {
+                pvp_match.player2_id
+            } else {
+                pvp_match.player1_id
+            };
+            let disconnected_at: Option<DateTime<Utc>> = sqlx::query_scalar!(
+                r#"
+                SELECT CASE WHEN player1_id = $2 THEN player1_disconnected_at ELSE player2_disconnected_at END AS disconnected_at
+                FROM pvp_matches WHERE id = $1
+                "#,
+                pvp_match.id,
+                player_id
+            )
+            .fetch_one(&self.db.pg)
+            .await?;
+
+            let (skips, forfeited) = match TurnTimeoutAction::decide(
+                disconnected_at,
+                Utc::now(),
+                reconnect_grace,
+                pvp_match.consecutive_skips(player_id),
+                max_skips,
+            ) {
+                TurnTimeoutAction::Hold => continue,
+                TurnTimeoutAction::ForfeitDisconnected => {
+                    if self.forfeit_disconnected(&pvp_match, player_id, opponent_id).await? {
+                        timeouts.push(TurnTimeout {
+                            match_id: pvp_match.id,
+                            player_id,
+                            opponent_id,
+                            consecutive_skips: pvp_match.consecutive_skips(player_id),
+                            forfeited: true,
+                        });
+                    }
+                    continue;
+                }
+                TurnTimeoutAction::Defend { skips, forfeit } => (skips, forfeit),
+            };
+
+            // Refused when the player acted, or the match ended, meanwhile
+            let req = SubmitActionRequest {
+                match_id: pvp_match.id,
+                action: PvpActionType::Defend,
+            };
+            match self.play_action(player_id, req, true).await {
+                Ok(_) => {}
+                Err(AppError::BadRequest(_)) => continue,
+                Err(e) => return Err(e),
+            }
+
+            if forfeited {
+                self.end_match(pvp_match.id, opponent_id, "timeout_forfeit").await?;
+            }
+
+            tracing::info!(
+                "PvP match {}: {} timed out on turn {} ({} in a row){}",
+                pvp_match.id,
+                player_id,
+                pvp_match.turn_number + 1,
+                skips,
+                if forfeited { ", forfeited" } else { "" }
+            );
+
+            timeouts.push(TurnTimeout {
+                match_id: pvp_match.id,
+                player_id,
+                opponent_id,
+                consecutive_skips: skips,
+                forfeited,
+            });
+        }
+
+        Ok(timeouts)
+    }
+
+    /// Hand the opponent the win over a player who never came back from a
+    /// disconnect. Guarded on the turn number, and on the player still being gone,
+    /// so a reconnect or action that just landed wins.
+    async fn forfeit_disconnected(&self, pvp_match: &PvpMatch, player_id: Uuid, opponent_id: Uuid) -> ApiResult<bool> {
+        let claimed = sqlx::query!(
+            r#"
+            UPDATE pvp_matches SET current_turn = NULL
+            WHERE id = $1 AND status = 'active' AND turn_number = $2
+              AND CASE WHEN player1_id = $3 THEN player1_disconnected_at ELSE player2_disconnected_at END IS NOT NULL
+            "#,
+            pvp_match.id,
+            pvp_match.turn_number,
+            player_id
+        )
+        .execute(&self.db.pg)
+        .await?
+        .rows_affected()
+            > 0;
+
+        if claimed {
+            self.end_match(pvp_match.id, opponent_id, "disconnect").await?;
+            tracing::info!(
+                "PvP match {}: {} never reconnected, forfeited",
+                pvp_match.id,
+                player_id
+            );
+        }
+
+        Ok(claimed)
+    }
+
     // ==========================================
     // LEADERBOARD & HISTORY
     // ==========================================
@@ -913,11 +2102,20 @@ impl PvpService {
         limit: i64,
         offset: i64,
     ) -> ApiResult<Vec<PvpLeaderboardEntry>> {
-        let entries = sqlx::query_as::<_, PvpLeaderboardEntry>(
-            r#"SELECT * FROM pvp_leaderboard LIMIT $1 OFFSET $2"#,
+        let entries = sqlx::query_as!(
+            PvpLeaderboardEntry,
+            r#"
+            SELECT player_id as "player_id!", username, wallet_address as "wallet_address!",
+                   elo_rating as "elo_rating!", peak_rating as "peak_rating!",
+                   rank_tier as "rank_tier!", rank_division as "rank_division!",
+                   matches_played as "matches_played!", matches_won as "matches_won!",
+                   matches_lost as "matches_lost!", win_rate::FLOAT8 as "win_rate!",
+                   max_win_streak as "max_win_streak!", rank as "rank!"
+            FROM pvp_leaderboard LIMIT $1 OFFSET $2
+            "#,
+            limit,
+            offset
         )
-        .bind(limit)
-        .bind(offset)
         .fetch_all(&self.db.pg)
         .await?;
 
@@ -930,19 +2128,20 @@ impl PvpService {
         player_id: Uuid,
         limit: i64,
     ) -> ApiResult<Vec<MatchHistoryEntry>> {
-        let matches = sqlx::query_as::<_, MatchHistoryEntry>(
+        let matches = sqlx::query_as!(
+            MatchHistoryEntry,
             r#"
             SELECT 
                 m.id,
-                CASE WHEN m.player1_id = $1 THEN m.player2_id ELSE m.player1_id END as opponent_id,
+                CASE WHEN m.player1_id = $1 THEN m.player2_id ELSE m.player1_id END as "opponent_id!",
                 CASE WHEN m.player1_id = $1 THEN p2.username ELSE p1.username END as opponent_username,
-                CASE WHEN m.player1_id = $1 THEN m.player1_elo ELSE m.player2_elo END as my_elo,
-                CASE WHEN m.player1_id = $1 THEN m.player2_elo ELSE m.player1_elo END as opponent_elo,
-                (m.winner_id = $1) as won,
-                CASE WHEN m.winner_id = $1 THEN m.winner_elo_change ELSE m.loser_elo_change END as elo_change,
+                CASE WHEN m.player1_id = $1 THEN m.player1_elo ELSE m.player2_elo END as "my_elo!",
+                CASE WHEN m.player1_id = $1 THEN m.player2_elo ELSE m.player1_elo END as "opponent_elo!",
+                COALESCE(m.winner_id = $1, FALSE) as "won!",
+                CASE WHEN m.winner_id = $1 THEN m.winner_elo_change ELSE m.loser_elo_change END as "elo_change!",
                 m.win_reason,
                 m.turn_number as total_turns,
-                EXTRACT(EPOCH FROM (m.ended_at - m.started_at)) as duration_seconds,
+                EXTRACT(EPOCH FROM (m.ended_at - m.started_at))::FLOAT8 as duration_seconds,
                 m.ended_at
             FROM pvp_matches m
             JOIN players p1 ON p1.id = m.player1_id
@@ -952,9 +2151,9 @@ impl PvpService {
             ORDER BY m.ended_at DESC
             LIMIT $2
             "#,
+            player_id,
+            limit
         )
-        .bind(player_id)
-        .bind(limit)
         .fetch_all(&self.db.pg)
         .await?;
 
@@ -2,23 +2,49 @@
 
 use chrono::{Duration, Utc};
 use rand::seq::SliceRandom;
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
 use crate::db::Database;
 use crate::error::{ApiResult, AppError};
 use crate::models::{
-    Element, PlayerQuest, QuestRewardResponse, QuestTemplate, QuestType, QuestWithDetails,
+    Element, PlayerQuest, QuestEvent, QuestRewardResponse, QuestTemplate, QuestType, QuestUpdate,
+    QuestWithDetails,
 };
 
+/// Quest updates waiting to be pushed to online players
+const LIVE_CHANNEL_CAPACITY: usize = 256;
+
 /// Quest service for daily quests management
 #[derive(Clone)]
 pub struct QuestService {
     db: Database,
+    /// Every quest advanced on this instance, for the WebSocket push
+    live: broadcast::Sender<QuestUpdate>,
+}
+
+/// An open quest locked for update by `record_event`
+#[derive(sqlx::FromRow)]
+struct OpenQuest {
+    id: Uuid,
+    quest_type: QuestType,
+    element: Option<Element>,
+    title: String,
+    target_count: i32,
+    progress: i32,
+    xp_reward: i32,
+    breach_reward: i64,
 }
 
 impl QuestService {
     pub fn new(db: Database) -> Self {
-        Self { db }
+        let (live, _) = broadcast::channel(LIVE_CHANNEL_CAPACITY);
+        Self { db, live }
+    }
+
+    /// Quest updates as they are recorded
+    pub fn subscribe(&self) -> broadcast::Receiver<QuestUpdate> {
+        self.live.subscribe()
     }
 
     /// Get all active quests for a player
@@ -168,6 +194,119 @@ impl QuestService {
         Ok(updated)
     }
 
+    /// Advance the player's open quests today that `event` counts toward.
+    /// A quest the event finishes is completed and its XP and $BREACH paid in
+    /// the same transaction, so it's never rewarded twice.
+    pub async fn record_event(&self, player_id: Uuid, event: QuestEvent) -> ApiResult<Vec<QuestUpdate>> {
+        let today = Utc::now().date_naive();
+        let mut tx = self.db.pg.begin().await?;
+
+        // Locked so concurrent events for the same quest queue up; one that
+        // waited on a quest completed meanwhile no longer matches
+        let quests = sqlx::query_as::<_, OpenQuest>(
+            r#"
+            SELECT pq.id, qt.quest_type, qt.element, qt.title, qt.target_count,
+                   pq.progress, qt.xp_reward, qt.breach_reward
+            FROM player_quests pq
+            JOIN quest_templates qt ON pq.template_id = qt.id
+            WHERE pq.player_id = $1
+              AND pq.assigned_date = $2
+              AND pq.expires_at > NOW()
+              AND pq.is_completed = false
+            FOR UPDATE OF pq
+            "#,
+        )
+        .bind(player_id)
+        .bind(today)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut updates = Vec::new();
+        for quest in quests {
+            let amount = event.progress_for(quest.quest_type, quest.element);
+            if amount == 0 {
+                continue;
+            }
+            let progress = quest.progress.saturating_add(amount).min(quest.target_count);
+            let completed = progress >= quest.target_count;
+
+            sqlx::query(
+                r#"
+                UPDATE player_quests
+                SET progress = $2,
+                    is_completed = $3,
+                    completed_at = CASE WHEN $3 THEN NOW() END,
+                    reward_claimed = $3
+                WHERE id = $1
+                "#,
+            )
+            .bind(quest.id)
+            .bind(progress)
+            .bind(completed)
+            .execute(&mut *tx)
+            .await?;
+
+            updates.push(QuestUpdate {
+                player_id,
+                quest_id: quest.id,
+                title: quest.title,
+                progress,
+                target_count: quest.target_count,
+                completed,
+                xp_reward: quest.xp_reward,
+                breach_reward: quest.breach_reward,
+            });
+        }
+
+        let (xp, breach) = updates
+            .iter()
+            .filter(|update| update.completed)
+            .fold((0_i64, 0_i64), |(xp, breach), update| {
+                (xp + i64::from(update.xp_reward), breach + update.breach_reward)
+            });
+        if xp > 0 || breach > 0 {
+            sqlx::query(
+                r#"
+                UPDATE players 
+                SET experience = experience + $2,
+                    breach_earned = breach_earned + $3,
+                    updated_at = NOW()
+                WHERE id = $1
+                "#,
+            )
+            .bind(player_id)
+            .bind(xp)
+            .bind(breach)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        for update in &updates {
+            if update.completed {
+                tracing::info!(
+                    "Player {} completed quest {}: {} XP, {} BREACH",
+                    player_id,
+                    update.quest_id,
+                    update.xp_reward,
+                    update.breach_reward
+                );
+            }
+            // No receivers just means nothing is forwarding (tests, scripts)
+            let _ = self.live.send(update.clone());
+        }
+
+        Ok(updates)
+    }
+
+    /// Record `event`, logging instead of failing the action it came from
+    pub async fn record_event_or_warn(&self, player_id: Uuid, event: QuestEvent) {
+        if let Err(e) = self.record_event(player_id, event).await {
+            tracing::warn!("Failed to record quest event {:?} for {}: {}", event, player_id, e);
+        }
+    }
+
     /// Claim quest reward
     pub async fn claim_reward(&self, player_id: Uuid, quest_id: Uuid) -> ApiResult<QuestRewardResponse> {
         // Get quest with template details
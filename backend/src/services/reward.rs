@@ -0,0 +1,232 @@
+//! Failed reward service
+//!
+//! Records $BREACH rewards whose transfer failed and retries them with
+//! exponential backoff until they are paid or run out of attempts.
+
+use axum::async_trait;
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::error::{ApiResult, AppError};
+use crate::models::{
+    reward_retry_delay, FailedReward, FailedRewardStatus, PendingRewardsResponse, RewardReason,
+};
+use crate::services::SolanaService;
+
+/// Transfers, the original one included, before a reward is left for
+/// manual review
+const MAX_ATTEMPTS: i32 = 8;
+
+/// Rewards retried per run
+const RETRY_BATCH_SIZE: i64 = 50;
+
+/// Sends a reward to a player's wallet, returning the transaction signature
+#[async_trait]
+pub trait RewardTransfer: Send + Sync {
+    async fn transfer(&self, wallet: &str, amount: u64) -> ApiResult<String>;
+}
+
+#[async_trait]
+impl RewardTransfer for SolanaService {
+    async fn transfer(&self, wallet: &str, amount: u64) -> ApiResult<String> {
+        Ok(self.transfer_breach_tokens(wallet, amount).await?.signature)
+    }
+}
+
+/// Retry run summary
+#[derive(Debug, Default)]
+pub struct RewardRetrySummary {
+    pub paid: u32,
+    pub failed: u32,
+    pub retrying: u32,
+}
+
+/// Failed reward service
+#[derive(Clone)]
+pub struct RewardService {
+    db: Database,
+}
+
+impl RewardService {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Owe a player a reward whose transfer just failed
+    pub async fn record_failure(
+        &self,
+        player_id: Uuid,
+        amount: u64,
+        reason: RewardReason,
+        error: &str,
+    ) -> ApiResult<FailedReward> {
+        let reward = sqlx::query_as::<_, FailedReward>(
+            r#"
+            INSERT INTO failed_rewards (player_id, amount, reason, last_error, next_retry_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(player_id)
+        .bind(amount as i64)
+        .bind(reason)
+        .bind(error)
+        .bind(Utc::now() + reward_retry_delay(1))
+        .fetch_one(&self.db.pg)
+        .await?;
+
+        tracing::info!(
+            "{:?} reward of {} for player {} queued for retry",
+            reason,
+            amount,
+            player_id
+        );
+
+        Ok(reward)
+    }
+
+    /// Record a failed transfer, logging rather than failing the caller
+    pub async fn record_failure_or_warn(&self, player_id: Uuid, amount: u64, reason: RewardReason, error: &AppError) {
+        if let Err(e) = self.record_failure(player_id, amount, reason, &error.to_string()).await {
+            tracing::error!(
+                "Failed to queue {:?} reward of {} for player {}: {}",
+                reason,
+                amount,
+                player_id,
+                e
+            );
+        }
+    }
+
+    /// Rewards the player is still owed: waiting for a retry or for review
+    pub async fn get_pending(&self, player_id: Uuid) -> ApiResult<PendingRewardsResponse> {
+        let rewards = sqlx::query_as::<_, FailedReward>(
+            r#"
+            SELECT * FROM failed_rewards
+            WHERE player_id = $1 AND status <> 'paid'
+            ORDER BY created_at
+            "#,
+        )
+        .bind(player_id)
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        let total_owed = rewards.iter().map(|r| r.amount).sum();
+
+        Ok(PendingRewardsResponse { rewards, total_owed })
+    }
+
+    /// Retry every reward whose next retry is due
+    pub async fn retry_due(&self, transfer: &dyn RewardTransfer) -> ApiResult<RewardRetrySummary> {
+        let due: Vec<Uuid> = sqlx::query_scalar(
+            r#"
+            SELECT id FROM failed_rewards
+            WHERE status = 'pending' AND next_retry_at <= NOW()
+            ORDER BY next_retry_at
+            LIMIT $1
+            "#,
+        )
+        .bind(RETRY_BATCH_SIZE)
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        let mut summary = RewardRetrySummary::default();
+        for reward_id in due {
+            match self.retry(reward_id, transfer).await? {
+                Some(FailedRewardStatus::Paid) => summary.paid += 1,
+                Some(FailedRewardStatus::Failed) => summary.failed += 1,
+                Some(FailedRewardStatus::Pending) => summary.retrying += 1,
+                None => {}
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Retry one reward, holding its row so a concurrent run can't pay it
+    /// too. `None` when another run has it or it is no longer due.
+    async fn retry(&self, reward_id: Uuid, transfer: &dyn RewardTransfer) -> ApiResult<Option<FailedRewardStatus>> {
+        let mut tx = self.db.pg.begin().await?;
+
+        let reward = sqlx::query_as::<_, FailedReward>(
+            r#"
+            SELECT * FROM failed_rewards
+            WHERE id = $1 AND status = 'pending' AND next_retry_at <= NOW()
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .bind(reward_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(reward) = reward else {
+            return Ok(None);
+        };
+
+        // Paid already; only the status update was lost
+        if reward.tx_signature.is_some() {
+            sqlx::query("UPDATE failed_rewards SET status = 'paid', paid_at = COALESCE(paid_at, NOW()) WHERE id = $1")
+                .bind(reward.id)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            return Ok(Some(FailedRewardStatus::Paid));
+        }
+
+        let wallet: Option<String> = sqlx::query_scalar("SELECT wallet_address FROM players WHERE id = $1")
+            .bind(reward.player_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        let result = match wallet {
+            Some(wallet) => transfer.transfer(&wallet, reward.amount as u64).await,
+            None => Err(AppError::PlayerNotFound),
+        };
+
+        let attempts = reward.attempts + 1;
+        let (status, signature, error) = match result {
+            Ok(signature) => (FailedRewardStatus::Paid, Some(signature), None),
+            Err(e) if attempts >= MAX_ATTEMPTS => (FailedRewardStatus::Failed, None, Some(e.to_string())),
+            Err(e) => (FailedRewardStatus::Pending, None, Some(e.to_string())),
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE failed_rewards
+            SET status = $2, attempts = $3, tx_signature = $4,
+                last_error = COALESCE($5, last_error), next_retry_at = $6,
+                paid_at = CASE WHEN $2 = 'paid'::failed_reward_status THEN NOW() END
+            WHERE id = $1
+            "#,
+        )
+        .bind(reward.id)
+        .bind(status)
+        .bind(attempts)
+        .bind(&signature)
+        .bind(&error)
+        .bind(Utc::now() + reward_retry_delay(attempts))
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        match status {
+            FailedRewardStatus::Paid => tracing::info!(
+                "Reward {} of {} paid to player {} on attempt {}",
+                reward.id,
+                reward.amount,
+                reward.player_id,
+                attempts
+            ),
+            FailedRewardStatus::Failed => tracing::error!(
+                "Reward {} of {} for player {} failed {} times, left for manual review: {}",
+                reward.id,
+                reward.amount,
+                reward.player_id,
+                attempts,
+                error.unwrap_or_default()
+            ),
+            FailedRewardStatus::Pending => {}
+        }
+
+        Ok(Some(status))
+    }
+}
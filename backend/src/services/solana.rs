@@ -6,16 +6,22 @@
 //! - Game logic contract interactions
 
 use std::str::FromStr;
+use std::sync::RwLock;
+use std::time::Duration;
 
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_client::nonblocking::rpc_client::RpcClient;
+use breach_evolution::EvolutionError;
+use breach_program_errors::{ErrorInfo, Program};
+use solana_client::{client_error::ClientError, nonblocking::rpc_client::RpcClient};
 use solana_sdk::{
     commitment_config::CommitmentConfig,
-    instruction::{AccountMeta, Instruction},
+    hash::Hash,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    packet::PACKET_DATA_SIZE,
     pubkey::Pubkey,
     signature::{Keypair, Signer},
     system_program::ID as SYSTEM_PROGRAM_ID,
-    transaction::Transaction,
+    transaction::{Transaction, TransactionError},
 };
 use spl_associated_token_account::get_associated_token_address;
 use spl_token::ID as TOKEN_PROGRAM_ID;
@@ -24,7 +30,10 @@ use serde::Serialize;
 
 use crate::config::SolanaConfig;
 use crate::error::{ApiResult, AppError};
+use crate::metrics::Metrics;
 use crate::models::Element;
+use crate::utils::stats::{derive_stats, TitanStats};
+use crate::services::fusion::{self, FusePreview};
 
     /// Solana service for blockchain interactions
 #[derive(Clone)]
@@ -35,10 +44,13 @@ pub struct SolanaService {
     titan_program_id: Pubkey,
     game_program_id: Pubkey,
     breach_token_mint: Pubkey,
+    metrics: Metrics,
+    /// Latest blockhash and when it was fetched, shared by all clones
+    blockhash: std::sync::Arc<RwLock<Option<(Hash, tokio::time::Instant)>>>,
 }
 
     /// Titan NFT data for minting (matches contract `MintTitanData`).
-    /// Total size: 88 bytes (packed).
+    /// Total size: 94 bytes (packed).
 #[repr(C, packed)]
 #[derive(Debug, Clone, Copy)]
 pub struct TitanMintData {
@@ -59,7 +71,7 @@ pub struct TitanMintData {
 impl TitanMintData {
     // Serialize to a byte array
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut bytes = Vec::with_capacity(88);
+        let mut bytes = Vec::with_capacity(94);
         bytes.extend_from_slice(&self.species_id.to_le_bytes());
         bytes.push(self.threat_class);
         bytes.push(self.element_type);
@@ -76,6 +88,51 @@ impl TitanMintData {
     }
 }
 
+/// Split `mints` into batches of at most `MAX_MINT_BATCH`, each as large as
+/// `fits` allows (a single mint always makes a batch)
+fn chunk_mints<T>(mints: &[T], fits: impl Fn(&[T]) -> bool) -> Vec<&[T]> {
+    let mut chunks = Vec::new();
+    let mut rest = mints;
+    while !rest.is_empty() {
+        let mut len = rest.len().min(MAX_MINT_BATCH);
+        while len > 1 && !fits(&rest[..len]) {
+            len -= 1;
+        }
+        let (chunk, tail) = rest.split_at(len);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
+}
+
+/// Mint data with base stats derived from the genes and the first 6 gene bytes
+fn titan_mint_data(element: Element, threat_class: u8, species_id: u32, genes: &[u8; 32]) -> TitanMintData {
+    let TitanStats { power, fortitude, velocity, resonance } = derive_stats(genes);
+
+    // 生成 6 字节基因
+    let mut genes_6: [u8; 6] = [0u8; 6];
+    genes_6.copy_from_slice(&genes[..6]);
+
+    TitanMintData {
+        species_id: species_id as u16,
+        threat_class,
+        element_type: element.as_u8(),
+        power,
+        fortitude,
+        velocity,
+        resonance,
+        genes: genes_6,
+        // Example capture location: Tokyo (lat/lng * 10^6)
+        capture_lat: 35658600,  // Tokyo latitude * 10^6
+        capture_lng: 139745200, // Tokyo longitude * 10^6
+        nonce: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        signature: [0u8; 64], // Placeholder signature, not verified on-chain
+    }
+}
+
 /// Battle record data for on-chain storage
 #[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
 pub struct BattleRecordData {
@@ -103,6 +160,35 @@ pub struct MintResult {
     pub token_account: String,
 }
 
+/// Titans per `mint_titan_batch` instruction (contract `MAX_BATCH`)
+pub const MAX_MINT_BATCH: usize = 5;
+
+/// Accounts per `getMultipleAccounts` call (RPC limit)
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// First wait between signature status polls, doubled after each one
+const CONFIRM_POLL_INITIAL: Duration = Duration::from_millis(250);
+
+/// Longest wait between signature status polls
+const CONFIRM_POLL_MAX: Duration = Duration::from_secs(4);
+
+/// How long a fetched blockhash is reused for new transactions. Blockhashes
+/// stay valid for about a minute, so this leaves time to sign and send.
+const BLOCKHASH_TTL: Duration = Duration::from_secs(20);
+
+/// Game Logic reward type for capture rewards (1x multiplier)
+pub const REWARD_TYPE_CAPTURE: u8 = 0;
+
+/// One Titan to mint in a batch
+#[derive(Debug, Clone)]
+pub struct MintParams {
+    pub player_wallet: String,
+    pub element: Element,
+    pub threat_class: u8,
+    pub species_id: u32,
+    pub genes: [u8; 32],
+}
+
 /// Transfer result containing transaction signature
 #[derive(Debug, Clone)]
 pub struct TransferResult {
@@ -147,6 +233,8 @@ impl SolanaService {
             titan_program_id,
             game_program_id,
             breach_token_mint,
+            metrics: Metrics::default(),
+            blockhash: Default::default(),
         })
     }
 
@@ -176,14 +264,174 @@ impl SolanaService {
             titan_program_id,
             game_program_id,
             breach_token_mint,
+            metrics: Metrics::default(),
+            blockhash: Default::default(),
         })
     }
 
+    /// Record RPC latency, mints and $BREACH payouts in `metrics`.
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
     /// Get backend wallet public key.
     pub fn backend_pubkey(&self) -> Pubkey {
         self.backend_keypair.pubkey()
     }
 
+    /// Backend keypair, shared with services that sign off-chain messages.
+    pub fn backend_keypair(&self) -> std::sync::Arc<Keypair> {
+        self.backend_keypair.clone()
+    }
+
+    /// Send a transaction and wait for confirmation, timing the round trip.
+    async fn send_and_confirm(&self, transaction: &Transaction) -> Result<solana_sdk::signature::Signature, ClientError> {
+        let started = std::time::Instant::now();
+        let result = self.rpc_client.send_and_confirm_transaction(transaction).await;
+        self.metrics.solana_rpc_latency.observe(started.elapsed());
+        result
+    }
+
+    /// Send a transaction once, then poll its status with exponential backoff
+    /// until it is confirmed or `timeout` has passed, timing the round trip.
+    ///
+    /// Running out of time is `ServiceUnavailable("confirmation timeout")` so
+    /// the client can retry. The transaction may still land afterwards; its
+    /// signature is logged.
+    pub async fn send_with_confirm_timeout(
+        &self,
+        transaction: &Transaction,
+        timeout: Duration,
+    ) -> ApiResult<solana_sdk::signature::Signature> {
+        let started = std::time::Instant::now();
+        let result = self.confirm_within(transaction, timeout).await;
+        self.metrics.solana_rpc_latency.observe(started.elapsed());
+        result
+    }
+
+    async fn confirm_within(
+        &self,
+        transaction: &Transaction,
+        timeout: Duration,
+    ) -> ApiResult<solana_sdk::signature::Signature> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let signature = self
+            .rpc_client
+            .send_transaction(transaction)
+            .await
+            .map_err(|e| self.classify_send_error(transaction, "Transaction send", e))?;
+
+        let mut delay = CONFIRM_POLL_INITIAL;
+        loop {
+            match self.rpc_client.get_signature_status(&signature).await {
+                Ok(Some(Ok(()))) => return Ok(signature),
+                Ok(Some(Err(err))) => {
+                    return Err(self.classify_send_error(transaction, "Transaction", err.into()));
+                }
+                // Not seen at the client's commitment yet
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Status check for {} failed: {}", signature, e),
+            }
+
+            let now = tokio::time::Instant::now();
+            if now >= deadline {
+                tracing::warn!("Transaction {} not confirmed within {:?}", signature, timeout);
+                return Err(AppError::ServiceUnavailable("confirmation timeout".into()));
+            }
+            tokio::time::sleep(delay.min(deadline - now)).await;
+            delay = (delay * 2).min(CONFIRM_POLL_MAX);
+        }
+    }
+
+    /// Recent blockhash for a new transaction, fetched at most once per
+    /// `BLOCKHASH_TTL` rather than on every build
+    pub async fn get_cached_blockhash(&self) -> ApiResult<Hash> {
+        if let Some((hash, fetched_at)) = *self.blockhash.read().unwrap() {
+            if fetched_at.elapsed() < BLOCKHASH_TTL {
+                return Ok(hash);
+            }
+        }
+
+        let hash = self.rpc_client.get_latest_blockhash().await
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to get blockhash: {}", e)))?;
+        *self.blockhash.write().unwrap() = Some((hash, tokio::time::Instant::now()));
+        Ok(hash)
+    }
+
+    /// Drop the cached blockhash so the next build fetches a fresh one
+    fn invalidate_blockhash(&self) {
+        *self.blockhash.write().unwrap() = None;
+    }
+
+    /// Deadline for `send_with_confirm_timeout` from `solana.confirm_timeout_seconds`
+    fn confirm_timeout(&self) -> Duration {
+        Duration::from_secs(self.config.confirm_timeout_seconds)
+    }
+
+    /// Map a failed send to an API error.
+    ///
+    /// Custom errors raised by the Titan NFT or Game Logic programs, including
+    /// preflight simulation failures, are explained from the shared error
+    /// catalog; anything else is an internal error. An expired blockhash
+    /// also drops the cached one.
+    fn classify_send_error(&self, transaction: &Transaction, context: &str, e: ClientError) -> AppError {
+        tracing::error!("{} failed: {:?}", context, e);
+
+        let err = e.get_transaction_error();
+        if err == Some(TransactionError::BlockhashNotFound) {
+            self.invalidate_blockhash();
+        }
+        match err.and_then(|err| self.program_error(transaction, &err)) {
+            Some(info) => AppError::ProgramError(info),
+            None => AppError::Internal(anyhow::anyhow!("{} failed: {}", context, e)),
+        }
+    }
+
+    /// Map a failed send of a transaction the client built and signed. An
+    /// expired blockhash is `StaleBlockhash`: the signed bytes can't be sent
+    /// again, so the client has to build the transaction again.
+    fn classify_client_send_error(&self, transaction: &Transaction, context: &str, e: ClientError) -> AppError {
+        let stale = e.get_transaction_error() == Some(TransactionError::BlockhashNotFound);
+        let error = self.classify_send_error(transaction, context, e);
+        if stale { AppError::StaleBlockhash } else { error }
+    }
+
+    /// Catalog entry for a custom error raised by one of our programs
+    pub fn program_error(
+        &self,
+        transaction: &Transaction,
+        err: &TransactionError,
+    ) -> Option<&'static ErrorInfo> {
+        let TransactionError::InstructionError(index, InstructionError::Custom(code)) = err else {
+            return None;
+        };
+
+        let instruction = transaction.message.instructions.get(*index as usize)?;
+        let program_id = transaction
+            .message
+            .account_keys
+            .get(instruction.program_id_index as usize)?;
+
+        let program = if *program_id == self.titan_program_id {
+            Program::TitanNft
+        } else if *program_id == self.game_program_id {
+            Program::GameLogic
+        } else {
+            return None;
+        };
+
+        breach_program_errors::lookup(program, *code)
+    }
+
+    /// Whether the RPC node answers and reports itself healthy
+    pub async fn check_health(&self) -> ApiResult<()> {
+        self.rpc_client
+            .get_health()
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("Solana RPC unhealthy: {}", e)))
+    }
+
     /// Get current SOL balance for an address.
     pub async fn get_balance(&self, address: &str) -> ApiResult<u64> {
         let pubkey = Pubkey::from_str(address)
@@ -251,37 +499,7 @@ impl SolanaService {
             &[b"config"],
             &self.titan_program_id,
         );
-        
-        // Read `total_titans_minted` from config
-        let config_account = self.rpc_client.get_account(&config_pda).await
-            .map_err(|e| {
-                tracing::error!("Failed to get config account: {}", e);
-                AppError::Internal(anyhow::anyhow!("Failed to get config: {}", e))
-            })?;
-        
-        tracing::debug!("Config account data length: {}", config_account.data.len());
-        
-        // GlobalConfig layout (packed, no padding):
-        // - discriminator: [u8; 8] - offset 0-7
-        // - authority: Pubkey - offset 8-39
-        // - treasury: Pubkey - offset 40-71
-        // - breach_mint: Pubkey - offset 72-103
-        // - capture_authority: Pubkey - offset 104-135
-        // - capture_fee_bps: u16 - offset 136-137
-        // - marketplace_fee_bps: u16 - offset 138-139
-        // - fusion_fee_bps: u16 - offset 140-141
-        // - max_titans_per_wallet: u16 - offset 142-143
-        // - capture_cooldown_seconds: u32 - offset 144-147
-        // - paused: bool - offset 148
-        // - bump: u8 - offset 149
-        // - total_titans_minted: u64 - offset 150-157
-        // Total size: 182 bytes
-        let total_minted = if config_account.data.len() >= 158 {
-            u64::from_le_bytes(config_account.data[150..158].try_into().unwrap_or([0u8; 8]))
-        } else {
-            tracing::warn!("Config account too small, assuming total_minted=0");
-            0
-        };
+        let total_minted = self.total_titans_minted(&config_pda).await?;
         let titan_id = total_minted + 1;
         tracing::info!("Next Titan ID: {}", titan_id);
 
@@ -300,42 +518,7 @@ impl SolanaService {
         );
         tracing::debug!("Titan PDA: {}", titan_pda);
 
-        // 生成随机属性 (power, fortitude, velocity, resonance)
-        // 在单独的块中使用 rng，避免跨 await 点
-        let (power, fortitude, velocity, resonance) = {
-            use rand::Rng;
-            let mut rng = rand::thread_rng();
-            (
-                rng.gen_range(10..100u8),
-                rng.gen_range(10..100u8),
-                rng.gen_range(10..100u8),
-                rng.gen_range(10..100u8),
-            )
-        };
-
-        // 生成 6 字节基因
-        let mut genes_6: [u8; 6] = [0u8; 6];
-        genes_6.copy_from_slice(&genes[..6]);
-
-        // 准备 mint 指令数据 (instruction discriminator 1 = mint_titan)
-        let mint_data = TitanMintData {
-            species_id: species_id as u16,
-            threat_class,
-            element_type: element.as_u8(),
-            power,
-            fortitude,
-            velocity,
-            resonance,
-            genes: genes_6,
-            // Example capture location: Tokyo (lat/lng * 10^6)
-            capture_lat: 35658600,  // Tokyo latitude * 10^6
-            capture_lng: 139745200, // Tokyo longitude * 10^6
-            nonce: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-            signature: [0u8; 64], // Placeholder signature, not verified on-chain
-        };
+        let mint_data = titan_mint_data(element, threat_class, species_id, &genes);
 
         // Build instruction data: discriminator(1) + MintTitanData
         let mut instruction_data = vec![1u8]; // 1 = mint_titan instruction
@@ -364,8 +547,7 @@ impl SolanaService {
         };
 
         // 获取最新 blockhash
-        let recent_blockhash = self.rpc_client.get_latest_blockhash().await
-            .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to get blockhash: {}", e)))?;
+        let recent_blockhash = self.get_cached_blockhash().await?;
 
         // 注意: 这里需要玩家签名，但后端无法获取玩家私钥
         // 在实际应用中，应该使用交易预签名或由前端发起交易
@@ -383,11 +565,12 @@ impl SolanaService {
 
         // 发送交易
         tracing::info!("Sending mint transaction to Solana...");
-        let signature = self.rpc_client.send_and_confirm_transaction(&transaction).await
+        let signature = self.send_and_confirm(&transaction).await
             .map_err(|e| {
-                tracing::error!("Mint transaction failed: {:?}", e);
-                AppError::Internal(anyhow::anyhow!("Mint transaction failed: {}", e))
+                self.metrics.mint_failures.inc();
+                self.classify_send_error(&transaction, "Mint transaction", e)
             })?;
+        self.metrics.nfts_minted.inc();
         
         tracing::info!("Mint transaction successful: {}", signature);
 
@@ -398,6 +581,175 @@ impl SolanaService {
         })
     }
 
+    /// Mint up to `MAX_MINT_BATCH` Titans in one `mint_titan_batch` transaction.
+    ///
+    /// The Titan PDAs are derived for sequential IDs after the config's
+    /// `total_titans_minted`; results are in the order of `mints`.
+    pub async fn mint_titan_nft_batch(&self, mints: Vec<MintParams>) -> ApiResult<Vec<MintResult>> {
+        if mints.is_empty() || mints.len() > MAX_MINT_BATCH {
+            return Err(AppError::BadRequest(format!(
+                "Batch mint takes 1-{} Titans, got {}",
+                MAX_MINT_BATCH,
+                mints.len()
+            )));
+        }
+        for mint in &mints {
+            Pubkey::from_str(&mint.player_wallet)
+                .map_err(|e| AppError::BadRequest(format!("Invalid player wallet: {}", e)))?;
+        }
+
+        let payer = self.backend_keypair.pubkey();
+        let (config_pda, _) = Pubkey::find_program_address(&[b"config"], &self.titan_program_id);
+        let total_minted = self.total_titans_minted(&config_pda).await?;
+        let (instruction, titan_pdas, player_pda) = self.mint_batch_instruction(&mints, total_minted + 1);
+
+        tracing::info!(
+            "Batch minting {} Titans from ID {}",
+            mints.len(),
+            total_minted + 1
+        );
+
+        let recent_blockhash = self.get_cached_blockhash().await?;
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer),
+            &[&*self.backend_keypair],
+            recent_blockhash,
+        );
+
+        let signature = self.send_and_confirm(&transaction).await
+            .map_err(|e| {
+                self.metrics.mint_failures.add(mints.len() as u64);
+                self.classify_send_error(&transaction, "Batch mint transaction", e)
+            })?;
+        self.metrics.nfts_minted.add(mints.len() as u64);
+
+        tracing::info!("Batch mint transaction successful: {}", signature);
+
+        Ok(titan_pdas
+            .into_iter()
+            .map(|titan_pda| MintResult {
+                signature: signature.to_string(),
+                mint_address: titan_pda.to_string(),
+                token_account: player_pda.to_string(),
+            })
+            .collect())
+    }
+
+    /// Mint any number of Titans, packed `MAX_MINT_BATCH` to a transaction
+    /// and fewer when a full batch would exceed the transaction size limit.
+    ///
+    /// Results are per mint in the order of `mints`: a failed transaction
+    /// fails only the Titans packed into it.
+    pub async fn mint_titans_batch(&self, mints: Vec<MintParams>) -> Vec<ApiResult<MintResult>> {
+        let mut results = Vec::with_capacity(mints.len());
+        for chunk in chunk_mints(&mints, |chunk| self.mint_batch_fits(chunk)) {
+            match self.mint_titan_nft_batch(chunk.to_vec()).await {
+                Ok(minted) => results.extend(minted.into_iter().map(Ok)),
+                Err(e) => {
+                    tracing::warn!("Batch mint of {} Titans failed: {}", chunk.len(), e);
+                    let message = format!("Batch mint failed: {}", e);
+                    results.extend(chunk.iter().map(|_| Err(AppError::ServiceUnavailable(message.clone()))));
+                }
+            }
+        }
+        results
+    }
+
+    /// `mint_titan_batch` instruction for `mints` as Titans `first_titan_id..`,
+    /// with the Titan PDAs and the player PDA it mints to.
+    ///
+    /// Account layout (must match contract):
+    /// [0] payer - backend wallet (signer)
+    /// [1] config_account - config PDA
+    /// [2] player_account - player PDA
+    /// [3] capture_authority - backend wallet (signer)
+    /// [4] system_program
+    /// [5..] titan_accounts - one Titan PDA per mint
+    fn mint_batch_instruction(&self, mints: &[MintParams], first_titan_id: u64) -> (Instruction, Vec<Pubkey>, Pubkey) {
+        // Same temporary approach as `mint_titan_nft`: backend pays and signs
+        let payer = self.backend_keypair.pubkey();
+        let (config_pda, _) = Pubkey::find_program_address(&[b"config"], &self.titan_program_id);
+        let (player_pda, _) = Pubkey::find_program_address(
+            &[b"player", payer.as_ref()],
+            &self.titan_program_id,
+        );
+
+        let titan_pdas: Vec<Pubkey> = (first_titan_id..first_titan_id + mints.len() as u64)
+            .map(|titan_id| {
+                Pubkey::find_program_address(&[b"titan", &titan_id.to_le_bytes()], &self.titan_program_id).0
+            })
+            .collect();
+
+        // Instruction data: discriminator(1) + count(1) + count * MintTitanData
+        let mut instruction_data = vec![10u8, mints.len() as u8]; // 10 = mint_titan_batch
+        for mint in mints {
+            let data = titan_mint_data(mint.element, mint.threat_class, mint.species_id, &mint.genes);
+            instruction_data.extend(data.to_bytes());
+        }
+
+        let mut accounts = vec![
+            AccountMeta::new(payer, true),                          // [0] payer (backend signer, temporary)
+            AccountMeta::new(config_pda, false),                    // [1] config_account
+            AccountMeta::new(player_pda, false),                    // [2] player_account
+            AccountMeta::new(self.backend_keypair.pubkey(), true),  // [3] capture_authority (backend signer)
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),    // [4] system_program
+        ];
+        accounts.extend(titan_pdas.iter().map(|pda| AccountMeta::new(*pda, false))); // [5..] titans
+
+        let instruction = Instruction {
+            program_id: self.titan_program_id,
+            accounts,
+            data: instruction_data,
+        };
+        (instruction, titan_pdas, player_pda)
+    }
+
+    /// Whether a batch mint of `mints` fits in one transaction
+    fn mint_batch_fits(&self, mints: &[MintParams]) -> bool {
+        // Titan IDs only change the PDAs, not the size
+        let (instruction, _, _) = self.mint_batch_instruction(mints, 1);
+        let mut transaction = Transaction::new_with_payer(&[instruction], Some(&self.backend_keypair.pubkey()));
+        let num_signers = transaction.message.header.num_required_signatures as usize;
+        transaction.signatures = vec![solana_sdk::signature::Signature::default(); num_signers];
+
+        bincode::serialized_size(&transaction)
+            .is_ok_and(|size| size as usize <= PACKET_DATA_SIZE)
+    }
+
+    /// Read `total_titans_minted` from the Titan program config
+    async fn total_titans_minted(&self, config_pda: &Pubkey) -> ApiResult<u64> {
+        let config_account = self.rpc_client.get_account(config_pda).await
+            .map_err(|e| {
+                tracing::error!("Failed to get config account: {}", e);
+                AppError::Internal(anyhow::anyhow!("Failed to get config: {}", e))
+            })?;
+        
+        tracing::debug!("Config account data length: {}", config_account.data.len());
+        
+        // GlobalConfig layout (packed, no padding):
+        // - discriminator: [u8; 8] - offset 0-7
+        // - authority: Pubkey - offset 8-39
+        // - treasury: Pubkey - offset 40-71
+        // - breach_mint: Pubkey - offset 72-103
+        // - capture_authority: Pubkey - offset 104-135
+        // - capture_fee_bps: u16 - offset 136-137
+        // - marketplace_fee_bps: u16 - offset 138-139
+        // - fusion_fee_bps: u16 - offset 140-141
+        // - max_titans_per_wallet: u16 - offset 142-143
+        // - capture_cooldown_seconds: u32 - offset 144-147
+        // - paused: bool - offset 148
+        // - bump: u8 - offset 149
+        // - total_titans_minted: u64 - offset 150-157
+        // Total size: 182 bytes
+        if config_account.data.len() >= 158 {
+            Ok(u64::from_le_bytes(config_account.data[150..158].try_into().unwrap_or([0u8; 8])))
+        } else {
+            tracing::warn!("Config account too small, assuming total_minted=0");
+            Ok(0)
+        }
+    }
+
     /// Transfer $BREACH tokens to a player as reward
     pub async fn transfer_breach_tokens(
         &self,
@@ -448,8 +800,7 @@ impl SolanaService {
         instructions.push(transfer_ix);
 
         // Get recent blockhash
-        let recent_blockhash = self.rpc_client.get_latest_blockhash().await
-            .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to get blockhash: {}", e)))?;
+        let recent_blockhash = self.get_cached_blockhash().await?;
 
         // Create and sign transaction
         let transaction = Transaction::new_signed_with_payer(
@@ -460,8 +811,10 @@ impl SolanaService {
         );
 
         // Send transaction
-        let signature = self.rpc_client.send_and_confirm_transaction(&transaction).await
-            .map_err(|e| AppError::Internal(anyhow::anyhow!("Token transfer failed: {}", e)))?;
+        let signature = self
+            .send_with_confirm_timeout(&transaction, self.confirm_timeout())
+            .await?;
+        self.metrics.breach_distributed.add(amount);
 
         Ok(TransferResult {
             signature: signature.to_string(),
@@ -520,8 +873,7 @@ impl SolanaService {
             data: instruction_data,
         };
 
-        let recent_blockhash = self.rpc_client.get_latest_blockhash().await
-            .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to get blockhash: {}", e)))?;
+        let recent_blockhash = self.get_cached_blockhash().await?;
 
         let transaction = Transaction::new_signed_with_payer(
             &[instruction],
@@ -530,8 +882,8 @@ impl SolanaService {
             recent_blockhash,
         );
 
-        let signature = self.rpc_client.send_and_confirm_transaction(&transaction).await
-            .map_err(|e| AppError::Internal(anyhow::anyhow!("Record capture failed: {}", e)))?;
+        let signature = self.send_and_confirm(&transaction).await
+            .map_err(|e| self.classify_send_error(&transaction, "Record capture", e))?;
 
         Ok(signature.to_string())
     }
@@ -588,8 +940,7 @@ impl SolanaService {
             data: instruction_data,
         };
 
-        let recent_blockhash = self.rpc_client.get_latest_blockhash().await
-            .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to get blockhash: {}", e)))?;
+        let recent_blockhash = self.get_cached_blockhash().await?;
 
         let transaction = Transaction::new_signed_with_payer(
             &[instruction],
@@ -598,8 +949,8 @@ impl SolanaService {
             recent_blockhash,
         );
 
-        let signature = self.rpc_client.send_and_confirm_transaction(&transaction).await
-            .map_err(|e| AppError::Internal(anyhow::anyhow!("Record battle failed: {}", e)))?;
+        let signature = self.send_and_confirm(&transaction).await
+            .map_err(|e| self.classify_send_error(&transaction, "Record battle", e))?;
 
         Ok(signature.to_string())
     }
@@ -635,8 +986,7 @@ impl SolanaService {
             data: instruction_data,
         };
 
-        let recent_blockhash = self.rpc_client.get_latest_blockhash().await
-            .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to get blockhash: {}", e)))?;
+        let recent_blockhash = self.get_cached_blockhash().await?;
 
         let transaction = Transaction::new_signed_with_payer(
             &[instruction],
@@ -645,8 +995,8 @@ impl SolanaService {
             recent_blockhash,
         );
 
-        let signature = self.rpc_client.send_and_confirm_transaction(&transaction).await
-            .map_err(|e| AppError::Internal(anyhow::anyhow!("Add experience failed: {}", e)))?;
+        let signature = self.send_and_confirm(&transaction).await
+            .map_err(|e| self.classify_send_error(&transaction, "Add experience", e))?;
 
         Ok(signature.to_string())
     }
@@ -665,6 +1015,19 @@ impl SolanaService {
         }
     }
 
+    /// Whether a transaction built on `blockhash` can still land
+    pub async fn is_blockhash_valid(&self, blockhash: &str) -> ApiResult<bool> {
+        use solana_sdk::hash::Hash;
+
+        let hash = Hash::from_str(blockhash)
+            .map_err(|e| AppError::BadRequest(format!("Invalid blockhash: {}", e)))?;
+
+        self.rpc_client
+            .is_blockhash_valid(&hash, CommitmentConfig::processed())
+            .await
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("RPC error: {}", e)))
+    }
+
     /// Airdrop SOL (devnet only)
     #[cfg(debug_assertions)]
     pub async fn request_airdrop(&self, address: &str, lamports: u64) -> ApiResult<String> {
@@ -688,6 +1051,7 @@ impl SolanaService {
     /// 1. Backend builds transaction and returns base64-encoded bytes
     /// 2. Frontend signs `message_to_sign` with the user's wallet
     /// 3. Frontend calls `submit_signed_transaction` with the signature
+    #[allow(clippy::too_many_arguments)]
     pub async fn build_mint_transaction(
         &self,
         player_wallet: &str,
@@ -741,17 +1105,8 @@ impl SolanaService {
             &self.titan_program_id,
         );
 
-        // Generate random attributes
-        let (power, fortitude, velocity, resonance) = {
-            use rand::Rng;
-            let mut rng = rand::thread_rng();
-            (
-                rng.gen_range(10..100u8),
-                rng.gen_range(10..100u8),
-                rng.gen_range(10..100u8),
-                rng.gen_range(10..100u8),
-            )
-        };
+        // Base stats follow from the genes
+        let TitanStats { power, fortitude, velocity, resonance } = derive_stats(&genes);
 
         // Build MintTitanData
         let mut genes_6: [u8; 6] = [0u8; 6];
@@ -796,8 +1151,7 @@ impl SolanaService {
         };
 
         // Get latest blockhash
-        let recent_blockhash = self.rpc_client.get_latest_blockhash().await
-            .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to get blockhash: {}", e)))?;
+        let recent_blockhash = self.get_cached_blockhash().await?;
 
         // Create unsigned transaction
         use solana_sdk::message::Message;
@@ -840,21 +1194,21 @@ impl SolanaService {
         })
     }
 
-    /// Submit a signed transaction.
+    /// Sign a player-signed transaction for broadcast.
     ///
-    /// Accepts the frontend signature and original transaction,
-    /// adds the backend signature and broadcasts.
+    /// Accepts the frontend signature and original transaction and
+    /// adds the backend signature; `send_signed` broadcasts it.
     ///
     /// Flow:
     /// 1. Frontend signs `message_to_sign`
     /// 2. Frontend sends signature + original transaction to backend
     /// 3. Backend verifies signature, adds its own signature, broadcasts
-    pub async fn submit_signed_transaction(
+    pub fn sign_player_transaction(
         &self,
         serialized_transaction: &str,
         player_signature: &str,
         player_wallet: &str,
-    ) -> ApiResult<SubmitTransactionResult> {
+    ) -> ApiResult<Transaction> {
         use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
         use solana_sdk::signature::Signature;
         
@@ -922,14 +1276,18 @@ impl SolanaService {
             return Err(AppError::Internal(anyhow::anyhow!("Backend signature index out of bounds")));
         }
 
+        Ok(transaction)
+    }
+
+    /// Broadcast a fully signed transaction and wait for confirmation.
+    ///
+    /// Used for transactions the client signed, so an expired blockhash is
+    /// `StaleBlockhash` rather than an internal error.
+    pub async fn send_signed(&self, transaction: &Transaction) -> ApiResult<SubmitTransactionResult> {
         tracing::info!("Submitting transaction with {} signatures", transaction.signatures.len());
 
-        // Send transaction
-        let signature = self.rpc_client.send_and_confirm_transaction(&transaction).await
-            .map_err(|e| {
-                tracing::error!("Transaction submission failed: {:?}", e);
-                AppError::Internal(anyhow::anyhow!("Transaction failed: {}", e))
-            })?;
+        let signature = self.send_and_confirm(transaction).await
+            .map_err(|e| self.classify_client_send_error(transaction, "Transaction", e))?;
 
         tracing::info!("Transaction submitted successfully: {}", signature);
 
@@ -979,7 +1337,8 @@ impl SolanaService {
 
     /// Build Evolve transaction.
     ///
-    /// Evolves Titan to a higher form (requires level >= 30).
+    /// Evolves Titan to a higher form (requires level >= 30). The target is
+    /// checked against the same evolution table the program enforces.
     pub async fn build_evolve_transaction(
         &self,
         player_wallet: &str,
@@ -989,6 +1348,9 @@ impl SolanaService {
         let player = Pubkey::from_str(player_wallet)
             .map_err(|e| AppError::BadRequest(format!("Invalid player wallet: {}", e)))?;
 
+        let titan = self.get_onchain_titan(titan_id).await?.ok_or(AppError::TitanNotFound)?;
+        check_evolution(&titan, new_species_id)?;
+
         // Derive Titan PDA
         let titan_id_bytes = titan_id.to_le_bytes();
         let (titan_pda, _) = Pubkey::find_program_address(
@@ -1014,9 +1376,68 @@ impl SolanaService {
         self.build_simple_transaction(&player, instruction).await
     }
 
+    /// Read a Titan's data account.
+    pub async fn get_onchain_titan(&self, titan_id: u64) -> ApiResult<Option<OnchainTitan>> {
+        let (titan_pda, _) = Pubkey::find_program_address(
+            &[b"titan", &titan_id.to_le_bytes()],
+            &self.titan_program_id,
+        );
+
+        let account = self.rpc_client
+            .get_account_with_commitment(&titan_pda, CommitmentConfig::confirmed())
+            .await
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to get titan: {}", e)))?
+            .value;
+
+        Ok(account.and_then(|a| OnchainTitan::from_account_data(&a.data)))
+    }
+
+    /// Read many Titan data accounts with batched `getMultipleAccounts`
+    /// calls. Entries line up with `titan_ids`; `None` means the PDA is
+    /// gone or no longer holds Titan data.
+    pub async fn get_titan_accounts(&self, titan_ids: &[u64]) -> ApiResult<Vec<Option<OnchainTitan>>> {
+        let pdas: Vec<Pubkey> = titan_ids
+            .iter()
+            .map(|titan_id| {
+                Pubkey::find_program_address(&[b"titan", &titan_id.to_le_bytes()], &self.titan_program_id).0
+            })
+            .collect();
+
+        let mut titans = Vec::with_capacity(pdas.len());
+        for chunk in pdas.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let accounts = self.rpc_client
+                .get_multiple_accounts_with_commitment(chunk, CommitmentConfig::confirmed())
+                .await
+                .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to get titans: {}", e)))?
+                .value;
+
+            titans.extend(
+                accounts
+                    .into_iter()
+                    .map(|account| account.and_then(|a| OnchainTitan::from_account_data(&a.data))),
+            );
+        }
+
+        Ok(titans)
+    }
+
+    /// Check two Titans against the contract's fusion rules and predict the offspring.
+    pub async fn fuse_preview(&self, titan_a_id: u64, titan_b_id: u64) -> ApiResult<FusePreview> {
+        let (titan_a, titan_b) = tokio::try_join!(
+            self.get_onchain_titan(titan_a_id),
+            self.get_onchain_titan(titan_b_id),
+        )?;
+        let (Some(titan_a), Some(titan_b)) = (titan_a, titan_b) else {
+            return Err(AppError::TitanNotFound);
+        };
+
+        Ok(fusion::preview(&titan_a, &titan_b))
+    }
+
     /// Build Fuse transaction.
     ///
     /// Fuses two Titans to create a new one (requires same element, level >= 20).
+    /// Titans the contract would reject fail here, before the player signs.
     pub async fn build_fuse_transaction(
         &self,
         player_wallet: &str,
@@ -1026,6 +1447,11 @@ impl SolanaService {
         let player = Pubkey::from_str(player_wallet)
             .map_err(|e| AppError::BadRequest(format!("Invalid player wallet: {}", e)))?;
 
+        let preview = self.fuse_preview(titan_a_id, titan_b_id).await?;
+        if let Some(reason) = preview.reason {
+            return Err(AppError::Validation(reason));
+        }
+
         // Get config
         let (config_pda, _) = Pubkey::find_program_address(
             &[b"config"],
@@ -1158,8 +1584,7 @@ impl SolanaService {
         use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
         use solana_sdk::message::Message;
 
-        let recent_blockhash = self.rpc_client.get_latest_blockhash().await
-            .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to get blockhash: {}", e)))?;
+        let recent_blockhash = self.get_cached_blockhash().await?;
 
         let message = Message::new_with_blockhash(
             &[instruction],
@@ -1190,6 +1615,17 @@ impl SolanaService {
         user_signature: &str,
         user_wallet: &str,
     ) -> ApiResult<SubmitTransactionResult> {
+        let transaction = self.sign_user_transaction(serialized_transaction, user_signature, user_wallet)?;
+        self.send_signed(&transaction).await
+    }
+
+    /// Apply the user's signature to a transaction they are the only signer of
+    pub fn sign_user_transaction(
+        &self,
+        serialized_transaction: &str,
+        user_signature: &str,
+        user_wallet: &str,
+    ) -> ApiResult<Transaction> {
         use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
         use solana_sdk::signature::Signature;
         
@@ -1225,16 +1661,7 @@ impl SolanaService {
             transaction.signatures[0] = user_sig;
         }
 
-        // 发送交易
-        let signature = self.rpc_client.send_and_confirm_transaction(&transaction).await
-            .map_err(|e| {
-                tracing::error!("Transaction failed: {:?}", e);
-                AppError::Internal(anyhow::anyhow!("Transaction failed: {}", e))
-            })?;
-
-        Ok(SubmitTransactionResult {
-            signature: signature.to_string(),
-        })
+        Ok(transaction)
     }
 
     // ═══════════════════════════════════════════════════════════════════════════
@@ -1283,6 +1710,11 @@ impl SolanaService {
             &self.game_program_id,
         );
 
+        // Replay protection: the program requires the player's current nonce
+        // (the state account is created by the program on the first capture)
+        let capture_state_pda = self.capture_state_pda(&player);
+        let nonce = self.get_capture_nonce(&player).await?;
+
         // 构建指令数据 (discriminator = 1)
         let timestamp = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -1296,6 +1728,7 @@ impl SolanaService {
         instruction_data.push(threat_class);
         instruction_data.push(element_type);
         instruction_data.extend(timestamp.to_le_bytes());
+        instruction_data.extend(nonce.to_le_bytes());
 
         let accounts = vec![
             AccountMeta::new(player, true),                        // [0] player (signer)
@@ -1303,6 +1736,7 @@ impl SolanaService {
             AccountMeta::new(game_config_pda, false),              // [2] config
             AccountMeta::new(capture_record_pda, false),           // [3] capture_record
             AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),   // [4] system_program
+            AccountMeta::new(capture_state_pda, false),            // [5] player capture state
         ];
 
         let instruction = Instruction {
@@ -1320,12 +1754,34 @@ impl SolanaService {
             recent_blockhash: result.recent_blockhash,
             capture_id,
             capture_record_pda: capture_record_pda.to_string(),
+            nonce,
         })
     }
 
+    /// Player capture state PDA (`["capture_nonce", player]`)
+    pub fn capture_state_pda(&self, player: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"capture_nonce", player.as_ref()], &self.game_program_id).0
+    }
+
+    /// Nonce the player's next record_capture must carry (0 before the first capture)
+    pub async fn get_capture_nonce(&self, player: &Pubkey) -> ApiResult<u64> {
+        let account = self.rpc_client
+            .get_account_with_commitment(&self.capture_state_pda(player), CommitmentConfig::confirmed())
+            .await
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to get capture nonce: {}", e)))?
+            .value;
+
+        match account {
+            Some(account) => PlayerCaptureState::nonce_from_account_data(&account.data)
+                .ok_or_else(|| AppError::Internal(anyhow::anyhow!("Invalid capture state account"))),
+            None => Ok(0),
+        }
+    }
+
     /// Record a battle on-chain.
     ///
     /// Requires player A and backend signatures.
+    #[allow(clippy::too_many_arguments)]
     pub async fn record_battle_onchain(
         &self,
         player_a_wallet: &str,
@@ -1481,8 +1937,7 @@ impl SolanaService {
         use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
         use solana_sdk::message::Message;
 
-        let recent_blockhash = self.rpc_client.get_latest_blockhash().await
-            .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to get blockhash: {}", e)))?;
+        let recent_blockhash = self.get_cached_blockhash().await?;
 
         let message = Message::new_with_blockhash(
             &[instruction],
@@ -1580,8 +2035,7 @@ impl SolanaService {
         instructions.push(distribute_ix);
 
         // 构建并发送交易
-        let recent_blockhash = self.rpc_client.get_latest_blockhash().await
-            .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to get blockhash: {}", e)))?;
+        let recent_blockhash = self.get_cached_blockhash().await?;
 
         let transaction = Transaction::new_signed_with_payer(
             &instructions,
@@ -1593,17 +2047,142 @@ impl SolanaService {
         tracing::info!("Distributing {} BREACH reward (type {}) to {}", 
             amount as f64 / 1_000_000_000.0, reward_type, player_wallet);
 
-        let signature = self.rpc_client.send_and_confirm_transaction(&transaction).await
-            .map_err(|e| {
-                tracing::error!("Reward distribution failed: {:?}", e);
-                AppError::Internal(anyhow::anyhow!("Reward distribution failed: {}", e))
-            })?;
+        let signature = self
+            .send_with_confirm_timeout(&transaction, self.confirm_timeout())
+            .await?;
+        self.metrics.breach_distributed.add(amount);
 
         Ok(SubmitTransactionResult {
             signature: signature.to_string(),
         })
     }
 
+    /// Accrue a BREACH reward in the player's on-chain ledger (Game Logic discriminator = 11).
+    ///
+    /// Backend-only signer; the backend also pays rent for the ledger on the
+    /// player's first reward. Tokens stay in the reward pool until the player
+    /// claims them with `build_claim_rewards_transaction`.
+    pub async fn accrue_reward_onchain(
+        &self,
+        player_wallet: &str,
+        reward_type: u8,
+        amount: u64,
+    ) -> ApiResult<TransferResult> {
+        let player = Pubkey::from_str(player_wallet)
+            .map_err(|e| AppError::BadRequest(format!("Invalid player wallet: {}", e)))?;
+
+        let mut instruction_data = vec![11u8]; // ACCRUE_REWARD
+        instruction_data.push(reward_type);
+        instruction_data.extend(amount.to_le_bytes());
+
+        let accrue_ix = Instruction {
+            program_id: self.game_program_id,
+            accounts: vec![
+                AccountMeta::new(self.backend_keypair.pubkey(), true),        // [0] backend_authority (signer, payer)
+                AccountMeta::new_readonly(self.game_config_pda(), false),     // [1] config
+                AccountMeta::new_readonly(player, false),                     // [2] player
+                AccountMeta::new(self.reward_ledger_pda(&player), false),     // [3] reward_ledger
+                AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),          // [4] system_program
+            ],
+            data: instruction_data,
+        };
+
+        let recent_blockhash = self.get_cached_blockhash().await?;
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[accrue_ix],
+            Some(&self.backend_keypair.pubkey()),
+            &[&*self.backend_keypair],
+            recent_blockhash,
+        );
+
+        let signature = self.send_and_confirm(&transaction).await
+            .map_err(|e| self.classify_send_error(&transaction, "Reward accrual", e))?;
+        self.metrics.breach_distributed.add(amount);
+
+        Ok(TransferResult {
+            signature: signature.to_string(),
+            amount,
+        })
+    }
+
+    /// Build Claim Rewards transaction (Game Logic discriminator = 12).
+    ///
+    /// The player signs and pulls their whole pending balance from the reward
+    /// pool in one transfer. Creates the player's token account if missing.
+    pub async fn build_claim_rewards_transaction(
+        &self,
+        player_wallet: &str,
+    ) -> ApiResult<SimpleTransactionResult> {
+        let player = Pubkey::from_str(player_wallet)
+            .map_err(|e| AppError::BadRequest(format!("Invalid player wallet: {}", e)))?;
+
+        let reward_pool = self.reward_pool().await?;
+        let player_token_account = get_associated_token_address(&player, &self.breach_token_mint);
+
+        let accounts = vec![
+            AccountMeta::new(player, true),                                      // [0] player (signer)
+            AccountMeta::new(self.game_config_pda(), false),                     // [1] config
+            AccountMeta::new(self.reward_ledger_pda(&player), false),            // [2] reward_ledger
+            AccountMeta::new(reward_pool, false),                                // [3] reward_pool
+            AccountMeta::new(player_token_account, false),                       // [4] player_token_account
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),                  // [5] token_program
+        ];
+
+        let instructions = [
+            spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                &player,
+                &player,
+                &self.breach_token_mint,
+                &TOKEN_PROGRAM_ID,
+            ),
+            Instruction {
+                program_id: self.game_program_id,
+                accounts,
+                data: vec![12u8], // CLAIM_REWARDS
+            },
+        ];
+
+        self.build_multi_instruction_transaction(&player, &instructions).await
+    }
+
+    /// Reward ledger PDA (`["rewards", player]`)
+    pub fn reward_ledger_pda(&self, player: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"rewards", player.as_ref()], &self.game_program_id).0
+    }
+
+    /// Player's reward ledger (`None` before their first accrued reward)
+    pub async fn get_reward_ledger(&self, player_wallet: &str) -> ApiResult<Option<RewardLedgerState>> {
+        let player = Pubkey::from_str(player_wallet)
+            .map_err(|e| AppError::BadRequest(format!("Invalid player wallet: {}", e)))?;
+
+        let account = self.rpc_client
+            .get_account_with_commitment(&self.reward_ledger_pda(&player), CommitmentConfig::confirmed())
+            .await
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to get reward ledger: {}", e)))?
+            .value;
+
+        match account {
+            Some(account) => RewardLedgerState::from_account_data(&account.data)
+                .map(Some)
+                .ok_or_else(|| AppError::Internal(anyhow::anyhow!("Invalid reward ledger account"))),
+            None => Ok(None),
+        }
+    }
+
+    /// Reward pool token account from the game config (offset 136)
+    async fn reward_pool(&self) -> ApiResult<Pubkey> {
+        let config_account = self.rpc_client.get_account(&self.game_config_pda()).await
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to get game config: {}", e)))?;
+        if config_account.data.len() < 168 {
+            return Err(AppError::Internal(anyhow::anyhow!("Invalid config account size")));
+        }
+        Ok(Pubkey::new_from_array(
+            config_account.data[136..168].try_into()
+                .map_err(|_| AppError::Internal(anyhow::anyhow!("Invalid reward pool pubkey")))?
+        ))
+    }
+
     /// Submit a dual-signed transaction (player + backend signatures).
     pub async fn submit_dual_signed_transaction(
         &self,
@@ -1663,16 +2242,382 @@ impl SolanaService {
         tracing::info!("Submitting dual-signed transaction");
 
         // Send transaction
-        let signature = self.rpc_client.send_and_confirm_transaction(&transaction).await
-            .map_err(|e| {
-                tracing::error!("Transaction failed: {:?}", e);
-                AppError::Internal(anyhow::anyhow!("Transaction failed: {}", e))
-            })?;
+        let signature = self.send_and_confirm(&transaction).await
+            .map_err(|e| self.classify_client_send_error(&transaction, "Transaction", e))?;
 
         Ok(SubmitTransactionResult {
             signature: signature.to_string(),
         })
     }
+    // ═══════════════════════════════════════════════════════════════════════════
+    // Marketplace escrow (List, Cancel, Purchase)
+    // ═══════════════════════════════════════════════════════════════════════════
+
+    /// Derive the Game Logic Listing PDA for a Titan.
+    pub fn listing_pda(&self, titan_id: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"listing", &titan_id.to_le_bytes()],
+            &self.game_program_id,
+        ).0
+    }
+
+    /// Fetch a single on-chain listing, if it exists.
+    pub async fn get_onchain_listing(&self, titan_id: u64) -> ApiResult<Option<OnchainListing>> {
+        let listing_pda = self.listing_pda(titan_id);
+
+        let account = self.rpc_client
+            .get_account_with_commitment(&listing_pda, CommitmentConfig::confirmed())
+            .await
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to get listing: {}", e)))?
+            .value;
+
+        Ok(account.and_then(|a| OnchainListing::from_account_data(listing_pda, &a.data)))
+    }
+
+    /// Fetch every open listing held by the Game Logic escrow.
+    pub async fn get_onchain_listings(&self) -> ApiResult<Vec<OnchainListing>> {
+        use solana_client::rpc_config::RpcProgramAccountsConfig;
+        use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::DataSize(OnchainListing::SIZE as u64),
+                RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, OnchainListing::DISCRIMINATOR.to_vec())),
+            ]),
+            ..Default::default()
+        };
+
+        let accounts = self.rpc_client
+            .get_program_accounts_with_config(&self.game_program_id, config)
+            .await
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to get listings: {}", e)))?;
+
+        Ok(accounts
+            .into_iter()
+            .filter_map(|(address, account)| OnchainListing::from_account_data(address, &account.data))
+            .collect())
+    }
+
+    /// Read the current owner of a Titan from its on-chain data account.
+    pub async fn get_titan_owner(&self, titan_id: u64) -> ApiResult<Option<Pubkey>> {
+        let (titan_pda, _) = Pubkey::find_program_address(
+            &[b"titan", &titan_id.to_le_bytes()],
+            &self.titan_program_id,
+        );
+
+        let account = self.rpc_client
+            .get_account_with_commitment(&titan_pda, CommitmentConfig::confirmed())
+            .await
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to get titan: {}", e)))?
+            .value;
+
+        // owner 在 offset 76 处 (TitanData packed)
+        Ok(account
+            .filter(|a| a.data.len() >= 108)
+            .and_then(|a| <[u8; 32]>::try_from(&a.data[76..108]).ok())
+            .map(Pubkey::new_from_array))
+    }
+
+    /// Build List Titan transaction (Game Logic discriminator = 8).
+    ///
+    /// Moves the Titan into the Listing PDA escrow.
+    pub async fn build_list_titan_transaction(
+        &self,
+        seller_wallet: &str,
+        titan_id: u64,
+        price: u64,
+        duration_seconds: i64,
+    ) -> ApiResult<EscrowTransactionResult> {
+        let seller = Pubkey::from_str(seller_wallet)
+            .map_err(|e| AppError::BadRequest(format!("Invalid seller wallet: {}", e)))?;
+
+        let listing_pda = self.listing_pda(titan_id);
+
+        let mut instruction_data = vec![8u8]; // LIST_TITAN
+        instruction_data.extend(titan_id.to_le_bytes());
+        instruction_data.extend(price.to_le_bytes());
+        instruction_data.extend(duration_seconds.to_le_bytes());
+
+        let accounts = vec![
+            AccountMeta::new(seller, true),                                      // [0] seller (signer)
+            AccountMeta::new_readonly(self.game_config_pda(), false),            // [1] game_config
+            AccountMeta::new(listing_pda, false),                                // [2] listing
+            AccountMeta::new(self.titan_pda(titan_id), false),                   // [3] titan
+            AccountMeta::new_readonly(self.titan_config_pda(), false),           // [4] titan_config
+            AccountMeta::new(self.player_pda(&seller), false),                   // [5] seller_player
+            AccountMeta::new_readonly(self.titan_program_id, false),             // [6] titan_program
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),                 // [7] system_program
+        ];
+
+        let instruction = Instruction {
+            program_id: self.game_program_id,
+            accounts,
+            data: instruction_data,
+        };
+
+        let result = self.build_simple_transaction(&seller, instruction).await?;
+        Ok(EscrowTransactionResult::new(result, titan_id, listing_pda))
+    }
+
+    /// Build Cancel Listing transaction (Game Logic discriminator = 9).
+    ///
+    /// `authority` is the seller, or anyone once the listing has expired.
+    pub async fn build_cancel_listing_transaction(
+        &self,
+        authority_wallet: &str,
+        titan_id: u64,
+    ) -> ApiResult<EscrowTransactionResult> {
+        let authority = Pubkey::from_str(authority_wallet)
+            .map_err(|e| AppError::BadRequest(format!("Invalid authority wallet: {}", e)))?;
+
+        let listing = self.get_onchain_listing(titan_id).await?
+            .ok_or_else(|| AppError::NotFound("On-chain listing not found".into()))?;
+        let seller = listing.seller_pubkey()?;
+
+        let accounts = vec![
+            AccountMeta::new(authority, true),                                   // [0] authority (signer)
+            AccountMeta::new(seller, false),                                     // [1] seller
+            AccountMeta::new(listing.address_pubkey()?, false),                  // [2] listing
+            AccountMeta::new(self.titan_pda(titan_id), false),                   // [3] titan
+            AccountMeta::new_readonly(self.titan_config_pda(), false),           // [4] titan_config
+            AccountMeta::new(self.player_pda(&seller), false),                   // [5] seller_player
+            AccountMeta::new_readonly(self.titan_program_id, false),             // [6] titan_program
+        ];
+
+        let instruction = Instruction {
+            program_id: self.game_program_id,
+            accounts,
+            data: vec![9u8], // CANCEL_LISTING
+        };
+
+        let result = self.build_simple_transaction(&authority, instruction).await?;
+        Ok(EscrowTransactionResult::new(result, titan_id, self.listing_pda(titan_id)))
+    }
+
+    /// Build Purchase Listing transaction (Game Logic discriminator = 10).
+    ///
+    /// The buyer pays the seller and the treasury fee in $BREACH; the price is
+    /// taken from the on-chain listing and pinned in the instruction data.
+    pub async fn build_purchase_listing_transaction(
+        &self,
+        buyer_wallet: &str,
+        titan_id: u64,
+    ) -> ApiResult<EscrowTransactionResult> {
+        let buyer = Pubkey::from_str(buyer_wallet)
+            .map_err(|e| AppError::BadRequest(format!("Invalid buyer wallet: {}", e)))?;
+
+        let listing = self.get_onchain_listing(titan_id).await?
+            .ok_or_else(|| AppError::NotFound("On-chain listing not found".into()))?;
+        let seller = listing.seller_pubkey()?;
+
+        let titan_config_pda = self.titan_config_pda();
+        let treasury = self.treasury().await?;
+
+        let buyer_token = get_associated_token_address(&buyer, &self.breach_token_mint);
+        let seller_token = get_associated_token_address(&seller, &self.breach_token_mint);
+        let treasury_token = get_associated_token_address(&treasury, &self.breach_token_mint);
+
+        let mut instruction_data = vec![10u8]; // PURCHASE_LISTING
+        instruction_data.extend(listing.price.to_le_bytes());
+
+        let accounts = vec![
+            AccountMeta::new(buyer, true),                                       // [0] buyer (signer)
+            AccountMeta::new(seller, false),                                     // [1] seller
+            AccountMeta::new_readonly(self.game_config_pda(), false),            // [2] game_config
+            AccountMeta::new(listing.address_pubkey()?, false),                  // [3] listing
+            AccountMeta::new(self.titan_pda(titan_id), false),                   // [4] titan
+            AccountMeta::new_readonly(titan_config_pda, false),                  // [5] titan_config
+            AccountMeta::new(self.player_pda(&buyer), false),                    // [6] buyer_player
+            AccountMeta::new(buyer_token, false),                                // [7] buyer_token
+            AccountMeta::new(seller_token, false),                               // [8] seller_token
+            AccountMeta::new(treasury_token, false),                             // [9] treasury_token
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),                  // [10] token_program
+            AccountMeta::new_readonly(self.titan_program_id, false),             // [11] titan_program
+        ];
+
+        let mut instructions = Vec::new();
+
+        // 卖家没有 ATA 时由买家创建
+        if self.rpc_client.get_account(&seller_token).await.is_err() {
+            instructions.push(
+                spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                    &buyer,
+                    &seller,
+                    &self.breach_token_mint,
+                    &TOKEN_PROGRAM_ID,
+                ),
+            );
+        }
+
+        instructions.push(Instruction {
+            program_id: self.game_program_id,
+            accounts,
+            data: instruction_data,
+        });
+
+        let result = self.build_multi_instruction_transaction(&buyer, &instructions).await?;
+        Ok(EscrowTransactionResult::new(result, titan_id, self.listing_pda(titan_id)))
+    }
+
+    /// Build a $BREACH payment from buyer to seller for a marketplace sale.
+    ///
+    /// `seller_amount` goes to the seller and `fee` to the treasury; the buyer
+    /// pays the transaction fee and signs it.
+    pub async fn transfer_breach_between(
+        &self,
+        buyer_wallet: &str,
+        seller_wallet: &str,
+        seller_amount: u64,
+        fee: u64,
+    ) -> ApiResult<SimpleTransactionResult> {
+        let buyer = Pubkey::from_str(buyer_wallet)
+            .map_err(|e| AppError::BadRequest(format!("Invalid buyer wallet: {}", e)))?;
+        let seller = Pubkey::from_str(seller_wallet)
+            .map_err(|e| AppError::BadRequest(format!("Invalid seller wallet: {}", e)))?;
+        let treasury = self.treasury().await?;
+
+        let instructions = self.breach_payment_instructions(&buyer, &seller, &treasury, seller_amount, fee)?;
+        self.build_multi_instruction_transaction(&buyer, &instructions).await
+    }
+
+    /// Check that a buyer-built transaction carries the payment
+    /// `transfer_breach_between` would have built for the same sale
+    pub async fn verify_breach_payment(
+        &self,
+        serialized_transaction: &str,
+        buyer_wallet: &str,
+        seller_wallet: &str,
+        seller_amount: u64,
+        fee: u64,
+    ) -> ApiResult<()> {
+        use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+
+        let buyer = Pubkey::from_str(buyer_wallet)
+            .map_err(|e| AppError::BadRequest(format!("Invalid buyer wallet: {}", e)))?;
+        let seller = Pubkey::from_str(seller_wallet)
+            .map_err(|e| AppError::BadRequest(format!("Invalid seller wallet: {}", e)))?;
+        let treasury = self.treasury().await?;
+
+        let tx_bytes = BASE64.decode(serialized_transaction)
+            .map_err(|e| AppError::BadRequest(format!("Invalid base64 transaction: {}", e)))?;
+        let transaction: Transaction = bincode::deserialize(&tx_bytes)
+            .map_err(|e| AppError::BadRequest(format!("Invalid transaction format: {}", e)))?;
+
+        let expected = self.breach_payment_instructions(&buyer, &seller, &treasury, seller_amount, fee)?;
+        if !contains_instructions(&transaction, &expected) {
+            return Err(AppError::BadRequest("Transaction does not pay for this purchase".into()));
+        }
+
+        Ok(())
+    }
+
+    /// Seller ATA (created by the buyer if missing) and the two token transfers
+    fn breach_payment_instructions(
+        &self,
+        buyer: &Pubkey,
+        seller: &Pubkey,
+        treasury: &Pubkey,
+        seller_amount: u64,
+        fee: u64,
+    ) -> ApiResult<Vec<Instruction>> {
+        let buyer_token = get_associated_token_address(buyer, &self.breach_token_mint);
+        let seller_token = get_associated_token_address(seller, &self.breach_token_mint);
+        let treasury_token = get_associated_token_address(treasury, &self.breach_token_mint);
+
+        let transfer = |to: &Pubkey, amount: u64| {
+            spl_token::instruction::transfer(&TOKEN_PROGRAM_ID, &buyer_token, to, buyer, &[], amount)
+                .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to create transfer: {}", e)))
+        };
+
+        let mut instructions = vec![
+            spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                buyer,
+                seller,
+                &self.breach_token_mint,
+                &TOKEN_PROGRAM_ID,
+            ),
+            transfer(&seller_token, seller_amount)?,
+        ];
+        if fee > 0 {
+            instructions.push(transfer(&treasury_token, fee)?);
+        }
+
+        Ok(instructions)
+    }
+
+    /// Treasury wallet from the Titan config account (offset 40)
+    async fn treasury(&self) -> ApiResult<Pubkey> {
+        let titan_config = self.rpc_client.get_account(&self.titan_config_pda()).await
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to get titan config: {}", e)))?;
+        if titan_config.data.len() < 72 {
+            return Err(AppError::Internal(anyhow::anyhow!("Invalid titan config account size")));
+        }
+        Ok(Pubkey::new_from_array(
+            titan_config.data[40..72].try_into()
+                .map_err(|_| AppError::Internal(anyhow::anyhow!("Invalid treasury pubkey")))?
+        ))
+    }
+
+    /// Helper for building single-signer transactions with several instructions.
+    async fn build_multi_instruction_transaction(
+        &self,
+        payer: &Pubkey,
+        instructions: &[Instruction],
+    ) -> ApiResult<SimpleTransactionResult> {
+        use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+        use solana_sdk::message::Message;
+
+        let recent_blockhash = self.get_cached_blockhash().await?;
+
+        let message = Message::new_with_blockhash(instructions, Some(payer), &recent_blockhash);
+
+        let mut transaction = Transaction::new_unsigned(message);
+        let num_signers = transaction.message.header.num_required_signatures as usize;
+        transaction.signatures = vec![solana_sdk::signature::Signature::default(); num_signers];
+
+        let serialized_tx = bincode::serialize(&transaction)
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to serialize: {}", e)))?;
+
+        let message_to_sign = transaction.message.serialize();
+
+        Ok(SimpleTransactionResult {
+            serialized_transaction: BASE64.encode(&serialized_tx),
+            message_to_sign: BASE64.encode(&message_to_sign),
+            recent_blockhash: recent_blockhash.to_string(),
+        })
+    }
+
+    fn game_config_pda(&self) -> Pubkey {
+        Pubkey::find_program_address(&[b"game_config"], &self.game_program_id).0
+    }
+
+    fn titan_config_pda(&self) -> Pubkey {
+        Pubkey::find_program_address(&[b"config"], &self.titan_program_id).0
+    }
+
+    fn titan_pda(&self, titan_id: u64) -> Pubkey {
+        Pubkey::find_program_address(&[b"titan", &titan_id.to_le_bytes()], &self.titan_program_id).0
+    }
+
+    fn player_pda(&self, wallet: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"player", wallet.as_ref()], &self.titan_program_id).0
+    }
+}
+
+/// Whether every instruction in `expected` appears in the transaction,
+/// with the same program, accounts and data
+fn contains_instructions(transaction: &Transaction, expected: &[Instruction]) -> bool {
+    let keys = &transaction.message.account_keys;
+    let key = |index: u8| keys.get(index as usize);
+
+    expected.iter().all(|ix| {
+        transaction.message.instructions.iter().any(|compiled| {
+            key(compiled.program_id_index) == Some(&ix.program_id)
+                && compiled.data == ix.data
+                && compiled.accounts.len() == ix.accounts.len()
+                && compiled.accounts.iter().zip(&ix.accounts).all(|(&i, meta)| key(i) == Some(&meta.pubkey))
+        })
+    })
 }
 
 /// Record Capture result
@@ -1683,6 +2628,57 @@ pub struct RecordCaptureResult {
     pub recent_blockhash: String,
     pub capture_id: u64,
     pub capture_record_pda: String,
+    /// Player capture nonce embedded in the instruction
+    pub nonce: u64,
+}
+
+/// Game Logic `PlayerCaptureState` account (49 bytes packed)
+pub struct PlayerCaptureState;
+
+impl PlayerCaptureState {
+    /// Account size in bytes
+    pub const SIZE: usize = 49;
+
+    /// Account discriminator
+    pub const DISCRIMINATOR: [u8; 8] = *b"capnonce";
+
+    /// Read the nonce (offset 40, after the discriminator and player key)
+    pub fn nonce_from_account_data(data: &[u8]) -> Option<u64> {
+        if data.len() < Self::SIZE || data[0..8] != Self::DISCRIMINATOR {
+            return None;
+        }
+
+        data[40..48].try_into().ok().map(u64::from_le_bytes)
+    }
+}
+
+/// Game Logic `RewardLedger` account (65 bytes packed)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RewardLedgerState {
+    pub accrued_amount: u64,
+    pub last_accrual_ts: i64,
+    pub total_claimed: u64,
+}
+
+impl RewardLedgerState {
+    /// Account size in bytes
+    pub const SIZE: usize = 65;
+
+    /// Account discriminator
+    pub const DISCRIMINATOR: [u8; 8] = *b"rwdledgr";
+
+    /// Parse the balances (offset 40, after the discriminator and player key)
+    pub fn from_account_data(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::SIZE || data[0..8] != Self::DISCRIMINATOR {
+            return None;
+        }
+
+        Some(Self {
+            accrued_amount: u64::from_le_bytes(data[40..48].try_into().ok()?),
+            last_accrual_ts: i64::from_le_bytes(data[48..56].try_into().ok()?),
+            total_claimed: u64::from_le_bytes(data[56..64].try_into().ok()?),
+        })
+    }
 }
 
 /// Record Battle result
@@ -1747,6 +2743,154 @@ pub struct SubmitTransactionResult {
     pub signature: String,
 }
 
+/// On-chain marketplace listing (Game Logic `Listing` account, 73 bytes packed)
+#[derive(Debug, Clone, Serialize)]
+pub struct OnchainListing {
+    /// Listing PDA address
+    pub address: String,
+    pub titan_id: u64,
+    pub seller: String,
+    pub price: u64,
+    pub created_at: i64,
+    pub expires_at: i64,
+}
+
+impl OnchainListing {
+    /// Account size in bytes
+    pub const SIZE: usize = 73;
+
+    /// Account discriminator
+    pub const DISCRIMINATOR: [u8; 8] = *b"listing_";
+
+    /// Parse a listing account, returning `None` for closed or foreign accounts.
+    pub fn from_account_data(address: Pubkey, data: &[u8]) -> Option<Self> {
+        if data.len() < Self::SIZE || data[0..8] != Self::DISCRIMINATOR {
+            return None;
+        }
+
+        let read_u64 = |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap_or([0u8; 8]));
+        let seller = Pubkey::new_from_array(data[16..48].try_into().ok()?);
+
+        Some(Self {
+            address: address.to_string(),
+            titan_id: read_u64(8),
+            seller: seller.to_string(),
+            price: read_u64(48),
+            created_at: read_u64(56) as i64,
+            expires_at: read_u64(64) as i64,
+        })
+    }
+
+    fn seller_pubkey(&self) -> ApiResult<Pubkey> {
+        Pubkey::from_str(&self.seller)
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Invalid listing seller: {}", e)))
+    }
+
+    fn address_pubkey(&self) -> ApiResult<Pubkey> {
+        Pubkey::from_str(&self.address)
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Invalid listing address: {}", e)))
+    }
+}
+
+/// Reject an evolution the Titan NFT program would refuse
+pub fn check_evolution(titan: &OnchainTitan, new_species_id: u16) -> ApiResult<()> {
+    breach_evolution::check(titan.species_id, titan.element_type, titan.level, new_species_id).map_err(|e| {
+        match e {
+            EvolutionError::LevelTooLow => AppError::BadRequest(format!(
+                "Titan must be level {} to evolve",
+                breach_evolution::MIN_LEVEL
+            )),
+            EvolutionError::InvalidTarget => {
+                let allowed: Vec<String> = breach_evolution::targets(titan.species_id, titan.element_type)
+                    .map(|id| id.to_string())
+                    .collect();
+                AppError::BadRequest(if allowed.is_empty() {
+                    format!("Species {} cannot evolve", titan.species_id)
+                } else {
+                    format!(
+                        "Species {} cannot evolve into {} (allowed: {})",
+                        titan.species_id,
+                        new_species_id,
+                        allowed.join(", ")
+                    )
+                })
+            }
+        }
+    })
+}
+
+/// Titan NFT `TitanData` account (150 bytes packed), fields fusion depends on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnchainTitan {
+    pub titan_id: u64,
+    pub species_id: u16,
+    pub threat_class: u8,
+    pub element_type: u8,
+    pub power: u8,
+    pub fortitude: u8,
+    pub velocity: u8,
+    pub resonance: u8,
+    /// [ATK, SPD, DEF, GRW, SKL, MUT]
+    pub genes: [u8; 6],
+    pub level: u8,
+    pub experience: u32,
+    pub owner: Pubkey,
+    pub generation: u8,
+}
+
+impl OnchainTitan {
+    /// Account size in bytes
+    pub const SIZE: usize = 150;
+
+    /// Account discriminator ("TITANDAT")
+    pub const DISCRIMINATOR: [u8; 8] = *b"TITANDAT";
+
+    /// Parse a Titan account, returning `None` for foreign or short accounts
+    pub fn from_account_data(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::SIZE || data[0..8] != Self::DISCRIMINATOR {
+            return None;
+        }
+
+        Some(Self {
+            titan_id: u64::from_le_bytes(data[8..16].try_into().ok()?),
+            species_id: u16::from_le_bytes(data[16..18].try_into().ok()?),
+            threat_class: data[18],
+            element_type: data[19],
+            power: data[20],
+            fortitude: data[21],
+            velocity: data[22],
+            resonance: data[23],
+            genes: data[24..30].try_into().ok()?,
+            level: data[30],
+            experience: u32::from_le_bytes(data[31..35].try_into().ok()?),
+            owner: Pubkey::new_from_array(data[76..108].try_into().ok()?),
+            generation: data[116],
+        })
+    }
+}
+
+/// Escrow transaction result (user-only signature)
+#[derive(Debug, Clone, Serialize)]
+pub struct EscrowTransactionResult {
+    pub serialized_transaction: String,
+    pub message_to_sign: String,
+    pub recent_blockhash: String,
+    pub titan_id: u64,
+    pub listing_pda: String,
+}
+
+impl EscrowTransactionResult {
+    fn new(result: SimpleTransactionResult, titan_id: u64, listing_pda: Pubkey) -> Self {
+        Self {
+            serialized_transaction: result.serialized_transaction,
+            message_to_sign: result.message_to_sign,
+            recent_blockhash: result.recent_blockhash,
+            titan_id,
+            listing_pda: listing_pda.to_string(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1759,9 +2903,37 @@ mod tests {
             game_program_id: "DLk2GnDu9AYn7PeLprEDHDYH9UWKENX47UqqfeiQBaSX".to_string(),
             breach_token_mint: "CSH2Vz4MbgTLzB9SYJ7gBwNsyu7nKpbvEJzKQLgmmjt4".to_string(),
             backend_keypair_path: "~/.config/solana/id.json".to_string(),
+            mint_batch_window_ms: 0,
+            confirm_timeout_seconds: 30,
         }
     }
 
+    #[test]
+    fn test_check_evolution_matches_program_table() {
+        let titan = OnchainTitan {
+            titan_id: 1,
+            species_id: 1_004,
+            threat_class: 1,
+            element_type: 1,
+            power: 50,
+            fortitude: 50,
+            velocity: 50,
+            resonance: 50,
+            genes: [0; 6],
+            level: 30,
+            experience: 0,
+            owner: Pubkey::default(),
+            generation: 0,
+        };
+        assert!(check_evolution(&titan, 1_104).is_ok());
+
+        let err = check_evolution(&titan, 3_409).unwrap_err();
+        assert!(matches!(&err, AppError::BadRequest(msg) if msg.contains("allowed: 1104")), "{:?}", err);
+
+        let under_leveled = OnchainTitan { level: 29, ..titan };
+        assert!(matches!(check_evolution(&under_leveled, 1_104), Err(AppError::BadRequest(_))));
+    }
+
     #[test]
     fn test_create_service_without_keypair() {
         let config = test_config();
@@ -1769,22 +2941,199 @@ mod tests {
         assert!(service.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_mint_batch_size_checked_before_rpc() {
+        let service = SolanaService::new_without_keypair(&test_config()).unwrap();
+        let params = MintParams {
+            player_wallet: "11111111111111111111111111111111".to_string(),
+            element: Element::Storm,
+            threat_class: 1,
+            species_id: 1,
+            genes: [0u8; 32],
+        };
+
+        for mints in [Vec::new(), vec![params; MAX_MINT_BATCH + 1]] {
+            let err = service.mint_titan_nft_batch(mints).await.unwrap_err();
+            assert!(matches!(err, AppError::BadRequest(_)), "unexpected error: {:?}", err);
+        }
+    }
+
+    #[test]
+    fn test_chunk_mints() {
+        let mints: Vec<u32> = (0..12).collect();
+        let sizes = |chunks: Vec<&[u32]>| chunks.iter().map(|c| c.len()).collect::<Vec<_>>();
+
+        assert_eq!(sizes(chunk_mints(&mints, |_| true)), vec![5, 5, 2]);
+        assert_eq!(sizes(chunk_mints(&mints, |c| c.len() <= 3)), vec![3, 3, 3, 3]);
+        // Oversized single mints still go out alone
+        assert_eq!(sizes(chunk_mints(&mints[..2], |_| false)), vec![1, 1]);
+        assert!(chunk_mints(&mints[..0], |_| true).is_empty());
+
+        let chunks = chunk_mints(&mints, |c| c.len() <= 4);
+        assert_eq!(chunks.concat(), mints);
+    }
+
+    #[test]
+    fn test_full_mint_batch_fits_one_transaction() {
+        let service = SolanaService::new_without_keypair(&test_config()).unwrap();
+        let params = MintParams {
+            player_wallet: "11111111111111111111111111111111".to_string(),
+            element: Element::Storm,
+            threat_class: 1,
+            species_id: 1,
+            genes: [0u8; 32],
+        };
+
+        assert!(service.mint_batch_fits(&vec![params.clone(); MAX_MINT_BATCH]));
+        // A batch past the limit is too large to send at all
+        assert!(!service.mint_batch_fits(&vec![params; 3 * MAX_MINT_BATCH]));
+    }
+
     #[test]
     fn test_titan_mint_data_serialization() {
         let data = TitanMintData {
-            element: 1,
-            threat_class: 3,
             species_id: 42,
-            genes: [0u8; 32],
+            threat_class: 3,
+            element_type: 1,
+            power: 50,
+            fortitude: 60,
+            velocity: 70,
+            resonance: 80,
+            genes: [1, 2, 3, 4, 5, 6],
+            capture_lat: 35_689_500,
+            capture_lng: 139_691_700,
+            nonce: 7,
+            signature: [0u8; 64],
         };
-        
-        let bytes = borsh::to_vec(&data).unwrap();
-        assert!(!bytes.is_empty());
-        
-        let decoded: TitanMintData = BorshDeserialize::try_from_slice(&bytes).unwrap();
-        assert_eq!(decoded.element, 1);
-        assert_eq!(decoded.threat_class, 3);
-        assert_eq!(decoded.species_id, 42);
+
+        let bytes = data.to_bytes();
+        assert_eq!(bytes.len(), std::mem::size_of::<TitanMintData>());
+        assert_eq!(bytes.len(), 94);
+        assert_eq!(&bytes[0..2], &42u16.to_le_bytes());
+        assert_eq!(bytes[2], 3);
+        assert_eq!(bytes[3], 1);
+        assert_eq!(&bytes[8..14], &[1, 2, 3, 4, 5, 6]);
+        assert_eq!(&bytes[14..18], &35_689_500i32.to_le_bytes());
+        assert_eq!(&bytes[22..30], &7u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_onchain_listing_parse() {
+        let address = Pubkey::new_unique();
+        let seller = Pubkey::new_unique();
+
+        let mut data = Vec::with_capacity(OnchainListing::SIZE);
+        data.extend_from_slice(&OnchainListing::DISCRIMINATOR);
+        data.extend_from_slice(&9u64.to_le_bytes());
+        data.extend_from_slice(seller.as_ref());
+        data.extend_from_slice(&5_000_000_000u64.to_le_bytes());
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        data.extend_from_slice(&1_700_086_400i64.to_le_bytes());
+        data.push(254);
+
+        let listing = OnchainListing::from_account_data(address, &data).unwrap();
+        assert_eq!(listing.titan_id, 9);
+        assert_eq!(listing.seller, seller.to_string());
+        assert_eq!(listing.price, 5_000_000_000);
+        assert_eq!(listing.expires_at, 1_700_086_400);
+
+        // Closed listings are zeroed before the account is reclaimed
+        assert!(OnchainListing::from_account_data(address, &[0u8; OnchainListing::SIZE]).is_none());
+    }
+
+    /// TitanData in contract field order, so a parser offset that drifts
+    /// from the packed layout reads the neighbouring field
+    fn titan_account_fixture(owner: Pubkey) -> Vec<u8> {
+        let mut data = Vec::with_capacity(OnchainTitan::SIZE);
+        data.extend_from_slice(&OnchainTitan::DISCRIMINATOR);
+        data.extend_from_slice(&0x0102_0304_0506_0708u64.to_le_bytes()); // titan_id
+        data.extend_from_slice(&2_301u16.to_le_bytes()); // species_id
+        data.push(4); // threat_class
+        data.push(3); // element_type
+        data.extend_from_slice(&[61, 62, 63, 64]); // power, fortitude, velocity, resonance
+        data.extend_from_slice(&[11, 12, 13, 14, 15, 16]); // genes
+        data.push(42); // level
+        data.extend_from_slice(&123_456u32.to_le_bytes()); // experience
+        data.push(77); // link_strength
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes()); // captured_at
+        data.extend_from_slice(&[0xAA; 32]); // original_owner
+        data.extend_from_slice(owner.as_ref());
+        data.extend_from_slice(&u64::MAX.to_le_bytes()); // capture_location
+        data.push(2); // generation
+        data.extend_from_slice(&5u64.to_le_bytes()); // parent_a
+        data.extend_from_slice(&6u64.to_le_bytes()); // parent_b
+        data.push(253); // bump
+        data.push(1); // listed
+        data.extend_from_slice(&[0xEE; 15]); // reserved
+        data
+    }
+
+    #[test]
+    fn test_onchain_titan_parse() {
+        let owner = Pubkey::new_unique();
+        let data = titan_account_fixture(owner);
+        assert_eq!(data.len(), OnchainTitan::SIZE);
+
+        let titan = OnchainTitan::from_account_data(&data).unwrap();
+        assert_eq!(titan.titan_id, 0x0102_0304_0506_0708);
+        assert_eq!(titan.species_id, 2_301);
+        assert_eq!((titan.threat_class, titan.element_type), (4, 3));
+        assert_eq!(
+            [titan.power, titan.fortitude, titan.velocity, titan.resonance],
+            [61, 62, 63, 64]
+        );
+        assert_eq!(titan.genes, [11, 12, 13, 14, 15, 16]);
+        assert_eq!(titan.level, 42);
+        assert_eq!(titan.experience, 123_456);
+        assert_eq!(titan.owner, owner);
+        assert_eq!(titan.generation, 2);
+    }
+
+    #[test]
+    fn test_onchain_titan_parse_rejects_foreign_accounts() {
+        let data = titan_account_fixture(Pubkey::new_unique());
+
+        assert!(OnchainTitan::from_account_data(&data[..OnchainTitan::SIZE - 1]).is_none());
+        assert!(OnchainTitan::from_account_data(&[0u8; OnchainTitan::SIZE]).is_none());
+
+        let mut listing = data.clone();
+        listing[0..8].copy_from_slice(&OnchainListing::DISCRIMINATOR);
+        assert!(OnchainTitan::from_account_data(&listing).is_none());
+    }
+
+    #[test]
+    fn test_player_capture_state_parse() {
+        let mut data = Vec::with_capacity(PlayerCaptureState::SIZE);
+        data.extend_from_slice(&PlayerCaptureState::DISCRIMINATOR);
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.extend_from_slice(&3u64.to_le_bytes());
+        data.push(255);
+
+        assert_eq!(PlayerCaptureState::nonce_from_account_data(&data), Some(3));
+        assert_eq!(PlayerCaptureState::nonce_from_account_data(&data[..40]), None);
+        assert_eq!(PlayerCaptureState::nonce_from_account_data(&[0u8; PlayerCaptureState::SIZE]), None);
+    }
+
+    #[test]
+    fn test_reward_ledger_parse() {
+        let mut data = Vec::with_capacity(RewardLedgerState::SIZE);
+        data.extend_from_slice(&RewardLedgerState::DISCRIMINATOR);
+        data.extend_from_slice(&[7u8; 32]);
+        data.extend_from_slice(&600u64.to_le_bytes());
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        data.extend_from_slice(&250u64.to_le_bytes());
+        data.push(254);
+
+        assert_eq!(
+            RewardLedgerState::from_account_data(&data),
+            Some(RewardLedgerState {
+                accrued_amount: 600,
+                last_accrual_ts: 1_700_000_000,
+                total_claimed: 250,
+            })
+        );
+        assert_eq!(RewardLedgerState::from_account_data(&data[..64]), None);
+        assert_eq!(RewardLedgerState::from_account_data(&[0u8; RewardLedgerState::SIZE]), None);
     }
 
     #[test]
@@ -1818,4 +3167,181 @@ mod tests {
         assert_eq!(decoded.battle_type, 1);
         assert_eq!(decoded.result, 1);
     }
+
+    #[test]
+    fn test_program_error_classification() {
+        let service = SolanaService::new_without_keypair(&test_config()).unwrap();
+        let payer = Pubkey::new_unique();
+
+        let transaction = Transaction::new_with_payer(
+            &[
+                Instruction::new_with_bytes(service.titan_program_id, &[0], vec![]),
+                Instruction::new_with_bytes(service.game_program_id, &[0], vec![]),
+                Instruction::new_with_bytes(TOKEN_PROGRAM_ID, &[0], vec![]),
+            ],
+            Some(&payer),
+        );
+
+        let custom = |index, code| TransactionError::InstructionError(index, InstructionError::Custom(code));
+
+        let info = service.program_error(&transaction, &custom(0, 6306)).unwrap();
+        assert_eq!(info.program, Program::TitanNft);
+        assert_eq!(info.name, "TitanListed");
+
+        let info = service.program_error(&transaction, &custom(1, 7701)).unwrap();
+        assert_eq!(info.program, Program::GameLogic);
+        assert_eq!(info.name, "ListingExpired");
+
+        // Code from the wrong program's range, another program, or not custom
+        assert!(service.program_error(&transaction, &custom(0, 7701)).is_none());
+        assert!(service.program_error(&transaction, &custom(2, 6000)).is_none());
+        assert!(service.program_error(&transaction, &custom(5, 6000)).is_none());
+        assert!(service
+            .program_error(&transaction, &TransactionError::InstructionError(0, InstructionError::InvalidArgument))
+            .is_none());
+        assert!(service.program_error(&transaction, &TransactionError::BlockhashNotFound).is_none());
+    }
+
+    #[test]
+    fn test_breach_payment_must_match_sale() {
+        let service = SolanaService::new_without_keypair(&test_config()).unwrap();
+        let (buyer, seller, treasury) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        let expected = service.breach_payment_instructions(&buyer, &seller, &treasury, 975, 25).unwrap();
+        let paid = Transaction::new_with_payer(&expected, Some(&buyer));
+        assert!(contains_instructions(&paid, &expected));
+
+        // Seller paid less than the sale price
+        let short = service.breach_payment_instructions(&buyer, &seller, &treasury, 900, 25).unwrap();
+        assert!(!contains_instructions(&Transaction::new_with_payer(&short, Some(&buyer)), &expected));
+
+        // Paid to someone else
+        let other = service.breach_payment_instructions(&buyer, &buyer, &treasury, 975, 25).unwrap();
+        assert!(!contains_instructions(&Transaction::new_with_payer(&other, Some(&buyer)), &expected));
+    }
+
+    /// Service whose RPC calls are answered by a `MockSender` configured by `url`
+    fn mock_service(url: &str) -> SolanaService {
+        SolanaService {
+            rpc_client: std::sync::Arc::new(RpcClient::new_mock(url.to_string())),
+            ..SolanaService::new_without_keypair(&test_config()).unwrap()
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_confirm_timeout() {
+        let payer = Keypair::new();
+        let transaction = Transaction::new_signed_with_payer(
+            &[],
+            Some(&payer.pubkey()),
+            &[&payer],
+            solana_sdk::hash::Hash::default(),
+        );
+
+        // Never seen by the cluster: gives up at the deadline with a 503
+        let started = tokio::time::Instant::now();
+        let err = mock_service("sig_not_found")
+            .send_with_confirm_timeout(&transaction, Duration::from_secs(10))
+            .await
+            .unwrap_err();
+        assert!(matches!(&err, AppError::ServiceUnavailable(msg) if msg == "confirmation timeout"), "{:?}", err);
+        assert_eq!(started.elapsed(), Duration::from_secs(10));
+        assert_eq!(err.status_and_body().0, axum::http::StatusCode::SERVICE_UNAVAILABLE);
+
+        let signature = mock_service("succeeds")
+            .send_with_confirm_timeout(&transaction, Duration::from_secs(10))
+            .await
+            .unwrap();
+        assert_eq!(signature, transaction.signatures[0]);
+
+        // A failed transaction is an error straight away, not a timeout
+        let err = mock_service("instruction_error")
+            .send_with_confirm_timeout(&transaction, Duration::from_secs(10))
+            .await
+            .unwrap_err();
+        assert!(!matches!(err, AppError::ServiceUnavailable(_)), "{:?}", err);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_blockhash_cached_within_ttl() {
+        use solana_client::rpc_request::RpcRequest;
+
+        // The mocked blockhash is served once; later fetches get the mock
+        // client's default
+        let first = Hash::new_unique();
+        let mocks = [(
+            RpcRequest::GetLatestBlockhash,
+            serde_json::json!({
+                "context": { "slot": 1 },
+                "value": { "blockhash": first.to_string(), "lastValidBlockHeight": 100 },
+            }),
+        )];
+        let service = SolanaService {
+            rpc_client: std::sync::Arc::new(RpcClient::new_mock_with_mocks(
+                "succeeds".to_string(),
+                mocks.into_iter().collect(),
+            )),
+            ..SolanaService::new_without_keypair(&test_config()).unwrap()
+        };
+        let payer = Pubkey::new_unique();
+        let build = || async {
+            let instruction = Instruction::new_with_bytes(Pubkey::new_unique(), &[1], vec![]);
+            service.build_simple_transaction(&payer, instruction).await.unwrap().recent_blockhash
+        };
+
+        // Two builds within the TTL share one fetch
+        assert_eq!(build().await, first.to_string());
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_eq!(build().await, first.to_string());
+
+        // Past the TTL it's fetched again
+        tokio::time::advance(Duration::from_secs(11)).await;
+        let refreshed = build().await;
+        assert_ne!(refreshed, first.to_string());
+
+        // A send rejected for an unknown blockhash drops it early
+        *service.blockhash.write().unwrap() = Some((first, tokio::time::Instant::now()));
+        let transaction = Transaction::new_with_payer(&[], Some(&payer));
+        service.classify_send_error(&transaction, "Transaction", TransactionError::BlockhashNotFound.into());
+        assert_eq!(build().await, refreshed);
+    }
+
+    #[tokio::test]
+    async fn test_stale_blockhash_on_client_send() {
+        use solana_client::rpc_request::RpcRequest;
+
+        // The cluster reports the sent transaction failed for an unknown blockhash
+        let mocks = [(
+            RpcRequest::GetSignatureStatuses,
+            serde_json::json!({
+                "context": { "slot": 1 },
+                "value": [{
+                    "slot": 1,
+                    "confirmations": null,
+                    "err": "BlockhashNotFound",
+                    "status": { "Err": "BlockhashNotFound" },
+                    "confirmationStatus": "processed",
+                }],
+            }),
+        )];
+        let service = SolanaService {
+            rpc_client: std::sync::Arc::new(RpcClient::new_mock_with_mocks(
+                "succeeds".to_string(),
+                mocks.into_iter().collect(),
+            )),
+            ..SolanaService::new_without_keypair(&test_config()).unwrap()
+        };
+        let payer = Keypair::new();
+        let transaction =
+            Transaction::new_signed_with_payer(&[], Some(&payer.pubkey()), &[&payer], Hash::new_unique());
+        *service.blockhash.write().unwrap() = Some((transaction.message.recent_blockhash, tokio::time::Instant::now()));
+
+        let err = service.send_signed(&transaction).await.unwrap_err();
+        assert!(matches!(err, AppError::StaleBlockhash), "{:?}", err);
+        let (status, body) = err.status_and_body();
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "STALE_BLOCKHASH");
+        // The next build won't reuse it
+        assert!(service.blockhash.read().unwrap().is_none());
+    }
 }
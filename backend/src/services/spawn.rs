@@ -4,21 +4,21 @@ use chrono::{Datelike, Duration, Utc};
 use rand::Rng;
 use uuid::Uuid;
 
-use crate::config::AppConfig;
+use crate::config::{SharedGameConfig, SpawnSchedule};
 use crate::db::Database;
 use crate::error::ApiResult;
-use crate::models::{Element, POI, TerrainType, TitanSpawn};
+use crate::models::{Element, SpawnEvent, Species, POI, TerrainType, TitanSpawn};
 
 /// Spawn service for generating Titans
 #[derive(Clone)]
 pub struct SpawnService {
-    config: AppConfig,
+    game: SharedGameConfig,
     db: Database,
 }
 
 impl SpawnService {
-    pub fn new(config: AppConfig, db: Database) -> Self {
-        Self { config, db }
+    pub fn new(game: SharedGameConfig, db: Database) -> Self {
+        Self { game, db }
     }
 
     /// Run spawn cycle for a region
@@ -29,31 +29,179 @@ impl SpawnService {
         let pois = self.get_eligible_pois(region_id).await?;
 
         for poi in pois {
-            // Check if POI already has active Titan
-            if self.poi_has_active_titan(poi.id).await? {
-                continue;
+            if let Some(titan) = self.try_spawn_at(&poi).await? {
+                spawns.push(titan);
             }
+        }
+
+        tracing::info!(
+            "Spawn cycle complete: {} new Titans, by class I-V {:?}",
+            spawns.len(),
+            class_counts(&spawns)
+        );
+
+        Ok(spawns)
+    }
 
-            // Calculate spawn probability
-            let spawn_chance = self.calculate_spawn_probability(&poi);
+    /// Run the spawn cycle only at POIs inside the given 5-character geohash
+    /// prefixes (regions with players online), keeping each region at or
+    /// below `game.max_spawns_per_region` active Titans
+    pub async fn run_spawn_cycle_for_regions(&self, geohashes: Vec<String>) -> ApiResult<Vec<TitanSpawn>> {
+        let mut spawns = Vec::new();
+        let cap = self.game.read().await.max_spawns_per_region as i64;
 
-            // Generate random outside of async context
-            let should_spawn = {
-                let mut rng = rand::thread_rng();
-                rng.gen::<f64>() < spawn_chance
+        for prefix in geohashes {
+            let Ok(bbox) = geohash::decode_bbox(&prefix) else {
+                tracing::warn!("Skipping invalid spawn region {:?}", prefix);
+                continue;
             };
 
-            if should_spawn {
-                let titan = self.generate_titan_for_poi(&poi).await?;
-                spawns.push(titan);
+            let mut room = cap - self.active_spawns_in_region(&prefix).await?;
+            if room <= 0 {
+                continue;
+            }
+
+            // Busiest POIs get the first chance at the region's free slots
+            let pois = sqlx::query_as::<_, POI>(
+                r#"
+                SELECT * FROM pois
+                WHERE is_active = true
+                  AND location_lat >= $1 AND location_lat < $2
+                  AND location_lng >= $3 AND location_lng < $4
+                ORDER BY spawn_weight DESC
+                "#,
+            )
+            .bind(bbox.min().y)
+            .bind(bbox.max().y)
+            .bind(bbox.min().x)
+            .bind(bbox.max().x)
+            .fetch_all(&self.db.pg)
+            .await?;
+
+            for poi in pois {
+                if room <= 0 {
+                    break;
+                }
+                if let Some(titan) = self.try_spawn_at(&poi).await? {
+                    spawns.push(titan);
+                    room -= 1;
+                }
+            }
+        }
+
+        tracing::info!(
+            "Regional spawn cycle complete: {} new Titans, by class I-V {:?}",
+            spawns.len(),
+            class_counts(&spawns)
+        );
+
+        Ok(spawns)
+    }
+
+    /// Roll a spawn at a POI that has no active Titan, unless its geohash
+    /// cell already holds `game.max_active_per_geohash` active Titans
+    async fn try_spawn_at(&self, poi: &POI) -> ApiResult<Option<TitanSpawn>> {
+        // Check if POI already has active Titan
+        if self.poi_has_active_titan(poi.id).await? {
+            return Ok(None);
+        }
+
+        // Calculate spawn probability
+        let spawn_chance = self.calculate_spawn_probability(poi);
+
+        // Generate random outside of async context
+        let should_spawn = {
+            let mut rng = rand::thread_rng();
+            rng.gen::<f64>() < spawn_chance
+        };
+
+        if !should_spawn {
+            return Ok(None);
+        }
+
+        // Dense areas would otherwise fill up with Titans
+        let cell = geohash::encode(
+            geohash::Coord {
+                x: poi.location_lng,
+                y: poi.location_lat,
+            },
+            5,
+        )
+        .unwrap_or_default();
+        let cap = self.game.read().await.max_active_per_geohash as i64;
+        if self.active_spawns_in_region(&cell).await? >= cap {
+            tracing::debug!("Skipping spawn at POI {}: geohash cell {} is full", poi.id, cell);
+            return Ok(None);
+        }
+
+        self.generate_titan_for_poi(poi, None).await.map(Some)
+    }
+
+    /// Spawn an event's Titans at the highest-weight POIs without an active
+    /// Titan inside its geohash filter. The usual spawn roll and density caps
+    /// don't apply; `spawn_config.max_spawns` bounds the run instead.
+    pub async fn run_event_spawns(&self, event: &SpawnEvent) -> ApiResult<Vec<(TitanSpawn, String)>> {
+        let mut room = event.spawn_config.max_spawns as i64;
+        let mut spawns = Vec::new();
+
+        for bounds in filter_bounds(&event.geohash_filter) {
+            if room <= 0 {
+                break;
+            }
+
+            let pois = sqlx::query_as::<_, POI>(
+                r#"
+                SELECT * FROM pois p
+                WHERE is_active = true
+                  AND ($1::float8 IS NULL OR (location_lat >= $1 AND location_lat < $2
+                       AND location_lng >= $3 AND location_lng < $4))
+                  AND NOT EXISTS (
+                      SELECT 1 FROM titan_spawns t
+                      WHERE t.poi_id = p.id
+                        AND t.expires_at > NOW()
+                        AND (t.captured_by IS NULL OR t.capture_count < t.max_captures)
+                  )
+                ORDER BY spawn_weight DESC
+                LIMIT $5
+                "#,
+            )
+            .bind(bounds.map(|b| b.min().y))
+            .bind(bounds.map(|b| b.max().y))
+            .bind(bounds.map(|b| b.min().x))
+            .bind(bounds.map(|b| b.max().x))
+            .bind(room)
+            .fetch_all(&self.db.pg)
+            .await?;
+
+            for poi in pois {
+                let titan = self.generate_titan_for_poi(&poi, Some(event)).await?;
+                spawns.push((titan, poi.name));
+                room -= 1;
             }
         }
 
-        tracing::info!("Spawn cycle complete: {} new Titans", spawns.len());
+        tracing::info!("Event {:?} spawned {} Titans", event.name, spawns.len());
 
         Ok(spawns)
     }
 
+    /// Active Titans whose geohash starts with `prefix`
+    async fn active_spawns_in_region(&self, prefix: &str) -> ApiResult<i64> {
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM titan_spawns
+            WHERE geohash LIKE $1 || '%'
+              AND expires_at > NOW()
+              AND (captured_by IS NULL OR capture_count < max_captures)
+            "#,
+        )
+        .bind(prefix)
+        .fetch_one(&self.db.pg)
+        .await?;
+
+        Ok(count)
+    }
+
     /// Get POIs eligible for spawning
     async fn get_eligible_pois(&self, region_id: Option<Uuid>) -> ApiResult<Vec<POI>> {
         let pois = if let Some(rid) = region_id {
@@ -122,17 +270,41 @@ impl SpawnService {
         base_probability * weight_factor * time_factor * day_factor
     }
 
-    /// Generate a Titan for a POI
-    async fn generate_titan_for_poi(&self, poi: &POI) -> ApiResult<TitanSpawn> {
+    /// Generate a Titan for a POI. Event Titans take their threat class and
+    /// species from the event and last until it ends.
+    async fn generate_titan_for_poi(&self, poi: &POI, event: Option<&SpawnEvent>) -> ApiResult<TitanSpawn> {
+        let (schedule, rarity) = {
+            let game = self.game.read().await;
+            (game.spawn_schedule.clone(), game.spawn_rarity.clone())
+        };
+
         // Generate all random values BEFORE any await
         let (element, threat_class, spawn_lat, spawn_lng, geohash, species_id, genes, max_captures, duration) = {
             let mut rng = rand::thread_rng();
 
-            // Determine element based on terrain
-            let element = self.determine_element_sync(poi.terrain_type, &mut rng);
-
             // Determine threat class
-            let threat_class = self.determine_threat_class_sync(poi, &mut rng);
+            let threat_class = match event {
+                Some(event) => weighted_class(&event.spawn_config.threat_class_weights, &mut rng),
+                None => weighted_class(&rarity.weights_for(poi.spawn_weight), &mut rng),
+            };
+
+            // An event's pool species of that class, if it has any
+            let event_species = event.and_then(|event| {
+                let pool: Vec<Species> = event
+                    .spawn_config
+                    .species_pool
+                    .iter()
+                    .filter_map(|&id| Species::from_id(id))
+                    .filter(|species| species.threat_class == threat_class)
+                    .collect();
+                (!pool.is_empty()).then(|| pool[rng.gen_range(0..pool.len())])
+            });
+
+            // Determine element based on terrain
+            let element = match event_species {
+                Some(species) => species.element,
+                None => self.determine_element_sync(poi.terrain_type, &schedule, &mut rng),
+            };
 
             // Generate random position within POI radius
             let angle = rng.gen::<f64>() * 2.0 * std::f64::consts::PI;
@@ -165,7 +337,10 @@ impl SpawnService {
             };
 
             // Generate species ID and genes
-            let species_id = self.generate_species_id_sync(element, threat_class, &mut rng);
+            let species_id = match event_species {
+                Some(species) => species.id,
+                None => self.generate_species_id_sync(element, threat_class, &mut rng),
+            };
             let genes = self.generate_genes_sync(&mut rng);
 
             // Determine max captures
@@ -186,8 +361,8 @@ impl SpawnService {
             r#"
             INSERT INTO titan_spawns 
             (poi_id, location_lat, location_lng, geohash, element, threat_class, 
-             species_id, genes, expires_at, max_captures)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+             species_id, genes, expires_at, max_captures, event_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
             RETURNING *
             "#,
         )
@@ -199,8 +374,9 @@ impl SpawnService {
         .bind(threat_class)
         .bind(species_id)
         .bind(&genes)
-        .bind(Utc::now() + duration)
+        .bind(event.map_or(Utc::now() + duration, |event| event.end_at))
         .bind(max_captures)
+        .bind(event.map(|event| event.id))
         .fetch_one(&self.db.pg)
         .await?;
 
@@ -215,106 +391,74 @@ impl SpawnService {
         Ok(titan)
     }
 
-    /// Determine element based on terrain (sync version with external rng)
-    fn determine_element_sync(&self, terrain: TerrainType, rng: &mut impl Rng) -> Element {
-        let roll = rng.gen::<f64>() * 100.0;
-
-        match terrain {
-            TerrainType::Water => {
-                if roll < 70.0 {
-                    Element::Abyssal
-                } else if roll < 90.0 {
-                    Element::Storm
-                } else {
-                    Element::Parasitic
-                }
-            }
-            TerrainType::Mountain => {
-                if roll < 60.0 {
-                    Element::Volcanic
-                } else if roll < 85.0 {
-                    Element::Storm
-                } else {
-                    Element::Ossified
-                }
-            }
-            TerrainType::Urban => {
-                if roll < 40.0 {
-                    Element::Storm
-                } else if roll < 75.0 {
-                    Element::Void
-                } else {
-                    Element::Parasitic
-                }
-            }
-            TerrainType::Forest => {
-                if roll < 65.0 {
-                    Element::Parasitic
-                } else if roll < 85.0 {
-                    Element::Ossified
-                } else {
-                    Element::Abyssal
-                }
-            }
-            TerrainType::Desert => {
-                if roll < 50.0 {
-                    Element::Volcanic
-                } else if roll < 85.0 {
-                    Element::Ossified
-                } else {
-                    Element::Void
-                }
-            }
-            TerrainType::Coastal => {
-                if roll < 45.0 {
-                    Element::Abyssal
-                } else if roll < 80.0 {
-                    Element::Storm
-                } else {
-                    Element::Volcanic
-                }
-            }
-            TerrainType::Arctic => {
-                if roll < 60.0 {
-                    Element::Ossified
-                } else if roll < 85.0 {
-                    Element::Void
-                } else {
-                    Element::Storm
-                }
-            }
-        }
-    }
-
-    /// Determine threat class based on POI category (sync version)
-    fn determine_threat_class_sync(&self, poi: &POI, rng: &mut impl Rng) -> i16 {
-        let roll = rng.gen::<f64>() * 100.0;
-
-        // Base distribution
-        let mut weights = [60.0, 25.0, 10.0, 4.0, 1.0]; // Class I-V
-
-        // Adjust based on POI spawn weight (higher weight = better chances)
-        if poi.spawn_weight >= 4.0 {
-            weights[2] *= 2.0; // Class III
-            weights[3] *= 3.0; // Class IV
-            weights[4] *= 5.0; // Class V
-        } else if poi.spawn_weight >= 3.0 {
-            weights[2] *= 1.5;
-            weights[3] *= 2.0;
+    /// Determine element from terrain weights scaled by the spawn schedule
+    /// for the current UTC time (sync version with external rng)
+    fn determine_element_sync(
+        &self,
+        terrain: TerrainType,
+        schedule: &SpawnSchedule,
+        rng: &mut impl Rng,
+    ) -> Element {
+        let now = Utc::now();
+        let weights = Self::terrain_element_weights(terrain)
+            .map(|(element, weight)| (element, weight * schedule.multiplier(element, now.hour(), now.minute())));
+
+        let total: f64 = weights.iter().map(|(_, weight)| weight).sum();
+        if total <= 0.0 {
+            return weights[0].0;
         }
 
-        let total: f64 = weights.iter().sum();
-        let normalized: Vec<f64> = weights.iter().map(|w| w / total * 100.0).collect();
-
+        let roll = rng.gen::<f64>() * total;
         let mut cumulative = 0.0;
-        for (i, &weight) in normalized.iter().enumerate() {
+        for &(element, weight) in &weights {
             cumulative += weight;
             if roll < cumulative {
-                return (i + 1) as i16;
+                return element;
             }
         }
 
-        1 // Default to Class I
+        weights[0].0
+    }
+
+    /// Base element weights (percent) for each terrain
+    fn terrain_element_weights(terrain: TerrainType) -> [(Element, f64); 3] {
+        match terrain {
+            TerrainType::Water => [
+                (Element::Abyssal, 70.0),
+                (Element::Storm, 20.0),
+                (Element::Parasitic, 10.0),
+            ],
+            TerrainType::Mountain => [
+                (Element::Volcanic, 60.0),
+                (Element::Storm, 25.0),
+                (Element::Ossified, 15.0),
+            ],
+            TerrainType::Urban => [
+                (Element::Storm, 40.0),
+                (Element::Void, 35.0),
+                (Element::Parasitic, 25.0),
+            ],
+            TerrainType::Forest => [
+                (Element::Parasitic, 65.0),
+                (Element::Ossified, 20.0),
+                (Element::Abyssal, 15.0),
+            ],
+            TerrainType::Desert => [
+                (Element::Volcanic, 50.0),
+                (Element::Ossified, 35.0),
+                (Element::Void, 15.0),
+            ],
+            TerrainType::Coastal => [
+                (Element::Abyssal, 45.0),
+                (Element::Storm, 35.0),
+                (Element::Volcanic, 20.0),
+            ],
+            TerrainType::Arctic => [
+                (Element::Ossified, 60.0),
+                (Element::Void, 25.0),
+                (Element::Storm, 15.0),
+            ],
+        }
     }
 
     /// Generate species ID (sync version)
@@ -332,13 +476,116 @@ impl SpawnService {
     }
 }
 
-// Helper trait for hour
+/// Threat class (I-V) drawn from relative weights; class I when they are all zero
+fn weighted_class(weights: &[f64; 5], rng: &mut impl Rng) -> i16 {
+    let total: f64 = weights.iter().sum();
+    let roll = rng.gen::<f64>() * total;
+
+    let mut cumulative = 0.0;
+    for (i, &weight) in weights.iter().enumerate() {
+        cumulative += weight;
+        if roll < cumulative {
+            return (i + 1) as i16;
+        }
+    }
+
+    1 // Default to Class I
+}
+
+/// Spawns of each threat class I-V, for logging a cycle's distribution
+pub fn class_counts(spawns: &[TitanSpawn]) -> [usize; 5] {
+    let mut counts = [0; 5];
+    for spawn in spawns {
+        if let Some(count) = counts.get_mut((spawn.threat_class - 1) as usize) {
+            *count += 1;
+        }
+    }
+    counts
+}
+
+/// Bounding boxes of an event's geohash prefixes; a single `None` (no
+/// bounds) for an empty filter. Invalid prefixes are rejected when the event
+/// is created.
+pub(crate) fn filter_bounds(prefixes: &[String]) -> Vec<Option<geohash::Rect>> {
+    if prefixes.is_empty() {
+        return vec![None];
+    }
+
+    prefixes
+        .iter()
+        .filter_map(|prefix| geohash::decode_bbox(prefix).ok())
+        .map(Some)
+        .collect()
+}
+
+// Helper trait for hour and minute
 trait DateTimeHour {
     fn hour(&self) -> u32;
+    fn minute(&self) -> u32;
 }
 
 impl DateTimeHour for chrono::DateTime<Utc> {
     fn hour(&self) -> u32 {
         chrono::Timelike::hour(self)
     }
+
+    fn minute(&self) -> u32 {
+        chrono::Timelike::minute(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::config::SpawnRarity;
+
+    const DRAWS: usize = 200_000;
+
+    /// Share of each class over many seeded draws
+    fn empirical(weights: &[f64; 5], seed: u64) -> [f64; 5] {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut counts = [0usize; 5];
+        for _ in 0..DRAWS {
+            counts[(weighted_class(weights, &mut rng) - 1) as usize] += 1;
+        }
+        counts.map(|count| count as f64 / DRAWS as f64)
+    }
+
+    #[test]
+    fn test_class_distribution_matches_rarity_curve() {
+        let rarity = SpawnRarity::default();
+        for (spawn_weight, seed) in [(1.0, 1), (3.0, 2), (5.0, 3), (0.5, 4)] {
+            let weights = rarity.weights_for(spawn_weight);
+            let total: f64 = weights.iter().sum();
+            let shares = empirical(&weights, seed);
+
+            for (class, (&share, &weight)) in shares.iter().zip(&weights).enumerate() {
+                let expected = weight / total;
+                assert!(
+                    (share - expected).abs() < 0.005,
+                    "class {} at spawn weight {}: {:.4} drawn, {:.4} configured",
+                    class + 1,
+                    spawn_weight,
+                    share,
+                    expected
+                );
+            }
+        }
+
+        // Class V is 1% at an ordinary POI and several times that at a busy one
+        let ordinary = empirical(&rarity.weights_for(1.0), 5)[4];
+        let busy = empirical(&rarity.weights_for(5.0), 6)[4];
+        assert!((ordinary - 0.01).abs() < 0.002, "{}", ordinary);
+        assert!(busy > ordinary * 3.0, "{} vs {}", busy, ordinary);
+    }
+
+    #[test]
+    fn test_zero_weight_classes_never_spawn() {
+        let shares = empirical(&[0.0, 0.0, 0.0, 3.0, 1.0], 7);
+        assert_eq!(&shares[..3], &[0.0; 3]);
+        assert!((shares[3] - 0.75).abs() < 0.005, "{:?}", shares);
+    }
 }
@@ -0,0 +1,266 @@
+//! Transaction tracking service
+//!
+//! Records player-signed transactions before they are sent and polls the
+//! ones still pending, so a transaction that lands after the RPC call timed
+//! out still finishes its capture or marketplace settlement.
+
+use solana_sdk::transaction::Transaction;
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::error::{ApiResult, AppError};
+use crate::models::{
+    PendingTransaction, TrackedTransaction, TrackedTransactionKind, TrackedTransactionStatus,
+};
+use crate::services::{CaptureService, MarketplaceService, SolanaService};
+
+/// Polls before a transaction whose blockhash still looks valid is expired
+const MAX_ATTEMPTS: i32 = 20;
+
+/// Pending rows polled per run
+const POLL_BATCH_SIZE: i64 = 50;
+
+/// Left to the request that sent the transaction before the first poll
+const SEND_GRACE_SECS: i64 = 30;
+
+/// Poll summary
+#[derive(Debug, Default)]
+pub struct TransactionPollSummary {
+    pub confirmed: Vec<PendingTransaction>,
+    pub failed: u32,
+    pub expired: u32,
+    pub still_pending: u32,
+}
+
+/// Transaction tracking service
+#[derive(Clone)]
+pub struct TransactionService {
+    db: Database,
+    capture: CaptureService,
+    marketplace: MarketplaceService,
+}
+
+impl TransactionService {
+    pub fn new(db: Database, capture: CaptureService, marketplace: MarketplaceService) -> Self {
+        Self { db, capture, marketplace }
+    }
+
+    /// Record a signed transaction before it is sent; returns its signature
+    pub async fn track(&self, tracked: TrackedTransaction, transaction: &Transaction) -> ApiResult<String> {
+        let signature = transaction
+            .signatures
+            .first()
+            .ok_or_else(|| AppError::BadRequest("Transaction has no signatures".to_string()))?
+            .to_string();
+
+        // A resubmit of the same transaction keeps its row
+        sqlx::query(
+            r#"
+            INSERT INTO pending_transactions
+                (signature, kind, player_id, related_id, mint_address, recent_blockhash)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (signature) DO NOTHING
+            "#,
+        )
+        .bind(&signature)
+        .bind(tracked.kind)
+        .bind(tracked.player_id)
+        .bind(tracked.related_id)
+        .bind(tracked.mint_address)
+        .bind(transaction.message.recent_blockhash.to_string())
+        .execute(&self.db.pg)
+        .await?;
+
+        Ok(signature)
+    }
+
+    /// Record the outcome of sending a tracked transaction.
+    ///
+    /// A confirmed send is final, and so is a program error (the transaction
+    /// can't land). Anything else, such as an RPC timeout, leaves the row
+    /// pending for the scheduler to poll.
+    pub async fn record_send<T>(&self, signature: &str, result: &ApiResult<T>) {
+        let (status, error) = match result {
+            Ok(_) => (TrackedTransactionStatus::Confirmed, None),
+            Err(e @ AppError::ProgramError(_)) => (TrackedTransactionStatus::Failed, Some(e.to_string())),
+            Err(e) => {
+                tracing::warn!("Transaction {} send unresolved, polling: {}", signature, e);
+                return;
+            }
+        };
+
+        if let Err(e) = self.set_status(signature, status, error).await {
+            tracing::warn!("Failed to record transaction {} as {:?}: {}", signature, status, e);
+        }
+    }
+
+    /// A tracked transaction, if it belongs to the player
+    pub async fn get_for_player(
+        &self,
+        player_id: Uuid,
+        signature: &str,
+    ) -> ApiResult<PendingTransaction> {
+        sqlx::query_as::<_, PendingTransaction>(
+            r#"SELECT * FROM pending_transactions WHERE signature = $1 AND player_id = $2"#,
+        )
+        .bind(signature)
+        .bind(player_id)
+        .fetch_optional(&self.db.pg)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Transaction not found".to_string()))
+    }
+
+    /// Poll pending transactions, running the follow-up for each one that
+    /// finalized. Expires those that can no longer land.
+    pub async fn poll_pending(&self, solana: &SolanaService) -> ApiResult<TransactionPollSummary> {
+        let pending = sqlx::query_as::<_, PendingTransaction>(
+            r#"
+            SELECT * FROM pending_transactions
+            WHERE status = 'pending' AND created_at < NOW() - make_interval(secs => $2)
+            ORDER BY created_at
+            LIMIT $1
+            "#,
+        )
+        .bind(POLL_BATCH_SIZE)
+        .bind(SEND_GRACE_SECS as f64)
+        .fetch_all(&self.db.pg)
+        .await?;
+
+        let mut summary = TransactionPollSummary::default();
+
+        for transaction in pending {
+            let attempts = transaction.attempts + 1;
+            let (status, error) = match solana.get_transaction_status(&transaction.signature).await {
+                Ok(Some(true)) => (TrackedTransactionStatus::Confirmed, None),
+                Ok(Some(false)) => (TrackedTransactionStatus::Failed, Some("Transaction failed on-chain".to_string())),
+                Ok(None) => {
+                    // Not seen yet; it can still land while its blockhash is valid
+                    let valid = solana.is_blockhash_valid(&transaction.recent_blockhash).await;
+                    match valid {
+                        Ok(false) => (TrackedTransactionStatus::Expired, None),
+                        _ if attempts >= MAX_ATTEMPTS => (TrackedTransactionStatus::Expired, None),
+                        Ok(true) => (TrackedTransactionStatus::Pending, None),
+                        Err(e) => (TrackedTransactionStatus::Pending, Some(e.to_string())),
+                    }
+                }
+                Err(e) if attempts >= MAX_ATTEMPTS => (TrackedTransactionStatus::Expired, Some(e.to_string())),
+                Err(e) => (TrackedTransactionStatus::Pending, Some(e.to_string())),
+            };
+
+            // A follow-up error is kept on the row; the status stays final
+            let error = match status {
+                TrackedTransactionStatus::Confirmed => self.on_confirmed(&transaction).await.err().map(|e| {
+                    tracing::error!("Follow-up for transaction {} failed: {:?}", transaction.signature, e);
+                    e.to_string()
+                }),
+                _ => error,
+            };
+
+            sqlx::query(
+                r#"
+                UPDATE pending_transactions
+                SET status = $2, attempts = $3, error = COALESCE($4, error), updated_at = NOW()
+                WHERE signature = $1 AND status = 'pending'
+                "#,
+            )
+            .bind(&transaction.signature)
+            .bind(status)
+            .bind(attempts)
+            .bind(error)
+            .execute(&self.db.pg)
+            .await?;
+
+            match status {
+                TrackedTransactionStatus::Confirmed => summary.confirmed.push(PendingTransaction {
+                    status,
+                    attempts,
+                    ..transaction
+                }),
+                TrackedTransactionStatus::Failed => {
+                    summary.failed += 1;
+                    self.on_failed(&transaction);
+                }
+                TrackedTransactionStatus::Expired => {
+                    summary.expired += 1;
+                    self.on_failed(&transaction);
+                }
+                TrackedTransactionStatus::Pending => summary.still_pending += 1,
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Finish what the send would have done had it returned in time
+    async fn on_confirmed(&self, transaction: &PendingTransaction) -> ApiResult<()> {
+        match transaction.kind {
+            TrackedTransactionKind::CaptureMint => {
+                let mint_address = transaction.mint_address.as_deref().unwrap_or_default();
+                self.capture
+                    .mark_minted(transaction.related_id, &transaction.signature, mint_address, None)
+                    .await?;
+                let attempt = self.capture.finalize_capture(transaction.related_id).await?;
+                tracing::info!(
+                    "Late mint {} finalized capture attempt {} ({:?})",
+                    transaction.signature, attempt.id, attempt.state
+                );
+            }
+            TrackedTransactionKind::ListingPurchase | TrackedTransactionKind::ListingCancel => {
+                // Already settled by the request or the escrow reconcile
+                let listing = match self.marketplace.get_escrow_listing(transaction.related_id).await {
+                    Ok(listing) => listing,
+                    Err(AppError::NotFound(_)) => return Ok(()),
+                    Err(e) => return Err(e),
+                };
+
+                let buyer_wallet = match transaction.kind {
+                    TrackedTransactionKind::ListingPurchase => sqlx::query_scalar::<_, String>(
+                        "SELECT wallet_address FROM players WHERE id = $1",
+                    )
+                    .bind(transaction.player_id)
+                    .fetch_optional(&self.db.pg)
+                    .await?,
+                    _ => None,
+                };
+                let signature = buyer_wallet.as_ref().map(|_| transaction.signature.as_str());
+
+                self.marketplace
+                    .settle_escrow_listing(&listing, buyer_wallet.as_deref(), signature)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Nothing was written for a transaction that never landed: the capture
+    /// attempt stays pending for a retry and the listing stays open
+    fn on_failed(&self, transaction: &PendingTransaction) {
+        tracing::info!(
+            "Transaction {} ({:?}) did not land; {} left as it was",
+            transaction.signature, transaction.kind, transaction.related_id
+        );
+    }
+
+    async fn set_status(
+        &self,
+        signature: &str,
+        status: TrackedTransactionStatus,
+        error: Option<String>,
+    ) -> ApiResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE pending_transactions
+            SET status = $2, error = $3, updated_at = NOW()
+            WHERE signature = $1 AND status = 'pending'
+            "#,
+        )
+        .bind(signature)
+        .bind(status)
+        .bind(error)
+        .execute(&self.db.pg)
+        .await?;
+
+        Ok(())
+    }
+}
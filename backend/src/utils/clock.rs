@@ -0,0 +1,132 @@
+//! Client clock skew checks
+
+use chrono::{DateTime, Utc};
+
+use crate::error::{ApiResult, AppError};
+
+/// Skew advertised to clients (Welcome message, `/time`).
+///
+/// This is the strictest per-context bound, so a client within it passes
+/// every check below.
+pub const MAX_ALLOWED_SKEW_SECONDS: i64 = 30;
+
+/// Where a client-supplied timestamp is being validated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkewContext {
+    /// Capture tap time (`CaptureRequest.player_location.timestamp`)
+    Capture,
+    /// Location report time
+    LocationReport,
+    /// Wallet signature issued-at
+    Signature,
+}
+
+impl SkewContext {
+    /// Maximum tolerated difference between client and server clocks
+    pub fn max_skew_seconds(self) -> i64 {
+        match self {
+            SkewContext::Capture => MAX_ALLOWED_SKEW_SECONDS,
+            SkewContext::LocationReport => 120,
+            SkewContext::Signature => 300,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SkewContext::Capture => "capture",
+            SkewContext::LocationReport => "location_report",
+            SkewContext::Signature => "signature",
+        }
+    }
+}
+
+/// Validate a client timestamp against the server clock
+pub fn check_skew(context: SkewContext, client_time: DateTime<Utc>) -> ApiResult<()> {
+    check_skew_at(context, client_time, Utc::now())
+}
+
+/// Validate a client timestamp against a given server time
+pub fn check_skew_at(
+    context: SkewContext,
+    client_time: DateTime<Utc>,
+    server_time: DateTime<Utc>,
+) -> ApiResult<()> {
+    // Positive offset means the client clock is ahead
+    let offset_ms = (client_time - server_time).num_milliseconds();
+    let max_skew_seconds = context.max_skew_seconds();
+
+    if offset_ms.abs() > max_skew_seconds * 1000 {
+        return Err(AppError::ClockSkew {
+            context: context.as_str(),
+            offset_ms,
+            max_skew_seconds,
+            server_time: server_time.timestamp_millis(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Validate a client Unix timestamp (seconds) against the server clock
+pub fn check_skew_unix(context: SkewContext, client_time: i64) -> ApiResult<()> {
+    let client_time = DateTime::from_timestamp(client_time, 0)
+        .ok_or_else(|| AppError::Validation("Invalid timestamp".into()))?;
+    check_skew(context, client_time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_within_bounds() {
+        let now = Utc::now();
+        for context in [SkewContext::Capture, SkewContext::LocationReport, SkewContext::Signature] {
+            let max = context.max_skew_seconds();
+            assert!(check_skew_at(context, now, now).is_ok());
+            assert!(check_skew_at(context, now + Duration::seconds(max), now).is_ok());
+            assert!(check_skew_at(context, now - Duration::seconds(max), now).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_outside_bounds() {
+        let now = Utc::now();
+        for context in [SkewContext::Capture, SkewContext::LocationReport, SkewContext::Signature] {
+            let max = context.max_skew_seconds();
+            assert!(check_skew_at(context, now + Duration::seconds(max + 1), now).is_err());
+            assert!(check_skew_at(context, now - Duration::seconds(max + 1), now).is_err());
+        }
+    }
+
+    #[test]
+    fn test_error_detail() {
+        let now = Utc::now();
+        let err = check_skew_at(SkewContext::Capture, now - Duration::seconds(90), now).unwrap_err();
+
+        match err {
+            AppError::ClockSkew { context, offset_ms, max_skew_seconds, server_time } => {
+                assert_eq!(context, "capture");
+                assert_eq!(offset_ms, -90_000);
+                assert_eq!(max_skew_seconds, MAX_ALLOWED_SKEW_SECONDS);
+                assert_eq!(server_time, now.timestamp_millis());
+            }
+            other => panic!("expected ClockSkew, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_advertised_skew_is_strictest() {
+        for context in [SkewContext::Capture, SkewContext::LocationReport, SkewContext::Signature] {
+            assert!(MAX_ALLOWED_SKEW_SECONDS <= context.max_skew_seconds());
+        }
+    }
+
+    #[test]
+    fn test_unix_timestamp() {
+        let now = Utc::now().timestamp();
+        assert!(check_skew_unix(SkewContext::Signature, now).is_ok());
+        assert!(check_skew_unix(SkewContext::Signature, now - 3600).is_err());
+    }
+}
@@ -1,5 +1,7 @@
 //! Geographic utilities
 
+use chrono::{DateTime, Timelike, Utc};
+
 /// Earth radius in meters
 pub const EARTH_RADIUS_M: f64 = 6_371_000.0;
 
@@ -81,6 +83,107 @@ pub fn get_geohash_neighbors(geohash: &str) -> Vec<String> {
     }
 }
 
+/// Approximate local solar time (hours, 0.0..24.0) at a longitude.
+///
+/// Mean solar time shifts one hour per 15° of longitude; the equation of time
+/// (at most ~16 minutes) and political time zones are ignored.
+pub fn local_solar_hour(at: DateTime<Utc>, lng: f64) -> f64 {
+    let utc_hour = at.num_seconds_from_midnight() as f64 / 3600.0;
+    (utc_hour + lng / 15.0).rem_euclid(24.0)
+}
+
+/// Apparent speed in m/s between two fixes `elapsed_secs` apart.
+///
+/// Up to `accuracy_allowance_m` of the distance is forgiven so GPS jitter on
+/// a standing player doesn't read as movement. Elapsed time is floored at one
+/// second so back-to-back fixes don't divide by zero.
+pub fn implied_speed(
+    from: (f64, f64),
+    to: (f64, f64),
+    elapsed_secs: f64,
+    accuracy_allowance_m: f64,
+) -> f64 {
+    let distance = haversine_distance(from.0, from.1, to.0, to.1);
+    (distance - accuracy_allowance_m).max(0.0) / elapsed_secs.max(1.0)
+}
+
+/// Root-mean-square distance in metres between a trail of fixes and the
+/// least-squares quadratic path through them (fitted per axis over time).
+///
+/// `trail` holds `(lat, lng, seconds)`, oldest first. Real movement traces a
+/// smooth path; fixes that hop back and forth around it don't fit one. `None`
+/// with fewer than 4 fixes (a quadratic fits 3 exactly) or fixes too close
+/// together in time to fit.
+pub fn trail_fit_residual(trail: &[(f64, f64, f64)]) -> Option<f64> {
+    if trail.len() < 4 {
+        return None;
+    }
+    let (lat0, lng0, t0) = trail[0];
+    let span = trail.iter().map(|fix| fix.2 - t0).fold(0.0, f64::max);
+    if span <= 0.0 {
+        return None;
+    }
+
+    // Local flat projection around the first fix; time scaled to 0..1
+    let metres_per_deg = EARTH_RADIUS_M * std::f64::consts::PI / 180.0;
+    let ts: Vec<f64> = trail.iter().map(|fix| (fix.2 - t0) / span).collect();
+    let xs: Vec<f64> = trail
+        .iter()
+        .map(|fix| (fix.1 - lng0) * metres_per_deg * lat0.to_radians().cos())
+        .collect();
+    let ys: Vec<f64> = trail.iter().map(|fix| (fix.0 - lat0) * metres_per_deg).collect();
+
+    let fx = fit_quadratic(&ts, &xs)?;
+    let fy = fit_quadratic(&ts, &ys)?;
+    let at = |c: [f64; 3], t: f64| c[0] + c[1] * t + c[2] * t * t;
+    let squared: f64 = ts
+        .iter()
+        .zip(xs.iter().zip(&ys))
+        .map(|(&t, (&x, &y))| (x - at(fx, t)).powi(2) + (y - at(fy, t)).powi(2))
+        .sum();
+
+    Some((squared / trail.len() as f64).sqrt())
+}
+
+/// Least-squares `[c0, c1, c2]` for `y = c0 + c1·t + c2·t²`, from the normal
+/// equations by Cramer's rule; `None` when they're singular
+fn fit_quadratic(ts: &[f64], ys: &[f64]) -> Option<[f64; 3]> {
+    // Sums of t^0..t^4 and of y·t^0..y·t^2
+    let mut s = [0.0; 5];
+    let mut r = [0.0; 3];
+    for (&t, &y) in ts.iter().zip(ys) {
+        let mut power = 1.0;
+        for (k, sum) in s.iter_mut().enumerate() {
+            *sum += power;
+            if k < 3 {
+                r[k] += y * power;
+            }
+            power *= t;
+        }
+    }
+
+    let m = [[s[0], s[1], s[2]], [s[1], s[2], s[3]], [s[2], s[3], s[4]]];
+    let det = det3(m);
+    if det.abs() < 1e-9 {
+        return None;
+    }
+    let mut coefficients = [0.0; 3];
+    for (i, c) in coefficients.iter_mut().enumerate() {
+        let mut replaced = m;
+        for (row, &value) in replaced.iter_mut().zip(&r) {
+            row[i] = value;
+        }
+        *c = det3(replaced) / det;
+    }
+    Some(coefficients)
+}
+
+fn det3(m: [[f64; 3]; 3]) -> f64 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -143,6 +246,109 @@ mod tests {
         assert!((d1 - d2).abs() < 0.001, "Distance should be symmetric");
     }
 
+    // ========================================
+    // Implied Speed Tests
+    // ========================================
+
+    const MAX_SPEED: f64 = 42.0;
+    const ACCURACY: f64 = 100.0;
+
+    #[test]
+    fn test_implied_speed_gps_jitter_is_stationary() {
+        // A standing player's fix wanders 80m within a second
+        let here = (35.6762, 139.6503);
+        let jitter = destination_point(here.0, here.1, 45.0, 80.0);
+        assert_eq!(implied_speed(here, jitter, 1.0, ACCURACY), 0.0);
+        // Even with no time between the fixes
+        assert_eq!(implied_speed(here, jitter, 0.0, ACCURACY), 0.0);
+    }
+
+    #[test]
+    fn test_implied_speed_walking() {
+        // 700m in 10 minutes, less the allowance
+        let here = (35.6762, 139.6503);
+        let there = destination_point(here.0, here.1, 90.0, 700.0);
+        let speed = implied_speed(here, there, 600.0, ACCURACY);
+        assert!((speed - 1.0).abs() < 0.01, "Expected ~1 m/s, got {}", speed);
+    }
+
+    #[test]
+    fn test_implied_speed_boundary() {
+        // 10s at exactly the limit after the allowance is allowed; a metre more isn't
+        let here = (35.6762, 139.6503);
+        let at_limit = destination_point(here.0, here.1, 0.0, ACCURACY + MAX_SPEED * 10.0 - 0.01);
+        let over_limit = destination_point(here.0, here.1, 0.0, ACCURACY + MAX_SPEED * 10.0 + 1.0);
+        assert!(implied_speed(here, at_limit, 10.0, ACCURACY) <= MAX_SPEED);
+        assert!(implied_speed(here, over_limit, 10.0, ACCURACY) > MAX_SPEED);
+    }
+
+    #[test]
+    fn test_implied_speed_teleport() {
+        // Tokyo to Osaka in a minute
+        let tokyo = (35.6762, 139.6503);
+        let osaka = (34.6937, 135.5023);
+        let speed = implied_speed(tokyo, osaka, 60.0, ACCURACY);
+        assert!(speed > 6_000.0, "Expected ~6.6 km/s, got {}", speed);
+    }
+
+    #[test]
+    fn test_implied_speed_instant_jump_uses_one_second() {
+        // A 1km jump with no elapsed time counts as 900 m/s, not infinity
+        let here = (35.6762, 139.6503);
+        let there = destination_point(here.0, here.1, 180.0, 1_000.0);
+        let speed = implied_speed(here, there, 0.0, ACCURACY);
+        assert!(speed.is_finite());
+        assert!((speed - 900.0).abs() < 0.5, "Expected ~900 m/s, got {}", speed);
+    }
+
+    // ========================================
+    // Trail Fit Tests
+    // ========================================
+
+    /// `(lat, lng, seconds)` fixes `distances` metres east of a start point,
+    /// `interval` seconds apart
+    fn trail_east(distances: &[f64], interval: f64) -> Vec<(f64, f64, f64)> {
+        let start = (35.6762, 139.6503);
+        distances
+            .iter()
+            .enumerate()
+            .map(|(i, &d)| {
+                let (lat, lng) = destination_point(start.0, start.1, 90.0, d);
+                (lat, lng, i as f64 * interval)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_trail_fit_steady_walk() {
+        // 1.4 m/s east, fixes every 10s
+        let distances: Vec<f64> = (0..10).map(|i| i as f64 * 14.0).collect();
+        let residual = trail_fit_residual(&trail_east(&distances, 10.0)).unwrap();
+        assert!(residual < 1.0, "Expected a near-perfect fit, got {}", residual);
+    }
+
+    #[test]
+    fn test_trail_fit_accelerating_drive() {
+        // Speeding up from a stop is a quadratic path too
+        let distances: Vec<f64> = (0..10).map(|i| (i * i) as f64 * 2.5).collect();
+        let residual = trail_fit_residual(&trail_east(&distances, 5.0)).unwrap();
+        assert!(residual < 1.0, "got {}", residual);
+    }
+
+    #[test]
+    fn test_trail_fit_hops() {
+        // Every hop is under 42 m/s over 30s, but the trail zig-zags 1km
+        let distances: Vec<f64> = (0..10).map(|i| if i % 2 == 0 { 0.0 } else { 1_000.0 }).collect();
+        let residual = trail_fit_residual(&trail_east(&distances, 30.0)).unwrap();
+        assert!(residual > 400.0, "got {}", residual);
+    }
+
+    #[test]
+    fn test_trail_fit_needs_four_timed_fixes() {
+        assert_eq!(trail_fit_residual(&trail_east(&[0.0, 10.0, 20.0], 10.0)), None);
+        assert_eq!(trail_fit_residual(&trail_east(&[0.0, 10.0, 20.0, 30.0], 0.0)), None);
+    }
+
     // ========================================
     // Bearing Tests
     // ========================================
@@ -256,6 +462,7 @@ mod tests {
         
         // For uniform distribution, outer ring (50-100%) has 3x area of inner (0-50%)
         // So we expect ~75% in outer, ~25% in inner
+        assert_eq!(inner_count + outer_count, distances.len());
         let inner_ratio = inner_count as f64 / distances.len() as f64;
         assert!(inner_ratio > 0.15 && inner_ratio < 0.40, 
                 "Inner ratio {} should be ~0.25 for uniform distribution", inner_ratio);
@@ -332,4 +539,40 @@ mod tests {
         // Should be ~222km (2 degrees at equator), not ~40,000km
         assert!(distance < 500_000.0, "Date line crossing should be short distance");
     }
+
+    // ========================================
+    // Local Solar Time Tests
+    // ========================================
+
+    fn utc(hour: u32, minute: u32) -> DateTime<Utc> {
+        use chrono::TimeZone;
+        Utc.with_ymd_and_hms(2026, 6, 1, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_local_solar_hour_greenwich() {
+        assert!((local_solar_hour(utc(0, 0), 0.0) - 0.0).abs() < 1e-9);
+        assert!((local_solar_hour(utc(13, 30), 0.0) - 13.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_local_solar_hour_east_and_west() {
+        // Tokyo (139.65°E) is ~9.3h ahead of UTC in solar time
+        assert!((local_solar_hour(utc(12, 0), 139.65) - 21.31).abs() < 0.01);
+        // New York (74.0°W) is ~4.9h behind
+        assert!((local_solar_hour(utc(12, 0), -74.006) - 7.066).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_local_solar_hour_wraps() {
+        // 20:00 UTC at 90°E is 02:00 the next solar day
+        assert!((local_solar_hour(utc(20, 0), 90.0) - 2.0).abs() < 1e-9);
+        // 02:00 UTC at 90°W is 20:00 the previous solar day
+        assert!((local_solar_hour(utc(2, 0), -90.0) - 20.0).abs() < 1e-9);
+        // Both sides of the date line agree
+        let east = local_solar_hour(utc(6, 0), 180.0);
+        let west = local_solar_hour(utc(6, 0), -180.0);
+        assert!((east - 18.0).abs() < 1e-9);
+        assert!((west - 18.0).abs() < 1e-9);
+    }
 }
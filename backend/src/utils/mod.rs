@@ -1,3 +1,5 @@
 //! Utility functions
 
+pub mod clock;
 pub mod geo;
+pub mod stats;
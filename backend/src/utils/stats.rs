@@ -0,0 +1,70 @@
+//! Titan base stat derivation
+
+use sha2::{Digest, Sha256};
+
+/// Lowest base stat a Titan is minted with
+pub const MIN_BASE_STAT: u8 = 10;
+
+/// Highest base stat a Titan is minted with
+pub const MAX_BASE_STAT: u8 = 99;
+
+/// Base stats written to a Titan's NFT at mint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TitanStats {
+    pub power: u8,
+    pub fortitude: u8,
+    pub velocity: u8,
+    pub resonance: u8,
+}
+
+/// Base stats for a Titan's genes, so a spawn mints the same Titan whoever
+/// captures it. The genes are hashed and each stat takes 8 bytes of the
+/// digest, reduced into `MIN_BASE_STAT..=MAX_BASE_STAT`.
+pub fn derive_stats(genes: &[u8; 32]) -> TitanStats {
+    let mut hasher = Sha256::new();
+    hasher.update(b"breach:titan-stats");
+    hasher.update(genes);
+    let digest = hasher.finalize();
+
+    let span = (MAX_BASE_STAT - MIN_BASE_STAT) as u64 + 1;
+    let stat = |i: usize| {
+        let word = u64::from_le_bytes(digest[i * 8..(i + 1) * 8].try_into().unwrap());
+        MIN_BASE_STAT + (word % span) as u8
+    };
+
+    TitanStats {
+        power: stat(0),
+        fortitude: stat(1),
+        velocity: stat(2),
+        resonance: stat(3),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derive_stats_is_deterministic() {
+        let genes = [7u8; 32];
+        assert_eq!(derive_stats(&genes), derive_stats(&genes));
+
+        let mut other = genes;
+        other[31] ^= 1;
+        assert_ne!(derive_stats(&genes), derive_stats(&other));
+    }
+
+    #[test]
+    fn test_derive_stats_within_bounds() {
+        let range = MIN_BASE_STAT..=MAX_BASE_STAT;
+        for seed in 0..=255u8 {
+            let stats = derive_stats(&[seed; 32]);
+            for stat in [stats.power, stats.fortitude, stats.velocity, stats.resonance] {
+                assert!(range.contains(&stat), "{:?} from genes {}", stats, seed);
+            }
+        }
+        // Extremes of the genes space too
+        let stats = derive_stats(&[0xff; 32]);
+        assert!(range.contains(&stats.power) && range.contains(&stats.resonance));
+    }
+}
@@ -0,0 +1,85 @@
+//! Wire encoding of `WsMessage` frames
+//!
+//! Connections use JSON text frames unless they open with `?format=msgpack`,
+//! which switches both directions to MessagePack binary frames. MessagePack
+//! maps keep the field names, so the payload has the same shape as the JSON.
+
+use axum::extract::ws::Message;
+use serde::Deserialize;
+
+use super::WsMessage;
+
+/// Frame encoding negotiated when the socket opens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WireFormat {
+    #[default]
+    Json,
+    Msgpack,
+}
+
+impl WireFormat {
+    /// Encode a message as a frame; `None` (logged) if it can't be encoded
+    pub fn encode(self, message: &WsMessage) -> Option<Message> {
+        let frame = match self {
+            WireFormat::Json => serde_json::to_string(message).map(Message::Text).map_err(|e| e.to_string()),
+            WireFormat::Msgpack => rmp_serde::to_vec_named(message).map(Message::Binary).map_err(|e| e.to_string()),
+        };
+        frame
+            .map_err(|e| tracing::warn!("Failed to encode {:?} WebSocket message: {}", self, e))
+            .ok()
+    }
+
+    /// Decode a client frame; text frames are always JSON, binary frames are
+    /// only accepted on MessagePack connections
+    pub fn decode(self, frame: &Message) -> Option<WsMessage> {
+        match (self, frame) {
+            (_, Message::Text(text)) => serde_json::from_str(text).ok(),
+            (WireFormat::Msgpack, Message::Binary(bytes)) => rmp_serde::from_slice(bytes).ok(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_msgpack_round_trip() {
+        let messages = [
+            WsMessage::Subscribe { geohashes: vec!["xn77h".into(), "xn77j".into()] },
+            WsMessage::LocationUpdate { lat: 35.68, lng: 139.76, geohash: "xn77h".into() },
+            WsMessage::Ping,
+            WsMessage::TitanUpdate { titan_id: "t1".into(), capture_count: 2, remaining_captures: 3 },
+        ];
+
+        for message in messages {
+            let frame = WireFormat::Msgpack.encode(&message).unwrap();
+            let Message::Binary(bytes) = &frame else {
+                panic!("expected a binary frame, got {:?}", frame);
+            };
+            let decoded = WireFormat::Msgpack.decode(&frame).unwrap();
+            assert_eq!(
+                serde_json::to_value(&decoded).unwrap(),
+                serde_json::to_value(&message).unwrap()
+            );
+
+            // Smaller than the same message as JSON
+            let json = serde_json::to_string(&message).unwrap();
+            assert!(bytes.len() < json.len(), "{} >= {}", bytes.len(), json.len());
+        }
+    }
+
+    #[test]
+    fn test_json_connections_ignore_binary_frames() {
+        let ping = WireFormat::Json.encode(&WsMessage::Ping).unwrap();
+        assert!(matches!(ping, Message::Text(_)));
+        assert!(matches!(WireFormat::Json.decode(&ping), Some(WsMessage::Ping)));
+
+        let binary = WireFormat::Msgpack.encode(&WsMessage::Ping).unwrap();
+        assert!(WireFormat::Json.decode(&binary).is_none());
+        // MessagePack connections still understand JSON text
+        assert!(matches!(WireFormat::Msgpack.decode(&ping), Some(WsMessage::Ping)));
+    }
+}
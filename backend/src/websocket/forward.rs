@@ -0,0 +1,236 @@
+//! Per-connection forwarding of geohash region broadcasts
+
+use std::collections::HashMap;
+
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+
+use super::{get_geohash_prefix, WsMessage};
+
+/// One forwarding task per subscribed region, each draining its
+/// `broadcast::Receiver` into the connection's outbound queue
+pub struct RegionForwarders {
+    outbound: mpsc::Sender<WsMessage>,
+    tasks: HashMap<String, JoinHandle<()>>,
+}
+
+impl RegionForwarders {
+    pub fn new(outbound: mpsc::Sender<WsMessage>) -> Self {
+        Self {
+            outbound,
+            tasks: HashMap::new(),
+        }
+    }
+
+    /// Start forwarding the receivers `Broadcaster::subscribe` returned for
+    /// `geohashes` (same order). Regions already forwarded are skipped, so a
+    /// repeated subscribe doesn't deliver messages twice.
+    pub fn add(&mut self, geohashes: &[String], receivers: Vec<broadcast::Receiver<WsMessage>>) {
+        for (geohash, rx) in geohashes.iter().zip(receivers) {
+            let prefix = get_geohash_prefix(geohash);
+            if self.tasks.get(&prefix).is_some_and(|task| !task.is_finished()) {
+                continue;
+            }
+            let task = tokio::spawn(forward(prefix.clone(), rx, self.outbound.clone()));
+            self.tasks.insert(prefix, task);
+        }
+    }
+
+    /// Stop forwarding the given regions
+    pub fn remove(&mut self, geohashes: &[String]) {
+        for geohash in geohashes {
+            if let Some(task) = self.tasks.remove(&get_geohash_prefix(geohash)) {
+                task.abort();
+            }
+        }
+    }
+}
+
+impl Drop for RegionForwarders {
+    fn drop(&mut self) {
+        for task in self.tasks.values() {
+            task.abort();
+        }
+    }
+}
+
+async fn forward(
+    prefix: String,
+    mut rx: broadcast::Receiver<WsMessage>,
+    outbound: mpsc::Sender<WsMessage>,
+) {
+    loop {
+        match rx.recv().await {
+            Ok(msg) => {
+                if outbound.send(msg).await.is_err() {
+                    break;
+                }
+            }
+            // A slow client misses some messages but stays subscribed; tell it
+            // so it can re-sync the region
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!("Connection lagged {} messages behind region {}", skipped, prefix);
+                let error = WsMessage::Error {
+                    code: "LAGGED".into(),
+                    message: format!("Missed {} messages in region {}; re-sync it", skipped, prefix),
+                };
+                if outbound.send(error).await.is_err() {
+                    break;
+                }
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::websocket::{Broadcaster, Location};
+
+    fn expired(titan_id: &str) -> WsMessage {
+        WsMessage::TitanExpired { titan_id: titan_id.into() }
+    }
+
+    fn spawn(titan_id: &str, geohash: &str) -> WsMessage {
+        WsMessage::TitanSpawn {
+            titan_id: titan_id.into(),
+            poi_name: None,
+            location: Location { lat: 0.0, lng: 0.0 },
+            element: format!("spawned in {}", geohash),
+            threat_class: 1,
+            species_id: 1,
+            expires_at: "2026-01-01T00:00:00Z".into(),
+        }
+    }
+
+    /// Next titan event's ID, skipping the presence counts subscribing pushes
+    async fn next_titan_id(rx: &mut mpsc::Receiver<WsMessage>) -> String {
+        loop {
+            match tokio::time::timeout(Duration::from_secs(1), rx.recv()).await {
+                Ok(Some(WsMessage::TitanExpired { titan_id })) => return titan_id,
+                Ok(Some(WsMessage::TitanSpawn { titan_id, .. })) => return titan_id,
+                Ok(Some(WsMessage::PresenceCount { .. })) => continue,
+                other => panic!("expected a titan event, got {:?}", other),
+            }
+        }
+    }
+
+    /// Nothing but presence counts left in the queue
+    async fn assert_no_titan_messages(rx: &mut mpsc::Receiver<WsMessage>) {
+        while let Ok(msg) = tokio::time::timeout(Duration::from_millis(100), rx.recv()).await {
+            assert!(matches!(msg, Some(WsMessage::PresenceCount { .. })), "unexpected {:?}", msg);
+        }
+    }
+
+    /// Broadcaster with one registered connection and its forwarders
+    async fn connection(broadcaster: &Broadcaster) -> (RegionForwarders, mpsc::Receiver<WsMessage>) {
+        let (tx, rx) = mpsc::channel(16);
+        broadcaster.register_client("conn", None, None, tx.clone()).await;
+        (RegionForwarders::new(tx), rx)
+    }
+
+    #[tokio::test]
+    async fn test_messages_from_every_region_arrive() {
+        let broadcaster = Broadcaster::new();
+        let (mut forwarders, mut rx) = connection(&broadcaster).await;
+
+        let geohashes = vec!["xn77h".to_string(), "u4pru".to_string()];
+        let receivers = broadcaster.subscribe("conn", geohashes.clone()).await.unwrap();
+        forwarders.add(&geohashes, receivers);
+
+        broadcaster.broadcast("xn77h", expired("tokyo")).await;
+        broadcaster.broadcast("u4pru", expired("oslo")).await;
+
+        let mut received = vec![next_titan_id(&mut rx).await, next_titan_id(&mut rx).await];
+        received.sort();
+        assert_eq!(received, ["oslo", "tokyo"]);
+    }
+
+    #[tokio::test]
+    async fn test_spawns_in_both_regions_arrive_once() {
+        let broadcaster = Broadcaster::new();
+        let (mut forwarders, mut rx) = connection(&broadcaster).await;
+
+        // Two geohashes in the same region plus a second region
+        let geohashes = vec!["xn77h9".to_string(), "xn77hb".to_string(), "u4pru".to_string()];
+        let receivers = broadcaster.subscribe("conn", geohashes.clone()).await.unwrap();
+        forwarders.add(&geohashes, receivers);
+
+        broadcaster.broadcast("xn77h", spawn("tokyo", "xn77h")).await;
+        broadcaster.broadcast("u4pru", spawn("oslo", "u4pru")).await;
+
+        let mut received = vec![next_titan_id(&mut rx).await, next_titan_id(&mut rx).await];
+        received.sort();
+        assert_eq!(received, ["oslo", "tokyo"]);
+        assert_no_titan_messages(&mut rx).await;
+    }
+
+    #[tokio::test]
+    async fn test_later_subscribe_is_forwarded_once() {
+        let broadcaster = Broadcaster::new();
+        let (mut forwarders, mut rx) = connection(&broadcaster).await;
+
+        let first = vec!["xn77h".to_string()];
+        forwarders.add(&first, broadcaster.subscribe("conn", first.clone()).await.unwrap());
+
+        // A later Subscribe that repeats the first region
+        let later = vec!["xn77h".to_string(), "u4pru".to_string()];
+        forwarders.add(&later, broadcaster.subscribe("conn", later.clone()).await.unwrap());
+
+        broadcaster.broadcast("u4pru", expired("oslo")).await;
+        broadcaster.broadcast("xn77h", expired("tokyo")).await;
+
+        let mut received = vec![next_titan_id(&mut rx).await, next_titan_id(&mut rx).await];
+        received.sort();
+        assert_eq!(received, ["oslo", "tokyo"]);
+        // One forwarder per region, so no duplicate
+        assert_no_titan_messages(&mut rx).await;
+    }
+
+    #[tokio::test]
+    async fn test_lagging_connection_stays_subscribed() {
+        let broadcaster = Broadcaster::new();
+        let (mut forwarders, mut rx) = connection(&broadcaster).await;
+
+        let geohashes = vec!["xn77h".to_string()];
+        forwarders.add(&geohashes, broadcaster.subscribe("conn", geohashes.clone()).await.unwrap());
+
+        // Nobody drains the outbound queue while the region channel (256) overflows
+        for i in 0..400 {
+            broadcaster.broadcast("xn77h", expired(&i.to_string())).await;
+        }
+
+        let mut lagged = false;
+        while let Ok(Some(msg)) = tokio::time::timeout(Duration::from_millis(100), rx.recv()).await {
+            if let WsMessage::Error { code, .. } = msg {
+                assert_eq!(code, "LAGGED");
+                lagged = true;
+            }
+        }
+        assert!(lagged);
+
+        // Still forwarding after the lag
+        broadcaster.broadcast("xn77h", expired("after")).await;
+        assert_eq!(next_titan_id(&mut rx).await, "after");
+    }
+
+    #[tokio::test]
+    async fn test_removed_region_stops_forwarding() {
+        let broadcaster = Broadcaster::new();
+        let (mut forwarders, mut rx) = connection(&broadcaster).await;
+
+        let geohashes = vec!["xn77h".to_string(), "u4pru".to_string()];
+        forwarders.add(&geohashes, broadcaster.subscribe("conn", geohashes.clone()).await.unwrap());
+        forwarders.remove(&["xn77h".to_string()]);
+        tokio::task::yield_now().await;
+
+        broadcaster.broadcast("xn77h", expired("tokyo")).await;
+        broadcaster.broadcast("u4pru", expired("oslo")).await;
+
+        assert_eq!(next_titan_id(&mut rx).await, "oslo");
+        assert_no_titan_messages(&mut rx).await;
+    }
+}
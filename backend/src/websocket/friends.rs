@@ -0,0 +1,108 @@
+//! Friend location pushes
+//!
+//! When a player sharing their location moves into another 5-character
+//! geohash region, each friend they share with who is subscribed to that
+//! region gets a `player_nearby` naming the region only. Recipients are read
+//! from the friendships on every move, so unfriending or turning sharing off
+//! stops the pushes at once.
+
+use super::{ConnectedClient, Location, WsMessage};
+use crate::services::coarse_geohash;
+use crate::AppState;
+
+/// Region `location` is in if it isn't the one `previous` was in
+fn entered_region(previous: Option<&Location>, location: &Location) -> Option<String> {
+    let region = coarse_geohash(location.lat, location.lng)?;
+    let left = previous.and_then(|previous| coarse_geohash(previous.lat, previous.lng));
+    (left.as_deref() != Some(region.as_str())).then_some(region)
+}
+
+/// Tell `client`'s sharing friends watching the region it just entered;
+/// `client` is the connection as it was before the move
+pub async fn push_location(state: &AppState, client: &ConnectedClient, location: Location) {
+    let Some(player_id) = client.player_id else {
+        return;
+    };
+    let Some(geohash) = entered_region(client.last_location.as_ref(), &location) else {
+        return;
+    };
+
+    let recipients = match state.services.friend.location_recipients(player_id).await {
+        Ok(recipients) => recipients,
+        Err(e) => {
+            tracing::warn!("Failed to load location recipients for {}: {:?}", player_id, e);
+            return;
+        }
+    };
+
+    let message = WsMessage::PlayerNearby {
+        player_id: player_id.to_string(),
+        username: client.username.clone(),
+        geohash,
+        last_seen: chrono::Utc::now().to_rfc3339(),
+    };
+    for friend_id in recipients {
+        if state.broadcaster.is_player_online(friend_id).await {
+            state.broadcaster.broadcast_to_player(friend_id, message.clone()).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::sync::mpsc;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::websocket::Broadcaster;
+
+    /// Shibuya, in region xn76f
+    const SHIBUYA: Location = Location { lat: 35.659_5, lng: 139.700_5 };
+
+    #[test]
+    fn test_pushes_only_on_entering_a_region() {
+        let across_the_street = Location { lat: 35.660_0, lng: 139.701_0 };
+        let shinjuku = Location { lat: 35.690_0, lng: 139.700_0 };
+
+        assert_eq!(entered_region(None, &SHIBUYA).as_deref(), Some("xn76f"));
+        assert_eq!(entered_region(Some(&SHIBUYA), &across_the_street), None);
+        assert_eq!(entered_region(Some(&SHIBUYA), &shinjuku).as_deref(), Some("xn774"));
+    }
+
+    #[tokio::test]
+    async fn test_player_nearby_reaches_region_subscribers_only() {
+        let broadcaster = Broadcaster::new();
+        let (watching, elsewhere) = (Uuid::new_v4(), Uuid::new_v4());
+        let (watching_tx, mut watching_rx) = mpsc::channel(8);
+        let (elsewhere_tx, mut elsewhere_rx) = mpsc::channel(8);
+        broadcaster.register_client("watching", Some(watching), None, watching_tx).await;
+        broadcaster.register_client("elsewhere", Some(elsewhere), None, elsewhere_tx).await;
+        broadcaster.subscribe("watching", vec!["xn76f".into()]).await.unwrap();
+        broadcaster.subscribe("elsewhere", vec!["xn774".into()]).await.unwrap();
+
+        let nearby = WsMessage::PlayerNearby {
+            player_id: Uuid::new_v4().to_string(),
+            username: Some("alice".into()),
+            geohash: "xn76f".into(),
+            last_seen: chrono::Utc::now().to_rfc3339(),
+        };
+        broadcaster.broadcast_to_player(watching, nearby.clone()).await;
+        broadcaster.broadcast_to_player(elsewhere, nearby).await;
+
+        loop {
+            match tokio::time::timeout(Duration::from_secs(1), watching_rx.recv()).await {
+                Ok(Some(WsMessage::PresenceCount { .. })) => continue,
+                Ok(Some(WsMessage::PlayerNearby { geohash, .. })) => {
+                    assert_eq!(geohash, "xn76f");
+                    break;
+                }
+                other => panic!("expected player_nearby, got {:?}", other),
+            }
+        }
+        while let Ok(message) = elsewhere_rx.try_recv() {
+            assert!(matches!(message, WsMessage::PresenceCount { .. }), "unexpected {:?}", message);
+        }
+    }
+}
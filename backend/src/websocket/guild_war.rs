@@ -0,0 +1,97 @@
+//! Live guild war updates
+//!
+//! War starts, scores and results seen on this instance are pushed to the
+//! online members of both guilds (on any instance, through the relay).
+//! Offline members see the same state over `GET /guilds/:id/war`.
+
+use std::sync::Arc;
+
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use super::{Broadcaster, WsMessage};
+use crate::models::GuildWarUpdate;
+use crate::AppState;
+
+/// Push war updates seen on this instance to the guilds' online members
+pub fn start_forwarder(state: Arc<AppState>) {
+    let updates = state.services.guild.subscribe_wars();
+    tokio::spawn(async move { forward(&state.broadcaster, updates).await });
+}
+
+async fn forward(broadcaster: &Broadcaster, mut updates: broadcast::Receiver<GuildWarUpdate>) {
+    loop {
+        match updates.recv().await {
+            Ok(update) => {
+                let message = WsMessage::guild_war(&update);
+                for &player_id in &update.recipients {
+                    if broadcaster.is_player_online(player_id).await {
+                        broadcaster.broadcast_to_player(player_id, message.clone()).await;
+                    }
+                }
+            }
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!("Guild war push fell behind, {} updates not pushed", skipped);
+            }
+            Err(RecvError::Closed) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use tokio::sync::mpsc;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::models::{GuildWar, GuildWarEvent, GuildWarStatus};
+
+    fn update(event: GuildWarEvent, recipients: Vec<Uuid>) -> GuildWarUpdate {
+        let war = GuildWar {
+            id: Uuid::new_v4(),
+            challenger_guild_id: Some(Uuid::new_v4()),
+            defender_guild_id: None,
+            challenger_name: "Alpha".into(),
+            defender_name: "Bravo".into(),
+            status: GuildWarStatus::Active,
+            duration_hours: 24,
+            challenger_score: 3,
+            defender_score: 1,
+            winner_guild_id: None,
+            challenged_by: None,
+            created_at: Utc::now(),
+            starts_at: Some(Utc::now()),
+            ends_at: Some(Utc::now()),
+            ended_at: None,
+        };
+        GuildWarUpdate { event, war, recipients }
+    }
+
+    #[tokio::test]
+    async fn test_pushes_to_members_only() {
+        let broadcaster = Broadcaster::new();
+        let (member, outsider) = (Uuid::new_v4(), Uuid::new_v4());
+        let (member_tx, mut member_rx) = mpsc::channel(8);
+        let (outsider_tx, mut outsider_rx) = mpsc::channel(8);
+        broadcaster.register_client("member", Some(member), None, member_tx).await;
+        broadcaster.register_client("outsider", Some(outsider), None, outsider_tx).await;
+
+        let (live, updates) = broadcast::channel(8);
+        let forwarder = tokio::spawn(async move { forward(&broadcaster, updates).await });
+
+        live.send(update(GuildWarEvent::Scored, vec![member, Uuid::new_v4()])).unwrap();
+
+        match tokio::time::timeout(Duration::from_secs(1), member_rx.recv()).await {
+            Ok(Some(WsMessage::GuildWarScore { challenger_score, defender_score, .. })) => {
+                assert_eq!((challenger_score, defender_score), (3, 1));
+            }
+            other => panic!("expected guild_war_score, got {:?}", other),
+        }
+
+        drop(live);
+        forwarder.await.unwrap();
+        assert!(outsider_rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,96 @@
+//! Live marketplace activity feed
+//!
+//! Activity is logged to Postgres and paged over REST; the entries logged on
+//! this instance are also pushed to every connection that sent
+//! `subscribe_marketplace`, on any instance through the relay.
+
+use std::sync::Arc;
+
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use super::{Broadcaster, WsMessage};
+use crate::models::MarketplaceActivity;
+use crate::AppState;
+
+/// Push activity logged on this instance to the feed's followers
+pub fn start_forwarder(state: Arc<AppState>) {
+    let activity = state.services.marketplace.subscribe_activity();
+    tokio::spawn(async move { forward(&state.broadcaster, activity).await });
+}
+
+async fn forward(broadcaster: &Broadcaster, mut activity: broadcast::Receiver<MarketplaceActivity>) {
+    loop {
+        match activity.recv().await {
+            Ok(entry) => {
+                broadcaster
+                    .broadcast_marketplace(WsMessage::marketplace_activity(&entry))
+                    .await;
+            }
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!("Marketplace feed push fell behind, {} not pushed", skipped);
+            }
+            Err(RecvError::Closed) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use tokio::sync::mpsc;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::models::MarketplaceActivityType;
+
+    fn activity() -> MarketplaceActivity {
+        MarketplaceActivity {
+            id: Uuid::new_v4(),
+            activity_type: MarketplaceActivityType::BidPlaced,
+            listing_id: Uuid::new_v4(),
+            titan_id: Uuid::new_v4(),
+            price: 1_500,
+            player_username: Some("alice".into()),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pushes_to_subscribers_only() {
+        let broadcaster = Arc::new(Broadcaster::new());
+        let (follower_tx, mut follower_rx) = mpsc::channel(8);
+        let (other_tx, mut other_rx) = mpsc::channel(8);
+        broadcaster.register_client("follower", None, None, follower_tx).await;
+        broadcaster.register_client("other", None, None, other_tx).await;
+        broadcaster.subscribe_marketplace("follower").await;
+
+        let (live, entries) = broadcast::channel(8);
+        let forwarder = {
+            let broadcaster = broadcaster.clone();
+            tokio::spawn(async move { forward(&broadcaster, entries).await })
+        };
+
+        let sent = activity();
+        live.send(sent.clone()).unwrap();
+
+        match tokio::time::timeout(Duration::from_secs(1), follower_rx.recv()).await {
+            Ok(Some(WsMessage::MarketplaceActivity { id, activity_type, price, player_username, .. })) => {
+                assert_eq!(id, sent.id.to_string());
+                assert_eq!(activity_type, MarketplaceActivityType::BidPlaced);
+                assert_eq!(price, 1_500);
+                assert_eq!(player_username.as_deref(), Some("alice"));
+            }
+            other => panic!("expected marketplace activity, got {:?}", other),
+        }
+        assert!(other_rx.try_recv().is_err());
+
+        // Unsubscribing stops the feed; the forwarder stops with the service
+        broadcaster.unsubscribe_marketplace("follower").await;
+        live.send(activity()).unwrap();
+        drop(live);
+        forwarder.await.unwrap();
+        assert!(follower_rx.try_recv().is_err());
+    }
+}
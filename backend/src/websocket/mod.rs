@@ -1,12 +1,25 @@
 //! WebSocket handling for real-time updates
 
-use std::collections::{HashMap, HashSet};
+mod codec;
+mod forward;
+mod friends;
+mod guild_war;
+mod market;
+mod notify;
+mod quest;
+mod rate_limit;
+mod relay;
+mod resume;
+mod spectate;
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use axum::{
     extract::{
-        ws::{Message, WebSocket, WebSocketUpgrade},
+        ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade},
         Query, State,
     },
     response::Response,
@@ -16,10 +29,29 @@ use axum::{
 use futures_util::stream::{SplitSink, SplitStream};
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
-use tokio::sync::{broadcast, RwLock};
+use tokio::sync::{broadcast, mpsc, RwLock};
 use uuid::Uuid;
 
+use crate::models::{
+    CooperativeCapture, FoundMatch, GuildWarEvent, GuildWarUpdate, LeaderboardBoard, LocationSource, MarketplaceActivity, MarketplaceActivityType, MatchedPlayer,
+    MatchFormat, MovementCheck, Notification, NotificationType, PvpChallenge, QuestUpdate,
+    SpectatorMatchState, TitanSpawn, TitanSpawnResponse, TrackedTransactionKind,
+};
+use crate::services::PresenceService;
 use crate::AppState;
+use codec::WireFormat;
+use forward::RegionForwarders;
+use rate_limit::{RateDecision, RateLimiter};
+use relay::RelayTarget;
+use resume::ResumeSession;
+
+pub use friends::push_location as push_friend_location;
+pub use guild_war::start_forwarder as start_guild_war_forwarder;
+pub use market::start_forwarder as start_marketplace_forwarder;
+pub use notify::start_forwarder as start_notification_forwarder;
+pub use quest::start_forwarder as start_quest_forwarder;
+pub use relay::{start_listener as start_relay_listener, RedisRelay};
+pub use spectate::{publish_match_update, start as start_spectating};
 
 /// WebSocket query params
 #[derive(Debug, Deserialize)]
@@ -29,6 +61,12 @@ pub struct WsQuery {
     pub token: Option<String>,
     /// Initial geohash to subscribe to
     pub geohash: String,
+    /// Resume token from a previous connection's `welcome`
+    #[serde(default, alias = "resume_token")]
+    pub resume: Option<Uuid>,
+    /// `msgpack` for MessagePack binary frames instead of JSON text
+    #[serde(default)]
+    pub format: WireFormat,
 }
 
 /// WebSocket message types
@@ -48,6 +86,24 @@ pub enum WsMessage {
     #[serde(rename = "ping")]
     Ping,
 
+    #[serde(rename = "get_presence")]
+    GetPresence { geohash: String },
+
+    /// Watch a PvP match; spectators only receive `MatchUpdate`. One of the
+    /// match's players sending it is back from a disconnect.
+    #[serde(rename = "spectate_match")]
+    SpectateMatch { match_id: String },
+
+    #[serde(rename = "stop_spectating")]
+    StopSpectating { match_id: String },
+
+    /// Follow the marketplace activity feed; only `MarketplaceActivity`
+    #[serde(rename = "subscribe_marketplace")]
+    SubscribeMarketplace,
+
+    #[serde(rename = "unsubscribe_marketplace")]
+    UnsubscribeMarketplace,
+
     // Server -> Client
     #[serde(rename = "titan_spawn")]
     TitanSpawn {
@@ -60,6 +116,33 @@ pub enum WsMessage {
         expires_at: String,
     },
 
+    /// A threat class IV or V spawn, sent instead of `titan_spawn`
+    #[serde(rename = "titan_rare_spawn")]
+    TitanRareSpawn {
+        titan_id: String,
+        poi_name: Option<String>,
+        location: Location,
+        element: String,
+        threat_class: i16,
+        species_id: i32,
+        expires_at: String,
+        /// Threat class V
+        is_legendary: bool,
+        /// The genes, when at least one is exceptional
+        special_genes: Option<[u8; 6]>,
+        /// e.g. "A storm Titan has appeared near Tokyo Station!"
+        spawn_announcement: String,
+    },
+
+    /// Active Titans in the initial region, sent once after connecting
+    #[serde(rename = "titan_snapshot")]
+    TitanSnapshot {
+        titans: Vec<TitanSpawnInfo>,
+        /// More than `SNAPSHOT_MAX_TITANS` were active; fetch the rest from
+        /// the map endpoint
+        truncated: bool,
+    },
+
     #[serde(rename = "titan_captured")]
     TitanCaptured {
         titan_id: String,
@@ -67,14 +150,36 @@ pub enum WsMessage {
         remaining_captures: i32,
     },
 
+    #[serde(rename = "titan_update")]
+    TitanUpdate {
+        titan_id: String,
+        capture_count: i32,
+        remaining_captures: i32,
+    },
+
     #[serde(rename = "titan_expired")]
     TitanExpired { titan_id: String },
 
+    /// A cooperative capture nearby still needs players; sent around the
+    /// Titan whenever someone joins
+    #[serde(rename = "cooperative_capture_invite")]
+    CooperativeCaptureInvite {
+        titan_id: String,
+        /// Wallet of the player who opened it
+        leader: String,
+        joined: u8,
+        required: u8,
+        expires_at: String,
+    },
+
+    /// A friend sharing their location entered a region the player is
+    /// subscribed to; only ever the 5-character geohash
     #[serde(rename = "player_nearby")]
     PlayerNearby {
         player_id: String,
-        username: String,
-        location: Location,
+        username: Option<String>,
+        geohash: String,
+        last_seen: String,
     },
 
     #[serde(rename = "player_left")]
@@ -83,12 +188,20 @@ pub enum WsMessage {
     #[serde(rename = "pong")]
     Pong { server_time: i64 },
 
+    /// Online players subscribed to a 5-character geohash region
+    #[serde(rename = "presence_count")]
+    PresenceCount { geohash: String, count: usize },
+
     #[serde(rename = "subscribed")]
     Subscribed { geohashes: Vec<String> },
 
     #[serde(rename = "unsubscribed")]
     Unsubscribed { geohashes: Vec<String> },
 
+    /// Sent after `subscribed` when a resume token was accepted
+    #[serde(rename = "resumed")]
+    Resumed { restored_geohashes: Vec<String> },
+
     #[serde(rename = "error")]
     Error { code: String, message: String },
 
@@ -96,6 +209,11 @@ pub enum WsMessage {
     Welcome {
         connection_id: String,
         server_time: i64,
+        max_allowed_skew_seconds: i64,
+        /// Reconnect with `?resume=<token>` within a minute of closing
+        resume_token: String,
+        /// Whether this connection took over a previous session
+        resumed: bool,
     },
 
     // Chat messages
@@ -122,6 +240,425 @@ pub enum WsMessage {
         channel_id: String,
         message_id: String,
     },
+
+    // Direct notifications
+    #[serde(rename = "friend_request")]
+    FriendRequest {
+        request_id: String,
+        sender_id: String,
+        message: Option<String>,
+        created_at: String,
+    },
+
+    /// A notification, pushed as it is created while the player is online
+    #[serde(rename = "notification")]
+    Notification {
+        id: String,
+        #[serde(rename = "type")]
+        notification_type: NotificationType,
+        title: String,
+        message: String,
+        data: Option<serde_json::Value>,
+        created_at: String,
+    },
+
+    /// Unread notifications, sent after `welcome` and when some are marked read
+    #[serde(rename = "notification_count")]
+    NotificationCount { unread: i64 },
+
+    /// A daily quest advanced without finishing
+    #[serde(rename = "quest_progress")]
+    QuestProgress {
+        quest_id: String,
+        title: String,
+        progress: i32,
+        target_count: i32,
+    },
+
+    /// A daily quest finished; its rewards are already paid
+    #[serde(rename = "quest_completed")]
+    QuestCompleted {
+        quest_id: String,
+        title: String,
+        xp_earned: i32,
+        breach_earned: i64,
+    },
+
+    /// The player's guild went to war; sent to the members of both guilds
+    #[serde(rename = "guild_war_started")]
+    GuildWarStarted {
+        war_id: String,
+        challenger_guild_id: Option<String>,
+        challenger_name: String,
+        defender_guild_id: Option<String>,
+        defender_name: String,
+        ends_at: Option<String>,
+    },
+
+    /// A member of either guild made a capture during the war
+    #[serde(rename = "guild_war_score")]
+    GuildWarScore {
+        war_id: String,
+        challenger_score: i32,
+        defender_score: i32,
+    },
+
+    /// The war is over; `winner_guild_id` is `None` for a tie
+    #[serde(rename = "guild_war_ended")]
+    GuildWarEnded {
+        war_id: String,
+        winner_guild_id: Option<String>,
+        challenger_score: i32,
+        defender_score: i32,
+    },
+
+    /// Another player challenged this one to a match; answer by
+    /// `expires_at` through `POST /pvp/challenge/:id/accept` or `/reject`
+    #[serde(rename = "pvp_challenge_received")]
+    PvpChallengeReceived {
+        challenge_id: String,
+        challenger_id: String,
+        challenger_username: Option<String>,
+        /// The Titan the challenger means to fight with
+        titan_id: String,
+        match_format: MatchFormat,
+        expires_at: String,
+    },
+
+    /// Matchmaking, or an accepted challenge, paired the player; sent to
+    /// both players
+    #[serde(rename = "pvp_match_found")]
+    PvpMatchFound {
+        match_id: String,
+        opponent_id: String,
+        opponent_username: Option<String>,
+        opponent_elo: i32,
+        /// Pick a Titan by then or the match is cancelled
+        ready_deadline: String,
+    },
+
+    /// A player hadn't picked a Titan by the ready deadline; sent to both
+    /// players, who can queue again
+    #[serde(rename = "pvp_match_cancelled")]
+    PvpMatchCancelled { match_id: String },
+
+    /// A late bid pushed an auction's end time out; sent to its watchers
+    #[serde(rename = "listing_extended")]
+    ListingExtended {
+        listing_id: String,
+        expires_at: String,
+        current_bid: i64,
+    },
+
+    /// A player let their turn run out; sent to both players
+    #[serde(rename = "pvp_turn_timeout")]
+    PvpTurnTimeout {
+        match_id: String,
+        /// Player whose turn was skipped
+        player_id: String,
+        consecutive_skips: i32,
+        max_consecutive_skips: i32,
+        /// The skip limit was reached and the opponent won by timeout
+        match_ended: bool,
+        winner_id: Option<String>,
+    },
+
+    /// Spectating confirmation, with the match's spectators on this instance
+    #[serde(rename = "spectating")]
+    Spectating { match_id: String, spectators: usize },
+
+    /// New state of a match after an action, surrender or timeout; sent to
+    /// its spectators, the last one once the match is over
+    #[serde(rename = "match_update")]
+    MatchUpdate {
+        match_id: String,
+        state: Box<SpectatorMatchState>,
+    },
+
+    /// A higher bid displaced the player's winning bid; sent to them
+    #[serde(rename = "outbid")]
+    Outbid {
+        listing_id: String,
+        /// "You've been outbid"
+        title: String,
+        current_bid: i64,
+        expires_at: String,
+    },
+
+    /// A bid went past the player's proxy maximum, deactivating their proxy;
+    /// sent to them instead of `Outbid`
+    #[serde(rename = "proxy_bid_exceeded")]
+    ProxyBidExceeded {
+        listing_id: String,
+        max_bid: i64,
+        current_bid: i64,
+        expires_at: String,
+    },
+
+    /// A transaction the player sent landed after the request that sent it
+    /// had returned; sent to them
+    #[serde(rename = "transaction_confirmed")]
+    TransactionConfirmed {
+        signature: String,
+        kind: TrackedTransactionKind,
+        /// Capture attempt or listing ID
+        related_id: String,
+    },
+
+    /// A listing, bid, sale or cancellation; sent to connections following
+    /// the marketplace feed
+    #[serde(rename = "marketplace_activity")]
+    MarketplaceActivity {
+        id: String,
+        activity_type: MarketplaceActivityType,
+        listing_id: String,
+        titan_id: String,
+        price: i64,
+        player_username: Option<String>,
+        created_at: String,
+    },
+
+    // Sent to every connection
+    /// The weekly leaderboards rolled over: `period` (`YYYY-WW`) is now
+    /// history and the weekly counters start again
+    #[serde(rename = "leaderboard_rollover")]
+    LeaderboardRollover {
+        period: String,
+        boards: Vec<LeaderboardBoard>,
+    },
+
+    /// Admin notice or scheduled maintenance warning
+    #[serde(rename = "system_announcement")]
+    SystemAnnouncement {
+        title: String,
+        body: String,
+        /// One of `ANNOUNCEMENT_SEVERITIES`
+        severity: String,
+    },
+}
+
+/// Allowed `SystemAnnouncement` severities, least to most urgent
+pub const ANNOUNCEMENT_SEVERITIES: [&str; 3] = ["info", "warning", "critical"];
+
+/// Lowest threat class announced as `titan_rare_spawn`
+pub const RARE_SPAWN_MIN_THREAT_CLASS: i16 = 4;
+
+/// Gene value that makes a rare spawn's genes worth showing
+pub const SPECIAL_GENE_MIN: u8 = 240;
+
+impl WsMessage {
+    /// Announcement for a new spawn: `TitanRareSpawn` from threat class IV,
+    /// `TitanSpawn` below that
+    pub fn titan_spawn(titan: &TitanSpawn, poi_name: Option<String>) -> Self {
+        if titan.threat_class < RARE_SPAWN_MIN_THREAT_CLASS {
+            return WsMessage::TitanSpawn {
+                titan_id: titan.id.to_string(),
+                poi_name,
+                location: Location {
+                    lat: titan.location_lat,
+                    lng: titan.location_lng,
+                },
+                element: format!("{:?}", titan.element).to_lowercase(),
+                threat_class: titan.threat_class,
+                species_id: titan.species_id,
+                expires_at: titan.expires_at.to_rfc3339(),
+            };
+        }
+
+        Self::rare_spawn(titan, poi_name, None)
+    }
+
+    /// Announcement for a spawn event's Titan: always `TitanRareSpawn`, with
+    /// the event's name leading the announcement
+    pub fn event_titan_spawn(titan: &TitanSpawn, poi_name: Option<String>, event_name: &str) -> Self {
+        Self::rare_spawn(titan, poi_name, Some(event_name))
+    }
+
+    fn rare_spawn(titan: &TitanSpawn, poi_name: Option<String>, event_name: Option<&str>) -> Self {
+        let element = format!("{:?}", titan.element).to_lowercase();
+        let location = Location {
+            lat: titan.location_lat,
+            lng: titan.location_lng,
+        };
+
+        let is_legendary = titan.threat_class >= 5;
+        let special_genes = <[u8; 6]>::try_from(titan.genes.as_slice())
+            .ok()
+            .filter(|genes| genes.iter().any(|&gene| gene >= SPECIAL_GENE_MIN));
+        let spawn_announcement = format!(
+            "{}A {}{} Titan has appeared {}!",
+            event_name.map(|name| format!("{}: ", name)).unwrap_or_default(),
+            if is_legendary { "legendary " } else { "" },
+            element,
+            match &poi_name {
+                Some(name) => format!("near {}", name),
+                None => "nearby".to_string(),
+            }
+        );
+
+        WsMessage::TitanRareSpawn {
+            titan_id: titan.id.to_string(),
+            poi_name,
+            location,
+            element,
+            threat_class: titan.threat_class,
+            species_id: titan.species_id,
+            expires_at: titan.expires_at.to_rfc3339(),
+            is_legendary,
+            special_genes,
+            spawn_announcement,
+        }
+    }
+}
+
+/// An active Titan in a `titan_snapshot`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TitanSpawnInfo {
+    pub titan_id: String,
+    pub poi_name: Option<String>,
+    pub location: Location,
+    pub element: String,
+    pub threat_class: i16,
+    pub species_id: i32,
+    pub expires_at: String,
+}
+
+impl From<TitanSpawnResponse> for TitanSpawnInfo {
+    fn from(titan: TitanSpawnResponse) -> Self {
+        Self {
+            titan_id: titan.id.to_string(),
+            poi_name: titan.poi_name,
+            location: Location {
+                lat: titan.location.lat,
+                lng: titan.location.lng,
+            },
+            element: format!("{:?}", titan.element).to_lowercase(),
+            threat_class: titan.threat_class,
+            species_id: titan.species_id,
+            expires_at: titan.expires_at.to_rfc3339(),
+        }
+    }
+}
+
+/// Most Titans sent in a `titan_snapshot`
+pub const SNAPSHOT_MAX_TITANS: usize = 200;
+
+impl WsMessage {
+    /// Snapshot from up to `SNAPSHOT_MAX_TITANS + 1` region Titans; the extra
+    /// one only marks the snapshot as truncated
+    pub fn titan_snapshot(mut titans: Vec<TitanSpawnResponse>) -> Self {
+        let truncated = titans.len() > SNAPSHOT_MAX_TITANS;
+        titans.truncate(SNAPSHOT_MAX_TITANS);
+        WsMessage::TitanSnapshot {
+            titans: titans.into_iter().map(TitanSpawnInfo::from).collect(),
+            truncated,
+        }
+    }
+
+    pub fn cooperative_capture_invite(session: &CooperativeCapture) -> Self {
+        let leader = session
+            .participants
+            .iter()
+            .find(|captor| captor.player_id == session.leader_id)
+            .map(|captor| captor.wallet_address.clone())
+            .unwrap_or_default();
+        WsMessage::CooperativeCaptureInvite {
+            titan_id: session.titan_id.to_string(),
+            leader,
+            joined: session.participants.len() as u8,
+            required: session.required,
+            expires_at: session.expires_at.to_rfc3339(),
+        }
+    }
+
+    pub fn pvp_challenge_received(challenge: &PvpChallenge) -> Self {
+        WsMessage::PvpChallengeReceived {
+            challenge_id: challenge.id.to_string(),
+            challenger_id: challenge.challenger_id.to_string(),
+            challenger_username: challenge.challenger_username.clone(),
+            titan_id: challenge.titan_id.to_string(),
+            match_format: challenge.match_format,
+            expires_at: challenge.expires_at.to_rfc3339(),
+        }
+    }
+
+    /// `pvp_match_found` telling a player about `opponent`
+    pub fn pvp_match_found(found: &FoundMatch, opponent: &MatchedPlayer) -> Self {
+        WsMessage::PvpMatchFound {
+            match_id: found.match_id.to_string(),
+            opponent_id: opponent.player_id.to_string(),
+            opponent_username: opponent.username.clone(),
+            opponent_elo: opponent.elo,
+            ready_deadline: found.ready_deadline.to_rfc3339(),
+        }
+    }
+
+    pub fn notification(notification: &Notification) -> Self {
+        WsMessage::Notification {
+            id: notification.id.to_string(),
+            notification_type: notification.notification_type,
+            title: notification.title.clone(),
+            message: notification.message.clone(),
+            data: notification.data.clone(),
+            created_at: notification.created_at.to_rfc3339(),
+        }
+    }
+
+    pub fn marketplace_activity(activity: &MarketplaceActivity) -> Self {
+        WsMessage::MarketplaceActivity {
+            id: activity.id.to_string(),
+            activity_type: activity.activity_type,
+            listing_id: activity.listing_id.to_string(),
+            titan_id: activity.titan_id.to_string(),
+            price: activity.price,
+            player_username: activity.player_username.clone(),
+            created_at: activity.created_at.to_rfc3339(),
+        }
+    }
+
+    pub fn guild_war(update: &GuildWarUpdate) -> Self {
+        let war = &update.war;
+        match update.event {
+            GuildWarEvent::Started => WsMessage::GuildWarStarted {
+                war_id: war.id.to_string(),
+                challenger_guild_id: war.challenger_guild_id.map(|id| id.to_string()),
+                challenger_name: war.challenger_name.clone(),
+                defender_guild_id: war.defender_guild_id.map(|id| id.to_string()),
+                defender_name: war.defender_name.clone(),
+                ends_at: war.ends_at.map(|at| at.to_rfc3339()),
+            },
+            GuildWarEvent::Scored => WsMessage::GuildWarScore {
+                war_id: war.id.to_string(),
+                challenger_score: war.challenger_score,
+                defender_score: war.defender_score,
+            },
+            GuildWarEvent::Ended => WsMessage::GuildWarEnded {
+                war_id: war.id.to_string(),
+                winner_guild_id: war.winner_guild_id.map(|id| id.to_string()),
+                challenger_score: war.challenger_score,
+                defender_score: war.defender_score,
+            },
+        }
+    }
+
+    /// `quest_completed` when the update finished the quest, else `quest_progress`
+    pub fn quest_update(update: &QuestUpdate) -> Self {
+        if update.completed {
+            WsMessage::QuestCompleted {
+                quest_id: update.quest_id.to_string(),
+                title: update.title.clone(),
+                xp_earned: update.xp_reward,
+                breach_earned: update.breach_reward,
+            }
+        } else {
+            WsMessage::QuestProgress {
+                quest_id: update.quest_id.to_string(),
+                title: update.title.clone(),
+                progress: update.progress,
+                target_count: update.target_count,
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -138,7 +675,57 @@ pub struct ConnectedClient {
     pub username: Option<String>,
     pub subscribed_geohashes: HashSet<String>,
     pub last_location: Option<Location>,
-    pub last_heartbeat: std::time::Instant,
+    /// When `last_location` was accepted
+    pub last_location_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Location updates are dropped until then after repeated speed violations
+    pub location_quarantined_until: Option<tokio::time::Instant>,
+    /// Last message from the client or heartbeat delivered to it
+    pub last_heartbeat: tokio::time::Instant,
+    /// Last time this connection wrote the player's Redis presence
+    pub presence_refreshed_at: tokio::time::Instant,
+}
+
+impl ConnectedClient {
+    /// Geohash regions this connection is subscribed to
+    pub fn subscription_count(&self) -> usize {
+        self.subscribed_geohashes.len()
+    }
+}
+
+/// Default for `websocket.max_subscriptions_per_client`
+pub const DEFAULT_MAX_SUBSCRIPTIONS_PER_CLIENT: usize = 25;
+
+/// Default for `websocket.resume_replay_events`
+pub const DEFAULT_RESUME_REPLAY_EVENTS: usize = 50;
+
+/// Default for `websocket.max_spectators_per_match`
+pub const DEFAULT_MAX_SPECTATORS_PER_MATCH: usize = 50;
+
+/// A subscribe request would take a connection past its region limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubscriptionLimitExceeded {
+    pub limit: usize,
+    pub current: usize,
+    pub requested: usize,
+}
+
+/// A match already has as many spectators as it may
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpectatorLimitExceeded {
+    pub limit: usize,
+}
+
+/// Connection and traffic counters for this instance
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionStats {
+    pub total_connections: usize,
+    pub authenticated: usize,
+    pub anonymous: usize,
+    /// Connections subscribed to each 5-character geohash prefix
+    pub subscribers_by_geohash: BTreeMap<String, usize>,
+    /// Frames sent to / received from clients since startup
+    pub messages_sent: u64,
+    pub messages_received: u64,
 }
 
 /// Global broadcast channels for geohash regions
@@ -151,8 +738,35 @@ pub struct Broadcaster {
     player_counts: RwLock<HashMap<String, usize>>,
     /// Chat channel subscribers: channel_id -> set of connection_ids
     chat_subscribers: RwLock<HashMap<Uuid, HashSet<String>>>,
+    /// PvP match spectators: match_id -> set of connection_ids
+    match_spectators: RwLock<HashMap<Uuid, HashSet<String>>>,
+    /// Spectators one match may have on this instance
+    max_spectators_per_match: usize,
+    /// Connections following the marketplace activity feed
+    marketplace_subscribers: RwLock<HashSet<String>>,
     /// Player to connection mapping for direct messages
     player_connections: RwLock<HashMap<Uuid, String>>,
+    /// Outbound queue per connection, drained by its socket loop
+    senders: RwLock<HashMap<String, mpsc::Sender<WsMessage>>>,
+    /// Redis relay to other backend instances (None = single instance)
+    relay: Option<RedisRelay>,
+    /// Geohash regions one connection may hold
+    max_subscriptions_per_client: usize,
+    /// Recent events per geohash prefix with their sequence numbers, replayed
+    /// to resuming clients
+    history: RwLock<HashMap<String, VecDeque<(u64, WsMessage)>>>,
+    /// Events kept per prefix in `history` (0 = no replay)
+    history_capacity: usize,
+    /// Sequence number of the last geohash event delivered
+    last_seq: AtomicU64,
+    /// Frames written to client sockets
+    messages_sent: AtomicU64,
+    /// Text and binary frames read from client sockets
+    messages_received: AtomicU64,
+    /// Redis presence shared with other instances (None = local connections only)
+    presence: Option<PresenceService>,
+    /// Least time between presence refreshes from one connection
+    presence_refresh: Duration,
 }
 
 impl Broadcaster {
@@ -162,25 +776,84 @@ impl Broadcaster {
             clients: RwLock::new(HashMap::new()),
             player_counts: RwLock::new(HashMap::new()),
             chat_subscribers: RwLock::new(HashMap::new()),
+            match_spectators: RwLock::new(HashMap::new()),
+            max_spectators_per_match: DEFAULT_MAX_SPECTATORS_PER_MATCH,
+            marketplace_subscribers: RwLock::new(HashSet::new()),
             player_connections: RwLock::new(HashMap::new()),
+            senders: RwLock::new(HashMap::new()),
+            relay: None,
+            max_subscriptions_per_client: DEFAULT_MAX_SUBSCRIPTIONS_PER_CLIENT,
+            history: RwLock::new(HashMap::new()),
+            history_capacity: DEFAULT_RESUME_REPLAY_EVENTS,
+            last_seq: AtomicU64::new(0),
+            messages_sent: AtomicU64::new(0),
+            messages_received: AtomicU64::new(0),
+            presence: None,
+            presence_refresh: Duration::from_secs(30),
+        }
+    }
+
+    /// Write authenticated players' presence to Redis, refreshed at most
+    /// every `refresh` by heartbeats and location updates
+    pub fn with_presence(mut self, presence: PresenceService, refresh: Duration) -> Self {
+        self.presence = Some(presence);
+        self.presence_refresh = refresh;
+        self
+    }
+
+    /// Cap the geohash regions each connection may subscribe to
+    pub fn with_subscription_limit(mut self, max_subscriptions_per_client: usize) -> Self {
+        self.max_subscriptions_per_client = max_subscriptions_per_client;
+        self
+    }
+
+    /// Cap the spectators each match may have on this instance
+    pub fn with_spectator_limit(mut self, max_spectators_per_match: usize) -> Self {
+        self.max_spectators_per_match = max_spectators_per_match;
+        self
+    }
+
+    /// Keep this many recent events per region for resuming clients
+    pub fn with_replay_buffer(mut self, events_per_region: usize) -> Self {
+        self.history_capacity = events_per_region;
+        self
+    }
+
+    /// Broadcaster that also relays geohash and player broadcasts through
+    /// Redis; start `start_relay_listener` to receive other instances' messages
+    pub fn with_relay(relay: RedisRelay) -> Self {
+        Self {
+            relay: Some(relay),
+            ..Self::new()
         }
     }
 
     /// Register a new client connection
-    pub async fn register_client(&self, connection_id: &str, player_id: Option<Uuid>, username: Option<String>) {
+    pub async fn register_client(
+        &self,
+        connection_id: &str,
+        player_id: Option<Uuid>,
+        username: Option<String>,
+        sender: mpsc::Sender<WsMessage>,
+    ) {
         let client = ConnectedClient {
             connection_id: connection_id.to_string(),
             player_id,
             username,
             subscribed_geohashes: HashSet::new(),
             last_location: None,
-            last_heartbeat: std::time::Instant::now(),
+            last_location_at: None,
+            location_quarantined_until: None,
+            last_heartbeat: tokio::time::Instant::now(),
+            presence_refreshed_at: tokio::time::Instant::now(),
         };
         self.clients.write().await.insert(connection_id.to_string(), client);
+        self.senders.write().await.insert(connection_id.to_string(), sender);
         
         // Track player -> connection mapping
         if let Some(pid) = player_id {
             self.player_connections.write().await.insert(pid, connection_id.to_string());
+            self.write_presence(pid).await;
         }
         
         tracing::debug!("Client {} registered", connection_id);
@@ -188,25 +861,55 @@ impl Broadcaster {
 
     /// Unregister a client connection
     pub async fn unregister_client(&self, connection_id: &str) {
-        if let Some(client) = self.clients.write().await.remove(connection_id) {
+        let removed = self.clients.write().await.remove(connection_id);
+        if let Some(client) = removed {
             // Decrement player counts for subscribed geohashes
-            let mut counts = self.player_counts.write().await;
-            for geohash in &client.subscribed_geohashes {
-                if let Some(count) = counts.get_mut(geohash) {
-                    *count = count.saturating_sub(1);
+            let mut changed = Vec::new();
+            {
+                let mut counts = self.player_counts.write().await;
+                for geohash in &client.subscribed_geohashes {
+                    if let Some(count) = counts.get_mut(geohash) {
+                        *count = count.saturating_sub(1);
+                        changed.push((geohash.clone(), *count));
+                    }
                 }
             }
+            self.push_presence(changed).await;
             
-            // Remove from player connections
+            // Remove from player connections, unless the player has
+            // already reconnected on a newer connection
             if let Some(pid) = client.player_id {
-                self.player_connections.write().await.remove(&pid);
+                let gone = {
+                    let mut connections = self.player_connections.write().await;
+                    let current = connections.get(&pid).map(String::as_str) == Some(connection_id);
+                    if current {
+                        connections.remove(&pid);
+                    }
+                    current
+                };
+                // Another instance still holding a connection writes the
+                // player's presence back on its next refresh
+                if let (true, Some(presence)) = (gone, &self.presence) {
+                    if let Err(e) = presence.set_offline(pid).await {
+                        tracing::warn!("Failed to clear presence for {}: {}", pid, e);
+                    }
+                }
             }
+            self.senders.write().await.remove(connection_id);
             
             // Remove from all chat subscriptions
             let mut chat_subs = self.chat_subscribers.write().await;
             for subscribers in chat_subs.values_mut() {
                 subscribers.remove(connection_id);
             }
+            drop(chat_subs);
+
+            // And from spectated matches
+            self.match_spectators.write().await.retain(|_, spectators| {
+                spectators.remove(connection_id);
+                !spectators.is_empty()
+            });
+            self.marketplace_subscribers.write().await.remove(connection_id);
             
             tracing::debug!("Client {} unregistered", connection_id);
         }
@@ -235,41 +938,143 @@ impl Broadcaster {
     
     /// Broadcast a chat message to all subscribers of a channel
     pub async fn broadcast_chat_message(&self, channel_id: Uuid, message: WsMessage) {
-        let subscribers = self.chat_subscribers.read().await;
-        let clients = self.clients.read().await;
-        
-        if let Some(subscriber_ids) = subscribers.get(&channel_id) {
-            let json = match serde_json::to_string(&message) {
-                Ok(j) => j,
-                Err(_) => return,
-            };
-            
-            tracing::debug!(
-                "Broadcasting chat message to {} subscribers in channel {}",
-                subscriber_ids.len(),
-                channel_id
-            );
-            
-            // Note: In a production system, we'd need to maintain sender handles
-            // For now, we log the broadcast - actual delivery happens via channel receivers
-            for _conn_id in subscriber_ids {
-                // The actual delivery happens through the geohash-based channel system
-                // This is a simplified implementation that relies on clients being subscribed
+        // Copy the IDs out: `unregister_client` takes `senders` before `chat_subscribers`
+        let subscriber_ids: Vec<String> = match self.chat_subscribers.read().await.get(&channel_id) {
+            Some(ids) => ids.iter().cloned().collect(),
+            None => return,
+        };
+
+        tracing::debug!(
+            "Broadcasting chat message to {} subscribers in channel {}",
+            subscriber_ids.len(),
+            channel_id
+        );
+
+        let mut closed = Vec::new();
+        {
+            let senders = self.senders.read().await;
+            for connection_id in &subscriber_ids {
+                if let Some(sender) = senders.get(connection_id) {
+                    if !deliver(connection_id, sender, message.clone()) {
+                        closed.push(connection_id.clone());
+                    }
+                }
             }
         }
+        self.remove_closed_senders(&closed).await;
     }
-    
+
+    /// Add a connection to a match's spectators; returns their number
+    pub async fn spectate_match(
+        &self,
+        connection_id: &str,
+        match_id: Uuid,
+    ) -> Result<usize, SpectatorLimitExceeded> {
+        let mut matches = self.match_spectators.write().await;
+        let spectators = matches.entry(match_id).or_default();
+        if !spectators.contains(connection_id) && spectators.len() >= self.max_spectators_per_match {
+            return Err(SpectatorLimitExceeded {
+                limit: self.max_spectators_per_match,
+            });
+        }
+        spectators.insert(connection_id.to_string());
+        Ok(spectators.len())
+    }
+
+    /// Remove a connection from a match's spectators
+    pub async fn stop_spectating(&self, connection_id: &str, match_id: Uuid) {
+        let mut matches = self.match_spectators.write().await;
+        if let Some(spectators) = matches.get_mut(&match_id) {
+            spectators.remove(connection_id);
+            if spectators.is_empty() {
+                matches.remove(&match_id);
+            }
+        }
+    }
+
+    /// Send a match update to the match's spectators on every instance
+    pub async fn broadcast_match_update(&self, match_id: Uuid, message: WsMessage) {
+        let target = RelayTarget::Match(match_id);
+        self.publish(&target, &message);
+        self.deliver_local(&target, message).await;
+    }
+
+    /// Add a connection to the marketplace activity feed
+    pub async fn subscribe_marketplace(&self, connection_id: &str) {
+        self.marketplace_subscribers.write().await.insert(connection_id.to_string());
+    }
+
+    /// Remove a connection from the marketplace activity feed
+    pub async fn unsubscribe_marketplace(&self, connection_id: &str) {
+        self.marketplace_subscribers.write().await.remove(connection_id);
+    }
+
+    /// Send marketplace activity to the feed's followers on every instance
+    pub async fn broadcast_marketplace(&self, message: WsMessage) {
+        let target = RelayTarget::Marketplace;
+        self.publish(&target, &message);
+        self.deliver_local(&target, message).await;
+    }
+
     /// Broadcast to a specific player (for private messages)
     pub async fn broadcast_to_player(&self, player_id: Uuid, message: WsMessage) {
-        if let Some(connection_id) = self.player_connections.read().await.get(&player_id) {
-            tracing::debug!("Broadcasting message to player {} (connection {})", player_id, connection_id);
-            // In production, we'd send directly to the player's connection
-        }
+        let target = RelayTarget::Player(player_id);
+        self.publish(&target, &message);
+        self.deliver_local(&target, message).await;
     }
     
-    /// Check if a player is online
+    /// Send to every connection, on every instance, once each, whether or
+    /// not it is subscribed to a region
+    pub async fn broadcast_global(&self, message: WsMessage) {
+        let target = RelayTarget::Global;
+        self.publish(&target, &message);
+        self.deliver_local(&target, message).await;
+    }
+
+    /// Send to every connection on this instance only, for messages each
+    /// instance sends on its own (scheduled maintenance notices)
+    pub async fn broadcast_instance(&self, message: WsMessage) {
+        self.deliver_local(&RelayTarget::Global, message).await;
+    }
+
+    /// Check if a player is online: connected here, or to another instance
+    /// per their Redis presence
     pub async fn is_player_online(&self, player_id: Uuid) -> bool {
-        self.player_connections.read().await.contains_key(&player_id)
+        if self.player_connections.read().await.contains_key(&player_id) {
+            return true;
+        }
+        match &self.presence {
+            Some(presence) => presence.is_online(player_id).await,
+            None => false,
+        }
+    }
+
+    /// Extend the connection's player's presence on a heartbeat or location
+    /// update, if the last refresh is old enough
+    pub async fn refresh_presence(&self, connection_id: &str) {
+        let player_id = {
+            let mut clients = self.clients.write().await;
+            let Some(client) = clients.get_mut(connection_id) else {
+                return;
+            };
+            if client.presence_refreshed_at.elapsed() < self.presence_refresh {
+                return;
+            }
+            client.presence_refreshed_at = tokio::time::Instant::now();
+            client.player_id
+        };
+
+        if let Some(player_id) = player_id {
+            self.write_presence(player_id).await;
+        }
+    }
+
+    async fn write_presence(&self, player_id: Uuid) {
+        if let Some(presence) = &self.presence {
+            if let Err(e) = presence.set_online(player_id).await {
+                tracing::warn!("Failed to write presence for {}: {}", player_id, e);
+            }
+        }
     }
     
     /// Get all online player IDs
@@ -278,63 +1083,104 @@ impl Broadcaster {
     }
 
     /// Subscribe a client to geohash regions
-    pub async fn subscribe(&self, connection_id: &str, geohashes: Vec<String>) -> Vec<broadcast::Receiver<WsMessage>> {
+    ///
+    /// Fails without subscribing to any of them if the new regions would take
+    /// the connection past its subscription limit.
+    pub async fn subscribe(
+        &self,
+        connection_id: &str,
+        geohashes: Vec<String>,
+    ) -> Result<Vec<broadcast::Receiver<WsMessage>>, SubscriptionLimitExceeded> {
         let mut receivers = Vec::new();
-        let mut channels = self.channels.write().await;
-        let mut clients = self.clients.write().await;
-        let mut counts = self.player_counts.write().await;
-
-        if let Some(client) = clients.get_mut(connection_id) {
-            for geohash in geohashes {
-                let prefix = get_geohash_prefix(&geohash);
-                
-                // Add to client subscriptions
-                if client.subscribed_geohashes.insert(prefix.clone()) {
-                    // Increment player count
-                    *counts.entry(prefix.clone()).or_insert(0) += 1;
+        let mut changed = Vec::new();
+        {
+            let mut channels = self.channels.write().await;
+            let mut clients = self.clients.write().await;
+            let mut counts = self.player_counts.write().await;
+
+            if let Some(client) = clients.get_mut(connection_id) {
+                let new_prefixes: HashSet<String> = geohashes
+                    .iter()
+                    .map(|geohash| get_geohash_prefix(geohash))
+                    .filter(|prefix| !client.subscribed_geohashes.contains(prefix))
+                    .collect();
+                let current = client.subscription_count();
+                if current + new_prefixes.len() > self.max_subscriptions_per_client {
+                    return Err(SubscriptionLimitExceeded {
+                        limit: self.max_subscriptions_per_client,
+                        current,
+                        requested: new_prefixes.len(),
+                    });
                 }
 
-                // Get or create channel
-                let rx = if let Some(sender) = channels.get(&prefix) {
-                    sender.subscribe()
-                } else {
-                    let (tx, rx) = broadcast::channel(256);
-                    channels.insert(prefix, tx);
-                    rx
-                };
-                receivers.push(rx);
+                for geohash in geohashes {
+                    let prefix = get_geohash_prefix(&geohash);
+
+                    // Add to client subscriptions
+                    if client.subscribed_geohashes.insert(prefix.clone()) {
+                        // Increment player count
+                        let count = counts.entry(prefix.clone()).or_insert(0);
+                        *count += 1;
+                        changed.push((prefix.clone(), *count));
+                    }
+
+                    // Get or create channel
+                    let rx = if let Some(sender) = channels.get(&prefix) {
+                        sender.subscribe()
+                    } else {
+                        let (tx, rx) = broadcast::channel(256);
+                        channels.insert(prefix, tx);
+                        rx
+                    };
+                    receivers.push(rx);
+                }
             }
         }
 
-        receivers
+        self.push_presence(changed).await;
+        Ok(receivers)
     }
 
     /// Unsubscribe a client from geohash regions
     pub async fn unsubscribe(&self, connection_id: &str, geohashes: Vec<String>) {
-        let mut clients = self.clients.write().await;
-        let mut counts = self.player_counts.write().await;
-
-        if let Some(client) = clients.get_mut(connection_id) {
-            for geohash in geohashes {
-                let prefix = get_geohash_prefix(&geohash);
-                if client.subscribed_geohashes.remove(&prefix) {
-                    if let Some(count) = counts.get_mut(&prefix) {
-                        *count = count.saturating_sub(1);
+        let mut changed = Vec::new();
+        {
+            let mut clients = self.clients.write().await;
+            let mut counts = self.player_counts.write().await;
+
+            if let Some(client) = clients.get_mut(connection_id) {
+                for geohash in geohashes {
+                    let prefix = get_geohash_prefix(&geohash);
+                    if client.subscribed_geohashes.remove(&prefix) {
+                        if let Some(count) = counts.get_mut(&prefix) {
+                            *count = count.saturating_sub(1);
+                            changed.push((prefix, *count));
+                        }
                     }
                 }
             }
         }
+
+        self.push_presence(changed).await;
+    }
+
+    /// Tell each region's subscribers its new online player count. Counts are
+    /// per instance, so this isn't relayed.
+    async fn push_presence(&self, changed: Vec<(String, usize)>) {
+        for (prefix, count) in changed {
+            let message = WsMessage::PresenceCount {
+                geohash: prefix.clone(),
+                count,
+            };
+            self.deliver_local(&RelayTarget::Geohash(prefix), message).await;
+        }
     }
 
     /// Broadcast a message to all subscribers of a geohash region
     pub async fn broadcast(&self, geohash: &str, message: WsMessage) {
-        let prefix = get_geohash_prefix(geohash);
-        let channels = self.channels.read().await;
-
-        if let Some(sender) = channels.get(&prefix) {
-            // Ignore send errors (no receivers)
-            let _ = sender.send(message);
-        }
+        let target = RelayTarget::Geohash(get_geohash_prefix(geohash));
+        self.publish(&target, &message);
+        self.deliver_local(&target, message).await;
     }
 
     /// Broadcast to multiple geohash regions (for large events)
@@ -355,11 +1201,10 @@ impl Broadcaster {
                 neighbors.nw,
             ];
 
-            let channels = self.channels.read().await;
             for region in regions {
-                if let Some(sender) = channels.get(&region) {
-                    let _ = sender.send(message.clone());
-                }
+                let target = RelayTarget::Geohash(region);
+                self.publish(&target, &message);
+                self.deliver_local(&target, message.clone()).await;
             }
         } else {
             self.broadcast(geohash, message).await;
@@ -372,23 +1217,84 @@ impl Broadcaster {
         self.player_counts.read().await.get(&prefix).copied().unwrap_or(0)
     }
 
+    /// 5-character geohash regions with at least one subscribed connection
+    pub async fn active_regions(&self) -> Vec<String> {
+        self.player_counts
+            .read()
+            .await
+            .iter()
+            .filter(|(_, &count)| count > 0)
+            .map(|(prefix, _)| prefix.clone())
+            .collect()
+    }
+
     /// Get total connected clients
     pub async fn get_total_connections(&self) -> usize {
         self.clients.read().await.len()
     }
 
+    /// Count a frame written to a client socket
+    pub fn record_sent(&self) {
+        self.messages_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a frame read from a client socket
+    pub fn record_received(&self) {
+        self.messages_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Connection breakdown and message counters for this instance
+    pub async fn connection_stats(&self) -> ConnectionStats {
+        let clients = self.clients.read().await;
+        let authenticated = clients.values().filter(|client| client.player_id.is_some()).count();
+
+        let mut subscribers_by_geohash = BTreeMap::new();
+        for geohash in clients.values().flat_map(|client| &client.subscribed_geohashes) {
+            *subscribers_by_geohash.entry(geohash.clone()).or_insert(0) += 1;
+        }
+
+        ConnectionStats {
+            total_connections: clients.len(),
+            authenticated,
+            anonymous: clients.len() - authenticated,
+            subscribers_by_geohash,
+            messages_sent: self.messages_sent.load(Ordering::Relaxed),
+            messages_received: self.messages_received.load(Ordering::Relaxed),
+        }
+    }
+
     /// Update client location
     pub async fn update_client_location(&self, connection_id: &str, location: Location) {
         if let Some(client) = self.clients.write().await.get_mut(connection_id) {
             client.last_location = Some(location);
-            client.last_heartbeat = std::time::Instant::now();
+            client.last_location_at = Some(chrono::Utc::now());
+            client.last_heartbeat = tokio::time::Instant::now();
+        }
+    }
+
+    /// Drop a client's location updates for `duration`
+    pub async fn quarantine_client_location(&self, connection_id: &str, duration: Duration) {
+        if let Some(client) = self.clients.write().await.get_mut(connection_id) {
+            client.location_quarantined_until = Some(tokio::time::Instant::now() + duration);
+        }
+    }
+
+    /// A snapshot of a connected client
+    pub async fn get_client(&self, connection_id: &str) -> Option<ConnectedClient> {
+        self.clients.read().await.get(connection_id).cloned()
+    }
+
+    /// Mark a client as alive so stale-connection cleanup keeps it
+    pub async fn touch_client(&self, connection_id: &str) {
+        if let Some(client) = self.clients.write().await.get_mut(connection_id) {
+            client.last_heartbeat = tokio::time::Instant::now();
         }
     }
 
     /// Clean up stale connections (no heartbeat for 60 seconds)
     pub async fn cleanup_stale_connections(&self) -> Vec<String> {
         let threshold = Duration::from_secs(60);
-        let now = std::time::Instant::now();
+        let now = tokio::time::Instant::now();
         let mut stale = Vec::new();
 
         {
@@ -406,6 +1312,172 @@ impl Broadcaster {
 
         stale
     }
+
+    /// Hand a message to other instances through the relay, if any
+    fn publish(&self, target: &RelayTarget, message: &WsMessage) {
+        if let Some(relay) = &self.relay {
+            relay.publish(target, message);
+        }
+    }
+
+    /// Deliver to this instance's connections only
+    async fn deliver_local(&self, target: &RelayTarget, message: WsMessage) {
+        match target {
+            RelayTarget::Geohash(prefix) => {
+                self.record(prefix, &message).await;
+                if let Some(sender) = self.channels.read().await.get(prefix) {
+                    // Ignore send errors (no receivers)
+                    let _ = sender.send(message);
+                }
+            }
+            RelayTarget::Player(player_id) => {
+                let Some(connection_id) = self.player_connections.read().await.get(player_id).cloned() else {
+                    return;
+                };
+                // A friend's location only reaches a player watching its region
+                if let WsMessage::PlayerNearby { geohash, .. } = &message {
+                    let watching = self
+                        .clients
+                        .read()
+                        .await
+                        .get(&connection_id)
+                        .is_some_and(|client| client.subscribed_geohashes.contains(geohash));
+                    if !watching {
+                        return;
+                    }
+                }
+
+                tracing::debug!("Broadcasting message to player {} (connection {})", player_id, connection_id);
+                let open = match self.senders.read().await.get(&connection_id) {
+                    Some(sender) => deliver(&connection_id, sender, message),
+                    None => true,
+                };
+                if !open {
+                    self.remove_closed_senders(&[connection_id]).await;
+                }
+            }
+            RelayTarget::Match(match_id) => {
+                // The final update also ends the spectating
+                let over = matches!(&message, WsMessage::MatchUpdate { state, .. } if state.is_over());
+                let spectator_ids: Vec<String> = {
+                    let mut matches = self.match_spectators.write().await;
+                    let ids = match matches.get(match_id) {
+                        Some(ids) => ids.iter().cloned().collect(),
+                        None => return,
+                    };
+                    if over {
+                        matches.remove(match_id);
+                    }
+                    ids
+                };
+
+                let mut closed = Vec::new();
+                {
+                    let senders = self.senders.read().await;
+                    for connection_id in &spectator_ids {
+                        if let Some(sender) = senders.get(connection_id) {
+                            if !deliver(connection_id, sender, message.clone()) {
+                                closed.push(connection_id.clone());
+                            }
+                        }
+                    }
+                }
+                self.remove_closed_senders(&closed).await;
+            }
+            RelayTarget::Marketplace => {
+                let subscriber_ids: Vec<String> =
+                    self.marketplace_subscribers.read().await.iter().cloned().collect();
+
+                let mut closed = Vec::new();
+                {
+                    let senders = self.senders.read().await;
+                    for connection_id in &subscriber_ids {
+                        if let Some(sender) = senders.get(connection_id) {
+                            if !deliver(connection_id, sender, message.clone()) {
+                                closed.push(connection_id.clone());
+                            }
+                        }
+                    }
+                }
+                self.remove_closed_senders(&closed).await;
+            }
+            RelayTarget::Global => {
+                let mut closed = Vec::new();
+                for (connection_id, sender) in self.senders.read().await.iter() {
+                    if !deliver(connection_id, sender, message.clone()) {
+                        closed.push(connection_id.clone());
+                    }
+                }
+                self.remove_closed_senders(&closed).await;
+            }
+        }
+    }
+
+    /// Remember a region event for resuming clients. Presence counts are
+    /// skipped; they're stale by the time anyone reconnects.
+    async fn record(&self, prefix: &str, message: &WsMessage) {
+        if self.history_capacity == 0 || matches!(message, WsMessage::PresenceCount { .. }) {
+            return;
+        }
+
+        let seq = self.last_seq.fetch_add(1, Ordering::SeqCst) + 1;
+        let mut history = self.history.write().await;
+        let events = history.entry(prefix.to_string()).or_default();
+        if events.len() == self.history_capacity {
+            events.pop_front();
+        }
+        events.push_back((seq, message.clone()));
+    }
+
+    /// Sequence number of the latest region event; events after it are
+    /// "missed" for a connection closing now
+    pub fn last_event_seq(&self) -> u64 {
+        self.last_seq.load(Ordering::SeqCst)
+    }
+
+    /// Buffered events in the given regions with `after_seq < seq <= up_to_seq`,
+    /// oldest first
+    pub async fn missed_events(&self, geohashes: &[String], after_seq: u64, up_to_seq: u64) -> Vec<WsMessage> {
+        let prefixes: HashSet<String> = geohashes.iter().map(|g| get_geohash_prefix(g)).collect();
+        let history = self.history.read().await;
+
+        let mut missed: Vec<(u64, WsMessage)> = prefixes
+            .iter()
+            .filter_map(|prefix| history.get(prefix))
+            .flat_map(|events| {
+                events
+                    .iter()
+                    .filter(|(seq, _)| *seq > after_seq && *seq <= up_to_seq)
+                    .cloned()
+            })
+            .collect();
+        missed.sort_by_key(|(seq, _)| *seq);
+        missed.into_iter().map(|(_, message)| message).collect()
+    }
+
+    /// Geohash prefixes a connection is subscribed to
+    pub async fn subscriptions(&self, connection_id: &str) -> Vec<String> {
+        self.clients
+            .read()
+            .await
+            .get(connection_id)
+            .map(|client| client.subscribed_geohashes.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Drop send handles whose socket loop is gone, so dead channels don't
+    /// pile up if a connection ended without `unregister_client`
+    async fn remove_closed_senders(&self, connection_ids: &[String]) {
+        if connection_ids.is_empty() {
+            return;
+        }
+        let mut senders = self.senders.write().await;
+        for connection_id in connection_ids {
+            if senders.get(connection_id).is_some_and(|sender| sender.is_closed()) {
+                senders.remove(connection_id);
+            }
+        }
+    }
 }
 
 impl Default for Broadcaster {
@@ -414,6 +1486,23 @@ impl Default for Broadcaster {
     }
 }
 
+/// Queue a message on a client's connection without waiting; a full queue
+/// (client not keeping up) drops the message. Returns false once the
+/// connection's receiver is gone.
+fn deliver(connection_id: &str, sender: &mpsc::Sender<WsMessage>, message: WsMessage) -> bool {
+    match sender.try_send(message) {
+        Ok(()) => true,
+        Err(mpsc::error::TrySendError::Full(_)) => {
+            tracing::warn!("Dropping message for slow connection {}", connection_id);
+            true
+        }
+        Err(mpsc::error::TrySendError::Closed(_)) => {
+            tracing::debug!("Connection {} already closed", connection_id);
+            false
+        }
+    }
+}
+
 /// Get 5-character geohash prefix for region grouping
 fn get_geohash_prefix(geohash: &str) -> String {
     geohash.chars().take(5).collect()
@@ -430,6 +1519,7 @@ async fn ws_handler(
 
 /// Handle WebSocket connection
 async fn handle_socket(socket: WebSocket, state: Arc<AppState>, query: WsQuery) {
+    let format = query.format;
     let (sender, receiver) = socket.split();
     let mut sender: SplitSink<WebSocket, Message> = sender;
     let mut receiver: SplitStream<WebSocket> = receiver;
@@ -438,70 +1528,184 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>, query: WsQuery)
     // Try to authenticate if token provided
     let (player_id, username) = if let Some(token) = &query.token {
         match state.services.auth.verify_token(token) {
-            Ok(claims) => (Some(claims.player_id), Some(claims.wallet_address)),
-            Err(_) => (None, None),
+            // Banned players connect as anonymous
+            Ok(claims) if !state.services.player.is_banned(claims.player_id).await.unwrap_or(false) => {
+                (Some(claims.player_id), Some(claims.wallet_address))
+            }
+            _ => (None, None),
         }
     } else {
         (None, None)
     };
 
+    // Outbound queue: geohash broadcasts, chat and direct messages
+    let (broadcast_tx, mut broadcast_rx) = mpsc::channel::<WsMessage>(100);
+
     // Register client
-    state.broadcaster.register_client(&connection_id, player_id, username).await;
+    state
+        .broadcaster
+        .register_client(&connection_id, player_id, username, broadcast_tx.clone())
+        .await;
+
+    // Subscribe authenticated players to their chat channels
+    if let Some(player_id) = player_id {
+        match state.services.chat.get_player_channel_ids(player_id).await {
+            Ok(channel_ids) => {
+                for channel_id in channel_ids {
+                    state.broadcaster.subscribe_chat_channel(player_id, channel_id).await;
+                }
+            }
+            Err(e) => tracing::warn!("Failed to load chat channels for {}: {}", player_id, e),
+        }
+    }
 
-    // Subscribe to initial geohash
-    let initial_geohash = query.geohash.clone();
-    let mut receivers: Vec<broadcast::Receiver<WsMessage>> = state.broadcaster.subscribe(&connection_id, vec![initial_geohash.clone()]).await;
+    // Take over the previous connection's session, if the token is still valid
+    let resumed = match query.resume {
+        Some(token) => match resume::take(&mut state.db.redis.clone(), token).await {
+            Ok(session) => session.filter(|session| session.belongs_to(player_id)),
+            Err(e) => {
+                tracing::warn!("Failed to load resume session: {}", e);
+                None
+            }
+        },
+        None => None,
+    };
+
+    // Subscribe to the initial geohash plus any restored regions
+    let mut initial_geohashes = resumed
+        .as_ref()
+        .map(|session| session.geohashes.clone())
+        .unwrap_or_default();
+    if !initial_geohashes.contains(&get_geohash_prefix(&query.geohash)) {
+        initial_geohashes.push(query.geohash.clone());
+    }
+    initial_geohashes.truncate(state.config.websocket.max_subscriptions_per_client);
+    // Within the limit after truncating
+    let receivers = state
+        .broadcaster
+        .subscribe(&connection_id, initial_geohashes.clone())
+        .await
+        .unwrap_or_default();
+    let subscribed_seq = state.broadcaster.last_event_seq();
 
     // Send welcome message
+    let resume_token = Uuid::new_v4();
     let welcome = WsMessage::Welcome {
         connection_id: connection_id.clone(),
         server_time: chrono::Utc::now().timestamp_millis(),
+        max_allowed_skew_seconds: crate::utils::clock::MAX_ALLOWED_SKEW_SECONDS,
+        resume_token: resume_token.to_string(),
+        resumed: resumed.is_some(),
     };
-    if let Ok(json) = serde_json::to_string(&welcome) {
-        let _ = sender.send(Message::Text(json)).await;
+    send_message(&state.broadcaster, &mut sender, format, &welcome).await;
+
+    // Unread notifications, so the client can badge right away
+    if let Some(player_id) = player_id {
+        match state.services.notification.get_counts(player_id).await {
+            Ok(counts) => {
+                let count = WsMessage::NotificationCount { unread: counts.unread };
+                send_message(&state.broadcaster, &mut sender, format, &count).await;
+            }
+            Err(e) => tracing::warn!("Failed to count notifications for {}: {}", player_id, e),
+        }
     }
 
     // Send subscription confirmation
     let confirm = WsMessage::Subscribed {
-        geohashes: vec![initial_geohash],
+        geohashes: initial_geohashes.clone(),
     };
-    if let Ok(json) = serde_json::to_string(&confirm) {
-        let _ = sender.send(Message::Text(json)).await;
+    send_message(&state.broadcaster, &mut sender, format, &confirm).await;
+
+    let restored_geohashes = resumed
+        .as_ref()
+        .map(|session| session.restored(&initial_geohashes))
+        .unwrap_or_default();
+    if resumed.is_some() {
+        let restored = WsMessage::Resumed {
+            restored_geohashes: restored_geohashes.clone(),
+        };
+        send_message(&state.broadcaster, &mut sender, format, &restored).await;
     }
 
-    // Create a channel to receive broadcast messages
-    let (broadcast_tx, mut broadcast_rx) = tokio::sync::mpsc::channel::<WsMessage>(100);
-
-    // Spawn task to forward broadcast messages
-    let broadcast_handle = tokio::spawn({
-        let broadcast_tx = broadcast_tx.clone();
-        async move {
-            if let Some(mut rx) = receivers.pop() {
-                while let Ok(msg) = rx.recv().await {
-                    if broadcast_tx.send(msg).await.is_err() {
-                        break;
-                    }
-                }
+    // Titans already active in the initial region, unless a resumed client
+    // already has them and only needs the replayed events
+    let initial_prefix = get_geohash_prefix(&query.geohash);
+    if !restored_geohashes.contains(&initial_prefix) {
+        let snapshot = state
+            .services
+            .map
+            .get_region_titans(&initial_prefix, SNAPSHOT_MAX_TITANS as i64 + 1)
+            .await;
+        match snapshot {
+            Ok(titans) => {
+                let snapshot = WsMessage::titan_snapshot(titans);
+                send_message(&state.broadcaster, &mut sender, format, &snapshot).await;
             }
+            Err(e) => tracing::warn!("Failed to load Titan snapshot for {}: {}", connection_id, e),
+        }
+    }
+
+    // Replay region events that fired while the client was away; later ones
+    // arrive through the new subscriptions
+    if let Some(session) = &resumed {
+        let missed = state
+            .broadcaster
+            .missed_events(&initial_geohashes, session.last_seq, subscribed_seq)
+            .await;
+        tracing::debug!("Connection {} resumed, replaying {} events", connection_id, missed.len());
+        for message in missed {
+            send_message(&state.broadcaster, &mut sender, format, &message).await;
         }
-    });
+    }
+
+    // Forward region broadcasts; later Subscribe messages add more regions
+    let mut forwarders = RegionForwarders::new(broadcast_tx);
+    forwarders.add(&initial_geohashes, receivers);
 
     // Heartbeat interval
     let mut heartbeat_interval = tokio::time::interval(Duration::from_secs(30));
+    let mut rate_limiter = RateLimiter::new(&*state.game.read().await);
 
     loop {
         tokio::select! {
             // Handle incoming messages from client
             msg = receiver.next() => {
                 match msg {
-                    Some(Ok(Message::Text(text))) => {
-                        if let Ok(ws_msg) = serde_json::from_str::<WsMessage>(&text) {
-                            handle_client_message(
-                                &state,
-                                &connection_id,
-                                &mut sender,
-                                ws_msg,
-                            ).await;
+                    Some(Ok(frame @ (Message::Text(_) | Message::Binary(_)))) => {
+                        state.broadcaster.record_received();
+                        match rate_limiter.check(Instant::now()) {
+                            RateDecision::Allow => {
+                                if let Some(ws_msg) = format.decode(&frame) {
+                                    handle_client_message(
+                                        &state,
+                                        &connection_id,
+                                        &mut sender,
+                                        format,
+                                        &mut forwarders,
+                                        ws_msg,
+                                    ).await;
+                                }
+                            }
+                            RateDecision::Drop { notify } => {
+                                if notify {
+                                    send_rate_limited(&mut sender, format, &state).await;
+                                }
+                            }
+                            RateDecision::Disconnect => {
+                                tracing::warn!(
+                                    "Closing WebSocket {} after {} rate limit violations",
+                                    connection_id,
+                                    rate_limiter.violations()
+                                );
+                                send_rate_limited(&mut sender, format, &state).await;
+                                let _ = sender
+                                    .send(Message::Close(Some(CloseFrame {
+                                        code: close_code::POLICY,
+                                        reason: "rate limited".into(),
+                                    })))
+                                    .await;
+                                break;
+                            }
                         }
                     }
                     Some(Ok(Message::Ping(data))) => {
@@ -514,10 +1718,8 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>, query: WsQuery)
 
             // Forward broadcast messages to client
             Some(msg) = broadcast_rx.recv() => {
-                if let Ok(json) = serde_json::to_string(&msg) {
-                    if sender.send(Message::Text(json)).await.is_err() {
-                        break;
-                    }
+                if !send_message(&state.broadcaster, &mut sender, format, &msg).await {
+                    break;
                 }
             }
 
@@ -526,56 +1728,236 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>, query: WsQuery)
                 let pong = WsMessage::Pong {
                     server_time: chrono::Utc::now().timestamp_millis(),
                 };
-                if let Ok(json) = serde_json::to_string(&pong) {
-                    if sender.send(Message::Text(json)).await.is_err() {
-                        break;
-                    }
+                if !send_message(&state.broadcaster, &mut sender, format, &pong).await {
+                    break;
                 }
+                state.broadcaster.touch_client(&connection_id).await;
             }
         }
     }
 
-    // Cleanup
-    broadcast_handle.abort();
+    // Cleanup, leaving the session behind for the resume token
+    drop(forwarders);
+    let session = ResumeSession {
+        player_id,
+        geohashes: state.broadcaster.subscriptions(&connection_id).await,
+        last_seq: state.broadcaster.last_event_seq(),
+    };
+    if let Err(e) = resume::save(&mut state.db.redis.clone(), resume_token, &session).await {
+        tracing::warn!("Failed to save resume session for {}: {}", connection_id, e);
+    }
     state.broadcaster.unregister_client(&connection_id).await;
+
+    // Losing the last connection mid-match starts the PvP reconnect timer
+    if let Some(player_id) = player_id {
+        if !state.broadcaster.is_player_online(player_id).await {
+            let pvp = &state.services.pvp;
+            let marked = match pvp.active_match_id(player_id).await {
+                Ok(Some(match_id)) => pvp.mark_disconnected(player_id, match_id).await.map(|_| ()),
+                Ok(None) => Ok(()),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = marked {
+                tracing::warn!("Failed to mark {} disconnected from PvP: {:?}", player_id, e);
+            }
+        }
+    }
     tracing::debug!("WebSocket connection {} closed", connection_id);
 }
 
+/// Encode a message in the connection's wire format and send it, counting it
+/// in the broadcaster's stats; false once the socket is gone (a message that
+/// fails to encode is skipped)
+async fn send_message(
+    broadcaster: &Broadcaster,
+    sender: &mut SplitSink<WebSocket, Message>,
+    format: WireFormat,
+    message: &WsMessage,
+) -> bool {
+    match format.encode(message) {
+        Some(frame) => {
+            let sent = sender.send(frame).await.is_ok();
+            if sent {
+                broadcaster.record_sent();
+            }
+            sent
+        }
+        None => true,
+    }
+}
+
+/// Tell the client its messages are being dropped
+async fn send_rate_limited(
+    sender: &mut SplitSink<WebSocket, Message>,
+    format: WireFormat,
+    state: &AppState,
+) {
+    let error = WsMessage::Error {
+        code: "RATE_LIMITED".into(),
+        message: format!(
+            "Too many messages; the limit is {} per second",
+            state.game.read().await.ws_messages_per_second
+        ),
+    };
+    send_message(&state.broadcaster, sender, format, &error).await;
+}
+
+/// Check a location update against the player's previous fix, returning the error for a dropped one
+async fn check_location_update(
+    state: &Arc<AppState>,
+    connection_id: &str,
+    lat: f64,
+    lng: f64,
+) -> Result<(), WsMessage> {
+    let Some(client) = state.broadcaster.get_client(connection_id).await else {
+        return Ok(());
+    };
+    // Anonymous connections can't capture
+    let Some(player_id) = client.player_id else {
+        return Ok(());
+    };
+
+    if client
+        .location_quarantined_until
+        .is_some_and(|until| tokio::time::Instant::now() < until)
+    {
+        return Err(WsMessage::Error {
+            code: "LOCATION_QUARANTINED".into(),
+            message: "Location updates are paused after repeated speed violations".into(),
+        });
+    }
+
+    let location = &state.services.location;
+    let previous = match (client.last_location, client.last_location_at) {
+        (Some(last), Some(at)) => Some((last.lat, last.lng, at)),
+        _ => location.get_last_location(player_id).await.ok().flatten(),
+    };
+
+    match location
+        .check_movement(player_id, previous, lat, lng, LocationSource::WebSocket)
+        .await
+    {
+        Ok(MovementCheck::Accepted) => match location.check_trail(player_id, lat, lng, chrono::Utc::now()).await {
+            Ok(()) => Ok(()),
+            Err(crate::error::AppError::SpeedViolation) => Err(WsMessage::Error {
+                code: "SPEED_VIOLATION".into(),
+                message: "Recent locations don't follow a plausible path".into(),
+            }),
+            Err(e) => {
+                tracing::warn!("Trail check for {} failed: {:?}", player_id, e);
+                Ok(())
+            }
+        },
+        Ok(MovementCheck::SpeedViolation {
+            speed,
+            max,
+            quarantined,
+        }) => {
+            if quarantined {
+                let seconds = state.game.read().await.location_quarantine_seconds;
+                state
+                    .broadcaster
+                    .quarantine_client_location(connection_id, Duration::from_secs(seconds))
+                    .await;
+            }
+            Err(WsMessage::Error {
+                code: "SPEED_VIOLATION".into(),
+                message: format!("Moved at {:.0} m/s; the limit is {:.0} m/s", speed, max),
+            })
+        }
+        Err(e) => {
+            tracing::warn!("Location check for {} failed: {:?}", player_id, e);
+            Ok(())
+        }
+    }
+}
+
 /// Handle messages from client
 async fn handle_client_message(
     state: &Arc<AppState>,
     connection_id: &str,
     sender: &mut futures_util::stream::SplitSink<WebSocket, Message>,
+    format: WireFormat,
+    forwarders: &mut RegionForwarders,
     message: WsMessage,
 ) {
+    state.broadcaster.touch_client(connection_id).await;
+
     match message {
         WsMessage::Subscribe { geohashes } => {
-            let _ = state.broadcaster.subscribe(connection_id, geohashes.clone()).await;
-            let response = WsMessage::Subscribed { geohashes };
-            if let Ok(json) = serde_json::to_string(&response) {
-                let _ = sender.send(Message::Text(json)).await;
-            }
+            let response = match state.broadcaster.subscribe(connection_id, geohashes.clone()).await {
+                Ok(receivers) => {
+                    forwarders.add(&geohashes, receivers);
+                    WsMessage::Subscribed { geohashes }
+                }
+                Err(e) => WsMessage::Error {
+                    code: "SUBSCRIPTION_LIMIT".into(),
+                    message: format!(
+                        "Subscribed to {} of at most {} regions; {} more requested",
+                        e.current, e.limit, e.requested
+                    ),
+                },
+            };
+            send_message(&state.broadcaster, sender, format, &response).await;
         }
 
         WsMessage::Unsubscribe { geohashes } => {
             state.broadcaster.unsubscribe(connection_id, geohashes.clone()).await;
+            forwarders.remove(&geohashes);
             let response = WsMessage::Unsubscribed { geohashes };
-            if let Ok(json) = serde_json::to_string(&response) {
-                let _ = sender.send(Message::Text(json)).await;
-            }
+            send_message(&state.broadcaster, sender, format, &response).await;
         }
 
         WsMessage::LocationUpdate { lat, lng, geohash: _ } => {
-            state.broadcaster.update_client_location(connection_id, Location { lat, lng }).await;
+            match check_location_update(state, connection_id, lat, lng).await {
+                Ok(()) => {
+                    let before = state.broadcaster.get_client(connection_id).await;
+                    state.broadcaster.update_client_location(connection_id, Location { lat, lng }).await;
+                    if let Some(client) = before {
+                        friends::push_location(state, &client, Location { lat, lng }).await;
+                    }
+                }
+                Err(error) => {
+                    send_message(&state.broadcaster, sender, format, &error).await;
+                }
+            }
+            state.broadcaster.refresh_presence(connection_id).await;
+        }
+
+        WsMessage::GetPresence { geohash } => {
+            let response = WsMessage::PresenceCount {
+                count: state.broadcaster.get_player_count(&geohash).await,
+                geohash: get_geohash_prefix(&geohash),
+            };
+            send_message(&state.broadcaster, sender, format, &response).await;
+        }
+
+        WsMessage::SpectateMatch { match_id } => {
+            for response in spectate::start(state, connection_id, &match_id).await {
+                send_message(&state.broadcaster, sender, format, &response).await;
+            }
+        }
+
+        WsMessage::StopSpectating { match_id } => {
+            if let Ok(match_id) = Uuid::parse_str(&match_id) {
+                state.broadcaster.stop_spectating(connection_id, match_id).await;
+            }
+        }
+
+        WsMessage::SubscribeMarketplace => {
+            state.broadcaster.subscribe_marketplace(connection_id).await;
+        }
+
+        WsMessage::UnsubscribeMarketplace => {
+            state.broadcaster.unsubscribe_marketplace(connection_id).await;
         }
 
         WsMessage::Ping => {
+            state.broadcaster.refresh_presence(connection_id).await;
             let response = WsMessage::Pong {
                 server_time: chrono::Utc::now().timestamp_millis(),
             };
-            if let Ok(json) = serde_json::to_string(&response) {
-                let _ = sender.send(Message::Text(json)).await;
-            }
+            send_message(&state.broadcaster, sender, format, &response).await;
         }
 
         _ => {
@@ -589,3 +1971,517 @@ pub fn routes(state: Arc<AppState>) -> Router {
         .route("/ws", get(ws_handler))
         .with_state(state)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{PvpMatchStatus, SpectatorSide};
+
+    fn chat_message(channel_id: Uuid) -> WsMessage {
+        WsMessage::ChatMessage {
+            channel_id: channel_id.to_string(),
+            message_id: Uuid::new_v4().to_string(),
+            sender_id: Uuid::new_v4().to_string(),
+            sender_username: Some("alice".into()),
+            content: "hello".into(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Register a client backed by a plain channel instead of a socket
+    async fn fake_client(broadcaster: &Broadcaster, player_id: Uuid) -> mpsc::Receiver<WsMessage> {
+        let (tx, rx) = mpsc::channel(8);
+        broadcaster
+            .register_client(&Uuid::new_v4().to_string(), Some(player_id), None, tx)
+            .await;
+        rx
+    }
+
+    #[test]
+    fn test_pvp_match_found_is_about_the_opponent() {
+        let found = FoundMatch {
+            match_id: Uuid::new_v4(),
+            ready_deadline: chrono::Utc::now(),
+            players: [
+                MatchedPlayer { player_id: Uuid::new_v4(), username: Some("alice".into()), elo: 1200 },
+                MatchedPlayer { player_id: Uuid::new_v4(), username: None, elo: 1100 },
+            ],
+        };
+
+        let [(first, first_opponent), (second, second_opponent)] = found.pairings();
+        assert_eq!(first.player_id, second_opponent.player_id);
+        assert_eq!(second.player_id, first_opponent.player_id);
+
+        let json = serde_json::to_value(WsMessage::pvp_match_found(&found, first_opponent)).unwrap();
+        assert_eq!(json["type"], "pvp_match_found");
+        assert_eq!(json["data"]["opponent_id"], second.player_id.to_string());
+        assert_eq!(json["data"]["opponent_elo"], 1100);
+        assert!(json["data"]["opponent_username"].is_null());
+        assert_eq!(json["data"]["ready_deadline"], found.ready_deadline.to_rfc3339());
+    }
+
+    #[test]
+    fn test_titan_update_round_trip() {
+        let message = WsMessage::TitanUpdate {
+            titan_id: "t1".into(),
+            capture_count: 2,
+            remaining_captures: 3,
+        };
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(json["type"], "titan_update");
+        assert_eq!(json["data"]["capture_count"], 2);
+
+        match serde_json::from_value::<WsMessage>(json).unwrap() {
+            WsMessage::TitanUpdate { titan_id, capture_count, remaining_captures } => {
+                assert_eq!((titan_id.as_str(), capture_count, remaining_captures), ("t1", 2, 3));
+            }
+            other => panic!("expected titan_update, got {:?}", other),
+        }
+    }
+
+    fn spawn_row(threat_class: i16, genes: Vec<u8>) -> TitanSpawn {
+        TitanSpawn {
+            id: Uuid::new_v4(),
+            poi_id: None,
+            location_lat: 35.681,
+            location_lng: 139.767,
+            geohash: "xn77h".into(),
+            element: crate::models::Element::Storm,
+            threat_class,
+            species_id: 2_401,
+            genes,
+            spawned_at: chrono::Utc::now(),
+            expires_at: chrono::Utc::now(),
+            captured_by: None,
+            captured_at: None,
+            capture_count: 0,
+            max_captures: 1,
+        }
+    }
+
+    #[test]
+    fn test_rare_spawn_from_threat_class_four() {
+        let common = WsMessage::titan_spawn(&spawn_row(3, vec![255; 6]), None);
+        assert!(matches!(common, WsMessage::TitanSpawn { .. }));
+
+        let rare = WsMessage::titan_spawn(&spawn_row(4, vec![10, 20, 30, 40, 50, 60]), None);
+        match rare {
+            WsMessage::TitanRareSpawn { is_legendary, special_genes, spawn_announcement, .. } => {
+                assert!(!is_legendary);
+                assert_eq!(special_genes, None);
+                assert_eq!(spawn_announcement, "A storm Titan has appeared nearby!");
+            }
+            other => panic!("expected titan_rare_spawn, got {:?}", other),
+        }
+
+        let genes = vec![10, 20, 245, 40, 50, 60];
+        let legendary = WsMessage::titan_spawn(&spawn_row(5, genes), Some("Tokyo Station".into()));
+        let json = serde_json::to_value(&legendary).unwrap();
+        assert_eq!(json["type"], "titan_rare_spawn");
+        assert_eq!(json["data"]["is_legendary"], true);
+        assert_eq!(json["data"]["special_genes"], serde_json::json!([10, 20, 245, 40, 50, 60]));
+        assert_eq!(
+            json["data"]["spawn_announcement"],
+            "A legendary storm Titan has appeared near Tokyo Station!"
+        );
+    }
+
+    #[test]
+    fn test_event_spawn_always_rare() {
+        let event = WsMessage::event_titan_spawn(&spawn_row(2, vec![1; 6]), None, "Winter Solstice");
+        match event {
+            WsMessage::TitanRareSpawn { is_legendary, spawn_announcement, .. } => {
+                assert!(!is_legendary);
+                assert_eq!(spawn_announcement, "Winter Solstice: A storm Titan has appeared nearby!");
+            }
+            other => panic!("expected titan_rare_spawn, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_titan_snapshot_truncated_at_cap() {
+        let titan = |threat_class| TitanSpawnResponse {
+            id: Uuid::new_v4(),
+            location: crate::models::GeoPoint { lat: 35.68, lng: 139.76 },
+            element: crate::models::Element::Void,
+            threat_class,
+            species_id: 3_101,
+            distance: None,
+            expires_at: chrono::Utc::now(),
+            poi_name: Some("Tokyo Station".into()),
+            is_available: true,
+        };
+
+        match WsMessage::titan_snapshot(vec![titan(5), titan(1)]) {
+            WsMessage::TitanSnapshot { titans, truncated } => {
+                assert!(!truncated);
+                assert_eq!(titans.len(), 2);
+                assert_eq!(titans[0].element, "void");
+                assert_eq!(titans[0].poi_name.as_deref(), Some("Tokyo Station"));
+            }
+            other => panic!("expected titan_snapshot, got {:?}", other),
+        }
+
+        let full: Vec<_> = (0..=SNAPSHOT_MAX_TITANS).map(|_| titan(2)).collect();
+        match WsMessage::titan_snapshot(full) {
+            WsMessage::TitanSnapshot { titans, truncated } => {
+                assert!(truncated);
+                assert_eq!(titans.len(), SNAPSHOT_MAX_TITANS);
+            }
+            other => panic!("expected titan_snapshot, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chat_message_reaches_every_subscriber() {
+        let broadcaster = Broadcaster::new();
+        let channel_id = Uuid::new_v4();
+        let (alice, bob, carol) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        let mut alice_rx = fake_client(&broadcaster, alice).await;
+        let mut bob_rx = fake_client(&broadcaster, bob).await;
+        let mut carol_rx = fake_client(&broadcaster, carol).await;
+        broadcaster.subscribe_chat_channel(alice, channel_id).await;
+        broadcaster.subscribe_chat_channel(bob, channel_id).await;
+
+        broadcaster
+            .broadcast_chat_message(channel_id, chat_message(channel_id))
+            .await;
+
+        for rx in [&mut alice_rx, &mut bob_rx] {
+            match rx.try_recv() {
+                Ok(WsMessage::ChatMessage { channel_id: id, content, .. }) => {
+                    assert_eq!(id, channel_id.to_string());
+                    assert_eq!(content, "hello");
+                }
+                other => panic!("expected chat message, got {:?}", other),
+            }
+        }
+        // Not subscribed to the channel
+        assert!(carol_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unsubscribed_or_disconnected_clients_skipped() {
+        let broadcaster = Broadcaster::new();
+        let channel_id = Uuid::new_v4();
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let mut alice_rx = fake_client(&broadcaster, alice).await;
+        let bob_rx = fake_client(&broadcaster, bob).await;
+        broadcaster.subscribe_chat_channel(alice, channel_id).await;
+        broadcaster.subscribe_chat_channel(bob, channel_id).await;
+        broadcaster.unsubscribe_chat_channel(alice, channel_id).await;
+        drop(bob_rx);
+
+        broadcaster
+            .broadcast_chat_message(channel_id, chat_message(channel_id))
+            .await;
+        assert!(alice_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_to_player() {
+        let broadcaster = Broadcaster::new();
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut alice_rx = fake_client(&broadcaster, alice).await;
+        let mut bob_rx = fake_client(&broadcaster, bob).await;
+
+        broadcaster
+            .broadcast_to_player(bob, chat_message(Uuid::new_v4()))
+            .await;
+
+        assert!(matches!(bob_rx.try_recv(), Ok(WsMessage::ChatMessage { .. })));
+        assert!(alice_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_global_broadcast_reaches_each_connection_once() {
+        let broadcaster = Broadcaster::new();
+        let (tx, mut alice_rx) = mpsc::channel(8);
+        broadcaster.register_client("alice", Some(Uuid::new_v4()), None, tx).await;
+        let mut bob_rx = fake_client(&broadcaster, Uuid::new_v4()).await;
+
+        // Alice watches two regions; Bob none
+        broadcaster
+            .subscribe("alice", vec!["xn77h".into(), "xn77j".into()])
+            .await
+            .unwrap();
+
+        broadcaster
+            .broadcast_global(WsMessage::SystemAnnouncement {
+                title: "Maintenance".into(),
+                body: "Back at 04:00 UTC".into(),
+                severity: "warning".into(),
+            })
+            .await;
+
+        for rx in [&mut alice_rx, &mut bob_rx] {
+            assert!(matches!(rx.try_recv(), Ok(WsMessage::SystemAnnouncement { .. })));
+            assert!(rx.try_recv().is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_closed_connection_handle_is_dropped() {
+        let broadcaster = Broadcaster::new();
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let alice_rx = fake_client(&broadcaster, alice).await;
+        let _bob_rx = fake_client(&broadcaster, bob).await;
+
+        // Alice's socket loop ended without unregistering
+        drop(alice_rx);
+        broadcaster
+            .broadcast_to_player(alice, chat_message(Uuid::new_v4()))
+            .await;
+
+        assert_eq!(broadcaster.senders.read().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_old_connection_closing_keeps_reconnected_player() {
+        let broadcaster = Broadcaster::new();
+        let alice = Uuid::new_v4();
+
+        let (old_tx, _old_rx) = mpsc::channel(8);
+        broadcaster.register_client("old", Some(alice), None, old_tx).await;
+        let (new_tx, mut new_rx) = mpsc::channel(8);
+        broadcaster.register_client("new", Some(alice), None, new_tx).await;
+
+        // The stale socket goes away after the player reconnected
+        broadcaster.unregister_client("old").await;
+        assert!(broadcaster.is_player_online(alice).await);
+
+        broadcaster
+            .broadcast_to_player(alice, chat_message(Uuid::new_v4()))
+            .await;
+        assert!(matches!(new_rx.try_recv(), Ok(WsMessage::ChatMessage { .. })));
+
+        broadcaster.unregister_client("new").await;
+        assert!(!broadcaster.is_player_online(alice).await);
+        assert!(broadcaster.senders.read().await.is_empty());
+    }
+
+    fn next_presence(rx: &mut broadcast::Receiver<WsMessage>) -> (String, usize) {
+        match rx.try_recv() {
+            Ok(WsMessage::PresenceCount { geohash, count }) => (geohash, count),
+            other => panic!("expected presence_count, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_presence_pushed_on_subscription_changes() {
+        let broadcaster = Broadcaster::new();
+        let (tx, _rx) = mpsc::channel(8);
+        broadcaster.register_client("alice", None, None, tx).await;
+        let mut alice_region = broadcaster.subscribe("alice", vec!["xn77h9".into()]).await.unwrap().remove(0);
+        assert_eq!(next_presence(&mut alice_region), ("xn77h".into(), 1));
+
+        let (tx, _rx) = mpsc::channel(8);
+        broadcaster.register_client("bob", None, None, tx).await;
+        broadcaster.subscribe("bob", vec!["xn77h".into()]).await.unwrap();
+        assert_eq!(next_presence(&mut alice_region), ("xn77h".into(), 2));
+
+        // Repeating a subscription doesn't change the count
+        broadcaster.subscribe("bob", vec!["xn77h".into()]).await.unwrap();
+        assert!(alice_region.try_recv().is_err());
+
+        broadcaster.unsubscribe("bob", vec!["xn77h".into()]).await;
+        assert_eq!(next_presence(&mut alice_region), ("xn77h".into(), 1));
+        assert_eq!(broadcaster.get_player_count("xn77hzz").await, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_recent_message_survives_cleanup() {
+        let broadcaster = Broadcaster::new();
+        let (tx, _rx) = mpsc::channel(8);
+        broadcaster.register_client("alice", None, None, tx).await;
+
+        // A Ping 40s in, no location updates
+        tokio::time::advance(Duration::from_secs(40)).await;
+        broadcaster.touch_client("alice").await;
+
+        tokio::time::advance(Duration::from_secs(40)).await;
+        assert!(broadcaster.cleanup_stale_connections().await.is_empty());
+        assert_eq!(broadcaster.get_total_connections().await, 1);
+
+        tokio::time::advance(Duration::from_secs(30)).await;
+        assert_eq!(broadcaster.cleanup_stale_connections().await, ["alice"]);
+    }
+
+    fn titan_ids(messages: Vec<WsMessage>) -> Vec<String> {
+        messages
+            .into_iter()
+            .map(|message| match message {
+                WsMessage::TitanExpired { titan_id } => titan_id,
+                other => panic!("expected titan_expired, got {:?}", other),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_missed_events_for_resume() {
+        let broadcaster = Broadcaster::new().with_replay_buffer(3);
+        let expired = |id: &str| WsMessage::TitanExpired { titan_id: id.into() };
+
+        broadcaster.broadcast("xn77h", expired("before")).await;
+        let disconnected_at = broadcaster.last_event_seq();
+
+        broadcaster.broadcast("xn77h", expired("t1")).await;
+        broadcaster.broadcast("u4pru", expired("elsewhere")).await;
+        broadcaster.broadcast("xn77h", expired("t2")).await;
+        broadcaster.broadcast("dr5re", expired("o1")).await;
+        let resubscribed_at = broadcaster.last_event_seq();
+        broadcaster.broadcast("xn77h", expired("after")).await;
+
+        let regions = vec!["xn77h9".to_string(), "dr5re".to_string()];
+        let missed = broadcaster.missed_events(&regions, disconnected_at, resubscribed_at).await;
+        assert_eq!(titan_ids(missed), ["t1", "t2", "o1"]);
+
+        // Only the newest three per region are kept
+        let all = broadcaster.missed_events(&regions, 0, u64::MAX).await;
+        assert_eq!(titan_ids(all), ["t1", "t2", "o1", "after"]);
+    }
+
+    #[tokio::test]
+    async fn test_subscription_limit() {
+        let broadcaster = Broadcaster::new().with_subscription_limit(2);
+        let (tx, _rx) = mpsc::channel(8);
+        broadcaster.register_client("alice", None, None, tx).await;
+        broadcaster.subscribe("alice", vec!["xn77h".into()]).await.unwrap();
+
+        // Over the limit: none of the new regions are added
+        let err = broadcaster
+            .subscribe("alice", vec!["u4pru".into(), "dr5re".into()])
+            .await
+            .unwrap_err();
+        assert_eq!(err, SubscriptionLimitExceeded { limit: 2, current: 1, requested: 2 });
+        assert_eq!(broadcaster.get_player_count("u4pru").await, 0);
+
+        // Regions already held don't count again
+        let receivers = broadcaster
+            .subscribe("alice", vec!["xn77h9".into(), "u4pru".into()])
+            .await
+            .unwrap();
+        assert_eq!(receivers.len(), 2);
+        let clients = broadcaster.clients.read().await;
+        assert_eq!(clients["alice"].subscription_count(), 2);
+    }
+
+    fn match_update(match_id: Uuid, status: PvpMatchStatus) -> WsMessage {
+        let side = |hp| SpectatorSide {
+            player_id: Uuid::new_v4(),
+            username: None,
+            elo: 1000,
+            hp,
+            titan: None,
+        };
+        WsMessage::MatchUpdate {
+            match_id: match_id.to_string(),
+            state: Box::new(SpectatorMatchState {
+                match_id,
+                status,
+                turn_number: 3,
+                current_turn: None,
+                turn_deadline: None,
+                winner_id: None,
+                player1: side(100),
+                player2: side(40),
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_match_spectators() {
+        let broadcaster = Broadcaster::new().with_spectator_limit(2);
+        let match_id = Uuid::new_v4();
+        let mut receivers = Vec::new();
+        for connection_id in ["alice", "bob", "carol"] {
+            let (tx, rx) = mpsc::channel(8);
+            broadcaster.register_client(connection_id, None, None, tx).await;
+            receivers.push(rx);
+        }
+
+        assert_eq!(broadcaster.spectate_match("alice", match_id).await, Ok(1));
+        assert_eq!(broadcaster.spectate_match("bob", match_id).await, Ok(2));
+        // Watching again doesn't count twice
+        assert_eq!(broadcaster.spectate_match("bob", match_id).await, Ok(2));
+        assert_eq!(
+            broadcaster.spectate_match("carol", match_id).await,
+            Err(SpectatorLimitExceeded { limit: 2 })
+        );
+
+        broadcaster
+            .broadcast_match_update(match_id, match_update(match_id, PvpMatchStatus::Active))
+            .await;
+        broadcaster
+            .broadcast_match_update(Uuid::new_v4(), match_update(match_id, PvpMatchStatus::Active))
+            .await;
+        assert!(matches!(receivers[0].try_recv(), Ok(WsMessage::MatchUpdate { .. })));
+        assert!(matches!(receivers[1].try_recv(), Ok(WsMessage::MatchUpdate { .. })));
+        assert!(receivers[2].try_recv().is_err());
+
+        // Leaving frees a place
+        broadcaster.unregister_client("bob").await;
+        assert_eq!(broadcaster.spectate_match("carol", match_id).await, Ok(2));
+
+        // The final update is the last one
+        broadcaster
+            .broadcast_match_update(match_id, match_update(match_id, PvpMatchStatus::Completed))
+            .await;
+        assert!(broadcaster.match_spectators.read().await.is_empty());
+        assert!(matches!(
+            receivers[2].try_recv(),
+            Ok(WsMessage::MatchUpdate { state, .. }) if state.is_over()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_connection_stats() {
+        let broadcaster = Broadcaster::new();
+        let (alice_tx, _alice_rx) = mpsc::channel(8);
+        let (guest_tx, _guest_rx) = mpsc::channel(8);
+        broadcaster.register_client("alice", Some(Uuid::new_v4()), None, alice_tx).await;
+        broadcaster.register_client("guest", None, None, guest_tx).await;
+        broadcaster.subscribe("alice", vec!["xn77h".into(), "u4pru".into()]).await.unwrap();
+        broadcaster.subscribe("guest", vec!["xn77h9".into()]).await.unwrap();
+
+        broadcaster.record_received();
+        for _ in 0..3 {
+            broadcaster.record_sent();
+        }
+
+        let stats = broadcaster.connection_stats().await;
+        assert_eq!((stats.total_connections, stats.authenticated, stats.anonymous), (2, 1, 1));
+        assert_eq!(stats.subscribers_by_geohash.get("xn77h"), Some(&2));
+        assert_eq!(stats.subscribers_by_geohash.get("u4pru"), Some(&1));
+        assert_eq!((stats.messages_sent, stats.messages_received), (3, 1));
+    }
+
+    #[tokio::test]
+    async fn test_relay_reaches_clients_on_other_instance() {
+        let (relay_a, mut published) = RedisRelay::capture();
+        let (relay_b, _) = RedisRelay::capture();
+        let instance_a = Broadcaster::with_relay(relay_a);
+        let instance_b = Broadcaster::with_relay(relay_b);
+
+        // Alice is connected to instance B and watching her region
+        let alice = Uuid::new_v4();
+        let (tx, mut alice_rx) = mpsc::channel(8);
+        instance_b.register_client("alice", Some(alice), None, tx).await;
+        let mut region_rx = instance_b.subscribe("alice", vec!["xn77h9".into()]).await.unwrap().remove(0);
+        next_presence(&mut region_rx);
+
+        // Both broadcasts originate on instance A, which has no local clients
+        instance_a.broadcast("xn77h", WsMessage::TitanExpired { titan_id: "t1".into() }).await;
+        instance_a.broadcast_to_player(alice, chat_message(Uuid::new_v4())).await;
+
+        for _ in 0..2 {
+            let (channel, payload) = published.recv().await.unwrap();
+            instance_b.relay.as_ref().unwrap().receive(&instance_b, &channel, &payload).await;
+        }
+
+        assert!(matches!(region_rx.try_recv(), Ok(WsMessage::TitanExpired { .. })));
+        assert!(matches!(alice_rx.try_recv(), Ok(WsMessage::ChatMessage { .. })));
+    }
+}
@@ -0,0 +1,93 @@
+//! Live notification delivery
+//!
+//! Notifications are written to Postgres first and read over REST; the ones
+//! created on this instance are also pushed to their player's sockets (on any
+//! instance, through the relay) while the player is online.
+
+use std::sync::Arc;
+
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use super::{Broadcaster, WsMessage};
+use crate::models::Notification;
+use crate::AppState;
+
+/// Push notifications created on this instance to online players
+pub fn start_forwarder(state: Arc<AppState>) {
+    let notifications = state.services.notification.subscribe();
+    tokio::spawn(async move { forward(&state.broadcaster, notifications).await });
+}
+
+async fn forward(broadcaster: &Broadcaster, mut notifications: broadcast::Receiver<Notification>) {
+    loop {
+        match notifications.recv().await {
+            Ok(notification) => {
+                // Offline players read it over REST when they're back
+                if broadcaster.is_player_online(notification.player_id).await {
+                    let message = WsMessage::notification(&notification);
+                    broadcaster.broadcast_to_player(notification.player_id, message).await;
+                }
+            }
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!("Notification push fell behind, {} not pushed", skipped);
+            }
+            Err(RecvError::Closed) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use tokio::sync::mpsc;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::models::NotificationType;
+
+    fn notification(player_id: Uuid) -> Notification {
+        Notification {
+            id: Uuid::new_v4(),
+            player_id,
+            notification_type: NotificationType::GuildKicked,
+            title: "Kicked from Guild".into(),
+            message: "You have been kicked from the guild.".into(),
+            data: None,
+            is_read: false,
+            created_at: Utc::now(),
+            expires_at: None,
+            read_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pushes_to_online_player_only() {
+        let broadcaster = Broadcaster::new();
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let (tx, mut alice_rx) = mpsc::channel(8);
+        broadcaster.register_client("alice", Some(alice), None, tx).await;
+
+        let (live, notifications) = broadcast::channel(8);
+        let forwarder = tokio::spawn(async move { forward(&broadcaster, notifications).await });
+
+        let sent = notification(alice);
+        live.send(notification(bob)).unwrap();
+        live.send(sent.clone()).unwrap();
+
+        match tokio::time::timeout(Duration::from_secs(1), alice_rx.recv()).await {
+            Ok(Some(WsMessage::Notification { id, notification_type, title, .. })) => {
+                assert_eq!(id, sent.id.to_string());
+                assert_eq!(notification_type, NotificationType::GuildKicked);
+                assert_eq!(title, sent.title);
+            }
+            other => panic!("expected notification, got {:?}", other),
+        }
+
+        // The forwarder stops once the service is gone
+        drop(live);
+        forwarder.await.unwrap();
+        assert!(alice_rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,92 @@
+//! Live quest progress
+//!
+//! Quest events are recorded by the services that see them happen; the
+//! progress and completions recorded on this instance are pushed to the
+//! player's sockets (on any instance, through the relay) so the client can
+//! show a toast. Offline players see the same state over `GET /quests`.
+
+use std::sync::Arc;
+
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use super::{Broadcaster, WsMessage};
+use crate::models::QuestUpdate;
+use crate::AppState;
+
+/// Push quest updates recorded on this instance to online players
+pub fn start_forwarder(state: Arc<AppState>) {
+    let updates = state.services.quest.subscribe();
+    tokio::spawn(async move { forward(&state.broadcaster, updates).await });
+}
+
+async fn forward(broadcaster: &Broadcaster, mut updates: broadcast::Receiver<QuestUpdate>) {
+    loop {
+        match updates.recv().await {
+            Ok(update) => {
+                if broadcaster.is_player_online(update.player_id).await {
+                    let message = WsMessage::quest_update(&update);
+                    broadcaster.broadcast_to_player(update.player_id, message).await;
+                }
+            }
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!("Quest push fell behind, {} updates not pushed", skipped);
+            }
+            Err(RecvError::Closed) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::sync::mpsc;
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn update(player_id: Uuid, progress: i32) -> QuestUpdate {
+        QuestUpdate {
+            player_id,
+            quest_id: Uuid::new_v4(),
+            title: "Titan Hunter".into(),
+            progress,
+            target_count: 3,
+            completed: progress >= 3,
+            xp_reward: 100,
+            breach_reward: 10,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pushes_progress_then_completion() {
+        let broadcaster = Broadcaster::new();
+        let player = Uuid::new_v4();
+        let (tx, mut rx) = mpsc::channel(8);
+        broadcaster.register_client("player", Some(player), None, tx).await;
+
+        let (live, updates) = broadcast::channel(8);
+        let forwarder = tokio::spawn(async move { forward(&broadcaster, updates).await });
+
+        live.send(update(Uuid::new_v4(), 1)).unwrap();
+        live.send(update(player, 2)).unwrap();
+        live.send(update(player, 3)).unwrap();
+
+        match tokio::time::timeout(Duration::from_secs(1), rx.recv()).await {
+            Ok(Some(WsMessage::QuestProgress { progress, target_count, .. })) => {
+                assert_eq!((progress, target_count), (2, 3));
+            }
+            other => panic!("expected quest_progress, got {:?}", other),
+        }
+        match tokio::time::timeout(Duration::from_secs(1), rx.recv()).await {
+            Ok(Some(WsMessage::QuestCompleted { xp_earned, breach_earned, .. })) => {
+                assert_eq!((xp_earned, breach_earned), (100, 10));
+            }
+            other => panic!("expected quest_completed, got {:?}", other),
+        }
+
+        drop(live);
+        forwarder.await.unwrap();
+        assert!(rx.try_recv().is_err());
+    }
+}
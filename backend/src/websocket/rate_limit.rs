@@ -0,0 +1,141 @@
+//! Per-connection rate limiting for client messages
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::config::GameConfig;
+
+/// Length of the sliding window
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// Outcome of checking one incoming message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateDecision {
+    /// Under the limit; handle the message
+    Allow,
+    /// Over the limit; drop the message. `notify` is set on the first dropped
+    /// message of a burst, so a flood gets one error rather than one per message.
+    Drop { notify: bool },
+    /// Too many violations; close the connection
+    Disconnect,
+}
+
+/// Sliding-window limiter: at most `limit` messages in any one-second window
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: usize,
+    max_violations: u32,
+    accepted: VecDeque<Instant>,
+    violations: u32,
+    limited: bool,
+}
+
+impl RateLimiter {
+    pub fn new(config: &GameConfig) -> Self {
+        Self {
+            limit: config.ws_messages_per_second as usize,
+            max_violations: config.ws_max_rate_violations,
+            accepted: VecDeque::with_capacity(config.ws_messages_per_second as usize),
+            violations: 0,
+            limited: false,
+        }
+    }
+
+    /// Record a message received at `now`
+    ///
+    /// Each burst over the limit counts as one violation; once a connection
+    /// has more than `max_violations` (0 = never) it should be closed.
+    pub fn check(&mut self, now: Instant) -> RateDecision {
+        while let Some(&oldest) = self.accepted.front() {
+            if now.duration_since(oldest) < WINDOW {
+                break;
+            }
+            self.accepted.pop_front();
+        }
+
+        if self.accepted.len() < self.limit {
+            self.accepted.push_back(now);
+            self.limited = false;
+            return RateDecision::Allow;
+        }
+
+        if self.limited {
+            return RateDecision::Drop { notify: false };
+        }
+
+        self.limited = true;
+        self.violations += 1;
+        if self.max_violations > 0 && self.violations > self.max_violations {
+            RateDecision::Disconnect
+        } else {
+            RateDecision::Drop { notify: true }
+        }
+    }
+
+    pub fn violations(&self) -> u32 {
+        self.violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(per_second: u32, max_violations: u32) -> RateLimiter {
+        let config = GameConfig {
+            ws_messages_per_second: per_second,
+            ws_max_rate_violations: max_violations,
+            ..crate::config::AppConfig::default().game
+        };
+        RateLimiter::new(&config)
+    }
+
+    #[test]
+    fn test_allows_up_to_limit_then_drops() {
+        let mut limiter = limiter(3, 0);
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert_eq!(limiter.check(now), RateDecision::Allow);
+        }
+        assert_eq!(limiter.check(now), RateDecision::Drop { notify: true });
+        // Only the first dropped message of a burst is reported
+        assert_eq!(limiter.check(now), RateDecision::Drop { notify: false });
+        assert_eq!(limiter.violations(), 1);
+    }
+
+    #[test]
+    fn test_window_slides() {
+        let mut limiter = limiter(2, 0);
+        let start = Instant::now();
+
+        assert_eq!(limiter.check(start), RateDecision::Allow);
+        assert_eq!(limiter.check(start + Duration::from_millis(500)), RateDecision::Allow);
+        assert!(matches!(
+            limiter.check(start + Duration::from_millis(900)),
+            RateDecision::Drop { .. }
+        ));
+        // The first message left the window; the one at 500ms is still in it
+        assert_eq!(limiter.check(start + Duration::from_millis(1000)), RateDecision::Allow);
+        assert!(matches!(
+            limiter.check(start + Duration::from_millis(1100)),
+            RateDecision::Drop { .. }
+        ));
+    }
+
+    #[test]
+    fn test_disconnects_after_repeated_violations() {
+        let mut limiter = limiter(1, 2);
+        let start = Instant::now();
+
+        for second in 0..2 {
+            let now = start + Duration::from_secs(second);
+            assert_eq!(limiter.check(now), RateDecision::Allow);
+            assert_eq!(limiter.check(now), RateDecision::Drop { notify: true });
+        }
+
+        let now = start + Duration::from_secs(2);
+        assert_eq!(limiter.check(now), RateDecision::Allow);
+        assert_eq!(limiter.check(now), RateDecision::Disconnect);
+    }
+}
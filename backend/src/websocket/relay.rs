@@ -0,0 +1,254 @@
+//! Redis pub/sub relay between backend instances
+//!
+//! Every geohash, player, match, marketplace and global broadcast is also
+//! published to Redis (`breach:geohash:<prefix>` / `breach:player:<id>` /
+//! `breach:match:<id>` / `breach:marketplace` / `breach:global`); each
+//! instance listens on those channels and hands messages from other instances
+//! to its own sockets.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use super::{Broadcaster, WsMessage};
+use crate::AppState;
+
+const GEOHASH_PREFIX: &str = "breach:geohash:";
+const PLAYER_PREFIX: &str = "breach:player:";
+const MATCH_PREFIX: &str = "breach:match:";
+const MARKETPLACE_CHANNEL: &str = "breach:marketplace";
+const GLOBAL_CHANNEL: &str = "breach:global";
+
+/// Publishes waiting for the publisher task; overflow is dropped
+const PUBLISH_QUEUE_CAPACITY: usize = 1024;
+
+/// Wait before resubscribing after the pub/sub connection drops
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Where a relayed message goes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelayTarget {
+    /// Subscribers of a 5-character geohash region
+    Geohash(String),
+    /// A single player's connection
+    Player(Uuid),
+    /// Spectators of a PvP match
+    Match(Uuid),
+    /// Connections following the marketplace activity feed
+    Marketplace,
+    /// Every connection
+    Global,
+}
+
+impl RelayTarget {
+    pub fn channel(&self) -> String {
+        match self {
+            Self::Geohash(prefix) => format!("{}{}", GEOHASH_PREFIX, prefix),
+            Self::Player(player_id) => format!("{}{}", PLAYER_PREFIX, player_id),
+            Self::Match(match_id) => format!("{}{}", MATCH_PREFIX, match_id),
+            Self::Marketplace => MARKETPLACE_CHANNEL.to_string(),
+            Self::Global => GLOBAL_CHANNEL.to_string(),
+        }
+    }
+
+    pub fn from_channel(channel: &str) -> Option<Self> {
+        if channel == GLOBAL_CHANNEL {
+            return Some(Self::Global);
+        }
+        if channel == MARKETPLACE_CHANNEL {
+            return Some(Self::Marketplace);
+        }
+        if let Some(prefix) = channel.strip_prefix(GEOHASH_PREFIX) {
+            return Some(Self::Geohash(prefix.to_string()));
+        }
+        if let Some(id) = channel.strip_prefix(MATCH_PREFIX) {
+            return Uuid::parse_str(id).ok().map(Self::Match);
+        }
+        channel
+            .strip_prefix(PLAYER_PREFIX)
+            .and_then(|id| Uuid::parse_str(id).ok())
+            .map(Self::Player)
+    }
+}
+
+/// Message as published, tagged with the instance that sent it
+#[derive(Debug, Serialize, Deserialize)]
+struct RelayEnvelope {
+    origin: Uuid,
+    message: WsMessage,
+}
+
+/// Publishing half of the relay, owned by the `Broadcaster`
+pub struct RedisRelay {
+    instance_id: Uuid,
+    client: redis::Client,
+    publish_tx: mpsc::Sender<(String, String)>,
+}
+
+impl RedisRelay {
+    /// Start the publisher task. Must be called inside a Tokio runtime.
+    pub fn new(redis_url: &str, connection: ConnectionManager) -> redis::RedisResult<Self> {
+        let client = redis::Client::open(redis_url)?;
+        let (publish_tx, publish_rx) = mpsc::channel(PUBLISH_QUEUE_CAPACITY);
+        tokio::spawn(run_publisher(connection, publish_rx));
+
+        Ok(Self {
+            instance_id: Uuid::new_v4(),
+            client,
+            publish_tx,
+        })
+    }
+
+    /// Queue a message for other instances without waiting on Redis
+    pub fn publish(&self, target: &RelayTarget, message: &WsMessage) {
+        let envelope = RelayEnvelope {
+            origin: self.instance_id,
+            message: message.clone(),
+        };
+        let payload = match serde_json::to_string(&envelope) {
+            Ok(payload) => payload,
+            Err(e) => {
+                tracing::warn!("Failed to encode relay message: {}", e);
+                return;
+            }
+        };
+
+        if self.publish_tx.try_send((target.channel(), payload)).is_err() {
+            tracing::warn!("Redis relay queue full, dropping message for {:?}", target);
+        }
+    }
+
+    /// Decode a message from Redis; `None` for foreign channels, bad payloads
+    /// and this instance's own publishes (already delivered locally)
+    fn decode(&self, channel: &str, payload: &str) -> Option<(RelayTarget, WsMessage)> {
+        let target = RelayTarget::from_channel(channel)?;
+        match serde_json::from_str::<RelayEnvelope>(payload) {
+            Ok(envelope) if envelope.origin == self.instance_id => None,
+            Ok(envelope) => Some((target, envelope.message)),
+            Err(e) => {
+                tracing::warn!("Invalid relay message on {}: {}", channel, e);
+                None
+            }
+        }
+    }
+}
+
+async fn run_publisher(mut connection: ConnectionManager, mut rx: mpsc::Receiver<(String, String)>) {
+    while let Some((channel, payload)) = rx.recv().await {
+        if let Err(e) = connection.publish::<_, _, ()>(&channel, payload).await {
+            tracing::warn!("Failed to publish to {}: {}", channel, e);
+        }
+    }
+}
+
+/// Forward other instances' broadcasts to local connections; no-op when the
+/// broadcaster has no relay
+pub fn start_listener(state: Arc<AppState>) {
+    if state.broadcaster.relay.is_none() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        loop {
+            match listen(&state.broadcaster).await {
+                Ok(()) => tracing::warn!("Redis relay subscription closed, reconnecting"),
+                Err(e) => tracing::warn!("Redis relay subscription failed: {}", e),
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    });
+}
+
+async fn listen(broadcaster: &Broadcaster) -> redis::RedisResult<()> {
+    let Some(relay) = broadcaster.relay.as_ref() else {
+        return Ok(());
+    };
+
+    let mut pubsub = relay.client.get_async_connection().await?.into_pubsub();
+    pubsub.psubscribe(format!("{}*", GEOHASH_PREFIX)).await?;
+    pubsub.psubscribe(format!("{}*", PLAYER_PREFIX)).await?;
+    pubsub.psubscribe(format!("{}*", MATCH_PREFIX)).await?;
+    pubsub.subscribe(MARKETPLACE_CHANNEL).await?;
+    pubsub.subscribe(GLOBAL_CHANNEL).await?;
+    tracing::info!("Redis relay subscribed (instance {})", relay.instance_id);
+
+    let mut messages = pubsub.on_message();
+    while let Some(msg) = messages.next().await {
+        let payload: String = match msg.get_payload() {
+            Ok(payload) => payload,
+            Err(e) => {
+                tracing::warn!("Unreadable relay payload: {}", e);
+                continue;
+            }
+        };
+        if let Some((target, message)) = relay.decode(msg.get_channel_name(), &payload) {
+            broadcaster.deliver_local(&target, message).await;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+impl RedisRelay {
+    /// Relay whose publishes land in the returned receiver instead of Redis
+    pub(super) fn capture() -> (Self, mpsc::Receiver<(String, String)>) {
+        let (publish_tx, publish_rx) = mpsc::channel(16);
+        let relay = Self {
+            instance_id: Uuid::new_v4(),
+            client: redis::Client::open("redis://127.0.0.1/").unwrap(),
+            publish_tx,
+        };
+        (relay, publish_rx)
+    }
+
+    /// Feed a captured publish in as if it came from Redis
+    pub(super) async fn receive(&self, broadcaster: &Broadcaster, channel: &str, payload: &str) {
+        if let Some((target, message)) = self.decode(channel, payload) {
+            broadcaster.deliver_local(&target, message).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_round_trip() {
+        let player_id = Uuid::new_v4();
+        for target in [
+            RelayTarget::Geohash("xn77h".into()),
+            RelayTarget::Player(player_id),
+            RelayTarget::Match(player_id),
+            RelayTarget::Marketplace,
+            RelayTarget::Global,
+        ] {
+            assert_eq!(RelayTarget::from_channel(&target.channel()), Some(target));
+        }
+        assert_eq!(RelayTarget::Geohash("xn77h".into()).channel(), "breach:geohash:xn77h");
+        assert_eq!(RelayTarget::from_channel("breach:player:not-a-uuid"), None);
+        assert_eq!(RelayTarget::from_channel("other:xn77h"), None);
+    }
+
+    #[tokio::test]
+    async fn test_own_publishes_ignored() {
+        let (relay, mut published) = RedisRelay::capture();
+        relay.publish(&RelayTarget::Geohash("xn77h".into()), &WsMessage::Ping);
+
+        let (channel, payload) = published.recv().await.unwrap();
+        assert!(relay.decode(&channel, &payload).is_none());
+
+        let (other, _) = RedisRelay::capture();
+        assert!(matches!(
+            other.decode(&channel, &payload),
+            Some((RelayTarget::Geohash(prefix), WsMessage::Ping)) if prefix == "xn77h"
+        ));
+    }
+}
@@ -0,0 +1,116 @@
+//! Resume tokens for reconnecting WebSocket clients
+//!
+//! Each connection gets a token in its `welcome`. When the socket closes, its
+//! subscriptions and the broadcaster's event sequence are stored in Redis under
+//! that token for `RESUME_TTL_SECONDS`; a client reconnecting with
+//! `?resume=<token>` gets the subscriptions back plus the geohash events it
+//! missed, and no Titan snapshot for a region it already had. A token works
+//! once.
+
+use redis::aio::ConnectionManager;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// How long a closed connection can be resumed
+pub const RESUME_TTL_SECONDS: u64 = 60;
+
+const KEY_PREFIX: &str = "breach:ws_resume:";
+
+/// What a closed connection leaves behind for its resume token
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResumeSession {
+    /// Authenticated player, if any; a resume must come from the same player
+    pub player_id: Option<Uuid>,
+    /// Subscribed 5-character geohash prefixes
+    pub geohashes: Vec<String>,
+    /// Last geohash event sequence number when the socket closed
+    pub last_seq: u64,
+}
+
+impl ResumeSession {
+    /// Whether a new connection by `player_id` may take over this session
+    pub fn belongs_to(&self, player_id: Option<Uuid>) -> bool {
+        self.player_id == player_id
+    }
+
+    /// The session's regions that made it into the new subscription set
+    pub fn restored(&self, subscribed: &[String]) -> Vec<String> {
+        self.geohashes
+            .iter()
+            .filter(|geohash| subscribed.contains(geohash))
+            .cloned()
+            .collect()
+    }
+}
+
+fn key(token: Uuid) -> String {
+    format!("{}{}", KEY_PREFIX, token)
+}
+
+/// Store a closed connection's session under its token
+pub async fn save(redis: &mut ConnectionManager, token: Uuid, session: &ResumeSession) -> redis::RedisResult<()> {
+    let payload = serde_json::to_string(session).map_err(|e| {
+        redis::RedisError::from((redis::ErrorKind::TypeError, "encode resume session", e.to_string()))
+    })?;
+    redis::cmd("SET")
+        .arg(key(token))
+        .arg(payload)
+        .arg("EX")
+        .arg(RESUME_TTL_SECONDS)
+        .query_async(redis)
+        .await
+}
+
+/// Fetch and invalidate a token's session; `None` if unknown or expired
+pub async fn take(redis: &mut ConnectionManager, token: Uuid) -> redis::RedisResult<Option<ResumeSession>> {
+    let key = key(token);
+    let (payload,): (Option<String>,) = redis::pipe()
+        .atomic()
+        .get(&key)
+        .del(&key)
+        .ignore()
+        .query_async(redis)
+        .await?;
+
+    Ok(payload.and_then(|payload| match serde_json::from_str(&payload) {
+        Ok(session) => Some(session),
+        Err(e) => {
+            tracing::warn!("Invalid resume session {}: {}", token, e);
+            None
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_owner() {
+        let alice = Uuid::new_v4();
+        let session = ResumeSession {
+            player_id: Some(alice),
+            geohashes: vec!["xn77h".into()],
+            last_seq: 12,
+        };
+        assert!(session.belongs_to(Some(alice)));
+        assert!(!session.belongs_to(Some(Uuid::new_v4())));
+        assert!(!session.belongs_to(None));
+
+        let json = serde_json::to_string(&session).unwrap();
+        assert_eq!(serde_json::from_str::<ResumeSession>(&json).unwrap(), session);
+    }
+
+    #[test]
+    fn test_restored_regions() {
+        let session = ResumeSession {
+            player_id: None,
+            geohashes: vec!["xn77h".into(), "xn76u".into(), "xn7k0".into()],
+            last_seq: 3,
+        };
+        // One region was dropped by the subscription limit, one is new
+        let subscribed = vec!["xn77h".to_string(), "xn76u".to_string(), "u4pru".to_string()];
+        assert_eq!(session.restored(&subscribed), vec!["xn77h", "xn76u"]);
+        assert!(session.restored(&[]).is_empty());
+    }
+}
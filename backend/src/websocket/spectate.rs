@@ -0,0 +1,84 @@
+//! PvP match spectating
+//!
+//! A signed-in connection can watch any match that isn't over. It gets a
+//! `MatchUpdate` snapshot right away and another after every action,
+//! surrender or turn timeout, the last one when the match ends. Spectating is
+//! read-only; actions still come from the two players' REST calls. A player
+//! subscribing to their own match clears their reconnect grace timer.
+
+use uuid::Uuid;
+
+use super::WsMessage;
+use crate::error::AppError;
+use crate::AppState;
+
+/// Start spectating for a connection: `Spectating` and the current state, or
+/// the `Error` to send back
+pub async fn start(state: &AppState, connection_id: &str, match_id: &str) -> Vec<WsMessage> {
+    let error = |code: &str, message: &str| {
+        vec![WsMessage::Error {
+            code: code.into(),
+            message: message.into(),
+        }]
+    };
+
+    let player_id = state
+        .broadcaster
+        .get_client(connection_id)
+        .await
+        .and_then(|client| client.player_id);
+    let Some(player_id) = player_id else {
+        return error("UNAUTHORIZED", "Sign in to spectate matches");
+    };
+
+    let Ok(match_id) = Uuid::parse_str(match_id) else {
+        return error("MATCH_NOT_FOUND", "Match not found");
+    };
+    let match_state = match state.services.pvp.get_spectator_state(match_id).await {
+        Ok(match_state) => match_state,
+        Err(AppError::NotFound(_)) => return error("MATCH_NOT_FOUND", "Match not found"),
+        Err(e) => {
+            tracing::warn!("Failed to load match {} for spectating: {:?}", match_id, e);
+            return error("INTERNAL_ERROR", "Failed to load match");
+        }
+    };
+    if match_state.is_over() {
+        return error("MATCH_ENDED", "Match is already over");
+    }
+
+    // A player re-subscribing to their own match is back from a disconnect
+    if let Err(e) = state.services.pvp.mark_reconnected(player_id, match_id).await {
+        tracing::warn!("Failed to mark {} reconnected to match {}: {:?}", player_id, match_id, e);
+    }
+
+    match state.broadcaster.spectate_match(connection_id, match_id).await {
+        Ok(spectators) => vec![
+            WsMessage::Spectating {
+                match_id: match_id.to_string(),
+                spectators,
+            },
+            WsMessage::MatchUpdate {
+                match_id: match_id.to_string(),
+                state: Box::new(match_state),
+            },
+        ],
+        Err(e) => error(
+            "SPECTATOR_LIMIT",
+            &format!("Match already has {} spectators", e.limit),
+        ),
+    }
+}
+
+/// Push a match's current state to its spectators
+pub async fn publish_match_update(state: &AppState, match_id: Uuid) {
+    match state.services.pvp.get_spectator_state(match_id).await {
+        Ok(match_state) => {
+            let message = WsMessage::MatchUpdate {
+                match_id: match_id.to_string(),
+                state: Box::new(match_state),
+            };
+            state.broadcaster.broadcast_match_update(match_id, message).await;
+        }
+        Err(e) => tracing::warn!("Failed to load match {} for spectators: {:?}", match_id, e),
+    }
+}
@@ -62,7 +62,7 @@ async fn test_auth_challenge() {
     let wallet = common::random_wallet_address();
     
     let response = client
-        .post(&format!("{}/auth/challenge", BASE_URL))
+        .post(format!("{}/auth/challenge", BASE_URL))
         .json(&json!({ "wallet_address": wallet }))
         .send()
         .await
@@ -83,7 +83,7 @@ async fn test_auth_invalid_signature() {
     let wallet = common::random_wallet_address();
     
     let response = client
-        .post(&format!("{}/auth/authenticate", BASE_URL))
+        .post(format!("{}/auth/authenticate", BASE_URL))
         .json(&json!({
             "wallet_address": wallet,
             "signature": "invalid_signature",
@@ -106,7 +106,7 @@ async fn test_protected_endpoint_without_token() {
     let client = create_client();
     
     let response = client
-        .get(&format!("{}/player/me", BASE_URL))
+        .get(format!("{}/player/me", BASE_URL))
         .send()
         .await
         .expect("Failed to send request");
@@ -120,7 +120,7 @@ async fn test_protected_endpoint_with_invalid_token() {
     let client = create_client();
     
     let response = client
-        .get(&format!("{}/player/me", BASE_URL))
+        .get(format!("{}/player/me", BASE_URL))
         .header("Authorization", "Bearer invalid.token.here")
         .send()
         .await
@@ -142,7 +142,7 @@ async fn test_get_nearby_titans() {
     let token = common::test_jwt_token(player_id, &wallet);
     
     let response = client
-        .get(&format!("{}/map/titans", BASE_URL))
+        .get(format!("{}/map/titans", BASE_URL))
         .query(&[("lat", "35.6762"), ("lng", "139.6503"), ("radius", "1000")])
         .header("Authorization", format!("Bearer {}", token))
         .send()
@@ -167,7 +167,7 @@ async fn test_leaderboard_public() {
     let token = common::test_jwt_token(player_id, &wallet);
     
     let response = client
-        .get(&format!("{}/leaderboard", BASE_URL))
+        .get(format!("{}/leaderboard", BASE_URL))
         .query(&[("type", "experience"), ("limit", "10")])
         .header("Authorization", format!("Bearer {}", token))
         .send()
@@ -192,7 +192,7 @@ async fn test_get_quests() {
     let token = common::test_jwt_token(player_id, &wallet);
     
     let response = client
-        .get(&format!("{}/quests", BASE_URL))
+        .get(format!("{}/quests", BASE_URL))
         .header("Authorization", format!("Bearer {}", token))
         .send()
         .await
@@ -216,7 +216,7 @@ async fn test_get_achievements() {
     let token = common::test_jwt_token(player_id, &wallet);
     
     let response = client
-        .get(&format!("{}/achievements", BASE_URL))
+        .get(format!("{}/achievements", BASE_URL))
         .header("Authorization", format!("Bearer {}", token))
         .send()
         .await
@@ -239,7 +239,7 @@ async fn test_marketplace_search() {
     let token = common::test_jwt_token(player_id, &wallet);
     
     let response = client
-        .get(&format!("{}/marketplace", BASE_URL))
+        .get(format!("{}/marketplace", BASE_URL))
         .query(&[("limit", "10"), ("offset", "0")])
         .header("Authorization", format!("Bearer {}", token))
         .send()
@@ -263,7 +263,7 @@ async fn test_chat_channels() {
     let token = common::test_jwt_token(player_id, &wallet);
     
     let response = client
-        .get(&format!("{}/chat/channels", BASE_URL))
+        .get(format!("{}/chat/channels", BASE_URL))
         .header("Authorization", format!("Bearer {}", token))
         .send()
         .await
@@ -286,7 +286,7 @@ async fn test_pvp_season() {
     let token = common::test_jwt_token(player_id, &wallet);
     
     let response = client
-        .get(&format!("{}/pvp/season", BASE_URL))
+        .get(format!("{}/pvp/season", BASE_URL))
         .header("Authorization", format!("Bearer {}", token))
         .send()
         .await
@@ -305,7 +305,7 @@ async fn test_pvp_leaderboard() {
     let token = common::test_jwt_token(player_id, &wallet);
     
     let response = client
-        .get(&format!("{}/pvp/leaderboard", BASE_URL))
+        .get(format!("{}/pvp/leaderboard", BASE_URL))
         .query(&[("limit", "10")])
         .header("Authorization", format!("Bearer {}", token))
         .send()
@@ -1,5 +1,7 @@
 //! Common test utilities and fixtures
 
+#![allow(dead_code)]
+
 use breach_backend::config::AppConfig;
 
 /// Create a test configuration
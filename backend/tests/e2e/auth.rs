@@ -0,0 +1,31 @@
+//! Wallet sign-in and profile
+
+use serde_json::json;
+
+use crate::golden::assert_golden;
+use crate::harness::TestApp;
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_register_and_login() {
+    let mut app = TestApp::spawn().await;
+
+    let (alice, responses) = app.sign_in("alice").await;
+    assert_golden(
+        "auth_sign_in",
+        &app.aliases,
+        // The challenge message embeds the nonce and expiry
+        &["expires_at", "message"],
+        &json!({ "challenge": responses[0], "authenticate": responses[1] }),
+    );
+
+    let me = app.get_ok("/api/v1/player/me", &alice).await;
+    assert_eq!(me["wallet_address"], alice.wallet);
+    assert_golden("auth_player_me", &app.aliases, &[], &me);
+
+    // Signing in again with the same wallet returns the same player
+    let (_, auth) = app.authenticate(&alice.key).await;
+    assert_eq!(auth["player_id"], alice.id.to_string());
+
+    app.cleanup().await;
+}
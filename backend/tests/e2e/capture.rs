@@ -0,0 +1,770 @@
+//! Map query and the capture request/confirm flow
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use axum::http::{header, Method, StatusCode};
+use breach_backend::error::AppError;
+use breach_backend::services::CaptureService;
+use breach_backend::websocket::WsMessage;
+use serde_json::{json, Value};
+use solana_sdk::signature::Keypair;
+
+use crate::golden::assert_golden;
+use crate::harness::{Player, SpawnSeed, TestApp};
+
+const LAT: f64 = 35.681_236;
+const LNG: f64 = 139.767_125;
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_map_then_capture() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let spawn = app.seed_spawn("volcanic", LAT, LNG, SpawnSeed::default()).await;
+
+    let uri = format!("/api/v1/map/titans?lat={}&lng={}&radius=500", LAT, LNG);
+    let nearby = app.get_ok(&uri, &alice).await;
+    assert_golden("map_nearby", &app.aliases, &[], &nearby);
+
+    let location = json!({ "lat": LAT, "lng": LNG, "accuracy": 5.0 });
+    let authorization = app
+        .post_ok(
+            "/api/v1/capture/request",
+            &alice,
+            json!({ "titan_id": spawn, "player_location": location }),
+        )
+        .await;
+    assert_eq!(authorization["authorized"], true, "{}", authorization);
+    assert_golden("capture_request", &app.aliases, &[], &authorization);
+
+    let confirmed = app
+        .post_ok(
+            "/api/v1/capture/confirm",
+            &alice,
+            json!({ "titan_id": spawn, "challenge": authorization["challenge"] }),
+        )
+        .await;
+    assert_golden("capture_confirm", &app.aliases, &[], &confirmed);
+
+    // The spawn is gone from the map once its only capture is used
+    let nearby = app.get_ok(&uri, &alice).await;
+    assert_golden("map_nearby_after_capture", &app.aliases, &[], &nearby);
+
+    let stats = app.get_ok("/api/v1/player/me", &alice).await;
+    assert_eq!(stats["titans_captured"], 1, "{}", stats);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_confirm_retry_resumes_attempt() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let seed = SpawnSeed { max_captures: 2, ..SpawnSeed::default() };
+    let spawn = app.seed_spawn("volcanic", LAT, LNG, seed).await;
+
+    // A confirm that minted and then timed out before writing the capture
+    let mint = "MintedBeforeTimeout1111111111111111111111111";
+    let attempt: uuid::Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO capture_attempts (player_id, titan_spawn_id, state, mint_address, tx_signature)
+        VALUES ($1, $2, 'minted', $3, 'sig')
+        RETURNING id
+        "#,
+    )
+    .bind(alice.id)
+    .bind(spawn)
+    .bind(mint)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+
+    let location = json!({ "lat": LAT, "lng": LNG, "accuracy": 5.0 });
+    let authorization = app
+        .post_ok(
+            "/api/v1/capture/request",
+            &alice,
+            json!({ "titan_id": spawn, "player_location": location }),
+        )
+        .await;
+    let body = json!({ "titan_id": spawn, "challenge": authorization["challenge"] });
+
+    let confirmed = app.post_ok("/api/v1/capture/confirm", &alice, body.clone()).await;
+    assert_eq!(confirmed["attempt_id"], attempt.to_string(), "{}", confirmed);
+    assert_eq!(confirmed["mint_address"], mint, "{}", confirmed);
+    assert_eq!(confirmed["remaining_captures"], 1, "{}", confirmed);
+
+    // Retrying again, with the same challenge, returns the stored result
+    // without counting the capture twice
+    let retried = app.post_ok("/api/v1/capture/confirm", &alice, body).await;
+    assert_eq!(retried["attempt_id"], confirmed["attempt_id"]);
+    assert_eq!(retried["mint_address"], mint);
+    assert_eq!(retried["remaining_captures"], 1);
+
+    let stats = app.get_ok("/api/v1/player/me", &alice).await;
+    assert_eq!(stats["titans_captured"], 1, "{}", stats);
+
+    let capture_count: i32 =
+        sqlx::query_scalar("SELECT capture_count FROM titan_spawns WHERE id = $1")
+            .bind(spawn)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(capture_count, 1);
+
+    app.cleanup().await;
+}
+
+/// Value of `name` scraped from `/metrics`
+async fn scrape(app: &TestApp, name: &str) -> u64 {
+    let (status, body) = app.request(Method::GET, "/metrics", None, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let text = body.as_str().expect("metrics are plain text");
+    text.lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+        .unwrap_or_else(|| panic!("{} missing from:\n{}", name, text))
+        .parse()
+        .unwrap()
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_metrics_count_confirmed_captures() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let spawn = app.seed_spawn("titan", LAT, LNG, SpawnSeed::default()).await;
+    let before = scrape(&app, "breach_captures_confirmed_total").await;
+
+    let challenge = capture_challenge(&app, &alice, spawn).await;
+    let body = json!({ "titan_id": spawn, "challenge": challenge });
+    app.post_ok("/api/v1/capture/confirm", &alice, body).await;
+
+    assert_eq!(scrape(&app, "breach_captures_confirmed_total").await, before + 1);
+    // Every family is exported even before it has anything to count
+    for name in ["breach_nfts_minted_total", "breach_pvp_matches_completed_total", "breach_active_titans"] {
+        scrape(&app, name).await;
+    }
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_capture_rate_limit() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    app.state.game.write().await.capture_rate_limit_per_min = 2;
+
+    // Unknown Titan, so the handler fails, but the request still counts
+    let body = json!({ "titan_id": uuid::Uuid::new_v4() });
+    for _ in 0..2 {
+        let (status, _) = app.post("/api/v1/capture/confirm", &alice, body.clone()).await;
+        assert_ne!(status, StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    let (status, headers, error) = app
+        .request_with_headers(Method::POST, "/api/v1/capture/confirm", Some(&alice.token), Some(body.clone()))
+        .await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(error["error"]["code"], "TOO_MANY_REQUESTS");
+    // The oldest request leaves the one-minute window within 60s
+    let retry_after: u64 = headers[header::RETRY_AFTER].to_str().unwrap().parse().unwrap();
+    assert!((1..=60).contains(&retry_after), "{}", retry_after);
+
+    // Limits are per player
+    let (status, _) = app.post("/api/v1/capture/confirm", &bob, body).await;
+    assert_ne!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_capture_attempt_cooldown_is_per_titan() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let seed = SpawnSeed { max_captures: 3, ..SpawnSeed::default() };
+    let escaped = app.seed_spawn("escaped", LAT, LNG, seed).await;
+    let seed = SpawnSeed { max_captures: 3, ..SpawnSeed::default() };
+    let other = app.seed_spawn("other", LAT, LNG, seed).await;
+
+    // Alice just failed a roll on the first Titan
+    let mut conn = app.state.db.redis.clone();
+    let key = format!("capture_attempt:{}:{}", alice.id, escaped);
+    let _: () = redis::AsyncCommands::set_ex(&mut conn, &key, "1", 30u64).await.unwrap();
+
+    let location = json!({ "lat": LAT, "lng": LNG, "accuracy": 5.0 });
+    let (status, headers, _) = app
+        .request_with_headers(
+            Method::POST,
+            "/api/v1/capture/request",
+            Some(&alice.token),
+            Some(json!({ "titan_id": escaped, "player_location": location })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = headers[header::RETRY_AFTER].to_str().unwrap().parse().unwrap();
+    assert!((1..=30).contains(&retry_after), "{}", retry_after);
+
+    // Other Titans aren't affected, and neither is the global cooldown
+    app.post_ok(
+        "/api/v1/capture/request",
+        &alice,
+        json!({ "titan_id": other, "player_location": location }),
+    )
+    .await;
+
+    let _: () = redis::AsyncCommands::del(&mut conn, &key).await.unwrap();
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_capture_escape_counts_and_cools_down() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let seed = SpawnSeed { max_captures: 3, ..SpawnSeed::default() };
+    let spawn = app.seed_spawn("slippery", LAT, LNG, seed).await;
+
+    // The harness makes every roll succeed; this service never does
+    let mut config = app.state.config.clone();
+    config.capture_modifiers.base_chance = [0.0; 5];
+    let capture = CaptureService::new(
+        config,
+        app.state.game.clone(),
+        app.state.db.clone(),
+        app.state.services.location.clone(),
+        app.state.services.quest.clone(),
+        app.state.services.guild.clone(),
+        Arc::new(Keypair::new()),
+    );
+
+    let location = json!({ "lat": LAT, "lng": LNG, "accuracy": 5.0 });
+    let request = serde_json::from_value(json!({ "titan_id": spawn, "player_location": location })).unwrap();
+    let escaped = capture.request_capture(alice.id, &alice.wallet, request).await;
+    match escaped {
+        Err(AppError::CaptureEscaped { capture_chance, escaped_count }) => {
+            assert_eq!(capture_chance, 0.0);
+            assert_eq!(escaped_count, 1);
+        }
+        other => panic!("expected escape, got {:?}", other),
+    }
+
+    // The retry waits out the attempt cooldown
+    let (status, error) = app
+        .post(
+            "/api/v1/capture/request",
+            &alice,
+            json!({ "titan_id": spawn, "player_location": location }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(error["error"]["code"], "TOO_MANY_REQUESTS");
+
+    let escaped_count: i32 = sqlx::query_scalar("SELECT escaped_count FROM titan_spawns WHERE id = $1")
+        .bind(spawn)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(escaped_count, 1);
+
+    let mut conn = app.state.db.redis.clone();
+    let key = format!("capture_attempt:{}:{}", alice.id, spawn);
+    let _: () = redis::AsyncCommands::del(&mut conn, &key).await.unwrap();
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_capture_cooldown_retry_after() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let spawn = app.seed_spawn("titan", LAT, LNG, SpawnSeed::default()).await;
+    app.state.game.write().await.capture_cooldown_seconds = 60;
+
+    // Alice's last capture was 20s ago, leaving 40s of cooldown
+    sqlx::query("UPDATE players SET last_capture_at = NOW() - INTERVAL '20 seconds' WHERE id = $1")
+        .bind(alice.id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let location = json!({ "lat": LAT, "lng": LNG, "accuracy": 5.0 });
+    let (status, headers, error) = app
+        .request_with_headers(
+            Method::POST,
+            "/api/v1/capture/request",
+            Some(&alice.token),
+            Some(json!({ "titan_id": spawn, "player_location": location })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS, "{}", error);
+    assert_eq!(headers[header::RETRY_AFTER], "40");
+    assert_eq!(error["error"]["details"]["retry_after_secs"], 40);
+
+    app.cleanup().await;
+}
+
+/// Request a capture of `spawn` and return the signed challenge
+async fn capture_challenge(app: &TestApp, player: &Player, spawn: uuid::Uuid) -> Value {
+    let location = json!({ "lat": LAT, "lng": LNG, "accuracy": 5.0 });
+    let authorization = app
+        .post_ok(
+            "/api/v1/capture/request",
+            player,
+            json!({ "titan_id": spawn, "player_location": location }),
+        )
+        .await;
+    authorization["challenge"].clone()
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_confirm_rejects_tampered_challenge() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let spawn = app.seed_spawn("near", LAT, LNG, SpawnSeed::default()).await;
+    let far = app.seed_spawn("far", LAT + 1.0, LNG, SpawnSeed::default()).await;
+    let challenge = capture_challenge(&app, &alice, spawn).await;
+
+    // Re-targeted at a Titan the player never stood next to
+    let mut retargeted = challenge.clone();
+    retargeted["titan_id"] = json!(far);
+    // Re-dated to stretch the window
+    let mut redated = challenge.clone();
+    redated["issued_at"] = json!(chrono::Utc::now().to_rfc3339());
+    let mut forged = challenge.clone();
+    forged["signature"] = json!("1111111111111111111111111111111111111111111111111111111111111111");
+
+    for (titan_id, challenge) in [(far, retargeted), (spawn, redated), (spawn, forged)] {
+        let (status, error) = app
+            .post(
+                "/api/v1/capture/confirm",
+                &alice,
+                json!({ "titan_id": titan_id, "challenge": challenge }),
+            )
+            .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", error);
+        assert_eq!(error["error"]["code"], "INVALID_SIGNATURE");
+    }
+
+    // A challenge for another Titan doesn't carry over either
+    let (status, _) = app
+        .post("/api/v1/capture/confirm", &alice, json!({ "titan_id": far, "challenge": challenge }))
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_confirm_rejects_stale_challenge() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let spawn = app.seed_spawn("titan", LAT, LNG, SpawnSeed::default()).await;
+    let challenge = capture_challenge(&app, &alice, spawn).await;
+
+    app.state.game.write().await.capture_challenge_ttl_seconds = 0;
+    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+
+    let (status, error) = app
+        .post("/api/v1/capture/confirm", &alice, json!({ "titan_id": spawn, "challenge": challenge }))
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", error);
+    assert_eq!(error["error"]["code"], "INVALID_SIGNATURE");
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_confirm_rejects_replayed_challenge() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let seed = SpawnSeed { max_captures: 2, ..SpawnSeed::default() };
+    let spawn = app.seed_spawn("titan", LAT, LNG, seed).await;
+    let challenge = capture_challenge(&app, &alice, spawn).await;
+    let body = json!({ "titan_id": spawn, "challenge": challenge });
+
+    app.post_ok("/api/v1/capture/confirm", &alice, body.clone()).await;
+
+    // Without the attempt it started, the used challenge can't begin another capture
+    sqlx::query("DELETE FROM capture_attempts WHERE player_id = $1")
+        .bind(alice.id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let (status, error) = app.post("/api/v1/capture/confirm", &alice, body).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", error);
+    assert_eq!(error["error"]["code"], "INVALID_SIGNATURE");
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_confirm_idempotency_key_replays_response() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let seed = SpawnSeed { max_captures: 3, ..SpawnSeed::default() };
+    let spawn = app.seed_spawn("titan", LAT, LNG, seed).await;
+
+    let challenge = capture_challenge(&app, &alice, spawn).await;
+    let body = json!({ "titan_id": spawn, "challenge": challenge });
+    let key = ("Idempotency-Key", "retry-1");
+    let (status, first) = app
+        .post_with_header("/api/v1/capture/confirm", &alice, key, body.clone())
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", first);
+
+    let (status, repeat) = app
+        .post_with_header("/api/v1/capture/confirm", &alice, key, body)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", repeat);
+    assert_eq!(repeat, first);
+
+    // Keys are per player: Bob's confirm with the same key runs on its own
+    let challenge = capture_challenge(&app, &bob, spawn).await;
+    let (status, bobs) = app
+        .post_with_header(
+            "/api/v1/capture/confirm",
+            &bob,
+            key,
+            json!({ "titan_id": spawn, "challenge": challenge }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", bobs);
+    assert_ne!(bobs["attempt_id"], first["attempt_id"]);
+
+    let capture_count: i32 =
+        sqlx::query_scalar("SELECT capture_count FROM titan_spawns WHERE id = $1")
+            .bind(spawn)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(capture_count, 2);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_confirm_once_mints_once() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let capture = &app.state.services.capture;
+    let mints = AtomicUsize::new(0);
+    let mint = || async {
+        let n = mints.fetch_add(1, Ordering::SeqCst) + 1;
+        Ok(json!({ "mint": n }))
+    };
+
+    let first = capture.confirm_once(alice.id, "mint-1", mint).await.unwrap();
+    let repeat = capture.confirm_once(alice.id, "mint-1", mint).await.unwrap();
+    assert_eq!(first, json!({ "mint": 1 }));
+    assert_eq!(repeat, first);
+    assert_eq!(mints.load(Ordering::SeqCst), 1);
+
+    // A repeat while the first call is still running doesn't run either
+    let nested = capture
+        .confirm_once(alice.id, "mint-2", || async {
+            let repeat = capture.confirm_once(alice.id, "mint-2", mint).await;
+            assert!(matches!(repeat, Err(AppError::IdempotencyKeyInUse)), "{:?}", repeat);
+            Ok(json!({}))
+        })
+        .await;
+    assert!(nested.is_ok());
+    assert_eq!(mints.load(Ordering::SeqCst), 1);
+
+    // A failed confirm frees the key for the retry
+    let failed = capture
+        .confirm_once(alice.id, "mint-3", || async { Err::<Value, _>(AppError::TitanNotFound) })
+        .await;
+    assert!(failed.is_err());
+    capture.confirm_once(alice.id, "mint-3", mint).await.unwrap();
+    assert_eq!(mints.load(Ordering::SeqCst), 2);
+
+    app.cleanup().await;
+}
+
+/// Class V spawn, which takes `game.max_co_captors[4]` players
+fn apex_seed() -> SpawnSeed {
+    SpawnSeed { threat_class: 5, species_id: 1_401, ..SpawnSeed::default() }
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_confirm_batch_reports_each_capture() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let first = app.seed_spawn("first", LAT, LNG, SpawnSeed::default()).await;
+    let second = app.seed_spawn("second", LAT, LNG + 0.000_1, SpawnSeed::default()).await;
+    let taken = app.seed_spawn("taken", LAT, LNG - 0.000_1, SpawnSeed::default()).await;
+
+    let mut batch = Vec::new();
+    for spawn in [first, second, taken] {
+        let challenge = capture_challenge(&app, &alice, spawn).await;
+        batch.push(json!({ "titan_id": spawn, "challenge": challenge }));
+    }
+    // Captured by someone else between the request and the batch confirm
+    sqlx::query("UPDATE titan_spawns SET captured_by = $1, capture_count = max_captures WHERE id = $2")
+        .bind(alice.id)
+        .bind(taken)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let results = app.post_ok("/api/v1/capture/confirm-batch", &alice, json!(batch)).await;
+    let results = results.as_array().unwrap();
+    assert_eq!(results.len(), 3, "{:?}", results);
+
+    for (result, spawn) in results.iter().zip([first, second]) {
+        assert_eq!(result["titan_id"], spawn.to_string());
+        assert_eq!(result["success"], true, "{}", result);
+        assert_eq!(result["result"]["remaining_captures"], 0, "{}", result);
+    }
+    assert_eq!(results[2]["success"], false);
+    assert_eq!(results[2]["error"]["code"], "ALREADY_CAPTURED", "{}", results[2]);
+
+    let stats = app.get_ok("/api/v1/player/me", &alice).await;
+    assert_eq!(stats["titans_captured"], 2, "{}", stats);
+
+    // The same Titan twice is refused for the repeat only
+    let repeated = app
+        .post_ok("/api/v1/capture/confirm-batch", &alice, json!([batch[0], batch[0]]))
+        .await;
+    assert_eq!(repeated[0]["success"], true, "{}", repeated);
+    assert_eq!(repeated[0]["result"]["attempt_id"], results[0]["result"]["attempt_id"]);
+    assert_eq!(repeated[1]["error"]["code"], "BAD_REQUEST", "{}", repeated);
+    let (status, error) = app.post("/api/v1/capture/confirm-batch", &alice, json!([])).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", error);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_cooperative_capture() {
+    let mut app = TestApp::spawn().await;
+    let players = [
+        app.register("alice").await,
+        app.register("bob").await,
+        app.register("carol").await,
+        app.register("dave").await,
+    ];
+    let [alice, bob, carol, dave] = &players;
+    let spawn = app.seed_spawn("apex", LAT, LNG, apex_seed()).await;
+    let location = json!({ "lat": LAT, "lng": LNG, "accuracy": 5.0 });
+
+    // Nobody captures it alone
+    let authorization = app
+        .post_ok(
+            "/api/v1/capture/request",
+            alice,
+            json!({ "titan_id": spawn, "player_location": location }),
+        )
+        .await;
+    assert_eq!(authorization["authorized"], false, "{}", authorization);
+
+    // Someone nearby sees the invite
+    let region = geohash::encode(geohash::Coord { x: LNG, y: LAT }, 5).unwrap();
+    let (tx, mut nearby) = tokio::sync::mpsc::channel(16);
+    app.state.broadcaster.register_client("nearby-socket", None, None, tx).await;
+    app.state.broadcaster.subscribe("nearby-socket", vec![region]).await.unwrap();
+
+    let join = format!("/api/v1/capture/cooperative/join/{}", spawn);
+    let confirm = format!("/api/v1/capture/cooperative/confirm/{}", spawn);
+    let body = json!({ "player_location": location });
+
+    let session = app.post_ok(&join, alice, body.clone()).await;
+    assert_eq!(session["leader_id"], alice.id.to_string());
+    assert_eq!(session["required"], 3);
+    match nearby.recv().await {
+        Some(WsMessage::CooperativeCaptureInvite { titan_id, leader, joined, required, .. }) => {
+            assert_eq!(titan_id, spawn.to_string());
+            assert_eq!(leader, alice.wallet);
+            assert_eq!((joined, required), (1, 3));
+        }
+        other => panic!("expected invite, got {:?}", other),
+    }
+
+    // Confirming waits for the capture to fill up
+    let (status, _) = app.post(&confirm, alice, json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    app.post_ok(&join, bob, body.clone()).await;
+    // Joining again changes nothing
+    app.post_ok(&join, bob, body.clone()).await;
+    let session = app.post_ok(&join, carol, body.clone()).await;
+    assert_eq!(session["participants"].as_array().unwrap().len(), 3);
+    assert_eq!(session["participants"][0]["player_id"], alice.id.to_string());
+
+    let (status, error) = app.post(&join, dave, body.clone()).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(error["error"]["code"], "COOPERATIVE_CAPTURE_FULL");
+    let (status, _) = app.post(&confirm, dave, json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Everyone confirms; the last one settles
+    for player in [alice, bob] {
+        let waiting = app.post_ok(&confirm, player, json!({})).await;
+        assert_eq!(waiting["captured"], false, "{}", waiting);
+    }
+    let captured = app.post_ok(&confirm, carol, json!({ "skip_blockchain": true })).await;
+    assert_eq!(captured["captured"], true, "{}", captured);
+    assert_eq!(captured["remaining_captures"], 0);
+
+    // One capture, credited to the leader
+    let captured_by: Option<uuid::Uuid> = sqlx::query_scalar("SELECT captured_by FROM titan_spawns WHERE id = $1")
+        .bind(spawn)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(captured_by, Some(alice.id));
+
+    // The session is over
+    let (status, _) = app.post(&confirm, alice, json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    app.state.broadcaster.unregister_client("nearby-socket").await;
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_cooperative_capture_times_out() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let spawn = app.seed_spawn("apex", LAT, LNG, apex_seed()).await;
+    {
+        let mut game = app.state.game.write().await;
+        game.max_co_captors[4] = 2;
+        game.cooperative_capture_timeout_seconds = 1;
+    }
+
+    let join = format!("/api/v1/capture/cooperative/join/{}", spawn);
+    let body = json!({ "player_location": { "lat": LAT, "lng": LNG, "accuracy": 5.0 } });
+    app.post_ok(&join, &alice, body.clone()).await;
+    tokio::time::sleep(std::time::Duration::from_millis(2_100)).await;
+
+    // Bob opens a new capture rather than filling Alice's
+    let session = app.post_ok(&join, &bob, body).await;
+    assert_eq!(session["leader_id"], bob.id.to_string());
+    assert_eq!(session["participants"].as_array().unwrap().len(), 1);
+
+    let key = format!("coop_capture:{}", spawn);
+    let mut conn = app.state.db.redis.clone();
+    let _: () = redis::AsyncCommands::del(&mut conn, &key).await.unwrap();
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_shadow_banned_capture_quietly_fails() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let spawn = app.seed_spawn("bait", LAT, LNG, SpawnSeed::default()).await;
+
+    // Only admins may shadow-ban
+    let uri = format!("/api/v1/admin/players/{}/shadow-ban", alice.id);
+    let (status, _) = app.post(&uri, &bob, json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    app.state.services.player.set_shadow_ban(alice.id, true).await.unwrap();
+
+    // Alice is told she caught it
+    let challenge = capture_challenge(&app, &alice, spawn).await;
+    let confirmed = app
+        .post_ok("/api/v1/capture/confirm", &alice, json!({ "titan_id": spawn, "challenge": challenge }))
+        .await;
+    assert_eq!(confirmed["success"], true, "{}", confirmed);
+    assert_eq!(confirmed["remaining_captures"], 0);
+    assert_eq!(confirmed["breach_reward"], Value::Null);
+
+    // ...but the Titan is still there for Bob, and Alice has nothing to show for it
+    let (count,): (i32,) = sqlx::query_as("SELECT capture_count FROM titan_spawns WHERE id = $1")
+        .bind(spawn)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 0);
+    let stats = app.get_ok("/api/v1/player/me", &alice).await;
+    assert_eq!(stats["titans_captured"], 0, "{}", stats);
+    assert!(stats.get("shadow_banned").is_none(), "{}", stats);
+
+    let challenge = capture_challenge(&app, &bob, spawn).await;
+    let caught = app
+        .post_ok("/api/v1/capture/confirm", &bob, json!({ "titan_id": spawn, "challenge": challenge }))
+        .await;
+    assert_eq!(caught["success"], true, "{}", caught);
+    let stats = app.get_ok("/api/v1/player/me", &bob).await;
+    assert_eq!(stats["titans_captured"], 1, "{}", stats);
+
+    // Lifted: captures count again
+    app.state.services.player.set_shadow_ban(alice.id, false).await.unwrap();
+    let other = app.seed_spawn("other", LAT, LNG + 0.000_1, SpawnSeed::default()).await;
+    let challenge = capture_challenge(&app, &alice, other).await;
+    app.post_ok("/api/v1/capture/confirm", &alice, json!({ "titan_id": other, "challenge": challenge }))
+        .await;
+    let stats = app.get_ok("/api/v1/player/me", &alice).await;
+    assert_eq!(stats["titans_captured"], 1, "{}", stats);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_capture_radius_changes_live() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    // About 150m north of where Alice stands
+    let spawn = app.seed_spawn("distant", LAT + 0.001_35, LNG, SpawnSeed::default()).await;
+
+    let location = json!({ "lat": LAT, "lng": LNG, "accuracy": 5.0 });
+    let request = json!({ "titan_id": spawn, "player_location": location });
+    let authorization = app.post_ok("/api/v1/capture/request", &alice, request.clone()).await;
+    assert_eq!(authorization["authorized"], false, "{}", authorization);
+    assert_eq!(authorization["error"], "Too far from Titan");
+
+    // The harness has no admin wallet, so go through the service
+    let (status, _) = app.get("/api/v1/admin/config", &alice).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let patch = json!({ "capture_radius_meters": 300.0 });
+    let update = app
+        .state
+        .services
+        .game_config
+        .update(patch.as_object().unwrap(), "admin-wallet")
+        .await
+        .unwrap();
+    assert_eq!(update.config.capture_radius_meters, 300.0);
+    assert_eq!(update.changes["capture_radius_meters"]["to"], 300.0);
+
+    let authorization = app.post_ok("/api/v1/capture/request", &alice, request).await;
+    assert_eq!(authorization["authorized"], true, "{}", authorization);
+
+    // Out-of-range values and unknown settings change nothing
+    for patch in [json!({ "capture_radius_meters": 1000.0 }), json!({ "capture_radius": 80.0 })] {
+        let result = app.state.services.game_config.update(patch.as_object().unwrap(), "admin-wallet").await;
+        assert!(matches!(result, Err(AppError::Validation(_))), "{:?}", result);
+    }
+    assert_eq!(app.state.game.read().await.capture_radius_meters, 300.0);
+
+    let (source, changed_by, changes): (String, Option<String>, Value) =
+        sqlx::query_as("SELECT source, changed_by, changes FROM game_config_audit_log")
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(source, "patch");
+    assert_eq!(changed_by.as_deref(), Some("admin-wallet"));
+    assert_eq!(changes["capture_radius_meters"]["from"], 50.0);
+
+    app.cleanup().await;
+}
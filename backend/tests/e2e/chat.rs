@@ -0,0 +1,332 @@
+//! Sending and reading chat, channel list and history paging
+
+use axum::http::{header, Method, StatusCode};
+use breach_backend::models::{ModerationAction, ReportsQuery, ResolveReportRequest};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::golden::assert_golden;
+use crate::harness::{Player, TestApp};
+
+/// World channel seeded by the chat migration
+const WORLD_CHANNEL: &str = "00000000-0000-0000-0000-000000000001";
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_send_then_fetch() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    app.aliases.add(WORLD_CHANNEL.parse().unwrap(), "channel:world".into());
+
+    let uri = format!("/api/v1/chat/channels/{}/messages", WORLD_CHANNEL);
+    let sent = app
+        .post_ok(&uri, &alice, json!({ "content": "Void V spotted near the station" }))
+        .await;
+    let message_id = sent["id"].as_str().unwrap().parse().unwrap();
+    app.aliases.add(message_id, "message:alice".into());
+    assert_golden("chat_send", &app.aliases, &[], &sent);
+
+    let reply = app
+        .post_ok(&uri, &bob, json!({ "content": "On my way", "reply_to_id": message_id }))
+        .await;
+    app.aliases
+        .add(reply["id"].as_str().unwrap().parse().unwrap(), "message:bob".into());
+
+    let messages = app.get_ok(&uri, &bob).await;
+    assert_golden("chat_fetch", &app.aliases, &[], &messages);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_channel_and_message_pages() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+
+    // 50 private chats, each with one unread message, on top of world / trade / help
+    for i in 0..50 {
+        let other: Uuid = sqlx::query_scalar(
+            "INSERT INTO players (wallet_address, username) VALUES ($1, $2) RETURNING id",
+        )
+        .bind(format!("ChatPartnerWallet{:027}", i))
+        .bind(format!("partner{}", i))
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+
+        let channel: Uuid = sqlx::query_scalar("SELECT get_or_create_private_channel($1, $2)")
+            .bind(alice.id)
+            .bind(other)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+
+        sqlx::query("INSERT INTO chat_messages (channel_id, sender_id, content) VALUES ($1, $2, 'hi')")
+            .bind(channel)
+            .bind(other)
+            .execute(&app.pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE chat_channels SET last_message_at = NOW() - make_interval(mins => $2) WHERE id = $1")
+            .bind(channel)
+            .bind(i)
+            .execute(&app.pool)
+            .await
+            .unwrap();
+    }
+
+    let mut channels = Vec::new();
+    let mut offsets = Vec::new();
+    let mut uri = "/api/v1/chat/channels?limit=20".to_string();
+    loop {
+        let page = app.get_ok(&uri, &alice).await;
+        channels.extend(page["channels"].as_array().unwrap().iter().cloned());
+        match page["next_offset"].as_i64() {
+            Some(offset) => {
+                offsets.push(offset);
+                uri = format!("/api/v1/chat/channels?limit=20&offset={}", offset);
+            }
+            None => break,
+        }
+    }
+    assert_eq!(offsets, vec![20, 40]);
+    assert_eq!(channels.len(), 53);
+
+    // Most recent first, every private chat with its partner and unread message
+    let private: Vec<&Value> = channels.iter().filter(|c| c["channel_type"] == "private").collect();
+    assert_eq!(private.len(), 50);
+    assert_eq!(private[0]["participant"]["username"], "partner0");
+    assert_eq!(private[49]["participant"]["username"], "partner49");
+    for channel in &private {
+        assert_eq!(channel["unread_count"], 1, "{}", channel);
+        assert_eq!(channel["last_message"]["content_preview"], "hi", "{}", channel);
+    }
+
+    // Paging backwards through a channel's history
+    let uri = format!("/api/v1/chat/channels/{}/messages", WORLD_CHANNEL);
+    for content in ["one", "two", "three"] {
+        app.post_ok(&uri, &alice, json!({ "content": content })).await;
+    }
+
+    let newest = app.get_ok(&format!("{}?limit=2", uri), &alice).await;
+    let contents = |page: &Value| -> Vec<String> {
+        page["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["content"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(contents(&newest), vec!["two", "three"]);
+    assert_eq!(newest["next_cursor"], newest["messages"][0]["id"]);
+
+    let older = app
+        .get_ok(&format!("{}?limit=2&before_id={}", uri, newest["next_cursor"].as_str().unwrap()), &alice)
+        .await;
+    assert_eq!(contents(&older), vec!["one"]);
+    assert!(older["next_cursor"].is_null());
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_partner_presence_across_instances() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+
+    let channel = app.post_ok("/api/v1/chat/channels/private", &alice, json!({ "player_id": bob.id })).await;
+    let partner_online = |page: &Value| {
+        page["channels"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["id"] == channel["id"])
+            .map(|c| c["participant"]["is_online"].clone())
+            .unwrap()
+    };
+
+    let page = app.get_ok("/api/v1/chat/channels", &alice).await;
+    assert_eq!(partner_online(&page), false);
+
+    // Bob connects to this instance
+    let (tx, _rx) = tokio::sync::mpsc::channel(16);
+    app.state.broadcaster.register_client("bob-socket", Some(bob.id), None, tx).await;
+    let page = app.get_ok("/api/v1/chat/channels", &alice).await;
+    assert_eq!(partner_online(&page), true);
+
+    // Another instance (no local connections) sees him through Redis
+    let other_instance = breach_backend::Broadcaster::new()
+        .with_presence(app.state.services.presence.clone(), std::time::Duration::from_secs(30));
+    assert!(other_instance.is_player_online(bob.id).await);
+    assert!(!other_instance.is_player_online(alice.id).await);
+
+    app.state.broadcaster.unregister_client("bob-socket").await;
+    assert!(!other_instance.is_player_online(bob.id).await);
+    let page = app.get_ok("/api/v1/chat/channels", &alice).await;
+    assert_eq!(partner_online(&page), false);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_sixth_message_in_window_is_rate_limited() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+
+    // Default limit: 5 messages per 10 seconds
+    let uri = format!("/api/v1/chat/channels/{}/messages", WORLD_CHANNEL);
+    for i in 0..5 {
+        app.post_ok(&uri, &alice, json!({ "content": format!("Spam #{}", i) })).await;
+    }
+
+    let (status, headers, error) = app
+        .request_with_headers(
+            Method::POST,
+            &uri,
+            Some(&alice.token),
+            Some(json!({ "content": "Spam #5" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS, "{}", error);
+    assert_eq!(error["error"]["code"], "TOO_MANY_REQUESTS");
+    let retry_after: u64 = headers[header::RETRY_AFTER].to_str().unwrap().parse().unwrap();
+    assert!((1..=10).contains(&retry_after), "{}", retry_after);
+
+    // Reading isn't limited, and other players have their own budget
+    app.get_ok(&uri, &alice).await;
+    app.post_ok(&uri, &bob, json!({ "content": "Quiet down" })).await;
+
+    app.cleanup().await;
+}
+
+/// Bob reports a message Alice sent to the world channel; returns the
+/// report and message ids
+async fn report_alice(app: &TestApp, alice: &Player, bob: &Player, content: &str) -> (Uuid, Uuid) {
+    let uri = format!("/api/v1/chat/channels/{}/messages", WORLD_CHANNEL);
+    let sent = app.post_ok(&uri, alice, json!({ "content": content })).await;
+    let message_id: Uuid = sent["id"].as_str().unwrap().parse().unwrap();
+    let report = app
+        .post_ok(
+            &format!("/api/v1/chat/messages/{}/report", message_id),
+            bob,
+            json!({ "reason": "harassment" }),
+        )
+        .await;
+    (report["id"].as_str().unwrap().parse().unwrap(), message_id)
+}
+
+fn resolve(action: ModerationAction) -> ResolveReportRequest {
+    ResolveReportRequest { action, notes: Some("Checked the log".into()) }
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_deleted_report_message_disappears() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let (report_id, message_id) = report_alice(&app, &alice, &bob, "Rude words").await;
+
+    // Only admins see or resolve reports
+    let (status, _) = app.get("/api/v1/admin/chat/reports", &bob).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let chat = &app.state.services.chat;
+    let pending = chat
+        .get_reports(ReportsQuery { status: "pending".into(), limit: 50 })
+        .await
+        .unwrap();
+    assert!(pending.iter().any(|report| report.id == report_id));
+
+    let resolved = chat
+        .resolve_report(bob.id, report_id, resolve(ModerationAction::DeleteMessage))
+        .await
+        .unwrap();
+    assert_eq!(resolved.report.status, "action_taken");
+    assert_eq!(resolved.report.reviewed_by, Some(bob.id));
+    assert_eq!(resolved.deleted_from_channel, Some(WORLD_CHANNEL.parse().unwrap()));
+    assert!(resolved.sanction.is_none());
+
+    let uri = format!("/api/v1/chat/channels/{}/messages", WORLD_CHANNEL);
+    let messages = app.get_ok(&uri, &bob).await;
+    let ids: Vec<&str> = messages["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|message| message["id"].as_str().unwrap())
+        .collect();
+    assert!(!ids.contains(&message_id.to_string().as_str()), "{}", messages);
+
+    // A resolved report can't be resolved again
+    let again = chat
+        .resolve_report(bob.id, report_id, resolve(ModerationAction::Dismiss))
+        .await;
+    assert!(again.is_err());
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_mute_blocks_messages_until_expiry() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let (report_id, _) = report_alice(&app, &alice, &bob, "Spam spam spam").await;
+
+    let resolved = app
+        .state
+        .services
+        .chat
+        .resolve_report(bob.id, report_id, resolve(ModerationAction::MutePlayer { hours: 2 }))
+        .await
+        .unwrap();
+    let sanction = resolved.sanction.unwrap();
+    assert_eq!(sanction.sanction_type, "mute");
+    assert_eq!(sanction.player_id, alice.id);
+
+    let uri = format!("/api/v1/chat/channels/{}/messages", WORLD_CHANNEL);
+    let (status, error) = app.post(&uri, &alice, json!({ "content": "Hello?" })).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", error);
+    let message = error["error"]["message"].as_str().unwrap();
+    assert!(message.contains("muted until"), "{}", message);
+
+    // Once the mute runs out Alice can talk again
+    sqlx::query("UPDATE player_sanctions SET expires_at = NOW() - INTERVAL '1 second' WHERE id = $1")
+        .bind(sanction.id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    app.post_ok(&uri, &alice, json!({ "content": "Sorry" })).await;
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_banned_player_token_rejected() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let (report_id, _) = report_alice(&app, &alice, &bob, "Buy gold at scam.example").await;
+    app.get_ok("/api/v1/player/me", &alice).await;
+
+    app.state
+        .services
+        .chat
+        .resolve_report(bob.id, report_id, resolve(ModerationAction::BanPlayer))
+        .await
+        .unwrap();
+
+    let (status, _) = app.get("/api/v1/player/me", &alice).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    app.get_ok("/api/v1/player/me", &bob).await;
+
+    app.cleanup().await;
+}
@@ -0,0 +1,150 @@
+//! Friend location sharing
+
+use axum::http::{Method, StatusCode};
+use breach_backend::websocket::{push_friend_location, Location, WsMessage};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+
+use crate::harness::{Player, TestApp};
+
+/// Shibuya crossing, in region xn76f
+const LAT: f64 = 35.659_5;
+const LNG: f64 = 139.700_5;
+
+async fn befriend(app: &TestApp, sender: &Player, receiver: &Player) {
+    app.post_ok("/api/v1/friends/request", sender, json!({ "player_id": receiver.id })).await;
+    let requests = app.get_ok("/api/v1/friends/requests", receiver).await;
+    let uri = format!("/api/v1/friends/requests/{}/accept", requests[0]["id"].as_str().unwrap());
+    app.post_ok(&uri, receiver, json!({})).await;
+}
+
+async fn share_location(app: &TestApp, player: &Player, friend: &Player, share: bool) -> (StatusCode, Value) {
+    let uri = format!("/api/v1/friends/{}/settings", friend.id);
+    app.request(Method::PATCH, &uri, Some(&player.token), Some(json!({ "share_location": share })))
+        .await
+}
+
+async fn set_last_location(app: &TestApp, player: &Player, minutes_ago: i32) {
+    sqlx::query(
+        "UPDATE players SET last_location_lat = $2, last_location_lng = $3, \
+         last_location_at = NOW() - make_interval(mins => $4) WHERE id = $1",
+    )
+    .bind(player.id)
+    .bind(LAT)
+    .bind(LNG)
+    .bind(minutes_ago)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+}
+
+/// Push a move to Shibuya from the connection
+async fn move_to_shibuya(app: &TestApp, connection_id: &str) {
+    let client = app.state.broadcaster.get_client(connection_id).await.unwrap();
+    push_friend_location(&app.state, &client, Location { lat: LAT, lng: LNG }).await;
+}
+
+/// The region of the next `player_nearby`, or `None` if none arrives
+async fn next_nearby(rx: &mut mpsc::Receiver<WsMessage>) -> Option<String> {
+    while let Ok(Some(message)) = tokio::time::timeout(std::time::Duration::from_millis(300), rx.recv()).await {
+        if let WsMessage::PlayerNearby { geohash, .. } = message {
+            return Some(geohash);
+        }
+    }
+    None
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_friend_locations_are_coarse_and_opt_in() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    befriend(&app, &alice, &bob).await;
+    set_last_location(&app, &alice, 1).await;
+    set_last_location(&app, &bob, 1).await;
+
+    // Nothing is shared until asked for
+    let locations = app.get_ok("/api/v1/friends/locations", &bob).await;
+    assert_eq!(locations, json!([]));
+
+    let (status, settings) = share_location(&app, &alice, &bob, true).await;
+    assert_eq!(status, StatusCode::OK, "{}", settings);
+    assert_eq!(settings, json!({ "friend_id": bob.id, "share_location": true }));
+    let friends = app.get_ok("/api/v1/friends", &alice).await;
+    assert_eq!(friends[0]["share_location"], true);
+
+    // Bob sees the region and when, never the coordinates
+    let locations = app.get_ok("/api/v1/friends/locations", &bob).await;
+    assert_eq!(locations.as_array().unwrap().len(), 1, "{}", locations);
+    let location = locations[0].as_object().unwrap();
+    let mut fields: Vec<&str> = location.keys().map(String::as_str).collect();
+    fields.sort_unstable();
+    assert_eq!(fields, ["geohash", "last_seen", "player_id", "username"]);
+    assert_eq!(location["player_id"], json!(alice.id));
+    assert_eq!(location["geohash"], "xn76f");
+    assert!(!locations.to_string().contains("35.659"), "{}", locations);
+
+    // Sharing is one way
+    let locations = app.get_ok("/api/v1/friends/locations", &alice).await;
+    assert_eq!(locations, json!([]));
+
+    // A location older than the window isn't shared
+    set_last_location(&app, &alice, 31).await;
+    let locations = app.get_ok("/api/v1/friends/locations", &bob).await;
+    assert_eq!(locations, json!([]));
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_disabling_or_unfriending_stops_location_sharing() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let carol = app.register("carol").await;
+    befriend(&app, &alice, &bob).await;
+    befriend(&app, &alice, &carol).await;
+    set_last_location(&app, &alice, 1).await;
+    share_location(&app, &alice, &bob, true).await;
+    share_location(&app, &alice, &carol, true).await;
+
+    let broadcaster = &app.state.broadcaster;
+    let (alice_tx, _alice_rx) = mpsc::channel(16);
+    broadcaster.register_client("alice-socket", Some(alice.id), Some("alice".into()), alice_tx).await;
+    let (bob_tx, mut bob_rx) = mpsc::channel(16);
+    broadcaster.register_client("bob-socket", Some(bob.id), None, bob_tx).await;
+    broadcaster.subscribe("bob-socket", vec!["xn76f".into()]).await.unwrap();
+    let (carol_tx, mut carol_rx) = mpsc::channel(16);
+    broadcaster.register_client("carol-socket", Some(carol.id), None, carol_tx).await;
+    broadcaster.subscribe("carol-socket", vec!["xn76f".into()]).await.unwrap();
+
+    move_to_shibuya(&app, "alice-socket").await;
+    assert_eq!(next_nearby(&mut bob_rx).await.as_deref(), Some("xn76f"));
+    assert_eq!(next_nearby(&mut carol_rx).await.as_deref(), Some("xn76f"));
+
+    // Turning sharing off for Bob hides Alice from him only
+    share_location(&app, &alice, &bob, false).await;
+    assert_eq!(app.get_ok("/api/v1/friends/locations", &bob).await, json!([]));
+    move_to_shibuya(&app, "alice-socket").await;
+    assert_eq!(next_nearby(&mut bob_rx).await, None);
+    assert_eq!(next_nearby(&mut carol_rx).await.as_deref(), Some("xn76f"));
+
+    // Unfriending ends Carol's sharing with the friendship
+    let uri = format!("/api/v1/friends/{}", carol.id);
+    let (status, body) = app.request(Method::DELETE, &uri, Some(&alice.token), None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(app.get_ok("/api/v1/friends/locations", &carol).await, json!([]));
+    move_to_shibuya(&app, "alice-socket").await;
+    assert_eq!(next_nearby(&mut carol_rx).await, None);
+
+    // And it can't be turned back on without one
+    let (status, _) = share_location(&app, &alice, &carol, true).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    for socket in ["alice-socket", "bob-socket", "carol-socket"] {
+        broadcaster.unregister_client(socket).await;
+    }
+    app.cleanup().await;
+}
@@ -0,0 +1,120 @@
+//! Golden JSON fixtures
+//!
+//! Responses are normalized before comparison: known IDs and wallets become
+//! their alias (`<player:alice>`), other UUIDs are numbered in order of
+//! appearance (`<uuid:1>`), RFC 3339 timestamps become `<timestamp>` and
+//! volatile keys (tokens, signatures, nonces, ...) become `<volatile>`.
+//!
+//! Set `UPDATE_GOLDEN=1` to rewrite the fixtures from the current responses.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use chrono::DateTime;
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+/// Keys whose values differ on every run
+const VOLATILE_KEYS: &[&str] = &["token", "signature", "nonce", "message_signature"];
+
+/// Known values and the placeholder they normalize to
+#[derive(Debug, Default)]
+pub struct Aliases {
+    values: Vec<(String, String)>,
+}
+
+impl Aliases {
+    pub fn add(&mut self, id: Uuid, alias: String) {
+        self.add_str(&id.to_string(), alias);
+    }
+
+    pub fn add_str(&mut self, value: &str, alias: String) {
+        self.values.push((value.to_string(), format!("<{}>", alias)));
+    }
+}
+
+/// Normalize a response, then compare it with `fixtures/<name>.json`
+///
+/// `volatile` names extra keys to blank out for this fixture only.
+pub fn assert_golden(name: &str, aliases: &Aliases, volatile: &[&str], actual: &Value) {
+    let mut normalizer = Normalizer {
+        aliases,
+        volatile,
+        uuids: HashMap::new(),
+    };
+    let actual = normalizer.normalize(None, actual);
+
+    let path = fixture_path(name);
+    let pretty = serde_json::to_string_pretty(&actual).unwrap() + "\n";
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, pretty).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "missing fixture {}; run with UPDATE_GOLDEN=1 to create it",
+            path.display()
+        )
+    });
+    let expected: Value = serde_json::from_str(&expected).unwrap();
+
+    pretty_assertions::assert_eq!(expected, actual, "golden mismatch for {}", name);
+}
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/e2e/fixtures")
+        .join(format!("{}.json", name))
+}
+
+struct Normalizer<'a> {
+    aliases: &'a Aliases,
+    volatile: &'a [&'a str],
+    uuids: HashMap<String, usize>,
+}
+
+impl Normalizer<'_> {
+    fn normalize(&mut self, key: Option<&str>, value: &Value) -> Value {
+        if let Some(key) = key {
+            if !value.is_null() && (VOLATILE_KEYS.contains(&key) || self.volatile.contains(&key)) {
+                return Value::String("<volatile>".into());
+            }
+        }
+
+        match value {
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(k, v)| (k.clone(), self.normalize(Some(k), v)))
+                    .collect::<Map<_, _>>(),
+            ),
+            Value::Array(items) => {
+                Value::Array(items.iter().map(|v| self.normalize(None, v)).collect())
+            }
+            Value::String(s) => Value::String(self.normalize_str(s)),
+            other => other.clone(),
+        }
+    }
+
+    fn normalize_str(&mut self, s: &str) -> String {
+        if DateTime::parse_from_rfc3339(s).is_ok() {
+            return "<timestamp>".into();
+        }
+
+        // Known values, also inside longer strings (e.g. challenge messages)
+        let mut out = s.to_string();
+        for (value, alias) in &self.aliases.values {
+            out = out.replace(value.as_str(), alias);
+        }
+
+        if Uuid::parse_str(&out).is_ok() {
+            let next = self.uuids.len() + 1;
+            let n = *self.uuids.entry(out).or_insert(next);
+            return format!("<uuid:{}>", n);
+        }
+
+        out
+    }
+}
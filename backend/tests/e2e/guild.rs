@@ -0,0 +1,251 @@
+//! Creating a guild and joining it, its treasury, and guild wars
+
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use crate::golden::assert_golden;
+use crate::harness::{Player, SpawnSeed, TestApp};
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_create_then_join() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+
+    let guild = app
+        .post_ok(
+            "/api/v1/guild",
+            &alice,
+            json!({
+                "name": "Night Watch",
+                "tag": "NW",
+                "description": "Hunting after dark",
+                "is_public": true,
+            }),
+        )
+        .await;
+    let guild_id = guild["id"].as_str().unwrap().parse().unwrap();
+    app.aliases.add(guild_id, "guild:night_watch".into());
+    assert_golden("guild_create", &app.aliases, &[], &guild);
+
+    // Public guilds accept join requests immediately
+    let uri = format!("/api/v1/guilds/{}/join", guild_id);
+    let joined = app.post_ok(&uri, &bob, json!({ "message": "hi" })).await;
+    assert_golden("guild_join", &app.aliases, &[], &joined);
+
+    let uri = format!("/api/v1/guilds/{}/members", guild_id);
+    let members = app.get_ok(&uri, &alice).await;
+    assert_golden("guild_members", &app.aliases, &[], &members);
+
+    let membership = app.get_ok("/api/v1/guild/me", &bob).await;
+    assert_golden("guild_me", &app.aliases, &[], &membership);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_concurrent_treasury_withdrawals_cannot_overdraw() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+
+    let guild = app
+        .post_ok(
+            "/api/v1/guild",
+            &alice,
+            json!({ "name": "Night Watch", "tag": "NW", "is_public": true }),
+        )
+        .await;
+    let guild_id = guild["id"].as_str().unwrap().to_string();
+    app.post_ok(&format!("/api/v1/guilds/{}/join", guild_id), &bob, json!({})).await;
+
+    sqlx::query("UPDATE players SET breach_earned = 1000 WHERE id = $1")
+        .bind(alice.id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let deposit = app
+        .post_ok("/api/v1/guild/treasury/deposit", &alice, json!({ "amount": 1000 }))
+        .await;
+    assert_eq!(deposit["balance"], 1000);
+
+    // Plain members can't withdraw
+    let withdraw = json!({ "amount": 700, "reason": "raid supplies" });
+    let (status, _) = app.post("/api/v1/guild/treasury/withdraw", &bob, withdraw.clone()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Only one of two racing withdrawals fits the balance
+    let (first, second) = tokio::join!(
+        app.post("/api/v1/guild/treasury/withdraw", &alice, withdraw.clone()),
+        app.post("/api/v1/guild/treasury/withdraw", &alice, withdraw.clone()),
+    );
+    let mut statuses = [first.0, second.0];
+    statuses.sort();
+    assert_eq!(statuses, [StatusCode::OK, StatusCode::BAD_REQUEST]);
+
+    let treasury = app
+        .get_ok(&format!("/api/v1/guilds/{}/treasury", guild_id), &bob)
+        .await;
+    assert_eq!(treasury["balance"], 300);
+    let kinds: Vec<&str> = treasury["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["kind"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds, ["withdrawal", "deposit"]);
+
+    app.cleanup().await;
+}
+
+/// Create a public guild led by `leader`, returning its ID
+async fn create_guild(app: &TestApp, leader: &Player, name: &str, tag: &str) -> String {
+    let guild = app
+        .post_ok("/api/v1/guild", leader, json!({ "name": name, "tag": tag, "is_public": true }))
+        .await;
+    guild["id"].as_str().unwrap().to_string()
+}
+
+/// Capture a fresh spawn where `player` stands
+async fn capture(app: &mut TestApp, player: &Player, name: &str) {
+    let (lat, lng) = (35.681_236, 139.767_125);
+    let spawn = app.seed_spawn(name, lat, lng, SpawnSeed::default()).await;
+    let location = json!({ "lat": lat, "lng": lng, "accuracy": 5.0 });
+    let authorization = app
+        .post_ok("/api/v1/capture/request", player, json!({ "titan_id": spawn, "player_location": location }))
+        .await;
+    app.post_ok(
+        "/api/v1/capture/confirm",
+        player,
+        json!({ "titan_id": spawn, "challenge": authorization["challenge"] }),
+    )
+    .await;
+}
+
+/// End a war's window now
+async fn end_war(app: &TestApp, war_id: &str) {
+    sqlx::query("UPDATE guild_wars SET ends_at = NOW() - INTERVAL '1 minute' WHERE id = $1::uuid")
+        .bind(war_id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+}
+
+async fn season_points(app: &TestApp, guild_id: &str) -> i64 {
+    sqlx::query_scalar("SELECT season_points FROM guilds WHERE id = $1::uuid")
+        .bind(guild_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_guild_war_scored_by_captures() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let carol = app.register("carol").await;
+    let alpha = create_guild(&app, &alice, "Alpha Pack", "ALP").await;
+    let bravo = create_guild(&app, &bob, "Bravo Company", "BRV").await;
+    app.post_ok(&format!("/api/v1/guilds/{}/join", bravo), &carol, json!({})).await;
+
+    // Only leaders declare war, and not on their own guild
+    let challenge_uri = format!("/api/v1/guilds/{}/war/challenge", alpha);
+    let (status, _) = app.post(&challenge_uri, &carol, json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = app.post(&challenge_uri, &alice, json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let challenge_uri = format!("/api/v1/guilds/{}/war/challenge", bravo);
+    let war = app.post_ok(&challenge_uri, &alice, json!({ "duration_hours": 2 })).await;
+    assert_eq!(war["status"], "pending", "{}", war);
+    let war_id = war["id"].as_str().unwrap().to_string();
+
+    // The challenger can't accept its own challenge
+    let accept_uri = format!("/api/v1/guild/wars/{}/accept", war_id);
+    let (status, _) = app.post(&accept_uri, &alice, json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let war = app.post_ok(&accept_uri, &bob, json!({})).await;
+    assert_eq!(war["status"], "active", "{}", war);
+    assert_eq!(war["duration_hours"], 2);
+
+    capture(&mut app, &alice, "alpha_1").await;
+    capture(&mut app, &bob, "bravo_1").await;
+    capture(&mut app, &carol, "bravo_2").await;
+
+    let active = app.get_ok(&format!("/api/v1/guilds/{}/war", alpha), &alice).await;
+    assert_eq!((active["challenger_score"].as_i64(), active["defender_score"].as_i64()), (Some(1), Some(2)));
+    let scores = active["scores"].as_array().unwrap();
+    assert_eq!(scores.len(), 3, "{}", active);
+    assert!(scores.iter().all(|entry| entry["captures"] == 1));
+
+    end_war(&app, &war_id).await;
+    assert_eq!(app.state.services.guild.close_expired_wars().await.unwrap(), 1);
+
+    let history = app.get_ok(&format!("/api/v1/guilds/{}/wars", alpha), &alice).await;
+    assert_eq!(history[0]["status"], "completed", "{}", history);
+    assert_eq!(history[0]["winner_guild_id"], Value::String(bravo.clone()));
+    assert_eq!(season_points(&app, &bravo).await, 100);
+    assert_eq!(season_points(&app, &alpha).await, 0);
+    let treasury = app.get_ok(&format!("/api/v1/guilds/{}/treasury", bravo), &bob).await;
+    assert_eq!(treasury["balance"], 500, "{}", treasury);
+    assert_eq!(treasury["entries"][0]["kind"], "war_bonus");
+
+    let notifications = app.get_ok("/api/v1/notifications", &carol).await;
+    assert!(notifications.to_string().contains("War Won"), "{}", notifications);
+    let active = app.get_ok(&format!("/api/v1/guilds/{}/war", alpha), &alice).await;
+    assert!(active.is_null());
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_guild_war_forfeited_when_guild_disbands() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let alpha = create_guild(&app, &alice, "Alpha Pack", "ALP").await;
+    let bravo = create_guild(&app, &bob, "Bravo Company", "BRV").await;
+
+    let war = app
+        .post_ok(&format!("/api/v1/guilds/{}/war/challenge", bravo), &alice, json!({}))
+        .await;
+    let war_id = war["id"].as_str().unwrap().to_string();
+    app.post_ok(&format!("/api/v1/guild/wars/{}/accept", war_id), &bob, json!({})).await;
+
+    // A guild already at war can't be challenged again
+    let carol = app.register("carol").await;
+    let charlie = create_guild(&app, &carol, "Charlie Squad", "CHS").await;
+    let (status, _) = app
+        .post(&format!("/api/v1/guilds/{}/war/challenge", alpha), &carol, json!({}))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Alpha disbands mid-war, ahead on points
+    sqlx::query("UPDATE guild_wars SET challenger_score = 5 WHERE id = $1::uuid")
+        .bind(&war_id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM guilds WHERE id = $1::uuid")
+        .bind(&alpha)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    end_war(&app, &war_id).await;
+    assert_eq!(app.state.services.guild.close_expired_wars().await.unwrap(), 1);
+
+    let history = app.get_ok(&format!("/api/v1/guilds/{}/wars", bravo), &bob).await;
+    assert_eq!(history[0]["winner_guild_id"], Value::String(bravo.clone()), "{}", history);
+    assert!(history[0]["challenger_guild_id"].is_null());
+    assert_eq!(history[0]["challenger_name"], "Alpha Pack");
+    assert_eq!(season_points(&app, &bravo).await, 100);
+    assert_eq!(season_points(&app, &charlie).await, 0);
+
+    app.cleanup().await;
+}
@@ -0,0 +1,65 @@
+//! Weekly leaderboard snapshots
+
+use axum::http::StatusCode;
+use chrono::{DateTime, Duration, Utc, Weekday};
+
+use crate::harness::{Player, TestApp};
+
+async fn set_experience(app: &TestApp, player: &Player, experience: i64) {
+    sqlx::query("UPDATE players SET experience = $2 WHERE id = $1")
+        .bind(player.id)
+        .bind(experience)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_weekly_snapshot_freezes_standings() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    set_experience(&app, &alice, 500).await;
+    set_experience(&app, &bob, 300).await;
+
+    // Frozen on Wednesday 2026-10-14; week 41 rolled over on Monday
+    let now: DateTime<Utc> = "2026-10-14T12:00:00Z".parse().unwrap();
+    let leaderboard = &app.state.services.leaderboard;
+    let snapshot = leaderboard
+        .take_weekly_snapshots(Weekday::Mon, 0, 100, now)
+        .await
+        .unwrap()
+        .expect("week 41 not snapshotted yet");
+    assert_eq!(snapshot.period, "2026-41");
+    assert_eq!(snapshot.boards.len(), 4);
+
+    // Taken once per week
+    let again = leaderboard.take_weekly_snapshots(Weekday::Mon, 0, 100, now + Duration::hours(1)).await;
+    assert!(again.unwrap().is_none());
+
+    // Live standings move on; the week's stay put
+    set_experience(&app, &bob, 900).await;
+
+    let history = app.get_ok("/api/v1/leaderboard/experience/history?week=2026-41", &bob).await;
+    assert_eq!(history["period"], "2026-41");
+    let entries = history["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 2, "{}", history);
+    assert_eq!(entries[0]["subject_id"], alice.id.to_string());
+    assert_eq!(entries[0]["score"], 500);
+    assert_eq!(entries[1]["subject_id"], bob.id.to_string());
+    assert_eq!(entries[1]["score"], 300);
+    assert_eq!(history["my_rank"], 2);
+    assert_eq!(history["my_score"], 300);
+
+    let last_week = app.get_ok("/api/v1/leaderboard/experience/history/me", &alice).await;
+    assert_eq!(last_week["rank"], 1);
+    assert_eq!(last_week["score"], 500);
+
+    let (status, _) = app.get("/api/v1/leaderboard/experience/history?week=2026-40", &bob).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = app.get("/api/v1/leaderboard/experience/history?week=last", &bob).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    app.cleanup().await;
+}
@@ -0,0 +1,59 @@
+//! Location plausibility
+
+use breach_backend::error::AppError;
+use chrono::{Duration, Utc};
+
+use crate::harness::TestApp;
+
+/// Shibuya crossing
+const LAT: f64 = 35.659_5;
+const LNG: f64 = 139.700_5;
+
+async fn suspicious_movement_count(app: &TestApp, player_id: uuid::Uuid) -> i32 {
+    sqlx::query_scalar("SELECT suspicious_movement_count FROM players WHERE id = $1")
+        .bind(player_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_hopping_trail_is_refused_after_repeated_violations() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    {
+        let mut game = app.state.game.write().await;
+        game.location_trail_points = 4;
+        game.location_trail_violation_limit = 2;
+    }
+    let location = &app.state.services.location;
+    let start = Utc::now() - Duration::minutes(30);
+
+    // A steady walk north fits its path
+    for i in 0..8 {
+        let at = start + Duration::seconds(30 * i);
+        let lat = LAT + i as f64 * 0.000_2;
+        location.check_trail(bob.id, lat, LNG, at).await.unwrap();
+    }
+    assert_eq!(suspicious_movement_count(&app, bob.id).await, 0);
+
+    // Hopping 900 m east and back every 30s, each hop under the speed limit
+    let mut refused = 0;
+    for i in 0..12 {
+        let at = start + Duration::seconds(30 * i);
+        let lng = if i % 2 == 0 { LNG } else { LNG + 0.01 };
+        match location.check_trail(alice.id, LAT, lng, at).await {
+            Ok(()) => {}
+            Err(AppError::SpeedViolation) => refused += 1,
+            Err(e) => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    // Every full trail is a violation; only the ones from the limit on fail
+    assert_eq!(suspicious_movement_count(&app, alice.id).await, 3);
+    assert_eq!(refused, 2);
+
+    app.cleanup().await;
+}
@@ -0,0 +1,30 @@
+//! End-to-end tests: the full router against a real Postgres and Redis
+//!
+//! Each test creates its own database from `E2E_DATABASE_URL` (an admin
+//! connection, defaults to the `docker-compose.test.yml` service), runs every
+//! migration, and drives the API through the same middleware stack as the
+//! server. Solana is disabled, so nothing leaves the machine. Responses are
+//! compared against golden JSON in `tests/e2e/fixtures`.
+//!
+//! These tests require Postgres (with PostGIS) and Redis.
+//! Run with: make integration-test
+//! Or: cargo test --test e2e -- --ignored
+
+mod golden;
+mod harness;
+
+mod auth;
+mod capture;
+mod chat;
+mod friend;
+mod guild;
+mod leaderboard;
+mod location;
+mod marketplace;
+mod notification;
+mod player;
+mod pvp;
+mod quest;
+mod reward;
+mod spawn;
+mod transaction;
@@ -0,0 +1,747 @@
+//! Listing a Titan and buying it
+
+use axum::http::{Method, StatusCode};
+use breach_backend::models::{MarketplaceActivityType, MarketplaceTransaction, SaleOutcome};
+use breach_backend::websocket::WsMessage;
+use chrono::{DateTime, Duration, Utc};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::golden::assert_golden;
+use crate::harness::{TestApp, TitanSeed};
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_list_then_buy() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let titan = app.seed_titan("storm", &alice, TitanSeed::default()).await;
+
+    let listing = app
+        .post_ok(
+            "/api/v1/marketplace/listings",
+            &alice,
+            json!({ "titan_id": titan, "listing_type": "fixed_price", "price": 5_000 }),
+        )
+        .await;
+    let listing_id = listing["id"].as_str().unwrap().parse().unwrap();
+    app.aliases.add(listing_id, "listing:storm".into());
+    assert_golden("marketplace_create_listing", &app.aliases, &[], &listing);
+
+    let search = app.get_ok("/api/v1/marketplace?element=storm", &bob).await;
+    assert_golden("marketplace_search", &app.aliases, &[], &search);
+
+    let uri = format!("/api/v1/marketplace/listings/{}/buy", listing_id);
+    let purchase = app.post_ok(&uri, &bob, json!({})).await;
+    assert_golden("marketplace_buy", &app.aliases, &[], &purchase);
+
+    let history = app.get_ok("/api/v1/marketplace/history", &bob).await;
+    assert_golden("marketplace_history", &app.aliases, &[], &history);
+
+    // Sold listings drop out of search
+    let search = app.get_ok("/api/v1/marketplace?element=storm", &bob).await;
+    assert_eq!(search["listings"], json!([]), "{}", search);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_resale_pays_creator_royalty() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let carol = app.register("carol").await;
+    let titan = app.seed_titan("storm", &alice, TitanSeed::default()).await;
+
+    let sell = |price: i64| json!({ "titan_id": titan, "listing_type": "fixed_price", "price": price });
+    let buy = |listing: &serde_json::Value| format!("/api/v1/marketplace/listings/{}/buy", listing["id"].as_str().unwrap());
+
+    // Alice captured it, so her own sale owes no royalty
+    let listing = app.post_ok("/api/v1/marketplace/listings", &alice, sell(10_000)).await;
+    let first = app.post_ok(&buy(&listing), &bob, json!({})).await;
+    assert_eq!(first["royalty_amount"], 0);
+    assert_eq!(first["royalty_recipient_id"], json!(null));
+    assert_eq!(first["seller_receives"], 9_750);
+
+    // Bob's resale pays her 5% out of his share
+    let listing = app.post_ok("/api/v1/marketplace/listings", &bob, sell(10_000)).await;
+    let resale = app.post_ok(&buy(&listing), &carol, json!({})).await;
+    assert_eq!(resale["royalty_amount"], 500);
+    assert_eq!(resale["royalty_recipient_id"], alice.id.to_string());
+    assert_eq!(resale["fee"], 250);
+    assert_eq!(resale["seller_receives"], 9_250);
+
+    let creator: Uuid = sqlx::query_scalar("SELECT creator_id FROM player_titans WHERE id = $1")
+        .bind(titan)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(creator, alice.id);
+
+    app.cleanup().await;
+}
+
+/// Give a seeded Titan an on-chain ID
+async fn set_onchain_id(app: &TestApp, titan: Uuid, onchain_id: i64) {
+    sqlx::query("UPDATE player_titans SET onchain_id = $2 WHERE id = $1")
+        .bind(titan)
+        .bind(onchain_id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+}
+
+/// Listing holding a Titan in escrow
+async fn escrowed_listing(app: &TestApp, titan: Uuid) -> Option<Uuid> {
+    sqlx::query_scalar("SELECT escrowed_listing_id FROM player_titans WHERE id = $1")
+        .bind(titan)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_listing_escrows_titan_until_cancelled() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let titan = app.seed_titan("storm", &alice, TitanSeed::default()).await;
+    let partner = app.seed_titan("partner", &alice, TitanSeed::default()).await;
+    set_onchain_id(&app, titan, 1_001).await;
+    set_onchain_id(&app, partner, 1_002).await;
+
+    let listing = app
+        .post_ok(
+            "/api/v1/marketplace/listings",
+            &alice,
+            json!({ "titan_id": titan, "listing_type": "fixed_price", "price": 5_000 }),
+        )
+        .await;
+    let listing_id: Uuid = listing["id"].as_str().unwrap().parse().unwrap();
+    assert_eq!(escrowed_listing(&app, titan).await, Some(listing_id));
+
+    // Cannot fuse an escrowed Titan
+    let fuse = json!({ "titan_a_id": 1_002, "titan_b_id": 1_001 });
+    let (status, error) = app.post("/api/v1/titan/fuse/build", &alice, fuse.clone()).await;
+    assert_eq!(status, StatusCode::CONFLICT, "{}", error);
+    assert_eq!(error["error"]["code"], "TITAN_ESCROWED");
+
+    // Nor transfer it
+    let transfer = json!({ "titan_id": 1_001, "to_wallet": "11111111111111111111111111111111" });
+    let (_, error) = app.post("/api/v1/titan/transfer/build", &alice, transfer).await;
+    assert_eq!(error["error"]["code"], "TITAN_ESCROWED");
+
+    // Cancelling frees it; the sandbox then fails on Solana instead
+    let uri = format!("/api/v1/marketplace/listings/{}", listing_id);
+    let (status, body) = app.request(Method::DELETE, &uri, Some(&alice.token), None).await;
+    assert!(status.is_success(), "{}", body);
+    assert_eq!(escrowed_listing(&app, titan).await, None);
+
+    let (_, error) = app.post("/api/v1/titan/fuse/build", &alice, fuse).await;
+    assert_ne!(error["error"]["code"], "TITAN_ESCROWED", "{}", error);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_sale_releases_escrow_to_buyer() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let titan = app.seed_titan("storm", &alice, TitanSeed::default()).await;
+
+    let listing = app
+        .post_ok(
+            "/api/v1/marketplace/listings",
+            &alice,
+            json!({ "titan_id": titan, "listing_type": "fixed_price", "price": 5_000 }),
+        )
+        .await;
+    let uri = format!("/api/v1/marketplace/listings/{}/buy", listing["id"].as_str().unwrap());
+    app.post_ok(&uri, &bob, json!({})).await;
+
+    let (owner, escrow): (Uuid, Option<Uuid>) =
+        sqlx::query_as("SELECT player_id, escrowed_listing_id FROM player_titans WHERE id = $1")
+            .bind(titan)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(owner, bob.id);
+    assert_eq!(escrow, None);
+
+    app.cleanup().await;
+}
+
+/// Set a listing's status, with the buyer and price of a sale awaiting payment
+async fn hold_listing(app: &TestApp, listing_id: Uuid, status: &str, buyer: Option<(Uuid, i64)>) {
+    sqlx::query(
+        r#"
+        UPDATE marketplace_listings
+        SET status = $2::listing_status, buyer_id = $3, final_price = $4
+        WHERE id = $1
+        "#,
+    )
+    .bind(listing_id)
+    .bind(status)
+    .bind(buyer.map(|(buyer_id, _)| buyer_id))
+    .bind(buyer.map(|(_, price)| price))
+    .execute(&app.pool)
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_held_sales_only_sell_to_their_buyer() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let carol = app.register("carol").await;
+    let titan = app.seed_titan("storm", &alice, TitanSeed::default()).await;
+
+    let listing = app
+        .post_ok(
+            "/api/v1/marketplace/listings",
+            &alice,
+            json!({ "titan_id": titan, "listing_type": "auction", "price": 1_000, "min_price": 1_000 }),
+        )
+        .await;
+    let listing_id: Uuid = listing["id"].as_str().unwrap().parse().unwrap();
+    let buy = format!("/api/v1/marketplace/listings/{}/buy", listing_id);
+    let cancel = format!("/api/v1/marketplace/listings/{}", listing_id);
+
+    // A listing whose payment is in flight can't be bought or withdrawn
+    hold_listing(&app, listing_id, "settling", None).await;
+    let (status, body) = app.post(&buy, &carol, json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{}", body);
+    let (status, body) = app.request(Method::DELETE, &cancel, Some(&alice.token), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{}", body);
+    assert_eq!(escrowed_listing(&app, titan).await, Some(listing_id));
+
+    // An auction won by Bob waits for him alone, at his bid
+    hold_listing(&app, listing_id, "awaiting_payment", Some((bob.id, 1_800))).await;
+    let (status, body) = app.post(&buy, &carol, json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{}", body);
+
+    // Alice can't take it back while Bob still has time to pay
+    let (status, body) = app.request(Method::DELETE, &cancel, Some(&alice.token), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{}", body);
+
+    let sale = app.post_ok(&buy, &bob, json!({})).await;
+    assert_eq!(sale["transaction_type"], "auction_win", "{}", sale);
+    assert_eq!(sale["price"], 1_800);
+    assert_eq!(sale["buyer_id"], bob.id.to_string());
+    assert_eq!(escrowed_listing(&app, titan).await, None);
+
+    app.cleanup().await;
+}
+
+/// Move an auction's end time relative to now, with an optional original end
+async fn set_auction_end(
+    app: &TestApp,
+    listing_id: Uuid,
+    ends_in_secs: i64,
+    original_ended_secs_ago: Option<i64>,
+) {
+    sqlx::query(
+        r#"
+        UPDATE marketplace_listings
+        SET expires_at = NOW() + make_interval(secs => $2),
+            original_expires_at = NOW() - make_interval(secs => $3)
+        WHERE id = $1
+        "#,
+    )
+    .bind(listing_id)
+    .bind(ends_in_secs as f64)
+    .bind(original_ended_secs_ago.map(|secs| secs as f64))
+    .execute(&app.pool)
+    .await
+    .unwrap();
+}
+
+/// End an auction that has a winner; settlement is off, so it sells at once
+async fn completed_auction(app: &TestApp, listing_id: Uuid) -> MarketplaceTransaction {
+    match app.state.services.marketplace.end_auction(listing_id).await.unwrap() {
+        Some(SaleOutcome::Completed(sale)) => sale,
+        other => panic!("auction did not sell: {:?}", other),
+    }
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_late_bid_extends_auction() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let carol = app.register("carol").await;
+    let titan = app.seed_titan("storm", &alice, TitanSeed::default()).await;
+
+    let listing = app
+        .post_ok(
+            "/api/v1/marketplace/listings",
+            &alice,
+            json!({ "titan_id": titan, "listing_type": "auction", "price": 1_000, "min_price": 1_000 }),
+        )
+        .await;
+    let listing_id: Uuid = listing["id"].as_str().unwrap().parse().unwrap();
+    let bids_uri = format!("/api/v1/marketplace/listings/{}/bids", listing_id);
+
+    // A bid at T-30s leaves five minutes on the clock
+    set_auction_end(&app, listing_id, 30, None).await;
+    let bid = app.post_ok(&bids_uri, &bob, json!({ "amount": 1_500 })).await;
+    assert_eq!(bid["extended"], true, "{}", bid);
+    assert_eq!(bid["amount"], 1_500);
+    let expires_at: DateTime<Utc> = bid["expires_at"].as_str().unwrap().parse().unwrap();
+    let remaining = expires_at - Utc::now();
+    assert!(remaining > Duration::minutes(4) && remaining <= Duration::minutes(5), "{}", remaining);
+
+    let (count, original): (i32, Option<DateTime<Utc>>) = sqlx::query_as(
+        "SELECT extension_count, original_expires_at FROM marketplace_listings WHERE id = $1",
+    )
+    .bind(listing_id)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(count, 1);
+    assert!(original.unwrap() < Utc::now() + Duration::seconds(30));
+
+    // Listings show the new end time (stored to the microsecond)
+    let shown = app.get_ok(&format!("/api/v1/marketplace/listings/{}", listing_id), &carol).await;
+    let shown_at: DateTime<Utc> = shown["expires_at"].as_str().unwrap().parse().unwrap();
+    assert!((shown_at - expires_at).num_milliseconds().abs() < 1);
+
+    // The auction can't be settled early
+    assert!(app.state.services.marketplace.end_auction(listing_id).await.is_err());
+
+    // 59.5 minutes of extensions already: the cap leaves no room
+    set_auction_end(&app, listing_id, 30, Some(3_570)).await;
+    let capped = app.post_ok(&bids_uri, &carol, json!({ "amount": 2_000 })).await;
+    assert_eq!(capped["extended"], false, "{}", capped);
+    let capped_at: DateTime<Utc> = capped["expires_at"].as_str().unwrap().parse().unwrap();
+    assert!(capped_at - Utc::now() <= Duration::seconds(30));
+
+    // Once the (extended) end passes, the highest bidder wins
+    set_auction_end(&app, listing_id, -1, Some(3_600)).await;
+    let sale = completed_auction(&app, listing_id).await;
+    assert_eq!(sale.buyer_id, carol.id);
+    assert_eq!(sale.price, 2_000);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_outbid_notifies_displaced_bidder() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let carol = app.register("carol").await;
+    let titan = app.seed_titan("storm", &alice, TitanSeed::default()).await;
+
+    let listing = app
+        .post_ok(
+            "/api/v1/marketplace/listings",
+            &alice,
+            json!({ "titan_id": titan, "listing_type": "auction", "price": 1_000, "min_price": 1_000 }),
+        )
+        .await;
+    let listing_id = listing["id"].as_str().unwrap();
+    let bids_uri = format!("/api/v1/marketplace/listings/{}/bids", listing_id);
+
+    // Bob is connected and hears about it live
+    let (tx, mut bob_rx) = tokio::sync::mpsc::channel(16);
+    app.state.broadcaster.register_client("bob-socket", Some(bob.id), None, tx).await;
+
+    app.post_ok(&bids_uri, &bob, json!({ "amount": 1_000 })).await;
+    app.post_ok(&bids_uri, &carol, json!({ "amount": 1_500 })).await;
+    // Carol raising her own bid outbids nobody
+    app.post_ok(&bids_uri, &carol, json!({ "amount": 2_000 })).await;
+
+    let outbid = |player_id: Uuid| {
+        let pool = app.pool.clone();
+        async move {
+            sqlx::query_as::<_, (String, serde_json::Value)>(
+                "SELECT title, data FROM notifications WHERE player_id = $1 AND notification_type = 'outbid'",
+            )
+            .bind(player_id)
+            .fetch_all(&pool)
+            .await
+            .unwrap()
+        }
+    };
+
+    let bob_notifications = outbid(bob.id).await;
+    assert_eq!(bob_notifications.len(), 1);
+    assert_eq!(bob_notifications[0].0, "You've been outbid");
+    assert_eq!(bob_notifications[0].1["amount"], 1_500);
+    assert!(outbid(carol.id).await.is_empty());
+
+    // The outbid event, and the notification pushed alongside it in either order
+    let (mut outbid_events, mut notifications) = (0, 0);
+    for _ in 0..2 {
+        match tokio::time::timeout(std::time::Duration::from_secs(1), bob_rx.recv()).await {
+            Ok(Some(WsMessage::Outbid { listing_id: id, current_bid, .. })) => {
+                assert_eq!(id, listing_id);
+                assert_eq!(current_bid, 1_500);
+                outbid_events += 1;
+            }
+            Ok(Some(WsMessage::Notification { title, .. })) => {
+                assert_eq!(title, "You've been outbid");
+                notifications += 1;
+            }
+            other => panic!("expected outbid, got {:?}", other),
+        }
+    }
+    assert_eq!((outbid_events, notifications), (1, 1));
+    assert!(bob_rx.try_recv().is_err());
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_proxy_bid_raises_until_exceeded() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let carol = app.register("carol").await;
+    let titan = app.seed_titan("storm", &alice, TitanSeed::default()).await;
+
+    let listing = app
+        .post_ok(
+            "/api/v1/marketplace/listings",
+            &alice,
+            json!({ "titan_id": titan, "listing_type": "auction", "price": 1_000, "min_price": 1_000 }),
+        )
+        .await;
+    let listing_id: Uuid = listing["id"].as_str().unwrap().parse().unwrap();
+    let bids_uri = format!("/api/v1/marketplace/listings/{}/bids", listing_id);
+
+    let (tx, mut bob_rx) = tokio::sync::mpsc::channel(16);
+    app.state.broadcaster.register_client("bob-socket", Some(bob.id), None, tx).await;
+
+    // Bob's proxy opens at the minimum
+    let opened = app.post_ok(&bids_uri, &bob, json!({ "max_bid": 2_000 })).await;
+    assert_eq!(opened["amount"], 1_000, "{}", opened);
+    assert_eq!(opened["max_bid"], 2_000);
+
+    // Carol's bid is answered one 5% step higher
+    let answered = app.post_ok(&bids_uri, &carol, json!({ "amount": 1_500 })).await;
+    assert_eq!(answered["is_winning"], false, "{}", answered);
+    assert_eq!(answered["current_bid"], 1_575);
+
+    // Carol's proxy goes past Bob's maximum and leads one step over it
+    let leading = app.post_ok(&bids_uri, &carol, json!({ "max_bid": 3_000 })).await;
+    assert_eq!(leading["is_winning"], true, "{}", leading);
+    assert_eq!(leading["amount"], 2_100);
+    assert_eq!(leading["max_bid"], 3_000);
+
+    let active: bool = sqlx::query_scalar(
+        "SELECT active FROM auction_proxy_bids WHERE listing_id = $1 AND bidder_id = $2",
+    )
+    .bind(listing_id)
+    .bind(bob.id)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert!(!active);
+
+    let mut exceeded = 0;
+    while let Ok(Some(message)) =
+        tokio::time::timeout(std::time::Duration::from_millis(500), bob_rx.recv()).await
+    {
+        match message {
+            WsMessage::ProxyBidExceeded { max_bid, current_bid, .. } => {
+                assert_eq!((max_bid, current_bid), (2_000, 2_100));
+                exceeded += 1;
+            }
+            WsMessage::Notification { .. } => {}
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+    assert_eq!(exceeded, 1);
+
+    // The winner pays what it took to lead, not their maximum
+    set_auction_end(&app, listing_id, -1, None).await;
+    let sale = completed_auction(&app, listing_id).await;
+    assert_eq!(sale.buyer_id, carol.id);
+    assert_eq!(sale.price, 2_100);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_floor_prices_and_price_suggestion() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let listed = app.seed_titan("listed", &alice, TitanSeed::default()).await;
+    let unlisted = app.seed_titan("unlisted", &bob, TitanSeed::default()).await;
+
+    app.post_ok(
+        "/api/v1/marketplace/listings",
+        &alice,
+        json!({ "titan_id": listed, "listing_type": "fixed_price", "price": 6_000 }),
+    )
+    .await;
+
+    // Storm class III sales: two this week, one older, and one too old to compare
+    for (price, days_ago) in [(4_000, 1), (5_000, 2), (9_000, 20), (50_000, 45)] {
+        sqlx::query(
+            r#"
+            INSERT INTO price_history (element, threat_class, price, transaction_type, recorded_at)
+            VALUES ('storm', 3, $1, 'purchase', NOW() - make_interval(days => $2))
+            "#,
+        )
+        .bind(price as i64)
+        .bind(days_ago)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    }
+
+    // Redis is shared between tests; drop prices cached by earlier runs
+    let _: () = redis::cmd("DEL")
+        .arg("marketplace:floor_prices")
+        .arg("marketplace:suggested_price:storm:3")
+        .query_async(&mut app.state.db.redis.clone())
+        .await
+        .unwrap();
+
+    let floors = app.get_ok("/api/v1/marketplace/floor-prices", &bob).await;
+    let storm = floors
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|entry| entry["element"] == "storm" && entry["threat_class"] == 3)
+        .unwrap();
+    assert_eq!(storm["floor_price"], 6_000);
+    assert_eq!(storm["avg_price_7d"], 4_500);
+    assert_eq!(storm["volume_7d"], 9_000);
+    assert_eq!(storm["sales_7d"], 2);
+
+    let body = json!({ "titan_id": unlisted });
+    let suggestion = app.post_ok("/api/v1/marketplace/listings/suggest-price", &bob, body.clone()).await;
+    assert_eq!(suggestion["suggested_price"], 5_000);
+    assert_eq!(suggestion["comparable_sales"], 3);
+    assert_eq!(suggestion["floor_price"], 6_000);
+
+    // Only for the player's own Titans
+    let (status, _) = app.post("/api/v1/marketplace/listings/suggest-price", &alice, body).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_bundle_sells_every_titan_at_once() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let storm = app.seed_titan("storm", &alice, TitanSeed::default()).await;
+    let volcanic = app
+        .seed_titan("volcanic", &alice, TitanSeed { element: "volcanic", threat_class: 4, ..TitanSeed::default() })
+        .await;
+    let void = app
+        .seed_titan("void", &alice, TitanSeed { element: "void", threat_class: 2, ..TitanSeed::default() })
+        .await;
+    let titans = [storm, volcanic, void];
+
+    let bundle = json!({ "titan_ids": titans, "listing_type": "bundle", "price": 12_000 });
+    let listing = app.post_ok("/api/v1/marketplace/listings", &alice, bundle.clone()).await;
+    let listing_id: Uuid = listing["id"].as_str().unwrap().parse().unwrap();
+    assert_eq!(listing["titan_id"], storm.to_string(), "{}", listing);
+    for titan in titans {
+        assert_eq!(escrowed_listing(&app, titan).await, Some(listing_id));
+    }
+
+    // A bundled Titan can't be listed again, on its own or in another bundle
+    let single = json!({ "titan_id": void, "listing_type": "fixed_price", "price": 1_000 });
+    let (status, _) = app.post("/api/v1/marketplace/listings", &alice, single).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = app.post("/api/v1/marketplace/listings", &alice, bundle).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Composition filters: a Titan of each element, threat classes summing to 9
+    let found = app.get_ok("/api/v1/marketplace?bundle_elements=storm,void&min_threat_sum=9", &bob).await;
+    let listings = found["listings"].as_array().unwrap();
+    assert_eq!(listings.len(), 1, "{}", found);
+    let bundled: Vec<&str> = listings[0]["bundle_titans"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|titan| titan["element"].as_str().unwrap())
+        .collect();
+    assert_eq!(bundled, ["storm", "volcanic", "void"]);
+    for query in ["bundle_elements=abyssal", "max_threat_sum=8", "bundle_elements=storm&listing_type=fixed_price"] {
+        let found = app.get_ok(&format!("/api/v1/marketplace?{}", query), &bob).await;
+        assert_eq!(found["listings"], json!([]), "{}: {}", query, found);
+    }
+
+    let uri = format!("/api/v1/marketplace/listings/{}/buy", listing_id);
+    let purchase = app.post_ok(&uri, &bob, json!({})).await;
+    assert_eq!(purchase["price"], 12_000);
+
+    let owners: Vec<(Uuid, Option<Uuid>)> =
+        sqlx::query_as("SELECT player_id, escrowed_listing_id FROM player_titans WHERE id = ANY($1)")
+            .bind(&titans[..])
+            .fetch_all(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(owners, vec![(bob.id, None); 3]);
+
+    // One sale, and no per-Titan price history
+    let sales: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM marketplace_transactions WHERE listing_id = $1")
+        .bind(listing_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(sales, 1);
+    let history: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM price_history")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(history, 0);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_every_resale_pays_royalties() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let carol = app.register("carol").await;
+    let dave = app.register("dave").await;
+    let storm = app.seed_titan("storm", &alice, TitanSeed::default()).await;
+    let volcanic = app.seed_titan("volcanic", &alice, TitanSeed { element: "volcanic", ..TitanSeed::default() }).await;
+    let void = app.seed_titan("void", &carol, TitanSeed { element: "void", ..TitanSeed::default() }).await;
+
+    // Bob has since come to own them all
+    sqlx::query("UPDATE player_titans SET player_id = $1 WHERE id = ANY($2)")
+        .bind(bob.id)
+        .bind(&[storm, volcanic, void][..])
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    // A bundle splits its price across its Titans, each paying its creator
+    let bundle = json!({ "titan_ids": [storm, volcanic, void], "listing_type": "bundle", "price": 12_000 });
+    let listing = app.post_ok("/api/v1/marketplace/listings", &bob, bundle).await;
+    let uri = format!("/api/v1/marketplace/listings/{}/buy", listing["id"].as_str().unwrap());
+    let purchase = app.post_ok(&uri, &dave, json!({})).await;
+    assert_eq!(purchase["royalty_amount"], 600, "{}", purchase);
+    assert_eq!(purchase["royalty_recipient_id"], json!(null));
+    assert_eq!(purchase["seller_receives"], 12_000 - 300 - 600);
+
+    let transaction_id: Uuid = purchase["id"].as_str().unwrap().parse().unwrap();
+    let mut royalties: Vec<(Uuid, Uuid, i64)> =
+        sqlx::query_as("SELECT titan_id, creator_id, amount FROM marketplace_royalties WHERE transaction_id = $1")
+            .bind(transaction_id)
+            .fetch_all(&app.pool)
+            .await
+            .unwrap();
+    royalties.sort();
+    let mut expected = vec![(storm, alice.id, 200), (volcanic, alice.id, 200), (void, carol.id, 200)];
+    expected.sort();
+    assert_eq!(royalties, expected);
+
+    // A won auction pays one too
+    let auction = json!({ "titan_id": storm, "listing_type": "auction", "price": 1_000, "min_price": 1_000 });
+    let listing = app.post_ok("/api/v1/marketplace/listings", &dave, auction).await;
+    let listing_id: Uuid = listing["id"].as_str().unwrap().parse().unwrap();
+    let bids_uri = format!("/api/v1/marketplace/listings/{}/bids", listing_id);
+    app.post_ok(&bids_uri, &bob, json!({ "amount": 2_000 })).await;
+    set_auction_end(&app, listing_id, -1, None).await;
+    let sale = completed_auction(&app, listing_id).await;
+    assert_eq!((sale.royalty_recipient_id, sale.royalty_amount), (Some(alice.id), 100));
+
+    // And an accepted offer
+    let offer = app
+        .post_ok("/api/v1/marketplace/offers", &bob, json!({ "titan_id": void, "amount": 4_000 }))
+        .await;
+    let uri = format!("/api/v1/marketplace/offers/{}/accept", offer["id"].as_str().unwrap());
+    let accepted = app.post_ok(&uri, &dave, json!({})).await;
+    assert_eq!(accepted["status"], "completed", "{}", accepted);
+    assert_eq!(accepted["royalty_amount"], 200);
+    assert_eq!(accepted["royalty_recipient_id"], carol.id.to_string());
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_activity_feed_pages_and_pushes() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let storm = app.seed_titan("storm", &alice, TitanSeed::default()).await;
+    let tide = app.seed_titan("tide", &alice, TitanSeed::default()).await;
+
+    // A feed screen following along
+    let (tx, mut feed_rx) = tokio::sync::mpsc::channel(16);
+    app.state.broadcaster.register_client("feed-socket", None, None, tx).await;
+    app.state.broadcaster.subscribe_marketplace("feed-socket").await;
+
+    let sell = |titan: Uuid| json!({ "titan_id": titan, "listing_type": "fixed_price", "price": 5_000 });
+    let sold = app.post_ok("/api/v1/marketplace/listings", &alice, sell(storm)).await;
+    let cancelled = app.post_ok("/api/v1/marketplace/listings", &alice, sell(tide)).await;
+    app.post_ok(&format!("/api/v1/marketplace/listings/{}/buy", sold["id"].as_str().unwrap()), &bob, json!({})).await;
+    let uri = format!("/api/v1/marketplace/listings/{}", cancelled["id"].as_str().unwrap());
+    let (status, body) = app.request(Method::DELETE, &uri, Some(&alice.token), None).await;
+    assert!(status.is_success(), "{}", body);
+
+    let mut pushed = Vec::new();
+    for _ in 0..4 {
+        match tokio::time::timeout(std::time::Duration::from_secs(1), feed_rx.recv()).await {
+            Ok(Some(WsMessage::MarketplaceActivity { activity_type, player_username, .. })) => {
+                pushed.push((activity_type, player_username.unwrap()));
+            }
+            other => panic!("expected marketplace activity, got {:?}", other),
+        }
+    }
+    assert_eq!(
+        pushed,
+        [
+            (MarketplaceActivityType::ListingCreated, "alice".to_string()),
+            (MarketplaceActivityType::ListingCreated, "alice".to_string()),
+            (MarketplaceActivityType::SaleCompleted, "bob".to_string()),
+            (MarketplaceActivityType::ListingCancelled, "alice".to_string()),
+        ]
+    );
+
+    // Newest first, two to a page
+    let page = app.get_ok("/api/v1/marketplace/activity?limit=2", &bob).await;
+    let types = |page: &serde_json::Value| -> Vec<String> {
+        page["activities"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["activity_type"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(types(&page), ["listing_cancelled", "sale_completed"]);
+    assert_eq!(page["activities"][1]["titan_id"], storm.to_string());
+    assert_eq!(page["activities"][1]["price"], 5_000);
+
+    let cursor = page["next_cursor"].as_str().unwrap();
+    let page = app
+        .get_ok(&format!("/api/v1/marketplace/activity?limit=2&after_id={}", cursor), &bob)
+        .await;
+    assert_eq!(types(&page), ["listing_created", "listing_created"]);
+
+    let cursor = page["next_cursor"].as_str().unwrap();
+    let page = app
+        .get_ok(&format!("/api/v1/marketplace/activity?limit=2&after_id={}", cursor), &bob)
+        .await;
+    assert_eq!(page["activities"], json!([]));
+    assert_eq!(page["next_cursor"], json!(null));
+
+    app.cleanup().await;
+}
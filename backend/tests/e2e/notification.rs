@@ -0,0 +1,70 @@
+//! Notifications pushed over WebSocket and read over REST
+
+use std::time::Duration;
+
+use breach_backend::websocket::WsMessage;
+use serde_json::json;
+use tokio::sync::mpsc;
+
+use crate::harness::TestApp;
+
+/// Next message on a socket that isn't a `friend_request`
+async fn next_message(rx: &mut mpsc::Receiver<WsMessage>) -> WsMessage {
+    loop {
+        match tokio::time::timeout(Duration::from_secs(1), rx.recv()).await {
+            Ok(Some(WsMessage::FriendRequest { .. })) => continue,
+            Ok(Some(message)) => return message,
+            other => panic!("expected a message, got {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_notifications_pushed_live_and_kept_for_offline_players() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let carol = app.register("carol").await;
+
+    // Bob is connected and gets his notification as it's created
+    let (tx, mut bob_rx) = mpsc::channel(16);
+    app.state.broadcaster.register_client("bob-socket", Some(bob.id), None, tx).await;
+
+    app.post_ok("/api/v1/friends/request", &alice, json!({ "player_id": bob.id })).await;
+    match next_message(&mut bob_rx).await {
+        WsMessage::Notification { notification_type, title, data, .. } => {
+            assert_eq!(json!(notification_type), json!("friend_request"));
+            assert_eq!(title, "New Friend Request");
+            assert_eq!(data.unwrap()["sender_id"], alice.id.to_string());
+        }
+        other => panic!("expected notification, got {:?}", other),
+    }
+
+    // Reading it clears the badge on every device
+    app.post_ok("/api/v1/notifications/read-all", &bob, json!({})).await;
+    assert!(matches!(
+        next_message(&mut bob_rx).await,
+        WsMessage::NotificationCount { unread: 0 }
+    ));
+
+    // Carol is offline; hers waits in the feed
+    app.post_ok("/api/v1/friends/request", &alice, json!({ "player_id": carol.id })).await;
+    let feed = app.get_ok("/api/v1/notifications", &carol).await;
+    let notifications = feed.as_array().unwrap();
+    assert_eq!(notifications.len(), 1, "{}", feed);
+    assert_eq!(notifications[0]["notification_type"], "friend_request");
+    assert_eq!(notifications[0]["read_at"], json!(null));
+
+    let uri = format!("/api/v1/notifications/{}/read", notifications[0]["id"].as_str().unwrap());
+    app.post_ok(&uri, &carol, json!({})).await;
+    let feed = app.get_ok("/api/v1/notifications", &carol).await;
+    assert_eq!(feed[0]["is_read"], true);
+    assert!(feed[0]["read_at"].is_string(), "{}", feed);
+
+    let counts = app.get_ok("/api/v1/notifications/count", &carol).await;
+    assert_eq!(counts["unread"], 0);
+
+    app.state.broadcaster.unregister_client("bob-socket").await;
+    app.cleanup().await;
+}
@@ -0,0 +1,31 @@
+//! Player Titan collection
+
+use axum::http::StatusCode;
+
+use crate::harness::{TestApp, TitanSeed};
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_titan_collection_lists_sync_state() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let titan = app
+        .seed_titan("storm", &alice, TitanSeed { level: 12, ..TitanSeed::default() })
+        .await;
+
+    let body = app.get_ok("/api/v1/player/titans", &alice).await;
+    let titans = body["titans"].as_array().unwrap();
+    assert_eq!(titans.len(), 1);
+    assert_eq!(titans[0]["id"], titan.to_string());
+    assert_eq!(titans[0]["level"], 12);
+    assert_eq!(titans[0]["experience"], 0);
+    assert_eq!(titans[0]["onchain_owner_mismatch"], false);
+    assert_eq!(titans[0]["onchain_missing"], false);
+    assert!(body.get("sync_report").is_none());
+
+    // Syncing reads the Titan PDAs, and Solana is disabled here
+    let (status, _) = app.get("/api/v1/player/titans?sync=true", &alice).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+
+    app.cleanup().await;
+}
@@ -0,0 +1,921 @@
+//! Ranked PvP: queue, match, actions, completion
+
+use std::collections::HashMap;
+
+use axum::http::StatusCode;
+use breach_backend::error::{ApiResult, AppError};
+use breach_backend::services::RewardTransfer;
+use breach_backend::websocket::{start_spectating, WsMessage};
+use chrono::Duration;
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::golden::assert_golden;
+use crate::harness::{Player, TestApp, TitanSeed};
+
+/// Damage and crits are rolled, so HP and turn counts are not compared
+const VOLATILE: &[&str] = &[
+    "my_damage",
+    "critical",
+    "opponent_damage",
+    "my_hp",
+    "opponent_hp",
+    "my_hp_after",
+    "opponent_hp_after",
+    "turn_number",
+    "total_turns",
+    "duration_seconds",
+];
+
+/// Queue timing depends on how fast the test runs
+const QUEUE_VOLATILE: &[&str] = &["wait_time_seconds", "estimated_wait"];
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_queue_match_and_finish() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let alice_titan = app.seed_titan("alice", &alice, TitanSeed::default()).await;
+    let bob_titan = app
+        .seed_titan(
+            "bob",
+            &bob,
+            TitanSeed {
+                element: "abyssal",
+                species_id: 201,
+                ..TitanSeed::default()
+            },
+        )
+        .await;
+
+    let queued = app.post_ok("/api/v1/pvp/queue", &alice, json!({ "titan_id": alice_titan })).await;
+    assert_golden("pvp_queue_waiting", &app.aliases, QUEUE_VOLATILE, &queued);
+
+    // The second player in the queue is matched immediately
+    let matched = app.post_ok("/api/v1/pvp/queue", &bob, json!({ "titan_id": bob_titan })).await;
+    assert_eq!(matched["match_found"], true, "{}", matched);
+    let match_id = matched["match_id"].as_str().unwrap().parse().unwrap();
+    app.aliases.add(match_id, "match".into());
+    assert_golden("pvp_queue_matched", &app.aliases, QUEUE_VOLATILE, &matched);
+
+    let uri = format!("/api/v1/pvp/match/{}/titan", match_id);
+    app.post_ok(&uri, &alice, json!({ "titan_id": alice_titan })).await;
+    let state = app.post_ok(&uri, &bob, json!({ "titan_id": bob_titan })).await;
+    assert_golden("pvp_match_started", &app.aliases, VOLATILE, &state);
+
+    // Alice always attacks and Bob always defends, so Alice wins; Storm is
+    // super effective against Abyssal
+    let state_uri = format!("/api/v1/pvp/match/{}", match_id);
+    let mut last = Value::Null;
+    for _ in 0..500 {
+        let state = app.get_ok(&state_uri, &alice).await;
+        let (player, action): (&Player, _) = if state["is_my_turn"] == true {
+            (&alice, "attack")
+        } else {
+            (&bob, "defend")
+        };
+
+        last = app
+            .post_ok("/api/v1/pvp/action", player, json!({ "match_id": match_id, "action": action }))
+            .await;
+        if last["match_ended"] == true {
+            break;
+        }
+    }
+    assert_eq!(last["match_ended"], true, "match did not finish: {}", last);
+    assert_golden("pvp_final_action", &app.aliases, VOLATILE, &last);
+
+    let finished = app.get_ok(&state_uri, &bob).await;
+    assert_golden("pvp_match_completed", &app.aliases, VOLATILE, &finished);
+
+    let history = app.get_ok("/api/v1/pvp/history", &alice).await;
+    assert_golden("pvp_history", &app.aliases, VOLATILE, &history);
+
+    app.cleanup().await;
+}
+
+/// Queue Alice then Bob and have both pick a Titan; Alice moves first
+async fn start_match(app: &mut TestApp) -> (Player, Player, Uuid) {
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let alice_titan = app.seed_titan("alice", &alice, TitanSeed::default()).await;
+    let bob_titan = app.seed_titan("bob", &bob, TitanSeed::default()).await;
+
+    app.post_ok("/api/v1/pvp/queue", &alice, json!({ "titan_id": alice_titan })).await;
+    let matched = app.post_ok("/api/v1/pvp/queue", &bob, json!({ "titan_id": bob_titan })).await;
+    let match_id: Uuid = matched["match_id"].as_str().unwrap().parse().unwrap();
+
+    let uri = format!("/api/v1/pvp/match/{}/titan", match_id);
+    app.post_ok(&uri, &alice, json!({ "titan_id": alice_titan })).await;
+    app.post_ok(&uri, &bob, json!({ "titan_id": bob_titan })).await;
+
+    (alice, bob, match_id)
+}
+
+/// Move the current turn's deadline well past the grace period
+async fn expire_turn(app: &TestApp, match_id: Uuid) {
+    sqlx::query("UPDATE pvp_matches SET turn_deadline = NOW() - INTERVAL '1 minute' WHERE id = $1")
+        .bind(match_id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_turn_timeouts_defend_then_forfeit() {
+    let mut app = TestApp::spawn().await;
+    let (alice, bob, match_id) = start_match(&mut app).await;
+    let state_uri = format!("/api/v1/pvp/match/{}", match_id);
+    let pvp = &app.state.services.pvp;
+    let (grace, reconnect_grace) = (Duration::seconds(5), Duration::seconds(60));
+
+    // Nothing to do while the turn is still running
+    assert!(pvp.resolve_turn_timeouts(grace, 3, reconnect_grace).await.unwrap().is_empty());
+
+    // Each of Alice's timed-out turns is a defend played for her, and Bob
+    // acting in between doesn't clear her count
+    for timeouts_in_a_row in 1..=2 {
+        expire_turn(&app, match_id).await;
+        let timeouts = pvp.resolve_turn_timeouts(grace, 3, reconnect_grace).await.unwrap();
+        assert_eq!(timeouts.len(), 1);
+        assert_eq!(timeouts[0].player_id, alice.id);
+        assert_eq!(timeouts[0].consecutive_skips, timeouts_in_a_row);
+        assert!(!timeouts[0].forfeited);
+
+        let state = app.get_ok(&state_uri, &bob).await;
+        assert_eq!(state["status"], "active");
+        assert_eq!(state["is_my_turn"], true);
+        assert_eq!(state["my_consecutive_skips"], 0);
+        assert_eq!(state["opponent_consecutive_skips"], timeouts_in_a_row);
+
+        app.post_ok("/api/v1/pvp/action", &bob, json!({ "match_id": match_id, "action": "defend" }))
+            .await;
+    }
+
+    let defends: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FROM pvp_battle_turns t JOIN pvp_matches m ON m.id = t.match_id
+        WHERE t.match_id = $1
+          AND CASE WHEN m.player1_id = $2 THEN t.player1_action ELSE t.player2_action END = 'defend'
+        "#,
+    )
+    .bind(match_id)
+    .bind(alice.id)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(defends, 2);
+
+    // Her third timeout in a row hands Bob the win
+    expire_turn(&app, match_id).await;
+    let timeouts = pvp.resolve_turn_timeouts(grace, 3, reconnect_grace).await.unwrap();
+    assert_eq!(timeouts.len(), 1);
+    assert_eq!(timeouts[0].player_id, alice.id);
+    assert_eq!(timeouts[0].consecutive_skips, 3);
+    assert!(timeouts[0].forfeited);
+
+    let state = app.get_ok(&state_uri, &alice).await;
+    assert_eq!(state["status"], "completed");
+    assert_eq!(state["my_consecutive_skips"], 3);
+
+    let (winner_id, win_reason, winner_change, loser_change): (Uuid, String, i32, i32) = sqlx::query_as(
+        "SELECT winner_id, win_reason, winner_elo_change, loser_elo_change FROM pvp_matches WHERE id = $1",
+    )
+    .bind(match_id)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(winner_id, bob.id);
+    assert_eq!(win_reason, "timeout_forfeit");
+    // Equal ratings with K = 32
+    assert_eq!((winner_change, loser_change), (16, -16));
+
+    let elo: HashMap<Uuid, i32> =
+        sqlx::query_as::<_, (Uuid, i32)>("SELECT player_id, elo_rating FROM player_pvp_stats")
+            .fetch_all(&app.pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .collect();
+    assert_eq!(elo[&bob.id], 1016);
+    assert_eq!(elo[&alice.id], 984);
+
+    // A finished match is never picked up again
+    expire_turn(&app, match_id).await;
+    assert!(pvp.resolve_turn_timeouts(grace, 3, reconnect_grace).await.unwrap().is_empty());
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_item_action_refused() {
+    let mut app = TestApp::spawn().await;
+    let (alice, _bob, match_id) = start_match(&mut app).await;
+
+    // Alice moves first; an item is turned away without using up her turn
+    let (status, error) = app
+        .post("/api/v1/pvp/action", &alice, json!({ "match_id": match_id, "action": "item" }))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", error);
+
+    let state = app.get_ok(&format!("/api/v1/pvp/match/{}", match_id), &alice).await;
+    assert_eq!(state["is_my_turn"], true);
+    let turns: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM pvp_battle_turns WHERE match_id = $1")
+        .bind(match_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(turns, 0);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_racing_actions_play_one_turn() {
+    let mut app = TestApp::spawn().await;
+    let (alice, bob, match_id) = start_match(&mut app).await;
+
+    // Two actions for the same turn: the second waits on the first and
+    // finds it's no longer Alice's turn
+    let action = json!({ "match_id": match_id, "action": "attack" });
+    let (first, second) = tokio::join!(
+        app.post("/api/v1/pvp/action", &alice, action.clone()),
+        app.post("/api/v1/pvp/action", &alice, action),
+    );
+    let mut statuses = [first.0, second.0];
+    statuses.sort();
+    assert_eq!(statuses, [StatusCode::OK, StatusCode::BAD_REQUEST], "{} {}", first.1, second.1);
+
+    let turns: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM pvp_battle_turns WHERE match_id = $1")
+        .bind(match_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(turns, 1);
+
+    // Ending a match twice settles it once
+    let pvp = &app.state.services.pvp;
+    pvp.end_match(match_id, bob.id, "surrender").await.unwrap();
+    pvp.end_match(match_id, alice.id, "surrender").await.unwrap();
+    let results: HashMap<Uuid, (i32, i32)> =
+        sqlx::query_as::<_, (Uuid, i32, i32)>("SELECT player_id, matches_played, matches_won FROM player_pvp_stats")
+            .fetch_all(&app.pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(player_id, played, won)| (player_id, (played, won)))
+            .collect();
+    assert_eq!(results[&bob.id], (1, 1));
+    assert_eq!(results[&alice.id], (1, 0));
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_disconnect_grace_then_reconnect() {
+    let mut app = TestApp::spawn().await;
+    let (alice, bob, match_id) = start_match(&mut app).await;
+    let state_uri = format!("/api/v1/pvp/match/{}", match_id);
+    let pvp = &app.state.services.pvp;
+    let (grace, reconnect_grace) = (Duration::seconds(5), Duration::seconds(60));
+
+    // Alice drops on her turn and lets it run out
+    assert_eq!(pvp.active_match_id(alice.id).await.unwrap(), Some(match_id));
+    assert!(pvp.mark_disconnected(alice.id, match_id).await.unwrap());
+    expire_turn(&app, match_id).await;
+
+    // Within the grace window her turn waits for her
+    assert!(pvp.resolve_turn_timeouts(grace, 2, reconnect_grace).await.unwrap().is_empty());
+    let state = app.get_ok(&state_uri, &alice).await;
+    assert_eq!(state["status"], "active");
+    assert_eq!(state["is_my_turn"], true);
+    assert_eq!(state["my_consecutive_skips"], 0);
+
+    // Re-subscribing to the match clears the timer and restarts her turn
+    let _alice_rx = go_online(&app, &alice).await;
+    let connection_id = format!("{}-socket", alice.id);
+    let subscribed = start_spectating(&app.state, &connection_id, &match_id.to_string()).await;
+    assert!(matches!(&subscribed[0], WsMessage::Spectating { .. }), "{:?}", subscribed);
+    assert!(!pvp.mark_reconnected(alice.id, match_id).await.unwrap());
+    assert!(pvp.resolve_turn_timeouts(grace, 2, reconnect_grace).await.unwrap().is_empty());
+
+    // The match carries on
+    app.post_ok("/api/v1/pvp/action", &alice, json!({ "match_id": match_id, "action": "attack" }))
+        .await;
+    let state = app.get_ok(&state_uri, &bob).await;
+    assert_eq!(state["status"], "active");
+    assert_eq!(state["is_my_turn"], true);
+
+    // Bob drops on his turn and stays gone past the grace window
+    assert!(pvp.mark_disconnected(bob.id, match_id).await.unwrap());
+    sqlx::query(
+        r#"
+        UPDATE pvp_matches SET
+            player1_disconnected_at = player1_disconnected_at - INTERVAL '2 minutes',
+            player2_disconnected_at = player2_disconnected_at - INTERVAL '2 minutes'
+        WHERE id = $1
+        "#,
+    )
+    .bind(match_id)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+    expire_turn(&app, match_id).await;
+
+    let timeouts = pvp.resolve_turn_timeouts(grace, 2, reconnect_grace).await.unwrap();
+    assert_eq!(timeouts.len(), 1);
+    assert_eq!(timeouts[0].player_id, bob.id);
+    assert!(timeouts[0].forfeited);
+
+    let (winner_id, win_reason): (Uuid, String) =
+        sqlx::query_as("SELECT winner_id, win_reason FROM pvp_matches WHERE id = $1")
+            .bind(match_id)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(winner_id, alice.id);
+    assert_eq!(win_reason, "disconnect");
+
+    app.cleanup().await;
+}
+
+/// A connection for `player`, as if they had the app open
+async fn go_online(app: &TestApp, player: &Player) -> mpsc::Receiver<WsMessage> {
+    let (tx, rx) = mpsc::channel(16);
+    let connection_id = format!("{}-socket", player.id);
+    app.state.broadcaster.register_client(&connection_id, Some(player.id), None, tx).await;
+    rx
+}
+
+/// Next message of the kind `pick` accepts, skipping others (notifications)
+async fn next_matching<T>(rx: &mut mpsc::Receiver<WsMessage>, pick: impl Fn(WsMessage) -> Option<T>) -> T {
+    loop {
+        let message = tokio::time::timeout(std::time::Duration::from_secs(1), rx.recv())
+            .await
+            .expect("no message")
+            .expect("socket closed");
+        if let Some(found) = pick(message) {
+            return found;
+        }
+    }
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_match_found_pushed_and_unready_match_cancelled() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let alice_titan = app.seed_titan("alice", &alice, TitanSeed::default()).await;
+    let bob_titan = app.seed_titan("bob", &bob, TitanSeed::default()).await;
+    let mut alice_rx = go_online(&app, &alice).await;
+    let mut bob_rx = go_online(&app, &bob).await;
+
+    app.post_ok("/api/v1/pvp/queue", &alice, json!({ "titan_id": alice_titan })).await;
+    let matched = app.post_ok("/api/v1/pvp/queue", &bob, json!({ "titan_id": bob_titan })).await;
+    let match_id = matched["match_id"].as_str().unwrap().to_string();
+
+    // Both hear about it without polling, each about the other
+    for (rx, opponent) in [(&mut alice_rx, &bob), (&mut bob_rx, &alice)] {
+        let (found_id, opponent_id, opponent_elo) = next_matching(rx, |message| match message {
+            WsMessage::PvpMatchFound { match_id, opponent_id, opponent_elo, .. } => {
+                Some((match_id, opponent_id, opponent_elo))
+            }
+            _ => None,
+        })
+        .await;
+        assert_eq!(found_id, match_id);
+        assert_eq!(opponent_id, opponent.id.to_string());
+        assert_eq!(opponent_elo, 1000);
+    }
+
+    // Only Alice picks a Titan before the deadline passes
+    let uri = format!("/api/v1/pvp/match/{}/titan", match_id);
+    app.post_ok(&uri, &alice, json!({ "titan_id": alice_titan })).await;
+    let pvp = &app.state.services.pvp;
+    assert!(pvp.cancel_unready_matches().await.unwrap().is_empty());
+
+    sqlx::query("UPDATE pvp_matches SET ready_deadline = NOW() - INTERVAL '1 second' WHERE id = $1::uuid")
+        .bind(&match_id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let cancelled = pvp.cancel_unready_matches().await.unwrap();
+    assert_eq!(cancelled.len(), 1);
+    assert_eq!(cancelled[0].match_id.to_string(), match_id);
+
+    let status: String = sqlx::query_scalar("SELECT status::text FROM pvp_matches WHERE id = $1::uuid")
+        .bind(&match_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(status, "abandoned");
+
+    // Both are free to queue again
+    for (player, titan) in [(&alice, alice_titan), (&bob, bob_titan)] {
+        let status = app.get_ok("/api/v1/pvp/queue", player).await;
+        assert_eq!(status["in_queue"], false, "{}", status);
+        assert_eq!(status["match_found"], false, "{}", status);
+        app.post_ok("/api/v1/pvp/queue", player, json!({ "titan_id": titan })).await;
+    }
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_switch_titan_mid_battle() {
+    let mut app = TestApp::spawn().await;
+    let (alice, bob, match_id) = start_match(&mut app).await;
+    let reserve = app.seed_titan("reserve", &alice, TitanSeed::default()).await;
+    let state_uri = format!("/api/v1/pvp/match/{}", match_id);
+    let act = |action: Value| json!({ "match_id": match_id, "action": action });
+
+    let state = app.get_ok(&state_uri, &alice).await;
+    let starter: Uuid = state["my_titan"]["id"].as_str().unwrap().parse().unwrap();
+    let bob_titan = state["opponent_titan"]["id"].clone();
+
+    app.post_ok("/api/v1/pvp/action", &alice, act(json!("defend"))).await;
+    let hit = app.post_ok("/api/v1/pvp/action", &bob, act(json!("attack"))).await;
+    let starter_hp = hit["opponent_hp_after"].as_i64().unwrap();
+    assert!(starter_hp < 100, "{}", hit);
+
+    // Only her own Titans, and not the one already fighting
+    for titan in [bob_titan, json!(starter)] {
+        let (status, error) = app
+            .post("/api/v1/pvp/action", &alice, act(json!({ "switch": { "titan_id": titan } })))
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", error);
+    }
+
+    // Switching costs the turn: no damage, and Bob moves next
+    let switched = app
+        .post_ok("/api/v1/pvp/action", &alice, act(json!({ "switch": { "titan_id": reserve } })))
+        .await;
+    assert_eq!(switched["my_action"]["switch"]["titan_id"], reserve.to_string(), "{}", switched);
+    assert_eq!(switched["my_damage"], 0);
+    assert_eq!(switched["my_hp_after"], 100);
+    assert_eq!(switched["match_ended"], false);
+
+    let state = app.get_ok(&state_uri, &bob).await;
+    assert_eq!(state["is_my_turn"], true);
+    assert_eq!(state["opponent_titan"]["id"], reserve.to_string());
+    assert_eq!(state["opponent_hp"], 100);
+
+    let (action, damage, switched_titan): (String, i32, Option<Uuid>) = sqlx::query_as(
+        r#"
+        SELECT player1_action::text, player1_damage, switched_titan_id FROM pvp_battle_turns
+        WHERE match_id = $1 ORDER BY turn_number DESC LIMIT 1
+        "#,
+    )
+    .bind(match_id)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!((action.as_str(), damage, switched_titan), ("switch", 0, Some(reserve)));
+
+    // The reserve takes the next hit; the starter keeps its HP on the bench
+    let hit = app.post_ok("/api/v1/pvp/action", &bob, act(json!("attack"))).await;
+    assert!(hit["opponent_hp_after"].as_i64().unwrap() < 100, "{}", hit);
+
+    let attacked = app.post_ok("/api/v1/pvp/action", &alice, act(json!("attack"))).await;
+    assert!(attacked["my_damage"].as_i64().unwrap() > 0, "{}", attacked);
+    assert!(attacked["opponent_hp_after"].as_i64().unwrap() < 100, "{}", attacked);
+
+    app.post_ok("/api/v1/pvp/action", &bob, act(json!("defend"))).await;
+    let back = app
+        .post_ok("/api/v1/pvp/action", &alice, act(json!({ "switch": { "titan_id": starter } })))
+        .await;
+    assert_eq!(back["my_hp_after"], starter_hp, "{}", back);
+
+    app.cleanup().await;
+}
+
+/// Both players' defending flags
+async fn defending(app: &TestApp, match_id: Uuid) -> (bool, bool) {
+    sqlx::query_as("SELECT player1_defending, player2_defending FROM pvp_matches WHERE id = $1")
+        .bind(match_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_defend_halves_next_attack() {
+    let mut app = TestApp::spawn().await;
+    let (alice, bob, match_id) = start_match(&mut app).await;
+    let act = |action: &str| json!({ "match_id": match_id, "action": action });
+
+    let defended = app.post_ok("/api/v1/pvp/action", &alice, act("defend")).await;
+    assert_eq!(defended["my_damage"], 0);
+    assert!(defended["element_advantage"].is_null(), "{}", defended);
+    assert_eq!(defending(&app, match_id).await, (true, false));
+
+    // Between Titans of one class an attack rolls 15-24 before the type and
+    // crit multipliers; halved, the top of that range stays below the bottom
+    // of an unmitigated hit
+    let hit = app.post_ok("/api/v1/pvp/action", &bob, act("attack")).await;
+    let effectiveness = hit["effectiveness"].as_f64().unwrap();
+    assert_eq!(hit["element_advantage"].as_f64(), Some(effectiveness), "{}", hit);
+    let multiplier = effectiveness * if hit["critical"] == true { 1.5 } else { 1.0 };
+    let damage = hit["my_damage"].as_i64().unwrap();
+    assert_eq!(hit["opponent_defended"], true, "{}", hit);
+    assert!(damage > 0, "{}", hit);
+    assert!(damage <= (24.0 * multiplier * 0.5).round() as i64, "{}", hit);
+    assert!(damage < (15.0 * multiplier).round() as i64, "{}", hit);
+    assert_eq!(hit["opponent_hp_after"], 100 - damage);
+
+    // Used up by the hit
+    assert_eq!(defending(&app, match_id).await, (false, false));
+    app.post_ok("/api/v1/pvp/action", &alice, act("attack")).await;
+    let hit = app.post_ok("/api/v1/pvp/action", &bob, act("attack")).await;
+    assert_eq!(hit["opponent_defended"], false, "{}", hit);
+    assert!(hit["my_damage"].as_i64().unwrap() >= (15.0 * effectiveness).round() as i64, "{}", hit);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_spectator_receives_turn_updates() {
+    let mut app = TestApp::spawn().await;
+    let (alice, bob, match_id) = start_match(&mut app).await;
+    let carol = app.register("carol").await;
+    let mut spectator = go_online(&app, &carol).await;
+    let connection_id = format!("{}-socket", carol.id);
+    let match_update = |message| match message {
+        WsMessage::MatchUpdate { state, .. } => Some(state),
+        _ => None,
+    };
+
+    let started = start_spectating(&app.state, &connection_id, &match_id.to_string()).await;
+    assert!(matches!(&started[0], WsMessage::Spectating { spectators: 1, .. }), "{:?}", started);
+    assert!(matches!(&started[1], WsMessage::MatchUpdate { state, .. } if state.current_turn == Some(alice.id)));
+
+    let act = |action: &str| json!({ "match_id": match_id, "action": action });
+    let hit = app.post_ok("/api/v1/pvp/action", &alice, act("attack")).await;
+    let state = next_matching(&mut spectator, match_update).await;
+    assert_eq!(state.match_id, match_id);
+    assert_eq!(state.current_turn, Some(bob.id));
+    assert_eq!(state.player2.hp, hit["opponent_hp_after"].as_i64().unwrap() as i32);
+
+    app.post_ok(&format!("/api/v1/pvp/match/{}/surrender", match_id), &bob, json!({})).await;
+    let state = next_matching(&mut spectator, match_update).await;
+    assert!(state.is_over());
+    assert_eq!(state.winner_id, Some(alice.id));
+
+    // Nothing to watch once it's over
+    let rejected = start_spectating(&app.state, &connection_id, &match_id.to_string()).await;
+    assert!(matches!(&rejected[..], [WsMessage::Error { code, .. }] if code == "MATCH_ENDED"), "{:?}", rejected);
+
+    app.cleanup().await;
+}
+
+/// Leave `player` on 1 HP so the next attack knocks their Titan out
+async fn set_one_hp(app: &TestApp, match_id: Uuid, player: &Player) {
+    sqlx::query(
+        r#"
+        UPDATE pvp_matches SET
+            player1_hp = CASE WHEN player1_id = $2 THEN 1 ELSE player1_hp END,
+            player2_hp = CASE WHEN player2_id = $2 THEN 1 ELSE player2_hp END
+        WHERE id = $1
+        "#,
+    )
+    .bind(match_id)
+    .bind(player.id)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+}
+
+async fn attack(app: &TestApp, match_id: Uuid, player: &Player) -> Value {
+    app.post_ok(
+        "/api/v1/pvp/action",
+        player,
+        json!({ "match_id": match_id, "action": "attack" }),
+    )
+    .await
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_best_of_three_series() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let alice_titan = app.seed_titan("alice", &alice, TitanSeed::default()).await;
+    let bob_titan = app.seed_titan("bob", &bob, TitanSeed::default()).await;
+
+    // Formats are matched separately
+    app.post_ok(
+        "/api/v1/pvp/queue",
+        &alice,
+        json!({ "titan_id": alice_titan, "match_format": "bo3" }),
+    )
+    .await;
+    let single = app
+        .post_ok("/api/v1/pvp/queue", &bob, json!({ "titan_id": bob_titan }))
+        .await;
+    assert_eq!(single["match_found"], false, "{}", single);
+    app.state.services.pvp.leave_queue(bob.id).await.unwrap();
+
+    let matched = app
+        .post_ok(
+            "/api/v1/pvp/queue",
+            &bob,
+            json!({ "titan_id": bob_titan, "match_format": "bo3" }),
+        )
+        .await;
+    assert_eq!(matched["match_found"], true, "{}", matched);
+    let match_id: Uuid = matched["match_id"].as_str().unwrap().parse().unwrap();
+    let uri = format!("/api/v1/pvp/match/{}/titan", match_id);
+    app.post_ok(&uri, &alice, json!({ "titan_id": alice_titan }))
+        .await;
+    let state = app
+        .post_ok(&uri, &bob, json!({ "titan_id": bob_titan }))
+        .await;
+    assert_eq!(state["match_format"], "bo3");
+    assert_eq!(state["round_number"], 1);
+
+    // Round 1 to Alice: both sides are healed and Bob, who lost it, moves first
+    set_one_hp(&app, match_id, &bob).await;
+    let round1 = attack(&app, match_id, &alice).await;
+    assert_eq!(round1["round_ended"], true, "{}", round1);
+    assert_eq!(round1["round_winner_id"], alice.id.to_string());
+    assert_eq!(round1["match_ended"], false);
+    assert_eq!(round1["round_number"], 2);
+    assert_eq!(
+        (
+            round1["my_rounds_won"].clone(),
+            round1["opponent_rounds_won"].clone()
+        ),
+        (json!(1), json!(0))
+    );
+    assert_eq!(round1["my_hp_after"], 100);
+    assert_eq!(round1["opponent_hp_after"], 100);
+
+    let state_uri = format!("/api/v1/pvp/match/{}", match_id);
+    let state = app.get_ok(&state_uri, &bob).await;
+    assert_eq!(state["status"], "active");
+    assert_eq!(state["is_my_turn"], true);
+    assert_eq!(state["round_number"], 2);
+    assert_eq!(state["my_rounds_won"], 0);
+    assert_eq!(state["opponent_rounds_won"], 1);
+
+    // Round 2 to Bob levels the series
+    set_one_hp(&app, match_id, &alice).await;
+    let round2 = attack(&app, match_id, &bob).await;
+    assert_eq!(round2["round_ended"], true, "{}", round2);
+    assert_eq!(round2["match_ended"], false);
+    assert_eq!(round2["round_number"], 3);
+    assert_eq!(
+        (
+            round2["my_rounds_won"].clone(),
+            round2["opponent_rounds_won"].clone()
+        ),
+        (json!(1), json!(1))
+    );
+
+    // Rating is untouched until the series is decided
+    let elo_change: Option<i32> =
+        sqlx::query_scalar("SELECT winner_elo_change FROM pvp_matches WHERE id = $1")
+            .bind(match_id)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(elo_change, None);
+
+    // Round 3 takes it 2-1
+    set_one_hp(&app, match_id, &bob).await;
+    let round3 = attack(&app, match_id, &alice).await;
+    assert_eq!(round3["round_ended"], true, "{}", round3);
+    assert_eq!(round3["match_ended"], true);
+    assert_eq!(round3["winner_id"], alice.id.to_string());
+    assert_eq!(
+        (
+            round3["my_rounds_won"].clone(),
+            round3["opponent_rounds_won"].clone()
+        ),
+        (json!(2), json!(1))
+    );
+
+    let (winner_id, rounds1, rounds2, winner_change): (Uuid, i32, i32, i32) = sqlx::query_as(
+        r#"
+        SELECT winner_id, player1_rounds_won, player2_rounds_won, winner_elo_change
+        FROM pvp_matches WHERE id = $1
+        "#,
+    )
+    .bind(match_id)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(winner_id, alice.id);
+    assert_eq!((rounds1, rounds2), (2, 1));
+    assert_eq!(winner_change, 16);
+
+    let turn_rounds: Vec<i32> = sqlx::query_scalar(
+        "SELECT round_number FROM pvp_battle_turns WHERE match_id = $1 ORDER BY turn_number",
+    )
+    .bind(match_id)
+    .fetch_all(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(turn_rounds, vec![1, 2, 3]);
+
+    app.cleanup().await;
+}
+
+/// Pays everyone but `failing`
+struct SeasonTransfer {
+    failing: String,
+    sent: std::sync::Mutex<Vec<(String, u64)>>,
+}
+
+#[axum::async_trait]
+impl RewardTransfer for SeasonTransfer {
+    async fn transfer(&self, wallet: &str, amount: u64) -> ApiResult<String> {
+        if wallet == self.failing {
+            return Err(AppError::ServiceUnavailable("RPC node unavailable".into()));
+        }
+        self.sent.lock().unwrap().push((wallet.to_string(), amount));
+        Ok(format!("sig-{}", wallet))
+    }
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_season_finalized_and_rewarded_once() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let carol = app.register("carol").await;
+    let pvp = app.state.services.pvp.clone();
+
+    // Carol never played, so she isn't ranked
+    for (player, elo, played) in [(&alice, 1400, 10), (&bob, 1200, 8), (&carol, 1000, 0)] {
+        pvp.get_or_create_stats(player.id).await.unwrap();
+        sqlx::query("UPDATE player_pvp_stats SET elo_rating = $2, matches_played = $3, last_match_at = NOW() WHERE player_id = $1")
+            .bind(player.id)
+            .bind(elo)
+            .bind(played)
+            .execute(&app.pool)
+            .await
+            .unwrap();
+    }
+    let season = app.get_ok("/api/v1/pvp/season", &alice).await;
+    let season_id = season["id"].as_i64().unwrap() as i32;
+
+    // Not over yet
+    let transfer = SeasonTransfer { failing: bob.wallet.clone(), sent: Default::default() };
+    assert!(pvp.seasons_to_finalize().await.unwrap().is_empty());
+    assert!(matches!(pvp.finalize_season(season_id, &transfer).await, Err(AppError::BadRequest(_))));
+
+    sqlx::query("UPDATE pvp_seasons SET end_date = NOW() - INTERVAL '1 minute' WHERE id = $1")
+        .bind(season_id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(pvp.seasons_to_finalize().await.unwrap(), vec![season_id]);
+
+    let summary = pvp.finalize_season(season_id, &transfer).await.unwrap();
+    assert_eq!(summary.ranked, 2);
+    assert_eq!((summary.paid, summary.queued), (1, 1));
+    assert_eq!(*transfer.sent.lock().unwrap(), vec![(alice.wallet.clone(), 5_000_000_000_000)]);
+
+    let rankings: Vec<(Uuid, i32, i64, Option<String>)> = sqlx::query_as(
+        "SELECT player_id, rank, reward_amount, tx_signature FROM pvp_season_rankings WHERE season_id = $1 ORDER BY rank",
+    )
+    .bind(season_id)
+    .fetch_all(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(rankings.len(), 2);
+    assert_eq!((rankings[0].0, rankings[0].1, rankings[0].2), (alice.id, 1, 5_000_000_000_000));
+    assert!(rankings[0].3.is_some());
+    assert_eq!((rankings[1].0, rankings[1].1, rankings[1].2), (bob.id, 2, 2_500_000_000_000));
+    assert!(rankings[1].3.is_none());
+
+    // Bob's reward waits with the other failed rewards
+    let pending = app.get_ok("/api/v1/player/rewards/pending", &bob).await;
+    assert_eq!(pending["rewards"][0]["reason"], "pvp_season", "{}", pending);
+    assert_eq!(pending["total_owed"], 2_500_000_000_000_i64);
+
+    // The next season is open, and finalizing again sends nothing
+    let next = app.get_ok("/api/v1/pvp/season", &alice).await;
+    assert_eq!(next["id"].as_i64().map(|id| id as i32), summary.next_season_id);
+    assert!(pvp.seasons_to_finalize().await.unwrap().is_empty());
+    let again = pvp.finalize_season(season_id, &transfer).await.unwrap();
+    assert_eq!((again.ranked, again.paid, again.queued), (0, 0, 0));
+    assert_eq!(transfer.sent.lock().unwrap().len(), 1);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_challenge_accept_and_reject() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let carol = app.register("carol").await;
+    let alice_titan = app.seed_titan("alice", &alice, TitanSeed::default()).await;
+    let bob_titan = app.seed_titan("bob", &bob, TitanSeed::default()).await;
+
+    let (tx, mut bob_socket) = mpsc::channel(16);
+    app.state.broadcaster.register_client("bob-socket", Some(bob.id), None, tx).await;
+
+    let (status, _) = app
+        .post(&format!("/api/v1/pvp/challenge/{}", alice.id), &alice, json!({ "titan_id": alice_titan }))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Bob hears about the challenge as it's made
+    let challenge_uri = format!("/api/v1/pvp/challenge/{}", bob.id);
+    let challenge = app.post_ok(&challenge_uri, &alice, json!({ "titan_id": alice_titan })).await;
+    assert_eq!(challenge["status"], "pending", "{}", challenge);
+    let challenge_id = challenge["id"].as_str().unwrap().to_string();
+    let (id, challenger_username, titan_id) = next_matching(&mut bob_socket, |message| match message {
+        WsMessage::PvpChallengeReceived { challenge_id, challenger_username, titan_id, .. } => {
+            Some((challenge_id, challenger_username, titan_id))
+        }
+        _ => None,
+    })
+    .await;
+    assert_eq!(id, challenge_id);
+    assert_eq!(challenger_username.as_deref(), Some("alice"));
+    assert_eq!(titan_id, alice_titan.to_string());
+    let (status, _) = app.post(&challenge_uri, &alice, json!({ "titan_id": alice_titan })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Only Bob can answer it, and only once
+    let accept_uri = format!("/api/v1/pvp/challenge/{}/accept", challenge_id);
+    let (status, _) = app.post(&accept_uri, &carol, json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let accepted = app.post_ok(&accept_uri, &bob, json!({})).await;
+    assert_eq!(accepted["status"], "preparing", "{}", accepted);
+    let match_id = accepted["match_id"].as_str().unwrap().to_string();
+    let (status, _) = app.post(&accept_uri, &bob, json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (found, opponent_id) = next_matching(&mut bob_socket, |message| match message {
+        WsMessage::PvpMatchFound { match_id, opponent_id, .. } => Some((match_id, opponent_id)),
+        _ => None,
+    })
+    .await;
+    assert_eq!(found, match_id);
+    assert_eq!(opponent_id, alice.id.to_string());
+
+    // The match plays out like a queued one
+    let uri = format!("/api/v1/pvp/match/{}/titan", match_id);
+    app.post_ok(&uri, &alice, json!({ "titan_id": alice_titan })).await;
+    let started = app.post_ok(&uri, &bob, json!({ "titan_id": bob_titan })).await;
+    assert_eq!(started["status"], "active", "{}", started);
+
+    // Nobody can be challenged mid-match, and a rejected challenge is closed
+    let carol_titan = app.seed_titan("carol", &carol, TitanSeed::default()).await;
+    let (status, _) = app
+        .post(&format!("/api/v1/pvp/challenge/{}", bob.id), &carol, json!({ "titan_id": carol_titan }))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    app.post_ok(&format!("/api/v1/pvp/match/{}/surrender", match_id), &bob, json!({})).await;
+    let challenge = app
+        .post_ok(&format!("/api/v1/pvp/challenge/{}", bob.id), &carol, json!({ "titan_id": carol_titan }))
+        .await;
+    let challenge_id = challenge["id"].as_str().unwrap();
+    app.post_ok(&format!("/api/v1/pvp/challenge/{}/reject", challenge_id), &bob, json!({})).await;
+    let (status, _) = app
+        .post(&format!("/api/v1/pvp/challenge/{}/accept", challenge_id), &bob, json!({}))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // One left unanswered lapses quietly
+    let challenge = app
+        .post_ok(&format!("/api/v1/pvp/challenge/{}", alice.id), &carol, json!({ "titan_id": carol_titan }))
+        .await;
+    let challenge_id: Uuid = challenge["id"].as_str().unwrap().parse().unwrap();
+    sqlx::query("UPDATE pvp_challenges SET expires_at = NOW() - INTERVAL '1 second' WHERE id = $1")
+        .bind(challenge_id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    app.state.services.pvp.run_matchmaking_cycle().await.unwrap();
+    let status: String = sqlx::query_scalar("SELECT status::text FROM pvp_challenges WHERE id = $1")
+        .bind(challenge_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(status, "expired");
+    let (status, _) = app
+        .post(&format!("/api/v1/pvp/challenge/{}/accept", challenge_id), &alice, json!({}))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    app.state.broadcaster.unregister_client("bob-socket").await;
+    app.cleanup().await;
+}
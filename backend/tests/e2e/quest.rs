@@ -0,0 +1,182 @@
+//! Daily quests advanced by gameplay events
+
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use breach_backend::models::{Element, QuestEvent};
+use breach_backend::websocket::WsMessage;
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::harness::{Player, SpawnSeed, TestApp};
+
+const LAT: f64 = 35.681_236;
+const LNG: f64 = 139.767_125;
+
+/// Replace the player's random daily quests with the "Titan Hunter" one
+/// (capture 3 Titans, 150 XP and 10 $BREACH) and return its id
+async fn assign_titan_hunter(app: &TestApp, player: &Player) -> Uuid {
+    app.get_ok("/api/v1/quests", player).await;
+    sqlx::query("DELETE FROM player_quests WHERE player_id = $1")
+        .bind(player.id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    sqlx::query_scalar(
+        r#"
+        INSERT INTO player_quests (player_id, template_id, assigned_date, expires_at)
+        SELECT $1, id, CURRENT_DATE, NOW() + INTERVAL '1 day'
+        FROM quest_templates WHERE title = 'Titan Hunter'
+        RETURNING id
+        "#,
+    )
+    .bind(player.id)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap()
+}
+
+/// Request and confirm a capture of `spawn`
+async fn capture(app: &TestApp, player: &Player, spawn: Uuid) {
+    let location = json!({ "lat": LAT, "lng": LNG, "accuracy": 5.0 });
+    let authorization = app
+        .post_ok(
+            "/api/v1/capture/request",
+            player,
+            json!({ "titan_id": spawn, "player_location": location }),
+        )
+        .await;
+    app.post_ok(
+        "/api/v1/capture/confirm",
+        player,
+        json!({ "titan_id": spawn, "challenge": authorization["challenge"] }),
+    )
+    .await;
+}
+
+/// Next quest message on a socket, skipping others
+async fn next_quest_message(rx: &mut mpsc::Receiver<WsMessage>) -> WsMessage {
+    loop {
+        match tokio::time::timeout(Duration::from_secs(1), rx.recv()).await {
+            Ok(Some(message @ (WsMessage::QuestProgress { .. } | WsMessage::QuestCompleted { .. }))) => {
+                return message
+            }
+            Ok(Some(_)) => continue,
+            other => panic!("expected a quest message, got {:?}", other),
+        }
+    }
+}
+
+fn quest(quests: &Value, quest_id: Uuid) -> &Value {
+    quests
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|quest| quest["id"] == quest_id.to_string())
+        .unwrap_or_else(|| panic!("quest {} missing from {}", quest_id, quests))
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_captures_complete_quest_once() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let quest_id = assign_titan_hunter(&app, &alice).await;
+    let before = app.get_ok("/api/v1/player/me", &alice).await;
+
+    let (tx, mut rx) = mpsc::channel(16);
+    let connection_id = format!("{}-socket", alice.id);
+    app.state.broadcaster.register_client(&connection_id, Some(alice.id), None, tx).await;
+
+    for (i, name) in ["first", "second"].into_iter().enumerate() {
+        let spawn = app.seed_spawn(name, LAT, LNG, SpawnSeed::default()).await;
+        capture(&app, &alice, spawn).await;
+
+        match next_quest_message(&mut rx).await {
+            WsMessage::QuestProgress { quest_id: id, progress, target_count, .. } => {
+                assert_eq!(id, quest_id.to_string());
+                assert_eq!((progress, target_count), (i as i32 + 1, 3));
+            }
+            other => panic!("expected quest_progress, got {:?}", other),
+        }
+        let quests = app.get_ok("/api/v1/quests", &alice).await;
+        assert_eq!(quest(&quests, quest_id)["progress"], i + 1, "{}", quests);
+    }
+
+    let spawn = app.seed_spawn("third", LAT, LNG, SpawnSeed::default()).await;
+    capture(&app, &alice, spawn).await;
+    match next_quest_message(&mut rx).await {
+        WsMessage::QuestCompleted { quest_id: id, xp_earned, breach_earned, .. } => {
+            assert_eq!(id, quest_id.to_string());
+            assert_eq!((xp_earned, breach_earned), (150, 10));
+        }
+        other => panic!("expected quest_completed, got {:?}", other),
+    }
+
+    let quests = app.get_ok("/api/v1/quests", &alice).await;
+    let completed = quest(&quests, quest_id);
+    assert_eq!(completed["progress"], 3, "{}", completed);
+    assert_eq!(completed["is_completed"], true, "{}", completed);
+    assert_eq!(completed["reward_claimed"], true, "{}", completed);
+
+    // Paid once, on completion; later events and claims don't pay again
+    let event = QuestEvent::TitanCaptured { element: Element::Storm, threat_class: 1 };
+    let updates = app.state.services.quest.record_event(alice.id, event).await.unwrap();
+    assert!(updates.is_empty(), "{:?}", updates);
+    let (status, _) = app
+        .post(&format!("/api/v1/quests/{}/claim", quest_id), &alice, json!({}))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let after = app.get_ok("/api/v1/player/me", &alice).await;
+    assert_eq!(
+        after["experience"].as_i64().unwrap() - before["experience"].as_i64().unwrap(),
+        150,
+        "{}",
+        after
+    );
+    assert_eq!(
+        after["breach_earned"].as_i64().unwrap() - before["breach_earned"].as_i64().unwrap(),
+        10,
+        "{}",
+        after
+    );
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_concurrent_events_complete_quest_once() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let quest_id = assign_titan_hunter(&app, &alice).await;
+
+    // Five captures racing for a quest that needs three
+    let event = QuestEvent::TitanCaptured { element: Element::Void, threat_class: 2 };
+    let quest = app.state.services.quest.clone();
+    let tasks: Vec<_> = (0..5)
+        .map(|_| {
+            let quest = quest.clone();
+            let player_id = alice.id;
+            tokio::spawn(async move { quest.record_event(player_id, event).await.unwrap() })
+        })
+        .collect();
+
+    let mut completions = 0;
+    for task in tasks {
+        completions += task.await.unwrap().iter().filter(|update| update.completed).count();
+    }
+    assert_eq!(completions, 1);
+
+    let (progress, completed): (i32, bool) =
+        sqlx::query_as("SELECT progress, is_completed FROM player_quests WHERE id = $1")
+            .bind(quest_id)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!((progress, completed), (3, true));
+
+    app.cleanup().await;
+}
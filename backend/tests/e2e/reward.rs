@@ -0,0 +1,105 @@
+//! Failed reward transfers retried until paid
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use axum::async_trait;
+use breach_backend::error::{ApiResult, AppError};
+use breach_backend::models::RewardReason;
+use breach_backend::services::RewardTransfer;
+use uuid::Uuid;
+
+use crate::harness::TestApp;
+
+/// Fails its first `failures` transfers, then succeeds
+struct FlakyTransfer {
+    failures: u32,
+    calls: AtomicU32,
+    succeeded: AtomicU32,
+}
+
+impl FlakyTransfer {
+    fn new(failures: u32) -> Self {
+        Self { failures, calls: AtomicU32::new(0), succeeded: AtomicU32::new(0) }
+    }
+}
+
+#[async_trait]
+impl RewardTransfer for FlakyTransfer {
+    async fn transfer(&self, _wallet: &str, amount: u64) -> ApiResult<String> {
+        if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+            return Err(AppError::ServiceUnavailable("RPC node unavailable".into()));
+        }
+        self.succeeded.fetch_add(1, Ordering::SeqCst);
+        Ok(format!("sig-{}", amount))
+    }
+}
+
+/// Skip a reward's backoff so the next run retries it
+async fn make_due(app: &TestApp, reward_id: Uuid) {
+    sqlx::query("UPDATE failed_rewards SET next_retry_at = NOW() WHERE id = $1")
+        .bind(reward_id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_failed_reward_retried_until_paid_once() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let rewards = app.state.services.reward.clone();
+
+    let reward = rewards
+        .record_failure(alice.id, 1_000_000_000, RewardReason::Capture, "RPC node unavailable")
+        .await
+        .unwrap();
+    let pending = app.get_ok("/api/v1/player/rewards/pending", &alice).await;
+    assert_eq!(pending["total_owed"], 1_000_000_000_i64, "{}", pending);
+    assert_eq!(pending["rewards"][0]["reason"], "capture");
+    assert_eq!(pending["rewards"][0]["attempts"], 1);
+
+    // Not due yet: backing off from the original failure
+    let transfer = FlakyTransfer::new(2);
+    let summary = rewards.retry_due(&transfer).await.unwrap();
+    assert_eq!(summary.retrying + summary.paid, 0);
+
+    // Two more failures, then the transfer lands
+    for expected_attempts in [2, 3] {
+        make_due(&app, reward.id).await;
+        let summary = rewards.retry_due(&transfer).await.unwrap();
+        assert_eq!(summary.retrying, 1);
+        let pending = app.get_ok("/api/v1/player/rewards/pending", &alice).await;
+        assert_eq!(pending["rewards"][0]["attempts"], expected_attempts, "{}", pending);
+        assert_eq!(pending["rewards"][0]["status"], "pending");
+    }
+    make_due(&app, reward.id).await;
+    let summary = rewards.retry_due(&transfer).await.unwrap();
+    assert_eq!(summary.paid, 1);
+
+    let pending = app.get_ok("/api/v1/player/rewards/pending", &alice).await;
+    assert_eq!(pending["rewards"], serde_json::json!([]), "{}", pending);
+    assert_eq!(pending["total_owed"], 0);
+
+    // Even back in the queue, an entry with a signature is never paid again
+    sqlx::query("UPDATE failed_rewards SET status = 'pending', next_retry_at = NOW() WHERE id = $1")
+        .bind(reward.id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let summary = rewards.retry_due(&transfer).await.unwrap();
+    assert_eq!(summary.paid, 1);
+
+    assert_eq!(transfer.calls.load(Ordering::SeqCst), 3);
+    assert_eq!(transfer.succeeded.load(Ordering::SeqCst), 1);
+    let (attempts, signature): (i32, Option<String>) =
+        sqlx::query_as("SELECT attempts, tx_signature FROM failed_rewards WHERE id = $1")
+            .bind(reward.id)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(attempts, 4);
+    assert_eq!(signature.as_deref(), Some("sig-1000000000"));
+
+    app.cleanup().await;
+}
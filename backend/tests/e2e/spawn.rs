@@ -0,0 +1,179 @@
+//! Regional spawn cycle
+
+use std::collections::HashMap;
+
+use chrono::{Duration, Utc};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::harness::TestApp;
+
+/// Tokyo Station, Osaka Station and Sapporo Station
+const TOKYO: (f64, f64) = (35.681_236, 139.767_125);
+const OSAKA: (f64, f64) = (34.702_485, 135.495_951);
+const SAPPORO: (f64, f64) = (43.068_661, 141.350_755);
+
+fn prefix((lat, lng): (f64, f64)) -> String {
+    geohash::encode(geohash::Coord { x: lng, y: lat }, 5).unwrap()
+}
+
+/// Three POIs near the middle of a region's cell, weighted so they always spawn
+async fn seed_pois(app: &TestApp, region: &str) {
+    let (center, _, _) = geohash::decode(region).unwrap();
+    for i in 0..3 {
+        sqlx::query(
+            r#"
+            INSERT INTO pois (name, category, location_lat, location_lng, radius, spawn_weight)
+            VALUES ($1, 'landmark', $2, $3, 1.0, 50.0)
+            "#,
+        )
+        .bind(format!("{} #{}", region, i))
+        .bind(center.y + i as f64 * 0.001)
+        .bind(center.x)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    }
+}
+
+/// A connection subscribed to a region, as if a player were online there
+async fn go_online(app: &TestApp, region: &str) -> mpsc::Receiver<breach_backend::websocket::WsMessage> {
+    let connection_id = Uuid::new_v4().to_string();
+    let (tx, rx) = mpsc::channel(16);
+    let broadcaster = &app.state.broadcaster;
+    broadcaster.register_client(&connection_id, Some(Uuid::new_v4()), None, tx).await;
+    broadcaster.subscribe(&connection_id, vec![region.to_string()]).await.unwrap();
+    rx
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_regional_cycle_only_spawns_where_players_are() {
+    let app = TestApp::spawn().await;
+    let (tokyo, osaka, sapporo) = (prefix(TOKYO), prefix(OSAKA), prefix(SAPPORO));
+    for region in [&tokyo, &osaka, &sapporo] {
+        seed_pois(&app, region).await;
+    }
+
+    let _tokyo_player = go_online(&app, &tokyo).await;
+    let _osaka_player = go_online(&app, &osaka).await;
+
+    let mut regions = app.state.broadcaster.active_regions().await;
+    regions.sort();
+    let mut expected = vec![tokyo.clone(), osaka.clone()];
+    expected.sort();
+    assert_eq!(regions, expected);
+
+    // As if reloaded with a lower cap
+    app.state.game.write().await.max_spawns_per_region = 2;
+    let spawner = &app.state.services.spawn;
+
+    let spawns = spawner.run_spawn_cycle_for_regions(regions.clone()).await.unwrap();
+    assert_eq!(spawns.len(), 4);
+
+    let per_region: HashMap<String, i64> = sqlx::query_as::<_, (String, i64)>(
+        "SELECT LEFT(geohash, 5), COUNT(*) FROM titan_spawns GROUP BY 1",
+    )
+    .fetch_all(&app.pool)
+    .await
+    .unwrap()
+    .into_iter()
+    .collect();
+    assert_eq!(per_region.get(&tokyo), Some(&2));
+    assert_eq!(per_region.get(&osaka), Some(&2));
+    assert_eq!(per_region.get(&sapporo), None);
+
+    // Both regions are at the cap, so another cycle adds nothing
+    let spawns = spawner.run_spawn_cycle_for_regions(regions).await.unwrap();
+    assert!(spawns.is_empty());
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_full_cycle_respects_geohash_density_cap() {
+    let app = TestApp::spawn().await;
+    let tokyo = prefix(TOKYO);
+    seed_pois(&app, &tokyo).await;
+
+    app.state.game.write().await.max_active_per_geohash = 2;
+    let spawner = &app.state.services.spawn;
+
+    // Three POIs would always spawn, but the cell only takes two
+    spawner.run_spawn_cycle(None).await.unwrap();
+
+    let in_cell: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM titan_spawns WHERE geohash LIKE $1 || '%'")
+        .bind(&tokyo)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(in_cell, 2);
+
+    app.cleanup().await;
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_spawn_event_starts_and_ends() {
+    let app = TestApp::spawn().await;
+    let (tokyo, osaka) = (prefix(TOKYO), prefix(OSAKA));
+    seed_pois(&app, &tokyo).await;
+    seed_pois(&app, &osaka).await;
+    let events = &app.state.services.event_spawn;
+
+    let request = serde_json::from_value(serde_json::json!({
+        "name": "Tokyo Legends",
+        "start_at": Utc::now() + Duration::hours(1),
+        "end_at": Utc::now() + Duration::hours(2),
+        "spawn_config": { "species_pool": [2_409], "threat_class_weights": [0, 0, 0, 0, 1], "max_spawns": 2 },
+        "geohash_filter": [tokyo],
+    }))
+    .unwrap();
+    let event = events.create_event(request, "admin").await.unwrap();
+
+    // Nothing happens before the start time
+    assert!(events.start_due_events().await.unwrap().is_empty());
+
+    sqlx::query("UPDATE spawn_events SET start_at = NOW() - INTERVAL '1 minute' WHERE id = $1")
+        .bind(event.id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let started = events.start_due_events().await.unwrap();
+    assert_eq!(started.len(), 1);
+
+    // Two legendary pool species in Tokyo, none in Osaka
+    let spawns = &started[0].spawns;
+    assert_eq!(spawns.len(), 2);
+    for (titan, _) in spawns {
+        assert!(titan.geohash.starts_with(&tokyo), "{}", titan.geohash);
+        assert_eq!((titan.threat_class, titan.species_id), (5, 2_409));
+        assert_eq!(titan.expires_at, event.end_at);
+    }
+
+    // Claimed once
+    assert!(events.start_due_events().await.unwrap().is_empty());
+
+    // Captured-out Titans aren't expired again
+    sqlx::query("UPDATE titan_spawns SET captured_by = NULL, capture_count = max_captures WHERE id = $1")
+        .bind(spawns[0].0.id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    events.cancel_event(event.id).await.unwrap();
+    let ended = events.end_due_events().await.unwrap();
+    assert_eq!(ended.len(), 1);
+    assert_eq!(ended[0].expired, vec![(spawns[1].0.id, spawns[1].0.geohash.clone())]);
+
+    let active: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM titan_spawns WHERE event_id = $1 AND expires_at > NOW()")
+        .bind(event.id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(active, 0);
+    assert!(events.end_due_events().await.unwrap().is_empty());
+
+    app.cleanup().await;
+}
@@ -0,0 +1,41 @@
+//! Tracked transactions
+
+use axum::http::StatusCode;
+use uuid::Uuid;
+
+use crate::harness::TestApp;
+
+#[tokio::test]
+#[ignore] // Requires Postgres and Redis
+async fn test_tracked_transaction_visible_to_sender_only() {
+    let mut app = TestApp::spawn().await;
+    let alice = app.register("alice").await;
+    let bob = app.register("bob").await;
+    let attempt_id = Uuid::new_v4();
+
+    // As recorded by a capture submit whose send timed out
+    sqlx::query(
+        r#"
+        INSERT INTO pending_transactions
+            (signature, kind, player_id, related_id, mint_address, recent_blockhash)
+        VALUES ('5igSig', 'capture_mint', $1, $2, 'TitanPda', 'Blockhash')
+        "#,
+    )
+    .bind(alice.id)
+    .bind(attempt_id)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let body = app.get_ok("/api/v1/transactions/5igSig", &alice).await;
+    assert_eq!(body["status"], "pending");
+    assert_eq!(body["kind"], "capture_mint");
+    assert_eq!(body["related_id"], attempt_id.to_string());
+    assert_eq!(body["attempts"], 0);
+    assert!(body.get("recent_blockhash").is_none());
+
+    let (status, _) = app.get("/api/v1/transactions/5igSig", &bob).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    app.cleanup().await;
+}
@@ -0,0 +1,160 @@
+//! Schema tests for the queries that can't be checked at compile time
+//!
+//! Everything written with `sqlx::query!` is verified against the migrations
+//! when the crate is built (or by `cargo sqlx prepare --check`). The marketplace
+//! search is assembled with `QueryBuilder`, so these tests run it against a
+//! migrated database and decode every selected column into `ListingRow`.
+//!
+//! These tests require a migrated database in `DATABASE_URL`.
+//! Run with: cargo test --test schema_tests -- --ignored
+
+mod common;
+
+use breach_backend::models::{
+    Element, ListingRow, ListingStatus, ListingType, MarketplaceSearchQuery,
+};
+use breach_backend::services::MarketplaceService;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+async fn connect() -> PgPool {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    PgPool::connect(&url).await.expect("Failed to connect to database")
+}
+
+/// Insert a player, a Titan and an active auction listing; returns (player, listing)
+async fn seed_listing(tx: &mut Transaction<'_, Postgres>) -> (Uuid, Uuid) {
+    let player_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO players (wallet_address, username) VALUES ($1, 'schema_test') RETURNING id",
+    )
+    .bind(common::random_wallet_address())
+    .fetch_one(&mut **tx)
+    .await
+    .unwrap();
+
+    let titan_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO player_titans
+            (player_id, mint_address, species_id, element, threat_class, genes, captured_at, level)
+        VALUES ($1, $2, 7, 'abyssal', 3, '\x010203', NOW(), 12)
+        RETURNING id
+        "#,
+    )
+    .bind(player_id)
+    .bind(common::random_wallet_address())
+    .fetch_one(&mut **tx)
+    .await
+    .unwrap();
+
+    let listing_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO marketplace_listings (seller_id, titan_id, listing_type, price, expires_at)
+        VALUES ($1, $2, 'auction', 5000, NOW() + INTERVAL '1 day')
+        RETURNING id
+        "#,
+    )
+    .bind(player_id)
+    .bind(titan_id)
+    .fetch_one(&mut **tx)
+    .await
+    .unwrap();
+
+    sqlx::query("INSERT INTO listing_favorites (player_id, listing_id) VALUES ($1, $2)")
+        .bind(player_id)
+        .bind(listing_id)
+        .execute(&mut **tx)
+        .await
+        .unwrap();
+
+    (player_id, listing_id)
+}
+
+fn search(sort_by: Option<&str>) -> MarketplaceSearchQuery {
+    MarketplaceSearchQuery {
+        element: Some(Element::Abyssal),
+        min_threat_class: Some(1),
+        max_threat_class: Some(5),
+        min_price: Some(1),
+        max_price: Some(10_000),
+        min_level: Some(10),
+        listing_type: Some(ListingType::Auction),
+        bundle_elements: Vec::new(),
+        min_threat_sum: None,
+        max_threat_sum: None,
+        sort_by: sort_by.map(String::from),
+        limit: 20,
+        offset: 0,
+    }
+}
+
+#[tokio::test]
+#[ignore] // Requires database
+async fn test_search_query_decodes_listing_row() {
+    let pool = connect().await;
+    let mut tx = pool.begin().await.unwrap();
+    let (player_id, listing_id) = seed_listing(&mut tx).await;
+
+    let rows: Vec<ListingRow> = MarketplaceService::search_query(&search(None), Some(player_id))
+        .build_query_as()
+        .fetch_all(&mut *tx)
+        .await
+        .expect("search query no longer matches the schema");
+
+    let row = rows.iter().find(|r| r.id == listing_id).expect("seeded listing not returned");
+    assert_eq!(row.seller_username.as_deref(), Some("schema_test"));
+    assert_eq!(row.listing_type, ListingType::Auction);
+    assert_eq!(row.status, ListingStatus::Active);
+    assert_eq!(row.element, Element::Abyssal);
+    assert_eq!(row.threat_class, 3);
+    assert_eq!(row.species_id, 7);
+    assert_eq!(row.level, 12);
+    assert_eq!(row.genes, vec![1, 2, 3]);
+    assert_eq!(row.current_bid, 0);
+    assert_eq!(row.bid_count, 0);
+    assert!(row.is_favorited);
+
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore] // Requires database
+async fn test_search_query_every_sort_order() {
+    let pool = connect().await;
+    let mut tx = pool.begin().await.unwrap();
+    seed_listing(&mut tx).await;
+
+    for sort_by in [None, Some("price_asc"), Some("price_desc"), Some("ending_soon"), Some("newest")] {
+        MarketplaceService::search_query(&search(sort_by), None)
+            .build_query_as::<ListingRow>()
+            .fetch_all(&mut *tx)
+            .await
+            .unwrap_or_else(|e| panic!("sort {:?} failed: {}", sort_by, e));
+    }
+
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore] // Requires database
+async fn test_search_query_bundle_filters() {
+    let pool = connect().await;
+    let mut tx = pool.begin().await.unwrap();
+    seed_listing(&mut tx).await;
+
+    let query = MarketplaceSearchQuery {
+        listing_type: None,
+        bundle_elements: vec![Element::Abyssal, Element::Storm],
+        min_threat_sum: Some(4),
+        max_threat_sum: Some(20),
+        ..search(None)
+    };
+    let rows: Vec<ListingRow> = MarketplaceService::search_query(&query, None)
+        .build_query_as()
+        .fetch_all(&mut *tx)
+        .await
+        .expect("bundle filters no longer match the schema");
+    // The seeded listing is an auction, not a bundle
+    assert!(rows.is_empty());
+
+    tx.rollback().await.unwrap();
+}
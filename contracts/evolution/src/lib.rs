@@ -0,0 +1,154 @@
+//! BREACH Titan evolution paths
+//!
+//! Which species a Titan may evolve into. Shared by the Titan NFT `evolve`
+//! instruction and the backend's transaction builder, so the backend refuses
+//! to build any evolution the program would reject.
+//!
+//! Species IDs are `element * 1000 + (threat_class - 1) * 100 + variant` with
+//! variants 1-10. Evolving keeps the element, raises the threat class by one
+//! and keeps the variant or follows one of the element's branches. Apex
+//! (class 5) species only appear in the wild.
+//!
+//! Dependency-free and `no_std` so on-chain programs can use it directly.
+
+#![no_std]
+
+/// Minimum Titan level to evolve
+pub const MIN_LEVEL: u8 = 30;
+
+/// Highest threat class reachable by evolving
+pub const MAX_EVOLVED_CLASS: u8 = 4;
+
+/// Variant branches per element, indexed by element: `(from, to)` lets a
+/// variant evolve into `to` as well as into itself
+const BRANCHES: [&[(u8, u8)]; 6] = [
+    &[(1, 3), (4, 7)],  // Abyssal
+    &[(2, 10), (5, 6)], // Volcanic
+    &[(3, 8)],          // Storm
+    &[(1, 2), (6, 9)],  // Void
+    &[(4, 6)],          // Parasitic
+    &[(7, 4)],          // Ossified
+];
+
+/// Why an evolution is refused
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EvolutionError {
+    /// Titan is below `MIN_LEVEL`
+    LevelTooLow,
+    /// Target species is not on the Titan's evolution path
+    InvalidTarget,
+}
+
+/// Species ID split into `(element, threat_class, variant)`, `None` for IDs
+/// outside the species scheme
+pub const fn decode(species_id: u16) -> Option<(u8, u8, u8)> {
+    let element = species_id / 1000;
+    let threat_class = species_id % 1000 / 100 + 1;
+    let variant = species_id % 100;
+
+    if element > 5 || threat_class > 5 || variant < 1 || variant > 10 {
+        return None;
+    }
+    Some((element as u8, threat_class as u8, variant as u8))
+}
+
+/// Species ID for `(element, threat_class, variant)`
+pub const fn encode(element: u8, threat_class: u8, variant: u8) -> u16 {
+    element as u16 * 1000 + (threat_class as u16 - 1) * 100 + variant as u16
+}
+
+/// Whether a Titan of `species_id` and `element` may evolve into `target_id`
+pub fn is_valid_target(species_id: u16, element: u8, target_id: u16) -> bool {
+    let (Some(from), Some(to)) = (decode(species_id), decode(target_id)) else {
+        return false;
+    };
+    let (from_element, from_class, from_variant) = from;
+    let (to_element, to_class, to_variant) = to;
+
+    from_element == element
+        && to_element == element
+        && to_class == from_class + 1
+        && to_class <= MAX_EVOLVED_CLASS
+        && (to_variant == from_variant
+            || BRANCHES[element as usize].contains(&(from_variant, to_variant)))
+}
+
+/// Every species a Titan of `species_id` and `element` may evolve into
+pub fn targets(species_id: u16, element: u8) -> impl Iterator<Item = u16> {
+    let class = decode(species_id).map_or(0, |(_, class, _)| class);
+    (1..=10u8)
+        .map(move |variant| encode(element.min(5), (class + 1).clamp(1, 5), variant))
+        .filter(move |&target| is_valid_target(species_id, element, target))
+}
+
+/// Check a Titan's evolution into `target_id`
+pub fn check(species_id: u16, element: u8, level: u8, target_id: u16) -> Result<(), EvolutionError> {
+    if level < MIN_LEVEL {
+        return Err(EvolutionError::LevelTooLow);
+    }
+    if !is_valid_target(species_id, element, target_id) {
+        return Err(EvolutionError::InvalidTarget);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_roundtrip() {
+        assert_eq!(decode(3_409), Some((3, 5, 9)));
+        assert_eq!(encode(3, 5, 9), 3_409);
+        assert_eq!(decode(1_001), Some((1, 1, 1)));
+
+        assert_eq!(decode(6_001), None);
+        assert_eq!(decode(1_000), None);
+        assert_eq!(decode(1_011), None);
+        assert_eq!(decode(1_501), None);
+    }
+
+    #[test]
+    fn test_same_variant_next_class() {
+        // Volcanic Pioneer variant 4 -> Volcanic Hunter variant 4
+        assert!(is_valid_target(1_004, 1, 1_104));
+        assert_eq!(check(1_004, 1, 30, 1_104), Ok(()));
+
+        // Skipping a class or staying in it is not evolving
+        assert!(!is_valid_target(1_004, 1, 1_204));
+        assert!(!is_valid_target(1_004, 1, 1_004));
+    }
+
+    #[test]
+    fn test_branches() {
+        // Abyssal variant 1 branches to variant 3
+        assert!(is_valid_target(1, 0, 103));
+        assert!(!is_valid_target(1, 0, 105));
+
+        let mut targets = targets(1, 0);
+        assert_eq!(targets.next(), Some(101));
+        assert_eq!(targets.next(), Some(103));
+        assert_eq!(targets.next(), None);
+    }
+
+    #[test]
+    fn test_element_must_match() {
+        assert!(!is_valid_target(1_004, 1, 2_104));
+        // Titan element disagrees with its species
+        assert!(!is_valid_target(1_004, 2, 1_104));
+    }
+
+    #[test]
+    fn test_apex_is_wild_only() {
+        assert!(is_valid_target(2_204, 2, 2_304));
+        assert!(!is_valid_target(2_304, 2, 2_404));
+        assert_eq!(targets(2_404, 2).count(), 0);
+        assert_eq!(targets(2_304, 2).count(), 0);
+    }
+
+    #[test]
+    fn test_check_level() {
+        assert_eq!(check(1_004, 1, 29, 1_104), Err(EvolutionError::LevelTooLow));
+        assert_eq!(check(1_004, 1, 30, 5_104), Err(EvolutionError::InvalidTarget));
+    }
+}
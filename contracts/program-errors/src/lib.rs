@@ -0,0 +1,299 @@
+//! BREACH program error catalog
+//!
+//! Numeric codes, names and descriptions for every custom error returned by
+//! the Titan NFT (6000-6999) and Game Logic (7000-7999) programs. Shared by
+//! the programs, the backend error classifier and `GET /api/v1/errors/catalog`.
+//!
+//! Dependency-free and `no_std` so on-chain programs can use it directly.
+
+#![no_std]
+
+/// Program that owns an error code
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Program {
+    TitanNft,
+    GameLogic,
+}
+
+impl Program {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Program::TitanNft => "titan_nft",
+            Program::GameLogic => "game_logic",
+        }
+    }
+}
+
+/// One catalog entry
+///
+/// Clients localize with the key `program_error.<program>.<name>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ErrorInfo {
+    pub program: Program,
+    pub code: u32,
+    /// Variant name in the program's `error.rs`
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+impl ErrorInfo {
+    pub const fn new(program: Program, code: u32, name: &'static str, description: &'static str) -> Self {
+        Self { program, code, name, description }
+    }
+}
+
+/// Every program's errors
+pub const CATALOG: &[&[ErrorInfo]] = &[titan_nft::ERRORS, game_logic::ERRORS];
+
+/// Look up a code in a program's catalog
+pub fn lookup(program: Program, code: u32) -> Option<&'static ErrorInfo> {
+    match program {
+        Program::TitanNft => find_in(titan_nft::ERRORS, code),
+        Program::GameLogic => find_in(game_logic::ERRORS, code),
+    }
+}
+
+/// Look up a code across all programs (code ranges do not overlap)
+pub fn find(code: u32) -> Option<&'static ErrorInfo> {
+    CATALOG.iter().find_map(|errors| find_in(errors, code))
+}
+
+fn find_in(errors: &'static [ErrorInfo], code: u32) -> Option<&'static ErrorInfo> {
+    errors.iter().find(|e| e.code == code)
+}
+
+/// Titan NFT error codes and catalog
+pub mod titan_nft {
+    use super::{ErrorInfo, Program};
+
+    pub const UNAUTHORIZED: u32 = 6000;
+    pub const INVALID_CAPTURE_AUTHORITY: u32 = 6001;
+    pub const NOT_OWNER: u32 = 6002;
+    pub const INVALID_AUTHORITY: u32 = 6003;
+    pub const PROGRAM_PAUSED: u32 = 6100;
+    pub const ALREADY_INITIALIZED: u32 = 6101;
+    pub const NOT_INITIALIZED: u32 = 6102;
+    pub const CAPTURE_COOLDOWN: u32 = 6200;
+    pub const MAX_TITANS_REACHED: u32 = 6201;
+    pub const INVALID_CAPTURE_PROOF: u32 = 6202;
+    pub const INVALID_LOCATION: u32 = 6203;
+    pub const INVALID_BATCH_SIZE: u32 = 6204;
+    pub const BATCH_ACCOUNT_MISMATCH: u32 = 6205;
+    pub const INVALID_THREAT_CLASS: u32 = 6300;
+    pub const INVALID_ELEMENT_TYPE: u32 = 6301;
+    pub const MAX_LEVEL_REACHED: u32 = 6302;
+    pub const INSUFFICIENT_EXPERIENCE: u32 = 6303;
+    pub const CANNOT_EVOLVE: u32 = 6304;
+    pub const INVALID_SPECIES_ID: u32 = 6305;
+    pub const TITAN_LISTED: u32 = 6306;
+    pub const INVALID_EVOLUTION_TARGET: u32 = 6307;
+    pub const CANNOT_FUSE_WITH_SELF: u32 = 6400;
+    pub const LEVEL_TOO_LOW_FOR_FUSION: u32 = 6401;
+    pub const ELEMENT_MISMATCH: u32 = 6402;
+    pub const FUSION_OWNER_MISMATCH: u32 = 6403;
+    pub const INSUFFICIENT_BALANCE: u32 = 6500;
+    pub const TRANSFER_FAILED: u32 = 6501;
+    pub const INVALID_MINT: u32 = 6502;
+    pub const INVALID_ACCOUNT_DATA: u32 = 6600;
+    pub const ACCOUNT_DATA_TOO_SMALL: u32 = 6601;
+    pub const INVALID_SEEDS: u32 = 6602;
+    pub const INVALID_PROGRAM_ID: u32 = 6603;
+
+    /// Every Titan NFT error, in code order
+    pub const ERRORS: &[ErrorInfo] = &[
+        ErrorInfo::new(Program::TitanNft, UNAUTHORIZED, "Unauthorized", "Not authorized to perform this action"),
+        ErrorInfo::new(Program::TitanNft, INVALID_CAPTURE_AUTHORITY, "InvalidCaptureAuthority", "Invalid capture authority signer"),
+        ErrorInfo::new(Program::TitanNft, NOT_OWNER, "NotOwner", "Not the owner of this Titan"),
+        ErrorInfo::new(Program::TitanNft, INVALID_AUTHORITY, "InvalidAuthority", "Invalid admin authority"),
+        ErrorInfo::new(Program::TitanNft, PROGRAM_PAUSED, "ProgramPaused", "Program is currently paused"),
+        ErrorInfo::new(Program::TitanNft, ALREADY_INITIALIZED, "AlreadyInitialized", "Account already initialized"),
+        ErrorInfo::new(Program::TitanNft, NOT_INITIALIZED, "NotInitialized", "Account not initialized"),
+        ErrorInfo::new(Program::TitanNft, CAPTURE_COOLDOWN, "CaptureCooldown", "Capture cooldown not elapsed"),
+        ErrorInfo::new(Program::TitanNft, MAX_TITANS_REACHED, "MaxTitansReached", "Maximum Titans per wallet reached"),
+        ErrorInfo::new(Program::TitanNft, INVALID_CAPTURE_PROOF, "InvalidCaptureProof", "Invalid capture proof/signature"),
+        ErrorInfo::new(Program::TitanNft, INVALID_LOCATION, "InvalidLocation", "Invalid capture location"),
+        ErrorInfo::new(Program::TitanNft, INVALID_BATCH_SIZE, "InvalidBatchSize", "Batch mint size must be 1-5 and match the instruction data"),
+        ErrorInfo::new(Program::TitanNft, BATCH_ACCOUNT_MISMATCH, "BatchAccountMismatch", "Batch mint Titan accounts don't match the batch size"),
+        ErrorInfo::new(Program::TitanNft, INVALID_THREAT_CLASS, "InvalidThreatClass", "Invalid threat class (must be 1-5)"),
+        ErrorInfo::new(Program::TitanNft, INVALID_ELEMENT_TYPE, "InvalidElementType", "Invalid element type (must be 0-5)"),
+        ErrorInfo::new(Program::TitanNft, MAX_LEVEL_REACHED, "MaxLevelReached", "Maximum level reached"),
+        ErrorInfo::new(Program::TitanNft, INSUFFICIENT_EXPERIENCE, "InsufficientExperience", "Insufficient experience for level up"),
+        ErrorInfo::new(Program::TitanNft, CANNOT_EVOLVE, "CannotEvolve", "Cannot evolve this Titan"),
+        ErrorInfo::new(Program::TitanNft, INVALID_SPECIES_ID, "InvalidSpeciesId", "Invalid species ID"),
+        ErrorInfo::new(Program::TitanNft, TITAN_LISTED, "TitanListed", "Titan is listed on the marketplace"),
+        ErrorInfo::new(Program::TitanNft, INVALID_EVOLUTION_TARGET, "InvalidEvolutionTarget", "Species is not on the Titan's evolution path"),
+        ErrorInfo::new(Program::TitanNft, CANNOT_FUSE_WITH_SELF, "CannotFuseWithSelf", "Cannot fuse Titan with itself"),
+        ErrorInfo::new(Program::TitanNft, LEVEL_TOO_LOW_FOR_FUSION, "LevelTooLowForFusion", "Titan level too low for fusion"),
+        ErrorInfo::new(Program::TitanNft, ELEMENT_MISMATCH, "ElementMismatch", "Element type mismatch for fusion"),
+        ErrorInfo::new(Program::TitanNft, FUSION_OWNER_MISMATCH, "FusionOwnerMismatch", "Both Titans must be owned by same player"),
+        ErrorInfo::new(Program::TitanNft, INSUFFICIENT_BALANCE, "InsufficientBalance", "Insufficient $BREACH balance"),
+        ErrorInfo::new(Program::TitanNft, TRANSFER_FAILED, "TransferFailed", "Token transfer failed"),
+        ErrorInfo::new(Program::TitanNft, INVALID_MINT, "InvalidMint", "Invalid token mint"),
+        ErrorInfo::new(Program::TitanNft, INVALID_ACCOUNT_DATA, "InvalidAccountData", "Invalid account data"),
+        ErrorInfo::new(Program::TitanNft, ACCOUNT_DATA_TOO_SMALL, "AccountDataTooSmall", "Account data too small"),
+        ErrorInfo::new(Program::TitanNft, INVALID_SEEDS, "InvalidSeeds", "Invalid PDA seeds"),
+        ErrorInfo::new(Program::TitanNft, INVALID_PROGRAM_ID, "InvalidProgramId", "Invalid program ID"),
+    ];
+
+    /// Description for a code, if known
+    pub fn describe(code: u32) -> Option<&'static str> {
+        super::find_in(ERRORS, code).map(|e| e.description)
+    }
+}
+
+/// Game Logic error codes and catalog
+pub mod game_logic {
+    use super::{ErrorInfo, Program};
+
+    pub const UNAUTHORIZED: u32 = 7000;
+    pub const INVALID_BACKEND_AUTHORITY: u32 = 7001;
+    pub const NOT_OWNER: u32 = 7002;
+    pub const INVALID_AUTHORITY: u32 = 7003;
+    pub const PROGRAM_PAUSED: u32 = 7100;
+    pub const ALREADY_INITIALIZED: u32 = 7101;
+    pub const NOT_INITIALIZED: u32 = 7102;
+    pub const INVALID_CONFIG: u32 = 7103;
+    pub const INVALID_BATTLE_SIGNATURE: u32 = 7200;
+    pub const BATTLE_ALREADY_RECORDED: u32 = 7201;
+    pub const INVALID_OPPONENT: u32 = 7202;
+    pub const CANNOT_BATTLE_SELF: u32 = 7203;
+    pub const BATTLE_COOLDOWN: u32 = 7204;
+    pub const INVALID_CAPTURE_SIGNATURE: u32 = 7300;
+    pub const CAPTURE_ALREADY_RECORDED: u32 = 7301;
+    pub const INVALID_CAPTURE_LOCATION: u32 = 7302;
+    pub const CAPTURE_EXPIRED: u32 = 7303;
+    pub const INVALID_NONCE: u32 = 7304;
+    pub const INVALID_EXPERIENCE_AMOUNT: u32 = 7400;
+    pub const EXPERIENCE_OVERFLOW: u32 = 7401;
+    pub const INVALID_REWARD_AMOUNT: u32 = 7500;
+    pub const INSUFFICIENT_REWARD_POOL: u32 = 7501;
+    pub const REWARD_ALREADY_CLAIMED: u32 = 7502;
+    pub const REWARD_OVERFLOW: u32 = 7503;
+    pub const NO_REWARDS_TO_CLAIM: u32 = 7504;
+    pub const INVALID_ACCOUNT_DATA: u32 = 7600;
+    pub const ACCOUNT_DATA_TOO_SMALL: u32 = 7601;
+    pub const INVALID_SEEDS: u32 = 7602;
+    pub const INVALID_PROGRAM_ID: u32 = 7603;
+    pub const CPI_CALL_FAILED: u32 = 7604;
+    pub const INVALID_TITAN_PROGRAM: u32 = 7605;
+    pub const LISTING_NOT_ACTIVE: u32 = 7700;
+    pub const LISTING_EXPIRED: u32 = 7701;
+    pub const LISTING_NOT_EXPIRED: u32 = 7702;
+    pub const LISTING_ALREADY_EXISTS: u32 = 7703;
+    pub const INVALID_LISTING_PRICE: u32 = 7704;
+    pub const INVALID_LISTING_DURATION: u32 = 7705;
+    pub const CANNOT_BUY_OWN_LISTING: u32 = 7706;
+    pub const LISTING_PRICE_MISMATCH: u32 = 7707;
+    pub const INVALID_TOKEN_ACCOUNT: u32 = 7708;
+    pub const INVALID_MARKETPLACE_FEE: u32 = 7709;
+
+    /// Every Game Logic error, in code order
+    pub const ERRORS: &[ErrorInfo] = &[
+        ErrorInfo::new(Program::GameLogic, UNAUTHORIZED, "Unauthorized", "Not authorized to perform this action"),
+        ErrorInfo::new(Program::GameLogic, INVALID_BACKEND_AUTHORITY, "InvalidBackendAuthority", "Invalid backend authority signer"),
+        ErrorInfo::new(Program::GameLogic, NOT_OWNER, "NotOwner", "Not the owner of this Titan"),
+        ErrorInfo::new(Program::GameLogic, INVALID_AUTHORITY, "InvalidAuthority", "Invalid admin authority"),
+        ErrorInfo::new(Program::GameLogic, PROGRAM_PAUSED, "ProgramPaused", "Program is currently paused"),
+        ErrorInfo::new(Program::GameLogic, ALREADY_INITIALIZED, "AlreadyInitialized", "Account already initialized"),
+        ErrorInfo::new(Program::GameLogic, NOT_INITIALIZED, "NotInitialized", "Account not initialized"),
+        ErrorInfo::new(Program::GameLogic, INVALID_CONFIG, "InvalidConfig", "Invalid config account"),
+        ErrorInfo::new(Program::GameLogic, INVALID_BATTLE_SIGNATURE, "InvalidBattleSignature", "Invalid battle signature from backend"),
+        ErrorInfo::new(Program::GameLogic, BATTLE_ALREADY_RECORDED, "BattleAlreadyRecorded", "Battle already recorded"),
+        ErrorInfo::new(Program::GameLogic, INVALID_OPPONENT, "InvalidOpponent", "Invalid opponent titan"),
+        ErrorInfo::new(Program::GameLogic, CANNOT_BATTLE_SELF, "CannotBattleSelf", "Cannot battle own titan"),
+        ErrorInfo::new(Program::GameLogic, BATTLE_COOLDOWN, "BattleCooldown", "Battle cooldown not elapsed"),
+        ErrorInfo::new(Program::GameLogic, INVALID_CAPTURE_SIGNATURE, "InvalidCaptureSignature", "Invalid capture signature from backend"),
+        ErrorInfo::new(Program::GameLogic, CAPTURE_ALREADY_RECORDED, "CaptureAlreadyRecorded", "Capture already recorded"),
+        ErrorInfo::new(Program::GameLogic, INVALID_CAPTURE_LOCATION, "InvalidCaptureLocation", "Invalid capture location"),
+        ErrorInfo::new(Program::GameLogic, CAPTURE_EXPIRED, "CaptureExpired", "Capture timestamp too old"),
+        ErrorInfo::new(Program::GameLogic, INVALID_NONCE, "InvalidNonce", "Capture nonce does not match the player's on-chain nonce (replay)"),
+        ErrorInfo::new(Program::GameLogic, INVALID_EXPERIENCE_AMOUNT, "InvalidExperienceAmount", "Invalid experience amount"),
+        ErrorInfo::new(Program::GameLogic, EXPERIENCE_OVERFLOW, "ExperienceOverflow", "Experience overflow"),
+        ErrorInfo::new(Program::GameLogic, INVALID_REWARD_AMOUNT, "InvalidRewardAmount", "Invalid reward amount"),
+        ErrorInfo::new(Program::GameLogic, INSUFFICIENT_REWARD_POOL, "InsufficientRewardPool", "Insufficient reward pool"),
+        ErrorInfo::new(Program::GameLogic, REWARD_ALREADY_CLAIMED, "RewardAlreadyClaimed", "Reward already claimed"),
+        ErrorInfo::new(Program::GameLogic, REWARD_OVERFLOW, "RewardOverflow", "Pending reward balance overflow"),
+        ErrorInfo::new(Program::GameLogic, NO_REWARDS_TO_CLAIM, "NoRewardsToClaim", "No pending rewards to claim"),
+        ErrorInfo::new(Program::GameLogic, INVALID_ACCOUNT_DATA, "InvalidAccountData", "Invalid account data"),
+        ErrorInfo::new(Program::GameLogic, ACCOUNT_DATA_TOO_SMALL, "AccountDataTooSmall", "Account data too small"),
+        ErrorInfo::new(Program::GameLogic, INVALID_SEEDS, "InvalidSeeds", "Invalid PDA seeds"),
+        ErrorInfo::new(Program::GameLogic, INVALID_PROGRAM_ID, "InvalidProgramId", "Invalid program ID"),
+        ErrorInfo::new(Program::GameLogic, CPI_CALL_FAILED, "CpiCallFailed", "CPI call failed"),
+        ErrorInfo::new(Program::GameLogic, INVALID_TITAN_PROGRAM, "InvalidTitanProgram", "Invalid Titan NFT Program ID"),
+        ErrorInfo::new(Program::GameLogic, LISTING_NOT_ACTIVE, "ListingNotActive", "Listing does not exist or was already settled"),
+        ErrorInfo::new(Program::GameLogic, LISTING_EXPIRED, "ListingExpired", "Listing has expired"),
+        ErrorInfo::new(Program::GameLogic, LISTING_NOT_EXPIRED, "ListingNotExpired", "Listing has not expired yet"),
+        ErrorInfo::new(Program::GameLogic, LISTING_ALREADY_EXISTS, "ListingAlreadyExists", "Titan is already listed"),
+        ErrorInfo::new(Program::GameLogic, INVALID_LISTING_PRICE, "InvalidListingPrice", "Invalid listing price"),
+        ErrorInfo::new(Program::GameLogic, INVALID_LISTING_DURATION, "InvalidListingDuration", "Invalid listing duration"),
+        ErrorInfo::new(Program::GameLogic, CANNOT_BUY_OWN_LISTING, "CannotBuyOwnListing", "Cannot buy own listing"),
+        ErrorInfo::new(Program::GameLogic, LISTING_PRICE_MISMATCH, "ListingPriceMismatch", "Listing price does not match expected price"),
+        ErrorInfo::new(Program::GameLogic, INVALID_TOKEN_ACCOUNT, "InvalidTokenAccount", "Token account mint or owner mismatch"),
+        ErrorInfo::new(Program::GameLogic, INVALID_MARKETPLACE_FEE, "InvalidMarketplaceFee", "Invalid marketplace fee configuration"),
+    ];
+
+    /// Description for a code, if known
+    pub fn describe(code: u32) -> Option<&'static str> {
+        super::find_in(ERRORS, code).map(|e| e.description)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let info = lookup(Program::GameLogic, game_logic::LISTING_EXPIRED).unwrap();
+        assert_eq!(info.name, "ListingExpired");
+        assert_eq!(info.description, "Listing has expired");
+
+        assert!(lookup(Program::TitanNft, game_logic::LISTING_EXPIRED).is_none());
+        assert_eq!(find(titan_nft::TITAN_LISTED).unwrap().program, Program::TitanNft);
+        assert!(find(0).is_none());
+    }
+
+    /// `(Name, code)` for every `Name = code,` variant in an `error.rs`
+    fn program_variants(source: &'static str) -> impl Iterator<Item = (&'static str, u32)> {
+        let body = source.split("pub enum").nth(1).unwrap().split("\n}").next().unwrap();
+        body.lines().filter_map(|line| {
+            let (name, code) = line.trim().strip_suffix(',')?.split_once('=')?;
+            Some((name.trim(), code.trim().parse().ok()?))
+        })
+    }
+
+    /// Every variant in each program's `error.rs` must be in the catalog, and
+    /// vice versa, so the catalog can never drift from the programs.
+    #[test]
+    fn test_catalog_matches_programs() {
+        let programs = [
+            (include_str!("../../programs/titan_nft/src/error.rs"), titan_nft::ERRORS),
+            (include_str!("../../programs/game_logic/src/error.rs"), game_logic::ERRORS),
+        ];
+
+        for (source, errors) in programs {
+            let mut count = 0;
+            for (name, code) in program_variants(source) {
+                let entry = errors.iter().find(|e| e.name == name);
+                assert_eq!(entry.map(|e| e.code), Some(code), "{} missing or wrong in catalog", name);
+                count += 1;
+            }
+            assert!(count > 0);
+            assert_eq!(count, errors.len(), "catalog has entries not in error.rs");
+        }
+    }
+
+    #[test]
+    fn test_codes_unique_and_in_range() {
+        for (errors, range) in [(titan_nft::ERRORS, 6000..7000), (game_logic::ERRORS, 7000..8000)] {
+            for (i, a) in errors.iter().enumerate() {
+                assert!(range.contains(&a.code), "{} out of range", a.name);
+                for b in &errors[i + 1..] {
+                    assert_ne!(a.code, b.code);
+                    assert_ne!(a.name, b.name);
+                }
+            }
+        }
+    }
+}
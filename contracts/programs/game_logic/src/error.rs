@@ -2,6 +2,9 @@
 
 use pinocchio::program_error::ProgramError;
 
+// Every variant must have an entry in `breach_program_errors::game_logic`
+// (checked by the catalog crate tests).
+
 /// Game Logic program errors
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u32)]
@@ -65,6 +68,9 @@ pub enum GameError {
     /// Capture timestamp too old
     CaptureExpired = 7303,
     
+    /// Capture nonce does not match the player's on-chain nonce (replay)
+    InvalidNonce = 7304,
+    
     // ═══════════ Experience (7400-7499) ═══════════
     
     /// Invalid experience amount
@@ -84,6 +90,12 @@ pub enum GameError {
     /// Reward already claimed
     RewardAlreadyClaimed = 7502,
     
+    /// Pending reward balance overflow
+    RewardOverflow = 7503,
+    
+    /// No pending rewards to claim
+    NoRewardsToClaim = 7504,
+    
     // ═══════════ Account (7600-7699) ═══════════
     
     /// Invalid account data
@@ -103,6 +115,38 @@ pub enum GameError {
     
     /// Invalid Titan NFT Program ID
     InvalidTitanProgram = 7605,
+    
+    // ═══════════ Marketplace (7700-7799) ═══════════
+    
+    /// Listing does not exist or was already settled
+    ListingNotActive = 7700,
+    
+    /// Listing has expired
+    ListingExpired = 7701,
+    
+    /// Listing has not expired yet
+    ListingNotExpired = 7702,
+    
+    /// Titan is already listed
+    ListingAlreadyExists = 7703,
+    
+    /// Invalid listing price
+    InvalidListingPrice = 7704,
+    
+    /// Invalid listing duration
+    InvalidListingDuration = 7705,
+    
+    /// Cannot buy own listing
+    CannotBuyOwnListing = 7706,
+    
+    /// Listing price does not match expected price
+    ListingPriceMismatch = 7707,
+    
+    /// Token account mint or owner mismatch
+    InvalidTokenAccount = 7708,
+    
+    /// Invalid marketplace fee configuration
+    InvalidMarketplaceFee = 7709,
 }
 
 impl From<GameError> for ProgramError {
@@ -112,37 +156,8 @@ impl From<GameError> for ProgramError {
 }
 
 impl GameError {
-    /// Get error message
+    /// Get error message (from the shared error catalog)
     pub fn message(&self) -> &'static str {
-        match self {
-            Self::Unauthorized => "Not authorized to perform this action",
-            Self::InvalidBackendAuthority => "Invalid backend authority signer",
-            Self::NotOwner => "Not the owner of this Titan",
-            Self::InvalidAuthority => "Invalid admin authority",
-            Self::ProgramPaused => "Program is currently paused",
-            Self::AlreadyInitialized => "Account already initialized",
-            Self::NotInitialized => "Account not initialized",
-            Self::InvalidConfig => "Invalid config account",
-            Self::InvalidBattleSignature => "Invalid battle signature from backend",
-            Self::BattleAlreadyRecorded => "Battle already recorded",
-            Self::InvalidOpponent => "Invalid opponent titan",
-            Self::CannotBattleSelf => "Cannot battle own titan",
-            Self::BattleCooldown => "Battle cooldown not elapsed",
-            Self::InvalidCaptureSignature => "Invalid capture signature from backend",
-            Self::CaptureAlreadyRecorded => "Capture already recorded",
-            Self::InvalidCaptureLocation => "Invalid capture location",
-            Self::CaptureExpired => "Capture timestamp too old",
-            Self::InvalidExperienceAmount => "Invalid experience amount",
-            Self::ExperienceOverflow => "Experience overflow",
-            Self::InvalidRewardAmount => "Invalid reward amount",
-            Self::InsufficientRewardPool => "Insufficient reward pool",
-            Self::RewardAlreadyClaimed => "Reward already claimed",
-            Self::InvalidAccountData => "Invalid account data",
-            Self::AccountDataTooSmall => "Account data too small",
-            Self::InvalidSeeds => "Invalid PDA seeds",
-            Self::InvalidProgramId => "Invalid program ID",
-            Self::CpiCallFailed => "CPI call failed",
-            Self::InvalidTitanProgram => "Invalid Titan NFT Program ID",
-        }
+        breach_program_errors::game_logic::describe(*self as u32).unwrap_or("Unknown error")
     }
 }
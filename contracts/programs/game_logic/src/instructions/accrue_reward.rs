@@ -0,0 +1,128 @@
+//! Accrue Reward instruction
+//!
+//! Adds a $BREACH reward to the player's pending balance instead of
+//! transferring it; the player collects the balance with claim_rewards.
+
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvars::{clock::Clock, rent::Rent, Sysvar},
+    ProgramResult,
+};
+use pinocchio_system::instructions::CreateAccount;
+
+use super::distribute_reward::{final_reward_amount, DistributeRewardData};
+use crate::error::GameError;
+use crate::state::{GameConfig, RewardLedger};
+
+/// Process accrue reward instruction
+///
+/// Data: same layout as distribute_reward (`DistributeRewardData`).
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    // Parse accounts
+    let [
+        backend_authority,  // [0] Signer, backend authority (pays ledger rent)
+        config_account,     // [1] Config PDA
+        player,             // [2] Player wallet
+        reward_ledger,      // [3] Reward ledger PDA
+        _system_program,    // [4] System program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Verify backend authority is signer
+    if !backend_authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Load config
+    let config_data = config_account.try_borrow_data()?;
+    let config = GameConfig::from_account_data(&config_data)?;
+
+    // Check if paused
+    if config.paused {
+        return Err(GameError::ProgramPaused.into());
+    }
+
+    // Verify backend authority
+    if backend_authority.key() != &config.backend_authority {
+        return Err(GameError::InvalidBackendAuthority.into());
+    }
+    drop(config_data);
+
+    // Parse instruction data
+    if data.len() < core::mem::size_of::<DistributeRewardData>() {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let reward_data = unsafe { &*(data.as_ptr() as *const DistributeRewardData) };
+
+    // Validate reward amount
+    if reward_data.amount == 0 {
+        return Err(GameError::InvalidRewardAmount.into());
+    }
+
+    let final_amount = final_reward_amount(reward_data.reward_type, reward_data.amount)?;
+
+    // Verify ledger PDA
+    let (expected_pda, bump) = pinocchio::pubkey::find_program_address(
+        &[RewardLedger::SEED, player.key().as_ref()],
+        program_id,
+    );
+    if reward_ledger.key() != &expected_pda {
+        return Err(GameError::InvalidSeeds.into());
+    }
+
+    // Create on first reward
+    if reward_ledger.lamports() == 0 {
+        let rent = Rent::get()?;
+        let rent_lamports = rent.minimum_balance(RewardLedger::SIZE);
+
+        let bump_seed = [bump];
+        let signer_seeds: [Seed; 3] = [
+            Seed::from(RewardLedger::SEED),
+            Seed::from(player.key().as_ref()),
+            Seed::from(&bump_seed),
+        ];
+        let signer = Signer::from(&signer_seeds);
+
+        CreateAccount {
+            from: backend_authority,
+            to: reward_ledger,
+            lamports: rent_lamports,
+            space: RewardLedger::SIZE as u64,
+            owner: program_id,
+        }
+        .invoke_signed(&[signer])?;
+
+        let mut data = reward_ledger.try_borrow_mut_data()?;
+        let ledger = RewardLedger::from_account_data_mut(&mut data)?;
+        ledger.discriminator = RewardLedger::DISCRIMINATOR;
+        ledger.player = *player.key();
+        ledger.accrued_amount = 0;
+        ledger.last_accrual_ts = 0;
+        ledger.total_claimed = 0;
+        ledger.bump = bump;
+    }
+
+    if !reward_ledger.is_owned_by(program_id) {
+        return Err(GameError::InvalidAccountData.into());
+    }
+
+    let clock = Clock::get()?;
+    let mut data = reward_ledger.try_borrow_mut_data()?;
+    let ledger = RewardLedger::from_account_data_mut(&mut data)?;
+
+    if ledger.discriminator != RewardLedger::DISCRIMINATOR || ledger.player != *player.key() {
+        return Err(GameError::InvalidAccountData.into());
+    }
+
+    ledger.accrue(final_amount, clock.unix_timestamp)?;
+
+    Ok(())
+}
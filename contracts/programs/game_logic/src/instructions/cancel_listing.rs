@@ -0,0 +1,98 @@
+//! Cancel Listing instruction
+//!
+//! Returns an escrowed Titan to its seller and closes the Listing PDA.
+//! The seller can cancel at any time; anyone can clear an expired listing.
+
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+
+use super::escrow;
+use crate::error::GameError;
+use crate::state::Listing;
+use crate::TITAN_NFT_PROGRAM_ID;
+
+/// Process cancel listing instruction
+///
+/// Accounts:
+/// 0. `[signer]` Authority (seller, or anyone once the listing has expired)
+/// 1. `[writable]` Seller wallet (receives Titan and rent refund)
+/// 2. `[writable]` Listing PDA
+/// 3. `[writable]` Titan data PDA (from Titan NFT Program)
+/// 4. `[]` Titan NFT Global config PDA
+/// 5. `[writable]` Seller player PDA (from Titan NFT Program)
+/// 6. `[]` Titan NFT Program
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    // Parse accounts
+    let [
+        authority,       // [0] Signer
+        seller,          // [1] Seller wallet
+        listing_account, // [2] Listing PDA
+        titan_account,   // [3] Titan PDA
+        titan_config,    // [4] Titan NFT Global config PDA
+        seller_player,   // [5] Seller player PDA
+        titan_program,   // [6] Titan NFT Program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Verify signer
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Verify Titan NFT Program ID
+    if titan_program.key() != &TITAN_NFT_PROGRAM_ID {
+        return Err(GameError::InvalidTitanProgram.into());
+    }
+
+    // Load listing
+    if !listing_account.is_owned_by(program_id) {
+        return Err(GameError::ListingNotActive.into());
+    }
+    let listing_data = listing_account.try_borrow_data()?;
+    let listing = Listing::from_account_data(&listing_data)
+        .map_err(|_| ProgramError::from(GameError::ListingNotActive))?;
+    let titan_id = listing.titan_id;
+    let listing_seller = listing.seller;
+    let expires_at = listing.expires_at;
+    drop(listing_data);
+
+    let bump = escrow::check_listing_pda(program_id, listing_account, titan_id)?;
+
+    // Verify seller
+    if seller.key() != &listing_seller {
+        return Err(GameError::Unauthorized.into());
+    }
+
+    // Only the seller may cancel an active listing
+    if authority.key() != &listing_seller {
+        let clock = Clock::get()?;
+        if clock.unix_timestamp <= expires_at {
+            return Err(GameError::ListingNotExpired.into());
+        }
+    }
+
+    // Clear the listed flag, then hand the Titan back
+    escrow::set_listed(listing_account, titan_config, titan_account, titan_id, bump, false)?;
+    escrow::transfer_titan(
+        listing_account,
+        seller,
+        titan_config,
+        titan_account,
+        listing_account,
+        seller_player,
+        Some((titan_id, bump)),
+    )?;
+
+    // Close listing
+    escrow::close_listing(listing_account, seller)
+}
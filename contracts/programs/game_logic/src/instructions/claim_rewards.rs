@@ -0,0 +1,98 @@
+//! Claim Rewards instruction
+//!
+//! Pays out the player's pending reward balance from the reward pool in a
+//! single transfer signed by the config PDA.
+
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    ProgramResult,
+};
+use pinocchio_token::instructions::Transfer;
+
+use crate::error::GameError;
+use crate::state::{GameConfig, RewardLedger};
+
+/// Process claim rewards instruction (no instruction data)
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    // Parse accounts
+    let [
+        player,                // [0] Signer, player
+        config_account,        // [1] Config PDA (reward pool authority)
+        reward_ledger,         // [2] Reward ledger PDA
+        reward_pool,           // [3] Reward pool token account
+        player_token_account,  // [4] Player's token account
+        _token_program,        // [5] SPL Token Program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Verify player is signer
+    if !player.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Load config
+    let config_data = config_account.try_borrow_data()?;
+    let config = GameConfig::from_account_data(&config_data)?;
+
+    // Check if paused
+    if config.paused {
+        return Err(GameError::ProgramPaused.into());
+    }
+
+    // Verify reward pool
+    if reward_pool.key() != &config.reward_pool {
+        return Err(GameError::InvalidAccountData.into());
+    }
+
+    let config_bump = config.bump;
+    drop(config_data);
+
+    // Verify ledger
+    if !reward_ledger.is_owned_by(program_id) {
+        return Err(GameError::InvalidAccountData.into());
+    }
+
+    let mut ledger_data = reward_ledger.try_borrow_mut_data()?;
+    let ledger = RewardLedger::from_account_data_mut(&mut ledger_data)?;
+
+    if ledger.discriminator != RewardLedger::DISCRIMINATOR || ledger.player != *player.key() {
+        return Err(GameError::InvalidAccountData.into());
+    }
+
+    // Empty the ledger before paying out; a failed transfer reverts both
+    let amount = ledger.take_claim()?;
+    drop(ledger_data);
+
+    // Transfer the whole balance, signed by the config PDA
+    let bump_seed = [config_bump];
+    let signer_seeds: [Seed; 2] = [
+        Seed::from(GameConfig::SEED),
+        Seed::from(&bump_seed),
+    ];
+
+    Transfer {
+        from: reward_pool,
+        to: player_token_account,
+        authority: config_account,
+        amount,
+    }
+    .invoke_signed(&[Signer::from(&signer_seeds)])?;
+
+    // Update config stats
+    let mut config_data = config_account.try_borrow_mut_data()?;
+    let config = GameConfig::from_account_data_mut(&mut config_data)?;
+    config.total_rewards_distributed = config
+        .total_rewards_distributed
+        .checked_add(amount)
+        .ok_or(GameError::RewardOverflow)?;
+
+    Ok(())
+}
@@ -30,6 +30,17 @@ pub enum RewardType {
     DailyBonus = 2,
 }
 
+/// Apply the reward type multiplier to a base amount
+pub fn final_reward_amount(reward_type: u8, amount: u64) -> Result<u64, GameError> {
+    let multiplier = match reward_type {
+        0 => 1, // Capture reward
+        1 => 2, // Battle win reward (2x)
+        2 => 5, // Daily bonus (5x)
+        _ => return Err(GameError::InvalidRewardAmount),
+    };
+    amount.checked_mul(multiplier).ok_or(GameError::RewardOverflow)
+}
+
 /// Process distribute reward instruction
 pub fn process(
     _program_id: &Pubkey,
@@ -83,12 +94,7 @@ pub fn process(
     }
 
     // Calculate final reward based on type
-    let final_amount = match reward_data.reward_type {
-        0 => reward_data.amount, // Capture reward
-        1 => reward_data.amount * 2, // Battle win reward (2x)
-        2 => reward_data.amount * 5, // Daily bonus (5x)
-        _ => return Err(GameError::InvalidRewardAmount.into()),
-    };
+    let final_amount = final_reward_amount(reward_data.reward_type, reward_data.amount)?;
 
     // Transfer tokens from reward pool to player
     // Note: This requires the config PDA to have authority over the reward pool
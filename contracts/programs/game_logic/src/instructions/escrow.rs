@@ -0,0 +1,254 @@
+//! Shared helpers for the marketplace escrow instructions
+//!
+//! The Listing PDA takes ownership of the Titan in the Titan NFT Program while
+//! a listing is active, so every hand-off is a CPI signed with the PDA seeds.
+
+use pinocchio::{
+    account_info::AccountInfo,
+    cpi,
+    instruction::{AccountMeta, Instruction, Seed, Signer},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    ProgramResult,
+};
+use pinocchio_token::state::TokenAccount;
+
+use crate::error::GameError;
+use crate::state::Listing;
+use crate::TITAN_NFT_PROGRAM_ID;
+
+/// Transfer instruction discriminator in Titan NFT Program
+const TITAN_TRANSFER_DISCRIMINATOR: u8 = 5;
+
+/// Set listed instruction discriminator in Titan NFT Program
+const TITAN_SET_LISTED_DISCRIMINATOR: u8 = 9;
+
+// Titan NFT account layouts (packed, see titan_nft::state)
+const TITAN_DATA_SIZE: usize = 150;
+const TITAN_ID_OFFSET: usize = 8;
+const TITAN_OWNER_OFFSET: usize = 76;
+const TITAN_LISTED_OFFSET: usize = 134;
+const TITAN_CONFIG_SIZE: usize = 182;
+const TITAN_CONFIG_DISCRIMINATOR: [u8; 8] = *b"BREACHCF";
+const TITAN_CONFIG_TREASURY_OFFSET: usize = 40;
+const TITAN_CONFIG_MARKETPLACE_FEE_OFFSET: usize = 138;
+
+/// Titan fields the escrow cares about
+pub struct TitanInfo {
+    pub titan_id: u64,
+    pub owner: Pubkey,
+    pub listed: bool,
+}
+
+/// Read Titan id, owner and listed flag from a Titan NFT data account
+pub fn read_titan(titan_account: &AccountInfo) -> Result<TitanInfo, ProgramError> {
+    if !titan_account.is_owned_by(&TITAN_NFT_PROGRAM_ID) {
+        return Err(GameError::InvalidTitanProgram.into());
+    }
+
+    let data = titan_account.try_borrow_data()?;
+    if data.len() < TITAN_DATA_SIZE {
+        return Err(GameError::AccountDataTooSmall.into());
+    }
+
+    let mut id_bytes = [0u8; 8];
+    id_bytes.copy_from_slice(&data[TITAN_ID_OFFSET..TITAN_ID_OFFSET + 8]);
+    let mut owner = [0u8; 32];
+    owner.copy_from_slice(&data[TITAN_OWNER_OFFSET..TITAN_OWNER_OFFSET + 32]);
+
+    Ok(TitanInfo {
+        titan_id: u64::from_le_bytes(id_bytes),
+        owner,
+        listed: data[TITAN_LISTED_OFFSET] != 0,
+    })
+}
+
+/// Read (treasury, marketplace_fee_bps) from the Titan NFT global config
+pub fn read_marketplace_fee(titan_config: &AccountInfo) -> Result<(Pubkey, u16), ProgramError> {
+    if !titan_config.is_owned_by(&TITAN_NFT_PROGRAM_ID) {
+        return Err(GameError::InvalidTitanProgram.into());
+    }
+
+    let data = titan_config.try_borrow_data()?;
+    if data.len() < TITAN_CONFIG_SIZE || data[0..8] != TITAN_CONFIG_DISCRIMINATOR {
+        return Err(GameError::InvalidConfig.into());
+    }
+
+    let mut treasury = [0u8; 32];
+    treasury.copy_from_slice(&data[TITAN_CONFIG_TREASURY_OFFSET..TITAN_CONFIG_TREASURY_OFFSET + 32]);
+    let fee_bps = u16::from_le_bytes([
+        data[TITAN_CONFIG_MARKETPLACE_FEE_OFFSET],
+        data[TITAN_CONFIG_MARKETPLACE_FEE_OFFSET + 1],
+    ]);
+
+    Ok((treasury, fee_bps))
+}
+
+/// Verify a token account holds `mint` and is owned by `owner`
+pub fn check_token_account(
+    token_account: &AccountInfo,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> ProgramResult {
+    let account = TokenAccount::from_account_info(token_account)
+        .map_err(|_| ProgramError::from(GameError::InvalidTokenAccount))?;
+
+    if account.mint() != mint || account.owner() != owner {
+        return Err(GameError::InvalidTokenAccount.into());
+    }
+
+    Ok(())
+}
+
+/// Verify the listing account is the PDA for `titan_id`, returning the bump
+pub fn check_listing_pda(
+    program_id: &Pubkey,
+    listing_account: &AccountInfo,
+    titan_id: u64,
+) -> Result<u8, ProgramError> {
+    let (expected, bump) = pinocchio::pubkey::find_program_address(
+        &[Listing::SEED, &titan_id.to_le_bytes()],
+        program_id,
+    );
+
+    if listing_account.key() != &expected {
+        return Err(GameError::InvalidSeeds.into());
+    }
+
+    Ok(bump)
+}
+
+/// CPI to Titan NFT `set_listed`, signed by the Listing PDA
+pub fn set_listed(
+    listing_account: &AccountInfo,
+    titan_config: &AccountInfo,
+    titan_account: &AccountInfo,
+    titan_id: u64,
+    bump: u8,
+    listed: bool,
+) -> ProgramResult {
+    let data = [TITAN_SET_LISTED_DISCRIMINATOR, listed as u8];
+
+    let titan_id_bytes = titan_id.to_le_bytes();
+    let bump_seed = [bump];
+    let signer_seeds: [Seed; 3] = [
+        Seed::from(Listing::SEED),
+        Seed::from(titan_id_bytes.as_slice()),
+        Seed::from(&bump_seed),
+    ];
+
+    cpi::invoke_signed(
+        &Instruction {
+            program_id: &TITAN_NFT_PROGRAM_ID,
+            accounts: &[
+                AccountMeta {
+                    pubkey: listing_account.key(),
+                    is_signer: true,
+                    is_writable: false,
+                },
+                AccountMeta {
+                    pubkey: titan_config.key(),
+                    is_signer: false,
+                    is_writable: false,
+                },
+                AccountMeta {
+                    pubkey: titan_account.key(),
+                    is_signer: false,
+                    is_writable: true,
+                },
+            ],
+            data: &data,
+        },
+        &[listing_account, titan_config, titan_account],
+        &[Signer::from(&signer_seeds)],
+    )
+}
+
+/// CPI to Titan NFT `transfer`
+///
+/// When `from_owner` is the Listing PDA, pass `Some((titan_id, bump))` so the
+/// call is signed with the listing seeds.
+pub fn transfer_titan(
+    from_owner: &AccountInfo,
+    to_owner: &AccountInfo,
+    titan_config: &AccountInfo,
+    titan_account: &AccountInfo,
+    from_player: &AccountInfo,
+    to_player: &AccountInfo,
+    listing_signer: Option<(u64, u8)>,
+) -> ProgramResult {
+    let data = [TITAN_TRANSFER_DISCRIMINATOR];
+
+    let instruction = Instruction {
+        program_id: &TITAN_NFT_PROGRAM_ID,
+        accounts: &[
+            AccountMeta {
+                pubkey: from_owner.key(),
+                is_signer: true,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: to_owner.key(),
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: titan_config.key(),
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: titan_account.key(),
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: from_player.key(),
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: to_player.key(),
+                is_signer: false,
+                is_writable: true,
+            },
+        ],
+        data: &data,
+    };
+    let account_infos = [
+        from_owner,
+        to_owner,
+        titan_config,
+        titan_account,
+        from_player,
+        to_player,
+    ];
+
+    match listing_signer {
+        Some((titan_id, bump)) => {
+            let titan_id_bytes = titan_id.to_le_bytes();
+            let bump_seed = [bump];
+            let signer_seeds: [Seed; 3] = [
+                Seed::from(Listing::SEED),
+                Seed::from(titan_id_bytes.as_slice()),
+                Seed::from(&bump_seed),
+            ];
+            cpi::invoke_signed(&instruction, &account_infos, &[Signer::from(&signer_seeds)])
+        }
+        None => cpi::invoke(&instruction, &account_infos),
+    }
+}
+
+/// Close the listing account, refunding rent to `destination`
+pub fn close_listing(listing_account: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+    {
+        let mut data = listing_account.try_borrow_mut_data()?;
+        data.fill(0);
+    }
+
+    let lamports = listing_account.lamports();
+    *destination.try_borrow_mut_lamports()? += lamports;
+    *listing_account.try_borrow_mut_lamports()? = 0;
+
+    Ok(())
+}
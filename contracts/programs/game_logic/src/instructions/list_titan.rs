@@ -0,0 +1,174 @@
+//! List Titan instruction
+//!
+//! Moves a Titan into a Listing PDA escrow at a fixed price
+
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvars::{clock::Clock, rent::Rent, Sysvar},
+    ProgramResult,
+};
+use pinocchio_system::instructions::CreateAccount;
+
+use super::escrow;
+use crate::error::GameError;
+use crate::state::{GameConfig, Listing};
+use crate::TITAN_NFT_PROGRAM_ID;
+
+/// List Titan instruction data
+#[repr(C, packed)]
+pub struct ListTitanData {
+    /// Titan ID to list
+    pub titan_id: u64,
+    /// Asking price in $BREACH (with decimals)
+    pub price: u64,
+    /// Listing duration in seconds
+    pub duration_seconds: i64,
+}
+
+/// Process list titan instruction
+///
+/// Accounts:
+/// 0. `[signer, writable]` Seller (current Titan owner, pays listing rent)
+/// 1. `[]` Game config PDA
+/// 2. `[writable]` Listing PDA (to be created)
+/// 3. `[writable]` Titan data PDA (from Titan NFT Program)
+/// 4. `[]` Titan NFT Global config PDA
+/// 5. `[writable]` Seller player PDA (from Titan NFT Program)
+/// 6. `[]` Titan NFT Program
+/// 7. `[]` System Program
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    // Parse accounts
+    let [
+        seller,          // [0] Signer, seller wallet
+        config_account,  // [1] Game config PDA
+        listing_account, // [2] Listing PDA (to be created)
+        titan_account,   // [3] Titan PDA
+        titan_config,    // [4] Titan NFT Global config PDA
+        seller_player,   // [5] Seller player PDA
+        titan_program,   // [6] Titan NFT Program
+        _system_program, // [7] System Program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Verify signer
+    if !seller.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Verify Titan NFT Program ID
+    if titan_program.key() != &TITAN_NFT_PROGRAM_ID {
+        return Err(GameError::InvalidTitanProgram.into());
+    }
+
+    // Check if paused
+    let config_data = config_account.try_borrow_data()?;
+    let config = GameConfig::from_account_data(&config_data)?;
+    if config.paused {
+        return Err(GameError::ProgramPaused.into());
+    }
+    drop(config_data);
+
+    // Parse instruction data
+    if data.len() < core::mem::size_of::<ListTitanData>() {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let list_data = unsafe { &*(data.as_ptr() as *const ListTitanData) };
+    let titan_id = list_data.titan_id;
+    let price = list_data.price;
+    let duration_seconds = list_data.duration_seconds;
+
+    // Validate price and duration
+    if price == 0 {
+        return Err(GameError::InvalidListingPrice.into());
+    }
+    if duration_seconds <= 0 || duration_seconds > Listing::MAX_DURATION_SECONDS {
+        return Err(GameError::InvalidListingDuration.into());
+    }
+
+    // Verify Titan ownership
+    let titan = escrow::read_titan(titan_account)?;
+    if titan.titan_id != titan_id {
+        return Err(GameError::InvalidAccountData.into());
+    }
+    if titan.owner != *seller.key() {
+        return Err(GameError::NotOwner.into());
+    }
+    if titan.listed {
+        return Err(GameError::ListingAlreadyExists.into());
+    }
+
+    // Derive listing PDA
+    let bump = escrow::check_listing_pda(program_id, listing_account, titan_id)?;
+
+    // Check if already listed
+    let listing_data = listing_account.try_borrow_data()?;
+    let already_listed = listing_data.len() >= 8
+        && listing_data[0..8] == Listing::DISCRIMINATOR;
+    drop(listing_data);
+
+    if already_listed {
+        return Err(GameError::ListingAlreadyExists.into());
+    }
+
+    // Create listing account
+    if listing_account.lamports() == 0 {
+        let rent = Rent::get()?;
+        let rent_lamports = rent.minimum_balance(Listing::SIZE);
+
+        let titan_id_bytes = titan_id.to_le_bytes();
+        let bump_seed = [bump];
+        let signer_seeds: [Seed; 3] = [
+            Seed::from(Listing::SEED),
+            Seed::from(titan_id_bytes.as_slice()),
+            Seed::from(&bump_seed),
+        ];
+        let signer = Signer::from(&signer_seeds);
+
+        CreateAccount {
+            from: seller,
+            to: listing_account,
+            lamports: rent_lamports,
+            space: Listing::SIZE as u64,
+            owner: program_id,
+        }
+        .invoke_signed(&[signer])?;
+    }
+
+    // Move Titan into escrow (seller signed the outer transaction)
+    escrow::transfer_titan(
+        seller,
+        listing_account,
+        titan_config,
+        titan_account,
+        seller_player,
+        listing_account,
+        None,
+    )?;
+
+    // Mark Titan as listed (signed by the listing PDA, now the owner)
+    escrow::set_listed(listing_account, titan_config, titan_account, titan_id, bump, true)?;
+
+    // Initialize listing
+    let clock = Clock::get()?;
+
+    let mut listing_data = listing_account.try_borrow_mut_data()?;
+    let listing = Listing::from_account_data_mut(&mut listing_data)?;
+
+    listing.discriminator = Listing::DISCRIMINATOR;
+    listing.titan_id = titan_id;
+    listing.seller = *seller.key();
+    listing.price = price;
+    listing.created_at = clock.unix_timestamp;
+    listing.expires_at = clock.unix_timestamp + duration_seconds;
+    listing.bump = bump;
+
+    Ok(())
+}
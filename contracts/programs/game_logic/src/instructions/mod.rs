@@ -8,3 +8,9 @@ pub mod distribute_reward;
 pub mod update_config;
 pub mod set_paused;
 pub mod force_update_authority;
+pub mod escrow;
+pub mod list_titan;
+pub mod cancel_listing;
+pub mod purchase_listing;
+pub mod accrue_reward;
+pub mod claim_rewards;
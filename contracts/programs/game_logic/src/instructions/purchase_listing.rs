@@ -0,0 +1,169 @@
+//! Purchase Listing instruction
+//!
+//! Pays the seller (minus marketplace fee to treasury) in $BREACH, releases the
+//! escrowed Titan to the buyer and closes the Listing PDA.
+
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_token::instructions::Transfer;
+
+use super::escrow;
+use crate::error::GameError;
+use crate::state::{split_sale_price, GameConfig, Listing};
+use crate::TITAN_NFT_PROGRAM_ID;
+
+/// Purchase listing instruction data
+#[repr(C, packed)]
+pub struct PurchaseListingData {
+    /// Price the buyer agreed to pay (must match the listing)
+    pub expected_price: u64,
+}
+
+/// Process purchase listing instruction
+///
+/// Accounts:
+/// 0. `[signer, writable]` Buyer wallet
+/// 1. `[writable]` Seller wallet (receives rent refund)
+/// 2. `[]` Game config PDA
+/// 3. `[writable]` Listing PDA
+/// 4. `[writable]` Titan data PDA (from Titan NFT Program)
+/// 5. `[]` Titan NFT Global config PDA (fee rate and treasury)
+/// 6. `[writable]` Buyer player PDA (from Titan NFT Program)
+/// 7. `[writable]` Buyer $BREACH token account
+/// 8. `[writable]` Seller $BREACH token account
+/// 9. `[writable]` Treasury $BREACH token account
+/// 10. `[]` SPL Token Program
+/// 11. `[]` Titan NFT Program
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    // Parse accounts
+    let [
+        buyer,           // [0] Signer, buyer wallet
+        seller,          // [1] Seller wallet
+        config_account,  // [2] Game config PDA
+        listing_account, // [3] Listing PDA
+        titan_account,   // [4] Titan PDA
+        titan_config,    // [5] Titan NFT Global config PDA
+        buyer_player,    // [6] Buyer player PDA
+        buyer_token,     // [7] Buyer token account
+        seller_token,    // [8] Seller token account
+        treasury_token,  // [9] Treasury token account
+        token_program,   // [10] SPL Token Program
+        titan_program,   // [11] Titan NFT Program
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Verify signer
+    if !buyer.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Verify program IDs
+    if titan_program.key() != &TITAN_NFT_PROGRAM_ID {
+        return Err(GameError::InvalidTitanProgram.into());
+    }
+    if token_program.key() != &pinocchio_token::ID {
+        return Err(GameError::InvalidProgramId.into());
+    }
+
+    // Load config
+    let config_data = config_account.try_borrow_data()?;
+    let config = GameConfig::from_account_data(&config_data)?;
+
+    // Check if paused
+    if config.paused {
+        return Err(GameError::ProgramPaused.into());
+    }
+    let breach_mint = config.breach_mint;
+    drop(config_data);
+
+    // Parse instruction data
+    if data.len() < core::mem::size_of::<PurchaseListingData>() {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let purchase_data = unsafe { &*(data.as_ptr() as *const PurchaseListingData) };
+    let expected_price = purchase_data.expected_price;
+
+    // Load listing (a settled listing is closed, so a second purchase fails here)
+    if !listing_account.is_owned_by(program_id) {
+        return Err(GameError::ListingNotActive.into());
+    }
+    let listing_data = listing_account.try_borrow_data()?;
+    let listing = Listing::from_account_data(&listing_data)
+        .map_err(|_| ProgramError::from(GameError::ListingNotActive))?;
+    let titan_id = listing.titan_id;
+    let listing_seller = listing.seller;
+    let price = listing.price;
+    let expired = listing.is_expired(Clock::get()?.unix_timestamp);
+    drop(listing_data);
+
+    let bump = escrow::check_listing_pda(program_id, listing_account, titan_id)?;
+
+    // Validate parties, expiry and price
+    if seller.key() != &listing_seller {
+        return Err(GameError::Unauthorized.into());
+    }
+    if buyer.key() == &listing_seller {
+        return Err(GameError::CannotBuyOwnListing.into());
+    }
+    if expired {
+        return Err(GameError::ListingExpired.into());
+    }
+    if price != expected_price {
+        return Err(GameError::ListingPriceMismatch.into());
+    }
+
+    // Fee rate and treasury come from the Titan NFT global config
+    let (treasury, fee_bps) = escrow::read_marketplace_fee(titan_config)?;
+    let (fee, seller_amount) = split_sale_price(price, fee_bps)
+        .ok_or(GameError::InvalidMarketplaceFee)?;
+
+    // Verify token accounts
+    escrow::check_token_account(buyer_token, &breach_mint, buyer.key())?;
+    escrow::check_token_account(seller_token, &breach_mint, &listing_seller)?;
+    escrow::check_token_account(treasury_token, &breach_mint, &treasury)?;
+
+    // Pay seller
+    Transfer {
+        from: buyer_token,
+        to: seller_token,
+        authority: buyer,
+        amount: seller_amount,
+    }
+    .invoke()?;
+
+    // Pay marketplace fee
+    if fee > 0 {
+        Transfer {
+            from: buyer_token,
+            to: treasury_token,
+            authority: buyer,
+            amount: fee,
+        }
+        .invoke()?;
+    }
+
+    // Release Titan to buyer
+    escrow::set_listed(listing_account, titan_config, titan_account, titan_id, bump, false)?;
+    escrow::transfer_titan(
+        listing_account,
+        buyer,
+        titan_config,
+        titan_account,
+        listing_account,
+        buyer_player,
+        Some((titan_id, bump)),
+    )?;
+
+    // Close listing, rent goes back to the seller
+    escrow::close_listing(listing_account, seller)
+}
@@ -11,7 +11,7 @@ use pinocchio::{
 use pinocchio_system::instructions::CreateAccount;
 
 use crate::error::GameError;
-use crate::state::{CaptureRecord, GameConfig};
+use crate::state::{CaptureRecord, GameConfig, PlayerCaptureState};
 
 /// Record capture instruction data
 #[repr(C, packed)]
@@ -28,6 +28,8 @@ pub struct RecordCaptureData {
     pub element_type: u8,
     /// Backend signature timestamp
     pub signature_timestamp: i64,
+    /// Expected player capture nonce (must equal the on-chain value)
+    pub nonce: u64,
 }
 
 /// Process record capture instruction
@@ -43,6 +45,7 @@ pub fn process(
         config_account,    // [2] Config PDA
         capture_record,    // [3] Capture record PDA (to be created)
         _system_program,   // [4] System Program
+        capture_state,     // [5] Player capture state PDA (created on first capture)
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
@@ -91,6 +94,9 @@ pub fn process(
         return Err(GameError::InvalidAccountData.into());
     }
 
+    // Check and consume the player's capture nonce
+    consume_nonce(program_id, player, capture_state, capture_data.nonce)?;
+
     // Get capture ID from config (total_captures + 1)
     let capture_id = config.total_captures + 1;
 
@@ -178,3 +184,72 @@ pub fn process(
 
     Ok(())
 }
+
+/// Verify the instruction nonce against the player's capture state and
+/// increment it, creating the state account on the player's first capture.
+/// Runs inside the transaction, so a replay of the same payload fails with
+/// `InvalidNonce`.
+fn consume_nonce(
+    program_id: &Pubkey,
+    player: &AccountInfo,
+    capture_state: &AccountInfo,
+    expected_nonce: u64,
+) -> ProgramResult {
+    let (expected_pda, bump) = pinocchio::pubkey::find_program_address(
+        &[PlayerCaptureState::SEED, player.key().as_ref()],
+        program_id,
+    );
+
+    if capture_state.key() != &expected_pda {
+        return Err(GameError::InvalidSeeds.into());
+    }
+
+    // Create on first capture
+    if capture_state.lamports() == 0 {
+        let rent = Rent::get()?;
+        let rent_lamports = rent.minimum_balance(PlayerCaptureState::SIZE);
+
+        let bump_seed = [bump];
+        let signer_seeds: [Seed; 3] = [
+            Seed::from(PlayerCaptureState::SEED),
+            Seed::from(player.key().as_ref()),
+            Seed::from(&bump_seed),
+        ];
+        let signer = Signer::from(&signer_seeds);
+
+        CreateAccount {
+            from: player,
+            to: capture_state,
+            lamports: rent_lamports,
+            space: PlayerCaptureState::SIZE as u64,
+            owner: program_id,
+        }
+        .invoke_signed(&[signer])?;
+
+        let mut data = capture_state.try_borrow_mut_data()?;
+        let state = PlayerCaptureState::from_account_data_mut(&mut data)?;
+        state.discriminator = PlayerCaptureState::DISCRIMINATOR;
+        state.player = *player.key();
+        state.nonce = 0;
+        state.bump = bump;
+    }
+
+    if !capture_state.is_owned_by(program_id) {
+        return Err(GameError::InvalidAccountData.into());
+    }
+
+    let mut data = capture_state.try_borrow_mut_data()?;
+    let state = PlayerCaptureState::from_account_data_mut(&mut data)?;
+
+    if state.discriminator != PlayerCaptureState::DISCRIMINATOR || state.player != *player.key() {
+        return Err(GameError::InvalidAccountData.into());
+    }
+
+    if state.nonce != expected_nonce {
+        return Err(GameError::InvalidNonce.into());
+    }
+
+    state.nonce += 1;
+
+    Ok(())
+}
@@ -1,6 +1,7 @@
 //! BREACH Game Logic Program
 //! 
-//! Handles capture validation, battle records, experience distribution, and rewards.
+//! Handles capture validation, battle records, experience distribution, rewards,
+//! and the marketplace escrow for Titan listings.
 //! Interacts with the Titan NFT Program via CPI.
 
 use pinocchio::{
@@ -46,6 +47,13 @@ pub fn process_instruction(
         5 => update_config::process(program_id, accounts, data),
         6 => set_paused::process(program_id, accounts, data),
         7 => force_update_authority::process(program_id, accounts, data),
+        // === Marketplace Escrow Instructions ===
+        8 => list_titan::process(program_id, accounts, data),
+        9 => cancel_listing::process(program_id, accounts, data),
+        10 => purchase_listing::process(program_id, accounts, data),
+        // === Reward Ledger Instructions ===
+        11 => accrue_reward::process(program_id, accounts, data),
+        12 => claim_rewards::process(program_id, accounts, data),
         _ => Err(ProgramError::InvalidInstructionData),
     }
 }
@@ -78,6 +78,46 @@ impl CaptureRecord {
     }
 }
 
+/// Per-player capture nonce (replay protection for record_capture)
+/// PDA: ["capture_nonce", player]
+#[repr(packed)]
+pub struct PlayerCaptureState {
+    /// Account discriminator
+    pub discriminator: [u8; 8],
+    
+    /// Player wallet
+    pub player: Pubkey,
+    
+    /// Nonce the next record_capture must carry
+    pub nonce: u64,
+    
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl PlayerCaptureState {
+    /// Account size in bytes (packed)
+    pub const SIZE: usize = 8 + 32 + 8 + 1;
+    // = 49 bytes
+    
+    /// Account discriminator
+    pub const DISCRIMINATOR: [u8; 8] = *b"capnonce";
+    
+    /// PDA seed prefix
+    pub const SEED: &'static [u8] = b"capture_nonce";
+    
+    /// Deserialize mutable from account data
+    pub fn from_account_data_mut(data: &mut [u8]) -> Result<&mut Self, pinocchio::program_error::ProgramError> {
+        if data.len() < Self::SIZE {
+            return Err(pinocchio::program_error::ProgramError::AccountDataTooSmall);
+        }
+        
+        let state = unsafe { &mut *(data.as_mut_ptr() as *mut Self) };
+        
+        Ok(state)
+    }
+}
+
 /// Daily capture limit tracking per player
 /// PDA: ["daily_capture", player, day (u32)]
 #[repr(packed)]
@@ -0,0 +1,122 @@
+//! Marketplace listing escrow account
+
+use pinocchio::pubkey::Pubkey;
+
+/// Marketplace listing account
+/// PDA: ["listing", titan_id (u64)]
+///
+/// While the listing is active the PDA owns the Titan in the Titan NFT
+/// Program; it hands ownership back to the seller on cancel or to the buyer
+/// on purchase and is closed afterwards.
+#[repr(packed)]
+pub struct Listing {
+    /// Account discriminator
+    pub discriminator: [u8; 8],
+
+    /// Titan ID held in escrow
+    pub titan_id: u64,
+
+    /// Seller wallet (receives payment and rent refund)
+    pub seller: Pubkey,
+
+    /// Asking price in $BREACH (with decimals)
+    pub price: u64,
+
+    /// Listing creation timestamp (Unix timestamp)
+    pub created_at: i64,
+
+    /// Listing expiry timestamp (Unix timestamp)
+    pub expires_at: i64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl Listing {
+    /// Account size in bytes (packed)
+    pub const SIZE: usize = 8 + 8 + 32 + 8 + 8 + 8 + 1;
+    // = 73 bytes
+
+    /// Account discriminator
+    pub const DISCRIMINATOR: [u8; 8] = *b"listing_";
+
+    /// PDA seed prefix
+    pub const SEED: &'static [u8] = b"listing";
+
+    /// Maximum listing duration (30 days)
+    pub const MAX_DURATION_SECONDS: i64 = 30 * 24 * 60 * 60;
+
+    /// Deserialize from account data
+    pub fn from_account_data(data: &[u8]) -> Result<&Self, pinocchio::program_error::ProgramError> {
+        if data.len() < Self::SIZE {
+            return Err(pinocchio::program_error::ProgramError::AccountDataTooSmall);
+        }
+
+        let listing = unsafe { &*(data.as_ptr() as *const Self) };
+
+        if listing.discriminator != Self::DISCRIMINATOR {
+            return Err(pinocchio::program_error::ProgramError::InvalidAccountData);
+        }
+
+        Ok(listing)
+    }
+
+    /// Deserialize mutable from account data
+    pub fn from_account_data_mut(data: &mut [u8]) -> Result<&mut Self, pinocchio::program_error::ProgramError> {
+        if data.len() < Self::SIZE {
+            return Err(pinocchio::program_error::ProgramError::AccountDataTooSmall);
+        }
+
+        let listing = unsafe { &mut *(data.as_mut_ptr() as *mut Self) };
+
+        Ok(listing)
+    }
+
+    /// Check if the listing has expired at the given timestamp
+    pub fn is_expired(&self, now: i64) -> bool {
+        now > self.expires_at
+    }
+}
+
+/// Split a sale price into (fee, seller_amount) using basis points.
+///
+/// Returns `None` if the fee rate exceeds 100%.
+pub fn split_sale_price(price: u64, fee_bps: u16) -> Option<(u64, u64)> {
+    if fee_bps > 10_000 {
+        return None;
+    }
+    let fee = (price as u128 * fee_bps as u128 / 10_000) as u64;
+    Some((fee, price - fee))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_sale_price() {
+        // 2.5% of 100 BREACH
+        assert_eq!(split_sale_price(100_000_000_000, 250), Some((2_500_000_000, 97_500_000_000)));
+        // Zero fee
+        assert_eq!(split_sale_price(1_000, 0), Some((0, 1_000)));
+        // Rounds fee down, seller keeps the dust
+        assert_eq!(split_sale_price(399, 250), Some((9, 390)));
+    }
+
+    #[test]
+    fn test_split_sale_price_no_overflow() {
+        let (fee, seller) = split_sale_price(u64::MAX, 10_000).unwrap();
+        assert_eq!(fee, u64::MAX);
+        assert_eq!(seller, 0);
+    }
+
+    #[test]
+    fn test_split_sale_price_invalid_bps() {
+        assert_eq!(split_sale_price(1_000, 10_001), None);
+    }
+
+    #[test]
+    fn test_listing_size() {
+        assert_eq!(core::mem::size_of::<Listing>(), Listing::SIZE);
+    }
+}
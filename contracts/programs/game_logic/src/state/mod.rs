@@ -3,7 +3,11 @@
 pub mod config;
 pub mod battle;
 pub mod capture;
+pub mod listing;
+pub mod reward;
 
 pub use config::*;
 pub use battle::*;
 pub use capture::*;
+pub use listing::*;
+pub use reward::*;
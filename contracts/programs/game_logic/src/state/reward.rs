@@ -0,0 +1,146 @@
+//! Pending reward ledger
+
+use pinocchio::pubkey::Pubkey;
+
+use crate::error::GameError;
+
+/// Per-player pending reward balance
+/// PDA: ["rewards", player]
+///
+/// `accrue_reward` adds to the balance instead of transferring tokens for
+/// every reward; the player pulls the whole balance with one `claim_rewards`.
+#[repr(packed)]
+pub struct RewardLedger {
+    /// Account discriminator
+    pub discriminator: [u8; 8],
+
+    /// Player wallet
+    pub player: Pubkey,
+
+    /// Rewards accrued but not yet claimed
+    pub accrued_amount: u64,
+
+    /// Last accrual timestamp (Unix timestamp)
+    pub last_accrual_ts: i64,
+
+    /// Lifetime claimed amount
+    pub total_claimed: u64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl RewardLedger {
+    /// Account size in bytes (packed)
+    pub const SIZE: usize = 8 + 32 + 8 + 8 + 8 + 1;
+    // = 65 bytes
+
+    /// Account discriminator
+    pub const DISCRIMINATOR: [u8; 8] = *b"rwdledgr";
+
+    /// PDA seed prefix
+    pub const SEED: &'static [u8] = b"rewards";
+
+    /// Deserialize from account data
+    pub fn from_account_data(data: &[u8]) -> Result<&Self, pinocchio::program_error::ProgramError> {
+        if data.len() < Self::SIZE {
+            return Err(pinocchio::program_error::ProgramError::AccountDataTooSmall);
+        }
+
+        let ledger = unsafe { &*(data.as_ptr() as *const Self) };
+
+        if ledger.discriminator != Self::DISCRIMINATOR {
+            return Err(pinocchio::program_error::ProgramError::InvalidAccountData);
+        }
+
+        Ok(ledger)
+    }
+
+    /// Deserialize mutable from account data
+    pub fn from_account_data_mut(data: &mut [u8]) -> Result<&mut Self, pinocchio::program_error::ProgramError> {
+        if data.len() < Self::SIZE {
+            return Err(pinocchio::program_error::ProgramError::AccountDataTooSmall);
+        }
+
+        let ledger = unsafe { &mut *(data.as_mut_ptr() as *mut Self) };
+
+        Ok(ledger)
+    }
+
+    /// Add a reward to the pending balance
+    pub fn accrue(&mut self, amount: u64, now: i64) -> Result<(), GameError> {
+        self.accrued_amount = self
+            .accrued_amount
+            .checked_add(amount)
+            .ok_or(GameError::RewardOverflow)?;
+        self.last_accrual_ts = now;
+        Ok(())
+    }
+
+    /// Empty the pending balance and return the amount to pay out
+    pub fn take_claim(&mut self) -> Result<u64, GameError> {
+        let amount = self.accrued_amount;
+        if amount == 0 {
+            return Err(GameError::NoRewardsToClaim);
+        }
+
+        self.total_claimed = self
+            .total_claimed
+            .checked_add(amount)
+            .ok_or(GameError::RewardOverflow)?;
+        self.accrued_amount = 0;
+        Ok(amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ledger() -> RewardLedger {
+        RewardLedger {
+            discriminator: RewardLedger::DISCRIMINATOR,
+            player: [0; 32],
+            accrued_amount: 0,
+            last_accrual_ts: 0,
+            total_claimed: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_accrue_three_then_claim() {
+        let mut ledger = ledger();
+        ledger.accrue(100, 1).unwrap();
+        ledger.accrue(200, 2).unwrap();
+        ledger.accrue(300, 3).unwrap();
+
+        assert_eq!({ ledger.accrued_amount }, 600);
+        assert_eq!({ ledger.last_accrual_ts }, 3);
+
+        assert_eq!(ledger.take_claim().unwrap(), 600);
+        assert_eq!({ ledger.accrued_amount }, 0);
+        assert_eq!({ ledger.total_claimed }, 600);
+    }
+
+    #[test]
+    fn test_accrue_overflow() {
+        let mut ledger = ledger();
+        ledger.accrue(u64::MAX, 1).unwrap();
+        assert!(matches!(ledger.accrue(1, 2), Err(GameError::RewardOverflow)));
+        // A failed accrual leaves the balance alone
+        assert_eq!({ ledger.accrued_amount }, u64::MAX);
+        assert_eq!({ ledger.last_accrual_ts }, 1);
+    }
+
+    #[test]
+    fn test_claim_empty_ledger() {
+        let mut ledger = ledger();
+        assert!(matches!(ledger.take_claim(), Err(GameError::NoRewardsToClaim)));
+    }
+
+    #[test]
+    fn test_reward_ledger_size() {
+        assert_eq!(core::mem::size_of::<RewardLedger>(), RewardLedger::SIZE);
+    }
+}
@@ -2,6 +2,9 @@
 
 use pinocchio::program_error::ProgramError;
 
+// Every variant must have an entry in `breach_program_errors::titan_nft`
+// (checked by the catalog crate tests).
+
 /// Titan NFT program errors
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u32)]
@@ -45,6 +48,12 @@ pub enum TitanError {
     /// Invalid capture location
     InvalidLocation = 6203,
     
+    /// Batch mint size must be 1-5 and match the instruction data
+    InvalidBatchSize = 6204,
+    
+    /// Batch mint Titan accounts don't match the batch size
+    BatchAccountMismatch = 6205,
+    
     // ═══════════ Titan Validation (6300-6399) ═══════════
     
     /// Invalid threat class (must be 1-5)
@@ -65,6 +74,12 @@ pub enum TitanError {
     /// Invalid species ID
     InvalidSpeciesId = 6305,
     
+    /// Titan is listed on the marketplace
+    TitanListed = 6306,
+    
+    /// Species is not on the Titan's evolution path
+    InvalidEvolutionTarget = 6307,
+    
     // ═══════════ Fusion (6400-6499) ═══════════
     
     /// Cannot fuse Titan with itself
@@ -112,37 +127,8 @@ impl From<TitanError> for ProgramError {
 }
 
 impl TitanError {
-    /// Get error message
+    /// Get error message (from the shared error catalog)
     pub fn message(&self) -> &'static str {
-        match self {
-            Self::Unauthorized => "Not authorized to perform this action",
-            Self::InvalidCaptureAuthority => "Invalid capture authority signer",
-            Self::NotOwner => "Not the owner of this Titan",
-            Self::InvalidAuthority => "Invalid admin authority",
-            Self::ProgramPaused => "Program is currently paused",
-            Self::AlreadyInitialized => "Account already initialized",
-            Self::NotInitialized => "Account not initialized",
-            Self::CaptureCooldown => "Capture cooldown not elapsed",
-            Self::MaxTitansReached => "Maximum Titans per wallet reached",
-            Self::InvalidCaptureProof => "Invalid capture proof/signature",
-            Self::InvalidLocation => "Invalid capture location",
-            Self::InvalidThreatClass => "Invalid threat class (must be 1-5)",
-            Self::InvalidElementType => "Invalid element type (must be 0-5)",
-            Self::MaxLevelReached => "Maximum level reached",
-            Self::InsufficientExperience => "Insufficient experience for level up",
-            Self::CannotEvolve => "Cannot evolve this Titan",
-            Self::InvalidSpeciesId => "Invalid species ID",
-            Self::CannotFuseWithSelf => "Cannot fuse Titan with itself",
-            Self::LevelTooLowForFusion => "Titan level too low for fusion",
-            Self::ElementMismatch => "Element type mismatch for fusion",
-            Self::FusionOwnerMismatch => "Both Titans must be owned by same player",
-            Self::InsufficientBalance => "Insufficient $BREACH balance",
-            Self::TransferFailed => "Token transfer failed",
-            Self::InvalidMint => "Invalid token mint",
-            Self::InvalidAccountData => "Invalid account data",
-            Self::AccountDataTooSmall => "Account data too small",
-            Self::InvalidSeeds => "Invalid PDA seeds",
-            Self::InvalidProgramId => "Invalid program ID",
-        }
+        breach_program_errors::titan_nft::describe(*self as u32).unwrap_or("Unknown error")
     }
 }
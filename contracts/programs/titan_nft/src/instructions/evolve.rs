@@ -7,6 +7,8 @@ use pinocchio::{
     ProgramResult,
 };
 
+use breach_evolution::EvolutionError;
+
 use crate::{error::TitanError, state::TitanData};
 
 /// Evolve instruction data
@@ -16,9 +18,6 @@ pub struct EvolveData {
     pub new_species_id: u16,
 }
 
-/// Minimum level required for evolution
-const EVOLUTION_MIN_LEVEL: u8 = 30;
-
 /// Process evolve instruction
 pub fn process(
     _program_id: &Pubkey,
@@ -45,16 +44,73 @@ pub fn process(
     let mut titan_data = titan_account.try_borrow_mut_data()?;
     let titan = TitanData::from_account_data_mut(&mut titan_data)?;
 
-    // Check minimum level for evolution
-    if titan.level < EVOLUTION_MIN_LEVEL {
-        return Err(TitanError::CannotEvolve.into());
-    }
+    evolve(titan, evolve_data.new_species_id)?;
+
+    Ok(())
+}
 
-    // Evolution upgrades the species
-    titan.species_id = evolve_data.new_species_id;
+/// Move a Titan along its evolution path to `new_species_id`
+fn evolve(titan: &mut TitanData, new_species_id: u16) -> Result<(), TitanError> {
+    breach_evolution::check(titan.species_id, titan.element_type, titan.level, new_species_id)
+        .map_err(|e| match e {
+            EvolutionError::LevelTooLow => TitanError::CannotEvolve,
+            EvolutionError::InvalidTarget => TitanError::InvalidEvolutionTarget,
+        })?;
+
+    // Evolution upgrades the species and its threat class
+    titan.species_id = new_species_id;
+    if let Some((_, threat_class, _)) = breach_evolution::decode(new_species_id) {
+        titan.threat_class = threat_class;
+    }
 
     // Boost link strength on evolution
     titan.link_strength = titan.link_strength.saturating_add(10).min(100);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titan(data: &mut [u8; TitanData::SIZE], species_id: u16, level: u8) -> &mut TitanData {
+        let titan = TitanData::init_from_account_data(data).unwrap();
+        titan.species_id = species_id;
+        titan.threat_class = breach_evolution::decode(species_id).unwrap().1;
+        titan.element_type = breach_evolution::decode(species_id).unwrap().0;
+        titan.level = level;
+        titan.link_strength = 50;
+        titan
+    }
+
+    #[test]
+    fn test_evolve_valid_path() {
+        let mut data = [0u8; TitanData::SIZE];
+        let titan = titan(&mut data, 1_004, 30);
+
+        assert_eq!(evolve(titan, 1_104), Ok(()));
+        assert_eq!({ titan.species_id }, 1_104);
+        assert_eq!(titan.threat_class, 2);
+        assert_eq!(titan.link_strength, 60);
+    }
+
+    #[test]
+    fn test_evolve_invalid_target() {
+        let mut data = [0u8; TitanData::SIZE];
+        let titan = titan(&mut data, 1_004, 30);
+
+        // Another element's legendary
+        assert_eq!(evolve(titan, 3_409), Err(TitanError::InvalidEvolutionTarget));
+        assert_eq!({ titan.species_id }, 1_004);
+        assert_eq!(titan.link_strength, 50);
+    }
+
+    #[test]
+    fn test_evolve_under_leveled() {
+        let mut data = [0u8; TitanData::SIZE];
+        let titan = titan(&mut data, 1_004, 29);
+
+        assert_eq!(evolve(titan, 1_104), Err(TitanError::CannotEvolve));
+        assert_eq!({ titan.species_id }, 1_004);
+    }
+}
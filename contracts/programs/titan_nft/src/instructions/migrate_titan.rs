@@ -0,0 +1,80 @@
+//! Migrate Titan instruction
+//!
+//! Grows a Titan account created before the marketplace listing flag
+//! (`TitanData::LEGACY_SIZE` bytes) to `TitanData::SIZE`. The added bytes are
+//! zeroed, so the Titan comes out unlisted. Anyone may pay for it; an account
+//! already at the current size is left as is.
+
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvars::{rent::Rent, Sysvar},
+    ProgramResult,
+};
+
+use crate::state::TitanData;
+
+/// Process migrate_titan instruction
+///
+/// Accounts:
+/// 0. `[signer, writable]` Payer of the extra rent
+/// 1. `[writable]` Titan data PDA
+/// 2. `[]` System program
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    // Parse accounts
+    let [
+        payer,           // [0] Payer (signer)
+        titan_account,   // [1] Titan data PDA (writable)
+        _system_program, // [2] System program
+    ] = accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Validate payer is signer
+    if !payer.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if !titan_account.is_owned_by(program_id) {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // Already migrated
+    let len = titan_account.data_len();
+    if len >= TitanData::SIZE {
+        return Ok(());
+    }
+    if len != TitanData::LEGACY_SIZE {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let titan_data = titan_account.try_borrow_data()?;
+    if titan_data[..8] != TitanData::DISCRIMINATOR {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    drop(titan_data);
+
+    // Top the account up to rent exemption at its new size
+    let rent = Rent::get()?;
+    let shortfall = rent
+        .minimum_balance(TitanData::SIZE)
+        .saturating_sub(titan_account.lamports());
+    if shortfall > 0 {
+        pinocchio_system::instructions::Transfer {
+            from: payer,
+            to: titan_account,
+            lamports: shortfall,
+        }
+        .invoke()?;
+    }
+
+    titan_account.realloc(TitanData::SIZE, true)?;
+
+    Ok(())
+}
@@ -89,16 +89,7 @@ pub fn process(
         return Err(ProgramError::InvalidInstructionData);
     }
     let mint_data = unsafe { &*(data.as_ptr() as *const MintTitanData) };
-
-    // Validate threat class (1-5)
-    if mint_data.threat_class < 1 || mint_data.threat_class > 5 {
-        return Err(TitanError::InvalidThreatClass.into());
-    }
-
-    // Validate element type (0-5)
-    if mint_data.element_type > 5 {
-        return Err(TitanError::InvalidElementType.into());
-    }
+    validate_mint_data(mint_data)?;
 
     let clock = Clock::get()?;
     let rent = Rent::get()?;
@@ -107,7 +98,59 @@ pub fn process(
     let max_titans = config.max_titans_per_wallet;
     drop(config_data);
 
-    // Derive Player PDA
+    ensure_player_account(program_id, payer, player_account, &rent, &clock)?;
+
+    // Load player
+    let mut player_data = player_account.try_borrow_mut_data()?;
+    let player = PlayerAccount::from_account_data_mut(&mut player_data)?;
+
+    // Check capture cooldown
+    if !player.can_capture(clock.unix_timestamp, cooldown) {
+        return Err(TitanError::CaptureCooldown.into());
+    }
+
+    // Check max titans
+    if player.titans_owned >= max_titans as u32 {
+        return Err(TitanError::MaxTitansReached.into());
+    }
+
+    // Get new titan ID
+    let titan_id = total_minted + 1;
+    create_titan(program_id, payer, titan_account, titan_id, mint_data, &rent, &clock)?;
+
+    // Update player stats
+    player.titans_captured += 1;
+    player.titans_owned += 1;
+    player.last_capture_at = clock.unix_timestamp;
+    drop(player_data);
+
+    // Update config stats
+    let mut config_data_mut = config_account.try_borrow_mut_data()?;
+    let config = GlobalConfig::from_account_data_mut(&mut config_data_mut)?;
+    config.total_titans_minted = titan_id;
+
+    Ok(())
+}
+
+/// Validate threat class (1-5) and element type (0-5)
+pub(crate) fn validate_mint_data(mint_data: &MintTitanData) -> ProgramResult {
+    if mint_data.threat_class < 1 || mint_data.threat_class > 5 {
+        return Err(TitanError::InvalidThreatClass.into());
+    }
+    if mint_data.element_type > 5 {
+        return Err(TitanError::InvalidElementType.into());
+    }
+    Ok(())
+}
+
+/// Verify the payer's Player PDA and create it on first mint
+pub(crate) fn ensure_player_account(
+    program_id: &Pubkey,
+    payer: &AccountInfo,
+    player_account: &AccountInfo,
+    rent: &Rent,
+    clock: &Clock,
+) -> ProgramResult {
     let (player_pda, player_bump) = pinocchio::pubkey::find_program_address(
         &[PlayerAccount::SEED_PREFIX, payer.key().as_ref()],
         program_id,
@@ -117,7 +160,6 @@ pub fn process(
         return Err(TitanError::InvalidSeeds.into());
     }
 
-    // Create player account if needed
     if player_account.lamports() == 0 {
         let required_lamports = rent.minimum_balance(PlayerAccount::SIZE);
         let bump_slice = [player_bump];
@@ -144,27 +186,21 @@ pub fn process(
         player.elo_rating = PlayerAccount::INITIAL_ELO;
         player.peak_elo = PlayerAccount::INITIAL_ELO;
         player.created_at = clock.unix_timestamp;
-        drop(player_data);
     }
 
-    // Load player
-    let mut player_data = player_account.try_borrow_mut_data()?;
-    let player = PlayerAccount::from_account_data_mut(&mut player_data)?;
-
-    // Check capture cooldown
-    if !player.can_capture(clock.unix_timestamp, cooldown) {
-        return Err(TitanError::CaptureCooldown.into());
-    }
-
-    // Check max titans
-    if player.titans_owned >= max_titans as u32 {
-        return Err(TitanError::MaxTitansReached.into());
-    }
-
-    // Get new titan ID
-    let titan_id = total_minted + 1;
+    Ok(())
+}
 
-    // Derive Titan PDA
+/// Create and initialize the Titan PDA for `titan_id`
+pub(crate) fn create_titan(
+    program_id: &Pubkey,
+    payer: &AccountInfo,
+    titan_account: &AccountInfo,
+    titan_id: u64,
+    mint_data: &MintTitanData,
+    rent: &Rent,
+    clock: &Clock,
+) -> ProgramResult {
     let titan_id_bytes = titan_id.to_le_bytes();
     let (titan_pda, titan_bump) = pinocchio::pubkey::find_program_address(
         &[TitanData::SEED_PREFIX, &titan_id_bytes],
@@ -221,19 +257,6 @@ pub fn process(
     titan.parent_b = 0;
     titan.bump = titan_bump;
 
-    drop(titan_data_mut);
-
-    // Update player stats
-    player.titans_captured += 1;
-    player.titans_owned += 1;
-    player.last_capture_at = clock.unix_timestamp;
-    drop(player_data);
-
-    // Update config stats
-    let mut config_data_mut = config_account.try_borrow_mut_data()?;
-    let config = GlobalConfig::from_account_data_mut(&mut config_data_mut)?;
-    config.total_titans_minted = titan_id;
-
     Ok(())
 }
 
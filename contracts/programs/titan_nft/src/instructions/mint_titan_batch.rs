@@ -0,0 +1,127 @@
+//! Mint Titan batch instruction
+//!
+//! Mints up to `MAX_BATCH` Titans in one transaction so concurrent captures
+//! don't race each other for the next `titan_id`.
+
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvars::{clock::Clock, rent::Rent, Sysvar},
+    ProgramResult,
+};
+
+use super::mint_titan::{create_titan, ensure_player_account, validate_mint_data, MintTitanData};
+use crate::{
+    error::TitanError,
+    state::{GlobalConfig, PlayerAccount},
+};
+
+/// Maximum Titans per batch
+pub const MAX_BATCH: usize = 5;
+
+/// Process mint_titan_batch instruction
+///
+/// Data: count (u8) followed by `count` packed `MintTitanData` entries.
+/// Titan PDAs for IDs `total_titans_minted + 1 ..= + count` follow the fixed
+/// accounts, in order.
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    // Parse accounts
+    let [
+        payer,              // [0] Signer, player
+        config_account,     // [1] Config PDA
+        player_account,     // [2] Player PDA
+        capture_authority,  // [3] Backend signer
+        _system_program,    // [4] System program
+        titan_accounts @ .., // [5..] New Titan PDAs, one per entry
+    ] = accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Validate signers
+    if !payer.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if !capture_authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Parse instruction data
+    let (&count, entries) = data
+        .split_first()
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let count = count as usize;
+    let entry_size = core::mem::size_of::<MintTitanData>();
+
+    if count == 0 || count > MAX_BATCH || entries.len() != count * entry_size {
+        return Err(TitanError::InvalidBatchSize.into());
+    }
+    // A missing or extra Titan account fails the whole batch
+    if titan_accounts.len() != count {
+        return Err(TitanError::BatchAccountMismatch.into());
+    }
+
+    // Load config
+    let config_data = config_account.try_borrow_data()?;
+    let config = GlobalConfig::from_account_data(&config_data)?;
+
+    // Check program not paused
+    if config.paused {
+        return Err(TitanError::ProgramPaused.into());
+    }
+
+    // Validate capture authority
+    if capture_authority.key().as_ref() != config.capture_authority.as_ref() {
+        return Err(TitanError::InvalidCaptureAuthority.into());
+    }
+
+    let clock = Clock::get()?;
+    let rent = Rent::get()?;
+    let total_minted = config.total_titans_minted;
+    let cooldown = config.capture_cooldown_seconds;
+    let max_titans = config.max_titans_per_wallet;
+    drop(config_data);
+
+    ensure_player_account(program_id, payer, player_account, &rent, &clock)?;
+
+    // Load player
+    let mut player_data = player_account.try_borrow_mut_data()?;
+    let player = PlayerAccount::from_account_data_mut(&mut player_data)?;
+
+    // The batch counts as one capture for the cooldown
+    if !player.can_capture(clock.unix_timestamp, cooldown) {
+        return Err(TitanError::CaptureCooldown.into());
+    }
+
+    // Check max titans for the whole batch
+    if player.titans_owned as usize + count > max_titans as usize {
+        return Err(TitanError::MaxTitansReached.into());
+    }
+
+    for (i, titan_account) in titan_accounts.iter().enumerate() {
+        let entry = &entries[i * entry_size..(i + 1) * entry_size];
+        let mint_data = unsafe { &*(entry.as_ptr() as *const MintTitanData) };
+        validate_mint_data(mint_data)?;
+
+        let titan_id = total_minted + 1 + i as u64;
+        create_titan(program_id, payer, titan_account, titan_id, mint_data, &rent, &clock)?;
+    }
+
+    // Update player stats
+    player.titans_captured += count as u32;
+    player.titans_owned += count as u32;
+    player.last_capture_at = clock.unix_timestamp;
+    drop(player_data);
+
+    // Advance the counter once, by the number minted
+    let mut config_data_mut = config_account.try_borrow_mut_data()?;
+    let config = GlobalConfig::from_account_data_mut(&mut config_data_mut)?;
+    config.total_titans_minted = total_minted + count as u64;
+
+    Ok(())
+}
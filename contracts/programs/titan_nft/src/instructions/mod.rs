@@ -5,7 +5,10 @@ pub mod evolve;
 pub mod fuse;
 pub mod initialize;
 pub mod level_up;
+pub mod migrate_titan;
 pub mod mint_titan;
+pub mod mint_titan_batch;
+pub mod set_listed;
 pub mod set_paused;
 pub mod transfer;
 pub mod update_config;
@@ -21,4 +24,7 @@ pub mod discriminator {
     pub const UPDATE_CONFIG: u8 = 6;
     pub const SET_PAUSED: u8 = 7;
     pub const ADD_EXPERIENCE: u8 = 8;
+    pub const SET_LISTED: u8 = 9;
+    pub const MINT_TITAN_BATCH: u8 = 10;
+    pub const MIGRATE_TITAN: u8 = 11;
 }
@@ -0,0 +1,72 @@
+//! Set marketplace listed flag instruction
+//!
+//! Called by the Game Logic escrow via CPI. The signer must be the current
+//! owner of the Titan, which for an active listing is the Listing PDA.
+
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    ProgramResult,
+};
+
+use crate::{
+    error::TitanError,
+    state::{GlobalConfig, TitanData},
+};
+
+/// Process set_listed instruction
+///
+/// Accounts:
+/// 0. `[signer]` Current Titan owner
+/// 1. `[]` Global config PDA
+/// 2. `[writable]` Titan data PDA
+///
+/// Data:
+/// 0. `listed` (u8, 0 = unlisted, 1 = listed)
+pub fn process(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    // Parse accounts
+    let [
+        owner,          // [0] Current owner (signer)
+        config_account, // [1] Config PDA
+        titan_account,  // [2] Titan data PDA (writable)
+    ] = accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Validate owner is signer
+    if !owner.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Check program not paused
+    let config_data = config_account.try_borrow_data()?;
+    let config = GlobalConfig::from_account_data(&config_data)?;
+    if config.paused {
+        return Err(TitanError::ProgramPaused.into());
+    }
+    drop(config_data);
+
+    // Parse listed flag from data
+    if data.is_empty() {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let listed = data[0] != 0;
+
+    // Load Titan and verify ownership
+    let mut titan_data = titan_account.try_borrow_mut_data()?;
+    let titan = TitanData::from_account_data_mut(&mut titan_data)?;
+
+    if titan.owner.as_ref() != owner.key().as_ref() {
+        return Err(TitanError::NotOwner.into());
+    }
+
+    titan.listed = listed as u8;
+
+    Ok(())
+}
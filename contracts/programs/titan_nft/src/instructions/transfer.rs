@@ -65,6 +65,11 @@ pub fn process(
         return Err(TitanError::NotOwner.into());
     }
     
+    // Listed Titans can only move once the escrow clears the flag
+    if titan.is_listed() {
+        return Err(TitanError::TitanListed.into());
+    }
+    
     // Update owner to new owner
     titan.owner = *_to_owner.key();
     drop(titan_data);
@@ -5,6 +5,7 @@
 //! - Titan attributes and evolution
 //! - Titan fusion mechanics
 //! - Level up system
+//! - Marketplace listing flag (escrow held by Game Logic)
 
 use pinocchio::{
     account_info::AccountInfo,
@@ -64,6 +65,15 @@ pub fn process_instruction(
         // Add experience (CPI from Game Logic)
         8 => instructions::add_experience::process(program_id, accounts, data),
         
+        // Set marketplace listed flag (CPI from Game Logic escrow)
+        9 => instructions::set_listed::process(program_id, accounts, data),
+        
+        // Mint up to 5 Titans in one transaction
+        10 => instructions::mint_titan_batch::process(program_id, accounts, data),
+        
+        // Grow a pre-listing-flag Titan account to the current size
+        11 => instructions::migrate_titan::process(program_id, accounts, data),
+        
         _ => Err(ProgramError::InvalidInstructionData),
     }
 }
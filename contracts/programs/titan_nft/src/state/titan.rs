@@ -77,12 +77,21 @@ pub struct TitanData {
     
     /// PDA bump seed
     pub bump: u8,
+    
+    /// Marketplace listing flag (1 = held in a Game Logic escrow listing)
+    pub listed: u8,
+    
+    /// Reserved for future use
+    pub _reserved: [u8; 15],
 }
 
 impl TitanData {
     /// Account size in bytes (118 + 32 for owner field)
     pub const SIZE: usize = 150;
     
+    /// Size of accounts created before `listed`; `migrate_titan` grows them
+    pub const LEGACY_SIZE: usize = 134;
+    
     /// Account discriminator
     pub const DISCRIMINATOR: [u8; 8] = [0x54, 0x49, 0x54, 0x41, 0x4E, 0x44, 0x41, 0x54]; // "TITANDAT"
     
@@ -170,6 +179,11 @@ impl TitanData {
     pub fn can_level_up(&self) -> bool {
         self.level < Self::MAX_LEVEL && self.experience >= self.exp_for_next_level()
     }
+    
+    /// Check if Titan is currently listed on the marketplace
+    pub fn is_listed(&self) -> bool {
+        self.listed != 0
+    }
 }
 
 /// Element type enumeration
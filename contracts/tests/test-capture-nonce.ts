@@ -0,0 +1,416 @@
+/**
+ * BREACH Capture Replay Protection Test Script
+ *
+ * Runs record_capture against a local validator and checks the per-player
+ * capture nonce: the first capture creates the nonce account, sequential
+ * captures advance it, and a replayed capture payload is rejected with
+ * InvalidNonce (7304) even when it is re-signed with a fresh blockhash.
+ *
+ * Usage:
+ *   solana-test-validator --reset \
+ *     --bpf-program DLk2GnDu9AYn7PeLprEDHDYH9UWKENX47UqqfeiQBaSX ../target/deploy/game_logic.so
+ *   npx ts-node test-capture-nonce.ts
+ *
+ * Environment:
+ *   RPC_URL      Validator RPC (default http://127.0.0.1:8899)
+ *   WALLET_PATH  Payer keypair, also used as backend authority (default ~/.config/solana/id.json)
+ */
+
+import {
+  Connection,
+  PublicKey,
+  Keypair,
+  Transaction,
+  TransactionInstruction,
+  SystemProgram,
+  sendAndConfirmTransaction,
+  LAMPORTS_PER_SOL,
+} from "@solana/web3.js";
+import * as fs from "fs";
+import * as path from "path";
+
+const GAME_LOGIC_PROGRAM_ID = new PublicKey("DLk2GnDu9AYn7PeLprEDHDYH9UWKENX47UqqfeiQBaSX");
+const TITAN_NFT_PROGRAM_ID = new PublicKey("3KYPXMcodPCbnWLDX41yWtgxe6ctsPdnT3fYgp8udmd7");
+
+const INSTRUCTION = {
+  INITIALIZE: 0,
+  RECORD_CAPTURE: 1,
+};
+
+const GAME_CONFIG_SEED = Buffer.from("game_config");
+const CAPTURE_SEED = Buffer.from("capture");
+const CAPTURE_NONCE_SEED = Buffer.from("capture_nonce");
+
+// GameConfig offsets (packed)
+const CONFIG_BACKEND_AUTHORITY_OFFSET = 40;
+const CONFIG_TOTAL_CAPTURES_OFFSET = 204;
+
+// PlayerCaptureState: discriminator(8) + player(32) + nonce(8) + bump(1)
+const CAPTURE_STATE_SIZE = 49;
+const CAPTURE_STATE_NONCE_OFFSET = 40;
+
+const INVALID_NONCE = 7304;
+
+const connection = new Connection(process.env.RPC_URL || "http://127.0.0.1:8899", "confirmed");
+
+const testResults: { name: string; passed: boolean; error?: string }[] = [];
+
+function loadWallet(filepath: string): Keypair {
+  const secretKey = JSON.parse(fs.readFileSync(filepath, "utf-8"));
+  return Keypair.fromSecretKey(Uint8Array.from(secretKey));
+}
+
+function recordTest(name: string, passed: boolean, error?: string) {
+  testResults.push({ name, passed, error });
+  const icon = passed ? "✅" : "❌";
+  console.log(`${icon} ${name}${error ? ` (${error})` : ""}`);
+}
+
+function parseErrorCode(error: any): number {
+  const match = error.toString().match(/custom program error: (0x[0-9a-fA-F]+)/);
+  if (match) {
+    return parseInt(match[1], 16);
+  }
+  return 0;
+}
+
+// ============================================
+// PDAs and Account Readers
+// ============================================
+
+function getGameConfigPDA(): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync([GAME_CONFIG_SEED], GAME_LOGIC_PROGRAM_ID);
+}
+
+function getCaptureRecordPDA(captureId: bigint): [PublicKey, number] {
+  const idBuffer = Buffer.alloc(8);
+  idBuffer.writeBigUInt64LE(captureId);
+  return PublicKey.findProgramAddressSync([CAPTURE_SEED, idBuffer], GAME_LOGIC_PROGRAM_ID);
+}
+
+function getCaptureStatePDA(player: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync([CAPTURE_NONCE_SEED, player.toBuffer()], GAME_LOGIC_PROGRAM_ID);
+}
+
+async function readTotalCaptures(configPDA: PublicKey): Promise<bigint | null> {
+  const accountInfo = await connection.getAccountInfo(configPDA);
+  if (!accountInfo) {
+    return null;
+  }
+  return (accountInfo.data as Buffer).readBigUInt64LE(CONFIG_TOTAL_CAPTURES_OFFSET);
+}
+
+/** Player capture nonce, or null if the state account doesn't exist yet */
+async function readCaptureNonce(player: PublicKey): Promise<bigint | null> {
+  const [captureState] = getCaptureStatePDA(player);
+  const accountInfo = await connection.getAccountInfo(captureState);
+  if (!accountInfo || accountInfo.data.length < CAPTURE_STATE_SIZE) {
+    return null;
+  }
+  return (accountInfo.data as Buffer).readBigUInt64LE(CAPTURE_STATE_NONCE_OFFSET);
+}
+
+// ============================================
+// Instruction Builders
+// ============================================
+
+function buildInitializeInstruction(
+  authority: PublicKey,
+  configAccount: PublicKey,
+  backendAuthority: PublicKey
+): TransactionInstruction {
+  // Data: instruction(1) + backend_authority(32) + titan_program(32) + breach_mint(32) + reward_pool(32) = 129 bytes
+  const data = Buffer.alloc(1 + 32 * 4);
+  data.writeUInt8(INSTRUCTION.INITIALIZE, 0);
+  backendAuthority.toBuffer().copy(data, 1);
+  TITAN_NFT_PROGRAM_ID.toBuffer().copy(data, 33);
+  Keypair.generate().publicKey.toBuffer().copy(data, 65);
+  Keypair.generate().publicKey.toBuffer().copy(data, 97);
+
+  return new TransactionInstruction({
+    keys: [
+      { pubkey: authority, isSigner: true, isWritable: true },
+      { pubkey: configAccount, isSigner: false, isWritable: true },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    ],
+    programId: GAME_LOGIC_PROGRAM_ID,
+    data,
+  });
+}
+
+interface CapturePayload {
+  titanId: bigint;
+  signatureTimestamp: bigint;
+  nonce: bigint;
+}
+
+function buildRecordCaptureInstruction(
+  player: PublicKey,
+  backendAuthority: PublicKey,
+  configAccount: PublicKey,
+  captureRecord: PublicKey,
+  payload: CapturePayload
+): TransactionInstruction {
+  // Data: instruction(1) + titan_id(8) + lat(4) + lng(4) + threat(1) + element(1) + timestamp(8) + nonce(8) = 35 bytes
+  const data = Buffer.alloc(35);
+  let offset = 0;
+
+  data.writeUInt8(INSTRUCTION.RECORD_CAPTURE, offset);
+  offset += 1;
+  data.writeBigUInt64LE(payload.titanId, offset);
+  offset += 8;
+  data.writeInt32LE(35681236, offset); // Tokyo
+  offset += 4;
+  data.writeInt32LE(139767125, offset);
+  offset += 4;
+  data.writeUInt8(1, offset); // Pioneer
+  offset += 1;
+  data.writeUInt8(0, offset); // Abyssal
+  offset += 1;
+  data.writeBigInt64LE(payload.signatureTimestamp, offset);
+  offset += 8;
+  data.writeBigUInt64LE(payload.nonce, offset);
+
+  const [captureState] = getCaptureStatePDA(player);
+
+  return new TransactionInstruction({
+    keys: [
+      { pubkey: player, isSigner: true, isWritable: true },
+      { pubkey: backendAuthority, isSigner: true, isWritable: false },
+      { pubkey: configAccount, isSigner: false, isWritable: true },
+      { pubkey: captureRecord, isSigner: false, isWritable: true },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      { pubkey: captureState, isSigner: false, isWritable: true },
+    ],
+    programId: GAME_LOGIC_PROGRAM_ID,
+    data,
+  });
+}
+
+/** Send a capture for the next capture ID; throws on failure */
+async function sendCapture(
+  player: Keypair,
+  backendAuthority: Keypair,
+  configPDA: PublicKey,
+  payload: CapturePayload
+): Promise<string> {
+  const totalCaptures = await readTotalCaptures(configPDA);
+  const [capturePDA] = getCaptureRecordPDA((totalCaptures ?? BigInt(0)) + BigInt(1));
+
+  const ix = buildRecordCaptureInstruction(
+    player.publicKey,
+    backendAuthority.publicKey,
+    configPDA,
+    capturePDA,
+    payload
+  );
+
+  // sendAndConfirmTransaction fetches a fresh blockhash every call, so a
+  // resent payload is a new transaction as far as the runtime is concerned
+  const signers = player.publicKey.equals(backendAuthority.publicKey) ? [player] : [player, backendAuthority];
+  return sendAndConfirmTransaction(connection, new Transaction().add(ix), signers);
+}
+
+function newPayload(nonce: bigint): CapturePayload {
+  return {
+    titanId: BigInt(Math.floor(Math.random() * 100000)),
+    signatureTimestamp: BigInt(Math.floor(Date.now() / 1000)),
+    nonce,
+  };
+}
+
+// ============================================
+// Tests
+// ============================================
+
+async function runFirstCaptureTest(
+  player: Keypair,
+  backendAuthority: Keypair,
+  configPDA: PublicKey
+): Promise<boolean> {
+  console.log("\n📋 Test: First Capture (creates nonce account)");
+
+  const before = await readCaptureNonce(player.publicKey);
+  if (before !== null) {
+    recordTest("First Capture", false, `Nonce account already exists (nonce ${before})`);
+    return false;
+  }
+
+  try {
+    const sig = await sendCapture(player, backendAuthority, configPDA, newPayload(BigInt(0)));
+    console.log(`   Signature: ${sig.slice(0, 20)}...`);
+  } catch (error: any) {
+    recordTest("First Capture", false, `code ${parseErrorCode(error)}`);
+    return false;
+  }
+
+  const after = await readCaptureNonce(player.publicKey);
+  console.log(`   Nonce: ${after}`);
+  const passed = after === BigInt(1);
+  recordTest("First Capture", passed, passed ? undefined : `Expected nonce 1, got ${after}`);
+  return passed;
+}
+
+async function runSequentialCapturesTest(
+  player: Keypair,
+  backendAuthority: Keypair,
+  configPDA: PublicKey
+): Promise<CapturePayload | null> {
+  console.log("\n📋 Test: Sequential Captures");
+
+  let last: CapturePayload | null = null;
+  for (let i = 0; i < 2; i++) {
+    const nonce = (await readCaptureNonce(player.publicKey)) ?? BigInt(0);
+    const payload = newPayload(nonce);
+
+    try {
+      const sig = await sendCapture(player, backendAuthority, configPDA, payload);
+      console.log(`   Nonce ${nonce}: ${sig.slice(0, 20)}...`);
+    } catch (error: any) {
+      recordTest("Sequential Captures", false, `Nonce ${nonce}: code ${parseErrorCode(error)}`);
+      return null;
+    }
+
+    const after = await readCaptureNonce(player.publicKey);
+    if (after !== nonce + BigInt(1)) {
+      recordTest("Sequential Captures", false, `Expected nonce ${nonce + BigInt(1)}, got ${after}`);
+      return null;
+    }
+    last = payload;
+  }
+
+  recordTest("Sequential Captures", true);
+  return last;
+}
+
+async function runReplayTest(
+  player: Keypair,
+  backendAuthority: Keypair,
+  configPDA: PublicKey,
+  replayed: CapturePayload
+): Promise<boolean> {
+  console.log("\n📋 Test: Replay Captured Payload (should fail)");
+
+  const nonceBefore = await readCaptureNonce(player.publicKey);
+  const capturesBefore = await readTotalCaptures(configPDA);
+
+  try {
+    await sendCapture(player, backendAuthority, configPDA, replayed);
+    recordTest("Replay Rejected", false, "Replay succeeded");
+    return false;
+  } catch (error: any) {
+    const code = parseErrorCode(error);
+    if (code !== INVALID_NONCE) {
+      recordTest("Replay Rejected", false, `Expected ${INVALID_NONCE}, got code ${code}`);
+      return false;
+    }
+  }
+
+  // Nothing may change on a rejected replay
+  const nonceAfter = await readCaptureNonce(player.publicKey);
+  const capturesAfter = await readTotalCaptures(configPDA);
+  const unchanged = nonceAfter === nonceBefore && capturesAfter === capturesBefore;
+  recordTest("Replay Rejected", unchanged, unchanged ? undefined : "State changed after replay");
+  return unchanged;
+}
+
+async function runFutureNonceTest(
+  player: Keypair,
+  backendAuthority: Keypair,
+  configPDA: PublicKey
+): Promise<boolean> {
+  console.log("\n📋 Test: Skipped Nonce (should fail)");
+
+  const nonce = (await readCaptureNonce(player.publicKey)) ?? BigInt(0);
+
+  try {
+    await sendCapture(player, backendAuthority, configPDA, newPayload(nonce + BigInt(1)));
+    recordTest("Skipped Nonce Rejected", false, "Capture succeeded");
+    return false;
+  } catch (error: any) {
+    const code = parseErrorCode(error);
+    const passed = code === INVALID_NONCE;
+    recordTest("Skipped Nonce Rejected", passed, passed ? undefined : `Expected ${INVALID_NONCE}, got code ${code}`);
+    return passed;
+  }
+}
+
+// ============================================
+// Main
+// ============================================
+
+async function main() {
+  console.log("╔════════════════════════════════════════════════════════════════╗");
+  console.log("║         BREACH Capture Replay Protection Test Suite           ║");
+  console.log("╠════════════════════════════════════════════════════════════════╣");
+  console.log(`║ RPC: ${connection.rpcEndpoint.padEnd(58)}║`);
+  console.log("╚════════════════════════════════════════════════════════════════╝");
+
+  const walletPath =
+    process.env.WALLET_PATH || path.resolve(process.env.HOME || "~", ".config/solana/id.json");
+  if (!fs.existsSync(walletPath)) {
+    console.error(`\n❌ Wallet not found at ${walletPath}`);
+    process.exit(1);
+  }
+
+  const payer = loadWallet(walletPath);
+  const backendAuthority = payer;
+  console.log(`\nPayer / Backend Authority: ${payer.publicKey.toBase58()}`);
+
+  // A fresh player every run, so the first capture always starts at nonce 0
+  const player = Keypair.generate();
+  console.log(`Player: ${player.publicKey.toBase58()}`);
+
+  const airdrop = await connection.requestAirdrop(player.publicKey, LAMPORTS_PER_SOL);
+  await connection.confirmTransaction(airdrop);
+
+  const [configPDA] = getGameConfigPDA();
+  const configInfo = await connection.getAccountInfo(configPDA);
+  if (!configInfo) {
+    console.log("\nInitializing game config...");
+    await sendAndConfirmTransaction(
+      connection,
+      new Transaction().add(buildInitializeInstruction(payer.publicKey, configPDA, backendAuthority.publicKey)),
+      [payer]
+    );
+  } else {
+    const configured = new PublicKey(
+      configInfo.data.slice(CONFIG_BACKEND_AUTHORITY_OFFSET, CONFIG_BACKEND_AUTHORITY_OFFSET + 32)
+    );
+    if (!configured.equals(backendAuthority.publicKey)) {
+      console.error(`\n❌ Backend authority is ${configured.toBase58()}; restart the validator with --reset`);
+      process.exit(1);
+    }
+  }
+
+  await runFirstCaptureTest(player, backendAuthority, configPDA);
+  const last = await runSequentialCapturesTest(player, backendAuthority, configPDA);
+  if (last) {
+    await runReplayTest(player, backendAuthority, configPDA, last);
+  } else {
+    recordTest("Replay Rejected", false, "No capture to replay");
+  }
+  await runFutureNonceTest(player, backendAuthority, configPDA);
+
+  console.log("\n════════════════════════════════════════════════════════════════");
+  console.log("                        TEST SUMMARY                              ");
+  console.log("════════════════════════════════════════════════════════════════");
+
+  const passed = testResults.filter((t) => t.passed).length;
+  const failed = testResults.filter((t) => !t.passed).length;
+
+  console.log(`\n  Passed: ${passed}  |  Failed: ${failed}  |  Total: ${testResults.length}`);
+
+  if (failed > 0) {
+    console.log(`\n❌ ${failed} test(s) failed!`);
+    process.exit(1);
+  } else {
+    console.log(`\n✅ All ${passed} tests passed!`);
+    process.exit(0);
+  }
+}
+
+main().catch((error) => {
+  console.error(error);
+  process.exit(1);
+});
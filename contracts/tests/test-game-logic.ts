@@ -40,6 +40,7 @@ const INSTRUCTION = {
 const GAME_CONFIG_SEED = Buffer.from("game_config");
 const BATTLE_SEED = Buffer.from("battle");
 const CAPTURE_SEED = Buffer.from("capture");
+const CAPTURE_NONCE_SEED = Buffer.from("capture_nonce");
 
 // Connection to devnet
 const connection = new Connection("https://api.devnet.solana.com", "confirmed");
@@ -77,6 +78,10 @@ function getCaptureRecordPDA(captureId: bigint): [PublicKey, number] {
   return PublicKey.findProgramAddressSync([CAPTURE_SEED, idBuffer], GAME_LOGIC_PROGRAM_ID);
 }
 
+function getCaptureStatePDA(player: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync([CAPTURE_NONCE_SEED, player.toBuffer()], GAME_LOGIC_PROGRAM_ID);
+}
+
 // ============================================
 // Instruction Builders
 // ============================================
@@ -124,10 +129,11 @@ function buildRecordCaptureInstruction(
   locationLng: number,
   threatClass: number,
   elementType: number,
-  signatureTimestamp: bigint
+  signatureTimestamp: bigint,
+  nonce: bigint
 ): TransactionInstruction {
-  // Data: instruction(1) + titan_id(8) + lat(4) + lng(4) + threat(1) + element(1) + timestamp(8) = 27 bytes
-  const data = Buffer.alloc(27);
+  // Data: instruction(1) + titan_id(8) + lat(4) + lng(4) + threat(1) + element(1) + timestamp(8) + nonce(8) = 35 bytes
+  const data = Buffer.alloc(35);
   let offset = 0;
 
   data.writeUInt8(INSTRUCTION.RECORD_CAPTURE, offset);
@@ -143,6 +149,10 @@ function buildRecordCaptureInstruction(
   data.writeUInt8(elementType, offset);
   offset += 1;
   data.writeBigInt64LE(signatureTimestamp, offset);
+  offset += 8;
+  data.writeBigUInt64LE(nonce, offset);
+
+  const [captureState] = getCaptureStatePDA(player);
 
   return new TransactionInstruction({
     keys: [
@@ -151,6 +161,7 @@ function buildRecordCaptureInstruction(
       { pubkey: configAccount, isSigner: false, isWritable: true },
       { pubkey: captureRecord, isSigner: false, isWritable: true },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      { pubkey: captureState, isSigner: false, isWritable: true },
     ],
     programId: GAME_LOGIC_PROGRAM_ID,
     data,
@@ -538,6 +549,16 @@ async function testReadCaptureRecord(capturePDA: PublicKey): Promise<CaptureReco
   }
 }
 
+// PlayerCaptureState: discriminator(8) + player(32) + nonce(8) + bump(1); 0 until the first capture
+async function readCaptureNonce(player: PublicKey): Promise<bigint> {
+  const [captureState] = getCaptureStatePDA(player);
+  const accountInfo = await connection.getAccountInfo(captureState);
+  if (!accountInfo || accountInfo.data.length < 48) {
+    return BigInt(0);
+  }
+  return (accountInfo.data as Buffer).readBigUInt64LE(40);
+}
+
 function parseErrorCode(error: any): number {
   const match = error.toString().match(/custom program error: (0x[0-9a-fA-F]+)/);
   if (match) {
@@ -565,6 +586,7 @@ function getErrorMessage(code: number): string {
     7301: "CaptureAlreadyRecorded",
     7302: "InvalidCaptureLocation",
     7303: "CaptureExpired",
+    7304: "InvalidNonce",
     7400: "InvalidExperienceAmount",
     7401: "ExperienceOverflow",
     7500: "InvalidRewardAmount",
@@ -738,7 +760,8 @@ async function runRecordCaptureTest(
     locationLng,
     threatClass,
     elementType,
-    signatureTimestamp
+    signatureTimestamp,
+    await readCaptureNonce(player.publicKey)
   );
 
   try {
@@ -909,7 +932,8 @@ async function runRecordWhilePausedTest(
     139767125,
     1,
     0,
-    signatureTimestamp
+    signatureTimestamp,
+    await readCaptureNonce(player.publicKey)
   );
 
   try {
@@ -993,7 +1017,8 @@ async function runInvalidBackendAuthorityTest(
     139767125,
     1,
     0,
-    signatureTimestamp
+    signatureTimestamp,
+    await readCaptureNonce(player.publicKey)
   );
 
   try {
@@ -1089,7 +1114,8 @@ async function runExpiredCaptureTest(
     139767125,
     1,
     0,
-    expiredTimestamp
+    expiredTimestamp,
+    await readCaptureNonce(player.publicKey)
   );
 
   try {
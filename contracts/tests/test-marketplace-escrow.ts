@@ -0,0 +1,519 @@
+/**
+ * BREACH Marketplace Escrow Test Script
+ * Covers list_titan / cancel_listing / purchase_listing in the Game Logic program
+ *
+ * Usage:
+ *   SELLER_WALLET=~/.config/solana/id.json BUYER_WALLET=./buyer.json TITAN_ID=1 \
+ *     npx ts-node test-marketplace-escrow.ts
+ *
+ * The seller must own TITAN_ID and the buyer must hold enough $BREACH in its ATA.
+ */
+
+import {
+  Connection,
+  PublicKey,
+  Keypair,
+  Transaction,
+  TransactionInstruction,
+  SystemProgram,
+  sendAndConfirmTransaction,
+} from "@solana/web3.js";
+import * as fs from "fs";
+
+// Program IDs (deployed on devnet)
+const GAME_LOGIC_PROGRAM_ID = new PublicKey("DLk2GnDu9AYn7PeLprEDHDYH9UWKENX47UqqfeiQBaSX");
+const TITAN_NFT_PROGRAM_ID = new PublicKey("3KYPXMcodPCbnWLDX41yWtgxe6ctsPdnT3fYgp8udmd7");
+const TOKEN_PROGRAM_ID = new PublicKey("TokenkegQfeZyiNwAJbNbGMPXH8Q9HGbsJ8aPzjTPsP5wAJtt7i4");
+const ASSOCIATED_TOKEN_PROGRAM_ID = new PublicKey("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+// Account sizes
+const LISTING_SIZE = 73;
+
+// Instruction discriminators
+const INSTRUCTION = {
+  LIST_TITAN: 8,
+  CANCEL_LISTING: 9,
+  PURCHASE_LISTING: 10,
+};
+
+// PDA seeds
+const GAME_CONFIG_SEED = Buffer.from("game_config");
+const LISTING_SEED = Buffer.from("listing");
+const TITAN_SEED = Buffer.from("titan");
+const TITAN_CONFIG_SEED = Buffer.from("config");
+const PLAYER_SEED = Buffer.from("player");
+
+// Titan NFT GlobalConfig offsets (packed)
+const TITAN_CONFIG_TREASURY_OFFSET = 40;
+const TITAN_CONFIG_MARKETPLACE_FEE_OFFSET = 138;
+// Game Logic GameConfig offsets (packed)
+const GAME_CONFIG_BREACH_MINT_OFFSET = 104;
+// TitanData offsets (packed)
+const TITAN_OWNER_OFFSET = 76;
+const TITAN_LISTED_OFFSET = 134;
+
+// Connection to devnet
+const connection = new Connection("https://api.devnet.solana.com", "confirmed");
+
+// Test results tracker
+const testResults: { name: string; passed: boolean; error?: string }[] = [];
+
+// Load wallet from file
+function loadWallet(filepath: string): Keypair {
+  const expanded = filepath.replace("~", process.env.HOME || "");
+  const secretKey = JSON.parse(fs.readFileSync(expanded, "utf-8"));
+  return Keypair.fromSecretKey(Uint8Array.from(secretKey));
+}
+
+// Record test result
+function recordTest(name: string, passed: boolean, error?: string) {
+  testResults.push({ name, passed, error });
+  const icon = passed ? "✅" : "❌";
+  console.log(`${icon} ${name}${error ? ` (${error})` : ""}`);
+}
+
+function sleep(ms: number): Promise<void> {
+  return new Promise((resolve) => setTimeout(resolve, ms));
+}
+
+// ============================================
+// PDA Helpers
+// ============================================
+
+function u64Buffer(value: bigint): Buffer {
+  const buf = Buffer.alloc(8);
+  buf.writeBigUInt64LE(value);
+  return buf;
+}
+
+function getGameConfigPDA(): PublicKey {
+  return PublicKey.findProgramAddressSync([GAME_CONFIG_SEED], GAME_LOGIC_PROGRAM_ID)[0];
+}
+
+function getListingPDA(titanId: bigint): PublicKey {
+  return PublicKey.findProgramAddressSync([LISTING_SEED, u64Buffer(titanId)], GAME_LOGIC_PROGRAM_ID)[0];
+}
+
+function getTitanPDA(titanId: bigint): PublicKey {
+  return PublicKey.findProgramAddressSync([TITAN_SEED, u64Buffer(titanId)], TITAN_NFT_PROGRAM_ID)[0];
+}
+
+function getTitanConfigPDA(): PublicKey {
+  return PublicKey.findProgramAddressSync([TITAN_CONFIG_SEED], TITAN_NFT_PROGRAM_ID)[0];
+}
+
+function getPlayerPDA(wallet: PublicKey): PublicKey {
+  return PublicKey.findProgramAddressSync([PLAYER_SEED, wallet.toBuffer()], TITAN_NFT_PROGRAM_ID)[0];
+}
+
+function getAssociatedTokenAddress(owner: PublicKey, mint: PublicKey): PublicKey {
+  return PublicKey.findProgramAddressSync(
+    [owner.toBuffer(), TOKEN_PROGRAM_ID.toBuffer(), mint.toBuffer()],
+    ASSOCIATED_TOKEN_PROGRAM_ID
+  )[0];
+}
+
+// ============================================
+// Instruction Builders
+// ============================================
+
+function buildListTitanInstruction(
+  seller: PublicKey,
+  titanId: bigint,
+  price: bigint,
+  durationSeconds: bigint
+): TransactionInstruction {
+  // Data: instruction(1) + titan_id(8) + price(8) + duration(8) = 25 bytes
+  const data = Buffer.alloc(25);
+  data.writeUInt8(INSTRUCTION.LIST_TITAN, 0);
+  data.writeBigUInt64LE(titanId, 1);
+  data.writeBigUInt64LE(price, 9);
+  data.writeBigInt64LE(durationSeconds, 17);
+
+  return new TransactionInstruction({
+    keys: [
+      { pubkey: seller, isSigner: true, isWritable: true },
+      { pubkey: getGameConfigPDA(), isSigner: false, isWritable: false },
+      { pubkey: getListingPDA(titanId), isSigner: false, isWritable: true },
+      { pubkey: getTitanPDA(titanId), isSigner: false, isWritable: true },
+      { pubkey: getTitanConfigPDA(), isSigner: false, isWritable: false },
+      { pubkey: getPlayerPDA(seller), isSigner: false, isWritable: true },
+      { pubkey: TITAN_NFT_PROGRAM_ID, isSigner: false, isWritable: false },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    ],
+    programId: GAME_LOGIC_PROGRAM_ID,
+    data,
+  });
+}
+
+function buildCancelListingInstruction(
+  authority: PublicKey,
+  seller: PublicKey,
+  titanId: bigint
+): TransactionInstruction {
+  return new TransactionInstruction({
+    keys: [
+      { pubkey: authority, isSigner: true, isWritable: true },
+      { pubkey: seller, isSigner: false, isWritable: true },
+      { pubkey: getListingPDA(titanId), isSigner: false, isWritable: true },
+      { pubkey: getTitanPDA(titanId), isSigner: false, isWritable: true },
+      { pubkey: getTitanConfigPDA(), isSigner: false, isWritable: false },
+      { pubkey: getPlayerPDA(seller), isSigner: false, isWritable: true },
+      { pubkey: TITAN_NFT_PROGRAM_ID, isSigner: false, isWritable: false },
+    ],
+    programId: GAME_LOGIC_PROGRAM_ID,
+    data: Buffer.from([INSTRUCTION.CANCEL_LISTING]),
+  });
+}
+
+function buildPurchaseListingInstruction(
+  buyer: PublicKey,
+  seller: PublicKey,
+  titanId: bigint,
+  expectedPrice: bigint,
+  buyerToken: PublicKey,
+  sellerToken: PublicKey,
+  treasuryToken: PublicKey
+): TransactionInstruction {
+  // Data: instruction(1) + expected_price(8) = 9 bytes
+  const data = Buffer.alloc(9);
+  data.writeUInt8(INSTRUCTION.PURCHASE_LISTING, 0);
+  data.writeBigUInt64LE(expectedPrice, 1);
+
+  return new TransactionInstruction({
+    keys: [
+      { pubkey: buyer, isSigner: true, isWritable: true },
+      { pubkey: seller, isSigner: false, isWritable: true },
+      { pubkey: getGameConfigPDA(), isSigner: false, isWritable: false },
+      { pubkey: getListingPDA(titanId), isSigner: false, isWritable: true },
+      { pubkey: getTitanPDA(titanId), isSigner: false, isWritable: true },
+      { pubkey: getTitanConfigPDA(), isSigner: false, isWritable: false },
+      { pubkey: getPlayerPDA(buyer), isSigner: false, isWritable: true },
+      { pubkey: buyerToken, isSigner: false, isWritable: true },
+      { pubkey: sellerToken, isSigner: false, isWritable: true },
+      { pubkey: treasuryToken, isSigner: false, isWritable: true },
+      { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+      { pubkey: TITAN_NFT_PROGRAM_ID, isSigner: false, isWritable: false },
+    ],
+    programId: GAME_LOGIC_PROGRAM_ID,
+    data,
+  });
+}
+
+// ============================================
+// Account Readers
+// ============================================
+
+interface MarketplaceConfig {
+  breachMint: PublicKey;
+  treasury: PublicKey;
+  feeBps: number;
+}
+
+async function readMarketplaceConfig(): Promise<MarketplaceConfig> {
+  const gameConfig = await connection.getAccountInfo(getGameConfigPDA());
+  const titanConfig = await connection.getAccountInfo(getTitanConfigPDA());
+  if (!gameConfig || !titanConfig) {
+    throw new Error("Program configs not initialized");
+  }
+  return {
+    breachMint: new PublicKey(gameConfig.data.subarray(GAME_CONFIG_BREACH_MINT_OFFSET, GAME_CONFIG_BREACH_MINT_OFFSET + 32)),
+    treasury: new PublicKey(titanConfig.data.subarray(TITAN_CONFIG_TREASURY_OFFSET, TITAN_CONFIG_TREASURY_OFFSET + 32)),
+    feeBps: titanConfig.data.readUInt16LE(TITAN_CONFIG_MARKETPLACE_FEE_OFFSET),
+  };
+}
+
+async function readTitanOwner(titanId: bigint): Promise<{ owner: PublicKey; listed: boolean } | null> {
+  const account = await connection.getAccountInfo(getTitanPDA(titanId));
+  if (!account) return null;
+  return {
+    owner: new PublicKey(account.data.subarray(TITAN_OWNER_OFFSET, TITAN_OWNER_OFFSET + 32)),
+    listed: account.data[TITAN_LISTED_OFFSET] !== 0,
+  };
+}
+
+async function tokenBalance(account: PublicKey): Promise<bigint> {
+  const balance = await connection.getTokenAccountBalance(account);
+  return BigInt(balance.value.amount);
+}
+
+function parseErrorCode(error: any): number {
+  const match = error.toString().match(/custom program error: (0x[0-9a-fA-F]+)/);
+  if (match) {
+    return parseInt(match[1], 16);
+  }
+  return 0;
+}
+
+function getErrorMessage(code: number): string {
+  const errorMessages: Record<number, string> = {
+    7000: "Unauthorized",
+    7002: "NotOwner",
+    7100: "ProgramPaused",
+    7602: "InvalidSeeds",
+    7605: "InvalidTitanProgram",
+    7700: "ListingNotActive",
+    7701: "ListingExpired",
+    7702: "ListingNotExpired",
+    7703: "ListingAlreadyExists",
+    7704: "InvalidListingPrice",
+    7705: "InvalidListingDuration",
+    7706: "CannotBuyOwnListing",
+    7707: "ListingPriceMismatch",
+    7708: "InvalidTokenAccount",
+    7709: "InvalidMarketplaceFee",
+    6306: "TitanListed",
+  };
+  return errorMessages[code] || `Unknown error: ${code}`;
+}
+
+async function send(ix: TransactionInstruction, signers: Keypair[]): Promise<string> {
+  return sendAndConfirmTransaction(connection, new Transaction().add(ix), signers);
+}
+
+async function expectError(name: string, expectedCode: number, fn: () => Promise<unknown>) {
+  try {
+    await fn();
+    recordTest(name, false, "Should have failed but succeeded");
+  } catch (error: any) {
+    const code = parseErrorCode(error);
+    if (code === expectedCode) {
+      recordTest(`${name} (Rejected)`, true);
+    } else {
+      recordTest(name, false, `expected ${getErrorMessage(expectedCode)}, got ${getErrorMessage(code)}`);
+    }
+  }
+}
+
+// ============================================
+// Test Functions
+// ============================================
+
+function runFeeMathTest(feeBps: number) {
+  // Mirrors split_sale_price in game_logic/src/state/listing.rs
+  const split = (price: bigint, bps: bigint) => {
+    const fee = (price * bps) / 10_000n;
+    return [fee, price - fee];
+  };
+  const cases: [bigint, bigint, bigint, bigint][] = [
+    [100_000_000_000n, 250n, 2_500_000_000n, 97_500_000_000n],
+    [1_000n, 0n, 0n, 1_000n],
+    [399n, 250n, 9n, 390n],
+  ];
+  const ok = cases.every(([price, bps, fee, seller]) => {
+    const [f, s] = split(price, bps);
+    return f === fee && s === seller && f + s === price;
+  });
+  recordTest(`Fee Math (config fee = ${feeBps} bps)`, ok);
+}
+
+async function runListTest(seller: Keypair, titanId: bigint, price: bigint, duration: bigint): Promise<boolean> {
+  try {
+    await send(buildListTitanInstruction(seller.publicKey, titanId, price, duration), [seller]);
+    const listing = await connection.getAccountInfo(getListingPDA(titanId));
+    const titan = await readTitanOwner(titanId);
+    const ok =
+      listing !== null &&
+      listing.data.length === LISTING_SIZE &&
+      titan !== null &&
+      titan.owner.equals(getListingPDA(titanId)) &&
+      titan.listed;
+    recordTest("List Titan (escrowed + flagged)", ok);
+    return ok;
+  } catch (error: any) {
+    recordTest("List Titan", false, getErrorMessage(parseErrorCode(error)));
+    return false;
+  }
+}
+
+async function runPurchaseTest(
+  buyer: Keypair,
+  seller: PublicKey,
+  titanId: bigint,
+  price: bigint,
+  config: MarketplaceConfig
+) {
+  const buyerToken = getAssociatedTokenAddress(buyer.publicKey, config.breachMint);
+  const sellerToken = getAssociatedTokenAddress(seller, config.breachMint);
+  const treasuryToken = getAssociatedTokenAddress(config.treasury, config.breachMint);
+
+  const sellerBefore = await tokenBalance(sellerToken);
+  const treasuryBefore = await tokenBalance(treasuryToken);
+
+  // Fire two purchases at once; only one can settle the listing
+  const ix = () =>
+    buildPurchaseListingInstruction(buyer.publicKey, seller, titanId, price, buyerToken, sellerToken, treasuryToken);
+  const results = await Promise.allSettled([send(ix(), [buyer]), send(ix(), [buyer])]);
+  const succeeded = results.filter((r) => r.status === "fulfilled").length;
+  const rejected = results.filter(
+    (r) => r.status === "rejected" && parseErrorCode(r.reason) === 7700
+  ).length;
+  recordTest("Double Purchase Race (exactly one settles)", succeeded === 1 && rejected <= 1);
+
+  const fee = (price * BigInt(config.feeBps)) / 10_000n;
+  const sellerAfter = await tokenBalance(sellerToken);
+  const treasuryAfter = await tokenBalance(treasuryToken);
+  recordTest(
+    "Purchase Fee Split",
+    sellerAfter - sellerBefore === price - fee && treasuryAfter - treasuryBefore === fee
+  );
+
+  const titan = await readTitanOwner(titanId);
+  const listing = await connection.getAccountInfo(getListingPDA(titanId));
+  recordTest(
+    "Purchase Releases Titan + Closes Listing",
+    titan !== null && titan.owner.equals(buyer.publicKey) && !titan.listed && listing === null
+  );
+}
+
+// ============================================
+// Main Test Runner
+// ============================================
+
+async function main() {
+  console.log("════════════════════════════════════════════════════════════════");
+  console.log("              BREACH MARKETPLACE ESCROW TEST SUITE              ");
+  console.log("════════════════════════════════════════════════════════════════");
+
+  const seller = loadWallet(process.env.SELLER_WALLET || "~/.config/solana/id.json");
+  const buyer = loadWallet(process.env.BUYER_WALLET || "./buyer-wallet.json");
+  const titanId = BigInt(process.env.TITAN_ID || "1");
+  const price = BigInt(process.env.PRICE || "1000000000"); // 1 BREACH
+
+  console.log(`\n📁 Seller: ${seller.publicKey.toBase58()}`);
+  console.log(`📁 Buyer:  ${buyer.publicKey.toBase58()}`);
+  console.log(`🦖 Titan:  #${titanId}`);
+
+  const config = await readMarketplaceConfig();
+  console.log(`💰 Fee: ${config.feeBps} bps → treasury ${config.treasury.toBase58()}`);
+
+  const buyerToken = getAssociatedTokenAddress(buyer.publicKey, config.breachMint);
+  const sellerToken = getAssociatedTokenAddress(seller.publicKey, config.breachMint);
+  const treasuryToken = getAssociatedTokenAddress(config.treasury, config.breachMint);
+
+  console.log("\n════════════════════════════════════════════════════════════════");
+  console.log("                       VALIDATION TESTS                           ");
+  console.log("════════════════════════════════════════════════════════════════");
+
+  runFeeMathTest(config.feeBps);
+
+  await expectError("List With Zero Price", 7704, () =>
+    send(buildListTitanInstruction(seller.publicKey, titanId, 0n, 3600n), [seller])
+  );
+  await expectError("List With Excessive Duration", 7705, () =>
+    send(buildListTitanInstruction(seller.publicKey, titanId, price, 31n * 24n * 3600n), [seller])
+  );
+  await expectError("List Titan Not Owned", 7002, () =>
+    send(buildListTitanInstruction(buyer.publicKey, titanId, price, 3600n), [buyer])
+  );
+
+  console.log("\n════════════════════════════════════════════════════════════════");
+  console.log("                    LIST / CANCEL TESTS                           ");
+  console.log("════════════════════════════════════════════════════════════════");
+
+  if (!(await runListTest(seller, titanId, price, 3600n))) {
+    console.log("\n❌ Listing failed, aborting tests");
+    process.exit(1);
+  }
+
+  await expectError("List Same Titan Twice", 7703, () =>
+    send(buildListTitanInstruction(seller.publicKey, titanId, price, 3600n), [seller])
+  );
+  await expectError("Cancel Active Listing As Non-Seller", 7702, () =>
+    send(buildCancelListingInstruction(buyer.publicKey, seller.publicKey, titanId), [buyer])
+  );
+  await expectError("Purchase Own Listing", 7706, () =>
+    send(
+      buildPurchaseListingInstruction(seller.publicKey, seller.publicKey, titanId, price, sellerToken, sellerToken, treasuryToken),
+      [seller]
+    )
+  );
+  await expectError("Purchase With Stale Price", 7707, () =>
+    send(
+      buildPurchaseListingInstruction(buyer.publicKey, seller.publicKey, titanId, price - 1n, buyerToken, sellerToken, treasuryToken),
+      [buyer]
+    )
+  );
+  await expectError("Purchase With Wrong Seller Token Account", 7708, () =>
+    send(
+      buildPurchaseListingInstruction(buyer.publicKey, seller.publicKey, titanId, price, buyerToken, buyerToken, treasuryToken),
+      [buyer]
+    )
+  );
+  await expectError("Purchase With Wrong Treasury Token Account", 7708, () =>
+    send(
+      buildPurchaseListingInstruction(buyer.publicKey, seller.publicKey, titanId, price, buyerToken, sellerToken, sellerToken),
+      [buyer]
+    )
+  );
+
+  try {
+    await send(buildCancelListingInstruction(seller.publicKey, seller.publicKey, titanId), [seller]);
+    const titan = await readTitanOwner(titanId);
+    recordTest("Seller Cancel Returns Titan", titan !== null && titan.owner.equals(seller.publicKey) && !titan.listed);
+  } catch (error: any) {
+    recordTest("Seller Cancel Returns Titan", false, getErrorMessage(parseErrorCode(error)));
+  }
+
+  await expectError("Cancel Already Cancelled Listing", 7700, () =>
+    send(buildCancelListingInstruction(seller.publicKey, seller.publicKey, titanId), [seller])
+  );
+
+  console.log("\n════════════════════════════════════════════════════════════════");
+  console.log("                       EXPIRY TESTS                               ");
+  console.log("════════════════════════════════════════════════════════════════");
+
+  if (await runListTest(seller, titanId, price, 2n)) {
+    await sleep(5_000);
+    await expectError("Purchase Expired Listing", 7701, () =>
+      send(
+        buildPurchaseListingInstruction(buyer.publicKey, seller.publicKey, titanId, price, buyerToken, sellerToken, treasuryToken),
+        [buyer]
+      )
+    );
+    try {
+      await send(buildCancelListingInstruction(buyer.publicKey, seller.publicKey, titanId), [buyer]);
+      const titan = await readTitanOwner(titanId);
+      recordTest("Anyone Can Clear Expired Listing", titan !== null && titan.owner.equals(seller.publicKey));
+    } catch (error: any) {
+      recordTest("Anyone Can Clear Expired Listing", false, getErrorMessage(parseErrorCode(error)));
+    }
+  }
+
+  console.log("\n════════════════════════════════════════════════════════════════");
+  console.log("                      PURCHASE TESTS                              ");
+  console.log("════════════════════════════════════════════════════════════════");
+
+  if (await runListTest(seller, titanId, price, 3600n)) {
+    await runPurchaseTest(buyer, seller.publicKey, titanId, price, config);
+    await expectError("Purchase Settled Listing", 7700, () =>
+      send(
+        buildPurchaseListingInstruction(buyer.publicKey, seller.publicKey, titanId, price, buyerToken, sellerToken, treasuryToken),
+        [buyer]
+      )
+    );
+  }
+
+  // ═══════════════════════════════════════════
+  // Test Summary
+  // ═══════════════════════════════════════════
+
+  console.log("\n════════════════════════════════════════════════════════════════");
+  console.log("                        TEST SUMMARY                              ");
+  console.log("════════════════════════════════════════════════════════════════");
+
+  const passed = testResults.filter((t) => t.passed).length;
+  const failed = testResults.filter((t) => !t.passed).length;
+
+  console.log(`\n  Passed: ${passed}  |  Failed: ${failed}  |  Total: ${testResults.length}`);
+
+  if (failed > 0) {
+    console.log(`\n❌ ${failed} test(s) failed!`);
+    process.exit(1);
+  } else {
+    console.log(`\n✅ All ${passed} tests passed!`);
+    process.exit(0);
+  }
+}
+
+main().catch(console.error);
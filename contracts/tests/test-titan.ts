@@ -34,8 +34,13 @@ const INSTRUCTION = {
   TRANSFER: 5,
   UPDATE_CONFIG: 6,
   SET_PAUSED: 7,
+  MINT_TITAN_BATCH: 10,
 };
 
+// Mint data entry size (packed MintTitanData) and max Titans per batch
+const MINT_TITAN_DATA_SIZE = 94;
+const MAX_MINT_BATCH = 5;
+
 // PDA seeds
 const CONFIG_SEED = Buffer.from("config");
 const TITAN_SEED = Buffer.from("titan");
@@ -202,6 +207,58 @@ function buildMintTitanInstruction(
   });
 }
 
+function buildMintTitanBatchInstruction(
+  payer: PublicKey,
+  configAccount: PublicKey,
+  playerAccount: PublicKey,
+  captureAuthority: PublicKey,
+  titanAccounts: PublicKey[],
+  count: number,
+  elementType: number
+): TransactionInstruction {
+  // Data: instruction(1) + count(1) + count * MintTitanData(94)
+  const data = Buffer.alloc(2 + count * MINT_TITAN_DATA_SIZE);
+  data.writeUInt8(INSTRUCTION.MINT_TITAN_BATCH, 0);
+  data.writeUInt8(count, 1);
+
+  for (let i = 0; i < count; i++) {
+    let offset = 2 + i * MINT_TITAN_DATA_SIZE;
+    data.writeUInt16LE(1000 + i, offset);
+    offset += 2;
+    data.writeUInt8(THREAT_CLASS.HUNTER, offset);
+    offset += 1;
+    data.writeUInt8(elementType, offset);
+    offset += 1;
+    for (let stat = 0; stat < 4; stat++) {
+      data.writeUInt8(Math.floor(Math.random() * 50) + 50, offset);
+      offset += 1;
+    }
+    for (let g = 0; g < 6; g++) {
+      data.writeUInt8(Math.floor(Math.random() * 256), offset);
+      offset += 1;
+    }
+    data.writeInt32LE(37774900, offset);
+    offset += 4;
+    data.writeInt32LE(-122419400, offset);
+    offset += 4;
+    data.writeBigUInt64LE(BigInt(Date.now() + i), offset);
+    // signature(64) left zeroed
+  }
+
+  return new TransactionInstruction({
+    keys: [
+      { pubkey: payer, isSigner: true, isWritable: true },
+      { pubkey: configAccount, isSigner: false, isWritable: true },
+      { pubkey: playerAccount, isSigner: false, isWritable: true },
+      { pubkey: captureAuthority, isSigner: true, isWritable: false },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ...titanAccounts.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })),
+    ],
+    programId: PROGRAM_ID,
+    data,
+  });
+}
+
 function buildLevelUpInstruction(
   owner: PublicKey,
   titanAccount: PublicKey
@@ -663,12 +720,15 @@ function getErrorMessage(code: number): string {
     6201: "MaxTitansReached",
     6202: "InvalidCaptureProof",
     6203: "InvalidLocation",
+    6204: "InvalidBatchSize",
+    6205: "BatchAccountMismatch",
     6300: "InvalidThreatClass",
     6301: "InvalidElementType",
     6302: "MaxLevelReached",
     6303: "InsufficientExperience",
     6304: "CannotEvolve",
     6305: "InvalidSpeciesId",
+    6307: "InvalidEvolutionTarget",
     6400: "CannotFuseWithSelf",
     6401: "LevelTooLowForFusion",
     6402: "ElementMismatch",
@@ -889,7 +949,8 @@ async function runEvolveTest(payer: Keypair, titanPDA: PublicKey): Promise<boole
   console.log(`   Current Level: ${titan.level}, Species: ${titan.speciesId}`);
   console.log(`   Note: Evolution requires Level 30+`);
 
-  const newSpeciesId = titan.speciesId + 1000;
+  // Next threat class, same element and variant (see contracts/evolution)
+  const newSpeciesId = titan.speciesId + 100;
   const ix = buildEvolveInstruction(payer.publicKey, titanPDA, newSpeciesId);
 
   try {
@@ -1364,6 +1425,97 @@ async function runNotOwnerTransferTest(
   }
 }
 
+async function runMintTitanBatchTest(payer: Keypair, configPDA: PublicKey, count: number): Promise<boolean> {
+  console.log(`\n📋 Test: Mint Titan Batch (${count})`);
+
+  const config = await testReadConfig(configPDA);
+  if (!config) {
+    recordTest("Mint Titan Batch", false, "Config not found");
+    return false;
+  }
+
+  const [playerPDA] = getPlayerPDA(payer.publicKey);
+  const titanPDAs = Array.from({ length: count }, (_, i) =>
+    getTitanPDA(config.totalTitansMinted + BigInt(i + 1))[0]
+  );
+
+  const ix = buildMintTitanBatchInstruction(
+    payer.publicKey,
+    configPDA,
+    playerPDA,
+    payer.publicKey,
+    titanPDAs,
+    count,
+    ELEMENT.ABYSSAL
+  );
+
+  try {
+    const sig = await sendAndConfirmTransaction(connection, new Transaction().add(ix), [payer]);
+    console.log(`   Signature: ${sig.slice(0, 20)}...`);
+  } catch (error: any) {
+    recordTest("Mint Titan Batch", false, getErrorMessage(parseErrorCode(error)));
+    return false;
+  }
+
+  const after = await testReadConfig(configPDA);
+  const minted = after ? after.totalTitansMinted - config.totalTitansMinted : BigInt(0);
+  if (minted !== BigInt(count)) {
+    recordTest("Mint Titan Batch", false, `Counter advanced by ${minted}, expected ${count}`);
+    return false;
+  }
+
+  for (const titanPDA of titanPDAs) {
+    if (!(await testReadTitan(titanPDA))) {
+      recordTest("Mint Titan Batch", false, `Titan ${titanPDA.toBase58()} not created`);
+      return false;
+    }
+  }
+
+  console.log(`   Minted Titans #${config.totalTitansMinted + BigInt(1)}-#${after!.totalTitansMinted}`);
+  recordTest("Mint Titan Batch", true);
+  return true;
+}
+
+async function runBatchAccountMismatchTest(payer: Keypair, configPDA: PublicKey): Promise<boolean> {
+  console.log("\n📋 Test: Mint Titan Batch Missing Account (should fail)");
+
+  const config = await testReadConfig(configPDA);
+  if (!config) {
+    recordTest("Batch Account Mismatch", false, "Config not found");
+    return false;
+  }
+
+  // Three entries but only two Titan accounts
+  const [playerPDA] = getPlayerPDA(payer.publicKey);
+  const titanPDAs = [1, 2].map((i) => getTitanPDA(config.totalTitansMinted + BigInt(i))[0]);
+  const ix = buildMintTitanBatchInstruction(
+    payer.publicKey,
+    configPDA,
+    playerPDA,
+    payer.publicKey,
+    titanPDAs,
+    3,
+    ELEMENT.ABYSSAL
+  );
+
+  try {
+    await sendAndConfirmTransaction(connection, new Transaction().add(ix), [payer]);
+    recordTest("Batch Account Mismatch", false, "Should have failed");
+    return false;
+  } catch (error: any) {
+    const code = parseErrorCode(error);
+    const after = await testReadConfig(configPDA);
+    const unchanged = after?.totalTitansMinted === config.totalTitansMinted;
+    if (code === 6205 && unchanged) {
+      console.log("   Correctly rejected, counter unchanged");
+      recordTest("Batch Account Mismatch", true);
+      return true;
+    }
+    recordTest("Batch Account Mismatch", false, `${getErrorMessage(code)}, counter unchanged: ${unchanged}`);
+    return false;
+  }
+}
+
 async function runMaxTitansTest(payer: Keypair, configPDA: PublicKey): Promise<boolean> {
   console.log("\n📋 Test: Max Titans Per Wallet Check");
   
@@ -1507,6 +1659,10 @@ async function main() {
   // 4. Mint third Titan (different element)
   const titan3 = await runMintTitanTest(payer, configPDA, ELEMENT.VOLCANIC);
 
+  // 4.5 Mint a batch of Titans in one transaction
+  await runMintTitanBatchTest(payer, configPDA, 3);
+  await runMintTitanBatchTest(payer, configPDA, MAX_MINT_BATCH);
+
   // 5. Read Player
   await runReadPlayerTest(payer);
 
@@ -1549,6 +1705,9 @@ async function main() {
   // 13. Test max titans check
   await runMaxTitansTest(payer, configPDA);
 
+  // 13.5 Batch with a missing Titan account
+  await runBatchAccountMismatchTest(payer, configPDA);
+
   console.log("\n════════════════════════════════════════════════════════════════");
   console.log("                    AUTHORIZATION TESTS                           ");
   console.log("════════════════════════════════════════════════════════════════");